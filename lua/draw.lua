@@ -1,4 +1,4 @@
-function(id, ptr)
+function(id, ptr, context)
     local cr = __lgi_core.record.new(cairo.Context, ptr)
-    _G['__draw_' .. id](cr)
+    _G['__draw_' .. id](cr, context)
 end
\ No newline at end of file
@@ -1,15 +1,32 @@
-use crate::config::{BarConfig, BarPosition, MarginConfig, ModuleConfig};
+#[cfg(feature = "lock")]
+use crate::config::LockBehavior;
+use crate::config::{
+    BarAlign, BarConfig, BarHeight, BarLength, BarPosition, CutoutConfig, FontConfig, MarginConfig,
+    ModuleConfig, SpacingConfig, TouchConfig,
+};
+use crate::gtk_helpers::{emit_scroll, IronbarGtkExt};
+use crate::image::add_export_search_paths;
+#[cfg(feature = "lock")]
+use crate::modules::SENSITIVE_TAG;
 use crate::modules::{BarModuleFactory, ModuleInfo, ModuleLocation};
+use crate::overflow::Overflow;
 use crate::popup::Popup;
+use crate::style::{self, FontProvider};
 use crate::Ironbar;
 use color_eyre::Result;
 use glib::Propagation;
-use gtk::gdk::Monitor;
+use gtk::gdk::{keys, Monitor, ScrollDirection};
 use gtk::prelude::*;
-use gtk::{Application, ApplicationWindow, IconTheme, Orientation, Window, WindowType};
-use gtk_layer_shell::LayerShell;
+use gtk::{
+    Application, ApplicationWindow, DirectionType, IconTheme, Orientation, PolicyType,
+    ScrolledWindow, ShadowType, SizeGroup, SizeGroupMode, Viewport, Window, WindowType,
+};
+use gtk_layer_shell::{KeyboardMode, LayerShell};
+use std::cell::Cell;
 use std::rc::Rc;
 use std::time::Duration;
+#[cfg(feature = "lock")]
+use tracing::error;
 use tracing::{debug, info};
 
 #[derive(Debug, Clone)]
@@ -23,6 +40,7 @@ pub struct Bar {
     name: String,
     monitor_name: String,
     position: BarPosition,
+    structure_key: String,
 
     ironbar: Rc<Ironbar>,
 
@@ -34,9 +52,355 @@ pub struct Bar {
     center: gtk::Box,
     end: gtk::Box,
 
+    /// Splits the center section around a region to avoid, if configured.
+    cutout: Option<Cutout>,
+
+    /// Sizes the bar to fit its content, for `height: auto`.
+    auto_size: Option<AutoSize>,
+
+    /// Whether the bar currently has keyboard focus,
+    /// having been granted it using [`Bar::focus`].
+    keyboard_focus: Rc<Cell<bool>>,
+
+    dim_unfocused: bool,
+
+    /// Applies the configured font to the bar and its popup.
+    font: FontProvider,
+
+    edge: EdgeConfig,
+    hotspot_window: Option<Window>,
+    /// The length of the bar window along its edge,
+    /// used to check when modules overflow.
+    available_length: Rc<Cell<i32>>,
+
     inner: Inner,
 }
 
+/// The options controlling how the bar is placed along its edge.
+#[derive(Debug, Clone, Copy)]
+struct EdgeConfig {
+    length: Option<BarLength>,
+    align: BarAlign,
+    anchor_to_edges: bool,
+    /// The configured margins at the start and end of the edge.
+    margin: (i32, i32),
+}
+
+/// How the bar window is placed along its edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EdgePlacement {
+    /// Whether to anchor to the start and end of the edge.
+    anchor: (bool, bool),
+    /// The margins at the start and end of the edge.
+    margin: (i32, i32),
+    /// The window length to request, or `-1` to leave it unset.
+    length: i32,
+    /// The distance from the start of the output to the start of the bar,
+    /// or `None` if the bar is centered.
+    origin: Option<i32>,
+}
+
+impl EdgePlacement {
+    /// Gets the length of the bar window on an output of the given size.
+    fn window_length(self, output_length: i32) -> i32 {
+        if self.length >= 0 {
+            self.length
+        } else {
+            output_length - self.margin.0 - self.margin.1
+        }
+    }
+
+    /// Gets the distance from the start of an output of the given size
+    /// to the start of the bar window.
+    fn window_start(self, output_length: i32) -> i32 {
+        self.origin.unwrap_or_else(|| {
+            if self.length >= 0 {
+                (output_length - self.length) / 2
+            } else {
+                self.margin.0
+            }
+        })
+    }
+}
+
+/// The center section split around a region of the edge,
+/// with a spacer covering the region.
+#[derive(Debug, Clone)]
+struct Cutout {
+    config: CutoutConfig,
+    orientation: Orientation,
+    /// Holds the center section, the spacer and the section after the spacer.
+    container: gtk::Box,
+    /// Holds the center section, aligning its modules against the spacer.
+    before: gtk::Box,
+    spacer: gtk::Box,
+    /// The center modules placed after the spacer.
+    after: gtk::Box,
+}
+
+impl Cutout {
+    fn new(config: CutoutConfig, orientation: Orientation, center: &gtk::Box) -> Self {
+        let container = gtk::Box::new(orientation, 0);
+        let before = gtk::Box::new(orientation, 0);
+
+        let spacer = gtk::Box::new(orientation, 0);
+        spacer.add_class("cutout");
+
+        let after = create_container("center-after", orientation);
+
+        before.pack_end(center, false, false, 0);
+
+        // both sides are kept the same size, so that the spacer is centered in the container
+        let mode = if orientation == Orientation::Horizontal {
+            SizeGroupMode::Horizontal
+        } else {
+            SizeGroupMode::Vertical
+        };
+
+        let size_group = SizeGroup::new(mode);
+        size_group.add_widget(&before);
+        size_group.add_widget(&after);
+
+        container.add(&before);
+        container.add(&spacer);
+        container.add(&after);
+
+        Self {
+            config,
+            orientation,
+            container,
+            before,
+            spacer,
+            after,
+        }
+    }
+
+    /// Sizes the spacer to cover the region,
+    /// and shifts the container so that the spacer covers its position.
+    fn set_placement(&self, placement: EdgePlacement, output_length: i32) {
+        let (length, shift) = cutout_layout(
+            self.config,
+            placement.window_start(output_length),
+            placement.window_length(output_length),
+        );
+
+        let (shift_start, shift_end) = (shift.max(0), (-shift).max(0));
+
+        if self.orientation == Orientation::Horizontal {
+            self.spacer.set_size_request(length, -1);
+            self.container.set_margin_start(shift_start);
+            self.container.set_margin_end(shift_end);
+        } else {
+            self.spacer.set_size_request(-1, length);
+            self.container.set_margin_top(shift_start);
+            self.container.set_margin_bottom(shift_end);
+        }
+    }
+
+    fn show(&self) {
+        self.container.show();
+        self.before.show();
+        self.spacer.show();
+        self.after.show();
+    }
+}
+
+/// Sizes the bar across its edge to fit its content,
+/// for `height: auto`.
+///
+/// The content is held in a scrolled window without scrollbars,
+/// so that the bar can be both shrunk as the content shrinks,
+/// and kept smaller than the content when clamped to `max_height`.
+#[derive(Debug, Clone)]
+struct AutoSize {
+    orientation: Orientation,
+    min: Option<i32>,
+    max: Option<i32>,
+    container: ScrolledWindow,
+    viewport: Viewport,
+}
+
+impl AutoSize {
+    fn new(config: &BarConfig, orientation: Orientation, content: &gtk::Box) -> Self {
+        let horizontal = orientation == Orientation::Horizontal;
+
+        let (hscrollbar_policy, vscrollbar_policy) = if horizontal {
+            (PolicyType::Never, PolicyType::External)
+        } else {
+            (PolicyType::External, PolicyType::Never)
+        };
+
+        // the natural size is used until the content is first measured
+        let container = ScrolledWindow::builder()
+            .hscrollbar_policy(hscrollbar_policy)
+            .vscrollbar_policy(vscrollbar_policy)
+            .propagate_natural_height(horizontal)
+            .propagate_natural_width(!horizontal)
+            .shadow_type(ShadowType::None)
+            .build();
+
+        // clipped content should stay in place,
+        // and modules handle their own scroll events before this
+        container.connect_scroll_event(|_, _| Propagation::Stop);
+
+        let viewport = Viewport::builder().shadow_type(ShadowType::None).build();
+        viewport.add(content);
+        container.add(&viewport);
+
+        Self {
+            orientation,
+            min: config.min_height,
+            max: config.max_height,
+            container,
+            viewport,
+        }
+    }
+
+    /// Resizes the bar whenever its content changes size,
+    /// including after styles are reloaded.
+    fn connect(&self, window: &ApplicationWindow, content: &gtk::Box) {
+        let auto_size = self.clone();
+        let window = window.clone();
+
+        content.connect_size_allocate(move |content, _| {
+            let auto_size = auto_size.clone();
+            let window = window.clone();
+            let content = content.clone();
+
+            // size requests made during allocation are not applied until the next one
+            glib::idle_add_local_once(move || auto_size.fit(&window, &content));
+        });
+    }
+
+    /// Measures the content, and resizes the bar to its natural size
+    /// if that has changed.
+    fn fit(&self, window: &ApplicationWindow, content: &gtk::Box) {
+        let horizontal = self.orientation == Orientation::Horizontal;
+
+        let (_, natural) = if horizontal {
+            content.preferred_height()
+        } else {
+            content.preferred_width()
+        };
+
+        let size = clamp_auto_size(natural, self.min, self.max);
+
+        let current = if horizontal {
+            self.container.height_request()
+        } else {
+            self.container.width_request()
+        };
+
+        if size == current {
+            return;
+        }
+
+        debug!("Resizing bar to fit content: {size}px");
+
+        // the window only shrinks when asked to,
+        // and the exclusive zone follows its size
+        let (width, height) = window.size();
+        if horizontal {
+            self.container.set_height_request(size);
+            window.resize(width, size);
+        } else {
+            self.container.set_width_request(size);
+            window.resize(size, height);
+        }
+    }
+
+    fn show(&self) {
+        self.container.show();
+        self.viewport.show();
+    }
+}
+
+/// Clamps the natural size of the content to the configured range.
+/// The maximum takes priority if the two overlap.
+fn clamp_auto_size(natural: i32, min: Option<i32>, max: Option<i32>) -> i32 {
+    let size = min.map_or(natural, |min| natural.max(min));
+    max.map_or(size, |max| size.min(max))
+}
+
+/// Gets the length of the region within the bar window,
+/// and the margin to shift the centered container by to place the spacer over it.
+///
+/// A positive margin is applied at the start of the container,
+/// and a negative margin at the end.
+/// Moving a centered widget by half its margin,
+/// this is double the distance from the window center to the region center.
+fn cutout_layout(cutout: CutoutConfig, window_start: i32, window_length: i32) -> (i32, i32) {
+    let start = cutout.start - window_start;
+    let end = cutout.end - window_start;
+
+    let length = (end - start).max(0);
+    let shift = start + end - window_length;
+
+    (length, shift)
+}
+
+impl EdgeConfig {
+    fn new(config: &BarConfig) -> Self {
+        let margin = if config.position.orientation() == Orientation::Horizontal {
+            (config.margin.left, config.margin.right)
+        } else {
+            (config.margin.top, config.margin.bottom)
+        };
+
+        Self {
+            length: config.length,
+            align: config.align,
+            anchor_to_edges: config.anchor_to_edges,
+            margin,
+        }
+    }
+
+    /// Gets the placement for an output
+    /// of the given size along the bar's edge.
+    ///
+    /// Centered bars are only anchored to their own edge.
+    /// Bars aligned to either end stay anchored across the whole edge
+    /// and are shifted using margins instead,
+    /// as compositors do not reserve an exclusive zone for surfaces anchored to a corner.
+    fn placement(self, output_length: i32) -> EdgePlacement {
+        let (margin_start, margin_end) = self.margin;
+
+        let Some(length) = self.length else {
+            return EdgePlacement {
+                anchor: (self.anchor_to_edges, self.anchor_to_edges),
+                margin: self.margin,
+                length: -1,
+                origin: None,
+            };
+        };
+
+        let available = (output_length - margin_start - margin_end).max(0);
+        let length = length.resolve(output_length).clamp(0, available);
+        let remaining = available - length;
+
+        match self.align {
+            BarAlign::Center => EdgePlacement {
+                anchor: (false, false),
+                margin: self.margin,
+                length,
+                origin: None,
+            },
+            BarAlign::Start => EdgePlacement {
+                anchor: (true, true),
+                margin: (margin_start, margin_end + remaining),
+                length: -1,
+                origin: Some(margin_start),
+            },
+            BarAlign::End => EdgePlacement {
+                anchor: (true, true),
+                margin: (margin_start + remaining, margin_end),
+                length: -1,
+                origin: Some(margin_start + remaining),
+            },
+        }
+    }
+}
+
 impl Bar {
     pub fn new(
         app: &Application,
@@ -66,9 +430,9 @@ impl Bar {
             .name("bar");
 
         let content = if orientation == Orientation::Horizontal {
-            content.height_request(config.height)
+            content.height_request(config.height.request())
         } else {
-            content.width_request(config.height)
+            content.width_request(config.height.request())
         }
         .build();
 
@@ -78,11 +442,27 @@ impl Bar {
         let center = create_container("center", orientation);
         let end = create_container("end", orientation);
 
+        let cutout = config
+            .cutout
+            .map(|cutout| Cutout::new(cutout, orientation, &center));
+
         content.add(&start);
-        content.set_center_widget(Some(&center));
+        match &cutout {
+            Some(cutout) => content.set_center_widget(Some(&cutout.container)),
+            None => content.set_center_widget(Some(&center)),
+        }
         content.pack_end(&end, false, false, 0);
 
-        window.add(&content);
+        let auto_size = (config.height == BarHeight::Auto)
+            .then(|| AutoSize::new(&config, orientation, &content));
+
+        match &auto_size {
+            Some(auto_size) => {
+                window.add(&auto_size.container);
+                auto_size.connect(&window, &content);
+            }
+            None => window.add(&content),
+        }
 
         window.connect_destroy_event(|_, _| {
             info!("Shutting down");
@@ -90,20 +470,92 @@ impl Bar {
             Propagation::Proceed
         });
 
-        Self {
+        let structure_key = config.structure_key();
+        let (padding, spacing) = (config.padding, config.spacing);
+        let dim_unfocused = config.dim_unfocused;
+        let edge = EdgeConfig::new(&config);
+
+        let font = FontProvider::new(&window);
+        font.apply(config.font.as_ref());
+
+        let bar = Self {
             name,
             monitor_name,
             position,
+            structure_key,
             ironbar,
             window,
             content,
             start,
             center,
             end,
+            cutout,
+            auto_size,
+            keyboard_focus: Rc::new(Cell::new(false)),
+            dim_unfocused,
+            font,
+            edge,
+            hotspot_window: None,
+            available_length: Rc::new(Cell::new(0)),
             inner: Inner::New {
                 config: Some(config),
             },
+        };
+
+        bar.apply_layout(padding, spacing);
+        bar
+    }
+
+    /// Sets the padding around the bar content
+    /// and the spacing between modules.
+    ///
+    /// This can be called on a loaded bar
+    /// to update these without recreating its modules.
+    pub fn apply_layout(&self, padding: i32, spacing: SpacingConfig) {
+        if self.position.orientation() == Orientation::Horizontal {
+            self.content.set_margin_start(padding);
+            self.content.set_margin_end(padding);
+        } else {
+            self.content.set_margin_top(padding);
+            self.content.set_margin_bottom(padding);
         }
+
+        self.start.set_spacing(spacing.start());
+        self.center.set_spacing(spacing.center());
+        self.end.set_spacing(spacing.end());
+
+        if let Some(cutout) = &self.cutout {
+            cutout.after.set_spacing(spacing.center());
+        }
+    }
+
+    /// Checks whether the padding and spacing currently applied
+    /// match the provided values.
+    pub fn has_layout(&self, padding: i32, spacing: SpacingConfig) -> bool {
+        let current_padding = if self.position.orientation() == Orientation::Horizontal {
+            self.content.margin_start()
+        } else {
+            self.content.margin_top()
+        };
+
+        current_padding == padding
+            && self.start.spacing() == spacing.start()
+            && self.center.spacing() == spacing.center()
+            && self.end.spacing() == spacing.end()
+    }
+
+    /// Sets the font used for the bar and its popup.
+    ///
+    /// This can be called on a loaded bar
+    /// to update it without recreating its modules,
+    /// although module icons are only scaled when they are created.
+    pub fn apply_font(&self, font: Option<&FontConfig>) {
+        self.font.apply(font);
+    }
+
+    /// Checks whether the font currently applied matches the provided one.
+    pub fn has_font(&self, font: Option<&FontConfig>) -> bool {
+        self.font.has_font(font)
     }
 
     pub fn init(mut self, monitor: &Monitor) -> Result<Self> {
@@ -124,11 +576,21 @@ impl Bar {
             .start_hidden
             .unwrap_or_else(|| config.autohide.is_some());
 
+        let geometry = monitor.geometry();
+        let output_length = self.edge_length(geometry.width(), geometry.height());
+        let placement = self.edge.placement(output_length);
+        self.available_length
+            .set(placement.window_length(output_length));
+
+        if let Some(cutout) = &self.cutout {
+            cutout.set_placement(placement, output_length);
+        }
+
         self.setup_layer_shell(
             &self.window,
             config.exclusive_zone.unwrap_or(!start_hidden),
-            config.anchor_to_edges,
             config.margin,
+            placement,
             config.layer,
             monitor,
         );
@@ -139,8 +601,8 @@ impl Bar {
             self.setup_layer_shell(
                 &hotspot_window,
                 false,
-                config.anchor_to_edges,
                 config.margin,
+                placement,
                 gtk_layer_shell::Layer::Top,
                 monitor,
             );
@@ -148,9 +610,19 @@ impl Bar {
             if start_hidden {
                 hotspot_window.show();
             }
+
+            self.hotspot_window = Some(hotspot_window);
         }
 
+        #[cfg(feature = "lock")]
+        let lock_behavior = config.lock_behavior;
+
         let load_result = self.load_modules(config, monitor)?;
+        load_result.popup.set_bar_origin(placement.origin);
+        self.setup_keyboard_focus(&load_result.popup);
+
+        #[cfg(feature = "lock")]
+        self.setup_lock_behavior(lock_behavior, &load_result.popup);
 
         self.show(!start_hidden);
 
@@ -165,8 +637,8 @@ impl Bar {
         &self,
         win: &impl IsA<Window>,
         exclusive_zone: bool,
-        anchor_to_edges: bool,
         margin: MarginConfig,
+        placement: EdgePlacement,
         layer: gtk_layer_shell::Layer,
         monitor: &Monitor,
     ) {
@@ -188,28 +660,136 @@ impl Bar {
         win.set_layer_shell_margin(Edge::Left, margin.left);
         win.set_layer_shell_margin(Edge::Right, margin.right);
 
-        let bar_orientation = position.orientation();
+        win.set_anchor(Edge::Top, position == BarPosition::Top);
+        win.set_anchor(Edge::Bottom, position == BarPosition::Bottom);
+        win.set_anchor(Edge::Left, position == BarPosition::Left);
+        win.set_anchor(Edge::Right, position == BarPosition::Right);
 
-        win.set_anchor(
-            Edge::Top,
-            position == BarPosition::Top
-                || (bar_orientation == Orientation::Vertical && anchor_to_edges),
-        );
-        win.set_anchor(
-            Edge::Bottom,
-            position == BarPosition::Bottom
-                || (bar_orientation == Orientation::Vertical && anchor_to_edges),
-        );
-        win.set_anchor(
-            Edge::Left,
-            position == BarPosition::Left
-                || (bar_orientation == Orientation::Horizontal && anchor_to_edges),
-        );
-        win.set_anchor(
-            Edge::Right,
-            position == BarPosition::Right
-                || (bar_orientation == Orientation::Horizontal && anchor_to_edges),
-        );
+        self.apply_placement(win, placement);
+    }
+
+    /// Sets the anchors, margins and size along the bar's edge.
+    fn apply_placement(&self, win: &impl IsA<Window>, placement: EdgePlacement) {
+        use gtk_layer_shell::Edge;
+
+        let (start, end) = if self.position.orientation() == Orientation::Horizontal {
+            (Edge::Left, Edge::Right)
+        } else {
+            (Edge::Top, Edge::Bottom)
+        };
+
+        win.set_anchor(start, placement.anchor.0);
+        win.set_anchor(end, placement.anchor.1);
+        win.set_layer_shell_margin(start, placement.margin.0);
+        win.set_layer_shell_margin(end, placement.margin.1);
+
+        // keep the size across the edge, which the autohide hotspot sets
+        let (width, height) = win.size_request();
+        if self.position.orientation() == Orientation::Horizontal {
+            win.set_size_request(placement.length, height);
+        } else {
+            win.set_size_request(width, placement.length);
+        }
+    }
+
+    /// Gets the size of the output along the bar's edge.
+    fn edge_length(&self, width: i32, height: i32) -> i32 {
+        if self.position.orientation() == Orientation::Horizontal {
+            width
+        } else {
+            height
+        }
+    }
+
+    /// Re-calculates the bar's placement along its edge
+    /// after the output has changed size.
+    ///
+    /// The window is only moved when `length` is set.
+    pub fn set_output_size(&self, width: i32, height: i32) {
+        let output_length = self.edge_length(width, height);
+        let placement = self.edge.placement(output_length);
+        self.available_length
+            .set(placement.window_length(output_length));
+
+        if let Some(cutout) = &self.cutout {
+            cutout.set_placement(placement, output_length);
+        }
+
+        if self.edge.length.is_none() {
+            return;
+        }
+
+        debug!("Updating placement for bar '{}': {placement:?}", self.name);
+
+        self.apply_placement(&self.window, placement);
+        if let Some(hotspot_window) = &self.hotspot_window {
+            self.apply_placement(hotspot_window, placement);
+        }
+
+        if let Inner::Loaded { popup } = &self.inner {
+            popup.set_bar_origin(placement.origin);
+        }
+    }
+
+    /// Sets up keyboard navigation for while the bar has focus.
+    ///
+    /// Tab moves between widgets and Enter activates them,
+    /// which GTK handles itself.
+    /// Up and down scroll the focused widget,
+    /// and escape returns focus to the compositor.
+    fn setup_keyboard_focus(&self, popup: &Rc<Popup>) {
+        {
+            let keyboard_focus = self.keyboard_focus.clone();
+            let popup = popup.clone();
+
+            self.window.connect_key_press_event(move |win, ev| {
+                if !keyboard_focus.get() {
+                    return Propagation::Proceed;
+                }
+
+                let direction = match ev.keyval() {
+                    keys::constants::Escape => {
+                        release_keyboard_focus(win, &keyboard_focus, &popup);
+                        return Propagation::Stop;
+                    }
+                    keys::constants::Up => ScrollDirection::Up,
+                    keys::constants::Down => ScrollDirection::Down,
+                    _ => return Propagation::Proceed,
+                };
+
+                if let Some(widget) = win.focused_widget() {
+                    emit_scroll(&widget, direction);
+                }
+
+                Propagation::Stop
+            });
+        }
+
+        {
+            let keyboard_focus = self.keyboard_focus.clone();
+            let popup = popup.clone();
+
+            self.window.connect_focus_out_event(move |win, _| {
+                // focus moves to the popup when it opens,
+                // in which case the bar should keep its focus mode.
+                if keyboard_focus.get() && !popup.window.is_visible() {
+                    release_keyboard_focus(win, &keyboard_focus, &popup);
+                }
+
+                Propagation::Proceed
+            });
+        }
+
+        {
+            let keyboard_focus = self.keyboard_focus.clone();
+            let window = self.window.clone();
+
+            popup.window.connect_hide(move |_| {
+                if keyboard_focus.get() {
+                    window.present();
+                }
+            });
+        }
     }
 
     fn setup_autohide(window: &ApplicationWindow, hotspot_window: &Window, timeout: u64) {
@@ -246,45 +826,131 @@ impl Bar {
         }
     }
 
+    /// Hides the bar or blanks its sensitive modules while the session is locked,
+    /// restoring them exactly as they were once unlocked.
+    #[cfg(feature = "lock")]
+    fn setup_lock_behavior(&self, behavior: LockBehavior, popup: &Rc<Popup>) {
+        if behavior == LockBehavior::Normal {
+            return;
+        }
+
+        let client = match self.ironbar.clients.borrow_mut().session_lock() {
+            Ok(client) => client,
+            Err(err) => {
+                error!("{:?}", err.wrap_err("Failed to watch session lock state"));
+                return;
+            }
+        };
+
+        let mut rx = client.subscribe();
+
+        let window = self.window.clone();
+        let hotspot_window = self.hotspot_window.clone();
+        let content = self.content.clone();
+        let popup = popup.clone();
+
+        glib::spawn_future_local(async move {
+            let mut locked_state = None;
+
+            loop {
+                let locked = *rx.borrow_and_update();
+
+                match (locked, locked_state.take()) {
+                    (true, None) => {
+                        popup.hide();
+
+                        locked_state = Some(match behavior {
+                            LockBehavior::Hide => {
+                                LockedState::hide(&window, hotspot_window.as_ref())
+                            }
+                            _ => LockedState::censor(&content),
+                        });
+                    }
+                    (false, Some(state)) => state.restore(),
+                    (_, state) => locked_state = state,
+                }
+
+                if rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
     /// Loads the configured modules onto a bar.
     fn load_modules(&self, config: BarConfig, monitor: &Monitor) -> Result<BarLoadResult> {
         let icon_theme = IconTheme::new();
         if let Some(ref theme) = config.icon_theme {
             icon_theme.set_custom_theme(Some(theme));
         }
+        add_export_search_paths(&icon_theme);
 
         let app = &self.window.application().expect("to exist");
+        let scale = style::font_scale(config.font.as_ref());
 
         macro_rules! info {
             ($location:expr) => {
                 ModuleInfo {
                     app,
                     bar_position: config.position,
+                    bar_name: &self.name,
                     monitor,
                     output_name: &self.monitor_name,
                     location: $location,
                     icon_theme: &icon_theme,
+                    scale,
+                    scroll: config.scroll,
+                    // set for each module from its own config
+                    touch: TouchConfig::default(),
                 }
             };
         }
 
         // popup ignores module location so can bodge this for now
-        let popup = Popup::new(&info!(ModuleLocation::Left), config.popup_gap);
+        let popup = Popup::new(
+            &info!(ModuleLocation::Left),
+            config.popup_gap,
+            self.ironbar.clone(),
+        );
+        self.font.attach(&popup.window);
         let popup = Rc::new(popup);
 
         if let Some(modules) = config.start {
             let info = info!(ModuleLocation::Left);
-            add_modules(&self.start, modules, &info, &self.ironbar, &popup)?;
+            add_modules(&self.start, modules, &info, &self.ironbar, &popup);
         }
 
-        if let Some(modules) = config.center {
+        if let Some(mut modules) = config.center {
             let info = info!(ModuleLocation::Center);
-            add_modules(&self.center, modules, &info, &self.ironbar, &popup)?;
+
+            if let Some(cutout) = &self.cutout {
+                let split = cutout
+                    .config
+                    .split
+                    .unwrap_or_else(|| modules.len().div_ceil(2))
+                    .min(modules.len());
+
+                let after = modules.split_off(split);
+                add_modules(&cutout.after, after, &info, &self.ironbar, &popup);
+            }
+
+            add_modules(&self.center, modules, &info, &self.ironbar, &popup);
         }
 
         if let Some(modules) = config.end {
             let info = info!(ModuleLocation::Right);
-            add_modules(&self.end, modules, &info, &self.ironbar, &popup)?;
+            add_modules(&self.end, modules, &info, &self.ironbar, &popup);
+        }
+
+        if config.overflow {
+            Overflow::new(
+                &self.content,
+                &self.start,
+                &self.end,
+                config.position.orientation(),
+                self.available_length.clone(),
+                &popup,
+            );
         }
 
         let result = BarLoadResult { popup };
@@ -302,6 +968,14 @@ impl Bar {
         self.end.show();
         self.content.show();
 
+        if let Some(cutout) = &self.cutout {
+            cutout.show();
+        }
+
+        if let Some(auto_size) = &self.auto_size {
+            auto_size.show();
+        }
+
         if include_window {
             self.window.show();
         }
@@ -316,6 +990,25 @@ impl Bar {
         &self.monitor_name
     }
 
+    pub fn position(&self) -> BarPosition {
+        self.position
+    }
+
+    /// The size of the exclusive zone the bar currently reserves,
+    /// or `0` if it is hidden or does not reserve one.
+    pub fn exclusive_zone(&self) -> i32 {
+        if self.window.is_visible() {
+            self.window.exclusive_zone().max(0)
+        } else {
+            0
+        }
+    }
+
+    /// See [`BarConfig::structure_key`].
+    pub fn structure_key(&self) -> &str {
+        &self.structure_key
+    }
+
     pub fn popup(&self) -> Rc<Popup> {
         match &self.inner {
             Inner::New { .. } => {
@@ -334,6 +1027,20 @@ impl Bar {
         self.window.set_visible(visible)
     }
 
+    /// Whether the bar should be styled differently
+    /// while its output does not contain the focused window.
+    pub fn dim_unfocused(&self) -> bool {
+        self.dim_unfocused
+    }
+
+    /// Sets whether the bar's output contains the focused window,
+    /// adding the `.unfocused` class if not and `dim_unfocused` is enabled.
+    pub fn set_output_focused(&self, focused: bool) {
+        if self.dim_unfocused {
+            self.window.set_class("unfocused", !focused);
+        }
+    }
+
     pub fn set_exclusive(&self, exclusive: bool) {
         if exclusive {
             self.window.auto_exclusive_zone_enable();
@@ -341,6 +1048,117 @@ impl Bar {
             self.window.set_exclusive_zone(0);
         }
     }
+
+    /// Grants the bar keyboard focus,
+    /// allowing its widgets to be navigated using the keyboard.
+    ///
+    /// Popups opened while the bar has focus take focus too.
+    /// Focus is returned to the compositor on escape,
+    /// or when the user focuses another surface.
+    pub fn focus(&self) {
+        debug!("Granting keyboard focus to bar '{}'", self.name);
+
+        self.keyboard_focus.set(true);
+        self.window.set_keyboard_mode(KeyboardMode::OnDemand);
+        self.window.set_focus_visible(true);
+        self.window.present();
+
+        if self.window.focused_widget().is_none() {
+            self.content.child_focus(DirectionType::TabForward);
+        }
+
+        self.popup().set_keyboard_focus(true);
+    }
+}
+
+/// Returns the bar's keyboard focus to the compositor.
+fn release_keyboard_focus(window: &ApplicationWindow, keyboard_focus: &Cell<bool>, popup: &Popup) {
+    debug!("Releasing keyboard focus");
+
+    keyboard_focus.set(false);
+    window.set_keyboard_mode(KeyboardMode::None);
+    window.set_focus(None::<&gtk::Widget>);
+    popup.set_keyboard_focus(false);
+}
+
+/// What was changed while the session is locked,
+/// to be put back once unlocked.
+#[cfg(feature = "lock")]
+enum LockedState {
+    /// Each bar window, with whether it was visible.
+    Hidden(Vec<(gtk::Widget, bool)>),
+    /// Each sensitive module, with its opacity and whether it was sensitive to input.
+    Censored(Vec<(gtk::Widget, f64, bool)>),
+}
+
+#[cfg(feature = "lock")]
+impl LockedState {
+    fn hide(window: &ApplicationWindow, hotspot_window: Option<&Window>) -> Self {
+        let windows = std::iter::once(window.upcast_ref::<gtk::Widget>())
+            .chain(hotspot_window.map(|window| window.upcast_ref()))
+            .map(|window| {
+                let visible = window.is_visible();
+                window.hide();
+                (window.clone(), visible)
+            })
+            .collect();
+
+        Self::Hidden(windows)
+    }
+
+    /// Blanks the sensitive modules, keeping their space on the bar.
+    fn censor(content: &gtk::Box) -> Self {
+        let mut widgets = vec![];
+        find_sensitive(content.upcast_ref(), &mut widgets);
+
+        let widgets = widgets
+            .into_iter()
+            .map(|widget| {
+                let (opacity, sensitive) = (widget.opacity(), widget.is_sensitive());
+
+                widget.set_opacity(0.0);
+                widget.set_sensitive(false);
+                widget.set_class("censored", true);
+
+                (widget, opacity, sensitive)
+            })
+            .collect();
+
+        Self::Censored(widgets)
+    }
+
+    fn restore(self) {
+        match self {
+            Self::Hidden(windows) => {
+                for (window, visible) in windows {
+                    window.set_visible(visible);
+                }
+            }
+            Self::Censored(widgets) => {
+                for (widget, opacity, sensitive) in widgets {
+                    widget.set_opacity(opacity);
+                    widget.set_sensitive(sensitive);
+                    widget.set_class("censored", false);
+                }
+            }
+        }
+    }
+}
+
+/// Finds the modules inside the container which show private content,
+/// including those inside groups.
+#[cfg(feature = "lock")]
+fn find_sensitive(container: &gtk::Container, widgets: &mut Vec<gtk::Widget>) {
+    for child in container.children() {
+        if child
+            .get_tag::<bool>(SENSITIVE_TAG)
+            .is_some_and(|&sensitive| sensitive)
+        {
+            widgets.push(child);
+        } else if let Some(container) = child.downcast_ref::<gtk::Container>() {
+            find_sensitive(container, widgets);
+        }
+    }
 }
 
 /// Creates a `gtk::Box` container to place widgets inside.
@@ -368,14 +1186,12 @@ fn add_modules(
     info: &ModuleInfo,
     ironbar: &Rc<Ironbar>,
     popup: &Rc<Popup>,
-) -> Result<()> {
+) {
     let module_factory = BarModuleFactory::new(ironbar.clone(), popup.clone()).into();
 
     for config in modules {
-        config.create(&module_factory, content, info)?;
+        config.create(&module_factory, content, info);
     }
-
-    Ok(())
 }
 
 pub fn create_bar(
@@ -388,3 +1204,87 @@ pub fn create_bar(
     let bar = Bar::new(app, monitor_name, config, ironbar);
     bar.init(monitor)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_auto_size() {
+        assert_eq!(clamp_auto_size(30, None, None), 30);
+        assert_eq!(clamp_auto_size(30, Some(40), None), 40);
+        assert_eq!(clamp_auto_size(300, Some(20), Some(60)), 60);
+        assert_eq!(clamp_auto_size(30, Some(80), Some(60)), 60);
+    }
+
+    fn edge(length: Option<BarLength>, align: BarAlign) -> EdgeConfig {
+        EdgeConfig {
+            length,
+            align,
+            anchor_to_edges: true,
+            margin: (10, 20),
+        }
+    }
+
+    #[test]
+    fn fills_edge_without_length() {
+        let placement = edge(None, BarAlign::Start).placement(1920);
+
+        assert_eq!(placement.anchor, (true, true));
+        assert_eq!(placement.margin, (10, 20));
+        assert_eq!(placement.length, -1);
+        assert_eq!(placement.origin, None);
+        assert_eq!(placement.window_length(1920), 1890);
+    }
+
+    #[test]
+    fn centers_without_end_anchors() {
+        let placement = edge(Some(BarLength::Percent(50.0)), BarAlign::Center).placement(1920);
+
+        assert_eq!(placement.anchor, (false, false));
+        assert_eq!(placement.length, 960);
+        assert_eq!(placement.origin, None);
+        assert_eq!(placement.window_length(1920), 960);
+    }
+
+    #[test]
+    fn aligns_using_margins() {
+        let start = edge(Some(BarLength::Pixels(500)), BarAlign::Start).placement(1920);
+        assert_eq!(start.anchor, (true, true));
+        assert_eq!(start.margin, (10, 1410));
+        assert_eq!(start.origin, Some(10));
+        assert_eq!(start.window_length(1920), 500);
+
+        let end = edge(Some(BarLength::Pixels(500)), BarAlign::End).placement(1920);
+        assert_eq!(end.margin, (1400, 20));
+        assert_eq!(end.origin, Some(1400));
+    }
+
+    #[test]
+    fn clamps_length_to_output() {
+        let placement = edge(Some(BarLength::Pixels(5000)), BarAlign::Start).placement(1920);
+
+        assert_eq!(placement.margin, (10, 20));
+    }
+
+    #[test]
+    fn places_cutout_within_window() {
+        let cutout = CutoutConfig {
+            start: 880,
+            end: 1040,
+            split: None,
+        };
+
+        let full = edge(None, BarAlign::Start).placement(1920);
+        assert_eq!(full.window_start(1920), 10);
+        assert_eq!(cutout_layout(cutout, 10, 1890), (160, 10));
+
+        let centered = edge(Some(BarLength::Pixels(1000)), BarAlign::Center).placement(1920);
+        assert_eq!(centered.window_start(1920), 460);
+        assert_eq!(cutout_layout(cutout, 460, 1000), (160, 0));
+
+        let start = edge(Some(BarLength::Pixels(1000)), BarAlign::Start).placement(1920);
+        assert_eq!(start.window_start(1920), 10);
+        assert_eq!(cutout_layout(cutout, 10, 1000), (160, 900));
+    }
+}
@@ -40,12 +40,17 @@ pub enum Format {
 }
 
 pub fn handle_response(response: Response, format: Format) {
-    let is_err = matches!(response, Response::Err { .. });
+    let exit_code = match response {
+        Response::Err { .. } => Some(ExitCode::IpcResponseError),
+        Response::Mismatch { .. } => Some(ExitCode::IpcMismatch),
+        _ => None,
+    };
 
     match format {
         Format::Plain => match response {
             Response::Ok => println!("ok"),
             Response::OkValue { value } => println!("{value}"),
+            Response::Mismatch { value } => println!("{}", value.unwrap_or_default()),
             Response::Err { message } => eprintln!("error\n{}", message.unwrap_or_default()),
         },
         Format::Json => println!(
@@ -54,7 +59,7 @@ pub fn handle_response(response: Response, format: Format) {
         ),
     }
 
-    if is_err {
-        exit(ExitCode::IpcResponseError as i32)
+    if let Some(exit_code) = exit_code {
+        exit(exit_code as i32)
     }
 }
@@ -0,0 +1,585 @@
+// only the battery levels are used by the volume client
+#![cfg_attr(not(feature = "bluetooth"), allow(dead_code))]
+
+use crate::{register_fallible_client, spawn};
+use color_eyre::{Report, Result};
+use futures_lite::StreamExt;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::{debug, error};
+use zbus::fdo::{
+    DBusProxy, InterfacesAdded, InterfacesRemoved, ManagedObjects, ObjectManagerProxy,
+    PropertiesChanged,
+};
+use zbus::names::OwnedInterfaceName;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{MatchRule, Message, MessageStream, MessageType};
+
+const SERVICE: &str = "org.bluez";
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+
+#[zbus::dbus_proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+trait Adapter {
+    #[dbus_proxy(property)]
+    fn powered(&self) -> zbus::Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn set_powered(&self, powered: bool) -> zbus::Result<()>;
+}
+
+#[zbus::dbus_proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+trait Device {
+    fn connect(&self) -> zbus::Result<()>;
+
+    fn disconnect(&self) -> zbus::Result<()>;
+}
+
+/// The Bluetooth adapter in use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adapter {
+    /// The BlueZ object path, such as `/org/bluez/hci0`.
+    pub path: String,
+    pub powered: bool,
+}
+
+/// A device paired with the adapter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// The BlueZ object path, such as `/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF`.
+    pub path: String,
+    /// The device address, such as `AA:BB:CC:DD:EE:FF`.
+    pub address: String,
+    /// The user-set alias, falling back to the name the device reports.
+    pub name: String,
+    pub connected: bool,
+    /// The battery percentage, if the device reports it.
+    pub battery: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BluetoothState {
+    /// The first adapter, or `None` if there is no adapter or BlueZ is not running.
+    pub adapter: Option<Adapter>,
+    /// Devices paired with the adapter, sorted by name.
+    pub devices: Vec<Device>,
+}
+
+impl BluetoothState {
+    /// Whether any paired device is connected.
+    pub fn is_connected(&self) -> bool {
+        self.devices.iter().any(|device| device.connected)
+    }
+}
+
+/// Tracks the Bluetooth adapter and its paired devices through BlueZ.
+///
+/// This is shared by the Bluetooth module and the volume client,
+/// which shows the battery level of Bluetooth sinks.
+///
+/// The objects BlueZ manages are read once,
+/// then kept up to date from its signals.
+/// BlueZ not running is treated the same as there being no adapter,
+/// and it is picked up if it starts later.
+#[derive(Debug)]
+pub struct Client {
+    dbus: zbus::Connection,
+    tx: watch::Sender<BluetoothState>,
+    _rx: watch::Receiver<BluetoothState>,
+    batteries_tx: watch::Sender<HashMap<String, u8>>,
+    _batteries_rx: watch::Receiver<HashMap<String, u8>>,
+}
+
+impl Client {
+    pub async fn new() -> Result<Self> {
+        let dbus = Box::pin(zbus::Connection::system()).await?;
+
+        let manager = ObjectManagerProxy::builder(&dbus)
+            .destination(SERVICE)?
+            .path("/")?
+            .build()
+            .await?;
+
+        // every signal from BlueZ, which includes devices being added or removed
+        // through `InterfacesAdded` and `InterfacesRemoved`, and property changes.
+        // these come through one stream so that they are applied in order.
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(SERVICE)?
+            .build();
+        let mut signals = MessageStream::for_match_rule(rule, &dbus, None).await?;
+
+        // BlueZ starting or stopping
+        let mut owner_changes = DBusProxy::new(&dbus)
+            .await?
+            .receive_name_owner_changed_with_args(&[(0, SERVICE)])
+            .await?;
+
+        // subscribed before reading objects, so that nothing is missed in between
+        let mut objects = read_objects(&manager).await;
+
+        let (tx, rx) = watch::channel(parse_objects(&objects));
+        let (batteries_tx, batteries_rx) = watch::channel(parse_batteries(&objects));
+
+        {
+            let tx = tx.clone();
+            let batteries_tx = batteries_tx.clone();
+
+            spawn(async move {
+                loop {
+                    tokio::select! {
+                        Some(message) = signals.next() => match message {
+                            Ok(message) => {
+                                if let Err(err) = apply_signal(&mut objects, message) {
+                                    error!("Failed to read BlueZ signal: {err:?}");
+                                }
+                            }
+                            Err(err) => error!("Failed to read BlueZ signal: {err:?}"),
+                        },
+                        // everything is read again, as any objects are stale
+                        Some(_) = owner_changes.next() => objects = read_objects(&manager).await,
+                        else => break,
+                    }
+
+                    update(&tx, parse_objects(&objects));
+                    update(&batteries_tx, parse_batteries(&objects));
+                }
+
+                error!("BlueZ signal stream closed");
+            });
+        }
+
+        Ok(Self {
+            dbus,
+            tx,
+            _rx: rx,
+            batteries_tx,
+            _batteries_rx: batteries_rx,
+        })
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<BluetoothState> {
+        self.tx.subscribe()
+    }
+
+    /// Gets the last known state.
+    pub fn state(&self) -> BluetoothState {
+        self.tx.borrow().clone()
+    }
+
+    /// Subscribes to the battery level of every device which reports it,
+    /// by device address, whichever adapter it is paired with.
+    pub fn subscribe_batteries(&self) -> watch::Receiver<HashMap<String, u8>> {
+        self.batteries_tx.subscribe()
+    }
+
+    /// Turns the adapter on or off.
+    pub async fn set_powered(&self, powered: bool) -> Result<()> {
+        let path = self
+            .state()
+            .adapter
+            .map(|adapter| adapter.path)
+            .ok_or_else(|| Report::msg("No Bluetooth adapter found"))?;
+
+        AdapterProxy::builder(&self.dbus)
+            .path(path)?
+            .build()
+            .await?
+            .set_powered(powered)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Connects to the device with the object path.
+    pub async fn connect(&self, path: &str) -> Result<()> {
+        self.device(path).await?.connect().await?;
+        Ok(())
+    }
+
+    /// Disconnects from the device with the object path.
+    pub async fn disconnect(&self, path: &str) -> Result<()> {
+        self.device(path).await?.disconnect().await?;
+        Ok(())
+    }
+
+    async fn device(&self, path: &str) -> Result<DeviceProxy<'static>> {
+        let proxy = DeviceProxy::builder(&self.dbus)
+            .path(path.to_string())?
+            .build()
+            .await?;
+
+        Ok(proxy)
+    }
+}
+
+/// Reads every object BlueZ manages,
+/// returning none if it is not running.
+async fn read_objects(manager: &ObjectManagerProxy<'_>) -> ManagedObjects {
+    manager.get_managed_objects().await.unwrap_or_else(|err| {
+        debug!("Failed to read BlueZ objects: {err}");
+        ManagedObjects::new()
+    })
+}
+
+/// Sends the value to subscribers if it has changed.
+fn update<T: PartialEq + Debug>(tx: &watch::Sender<T>, value: T) {
+    tx.send_if_modified(|current| {
+        let changed = *current != value;
+        if changed {
+            debug!("Bluetooth state changed: {value:?}");
+            *current = value;
+        }
+        changed
+    });
+}
+
+/// Applies an object being added or removed,
+/// or its properties changing, to the objects BlueZ manages.
+fn apply_signal(objects: &mut ManagedObjects, message: Arc<Message>) -> Result<()> {
+    if let Some(signal) = InterfacesAdded::from_message(message.clone()) {
+        let args = signal.args()?;
+        let path = OwnedObjectPath::from(args.object_path().to_owned());
+        add_interfaces(objects, path, args.interfaces_and_properties())?;
+    } else if let Some(signal) = InterfacesRemoved::from_message(message.clone()) {
+        let args = signal.args()?;
+        let path = OwnedObjectPath::from(args.object_path().to_owned());
+        remove_interfaces(objects, &path, args.interfaces());
+    } else if let Some(signal) = PropertiesChanged::from_message(message) {
+        let Some(path) = signal.path() else {
+            return Ok(());
+        };
+
+        let args = signal.args()?;
+        update_properties(
+            objects,
+            &OwnedObjectPath::from(path.to_owned()),
+            args.interface_name().as_str(),
+            args.changed_properties(),
+            args.invalidated_properties(),
+        );
+    }
+
+    Ok(())
+}
+
+fn add_interfaces(
+    objects: &mut ManagedObjects,
+    path: OwnedObjectPath,
+    interfaces: &HashMap<&str, HashMap<&str, Value<'_>>>,
+) -> Result<()> {
+    let object = objects.entry(path).or_default();
+
+    for (&name, properties) in interfaces {
+        let properties = properties
+            .iter()
+            .map(|(&key, value)| (key.to_string(), OwnedValue::from(value)))
+            .collect();
+
+        object.insert(OwnedInterfaceName::try_from(name)?, properties);
+    }
+
+    Ok(())
+}
+
+fn remove_interfaces(objects: &mut ManagedObjects, path: &OwnedObjectPath, interfaces: &[&str]) {
+    if let Some(object) = objects.get_mut(path) {
+        object.retain(|name, _| !interfaces.contains(&name.as_str()));
+
+        if object.is_empty() {
+            objects.remove(path);
+        }
+    }
+}
+
+/// Updates the properties of an interface already known about.
+///
+/// Invalidated properties are removed,
+/// as their new values are not sent.
+fn update_properties(
+    objects: &mut ManagedObjects,
+    path: &OwnedObjectPath,
+    interface: &str,
+    changed: &HashMap<&str, Value<'_>>,
+    invalidated: &[&str],
+) {
+    let Some(properties) = objects
+        .get_mut(path)
+        .and_then(|object| object.get_mut(interface))
+    else {
+        return;
+    };
+
+    for (&key, value) in changed {
+        properties.insert(key.to_string(), OwnedValue::from(value));
+    }
+
+    for key in invalidated {
+        properties.remove(*key);
+    }
+}
+
+/// Builds the state from the objects BlueZ manages,
+/// using the first adapter and the devices paired with it.
+fn parse_objects(objects: &ManagedObjects) -> BluetoothState {
+    let adapter = objects
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            let properties = interfaces.get(ADAPTER_INTERFACE)?;
+
+            Some(Adapter {
+                path: path.to_string(),
+                powered: property(properties, "Powered").unwrap_or_default(),
+            })
+        })
+        .min_by(|a, b| a.path.cmp(&b.path));
+
+    let Some(adapter) = adapter else {
+        return BluetoothState::default();
+    };
+
+    let mut devices = objects
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            let properties = interfaces.get(DEVICE_INTERFACE)?;
+
+            let adapter_path = property::<OwnedObjectPath>(properties, "Adapter")?;
+            let paired = property::<bool>(properties, "Paired").unwrap_or_default();
+
+            if adapter_path.as_str() != adapter.path || !paired {
+                return None;
+            }
+
+            let address = property::<String>(properties, "Address").unwrap_or_default();
+
+            Some(Device {
+                path: path.to_string(),
+                name: property(properties, "Alias")
+                    .or_else(|| property(properties, "Name"))
+                    .unwrap_or_else(|| address.clone()),
+                address,
+                connected: property(properties, "Connected").unwrap_or_default(),
+                battery: interfaces
+                    .get(BATTERY_INTERFACE)
+                    .and_then(|properties| property(properties, "Percentage")),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // sorted by name only, so devices do not move around as they connect
+    devices.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.address.cmp(&b.address))
+    });
+
+    BluetoothState {
+        adapter: Some(adapter),
+        devices,
+    }
+}
+
+/// Gets the battery level of each device which reports it, by address.
+fn parse_batteries(objects: &ManagedObjects) -> HashMap<String, u8> {
+    objects
+        .values()
+        .filter_map(|interfaces| {
+            let address = property::<String>(interfaces.get(DEVICE_INTERFACE)?, "Address")?;
+            let level = property(interfaces.get(BATTERY_INTERFACE)?, "Percentage")?;
+
+            Some((address.to_uppercase(), level))
+        })
+        .collect()
+}
+
+fn property<T: TryFrom<OwnedValue>>(
+    properties: &HashMap<String, OwnedValue>,
+    name: &str,
+) -> Option<T> {
+    properties
+        .get(name)
+        .cloned()
+        .and_then(|value| T::try_from(value).ok())
+}
+
+register_fallible_client!(Client, bluetooth);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::ObjectPath;
+
+    fn object(
+        path: &str,
+        interfaces: &[(&str, Vec<(&str, Value)>)],
+    ) -> (
+        OwnedObjectPath,
+        HashMap<OwnedInterfaceName, HashMap<String, OwnedValue>>,
+    ) {
+        let interfaces = interfaces
+            .iter()
+            .map(|(name, properties)| {
+                let properties = properties
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), OwnedValue::from(value)))
+                    .collect();
+
+                (
+                    OwnedInterfaceName::try_from(*name).expect("valid interface name"),
+                    properties,
+                )
+            })
+            .collect();
+
+        (
+            OwnedObjectPath::try_from(path).expect("valid object path"),
+            interfaces,
+        )
+    }
+
+    fn device<'a>(adapter: &'a str, name: &'a str, paired: bool) -> Vec<(&'a str, Value<'a>)> {
+        vec![
+            (
+                "Adapter",
+                Value::from(ObjectPath::try_from(adapter).expect("valid object path")),
+            ),
+            ("Address", Value::from("AA:BB:CC:DD:EE:FF")),
+            ("Alias", Value::from(name)),
+            ("Paired", Value::from(paired)),
+            ("Connected", Value::from(name == "Headphones")),
+        ]
+    }
+
+    #[test]
+    fn reads_adapter_and_paired_devices() {
+        let objects = ManagedObjects::from([
+            object(
+                "/org/bluez/hci1",
+                &[(ADAPTER_INTERFACE, vec![("Powered", Value::from(false))])],
+            ),
+            object(
+                "/org/bluez/hci0",
+                &[(ADAPTER_INTERFACE, vec![("Powered", Value::from(true))])],
+            ),
+            object(
+                "/org/bluez/hci0/dev_1",
+                &[
+                    (
+                        DEVICE_INTERFACE,
+                        device("/org/bluez/hci0", "Headphones", true),
+                    ),
+                    (BATTERY_INTERFACE, vec![("Percentage", Value::from(80u8))]),
+                ],
+            ),
+            object(
+                "/org/bluez/hci0/dev_2",
+                &[(
+                    DEVICE_INTERFACE,
+                    device("/org/bluez/hci0", "keyboard", true),
+                )],
+            ),
+            object(
+                "/org/bluez/hci0/dev_3",
+                &[(DEVICE_INTERFACE, device("/org/bluez/hci0", "Nearby", false))],
+            ),
+            object(
+                "/org/bluez/hci1/dev_4",
+                &[(DEVICE_INTERFACE, device("/org/bluez/hci1", "Mouse", true))],
+            ),
+        ]);
+
+        let state = parse_objects(&objects);
+
+        assert_eq!(
+            state.adapter,
+            Some(Adapter {
+                path: String::from("/org/bluez/hci0"),
+                powered: true,
+            })
+        );
+
+        let names = state
+            .devices
+            .iter()
+            .map(|device| device.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Headphones", "keyboard"]);
+
+        assert!(state.is_connected());
+        assert_eq!(state.devices[0].battery, Some(80));
+        assert_eq!(state.devices[1].battery, None);
+    }
+
+    #[test]
+    fn reads_missing_adapter() {
+        assert_eq!(
+            parse_objects(&ManagedObjects::new()),
+            BluetoothState::default()
+        );
+    }
+
+    #[test]
+    fn applies_changes() {
+        let adapter = object(
+            "/org/bluez/hci0",
+            &[(ADAPTER_INTERFACE, vec![("Powered", Value::from(true))])],
+        );
+        let mut objects = ManagedObjects::from([adapter]);
+
+        let path = OwnedObjectPath::try_from("/org/bluez/hci0/dev_1").expect("valid object path");
+
+        add_interfaces(
+            &mut objects,
+            path.clone(),
+            &HashMap::from([(
+                DEVICE_INTERFACE,
+                device("/org/bluez/hci0", "Speaker", true)
+                    .into_iter()
+                    .collect(),
+            )]),
+        )
+        .expect("to add interfaces");
+
+        assert_eq!(parse_objects(&objects).devices[0].name, "Speaker");
+        assert!(!parse_objects(&objects).is_connected());
+
+        update_properties(
+            &mut objects,
+            &path,
+            DEVICE_INTERFACE,
+            &HashMap::from([("Connected", Value::from(true))]),
+            &["Alias"],
+        );
+
+        let state = parse_objects(&objects);
+        assert!(state.is_connected());
+        assert_eq!(state.devices[0].name, "AA:BB:CC:DD:EE:FF");
+
+        add_interfaces(
+            &mut objects,
+            path.clone(),
+            &HashMap::from([(
+                BATTERY_INTERFACE,
+                HashMap::from([("Percentage", Value::from(40u8))]),
+            )]),
+        )
+        .expect("to add interfaces");
+
+        assert_eq!(
+            parse_batteries(&objects),
+            HashMap::from([(String::from("AA:BB:CC:DD:EE:FF"), 40)])
+        );
+
+        remove_interfaces(&mut objects, &path, &[BATTERY_INTERFACE]);
+        assert!(parse_batteries(&objects).is_empty());
+        assert_eq!(parse_objects(&objects).devices.len(), 1);
+
+        remove_interfaces(&mut objects, &path, &[DEVICE_INTERFACE]);
+        assert!(parse_objects(&objects).devices.is_empty());
+        assert_eq!(objects.len(), 1);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::clients::wayland::{ClipboardItem, ClipboardValue, Selection};
+use crate::state::{state_file, write_state};
+use crate::{spawn, spawn_blocking, Ironbar};
+use glib::Bytes;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::{debug, error};
+
+const STATE_FILE: &str = "clipboard.json";
+
+/// Options for saving the clipboard history,
+/// so that it is restored after restarting Ironbar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Persistence {
+    /// The maximum number of items to save.
+    pub max_items: usize,
+    /// The maximum size of images to save, in bytes.
+    /// Images are not saved if this is zero.
+    pub image_max_size: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct SavedItem {
+    mime_type: String,
+    selection: Selection,
+    #[serde(flatten)]
+    value: SavedValue,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SavedValue {
+    Text(String),
+    /// The image, encoded as base64.
+    Image(String),
+}
+
+impl Persistence {
+    /// Loads the saved items, oldest first.
+    ///
+    /// Returns an empty list if the history has not been saved,
+    /// or cannot be read.
+    pub fn load(self) -> Vec<ClipboardItem> {
+        let Some(path) = state_file(STATE_FILE) else {
+            return vec![];
+        };
+
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return vec![];
+        };
+
+        match serde_json::from_str::<Vec<SavedItem>>(&contents) {
+            Ok(items) => {
+                let skip = items.len().saturating_sub(self.max_items);
+                debug!("Loaded {} saved clipboard items", items.len() - skip);
+
+                items
+                    .into_iter()
+                    .skip(skip)
+                    .map(SavedItem::into_item)
+                    .collect()
+            }
+            Err(err) => {
+                error!("Failed to read clipboard history: {err:?}");
+                vec![]
+            }
+        }
+    }
+
+    /// Starts a task saving the items sent on the returned channel, oldest first.
+    ///
+    /// Writing happens away from the async runtime,
+    /// and only the latest items are saved
+    /// if several are sent while a write is in progress.
+    pub fn saver(self) -> watch::Sender<Vec<ClipboardItem>> {
+        let (tx, mut rx) = watch::channel(vec![]);
+
+        spawn(async move {
+            while rx.changed().await.is_ok() {
+                let items = rx.borrow_and_update().clone();
+
+                if let Err(err) = spawn_blocking(move || self.save(items.iter())).await {
+                    error!("Failed to save clipboard history: {err:?}");
+                }
+            }
+        });
+
+        tx
+    }
+
+    /// Saves the items, oldest first.
+    ///
+    /// Sensitive items, and images over the size limit, are skipped.
+    /// Only the newest `max_items` of the rest are saved.
+    fn save<'a>(self, items: impl Iterator<Item = &'a ClipboardItem>) {
+        let Some(path) = state_file(STATE_FILE) else {
+            return;
+        };
+
+        let items = self.saved_items(items);
+
+        let contents = match serde_json::to_string(&items) {
+            Ok(contents) => contents,
+            Err(err) => {
+                error!("Failed to save clipboard history: {err:?}");
+                return;
+            }
+        };
+
+        if let Err(err) = write_state(&path, contents) {
+            error!("Failed to save clipboard history: {err:?}");
+        }
+    }
+
+    fn saved_items<'a>(self, items: impl Iterator<Item = &'a ClipboardItem>) -> Vec<SavedItem> {
+        let mut items = items
+            .filter_map(|item| SavedItem::from_item(item, self.image_max_size))
+            .collect::<Vec<_>>();
+
+        let skip = items.len().saturating_sub(self.max_items);
+        items.drain(..skip);
+
+        items
+    }
+}
+
+impl SavedItem {
+    /// Gets the item to save,
+    /// or `None` if it should not be saved.
+    fn from_item(item: &ClipboardItem, image_max_size: usize) -> Option<Self> {
+        if item.sensitive {
+            return None;
+        }
+
+        let value = match item.value.as_ref() {
+            ClipboardValue::Text(text) => SavedValue::Text(text.clone()),
+            ClipboardValue::Image(bytes) if image_max_size > 0 && bytes.len() <= image_max_size => {
+                SavedValue::Image(glib::base64_encode(bytes).to_string())
+            }
+            ClipboardValue::Image(_) | ClipboardValue::Other => return None,
+        };
+
+        Some(Self {
+            mime_type: item.mime_type.to_string(),
+            selection: item.selection,
+            value,
+        })
+    }
+
+    fn into_item(self) -> ClipboardItem {
+        let value = match self.value {
+            SavedValue::Text(text) => ClipboardValue::Text(text),
+            SavedValue::Image(image) => {
+                ClipboardValue::Image(Bytes::from_owned(glib::base64_decode(&image)))
+            }
+        };
+
+        ClipboardItem {
+            id: Ironbar::unique_id(),
+            value: Arc::new(value),
+            mime_type: self.mime_type.into(),
+            selection: self.selection,
+            sensitive: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(value: ClipboardValue, sensitive: bool) -> ClipboardItem {
+        ClipboardItem {
+            id: 0,
+            value: Arc::new(value),
+            mime_type: "text/plain".into(),
+            selection: Selection::Clipboard,
+            sensitive,
+        }
+    }
+
+    fn text(text: &str) -> ClipboardValue {
+        ClipboardValue::Text(text.to_string())
+    }
+
+    #[test]
+    fn skips_unsaved_items() {
+        let persistence = Persistence {
+            max_items: 2,
+            image_max_size: 4,
+        };
+
+        let items = [
+            item(text("first"), false),
+            item(text("second"), false),
+            item(text("password"), true),
+            item(ClipboardValue::Image(Bytes::from_static(&[0; 8])), false),
+            item(text("third"), false),
+        ];
+
+        let saved = persistence.saved_items(items.iter());
+
+        let values = saved.iter().map(|item| &item.value).collect::<Vec<_>>();
+
+        assert_eq!(
+            values,
+            [
+                &SavedValue::Text(String::from("second")),
+                &SavedValue::Text(String::from("third"))
+            ]
+        );
+    }
+
+    #[test]
+    fn saves_in_order() {
+        let persistence = Persistence {
+            max_items: 10,
+            image_max_size: 0,
+        };
+
+        let items = [
+            item(text("first"), false),
+            item(ClipboardValue::Image(Bytes::from_static(&[])), false),
+            item(text("second"), false),
+        ];
+        let saved = persistence.saved_items(items.iter());
+
+        let json = serde_json::to_string(&saved).expect("items to serialize");
+        assert_eq!(
+            json,
+            r#"[{"mime_type":"text/plain","selection":"clipboard","text":"first"},{"mime_type":"text/plain","selection":"clipboard","text":"second"}]"#
+        );
+
+        let loaded = serde_json::from_str::<Vec<SavedItem>>(&json).expect("items to parse");
+        assert_eq!(loaded, saved);
+    }
+}
@@ -0,0 +1,362 @@
+mod history;
+
+pub use self::history::Persistence;
+
+use super::wayland::{self, ClipboardItem, ClipboardValue, MimeFilter, Selection};
+use crate::{arc_mut, lock, register_client, spawn, try_send, Ironbar};
+use indexmap::map::Iter;
+use indexmap::IndexMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, watch};
+use tracing::{debug, trace};
+
+#[derive(Debug)]
+pub enum ClipboardEvent {
+    Add(ClipboardItem),
+    Remove(usize),
+    Activate(usize),
+}
+
+type EventSender = mpsc::Sender<ClipboardEvent>;
+
+/// Clipboard client singleton,
+/// to ensure bars don't duplicate requests to the compositor.
+#[derive(Debug)]
+pub struct Client {
+    wayland: Arc<wayland::Client>,
+
+    senders: Arc<Mutex<Vec<(EventSender, usize)>>>,
+    cache: Arc<Mutex<ClipboardCache>>,
+}
+
+impl Client {
+    pub(crate) fn new(wl: Arc<wayland::Client>) -> Self {
+        trace!("Initializing clipboard client");
+
+        let senders = arc_mut!(Vec::<(EventSender, usize)>::new());
+
+        let cache = arc_mut!(ClipboardCache::new());
+
+        {
+            let senders = senders.clone();
+            let cache = cache.clone();
+            let wl = wl.clone();
+
+            spawn(async move {
+                let item = wl.clipboard_item();
+                let mut rx = wl.subscribe_clipboard();
+
+                if let Some(item) = item {
+                    let senders = lock!(senders);
+                    let iter = senders.iter();
+                    for (tx, _) in iter {
+                        try_send!(tx, ClipboardEvent::Add(item.clone()));
+                    }
+
+                    lock!(cache).insert(item, senders.len());
+                }
+
+                while let Ok(item) = rx.recv().await {
+                    debug!("Received clipboard item (ID: {})", item.id);
+                    Self::add(&senders, &cache, item);
+                }
+            });
+        }
+
+        Self {
+            wayland: wl,
+            senders,
+            cache,
+        }
+    }
+
+    /// Adds the item to the history,
+    /// or activates the existing entry if it is already stored.
+    fn add(
+        senders: &Mutex<Vec<(EventSender, usize)>>,
+        cache: &Mutex<ClipboardCache>,
+        item: ClipboardItem,
+    ) {
+        let (existing_id, cache_size) = {
+            let cache = lock!(cache);
+            (cache.contains(&item), cache.len())
+        };
+
+        existing_id.map_or_else(
+            || {
+                {
+                    let mut cache = lock!(cache);
+                    let senders = lock!(senders);
+                    cache.insert(item.clone(), senders.len());
+                }
+                let senders = lock!(senders);
+                let iter = senders.iter();
+                for (tx, sender_cache_size) in iter {
+                    if cache_size == *sender_cache_size {
+                        let removed_id = lock!(cache)
+                            .remove_ref_first()
+                            .expect("Clipboard cache unexpectedly empty");
+                        try_send!(tx, ClipboardEvent::Remove(removed_id));
+                    }
+                    try_send!(tx, ClipboardEvent::Add(item.clone()));
+                }
+
+                lock!(cache).save();
+            },
+            |existing_id| {
+                // only the regular clipboard is shown as active
+                if item.selection != Selection::Clipboard {
+                    return;
+                }
+
+                let senders = lock!(senders);
+                let iter = senders.iter();
+                for (tx, _) in iter {
+                    try_send!(tx, ClipboardEvent::Activate(existing_id));
+                }
+            },
+        );
+    }
+
+    pub fn subscribe(&self, cache_size: usize) -> mpsc::Receiver<ClipboardEvent> {
+        let (tx, rx) = mpsc::channel(16);
+
+        {
+            let cache = lock!(self.cache);
+
+            let iter = cache.iter();
+            for (_, (item, _)) in iter {
+                try_send!(tx, ClipboardEvent::Add(item.clone()));
+            }
+        }
+
+        lock!(self.senders).push((tx, cache_size));
+
+        rx
+    }
+
+    /// Sets the filter controlling which offered mime types
+    /// are stored in the clipboard history.
+    ///
+    /// As the client is shared, this applies to all subscribers.
+    pub fn set_mime_filter(&self, filter: MimeFilter) {
+        self.wayland.set_clipboard_filter(filter);
+    }
+
+    /// Saves the history to disk whenever it changes,
+    /// and restores any previously saved items before the current ones.
+    ///
+    /// As the client is shared, this applies to all subscribers.
+    /// Saved items are only restored the first time this is called,
+    /// so should be called before subscribing.
+    pub fn set_persistence(&self, persistence: Persistence) {
+        if lock!(self.cache).saver.is_some() {
+            return;
+        }
+
+        let items = persistence.load();
+        let ref_count = lock!(self.senders).len().max(1);
+
+        let mut cache = lock!(self.cache);
+        if cache.saver.is_some() {
+            return;
+        }
+
+        cache.restore(items, persistence.max_items, ref_count);
+        cache.saver = Some(persistence.saver());
+    }
+
+    /// Sets which selections are stored in the history.
+    ///
+    /// As the client is shared, this applies to all subscribers.
+    pub fn set_selections(&self, selections: Vec<Selection>) {
+        self.wayland.set_clipboard_selections(selections);
+    }
+
+    /// Copies the item to the selection it was read from,
+    /// or to both the clipboard and primary selection if `both` is set.
+    pub fn copy(&self, id: usize, both: bool) {
+        debug!("Copying item with id {id}");
+
+        let Some(item) = self.item(id) else {
+            return;
+        };
+
+        let selections = if both {
+            vec![Selection::Clipboard, Selection::Primary]
+        } else {
+            vec![item.selection]
+        };
+
+        let activate = selections.contains(&Selection::Clipboard);
+        self.wayland.copy_to_clipboard(item, selections);
+
+        if !activate {
+            return;
+        }
+
+        let senders = lock!(self.senders);
+        let iter = senders.iter();
+        for (tx, _) in iter {
+            try_send!(tx, ClipboardEvent::Activate(id));
+        }
+    }
+
+    /// Copies new text to the clipboard, adding it to the history.
+    ///
+    /// Items copied by Ironbar are not read back from the compositor,
+    /// so the history is updated here instead.
+    pub fn copy_text(&self, text: String) {
+        let item = ClipboardItem {
+            id: Ironbar::unique_id(),
+            value: Arc::new(ClipboardValue::Text(text)),
+            mime_type: "text/plain;charset=utf-8".into(),
+            selection: Selection::Clipboard,
+            sensitive: false,
+        };
+
+        self.wayland
+            .copy_to_clipboard(item.clone(), vec![Selection::Clipboard]);
+        Self::add(&self.senders, &self.cache, item);
+    }
+
+    /// Gets the item with the ID from the history.
+    pub fn item(&self, id: usize) -> Option<ClipboardItem> {
+        lock!(self.cache).get(id)
+    }
+
+    pub fn remove(&self, id: usize) {
+        {
+            let mut cache = lock!(self.cache);
+            cache.remove(id);
+            cache.save();
+        }
+
+        let senders = lock!(self.senders);
+        let iter = senders.iter();
+        for (tx, _) in iter {
+            try_send!(tx, ClipboardEvent::Remove(id));
+        }
+    }
+}
+
+/// Shared clipboard item cache.
+///
+/// Items are stored with a number of references,
+/// allowing different consumers to 'remove' cached items
+/// at different times.
+#[derive(Debug)]
+struct ClipboardCache {
+    cache: IndexMap<usize, (ClipboardItem, usize)>,
+    /// Sends the items to save to disk, if enabled.
+    saver: Option<watch::Sender<Vec<ClipboardItem>>>,
+}
+
+impl ClipboardCache {
+    /// Creates a new empty cache.
+    fn new() -> Self {
+        Self {
+            cache: IndexMap::new(),
+            saver: None,
+        }
+    }
+
+    /// Gets the entry with key `id` from the cache.
+    fn get(&self, id: usize) -> Option<ClipboardItem> {
+        self.cache.get(&id).map(|(item, _)| item).cloned()
+    }
+
+    /// Inserts an entry with `ref_count` initial references.
+    fn insert(&mut self, item: ClipboardItem, ref_count: usize) -> Option<ClipboardItem> {
+        self.cache
+            .insert(item.id, (item, ref_count))
+            .map(|(item, _)| item)
+    }
+
+    /// Removes the entry with key `id`.
+    /// This ignores references.
+    fn remove(&mut self, id: usize) -> Option<ClipboardItem> {
+        self.cache.shift_remove(&id).map(|(item, _)| item)
+    }
+
+    /// Removes a reference to the entry with key `id`.
+    ///
+    /// If the reference count reaches zero, the entry
+    /// is removed from the cache.
+    fn remove_ref(&mut self, id: usize) {
+        if let Some(entry) = self.cache.get_mut(&id) {
+            entry.1 -= 1;
+
+            if entry.1 == 0 {
+                self.cache.shift_remove(&id);
+            }
+        }
+    }
+
+    /// Removes a reference to the first entry.
+    ///
+    /// If the reference count reaches zero, the entry
+    /// is removed from the cache.
+    fn remove_ref_first(&mut self) -> Option<usize> {
+        if let Some((id, _)) = self.cache.first() {
+            let id = *id;
+            self.remove_ref(id);
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    /// Checks if an item with matching mime type and value
+    /// already exists in the cache.
+    fn contains(&self, item: &ClipboardItem) -> Option<usize> {
+        self.cache.values().find_map(|(it, _)| {
+            if it.mime_type == item.mime_type && it.value == item.value {
+                Some(it.id)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Inserts previously saved items before the current entries,
+    /// keeping their order.
+    ///
+    /// Items already in the cache are skipped,
+    /// and only the newest are restored if there is not room for all of them.
+    fn restore(&mut self, items: Vec<ClipboardItem>, max_items: usize, ref_count: usize) {
+        let mut items = items
+            .into_iter()
+            .filter(|item| self.contains(item).is_none())
+            .collect::<Vec<_>>();
+
+        let space = max_items.saturating_sub(self.len());
+        items.drain(..items.len().saturating_sub(space));
+
+        for (index, item) in items.into_iter().enumerate() {
+            self.cache.shift_insert(index, item.id, (item, ref_count));
+        }
+    }
+
+    /// Saves the cache to disk, if enabled.
+    ///
+    /// Only a snapshot of the items is taken here,
+    /// and is written by a separate task.
+    fn save(&self) {
+        if let Some(saver) = &self.saver {
+            let items = self.cache.values().map(|(item, _)| item.clone()).collect();
+            saver.send_replace(items);
+        }
+    }
+
+    /// Gets the current number of items in the cache.
+    fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    fn iter(&self) -> Iter<'_, usize, (ClipboardItem, usize)> {
+        self.cache.iter()
+    }
+}
+
+register_client!(Client, clipboard);
@@ -0,0 +1,200 @@
+use super::{Workspace, WorkspaceClient, WorkspaceUpdate};
+use crate::spawn;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::{debug, trace};
+
+/// Tracks which output contains the focused window,
+/// shared between all bars.
+#[derive(Debug)]
+pub struct OutputFocus {
+    tx: watch::Sender<Option<String>>,
+}
+
+impl OutputFocus {
+    pub(crate) fn new(client: &Arc<dyn WorkspaceClient>) -> Self {
+        let (tx, _) = watch::channel(None);
+
+        {
+            let tx = tx.clone();
+            let mut rx = client.subscribe_workspace_change();
+
+            spawn(async move {
+                let mut state = FocusState::default();
+
+                while let Ok(update) = rx.recv().await {
+                    state.apply(update);
+
+                    let output = state.focused_output();
+                    tx.send_if_modified(|current| {
+                        if *current == output {
+                            false
+                        } else {
+                            debug!("Focused output changed: {current:?} -> {output:?}");
+                            *current = output;
+                            true
+                        }
+                    });
+                }
+            });
+        }
+
+        Self { tx }
+    }
+
+    /// Gets the name of the output containing the focused window,
+    /// or `None` if the focused workspace is empty.
+    pub fn current(&self) -> Option<String> {
+        self.tx.borrow().clone()
+    }
+
+    /// Creates a receiver which is notified whenever the focused output changes.
+    pub fn subscribe(&self) -> watch::Receiver<Option<String>> {
+        self.tx.subscribe()
+    }
+}
+
+/// The workspace state needed to find the focused output.
+#[derive(Debug, Default)]
+pub(crate) struct FocusState {
+    workspaces: HashMap<i64, Workspace>,
+    focused: Option<i64>,
+}
+
+impl FocusState {
+    pub(crate) fn apply(&mut self, update: WorkspaceUpdate) {
+        trace!("Applying workspace update to focus state: {update:?}");
+
+        match update {
+            WorkspaceUpdate::Init(workspaces) => {
+                self.focused = workspaces
+                    .iter()
+                    .find(|workspace| workspace.visibility.is_focused())
+                    .map(|workspace| workspace.id);
+
+                self.workspaces = workspaces
+                    .into_iter()
+                    .map(|workspace| (workspace.id, workspace))
+                    .collect();
+            }
+            WorkspaceUpdate::Add(workspace) | WorkspaceUpdate::Move(workspace) => {
+                if workspace.visibility.is_focused() {
+                    self.focused = Some(workspace.id);
+                }
+
+                self.workspaces.insert(workspace.id, workspace);
+            }
+            WorkspaceUpdate::Update(workspace) => {
+                if let Some(existing) = self.workspaces.get_mut(&workspace.id) {
+                    existing.windows = workspace.windows;
+                } else {
+                    self.workspaces.insert(workspace.id, workspace);
+                }
+            }
+            WorkspaceUpdate::Remove(id) => {
+                self.workspaces.remove(&id);
+
+                if self.focused == Some(id) {
+                    self.focused = None;
+                }
+            }
+            WorkspaceUpdate::Focus { old, new } => {
+                if let Some(old) = old {
+                    self.workspaces.insert(old.id, old);
+                }
+
+                self.focused = Some(new.id);
+                self.workspaces.insert(new.id, new);
+            }
+            WorkspaceUpdate::Rename { id, name } => {
+                if let Some(workspace) = self.workspaces.get_mut(&id) {
+                    workspace.name = name;
+                }
+            }
+            WorkspaceUpdate::Unknown => {}
+        }
+    }
+
+    /// Gets the focused workspace, if known.
+    pub(crate) fn focused_workspace(&self) -> Option<&Workspace> {
+        self.focused.and_then(|id| self.workspaces.get(&id))
+    }
+
+    /// Gets every known workspace, in no particular order.
+    pub(crate) fn workspaces(&self) -> impl Iterator<Item = &Workspace> {
+        self.workspaces.values()
+    }
+
+    fn focused_output(&self) -> Option<String> {
+        self.focused_workspace()
+            .filter(|workspace| !workspace.is_empty())
+            .map(|workspace| workspace.monitor.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::compositor::Visibility;
+
+    fn workspace(id: i64, monitor: &str, visibility: Visibility, windows: usize) -> Workspace {
+        Workspace {
+            id,
+            name: id.to_string(),
+            monitor: monitor.to_string(),
+            visibility,
+            windows,
+            persistent: false,
+        }
+    }
+
+    #[test]
+    fn follows_focused_workspace() {
+        let mut state = FocusState::default();
+
+        state.apply(WorkspaceUpdate::Init(vec![
+            workspace(1, "DP-1", Visibility::focused(), 1),
+            workspace(2, "DP-2", Visibility::visible(), 1),
+        ]));
+        assert_eq!(state.focused_output().as_deref(), Some("DP-1"));
+
+        state.apply(WorkspaceUpdate::Focus {
+            old: None,
+            new: workspace(2, "DP-2", Visibility::focused(), 1),
+        });
+        assert_eq!(state.focused_output().as_deref(), Some("DP-2"));
+
+        state.apply(WorkspaceUpdate::Move(workspace(
+            2,
+            "DP-1",
+            Visibility::focused(),
+            1,
+        )));
+        assert_eq!(state.focused_output().as_deref(), Some("DP-1"));
+    }
+
+    #[test]
+    fn empty_workspaces_are_unfocused() {
+        let mut state = FocusState::default();
+
+        state.apply(WorkspaceUpdate::Init(vec![workspace(
+            1,
+            "DP-1",
+            Visibility::focused(),
+            0,
+        )]));
+        assert_eq!(state.focused_output(), None);
+
+        state.apply(WorkspaceUpdate::Update(workspace(
+            1,
+            "DP-1",
+            Visibility::Hidden,
+            1,
+        )));
+        assert_eq!(state.focused_output().as_deref(), Some("DP-1"));
+
+        state.apply(WorkspaceUpdate::Remove(1));
+        assert_eq!(state.focused_output(), None);
+    }
+}
@@ -1,30 +1,67 @@
-use super::{Visibility, Workspace, WorkspaceClient, WorkspaceUpdate};
+use super::{
+    KeyboardLayoutUpdate, StateUpdate, Visibility, Workspace, WorkspaceClient, WorkspaceUpdate,
+};
 use crate::{arc_mut, lock, send, spawn_blocking};
-use color_eyre::Result;
-use hyprland::data::{Workspace as HWorkspace, Workspaces};
-use hyprland::dispatch::{Dispatch, DispatchType, WorkspaceIdentifierWithSpecial};
+use color_eyre::{Report, Result};
+use hyprland::ctl::switch_xkb_layout::{self, SwitchXKBLayoutCmdTypes};
+use hyprland::data::{
+    Clients, Devices, Monitors, Workspace as HWorkspace, WorkspaceRules, Workspaces,
+};
+use hyprland::dispatch::{
+    Dispatch, DispatchType, WindowIdentifier, WorkspaceIdentifierWithSpecial,
+};
 use hyprland::event_listener::EventListener;
 use hyprland::prelude::*;
 use hyprland::shared::{HyprDataVec, WorkspaceType};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast::{channel, Receiver, Sender};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, trace};
+
+/// Names of the workspaces set as persistent in Hyprland's workspace rules.
+type PersistentRules = Arc<Mutex<HashSet<String>>>;
 
 #[derive(Debug)]
 pub struct Client {
     workspace_tx: Sender<WorkspaceUpdate>,
     _workspace_rx: Receiver<WorkspaceUpdate>,
+
+    state_tx: Sender<StateUpdate>,
+    _state_rx: Receiver<StateUpdate>,
+
+    keyboard_tx: Sender<KeyboardLayoutUpdate>,
+    _keyboard_rx: Receiver<KeyboardLayoutUpdate>,
+
+    persistent: PersistentRules,
+    monitors: Arc<Mutex<WorkspaceMonitors>>,
+    /// Held while handling an event,
+    /// so that events from the listener and the socket are applied in turn.
+    event_lock: Arc<Mutex<()>>,
 }
 
 impl Client {
     pub(crate) fn new() -> Self {
         let (workspace_tx, workspace_rx) = channel(16);
+        let (state_tx, state_rx) = channel(16);
+        let (keyboard_tx, keyboard_rx) = channel(16);
 
         let instance = Self {
             workspace_tx,
             _workspace_rx: workspace_rx,
+            state_tx,
+            _state_rx: state_rx,
+            keyboard_tx,
+            _keyboard_rx: keyboard_rx,
+            persistent: arc_mut!(get_persistent_rules()),
+            monitors: arc_mut!(WorkspaceMonitors::fetch()),
+            event_lock: arc_mut!(()),
         };
 
         instance.listen_workspace_events();
+        instance.listen_socket_events();
         instance
     }
 
@@ -32,21 +69,34 @@ impl Client {
         info!("Starting Hyprland event listener");
 
         let tx = self.workspace_tx.clone();
+        let state_tx = self.state_tx.clone();
+        let keyboard_tx = self.keyboard_tx.clone();
+        let persistent = self.persistent.clone();
+        let lock = self.event_lock.clone();
 
         spawn_blocking(move || {
             let mut event_listener = EventListener::new();
 
-            // we need a lock to ensure events don't run at the same time
-            let lock = arc_mut!(());
-
             // cache the active workspace since Hyprland doesn't give us the prev active
-            let active = Self::get_active_workspace().expect("Failed to get active workspace");
+            let active = Self::get_active_workspace(&lock!(persistent))
+                .expect("Failed to get active workspace");
             let active = arc_mut!(Some(active));
 
+            // cache the window count of each workspace, to detect changes on window events
+            let windows = arc_mut!(Workspaces::get()
+                .map(|workspaces| {
+                    workspaces
+                        .into_iter()
+                        .map(|w| (w.id as i64, w.windows as usize))
+                        .collect::<HashMap<_, _>>()
+                })
+                .unwrap_or_default());
+
             {
                 let tx = tx.clone();
                 let lock = lock.clone();
                 let active = active.clone();
+                let persistent = persistent.clone();
 
                 event_listener.add_workspace_added_handler(move |workspace_type| {
                     let _lock = lock!(lock);
@@ -55,7 +105,11 @@ impl Client {
                     let workspace_name = get_workspace_name(workspace_type);
                     let prev_workspace = lock!(active);
 
-                    let workspace = Self::get_workspace(&workspace_name, prev_workspace.as_ref());
+                    let workspace = Self::get_workspace(
+                        &workspace_name,
+                        prev_workspace.as_ref(),
+                        &lock!(persistent),
+                    );
 
                     if let Some(workspace) = workspace {
                         send!(tx, WorkspaceUpdate::Add(workspace));
@@ -67,6 +121,7 @@ impl Client {
                 let tx = tx.clone();
                 let lock = lock.clone();
                 let active = active.clone();
+                let persistent = persistent.clone();
 
                 event_listener.add_workspace_change_handler(move |workspace_type| {
                     let _lock = lock!(lock);
@@ -79,7 +134,11 @@ impl Client {
                     );
 
                     let workspace_name = get_workspace_name(workspace_type);
-                    let workspace = Self::get_workspace(&workspace_name, prev_workspace.as_ref());
+                    let workspace = Self::get_workspace(
+                        &workspace_name,
+                        prev_workspace.as_ref(),
+                        &lock!(persistent),
+                    );
 
                     workspace.map_or_else(
                         || {
@@ -99,6 +158,7 @@ impl Client {
                 let tx = tx.clone();
                 let lock = lock.clone();
                 let active = active.clone();
+                let persistent = persistent.clone();
 
                 event_listener.add_active_monitor_change_handler(move |event_data| {
                     let _lock = lock!(lock);
@@ -112,7 +172,11 @@ impl Client {
                     );
 
                     let workspace_name = get_workspace_name(workspace_type);
-                    let workspace = Self::get_workspace(&workspace_name, prev_workspace.as_ref());
+                    let workspace = Self::get_workspace(
+                        &workspace_name,
+                        prev_workspace.as_ref(),
+                        &lock!(persistent),
+                    );
 
                     if let Some((false, workspace)) =
                         workspace.map(|w| (w.visibility.is_focused(), w))
@@ -124,29 +188,8 @@ impl Client {
                 });
             }
 
-            {
-                let tx = tx.clone();
-                let lock = lock.clone();
-
-                event_listener.add_workspace_moved_handler(move |event_data| {
-                    let _lock = lock!(lock);
-                    let workspace_type = event_data.workspace;
-                    debug!("Received workspace move: {workspace_type:?}");
-
-                    let mut prev_workspace = lock!(active);
-
-                    let workspace_name = get_workspace_name(workspace_type);
-                    let workspace = Self::get_workspace(&workspace_name, prev_workspace.as_ref());
-
-                    if let Some(workspace) = workspace {
-                        send!(tx, WorkspaceUpdate::Move(workspace.clone()));
-
-                        if !workspace.visibility.is_focused() {
-                            Self::send_focus_change(&mut prev_workspace, workspace, &tx);
-                        }
-                    }
-                });
-            }
+            // moves are sent from `listen_socket_events`,
+            // which follows the monitor of every workspace.
 
             {
                 let tx = tx.clone();
@@ -166,19 +209,222 @@ impl Client {
             }
 
             {
+                let tx = tx.clone();
+                let lock = lock.clone();
+                let windows = windows.clone();
+
                 event_listener.add_workspace_destroy_handler(move |data| {
                     let _lock = lock!(lock);
                     debug!("Received workspace destroy: {data:?}");
+
+                    lock!(windows).remove(&(data.workspace_id as i64));
                     send!(tx, WorkspaceUpdate::Remove(data.workspace_id as i64));
                 });
             }
 
+            {
+                let tx = tx.clone();
+                let lock = lock.clone();
+                let windows = windows.clone();
+                let persistent = persistent.clone();
+
+                event_listener.add_window_open_handler(move |_| {
+                    let _lock = lock!(lock);
+                    Self::send_window_changes(&windows, &lock!(persistent), &tx);
+                });
+            }
+
+            {
+                let tx = tx.clone();
+                let lock = lock.clone();
+                let windows = windows.clone();
+                let persistent = persistent.clone();
+
+                event_listener.add_window_close_handler(move |_| {
+                    let _lock = lock!(lock);
+                    Self::send_window_changes(&windows, &lock!(persistent), &tx);
+                });
+            }
+
+            {
+                event_listener.add_window_moved_handler(move |_| {
+                    let _lock = lock!(lock);
+                    Self::send_window_changes(&windows, &lock!(persistent), &tx);
+                });
+            }
+
+            {
+                let state_tx = state_tx.clone();
+
+                event_listener.add_urgent_state_handler(move |address| {
+                    debug!("Received urgent window: {address}");
+
+                    let client = Clients::get().ok().and_then(|clients| {
+                        clients.into_iter().find(|client| client.address == address)
+                    });
+
+                    if let Some(client) = client {
+                        state_tx
+                            .send(StateUpdate::Urgent {
+                                app_id: client.class,
+                                title: client.title,
+                                urgent: true,
+                            })
+                            .ok();
+                    }
+                });
+            }
+
+            {
+                event_listener.add_keyboard_layout_change_handler(move |event| {
+                    debug!(
+                        "Received keyboard layout change on '{}': {}",
+                        event.keyboard_name, event.layout_name
+                    );
+
+                    // there may be no subscribers yet
+                    keyboard_tx
+                        .send(KeyboardLayoutUpdate(event.layout_name))
+                        .ok();
+                });
+            }
+
+            {
+                event_listener.add_sub_map_change_handler(move |submap| {
+                    debug!("Received submap change: {submap}");
+
+                    // there may be no subscribers yet
+                    state_tx.send(StateUpdate::Submap(submap)).ok();
+                });
+            }
+
             event_listener
                 .start_listener()
                 .expect("Failed to start listener");
         });
     }
 
+    /// Listens for Hyprland reloading its config,
+    /// and re-fetches the workspace rules when it does.
+    /// Changes to the active special workspace are also sent from here.
+    ///
+    /// This also follows which monitor each workspace is on,
+    /// sending a `WorkspaceUpdate::Move` event whenever one is assigned to a new monitor,
+    /// so that each bar can pick up workspaces created or moved onto its monitor.
+    /// This is the only source of move events.
+    ///
+    /// The event listener does not support these events,
+    /// or does not include the monitor with them,
+    /// so the event socket is read directly.
+    fn listen_socket_events(&self) {
+        let tx = self.workspace_tx.clone();
+        let state_tx = self.state_tx.clone();
+        let persistent = self.persistent.clone();
+        let monitors = self.monitors.clone();
+        let lock = self.event_lock.clone();
+
+        spawn_blocking(move || {
+            let Some(path) = event_socket_path() else {
+                error!("Failed to find Hyprland event socket");
+                return;
+            };
+
+            let stream = match UnixStream::connect(&path) {
+                Ok(stream) => stream,
+                Err(err) => {
+                    error!("Failed to connect to Hyprland event socket: {err:?}");
+                    return;
+                }
+            };
+
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+
+                if let Some(data) = line.strip_prefix("activespecial>>") {
+                    if let Some(update) = parse_active_special(data) {
+                        debug!("Received special workspace change: {update:?}");
+                        state_tx.send(update).ok();
+                    }
+
+                    continue;
+                }
+
+                if let Some(event) = MonitorEvent::parse(&line) {
+                    trace!("Received workspace monitor event: {event:?}");
+                    let _lock = lock!(lock);
+
+                    let assigned = lock!(monitors).apply(event);
+                    if let Some((id, monitor)) = assigned {
+                        debug!("Workspace {id} assigned to monitor '{monitor}'");
+
+                        match Self::get_workspace_by_id(id, &lock!(persistent)) {
+                            Some(mut workspace) => {
+                                workspace.monitor = monitor;
+                                send!(tx, WorkspaceUpdate::Move(workspace));
+                            }
+                            None => debug!("Workspace {id} no longer exists"),
+                        }
+                    }
+
+                    continue;
+                }
+
+                if !line.starts_with("configreloaded>>") {
+                    continue;
+                }
+
+                debug!("Hyprland config reloaded, re-fetching workspace rules");
+
+                let rules = get_persistent_rules();
+                let changed = *lock!(persistent) != rules;
+
+                if changed {
+                    let workspaces = Workspaces::get().map_or(Vec::new(), HyprDataVec::to_vec);
+
+                    for w in workspaces {
+                        let workspace = Workspace::from((Visibility::Hidden, w, &rules));
+                        send!(tx, WorkspaceUpdate::Update(workspace));
+                    }
+
+                    *lock!(persistent) = rules;
+                }
+            }
+
+            error!("Hyprland event socket closed");
+        });
+    }
+
+    /// Checks which workspaces have gained or lost windows since last checked,
+    /// and sends a `WorkspaceUpdate::Update` event for each.
+    fn send_window_changes(
+        windows: &Mutex<HashMap<i64, usize>>,
+        persistent: &HashSet<String>,
+        tx: &Sender<WorkspaceUpdate>,
+    ) {
+        let workspaces = match Workspaces::get() {
+            Ok(workspaces) => workspaces,
+            Err(err) => {
+                error!("Failed to get workspaces: {err:?}");
+                return;
+            }
+        };
+
+        let mut windows = lock!(windows);
+
+        for w in workspaces {
+            let count = w.windows as usize;
+
+            if windows.insert(w.id as i64, count) != Some(count) {
+                debug!("Workspace '{}' windows: {count}", w.name);
+
+                let workspace = Workspace::from((Visibility::Hidden, w, persistent));
+                send!(tx, WorkspaceUpdate::Update(workspace));
+            }
+        }
+    }
+
     /// Sends a `WorkspaceUpdate::Focus` event
     /// and updates the active workspace cache.
     fn send_focus_change(
@@ -198,7 +444,11 @@ impl Client {
     }
 
     /// Gets a workspace by name from the server, given the active workspace if known.
-    fn get_workspace(name: &str, active: Option<&Workspace>) -> Option<Workspace> {
+    fn get_workspace(
+        name: &str,
+        active: Option<&Workspace>,
+        persistent: &HashSet<String>,
+    ) -> Option<Workspace> {
         Workspaces::get()
             .expect("Failed to get workspaces")
             .into_iter()
@@ -208,16 +458,32 @@ impl Client {
                         create_is_visible()(w)
                     }));
 
-                    Some(Workspace::from((vis, w)))
+                    Some(Workspace::from((vis, w, persistent)))
                 } else {
                     None
                 }
             })
     }
 
+    /// Gets a workspace by ID from the server, with its current visibility.
+    fn get_workspace_by_id(id: i64, persistent: &HashSet<String>) -> Option<Workspace> {
+        let active = HWorkspace::get_active().ok().map(|active| active.name);
+        let is_visible = create_is_visible();
+
+        Workspaces::get()
+            .ok()?
+            .into_iter()
+            .find(|w| w.id as i64 == id)
+            .map(|w| {
+                let vis = Visibility::from((&w, active.as_deref(), &is_visible));
+                Workspace::from((vis, w, persistent))
+            })
+    }
+
     /// Gets the active workspace from the server.
-    fn get_active_workspace() -> Result<Workspace> {
-        let w = HWorkspace::get_active().map(|w| Workspace::from((Visibility::focused(), w)))?;
+    fn get_active_workspace(persistent: &HashSet<String>) -> Result<Workspace> {
+        let w = HWorkspace::get_active()
+            .map(|w| Workspace::from((Visibility::focused(), w, persistent)))?;
         Ok(w)
     }
 }
@@ -233,6 +499,26 @@ impl WorkspaceClient for Client {
         Ok(())
     }
 
+    fn move_window(&self, app_id: &str, title: &str, workspace: &str) -> Result<()> {
+        // windows with the title come first, then the most recently focused
+        let window = Clients::get()?
+            .into_iter()
+            .filter(|client| client.class == app_id)
+            .min_by_key(|client| (client.title != title, client.focus_history_id))
+            .ok_or_else(|| Report::msg(format!("No window found for app '{app_id}'")))?;
+
+        let identifier = workspace.parse::<i32>().map_or_else(
+            |_| WorkspaceIdentifierWithSpecial::Name(workspace),
+            WorkspaceIdentifierWithSpecial::Id,
+        );
+
+        Dispatch::call(DispatchType::MoveToWorkspaceSilent(
+            identifier,
+            Some(WindowIdentifier::Address(window.address)),
+        ))?;
+        Ok(())
+    }
+
     fn subscribe_workspace_change(&self) -> Receiver<WorkspaceUpdate> {
         let rx = self.workspace_tx.subscribe();
 
@@ -241,6 +527,7 @@ impl WorkspaceClient for Client {
 
             let active_id = HWorkspace::get_active().ok().map(|active| active.name);
             let is_visible = create_is_visible();
+            let persistent = lock!(self.persistent);
 
             let workspaces = Workspaces::get()
                 .expect("Failed to get workspaces")
@@ -248,7 +535,7 @@ impl WorkspaceClient for Client {
                 .map(|w| {
                     let vis = Visibility::from((&w, active_id.as_deref(), &is_visible));
 
-                    Workspace::from((vis, w))
+                    Workspace::from((vis, w, &*persistent))
                 })
                 .collect();
 
@@ -257,6 +544,222 @@ impl WorkspaceClient for Client {
 
         rx
     }
+
+    fn subscribe_state_change(&self) -> Receiver<StateUpdate> {
+        self.state_tx.subscribe()
+    }
+
+    fn next_keyboard_layout(&self) -> Result<()> {
+        switch_xkb_layout::call("all", SwitchXKBLayoutCmdTypes::Next)?;
+        Ok(())
+    }
+
+    fn subscribe_keyboard_layout(&self) -> Receiver<KeyboardLayoutUpdate> {
+        let rx = self.keyboard_tx.subscribe();
+
+        match Devices::get() {
+            Ok(devices) => {
+                // devices such as power buttons also show up as keyboards,
+                // so the main keyboard is preferred
+                let keyboard = devices
+                    .keyboards
+                    .iter()
+                    .find(|keyboard| keyboard.main)
+                    .or_else(|| devices.keyboards.first());
+
+                if let Some(keyboard) = keyboard {
+                    send!(
+                        self.keyboard_tx,
+                        KeyboardLayoutUpdate(keyboard.active_keymap.clone())
+                    );
+                }
+            }
+            Err(err) => error!("Failed to get keyboards: {err:?}"),
+        }
+
+        rx
+    }
+}
+
+/// Parses the data of an `activespecial` event,
+/// in the format `NAME,MONITOR`.
+///
+/// The name is empty when the special workspace is closed,
+/// and otherwise includes a `special:` prefix which is removed.
+fn parse_active_special(data: &str) -> Option<StateUpdate> {
+    let (name, monitor) = data.rsplit_once(',')?;
+
+    let name =
+        (!name.is_empty()).then(|| name.strip_prefix("special:").unwrap_or(name).to_string());
+
+    Some(StateUpdate::SpecialWorkspace {
+        monitor: monitor.to_string(),
+        name,
+    })
+}
+
+/// A workspace event from Hyprland's event socket
+/// which affects which monitor a workspace is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MonitorEvent {
+    /// `createworkspacev2>>ID,NAME`
+    Create { id: i64, name: String },
+    /// `destroyworkspacev2>>ID,NAME`
+    Destroy { id: i64 },
+    /// `renameworkspace>>ID,NAME`
+    Rename { id: i64, name: String },
+    /// `moveworkspacev2>>ID,NAME,MONITOR`
+    Move {
+        id: i64,
+        name: String,
+        monitor: String,
+    },
+    /// `workspacev2>>ID,NAME`, sent when a workspace is activated on the focused monitor.
+    Activate { id: i64, name: String },
+    /// `focusedmon>>MONITOR,WORKSPACE`, including the monitor's active workspace name.
+    FocusMonitor { monitor: String, workspace: String },
+}
+
+impl MonitorEvent {
+    /// Parses a line from the event socket,
+    /// returning `None` for unrelated events.
+    ///
+    /// Workspace names may contain commas, but monitor names cannot.
+    fn parse(line: &str) -> Option<Self> {
+        let (event, data) = line.split_once(">>")?;
+
+        let id_name = || {
+            let (id, name) = data.split_once(',')?;
+            Some((id.parse::<i64>().ok()?, name.to_string()))
+        };
+
+        match event {
+            "createworkspacev2" => id_name().map(|(id, name)| Self::Create { id, name }),
+            "destroyworkspacev2" => id_name().map(|(id, _)| Self::Destroy { id }),
+            "renameworkspace" => id_name().map(|(id, name)| Self::Rename { id, name }),
+            "workspacev2" => id_name().map(|(id, name)| Self::Activate { id, name }),
+            "moveworkspacev2" => {
+                let (id, rest) = data.split_once(',')?;
+                let (name, monitor) = rest.rsplit_once(',')?;
+
+                Some(Self::Move {
+                    id: id.parse().ok()?,
+                    name: name.to_string(),
+                    monitor: monitor.to_string(),
+                })
+            }
+            "focusedmon" => {
+                let (monitor, workspace) = data.split_once(',')?;
+
+                Some(Self::FocusMonitor {
+                    monitor: monitor.to_string(),
+                    workspace: workspace.to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Tracks which monitor each workspace is on,
+/// following events in the order Hyprland sends them.
+#[derive(Debug, Default)]
+struct WorkspaceMonitors {
+    /// Monitor names, keyed by workspace ID.
+    /// Newly created workspaces are left out until they are seen on a monitor.
+    monitors: HashMap<i64, String>,
+    /// Workspace IDs, keyed by name.
+    ids: HashMap<String, i64>,
+    /// The name of the focused monitor.
+    focused: Option<String>,
+}
+
+impl WorkspaceMonitors {
+    fn new(
+        workspaces: impl IntoIterator<Item = (i64, String, String)>,
+        focused: Option<String>,
+    ) -> Self {
+        let mut monitors = Self {
+            focused,
+            ..Self::default()
+        };
+
+        for (id, name, monitor) in workspaces {
+            monitors.ids.insert(name, id);
+            monitors.monitors.insert(id, monitor);
+        }
+
+        monitors
+    }
+
+    /// Gets the current workspaces and focused monitor from the server.
+    fn fetch() -> Self {
+        let workspaces = Workspaces::get().map_or(Vec::new(), HyprDataVec::to_vec);
+        let focused = Monitors::get()
+            .ok()
+            .and_then(|monitors| monitors.into_iter().find(|monitor| monitor.focused))
+            .map(|monitor| monitor.name);
+
+        Self::new(
+            workspaces
+                .into_iter()
+                .map(|w| (w.id as i64, w.name, w.monitor)),
+            focused,
+        )
+    }
+
+    /// Applies an event, returning the workspace ID and monitor
+    /// if the workspace was assigned to a new monitor.
+    fn apply(&mut self, event: MonitorEvent) -> Option<(i64, String)> {
+        match event {
+            MonitorEvent::Create { id, name } => {
+                self.ids.insert(name, id);
+                self.monitors.remove(&id);
+                None
+            }
+            MonitorEvent::Destroy { id } => {
+                self.ids.retain(|_, workspace| *workspace != id);
+                self.monitors.remove(&id);
+                None
+            }
+            MonitorEvent::Rename { id, name } => {
+                self.ids.retain(|_, workspace| *workspace != id);
+                self.ids.insert(name, id);
+                None
+            }
+            MonitorEvent::Move { id, name, monitor } => {
+                self.ids.insert(name, id);
+                self.assign(id, monitor)
+            }
+            MonitorEvent::Activate { id, name } => {
+                self.ids.insert(name, id);
+
+                // activating a workspace only places it if it was just created,
+                // since moving it to another monitor sends its own event
+                if self.monitors.contains_key(&id) {
+                    None
+                } else {
+                    let monitor = self.focused.clone()?;
+                    self.assign(id, monitor)
+                }
+            }
+            MonitorEvent::FocusMonitor { monitor, workspace } => {
+                self.focused = Some(monitor.clone());
+
+                let id = *self.ids.get(&workspace)?;
+                self.assign(id, monitor)
+            }
+        }
+    }
+
+    fn assign(&mut self, id: i64, monitor: String) -> Option<(i64, String)> {
+        if self.monitors.get(&id) == Some(&monitor) {
+            None
+        } else {
+            self.monitors.insert(id, monitor.clone());
+            Some((id, monitor))
+        }
+    }
 }
 
 fn get_workspace_name(name: WorkspaceType) -> String {
@@ -276,13 +779,61 @@ fn create_is_visible() -> impl Fn(&HWorkspace) -> bool {
     move |w| monitors.iter().any(|m| m.active_workspace.id == w.id)
 }
 
-impl From<(Visibility, HWorkspace)> for Workspace {
-    fn from((visibility, workspace): (Visibility, HWorkspace)) -> Self {
+/// Gets the names of the workspaces set as persistent in Hyprland's workspace rules.
+fn get_persistent_rules() -> HashSet<String> {
+    match WorkspaceRules::get() {
+        Ok(rules) => rules
+            .into_iter()
+            .filter(|rule| rule.persistent == Some(true))
+            .filter_map(|rule| parse_rule_workspace(&rule.workspace_string))
+            .collect(),
+        Err(err) => {
+            error!("Failed to get workspace rules: {err:?}");
+            HashSet::new()
+        }
+    }
+}
+
+/// Gets the workspace name or ID a rule applies to.
+///
+/// Selectors matching multiple workspaces, such as `r[1-5]`, are not supported.
+fn parse_rule_workspace(workspace: &str) -> Option<String> {
+    let workspace = workspace.trim();
+    let workspace = workspace.strip_prefix("name:").unwrap_or(workspace);
+
+    if workspace.is_empty() || workspace.contains('[') {
+        None
+    } else {
+        Some(workspace.to_string())
+    }
+}
+
+/// Gets the path to Hyprland's event socket.
+fn event_socket_path() -> Option<PathBuf> {
+    let instance = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+
+    std::env::var("XDG_RUNTIME_DIR")
+        .map(|dir| PathBuf::from(dir).join("hypr"))
+        .into_iter()
+        .chain([PathBuf::from("/tmp/hypr")])
+        .map(|dir| dir.join(&instance).join(".socket2.sock"))
+        .find(|path| path.exists())
+}
+
+impl From<(Visibility, HWorkspace, &HashSet<String>)> for Workspace {
+    fn from(
+        (visibility, workspace, persistent): (Visibility, HWorkspace, &HashSet<String>),
+    ) -> Self {
+        let persistent =
+            persistent.contains(&workspace.name) || persistent.contains(&workspace.id.to_string());
+
         Self {
             id: workspace.id as i64,
             name: workspace.name,
             monitor: workspace.monitor,
             visibility,
+            windows: workspace.windows as usize,
+            persistent,
         }
     }
 }
@@ -302,3 +853,138 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rule_workspaces() {
+        assert_eq!(parse_rule_workspace("1"), Some(String::from("1")));
+        assert_eq!(parse_rule_workspace("name:web"), Some(String::from("web")));
+        assert_eq!(
+            parse_rule_workspace("special:scratch"),
+            Some(String::from("special:scratch"))
+        );
+        assert_eq!(parse_rule_workspace("r[1-5]"), None);
+        assert_eq!(parse_rule_workspace(""), None);
+    }
+
+    #[test]
+    fn parse_active_specials() {
+        assert_eq!(
+            parse_active_special("special:scratch,DP-1"),
+            Some(StateUpdate::SpecialWorkspace {
+                monitor: String::from("DP-1"),
+                name: Some(String::from("scratch"))
+            })
+        );
+        assert_eq!(
+            parse_active_special(",DP-1"),
+            Some(StateUpdate::SpecialWorkspace {
+                monitor: String::from("DP-1"),
+                name: None
+            })
+        );
+        assert_eq!(parse_active_special("invalid"), None);
+    }
+
+    fn replay(monitors: &mut WorkspaceMonitors, events: &str) -> Vec<(i64, String)> {
+        events
+            .lines()
+            .map(str::trim)
+            .filter_map(MonitorEvent::parse)
+            .filter_map(|event| monitors.apply(event))
+            .collect()
+    }
+
+    fn monitors() -> WorkspaceMonitors {
+        WorkspaceMonitors::new(
+            [
+                (1, String::from("1"), String::from("DP-1")),
+                (2, String::from("2"), String::from("DP-2")),
+            ],
+            Some(String::from("DP-1")),
+        )
+    }
+
+    #[test]
+    fn parse_monitor_events() {
+        assert_eq!(
+            MonitorEvent::parse("moveworkspacev2>>3,web, mail,DP-2"),
+            Some(MonitorEvent::Move {
+                id: 3,
+                name: String::from("web, mail"),
+                monitor: String::from("DP-2")
+            })
+        );
+        assert_eq!(
+            MonitorEvent::parse("focusedmon>>DP-1,2"),
+            Some(MonitorEvent::FocusMonitor {
+                monitor: String::from("DP-1"),
+                workspace: String::from("2")
+            })
+        );
+        assert_eq!(MonitorEvent::parse("workspacev2>>invalid,1"), None);
+        assert_eq!(MonitorEvent::parse("openwindow>>1,2,3,4"), None);
+    }
+
+    #[test]
+    fn tracks_moved_workspaces() {
+        let mut monitors = monitors();
+
+        // focusing a workspace on another monitor does not move it
+        let assigned = replay(
+            &mut monitors,
+            "focusedmon>>DP-2,2
+            workspace>>2
+            workspacev2>>2,2",
+        );
+        assert!(assigned.is_empty());
+
+        // `moveworkspacetomonitor 2 DP-1`
+        let assigned = replay(
+            &mut monitors,
+            "moveworkspace>>2,DP-1
+            moveworkspacev2>>2,2,DP-1
+            focusedmon>>DP-1,2
+            focusedmonv2>>DP-1,2",
+        );
+        assert_eq!(assigned, vec![(2, String::from("DP-1"))]);
+    }
+
+    #[test]
+    fn tracks_created_workspaces() {
+        let mut monitors = monitors();
+
+        // `workspace 3`, with a rule placing it on `DP-2`
+        let assigned = replay(
+            &mut monitors,
+            "focusedmon>>DP-2,3
+            focusedmonv2>>DP-2,3
+            createworkspace>>3
+            createworkspacev2>>3,3
+            workspace>>3
+            workspacev2>>3,3",
+        );
+        assert_eq!(assigned, vec![(3, String::from("DP-2"))]);
+
+        // `workspace 4` on the focused monitor
+        let assigned = replay(
+            &mut monitors,
+            "createworkspacev2>>4,4
+            workspacev2>>4,4",
+        );
+        assert_eq!(assigned, vec![(4, String::from("DP-2"))]);
+
+        // IDs are reused once a workspace is destroyed
+        let assigned = replay(
+            &mut monitors,
+            "destroyworkspacev2>>3,3
+            focusedmon>>DP-1,1
+            createworkspacev2>>3,3
+            workspacev2>>3,3",
+        );
+        assert_eq!(assigned, vec![(3, String::from("DP-1"))]);
+    }
+}
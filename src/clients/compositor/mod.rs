@@ -6,8 +6,14 @@ use std::sync::Arc;
 use tokio::sync::broadcast;
 use tracing::debug;
 
+pub(crate) use self::focus::FocusState;
+pub use self::focus::OutputFocus;
+pub use self::state::{CompositorState, StateValue, PREFIX};
+
+mod focus;
 #[cfg(feature = "workspaces+hyprland")]
 pub mod hyprland;
+mod state;
 #[cfg(feature = "workspaces+sway")]
 pub mod sway;
 
@@ -81,6 +87,18 @@ pub struct Workspace {
     pub monitor: String,
     /// How visible the workspace is
     pub visibility: Visibility,
+    /// The number of windows on the workspace
+    pub windows: usize,
+    /// Whether the compositor keeps the workspace open while empty.
+    /// This is only set by compositors which support persistent workspaces.
+    pub persistent: bool,
+}
+
+impl Workspace {
+    /// Whether the workspace has no windows.
+    pub fn is_empty(&self) -> bool {
+        self.windows == 0
+    }
 }
 
 /// Indicates workspace visibility. Visible workspaces have a boolean flag to indicate if they are also focused.
@@ -132,6 +150,12 @@ pub enum WorkspaceUpdate {
         name: String,
     },
 
+    /// The workspace's windows or rules changed,
+    /// affecting its window count or whether it is persistent.
+    ///
+    /// Visibility is not guaranteed to be up to date and should be ignored.
+    Update(Workspace),
+
     /// An update was triggered by the compositor but this was not mapped by Ironbar.
     ///
     /// This is purely used for ergonomics within the compositor clients
@@ -139,12 +163,68 @@ pub enum WorkspaceUpdate {
     Unknown,
 }
 
+/// A change to compositor state which is not tied to a single workspace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateUpdate {
+    /// The active keybind mode changed.
+    /// This is known as the submap on Hyprland, and the binding mode on Sway.
+    ///
+    /// The name is empty for the default mode.
+    Submap(String),
+    /// A special workspace was shown or hidden on a monitor.
+    /// This is only sent by Hyprland.
+    SpecialWorkspace {
+        monitor: String,
+        name: Option<String>,
+    },
+    /// A window was marked as urgent, or its urgency was cleared.
+    ///
+    /// The app ID is the one reported by the foreign toplevel protocol.
+    /// Hyprland only reports windows becoming urgent,
+    /// so urgency should also be cleared once the window is focused.
+    Urgent {
+        app_id: String,
+        title: String,
+        urgent: bool,
+    },
+}
+
+/// The active keyboard layout changed.
+///
+/// This is the full layout name, as reported by the compositor,
+/// for example `English (US)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyboardLayoutUpdate(pub String);
+
 pub trait WorkspaceClient: Debug + Send + Sync {
     /// Requests the workspace with this name is focused.
     fn focus(&self, name: String) -> Result<()>;
 
+    /// Requests the app's window with this title is moved to the workspace with this name.
+    /// If none of the app's windows have the title,
+    /// the most recently focused of them is moved instead.
+    ///
+    /// The app ID and title are the ones reported by the foreign toplevel protocol,
+    /// where the app ID is the window class for XWayland windows.
+    fn move_window(&self, app_id: &str, title: &str, workspace: &str) -> Result<()> {
+        let _ = (app_id, title, workspace);
+        Err(Report::msg(
+            "Moving windows is not supported by this compositor",
+        ))
+    }
+
     /// Creates a new to workspace event receiver.
     fn subscribe_workspace_change(&self) -> broadcast::Receiver<WorkspaceUpdate>;
+
+    /// Creates a new receiver for changes to the keybind mode and special workspaces.
+    fn subscribe_state_change(&self) -> broadcast::Receiver<StateUpdate>;
+
+    /// Requests the next keyboard layout is activated on all keyboards.
+    fn next_keyboard_layout(&self) -> Result<()>;
+
+    /// Creates a new receiver for keyboard layout changes.
+    /// The current layout is sent to all subscribers when a new subscription is created.
+    fn subscribe_keyboard_layout(&self) -> broadcast::Receiver<KeyboardLayoutUpdate>;
 }
 
 register_fallible_client!(dyn WorkspaceClient, workspaces);
@@ -0,0 +1,239 @@
+use super::focus::FocusState;
+use super::{StateUpdate, WorkspaceClient};
+use crate::spawn;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+use tracing::{debug, error};
+
+/// The prefix used to reference compositor state in dynamic strings,
+/// for example `#compositor.workspace`.
+pub const PREFIX: &str = "compositor.";
+
+/// Set once a dynamic string references compositor state,
+/// so that the compositor is only queried when needed.
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// A single value from the compositor state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateValue {
+    /// The name of the focused workspace.
+    Workspace,
+    /// The number of windows on the focused workspace.
+    WindowCount,
+    /// The active keybind mode, or empty for the default mode.
+    Submap,
+    /// The special workspace open on the focused monitor, or empty if none.
+    SpecialWorkspace,
+}
+
+impl StateValue {
+    /// Parses the value from its name,
+    /// without the `compositor.` prefix.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "workspace" => Some(Self::Workspace),
+            "window_count" => Some(Self::WindowCount),
+            "submap" => Some(Self::Submap),
+            "special_workspace" => Some(Self::SpecialWorkspace),
+            _ => None,
+        }
+    }
+}
+
+/// Compositor state which is not tied to a single workspace,
+/// shared between all dynamic strings.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CompositorState {
+    workspace: String,
+    window_count: usize,
+    submap: String,
+    special_workspace: String,
+}
+
+impl CompositorState {
+    /// Gets the value formatted for display.
+    pub fn get(&self, value: StateValue) -> String {
+        match value {
+            StateValue::Workspace => self.workspace.clone(),
+            StateValue::WindowCount => self.window_count.to_string(),
+            StateValue::Submap => self.submap.clone(),
+            StateValue::SpecialWorkspace => self.special_workspace.clone(),
+        }
+    }
+
+    fn sender() -> &'static watch::Sender<Self> {
+        static TX: OnceLock<watch::Sender<CompositorState>> = OnceLock::new();
+        TX.get_or_init(|| watch::channel(Self::default()).0)
+    }
+
+    /// Creates a receiver which is notified whenever the state changes.
+    ///
+    /// The state is not tracked until [`Self::is_requested`] is checked
+    /// and the watcher is started.
+    pub fn subscribe() -> watch::Receiver<Self> {
+        REQUESTED.store(true, Ordering::Relaxed);
+        Self::sender().subscribe()
+    }
+
+    /// Whether anything has subscribed to the state.
+    pub fn is_requested() -> bool {
+        REQUESTED.load(Ordering::Relaxed)
+    }
+
+    /// Starts tracking the state from the compositor client.
+    /// This should only be called once.
+    pub(crate) fn watch(client: &Arc<dyn WorkspaceClient>) {
+        let mut workspace_rx = client.subscribe_workspace_change();
+        let mut state_rx = client.subscribe_state_change();
+
+        spawn(async move {
+            let mut tracker = StateTracker::default();
+
+            loop {
+                tokio::select! {
+                    update = workspace_rx.recv() => match update {
+                        Ok(update) => tracker.focus.apply(update),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => {
+                            error!("Compositor workspace updates stopped");
+                            break;
+                        }
+                    },
+                    update = state_rx.recv() => match update {
+                        Ok(update) => tracker.apply(update),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => {
+                            error!("Compositor state updates stopped");
+                            break;
+                        }
+                    },
+                }
+
+                let state = tracker.state();
+                Self::sender().send_if_modified(|current| {
+                    if *current == state {
+                        false
+                    } else {
+                        debug!("Compositor state changed: {state:?}");
+                        *current = state;
+                        true
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// Combines workspace and state updates into the current state.
+#[derive(Debug, Default)]
+struct StateTracker {
+    focus: FocusState,
+    submap: String,
+    /// Open special workspace names, keyed by monitor.
+    special_workspaces: HashMap<String, String>,
+}
+
+impl StateTracker {
+    fn apply(&mut self, update: StateUpdate) {
+        match update {
+            StateUpdate::Submap(submap) => self.submap = submap,
+            StateUpdate::SpecialWorkspace {
+                monitor,
+                name: Some(name),
+            } => {
+                self.special_workspaces.insert(monitor, name);
+            }
+            StateUpdate::SpecialWorkspace {
+                monitor,
+                name: None,
+            } => {
+                self.special_workspaces.remove(&monitor);
+            }
+            StateUpdate::Urgent { .. } => {}
+        }
+    }
+
+    fn state(&self) -> CompositorState {
+        let focused = self.focus.focused_workspace();
+
+        let special_workspace = focused
+            .and_then(|workspace| self.special_workspaces.get(&workspace.monitor))
+            .cloned()
+            .unwrap_or_default();
+
+        CompositorState {
+            workspace: focused
+                .map(|workspace| workspace.name.clone())
+                .unwrap_or_default(),
+            window_count: focused.map_or(0, |workspace| workspace.windows),
+            submap: self.submap.clone(),
+            special_workspace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::compositor::{Visibility, Workspace, WorkspaceUpdate};
+
+    fn workspace(id: i64, monitor: &str, visibility: Visibility, windows: usize) -> Workspace {
+        Workspace {
+            id,
+            name: id.to_string(),
+            monitor: monitor.to_string(),
+            visibility,
+            windows,
+            persistent: false,
+        }
+    }
+
+    #[test]
+    fn tracks_focused_state() {
+        let mut tracker = StateTracker::default();
+
+        tracker.focus.apply(WorkspaceUpdate::Init(vec![
+            workspace(1, "DP-1", Visibility::focused(), 2),
+            workspace(2, "DP-2", Visibility::visible(), 0),
+        ]));
+        tracker.apply(StateUpdate::SpecialWorkspace {
+            monitor: String::from("DP-2"),
+            name: Some(String::from("scratch")),
+        });
+
+        let state = tracker.state();
+        assert_eq!(state.get(StateValue::Workspace), "1");
+        assert_eq!(state.get(StateValue::WindowCount), "2");
+        assert_eq!(state.get(StateValue::SpecialWorkspace), "");
+
+        tracker.focus.apply(WorkspaceUpdate::Focus {
+            old: None,
+            new: workspace(2, "DP-2", Visibility::focused(), 0),
+        });
+        tracker.apply(StateUpdate::Submap(String::from("resize")));
+
+        let state = tracker.state();
+        assert_eq!(state.get(StateValue::Workspace), "2");
+        assert_eq!(state.get(StateValue::WindowCount), "0");
+        assert_eq!(state.get(StateValue::SpecialWorkspace), "scratch");
+        assert_eq!(state.get(StateValue::Submap), "resize");
+
+        tracker.apply(StateUpdate::SpecialWorkspace {
+            monitor: String::from("DP-2"),
+            name: None,
+        });
+        assert_eq!(tracker.state().get(StateValue::SpecialWorkspace), "");
+    }
+
+    #[test]
+    fn parse_values() {
+        assert_eq!(
+            StateValue::parse("window_count"),
+            Some(StateValue::WindowCount)
+        );
+        assert_eq!(StateValue::parse("unknown"), None);
+    }
+}
@@ -1,60 +1,295 @@
-use super::{Visibility, Workspace, WorkspaceClient, WorkspaceUpdate};
+use super::{
+    KeyboardLayoutUpdate, StateUpdate, Visibility, Workspace, WorkspaceClient, WorkspaceUpdate,
+};
 use crate::{await_sync, send, spawn};
 use color_eyre::{Report, Result};
-use futures_lite::StreamExt;
+use futures_lite::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
 use std::sync::Arc;
-use swayipc_async::{Connection, Event, EventType, Node, WorkspaceChange, WorkspaceEvent};
+use swayipc_async::{
+    Connection, Event, EventType, Fallible, Input, InputChange, Node, NodeType, WindowChange,
+    WorkspaceChange, WorkspaceEvent,
+};
 use tokio::sync::broadcast::{channel, Receiver, Sender};
 use tokio::sync::Mutex;
-use tracing::{info, trace};
+use tracing::{error, info, trace};
+
+/// The requests the client makes over Sway IPC.
+///
+/// This is implemented for a real connection,
+/// and allows the client to be tested against canned replies.
+pub trait SwayIpc: Debug + Send + 'static {
+    fn get_workspaces(
+        &mut self,
+    ) -> impl Future<Output = Fallible<Vec<swayipc_async::Workspace>>> + Send;
+    fn get_tree(&mut self) -> impl Future<Output = Fallible<Node>> + Send;
+    fn get_inputs(&mut self) -> impl Future<Output = Fallible<Vec<Input>>> + Send;
+    fn run_command(
+        &mut self,
+        payload: String,
+    ) -> impl Future<Output = Fallible<Vec<Fallible<()>>>> + Send;
+}
+
+impl SwayIpc for Connection {
+    fn get_workspaces(
+        &mut self,
+    ) -> impl Future<Output = Fallible<Vec<swayipc_async::Workspace>>> + Send {
+        Self::get_workspaces(self)
+    }
+
+    fn get_tree(&mut self) -> impl Future<Output = Fallible<Node>> + Send {
+        Self::get_tree(self)
+    }
+
+    fn get_inputs(&mut self) -> impl Future<Output = Fallible<Vec<Input>>> + Send {
+        Self::get_inputs(self)
+    }
+
+    fn run_command(
+        &mut self,
+        payload: String,
+    ) -> impl Future<Output = Fallible<Vec<Fallible<()>>>> + Send {
+        Self::run_command(self, payload)
+    }
+}
 
 #[derive(Debug)]
-pub struct Client {
-    client: Arc<Mutex<Connection>>,
+pub struct Client<C = Connection> {
+    client: Arc<Mutex<C>>,
     workspace_tx: Sender<WorkspaceUpdate>,
     _workspace_rx: Receiver<WorkspaceUpdate>,
+
+    state_tx: Sender<StateUpdate>,
+    _state_rx: Receiver<StateUpdate>,
+
+    keyboard_tx: Sender<KeyboardLayoutUpdate>,
+    _keyboard_rx: Receiver<KeyboardLayoutUpdate>,
 }
 
 impl Client {
     pub(crate) async fn new() -> Result<Self> {
-        // Avoid using `arc_mut!` here because we need tokio Mutex.
-        let client = Arc::new(Mutex::new(Connection::new().await?));
+        let client = Connection::new().await?;
         info!("Sway IPC subscription client connected");
 
+        // create 2nd client as subscription takes ownership
+        let event_types = [
+            EventType::Workspace,
+            EventType::Window,
+            EventType::Mode,
+            EventType::Input,
+        ];
+        let events = Connection::new().await?.subscribe(event_types).await?;
+
+        Ok(Self::with_connection(client, events))
+    }
+}
+
+impl<C: SwayIpc> Client<C> {
+    /// Creates the client from a connection to make requests on,
+    /// and the stream of events it subscribed to.
+    pub(crate) fn with_connection<S>(client: C, mut events: S) -> Self
+    where
+        S: Stream<Item = Fallible<Event>> + Send + Unpin + 'static,
+    {
+        // Avoid using `arc_mut!` here because we need tokio Mutex.
+        let client = Arc::new(Mutex::new(client));
+
         let (workspace_tx, workspace_rx) = channel(16);
+        let (state_tx, state_rx) = channel(16);
+        let (keyboard_tx, keyboard_rx) = channel(16);
 
         {
-            // create 2nd client as subscription takes ownership
-            let client = Connection::new().await?;
             let workspace_tx = workspace_tx.clone();
+            let state_tx = state_tx.clone();
+            let keyboard_tx = keyboard_tx.clone();
+            let client = client.clone();
 
             spawn(async move {
-                let event_types = [EventType::Workspace];
-                let mut events = client.subscribe(event_types).await?;
-
                 while let Some(event) = events.next().await {
                     trace!("event: {:?}", event);
-                    if let Event::Workspace(event) = event? {
-                        let event = WorkspaceUpdate::from(*event);
-                        if !matches!(event, WorkspaceUpdate::Unknown) {
-                            workspace_tx.send(event)?;
+                    match event? {
+                        Event::Workspace(event) => {
+                            let event = WorkspaceUpdate::from(*event);
+                            if !matches!(event, WorkspaceUpdate::Unknown) {
+                                workspace_tx.send(event)?;
+                            }
+                        }
+                        // windows opening, closing or moving
+                        // change the number of windows on workspaces
+                        Event::Window(event)
+                            if matches!(
+                                event.change,
+                                WindowChange::New | WindowChange::Close | WindowChange::Move
+                            ) =>
+                        {
+                            let workspaces = get_workspaces(&mut *client.lock().await).await?;
+
+                            for workspace in workspaces {
+                                workspace_tx.send(WorkspaceUpdate::Update(workspace))?;
+                            }
+                        }
+                        Event::Window(event) if event.change == WindowChange::Urgent => {
+                            let container = event.container;
+
+                            if let Some(app_id) = window_app_id(&container) {
+                                // there may be no subscribers yet
+                                state_tx
+                                    .send(StateUpdate::Urgent {
+                                        app_id: app_id.to_string(),
+                                        title: container.name.clone().unwrap_or_default(),
+                                        urgent: container.urgent,
+                                    })
+                                    .ok();
+                            }
+                        }
+                        Event::Mode(event) => {
+                            // sway calls its default mode `default`
+                            let mode = if event.change == "default" {
+                                String::new()
+                            } else {
+                                event.change
+                            };
+
+                            // there may be no subscribers yet
+                            state_tx.send(StateUpdate::Submap(mode)).ok();
+                        }
+                        Event::Input(event)
+                            if matches!(
+                                event.change,
+                                InputChange::XkbLayout | InputChange::XkbKeymap
+                            ) =>
+                        {
+                            if let Some(layout) = event.input.xkb_active_layout_name {
+                                // there may be no subscribers yet
+                                keyboard_tx.send(KeyboardLayoutUpdate(layout)).ok();
+                            }
                         }
-                    };
+                        _ => {}
+                    }
                 }
 
                 Ok::<(), Report>(())
             });
         }
 
-        Ok(Self {
+        Self {
             client,
             workspace_tx,
             _workspace_rx: workspace_rx,
+            state_tx,
+            _state_rx: state_rx,
+            keyboard_tx,
+            _keyboard_rx: keyboard_rx,
+        }
+    }
+}
+
+/// Gets all workspaces, including their window counts.
+///
+/// The workspace list does not include windows,
+/// so these are counted from the tree.
+async fn get_workspaces(client: &mut impl SwayIpc) -> Result<Vec<Workspace>> {
+    let workspaces = client.get_workspaces().await?;
+    let tree = client.get_tree().await?;
+
+    let mut windows = HashMap::new();
+    count_workspace_windows(&tree, &mut windows);
+
+    Ok(workspaces
+        .into_iter()
+        .map(|workspace| {
+            let count = windows.get(&workspace.id).copied().unwrap_or_default();
+
+            Workspace {
+                windows: count,
+                ..Workspace::from(workspace)
+            }
         })
+        .collect())
+}
+
+/// Gets the active layout of the first keyboard which has one.
+async fn get_keyboard_layout(client: &mut impl SwayIpc) -> Result<Option<String>> {
+    let inputs = client.get_inputs().await?;
+
+    Ok(inputs
+        .into_iter()
+        .filter(|input| input.input_type == "keyboard")
+        .find_map(|input| input.xkb_active_layout_name))
+}
+
+/// Finds each workspace node in the tree,
+/// and records its number of windows.
+fn count_workspace_windows(node: &Node, windows: &mut HashMap<i64, usize>) {
+    if node.node_type == NodeType::Workspace {
+        windows.insert(node.id, count_windows(node));
+    } else {
+        for child in &node.nodes {
+            count_workspace_windows(child, windows);
+        }
     }
 }
 
-impl WorkspaceClient for Client {
+/// Counts the windows inside the node,
+/// including those inside nested containers.
+fn count_windows(node: &Node) -> usize {
+    node.nodes
+        .iter()
+        .chain(&node.floating_nodes)
+        .map(|child| {
+            if child.nodes.is_empty() && child.floating_nodes.is_empty() {
+                1
+            } else {
+                count_windows(child)
+            }
+        })
+        .sum()
+}
+
+/// Gets the app ID of the node if it is a window,
+/// using the class for XWayland windows.
+fn window_app_id(node: &Node) -> Option<&str> {
+    node.app_id.as_deref().or_else(|| {
+        node.window_properties
+            .as_ref()
+            .and_then(|properties| properties.class.as_deref())
+    })
+}
+
+/// Finds the most recently focused window belonging to the app,
+/// which also has the title if one is given.
+///
+/// Children are searched in the order they were focused,
+/// so windows in the most recently focused containers are found first.
+fn find_window(node: &Node, app_id: &str, title: Option<&str>) -> Option<i64> {
+    if let Some(window_app_id) = window_app_id(node) {
+        let matches = window_app_id == app_id
+            && title.is_none_or(|title| node.name.as_deref() == Some(title));
+
+        return matches.then_some(node.id);
+    }
+
+    let mut children = node
+        .nodes
+        .iter()
+        .chain(&node.floating_nodes)
+        .collect::<Vec<_>>();
+
+    children.sort_by_key(|child| {
+        node.focus
+            .iter()
+            .position(|&id| id == child.id)
+            .unwrap_or(usize::MAX)
+    });
+
+    children
+        .into_iter()
+        .find_map(|child| find_window(child, app_id, title))
+}
+
+impl<C: SwayIpc> WorkspaceClient for Client<C> {
     fn focus(&self, id: String) -> Result<()> {
         await_sync(async move {
             let mut client = self.client.lock().await;
@@ -63,6 +298,29 @@ impl WorkspaceClient for Client {
         Ok(())
     }
 
+    fn move_window(&self, app_id: &str, title: &str, workspace: &str) -> Result<()> {
+        await_sync(async move {
+            let mut client = self.client.lock().await;
+
+            let tree = client.get_tree().await?;
+            let id = find_window(&tree, app_id, Some(title))
+                .or_else(|| find_window(&tree, app_id, None))
+                .ok_or_else(|| Report::msg(format!("No window found for app '{app_id}'")))?;
+
+            let workspace = workspace.replace('"', "\\\"");
+            for outcome in client
+                .run_command(format!(
+                    "[con_id={id}] move container to workspace \"{workspace}\""
+                ))
+                .await?
+            {
+                outcome?;
+            }
+
+            Ok::<(), Report>(())
+        })
+    }
+
     fn subscribe_workspace_change(&self) -> Receiver<WorkspaceUpdate> {
         let rx = self.workspace_tx.subscribe();
 
@@ -72,12 +330,50 @@ impl WorkspaceClient for Client {
 
             await_sync(async {
                 let mut client = client.lock().await;
-                let workspaces = client.get_workspaces().await.expect("to get workspaces");
+                let workspaces = get_workspaces(&mut client)
+                    .await
+                    .expect("to get workspaces");
 
-                let event =
-                    WorkspaceUpdate::Init(workspaces.into_iter().map(Workspace::from).collect());
+                send!(tx, WorkspaceUpdate::Init(workspaces));
+            });
+        }
+
+        rx
+    }
+
+    fn subscribe_state_change(&self) -> Receiver<StateUpdate> {
+        self.state_tx.subscribe()
+    }
+
+    fn next_keyboard_layout(&self) -> Result<()> {
+        await_sync(async move {
+            let mut client = self.client.lock().await;
+
+            for outcome in client
+                .run_command(String::from("input * xkb_switch_layout next"))
+                .await?
+            {
+                outcome?;
+            }
 
-                send!(tx, event);
+            Ok::<(), Report>(())
+        })
+    }
+
+    fn subscribe_keyboard_layout(&self) -> Receiver<KeyboardLayoutUpdate> {
+        let rx = self.keyboard_tx.subscribe();
+
+        {
+            let tx = self.keyboard_tx.clone();
+            let client = self.client.clone();
+
+            await_sync(async {
+                let mut client = client.lock().await;
+                match get_keyboard_layout(&mut client).await {
+                    Ok(Some(layout)) => send!(tx, KeyboardLayoutUpdate(layout)),
+                    Ok(None) => {}
+                    Err(err) => error!("Failed to get keyboard layout: {err:?}"),
+                }
             });
         }
 
@@ -94,11 +390,15 @@ impl From<Node> for Workspace {
             name: node.name.unwrap_or_default(),
             monitor: node.output.unwrap_or_default(),
             visibility,
+            windows: count_windows(&node),
+            persistent: false,
         }
     }
 }
 
 impl From<swayipc_async::Workspace> for Workspace {
+    /// Creates the workspace without its window count,
+    /// which is not included in the workspace list.
     fn from(workspace: swayipc_async::Workspace) -> Self {
         let visibility = Visibility::from(&workspace);
 
@@ -107,6 +407,8 @@ impl From<swayipc_async::Workspace> for Workspace {
             name: workspace.name,
             monitor: workspace.output,
             visibility,
+            windows: 0,
+            persistent: false,
         }
     }
 }
@@ -0,0 +1,74 @@
+use crate::spawn;
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use tokio::sync::watch;
+use tracing::{debug, error};
+use zbus::zvariant::OwnedObjectPath;
+
+#[zbus::dbus_proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    fn get_session(&self, session_id: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::dbus_proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1"
+)]
+trait Session {
+    #[dbus_proxy(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+}
+
+/// Tracks whether the current logind session is locked,
+/// using the session's `LockedHint`.
+///
+/// The hint is set by the screen locker, or whatever starts it,
+/// so is only accurate if that reports to logind.
+#[derive(Debug)]
+pub struct Client {
+    tx: watch::Sender<bool>,
+    _rx: watch::Receiver<bool>,
+}
+
+impl Client {
+    pub async fn new() -> Result<Self> {
+        let dbus = Box::pin(zbus::Connection::system()).await?;
+
+        // `auto` is resolved to the real path,
+        // as property changes are only signalled on that
+        let path = ManagerProxy::new(&dbus).await?.get_session("auto").await?;
+        debug!("Watching lock state of session {}", path.as_str());
+
+        let session = SessionProxy::builder(&dbus).path(path)?.build().await?;
+
+        let (tx, rx) = watch::channel(session.locked_hint().await?);
+
+        {
+            let tx = tx.clone();
+            let mut changes = session.receive_locked_hint_changed().await;
+
+            spawn(async move {
+                while let Some(change) = changes.next().await {
+                    match change.get().await {
+                        Ok(locked) => {
+                            debug!("Session locked: {locked}");
+                            tx.send_replace(locked);
+                        }
+                        Err(err) => error!("Failed to read session lock state: {err:?}"),
+                    }
+                }
+            });
+        }
+
+        Ok(Self { tx, _rx: rx })
+    }
+
+    /// Subscribes to the lock state, which is `true` while locked.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.tx.subscribe()
+    }
+}
@@ -4,16 +4,22 @@ use std::path::Path;
 use std::rc::Rc;
 use std::sync::Arc;
 
+#[cfg(any(feature = "bluetooth", feature = "volume"))]
+pub mod bluetooth;
 #[cfg(feature = "clipboard")]
 pub mod clipboard;
 #[cfg(feature = "workspaces")]
 pub mod compositor;
+#[cfg(feature = "lock")]
+pub mod logind;
 #[cfg(feature = "cairo")]
 pub mod lua;
 #[cfg(feature = "music")]
 pub mod music;
+pub mod resume;
 #[cfg(feature = "notifications")]
 pub mod swaync;
+pub mod timer;
 #[cfg(feature = "tray")]
 pub mod tray;
 #[cfg(feature = "upower")]
@@ -29,8 +35,16 @@ pub struct Clients {
     wayland: Option<Arc<wayland::Client>>,
     #[cfg(feature = "workspaces")]
     workspaces: Option<Arc<dyn compositor::WorkspaceClient>>,
+    #[cfg(feature = "workspaces")]
+    output_focus: Option<Arc<compositor::OutputFocus>>,
+    #[cfg(feature = "workspaces")]
+    compositor_state_watched: bool,
+    #[cfg(any(feature = "bluetooth", feature = "volume"))]
+    bluetooth: Option<Arc<bluetooth::Client>>,
     #[cfg(feature = "clipboard")]
     clipboard: Option<Arc<clipboard::Client>>,
+    #[cfg(feature = "lock")]
+    session_lock: Option<Arc<logind::Client>>,
     #[cfg(feature = "cairo")]
     lua: Option<Rc<lua::LuaEngine>>,
     #[cfg(feature = "music")]
@@ -42,7 +56,7 @@ pub struct Clients {
     #[cfg(feature = "upower")]
     upower: Option<Arc<zbus::fdo::PropertiesProxy<'static>>>,
     #[cfg(feature = "volume")]
-    volume: Option<Arc<volume::Client>>,
+    volume: Option<Arc<dyn volume::VolumeClient>>,
 }
 
 pub type ClientResult<T> = Result<Arc<T>>;
@@ -58,6 +72,22 @@ impl Clients {
             .clone()
     }
 
+    /// Gets the Bluetooth client, creating it if required.
+    #[cfg(any(feature = "bluetooth", feature = "volume"))]
+    pub fn bluetooth(&mut self) -> ClientResult<bluetooth::Client> {
+        let client = match &self.bluetooth {
+            Some(client) => client.clone(),
+            None => {
+                let client = await_sync(async { bluetooth::Client::new().await })?;
+                let client = Arc::new(client);
+                self.bluetooth.replace(client.clone());
+                client
+            }
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "clipboard")]
     pub fn clipboard(&mut self) -> Arc<clipboard::Client> {
         let wayland = self.wayland();
@@ -81,6 +111,54 @@ impl Clients {
         Ok(client)
     }
 
+    /// Gets the focused output tracker, creating it if required.
+    ///
+    /// This subscribes to the workspace client once,
+    /// and is shared by every bar.
+    #[cfg(feature = "workspaces")]
+    pub fn output_focus(&mut self) -> ClientResult<compositor::OutputFocus> {
+        let client = match &self.output_focus {
+            Some(client) => client.clone(),
+            None => {
+                let workspaces = self.workspaces()?;
+                let client = Arc::new(compositor::OutputFocus::new(&workspaces));
+                self.output_focus.replace(client.clone());
+                client
+            }
+        };
+
+        Ok(client)
+    }
+
+    /// Starts tracking the compositor state used by dynamic strings,
+    /// if not already tracking.
+    #[cfg(feature = "workspaces")]
+    pub fn watch_compositor_state(&mut self) -> Result<()> {
+        if !self.compositor_state_watched {
+            let workspaces = self.workspaces()?;
+            compositor::CompositorState::watch(&workspaces);
+            self.compositor_state_watched = true;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the session lock tracker, creating it if required.
+    #[cfg(feature = "lock")]
+    pub fn session_lock(&mut self) -> ClientResult<logind::Client> {
+        let client = match &self.session_lock {
+            Some(client) => client.clone(),
+            None => {
+                let client = await_sync(async { logind::Client::new().await })?;
+                let client = Arc::new(client);
+                self.session_lock.replace(client.clone());
+                client
+            }
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "cairo")]
     pub fn lua(&mut self, config_dir: &Path) -> Rc<lua::LuaEngine> {
         self.lua
@@ -111,6 +189,10 @@ impl Clients {
         Ok(client)
     }
 
+    /// Gets the tray client, creating it if required.
+    ///
+    /// This registers a single `StatusNotifierHost` for the whole process,
+    /// which is shared by every tray module on every bar.
     #[cfg(feature = "tray")]
     pub fn tray(&mut self) -> ClientResult<tray::Client> {
         let client = match &self.tray {
@@ -128,6 +210,13 @@ impl Clients {
         Ok(client)
     }
 
+    /// Gets the tray client if it has already been created,
+    /// without creating it.
+    #[cfg(all(feature = "tray", feature = "ipc"))]
+    pub fn existing_tray(&self) -> Option<Arc<tray::Client>> {
+        self.tray.clone()
+    }
+
     #[cfg(feature = "upower")]
     pub fn upower(&mut self) -> Arc<zbus::fdo::PropertiesProxy<'static>> {
         self.upower
@@ -137,11 +226,50 @@ impl Clients {
             .clone()
     }
 
+    /// Gets the volume client, creating it if required.
+    ///
+    /// This shares the Bluetooth client, to show the battery level of Bluetooth sinks.
     #[cfg(feature = "volume")]
-    pub fn volume(&mut self) -> Arc<volume::Client> {
-        self.volume
-            .get_or_insert_with(volume::create_client)
-            .clone()
+    pub fn volume(&mut self) -> Arc<dyn volume::VolumeClient> {
+        match &self.volume {
+            Some(client) => client.clone(),
+            None => {
+                let bluetooth = match self.bluetooth() {
+                    Ok(client) => Some(client),
+                    Err(err) => {
+                        tracing::warn!("Bluetooth battery levels are unavailable: {err:?}");
+                        None
+                    }
+                };
+
+                let client = volume::create_client(bluetooth);
+                self.volume.replace(client.clone());
+                client
+            }
+        }
+    }
+}
+
+/// Setters used to swap clients for fakes in tests.
+#[cfg(test)]
+impl Clients {
+    #[cfg(feature = "workspaces")]
+    pub fn set_workspaces(&mut self, client: Arc<dyn compositor::WorkspaceClient>) {
+        self.workspaces.replace(client);
+    }
+
+    #[cfg(feature = "music")]
+    pub fn set_music(
+        &mut self,
+        client_type: music::ClientType,
+        client: Arc<dyn music::MusicClient>,
+    ) {
+        self.music.insert(client_type, client);
+    }
+
+    #[cfg(feature = "volume")]
+    pub fn set_volume(&mut self, client: Arc<dyn volume::VolumeClient>) {
+        self.volume.replace(client);
     }
 }
 
@@ -160,7 +288,7 @@ pub trait ProvidesFallibleClient<T: ?Sized> {
     fn try_provide(&self) -> ClientResult<T>;
 }
 
-/// Generates a `ProvidesClient` impl block on `WidgetContext`
+/// Generates `ProvidesClient` impl blocks on `WidgetContext` and `ControllerContext`
 /// for the provided `$ty` (first argument) client type.
 ///
 /// The implementation calls `$method` (second argument)
@@ -180,10 +308,20 @@ macro_rules! register_client {
                 self.ironbar.clients.borrow_mut().$method()
             }
         }
+
+        impl<TSend> $crate::clients::ProvidesClient<$ty>
+            for $crate::modules::ControllerContext<TSend>
+        where
+            TSend: Clone,
+        {
+            fn provide(&self) -> std::sync::Arc<$ty> {
+                self.clients.borrow_mut().$method()
+            }
+        }
     };
 }
 
-/// Generates a `ProvidesClient` impl block on `WidgetContext`
+/// Generates `ProvidesFallibleClient` impl blocks on `WidgetContext` and `ControllerContext`
 /// for the provided `$ty` (first argument) client type.
 ///
 /// The implementation calls `$method` (second argument)
@@ -203,5 +341,15 @@ macro_rules! register_fallible_client {
                 self.ironbar.clients.borrow_mut().$method()
             }
         }
+
+        impl<TSend> $crate::clients::ProvidesFallibleClient<$ty>
+            for $crate::modules::ControllerContext<TSend>
+        where
+            TSend: Clone,
+        {
+            fn try_provide(&self) -> color_eyre::Result<std::sync::Arc<$ty>> {
+                self.clients.borrow_mut().$method()
+            }
+        }
     };
 }
@@ -32,6 +32,16 @@ pub struct Track {
     pub genre: Option<String>,
     pub track: Option<u64>,
     pub cover_path: Option<String>,
+    /// The track's rating, if the player has one for it.
+    pub rating: Option<Rating>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating {
+    /// The rating, from `0.0` to `1.0`.
+    pub value: f64,
+    /// Whether the rating can be changed through [`MusicClient::set_rating`].
+    pub writable: bool,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -54,6 +64,9 @@ pub struct Status {
 pub struct ProgressTick {
     pub duration: Option<Duration>,
     pub elapsed: Option<Duration>,
+    /// Whether the current track can be seeked.
+    /// This is false for streams which report no length.
+    pub seekable: bool,
 }
 
 pub trait MusicClient: Debug + Send + Sync {
@@ -65,18 +78,31 @@ pub trait MusicClient: Debug + Send + Sync {
     fn set_volume_percent(&self, vol: u8) -> Result<()>;
     fn seek(&self, duration: Duration) -> Result<()>;
 
+    /// Sets the current track's rating, from `0.0` to `1.0`.
+    fn set_rating(&self, rating: f64) -> Result<()>;
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate>;
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum ClientType {
-    Mpd { host: String, music_dir: PathBuf },
+    Mpd {
+        host: String,
+        music_dir: PathBuf,
+        /// The sticker to read and write ratings to,
+        /// or `None` to not read ratings.
+        rating_sticker: Option<String>,
+    },
     Mpris,
 }
 
 pub fn create_client(client_type: ClientType) -> Arc<dyn MusicClient> {
     match client_type {
-        ClientType::Mpd { host, music_dir } => Arc::new(mpd::Client::new(host, music_dir)),
+        ClientType::Mpd {
+            host,
+            music_dir,
+            rating_sticker,
+        } => Arc::new(mpd::Client::new(host, music_dir, rating_sticker)),
         ClientType::Mpris => Arc::new(mpris::Client::new()),
     }
 }
@@ -1,10 +1,10 @@
 use super::{
-    MusicClient, PlayerState, PlayerUpdate, ProgressTick, Status, Track, TICK_INTERVAL_MS,
+    MusicClient, PlayerState, PlayerUpdate, ProgressTick, Rating, Status, Track, TICK_INTERVAL_MS,
 };
 use crate::{await_sync, send, spawn, Ironbar};
 use color_eyre::Report;
 use color_eyre::Result;
-use mpd_client::client::{ConnectionEvent, Subsystem};
+use mpd_client::client::{CommandError, ConnectionEvent, Subsystem};
 use mpd_client::commands::{self, SeekMode};
 use mpd_client::responses::{PlayState, Song};
 use mpd_client::tag::Tag;
@@ -22,16 +22,24 @@ macro_rules! command {
     };
 }
 
+/// The rating stickers' highest value,
+/// matching the half-star 0-10 scale used by other MPD clients.
+const MAX_RATING: f64 = 10.0;
+
+/// The error MPD responds with when a song has no such sticker.
+const ERROR_NO_EXIST: u64 = 50;
+
 #[derive(Debug)]
 pub struct Client {
     client: Arc<PersistentClient>,
     music_dir: PathBuf,
+    rating_sticker: Option<String>,
     tx: broadcast::Sender<PlayerUpdate>,
     _rx: broadcast::Receiver<PlayerUpdate>,
 }
 
 impl Client {
-    pub fn new(host: String, music_dir: PathBuf) -> Self {
+    pub fn new(host: String, music_dir: PathBuf, rating_sticker: Option<String>) -> Self {
         let client = Arc::new(PersistentClient::new(host, Duration::from_secs(5)));
         let mut client_rx = client.subscribe();
 
@@ -44,19 +52,23 @@ impl Client {
             let tx = tx.clone();
             let client = client.clone();
             let music_dir = music_dir.clone();
+            let rating_sticker = rating_sticker.clone();
 
             spawn(async move {
-                Self::send_update(&client, &tx, &music_dir)
+                Self::send_update(&client, &tx, &music_dir, rating_sticker.as_deref())
                     .await
                     .expect("Failed to send update");
 
                 while let Ok(change) = client_rx.recv().await {
                     debug!("Received state change: {change:?}");
                     if let ConnectionEvent::SubsystemChange(
-                        Subsystem::Player | Subsystem::Queue | Subsystem::Mixer,
+                        Subsystem::Player
+                        | Subsystem::Queue
+                        | Subsystem::Mixer
+                        | Subsystem::Sticker,
                     ) = *change
                     {
-                        Self::send_update(&client, &tx, &music_dir)
+                        Self::send_update(&client, &tx, &music_dir, rating_sticker.as_deref())
                             .await
                             .expect("Failed to send update");
                     }
@@ -80,6 +92,7 @@ impl Client {
             client,
             tx,
             music_dir,
+            rating_sticker,
             _rx: rx,
         }
     }
@@ -88,12 +101,25 @@ impl Client {
         client: &PersistentClient,
         tx: &broadcast::Sender<PlayerUpdate>,
         music_dir: &Path,
+        rating_sticker: Option<&str>,
     ) -> Result<(), broadcast::error::SendError<PlayerUpdate>> {
         let current_song = client.command(commands::CurrentSong).await;
         let status = client.command(commands::Status).await;
 
         if let (Ok(current_song), Ok(status)) = (current_song, status) {
-            let track = current_song.map(|s| convert_song(&s.song, music_dir));
+            let track = match current_song {
+                Some(song) => {
+                    let mut track = convert_song(&song.song, music_dir);
+
+                    if let Some(sticker) = rating_sticker {
+                        track.rating = get_rating(client, &song.song.url, sticker).await;
+                    }
+
+                    Some(track)
+                }
+                None => None,
+            };
+
             let status = Status::from(status);
 
             let update = PlayerUpdate::Update(Box::new(track), status);
@@ -111,6 +137,7 @@ impl Client {
                 let update = PlayerUpdate::ProgressTick(ProgressTick {
                     duration: status.duration,
                     elapsed: status.elapsed,
+                    seekable: status.duration.is_some(),
                 });
 
                 send!(tx, update);
@@ -141,15 +168,61 @@ impl MusicClient for Client {
     }
 
     fn seek(&self, duration: Duration) -> Result<()> {
-        command!(self, commands::Seek(SeekMode::Absolute(duration)))
+        await_sync(async move {
+            let status = self
+                .client
+                .command(commands::Status)
+                .await
+                .map_err(Report::new)?;
+
+            if status.duration.is_none() {
+                debug!("Current song has no length, not seeking");
+                return Ok(());
+            }
+
+            self.client
+                .command(commands::Seek(SeekMode::Absolute(duration)))
+                .await
+                .map_err(Report::new)
+        })
+    }
+
+    fn set_rating(&self, rating: f64) -> Result<()> {
+        let Some(sticker) = &self.rating_sticker else {
+            return Err(Report::msg("No rating sticker is configured"));
+        };
+
+        await_sync(async move {
+            let Some(song) = self
+                .client
+                .command(commands::CurrentSong)
+                .await
+                .map_err(Report::new)?
+            else {
+                debug!("No song is playing, not rating");
+                return Ok(());
+            };
+
+            let value = (rating.clamp(0.0, 1.0) * MAX_RATING).round().to_string();
+
+            self.client
+                .command(commands::StickerSet::new(&song.song.url, sticker, &value))
+                .await
+                .map_err(Report::new)
+        })
     }
 
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
         let rx = self.tx.subscribe();
         await_sync(async move {
-            Self::send_update(&self.client, &self.tx, &self.music_dir)
-                .await
-                .expect("to be able to send update");
+            Self::send_update(
+                &self.client,
+                &self.tx,
+                &self.music_dir,
+                self.rating_sticker.as_deref(),
+            )
+            .await
+            .expect("to be able to send update");
         });
         rx
     }
@@ -178,9 +251,46 @@ fn convert_song(song: &Song, music_dir: &Path) -> Track {
         disc: Some(disc),
         track: Some(track),
         cover_path,
+        rating: None,
     }
 }
 
+/// Reads the song's rating from its sticker.
+///
+/// Songs without the sticker have not been rated yet,
+/// whereas songs which cannot have stickers, such as streams, have no rating.
+async fn get_rating(client: &PersistentClient, uri: &str, sticker: &str) -> Option<Rating> {
+    // only songs in the database can have stickers
+    if uri.contains("://") {
+        return None;
+    }
+
+    let value = match client
+        .command(commands::StickerGet::new(uri, sticker))
+        .await
+    {
+        Ok(sticker) => parse_rating(&sticker.value)?,
+        Err(CommandError::ErrorResponse { error, .. }) if error.code == ERROR_NO_EXIST => 0.0,
+        Err(err) => {
+            debug!("Failed to read rating sticker for '{uri}': {err}");
+            return None;
+        }
+    };
+
+    Some(Rating {
+        value,
+        writable: true,
+    })
+}
+
+/// Converts a rating sticker value to between `0.0` and `1.0`.
+fn parse_rating(value: &str) -> Option<f64> {
+    let value = value.trim().parse::<f64>().ok()?;
+    value
+        .is_finite()
+        .then(|| (value / MAX_RATING).clamp(0.0, 1.0))
+}
+
 /// Attempts to read the first value for a tag
 /// (since the MPD client returns a vector of tags, or None)
 pub fn try_get_first_tag<'a>(song: &'a Song, tag: &'a Tag) -> Option<&'a str> {
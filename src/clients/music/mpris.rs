@@ -1,7 +1,7 @@
-use super::{MusicClient, PlayerState, PlayerUpdate, Status, Track, TICK_INTERVAL_MS};
+use super::{MusicClient, PlayerState, PlayerUpdate, Rating, Status, Track, TICK_INTERVAL_MS};
 use crate::clients::music::ProgressTick;
 use crate::{arc_mut, lock, send, spawn_blocking};
-use color_eyre::Result;
+use color_eyre::{Report, Result};
 use mpris::{DBusError, Event, Metadata, PlaybackStatus, Player, PlayerFinder};
 use std::cmp;
 use std::collections::HashSet;
@@ -233,9 +233,12 @@ impl Client {
             .and_then(|name| player_finder.find_by_name(name).ok())
         {
             if let Ok(metadata) = player.get_metadata() {
+                let duration = metadata.length();
+
                 let update = PlayerUpdate::ProgressTick(ProgressTick {
                     elapsed: player.get_position().ok(),
-                    duration: metadata.length(),
+                    duration,
+                    seekable: duration.is_some() && player.can_seek().unwrap_or_default(),
                 });
 
                 send!(tx, update);
@@ -286,6 +289,15 @@ impl MusicClient for Client {
 
     fn seek(&self, duration: Duration) -> Result<()> {
         if let Some(player) = Self::get_player(self) {
+            let length = player
+                .get_metadata()
+                .ok()
+                .and_then(|metadata| metadata.length());
+            if length.is_none() || !player.can_seek().unwrap_or_default() {
+                debug!("Player cannot seek the current track");
+                return Ok(());
+            }
+
             let pos = player.get_position().unwrap_or_default();
 
             let duration = duration.as_micros() as i64;
@@ -300,6 +312,13 @@ impl MusicClient for Client {
         Ok(())
     }
 
+    fn set_rating(&self, _rating: f64) -> Result<()> {
+        // MPRIS has no standard way to set the rating
+        Err(Report::msg(
+            "MPRIS players do not support setting the rating",
+        ))
+    }
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
         debug!("Creating new subscription");
         let rx = self.tx.subscribe();
@@ -326,6 +345,7 @@ impl From<Metadata> for Track {
     fn from(value: Metadata) -> Self {
         const KEY_DATE: &str = "xesam:contentCreated";
         const KEY_GENRE: &str = "xesam:genre";
+        const KEY_RATING: &str = "xesam:userRating";
 
         Self {
             title: value
@@ -351,6 +371,13 @@ impl From<Metadata> for Track {
                 .and_then(|arr| arr.first().map(|val| (*val).to_string())),
             track: value.track_number().map(|track| track as u64),
             cover_path: value.art_url().map(ToString::to_string),
+            rating: value
+                .get(KEY_RATING)
+                .and_then(mpris::MetadataValue::as_f64)
+                .map(|rating| Rating {
+                    value: rating.clamp(0.0, 1.0),
+                    writable: false,
+                }),
         }
     }
 }
@@ -0,0 +1,146 @@
+use crate::spawn;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+/// How often to compare the clocks when logind is unavailable.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How far the wall clock can run ahead of the monotonic clock
+/// before the difference is treated as a resume.
+const JUMP_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Notifies subscribers whenever the system resumes from suspend.
+///
+/// Timers use the monotonic clock, which does not advance while suspended,
+/// so interval-driven controllers otherwise show stale data until they next tick.
+#[derive(Debug)]
+pub struct Client {
+    tx: broadcast::Sender<()>,
+}
+
+impl Client {
+    fn new() -> Self {
+        let (tx, _) = broadcast::channel(4);
+
+        {
+            let tx = tx.clone();
+            spawn(async move {
+                #[cfg(feature = "zbus")]
+                match watch_logind(&tx).await {
+                    Ok(()) => warn!("logind sleep signal stream ended"),
+                    Err(err) => warn!("Failed to watch logind for resume: {err}"),
+                }
+
+                debug!("Detecting resume using clock jumps");
+                watch_clock(&tx).await;
+            });
+        }
+
+        Self { tx }
+    }
+
+    /// Creates a receiver which is notified whenever the system resumes.
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.tx.subscribe()
+    }
+}
+
+/// Gets the shared resume client, starting it on first use.
+pub fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(Client::new)
+}
+
+/// Waits for the duration to pass, or for the system to resume,
+/// whichever happens first.
+/// Returns `true` if the system resumed.
+///
+/// Using this in place of `sleep` in a polling loop refreshes immediately on resume,
+/// and then restarts the interval from that point.
+pub async fn wait(duration: Duration, rx: &mut broadcast::Receiver<()>) -> bool {
+    tokio::select! {
+        () = sleep(duration) => false,
+        Ok(()) | Err(RecvError::Lagged(_)) = rx.recv() => true,
+    }
+}
+
+#[cfg(feature = "zbus")]
+#[zbus::dbus_proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Login1Manager {
+    /// Sent with `true` before the system sleeps, and `false` after it resumes.
+    #[dbus_proxy(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+/// Sends an event each time logind reports the system has resumed.
+///
+/// Returns an error if logind is not available.
+#[cfg(feature = "zbus")]
+async fn watch_logind(tx: &broadcast::Sender<()>) -> zbus::Result<()> {
+    use zbus::export::ordered_stream::OrderedStreamExt;
+
+    let dbus = Box::pin(zbus::Connection::system()).await?;
+    let proxy = Login1ManagerProxy::new(&dbus).await?;
+    let mut stream = proxy.receive_prepare_for_sleep().await?;
+
+    debug!("Watching logind for resume");
+
+    while let Some(signal) = stream.next().await {
+        if let Ok(args) = signal.args() {
+            if !args.start {
+                debug!("System resumed");
+                tx.send(()).ok();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends an event each time the wall clock jumps ahead of the monotonic clock,
+/// which happens when the system has been suspended.
+async fn watch_clock(tx: &broadcast::Sender<()>) {
+    let mut last = (Instant::now(), SystemTime::now());
+
+    loop {
+        sleep(CHECK_INTERVAL).await;
+
+        let now = (Instant::now(), SystemTime::now());
+
+        let monotonic = now.0.duration_since(last.0);
+        let wall = now.1.duration_since(last.1).unwrap_or_default();
+
+        if is_clock_jump(monotonic, wall) {
+            debug!("System resumed (clock jumped by {:?})", wall - monotonic);
+            tx.send(()).ok();
+        }
+
+        last = now;
+    }
+}
+
+fn is_clock_jump(monotonic: Duration, wall: Duration) -> bool {
+    wall.saturating_sub(monotonic) > JUMP_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_clock_jumps() {
+        let interval = Duration::from_secs(5);
+
+        assert!(!is_clock_jump(interval, interval));
+        assert!(!is_clock_jump(interval, Duration::from_secs(6)));
+        assert!(!is_clock_jump(interval, Duration::ZERO));
+        assert!(is_clock_jump(interval, Duration::from_secs(3600)));
+    }
+}
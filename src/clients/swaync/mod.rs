@@ -1,9 +1,12 @@
 mod dbus;
+mod monitor;
 
 use crate::{register_fallible_client, send, spawn};
 use color_eyre::{Report, Result};
 use dbus::SwayNcProxy;
+pub use monitor::{Notification, NotificationEvent, Urgency};
 use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::broadcast;
 use tracing::{debug, error};
 use zbus::export::ordered_stream::OrderedStreamExt;
@@ -37,6 +40,10 @@ pub struct Client {
     proxy: SwayNcProxy<'static>,
     tx: broadcast::Sender<Event>,
     _rx: broadcast::Receiver<Event>,
+
+    notifications_tx: broadcast::Sender<NotificationEvent>,
+    _notifications_rx: broadcast::Receiver<NotificationEvent>,
+    monitor_started: AtomicBool,
 }
 
 impl Client {
@@ -60,13 +67,39 @@ impl Client {
             });
         }
 
-        Ok(Self { proxy, tx, _rx: rx })
+        let (notifications_tx, notifications_rx) = broadcast::channel(16);
+
+        Ok(Self {
+            proxy,
+            tx,
+            _rx: rx,
+            notifications_tx,
+            _notifications_rx: notifications_rx,
+            monitor_started: AtomicBool::new(false),
+        })
     }
 
     pub fn subscribe(&self) -> broadcast::Receiver<Event> {
         self.tx.subscribe()
     }
 
+    /// Subscribes to individual notifications as they are sent and closed.
+    ///
+    /// The session bus is only monitored once something first subscribes.
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<NotificationEvent> {
+        if !self.monitor_started.swap(true, Ordering::Relaxed) {
+            let tx = self.notifications_tx.clone();
+
+            spawn(async move {
+                if let Err(err) = monitor::run(tx).await {
+                    error!("Failed to monitor notifications: {err:?}");
+                }
+            });
+        }
+
+        self.notifications_tx.subscribe()
+    }
+
     pub async fn state(&self) -> Result<Event> {
         debug!("Getting subscribe data (current state)");
         match self.proxy.get_subscribe_data().await {
@@ -81,6 +114,20 @@ impl Client {
             error!("{err:?}");
         }
     }
+
+    pub async fn close_notification(&self, id: u32) {
+        debug!("Closing notification {id}");
+        if let Err(err) = self.proxy.close_notification(id).await {
+            error!("{err:?}");
+        }
+    }
+
+    pub async fn close_all_notifications(&self) {
+        debug!("Closing all notifications");
+        if let Err(err) = self.proxy.close_all_notifications().await {
+            error!("{err:?}");
+        }
+    }
 }
 
 register_fallible_client!(Client, notifications);
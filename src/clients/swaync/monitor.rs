@@ -0,0 +1,173 @@
+use crate::send;
+use color_eyre::Result;
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use tracing::{debug, trace};
+use zbus::export::futures_util::StreamExt;
+use zbus::fdo::{DBusProxy, MonitoringProxy};
+use zbus::names::{BusName, UniqueName};
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, ConnectionBuilder, MessageStream, MessageType};
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+impl From<u8> for Urgency {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Low,
+            2 => Self::Critical,
+            _ => Self::Normal,
+        }
+    }
+}
+
+impl Urgency {
+    pub const fn class(self) -> &'static str {
+        match self {
+            Self::Low => "urgency-low",
+            Self::Normal => "urgency-normal",
+            Self::Critical => "urgency-critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub urgency: Urgency,
+}
+
+#[derive(Debug, Clone)]
+pub enum NotificationEvent {
+    /// A notification was sent, or an existing one was replaced.
+    New(Notification),
+    /// The notification with the given ID was closed.
+    Closed(u32),
+}
+
+/// The body of a `Notify` method call.
+type NotifyArgs = (
+    String,
+    u32,
+    String,
+    String,
+    String,
+    Vec<String>,
+    HashMap<String, OwnedValue>,
+    i32,
+);
+
+/// Watches notifications sent to the notification server
+/// by monitoring the session bus.
+///
+/// The notification ID is only known once the server replies,
+/// so calls are held until the matching reply is seen.
+///
+/// This runs until the bus connection is closed.
+pub async fn run(tx: broadcast::Sender<NotificationEvent>) -> Result<()> {
+    let dbus = Box::pin(Connection::session()).await?;
+    let server = DBusProxy::new(&dbus)
+        .await?
+        .get_name_owner(BusName::try_from(NOTIFICATIONS_NAME)?)
+        .await?;
+
+    // a connection can no longer be used normally once it becomes a monitor,
+    // so a dedicated one is required.
+    let monitor = Box::pin(ConnectionBuilder::session()?.build()).await?;
+
+    let rules = [
+        format!(
+            "type='method_call',interface='{NOTIFICATIONS_NAME}',member='Notify',path='{NOTIFICATIONS_PATH}'"
+        ),
+        format!("type='method_return',sender='{server}'"),
+        format!(
+            "type='signal',interface='{NOTIFICATIONS_NAME}',member='NotificationClosed',path='{NOTIFICATIONS_PATH}'"
+        ),
+    ];
+    let rules = rules.iter().map(String::as_str).collect::<Vec<_>>();
+
+    MonitoringProxy::new(&monitor)
+        .await?
+        .become_monitor(&rules, 0)
+        .await?;
+
+    debug!("Monitoring notifications sent to {server}");
+
+    let mut pending: HashMap<(UniqueName<'static>, u32), Notification> = HashMap::new();
+    let mut stream = MessageStream::from(&monitor);
+
+    while let Some(msg) = stream.next().await {
+        let msg = msg?;
+        let header = msg.header()?;
+
+        match msg.message_type() {
+            MessageType::MethodCall => {
+                let (Some(sender), Some(serial)) =
+                    (header.sender()?, header.primary().serial_num())
+                else {
+                    continue;
+                };
+
+                let Ok((app_name, _replaces_id, app_icon, summary, body, _actions, hints, _)) =
+                    msg.body::<NotifyArgs>()
+                else {
+                    continue;
+                };
+
+                let urgency = hints
+                    .get("urgency")
+                    .and_then(|urgency| urgency.downcast_ref::<u8>())
+                    .map_or(Urgency::Normal, |&urgency| Urgency::from(urgency));
+
+                let notification = Notification {
+                    id: 0,
+                    app_name,
+                    app_icon,
+                    summary,
+                    body,
+                    urgency,
+                };
+
+                trace!("Notify call from {sender}: {notification:?}");
+                pending.insert((sender.to_owned(), *serial), notification);
+            }
+            MessageType::MethodReturn => {
+                let (Some(BusName::Unique(destination)), Some(serial)) =
+                    (header.destination()?, msg.reply_serial())
+                else {
+                    continue;
+                };
+
+                let Some(mut notification) = pending.remove(&(destination.to_owned(), serial))
+                else {
+                    continue;
+                };
+
+                if let Ok(id) = msg.body::<u32>() {
+                    notification.id = id;
+                    send!(tx, NotificationEvent::New(notification));
+                }
+            }
+            MessageType::Signal => {
+                if let Ok((id, _reason)) = msg.body::<(u32, u32)>() {
+                    send!(tx, NotificationEvent::Closed(id));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
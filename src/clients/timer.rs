@@ -0,0 +1,315 @@
+use super::resume;
+use crate::spawn;
+use std::future::pending;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::time::sleep_until;
+use tracing::debug;
+
+/// The number of one-second slots in the wheel.
+/// Longer intervals wrap around, and are skipped until due.
+const SLOTS: u64 = 64;
+
+/// The number of seconds over which registrations are spread.
+static JITTER: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the number of seconds over which timers with the same interval are spread,
+/// to avoid running everything at once.
+///
+/// This only affects timers created afterwards.
+pub fn set_jitter(seconds: u64) {
+    JITTER.store(seconds, Ordering::Relaxed);
+}
+
+#[derive(Debug)]
+struct Registration {
+    interval: u64,
+    tx: mpsc::Sender<()>,
+}
+
+/// Runs timers for interval-driven controllers from a single task,
+/// to avoid separate wakeups for each.
+///
+/// Intervals are aligned to whole seconds since the task started,
+/// so timers with the same interval fire together.
+/// All timers fire immediately when the system resumes from suspend.
+#[derive(Debug)]
+pub struct Client {
+    tx: mpsc::UnboundedSender<Registration>,
+}
+
+impl Client {
+    fn new() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        spawn(run(rx));
+
+        Self { tx }
+    }
+
+    /// Registers a timer firing every `interval` seconds.
+    fn register(&self, interval: u64) -> mpsc::Receiver<()> {
+        // ticks are merged if the receiver falls behind
+        let (tx, rx) = mpsc::channel(1);
+
+        self.tx
+            .send(Registration {
+                interval: interval.max(1),
+                tx,
+            })
+            .ok();
+
+        rx
+    }
+}
+
+/// Gets the shared timer client, starting it on first use.
+fn client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(Client::new)
+}
+
+/// Ticks at a fixed interval, and straight away when the system resumes.
+///
+/// Intervals of whole seconds use the shared timer,
+/// unless a dedicated task is requested.
+/// Shared ticks are aligned with others of the same interval,
+/// so the first may come sooner than the interval.
+#[derive(Debug)]
+pub enum Ticker {
+    Shared(mpsc::Receiver<()>),
+    Dedicated {
+        interval: Duration,
+        resume_rx: broadcast::Receiver<()>,
+    },
+}
+
+impl Ticker {
+    pub fn new(interval: Duration, dedicated: bool) -> Self {
+        if dedicated || interval.subsec_nanos() != 0 || interval.is_zero() {
+            Self::Dedicated {
+                interval,
+                resume_rx: resume::client().subscribe(),
+            }
+        } else {
+            Self::Shared(client().register(interval.as_secs()))
+        }
+    }
+
+    /// Waits for the next tick.
+    pub async fn tick(&mut self) {
+        match self {
+            Self::Shared(rx) => {
+                if rx.recv().await.is_none() {
+                    pending::<()>().await;
+                }
+            }
+            Self::Dedicated {
+                interval,
+                resume_rx,
+            } => {
+                resume::wait(*interval, resume_rx).await;
+            }
+        }
+    }
+}
+
+async fn run(mut rx: mpsc::UnboundedReceiver<Registration>) {
+    let start = Instant::now();
+    let now = || start.elapsed().as_secs();
+
+    let mut wheel = Wheel::default();
+    let mut resume_rx = resume::client().subscribe();
+    let mut registered = 0;
+
+    // the timer is dropped once its receiver is
+    let fire = |tx: &mpsc::Sender<()>| !matches!(tx.try_send(()), Err(TrySendError::Closed(())));
+
+    loop {
+        let next = wheel
+            .next_due()
+            .map(|tick| start + Duration::from_secs(tick));
+
+        let sleep = async {
+            match next {
+                Some(next) => sleep_until(next.into()).await,
+                None => pending().await,
+            }
+        };
+
+        tokio::select! {
+            () = sleep => wheel.advance(now(), fire),
+            Some(registration) = rx.recv() => {
+                wheel.advance(now(), fire);
+
+                // spread across the jitter, or the interval if shorter
+                let offset = registered % (JITTER.load(Ordering::Relaxed) + 1);
+                registered += 1;
+
+                debug!("Registered {}s timer (offset {offset}s)", registration.interval);
+                wheel.insert(registration.tx, registration.interval, offset);
+            },
+            Ok(()) | Err(RecvError::Lagged(_)) = resume_rx.recv() => wheel.fire_all(now(), fire),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    due: u64,
+    interval: u64,
+    offset: u64,
+    value: T,
+}
+
+/// A hashed timer wheel with one-second slots.
+#[derive(Debug)]
+struct Wheel<T> {
+    slots: Vec<Vec<Entry<T>>>,
+    /// The last tick advanced to.
+    now: u64,
+}
+
+impl<T> Default for Wheel<T> {
+    fn default() -> Self {
+        Self {
+            slots: (0..SLOTS).map(|_| vec![]).collect(),
+            now: 0,
+        }
+    }
+}
+
+impl<T> Wheel<T> {
+    /// Adds a timer, first due at the next tick aligned to the interval and offset.
+    fn insert(&mut self, value: T, interval: u64, offset: u64) {
+        let entry = Entry {
+            due: next_aligned(self.now, interval, offset),
+            interval,
+            offset,
+            value,
+        };
+
+        self.slot(entry.due).push(entry);
+    }
+
+    /// Gets the tick the next timer is due at.
+    fn next_due(&self) -> Option<u64> {
+        self.slots.iter().flatten().map(|entry| entry.due).min()
+    }
+
+    /// Fires every timer due up to and including the tick,
+    /// and schedules it again if `fire` returns `true`.
+    fn advance(&mut self, tick: u64, fire: impl FnMut(&T) -> bool) {
+        if tick <= self.now {
+            return;
+        }
+
+        let slots = if tick - self.now >= SLOTS {
+            (0..SLOTS).collect::<Vec<_>>()
+        } else {
+            (self.now + 1..=tick).map(|tick| tick % SLOTS).collect()
+        };
+
+        self.now = tick;
+        self.fire_where(&slots, |entry| entry.due <= tick, fire);
+    }
+
+    /// Fires every timer, and schedules each again from the tick.
+    fn fire_all(&mut self, tick: u64, fire: impl FnMut(&T) -> bool) {
+        self.now = self.now.max(tick);
+        self.fire_where(&(0..SLOTS).collect::<Vec<_>>(), |_| true, fire);
+    }
+
+    fn fire_where(
+        &mut self,
+        slots: &[u64],
+        is_due: impl Fn(&Entry<T>) -> bool,
+        mut fire: impl FnMut(&T) -> bool,
+    ) {
+        let mut rescheduled = vec![];
+
+        for &slot in slots {
+            let entries = std::mem::take(&mut self.slots[slot as usize]);
+
+            for mut entry in entries {
+                if !is_due(&entry) {
+                    self.slots[slot as usize].push(entry);
+                } else if fire(&entry.value) {
+                    entry.due = next_aligned(self.now, entry.interval, entry.offset);
+                    rescheduled.push(entry);
+                }
+            }
+        }
+
+        for entry in rescheduled {
+            self.slot(entry.due).push(entry);
+        }
+    }
+
+    fn slot(&mut self, tick: u64) -> &mut Vec<Entry<T>> {
+        &mut self.slots[(tick % SLOTS) as usize]
+    }
+}
+
+/// Gets the first tick after `after` which is a whole number of intervals from the offset.
+fn next_aligned(after: u64, interval: u64, offset: u64) -> u64 {
+    let offset = offset % interval;
+
+    if after < offset {
+        offset
+    } else {
+        offset + ((after - offset) / interval + 1) * interval
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_to_interval() {
+        assert_eq!(next_aligned(0, 5, 0), 5);
+        assert_eq!(next_aligned(4, 5, 0), 5);
+        assert_eq!(next_aligned(5, 5, 0), 10);
+        assert_eq!(next_aligned(0, 5, 2), 2);
+        assert_eq!(next_aligned(2, 5, 2), 7);
+        assert_eq!(next_aligned(3, 5, 7), 7);
+    }
+
+    #[test]
+    fn fires_due_timers_together() {
+        let mut wheel = Wheel::default();
+        wheel.insert("a", 2, 0);
+        wheel.insert("b", 2, 0);
+        wheel.insert("c", 100, 0);
+
+        let mut fired = vec![];
+        assert_eq!(wheel.next_due(), Some(2));
+        wheel.advance(2, |value| {
+            fired.push(*value);
+            true
+        });
+        assert_eq!(fired, ["a", "b"]);
+
+        // skipped ticks are caught up, and past the end of the wheel
+        fired.clear();
+        wheel.advance(101, |value| {
+            fired.push(*value);
+            *value != "b"
+        });
+        fired.sort_unstable();
+        assert_eq!(fired, ["a", "b", "c"]);
+
+        assert_eq!(wheel.next_due(), Some(102));
+        fired.clear();
+        wheel.fire_all(102, |value| {
+            fired.push(*value);
+            true
+        });
+        fired.sort_unstable();
+        assert_eq!(fired, ["a", "c"]);
+        assert_eq!(wheel.next_due(), Some(104));
+    }
+}
@@ -0,0 +1,403 @@
+use super::dbus::DBusMenuProxy;
+use super::host;
+use crate::{arc_mut, lock, send, send_async, spawn};
+use color_eyre::{Report, Result};
+use futures_lite::StreamExt;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use system_tray::client::{ActivateRequest, Event, UpdateEvent};
+use system_tray::item::StatusNotifierItem;
+use system_tray::menu::TrayMenu;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
+use zbus::fdo::DBusProxy;
+use zbus::names::BusName;
+use zbus::zvariant::Value;
+
+pub const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+
+/// How long to wait before trying again after failing to start,
+/// such as when the watcher changes owner part way through.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Every current item by address, with its menu if the client has read it.
+pub type State = HashMap<String, (StatusNotifierItem, Option<TrayMenu>)>;
+
+/// How the client gets its items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayMode {
+    /// Not yet connected to a watcher.
+    Starting,
+    /// Ironbar owns the `StatusNotifierWatcher`, which items register with.
+    Watcher,
+    /// Another process owns the `StatusNotifierWatcher`,
+    /// and Ironbar is registered with it as a host.
+    Host,
+}
+
+/// A message from whichever source is currently providing items.
+#[derive(Debug)]
+pub enum Message {
+    Event(Event),
+    /// Replaces every item, as when the source changes.
+    Sync(State),
+}
+
+/// Client for the system tray.
+///
+/// If another process already owns the `StatusNotifierWatcher`,
+/// such as a second bar or a notification daemon,
+/// this registers as a host with it instead.
+/// Otherwise, the `system-tray` client is used to run Ironbar's own watcher.
+///
+/// This is re-evaluated whenever the watcher's owner goes away.
+#[derive(Debug)]
+pub struct Client {
+    connection: zbus::Connection,
+    tx: broadcast::Sender<Event>,
+    _rx: broadcast::Receiver<Event>,
+    items: Arc<Mutex<State>>,
+    #[cfg_attr(not(feature = "ipc"), allow(dead_code))]
+    mode: Arc<Mutex<TrayMode>>,
+}
+
+impl Client {
+    pub async fn new(service_name: &str) -> Result<Self> {
+        let connection = zbus::Connection::session().await?;
+
+        // hosts are identified by a name they own
+        let host_name = format!(
+            "org.kde.StatusNotifierHost-{}-{service_name}",
+            std::process::id()
+        );
+        connection.request_name(host_name.as_str()).await?;
+
+        let (tx, rx) = broadcast::channel(32);
+        let (message_tx, mut message_rx) = mpsc::channel(64);
+
+        let items = arc_mut!(State::new());
+        let mode = arc_mut!(TrayMode::Starting);
+
+        {
+            let tx = tx.clone();
+            let items = items.clone();
+
+            spawn(async move {
+                while let Some(message) = message_rx.recv().await {
+                    let events = match message {
+                        Message::Event(event) => {
+                            apply(&mut lock!(items), event).into_iter().collect()
+                        }
+                        Message::Sync(state) => sync(&mut lock!(items), state),
+                    };
+
+                    for event in events {
+                        send!(tx, event);
+                    }
+                }
+            });
+        }
+
+        {
+            let connection = connection.clone();
+            let service_name = service_name.to_string();
+            let mode = mode.clone();
+
+            spawn(async move {
+                if let Err(err) =
+                    run(&connection, &service_name, &host_name, &message_tx, &mode).await
+                {
+                    error!("{:?}", err.wrap_err("Tray client stopped"));
+                }
+            });
+        }
+
+        Ok(Self {
+            connection,
+            tx,
+            _rx: rx,
+            items,
+            mode,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    pub fn items(&self) -> Arc<Mutex<State>> {
+        self.items.clone()
+    }
+
+    #[cfg(feature = "ipc")]
+    pub fn mode(&self) -> TrayMode {
+        *lock!(self.mode)
+    }
+
+    /// Activates one of an item's menu entries, as when it is clicked.
+    pub async fn activate(&self, request: ActivateRequest) -> Result<()> {
+        let proxy = DBusMenuProxy::builder(&self.connection)
+            .destination(request.address)?
+            .path(request.menu_path)?
+            .build()
+            .await?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs() as u32);
+
+        proxy
+            .event(request.submenu_id, "clicked", &Value::I32(0), timestamp)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// The current source of items,
+/// which stops providing them once dropped.
+enum Source {
+    /// Ironbar's own watcher, from the `system-tray` client.
+    Watcher {
+        _client: system_tray::client::Client,
+        task: JoinHandle<()>,
+    },
+    /// Another process's watcher.
+    Host(JoinHandle<()>),
+}
+
+impl Source {
+    const fn mode(&self) -> TrayMode {
+        match self {
+            Self::Watcher { .. } => TrayMode::Watcher,
+            Self::Host(_) => TrayMode::Host,
+        }
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        match self {
+            Self::Watcher { task, .. } | Self::Host(task) => task.abort(),
+        }
+    }
+}
+
+/// Starts getting items from the current watcher,
+/// and starts again each time its owner goes away.
+async fn run(
+    connection: &zbus::Connection,
+    service_name: &str,
+    host_name: &str,
+    tx: &mpsc::Sender<Message>,
+    mode: &Mutex<TrayMode>,
+) -> Result<()> {
+    let dbus = DBusProxy::new(connection).await?;
+    let mut owner_changes = dbus
+        .receive_name_owner_changed_with_args(&[(0, WATCHER_NAME)])
+        .await?;
+
+    loop {
+        let source = match start(&dbus, connection, service_name, host_name, tx).await {
+            Ok(source) => source,
+            Err(err) => {
+                warn!("{:?}", err.wrap_err("Failed to start tray, retrying"));
+                sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        *lock!(mode) = source.mode();
+
+        // the watcher cannot go away while Ironbar owns it,
+        // unless its connection is lost
+        loop {
+            let change = owner_changes
+                .next()
+                .await
+                .ok_or_else(|| Report::msg("Watcher owner stream closed"))?;
+
+            if change.args()?.old_owner().is_some() {
+                break;
+            }
+        }
+
+        info!("Tray: StatusNotifierWatcher owner went away, re-evaluating mode");
+
+        drop(source);
+        *lock!(mode) = TrayMode::Starting;
+
+        // items re-register with the new watcher
+        send_async!(tx, Message::Sync(State::new()));
+    }
+}
+
+/// Registers with the watcher as a host if it is running,
+/// or starts Ironbar's own otherwise.
+async fn start(
+    dbus: &DBusProxy<'_>,
+    connection: &zbus::Connection,
+    service_name: &str,
+    host_name: &str,
+    tx: &mpsc::Sender<Message>,
+) -> Result<Source> {
+    let owner = dbus
+        .get_name_owner(BusName::from_static_str(WATCHER_NAME)?)
+        .await
+        .ok();
+
+    if let Some(owner) = owner {
+        let task = host::start(connection, host_name, tx.clone()).await?;
+
+        info!("Tray: StatusNotifierWatcher is owned by {owner}, running in host mode");
+        return Ok(Source::Host(task));
+    }
+
+    let client = system_tray::client::Client::new(service_name).await?;
+    let mut rx = client.subscribe();
+    let items = client.items();
+
+    let tx = tx.clone();
+    let task = spawn(async move {
+        // items found before subscribing
+        let state = lock!(items).clone();
+        send_async!(tx, Message::Sync(state));
+
+        loop {
+            match rx.recv().await {
+                Ok(event) => send_async!(tx, Message::Event(event)),
+                Err(RecvError::Lagged(count)) => {
+                    warn!("Tray client missed {count} events, re-syncing state");
+
+                    let state = lock!(items).clone();
+                    send_async!(tx, Message::Sync(state));
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+
+        debug!("Tray watcher event stream closed");
+    });
+
+    info!("Tray: no StatusNotifierWatcher running, running in watcher mode");
+    Ok(Source::Watcher {
+        _client: client,
+        task,
+    })
+}
+
+/// Applies the event to the items,
+/// returning it if it changed anything.
+fn apply(items: &mut State, event: Event) -> Option<Event> {
+    match &event {
+        Event::Add(address, item) => {
+            items.insert(address.clone(), (*item.clone(), None));
+        }
+        Event::Update(address, update) => {
+            let (item, menu) = items.get_mut(address)?;
+
+            match update {
+                UpdateEvent::AttentionIcon(icon) => item.attention_icon_name.clone_from(icon),
+                UpdateEvent::Icon(icon) => item.icon_name.clone_from(icon),
+                UpdateEvent::OverlayIcon(icon) => item.overlay_icon_name.clone_from(icon),
+                UpdateEvent::Status(status) => item.status = *status,
+                UpdateEvent::Title(title) => item.title.clone_from(title),
+                UpdateEvent::Menu(new_menu) => *menu = Some(new_menu.clone()),
+            }
+        }
+        Event::Remove(address) => {
+            items.remove(address)?;
+        }
+    }
+
+    Some(event)
+}
+
+/// Replaces the items,
+/// returning the events to get from the old items to the new.
+fn sync(items: &mut State, state: State) -> Vec<Event> {
+    let mut events = items
+        .keys()
+        .filter(|address| !state.contains_key(*address))
+        .map(|address| Event::Remove(address.clone()))
+        .collect::<Vec<_>>();
+
+    for (address, (item, menu)) in &state {
+        events.push(Event::Add(address.clone(), Box::new(item.clone())));
+
+        if let Some(menu) = menu {
+            events.push(Event::Update(
+                address.clone(),
+                UpdateEvent::Menu(menu.clone()),
+            ));
+        }
+    }
+
+    *items = state;
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use system_tray::item::Status;
+
+    fn item(id: &str) -> Box<StatusNotifierItem> {
+        Box::new(StatusNotifierItem {
+            id: id.to_string(),
+            category: Default::default(),
+            title: None,
+            status: Status::Active,
+            window_id: 0,
+            icon_theme_path: None,
+            icon_name: None,
+            icon_pixmap: None,
+            overlay_icon_name: None,
+            overlay_icon_pixmap: None,
+            attention_icon_name: None,
+            attention_icon_pixmap: None,
+            attention_movie_name: None,
+            item_is_menu: false,
+            menu: None,
+        })
+    }
+
+    #[test]
+    fn applies_events() {
+        let mut items = State::new();
+
+        assert!(apply(&mut items, Event::Add(String::from(":1.5"), item("a"))).is_some());
+        assert!(apply(
+            &mut items,
+            Event::Update(
+                String::from(":1.5"),
+                UpdateEvent::Icon(Some(String::from("icon")))
+            )
+        )
+        .is_some());
+        assert_eq!(items[":1.5"].0.icon_name.as_deref(), Some("icon"));
+
+        // unknown items are not sent on
+        assert!(apply(
+            &mut items,
+            Event::Update(String::from(":1.6"), UpdateEvent::Title(None))
+        )
+        .is_none());
+        assert!(apply(&mut items, Event::Remove(String::from(":1.6"))).is_none());
+
+        let events = sync(
+            &mut items,
+            State::from([(String::from(":1.7"), (*item("b"), None))]),
+        );
+        assert!(
+            matches!(&events[..], [Event::Remove(removed), Event::Add(added, _)] if removed == ":1.5" && added == ":1.7")
+        );
+        assert_eq!(items.len(), 1);
+    }
+}
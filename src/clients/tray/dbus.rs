@@ -0,0 +1,71 @@
+//! # D-Bus interface proxies for: `org.kde.StatusNotifierItem`, `org.kde.StatusNotifierWatcher` and `com.canonical.dbusmenu`
+//!
+//! Only the members Ironbar uses directly are included,
+//! rather than through the `system-tray` client.
+
+use std::collections::HashMap;
+use zbus::zvariant::{OwnedValue, Value};
+
+#[cfg(feature = "ipc")]
+#[zbus::dbus_proxy(interface = "org.kde.StatusNotifierItem", assume_defaults = true)]
+trait StatusNotifierItem {
+    /// Activate method
+    fn activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+    /// SecondaryActivate method
+    fn secondary_activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+}
+
+#[zbus::dbus_proxy(
+    interface = "org.kde.StatusNotifierWatcher",
+    default_service = "org.kde.StatusNotifierWatcher",
+    default_path = "/StatusNotifierWatcher"
+)]
+trait StatusNotifierWatcher {
+    /// RegisterStatusNotifierHost method
+    fn register_status_notifier_host(&self, service: &str) -> zbus::Result<()>;
+
+    /// StatusNotifierItemRegistered signal
+    #[dbus_proxy(signal)]
+    fn status_notifier_item_registered(&self, service: &str) -> zbus::Result<()>;
+
+    /// StatusNotifierItemUnregistered signal
+    #[dbus_proxy(signal)]
+    fn status_notifier_item_unregistered(&self, service: &str) -> zbus::Result<()>;
+
+    /// RegisteredStatusNotifierItems property
+    #[dbus_proxy(property)]
+    fn registered_status_notifier_items(&self) -> zbus::Result<Vec<String>>;
+}
+
+/// A menu item's layout: its ID, properties and children.
+pub type Layout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+#[zbus::dbus_proxy(interface = "com.canonical.dbusmenu", assume_defaults = true)]
+trait DBusMenu {
+    /// AboutToShow method
+    fn about_to_show(&self, id: i32) -> zbus::Result<bool>;
+
+    /// Event method
+    fn event(&self, id: i32, event_id: &str, data: &Value<'_>, timestamp: u32) -> zbus::Result<()>;
+
+    /// GetLayout method
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        property_names: &[&str],
+    ) -> zbus::Result<(u32, Layout)>;
+
+    /// ItemsPropertiesUpdated signal
+    #[dbus_proxy(signal)]
+    fn items_properties_updated(
+        &self,
+        updated_props: Vec<(i32, HashMap<String, OwnedValue>)>,
+        removed_props: Vec<(i32, Vec<String>)>,
+    ) -> zbus::Result<()>;
+
+    /// LayoutUpdated signal
+    #[dbus_proxy(signal)]
+    fn layout_updated(&self, revision: u32, parent: i32) -> zbus::Result<()>;
+}
@@ -0,0 +1,356 @@
+use super::client::Message;
+use super::dbus::StatusNotifierWatcherProxy;
+use super::DEFAULT_ITEM_PATH;
+use crate::{send_async, spawn};
+use color_eyre::{Report, Result};
+use futures_lite::StreamExt;
+use std::collections::HashMap;
+use system_tray::client::{Event, UpdateEvent};
+use system_tray::item::{IconPixmap, Status, StatusNotifierItem};
+use tokio::sync::mpsc;
+use tokio::task::{AbortHandle, JoinHandle, JoinSet};
+use tracing::{debug, warn};
+use zbus::fdo::{DBusProxy, PropertiesProxy};
+use zbus::names::InterfaceName;
+use zbus::zvariant::{Array, ObjectPath, OwnedValue, Structure};
+
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+
+/// Registers as a host with another process's watcher,
+/// and sends every item registered with it, and changes to them,
+/// until the returned task is aborted.
+pub async fn start(
+    connection: &zbus::Connection,
+    host_name: &str,
+    tx: mpsc::Sender<Message>,
+) -> Result<JoinHandle<()>> {
+    let watcher = StatusNotifierWatcherProxy::new(connection).await?;
+
+    // before reading the current items, so none are missed in between
+    let mut registered = watcher.receive_status_notifier_item_registered().await?;
+    let mut unregistered = watcher.receive_status_notifier_item_unregistered().await?;
+
+    watcher.register_status_notifier_host(host_name).await?;
+    let current = watcher.registered_status_notifier_items().await?;
+
+    let mut items = Items {
+        connection: connection.clone(),
+        tx: tx.clone(),
+        tasks: JoinSet::new(),
+        handles: HashMap::new(),
+    };
+
+    let task = spawn(async move {
+        for address in current {
+            items.add(&address);
+        }
+
+        loop {
+            tokio::select! {
+                Some(signal) = registered.next() => match signal.args() {
+                    Ok(args) => items.add(args.service()),
+                    Err(err) => warn!("Invalid tray item registration: {err}"),
+                },
+                Some(signal) = unregistered.next() => match signal.args() {
+                    Ok(args) => {
+                        let (destination, _) = split_address(args.service());
+                        items.remove(destination);
+
+                        send_async!(tx, Message::Event(Event::Remove(destination.to_string())));
+                    }
+                    Err(err) => warn!("Invalid tray item unregistration: {err}"),
+                },
+                Some(_) = items.tasks.join_next() => {},
+                else => break,
+            }
+        }
+
+        debug!("Tray watcher signal stream closed");
+    });
+
+    Ok(task)
+}
+
+/// The task watching each item, by service name.
+///
+/// The tasks are aborted once this is dropped.
+struct Items {
+    connection: zbus::Connection,
+    tx: mpsc::Sender<Message>,
+    tasks: JoinSet<()>,
+    handles: HashMap<String, AbortHandle>,
+}
+
+impl Items {
+    fn add(&mut self, address: &str) {
+        let (destination, path) = split_address(address);
+
+        // the item may register again, such as after restarting
+        self.remove(destination);
+
+        let handle = self.tasks.spawn(watch_item(
+            self.connection.clone(),
+            destination.to_string(),
+            path.to_string(),
+            self.tx.clone(),
+        ));
+
+        self.handles.insert(destination.to_string(), handle);
+    }
+
+    fn remove(&mut self, destination: &str) {
+        if let Some(handle) = self.handles.remove(destination) {
+            handle.abort();
+        }
+    }
+}
+
+/// Sends the item, and any changes to it,
+/// followed by its removal once it goes away.
+async fn watch_item(
+    connection: zbus::Connection,
+    destination: String,
+    path: String,
+    tx: mpsc::Sender<Message>,
+) {
+    if let Err(err) = track_item(&connection, &destination, &path, &tx).await {
+        warn!(
+            "{:?}",
+            err.wrap_err(format!("Failed to read tray item '{destination}{path}'"))
+        );
+    }
+
+    send_async!(tx, Message::Event(Event::Remove(destination)));
+}
+
+async fn track_item(
+    connection: &zbus::Connection,
+    destination: &str,
+    path: &str,
+    tx: &mpsc::Sender<Message>,
+) -> Result<()> {
+    let properties = PropertiesProxy::builder(connection)
+        .destination(destination.to_string())?
+        .path(path.to_string())?
+        .build()
+        .await?;
+
+    // the `New*` signals, which only say which property changed
+    let item_proxy = zbus::Proxy::new(
+        connection,
+        destination.to_string(),
+        path.to_string(),
+        ITEM_INTERFACE,
+    )
+    .await?;
+    let mut signals = item_proxy.receive_all_signals().await?;
+
+    let mut owner_changes = DBusProxy::new(connection)
+        .await?
+        .receive_name_owner_changed_with_args(&[(0, destination)])
+        .await?;
+
+    let interface = InterfaceName::from_static_str_unchecked(ITEM_INTERFACE);
+
+    let item = parse_item(&properties.get_all(interface.clone()).await?)
+        .ok_or_else(|| Report::msg("Item has no ID"))?;
+
+    send_async!(
+        tx,
+        Message::Event(Event::Add(destination.to_string(), Box::new(item)))
+    );
+
+    loop {
+        tokio::select! {
+            Some(signal) = signals.next() => {
+                let Some(property) = signal.member().and_then(|member| changed_property(&member)) else {
+                    continue;
+                };
+
+                let value = properties.get(interface.clone(), property).await.ok();
+                if let Some(update) = update_event(property, value.as_ref()) {
+                    send_async!(tx, Message::Event(Event::Update(destination.to_string(), update)));
+                }
+            }
+            Some(change) = owner_changes.next() => {
+                if change.args()?.new_owner().is_none() {
+                    break;
+                }
+            }
+            else => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits an address registered with the watcher into its service name and object path.
+///
+/// Items may register with only a service name,
+/// or only an object path in which case the service is filled in by the watcher.
+fn split_address(address: &str) -> (&str, &str) {
+    match address.find('/') {
+        Some(index) => address.split_at(index),
+        None => (address, DEFAULT_ITEM_PATH),
+    }
+}
+
+/// Gets the property for a `New*` item signal.
+fn changed_property(signal: &str) -> Option<&'static str> {
+    match signal {
+        "NewAttentionIcon" => Some("AttentionIconName"),
+        "NewIcon" => Some("IconName"),
+        "NewOverlayIcon" => Some("OverlayIconName"),
+        "NewStatus" => Some("Status"),
+        "NewTitle" => Some("Title"),
+        _ => None,
+    }
+}
+
+fn update_event(property: &str, value: Option<&OwnedValue>) -> Option<UpdateEvent> {
+    let text = value
+        .and_then(|value| value.downcast_ref::<str>())
+        .map(ToString::to_string);
+
+    match property {
+        "AttentionIconName" => Some(UpdateEvent::AttentionIcon(text)),
+        "IconName" => Some(UpdateEvent::Icon(text)),
+        "OverlayIconName" => Some(UpdateEvent::OverlayIcon(text)),
+        "Status" => Some(UpdateEvent::Status(
+            text.as_deref().map(Status::from).unwrap_or_default(),
+        )),
+        "Title" => Some(UpdateEvent::Title(text)),
+        _ => None,
+    }
+}
+
+/// Reads an item from its properties,
+/// in the same way as the `system-tray` client.
+fn parse_item(properties: &HashMap<String, OwnedValue>) -> Option<StatusNotifierItem> {
+    let string = |key: &str| {
+        properties
+            .get(key)
+            .and_then(|value| value.downcast_ref::<str>())
+            .map(ToString::to_string)
+    };
+
+    let pixmaps = |key: &str| {
+        properties
+            .get(key)
+            .and_then(|value| value.downcast_ref::<Array>())
+            .map(parse_pixmaps)
+    };
+
+    Some(StatusNotifierItem {
+        id: string("Id")?,
+        category: string("Category")
+            .as_deref()
+            .map(Into::into)
+            .unwrap_or_default(),
+        title: string("Title"),
+        status: string("Status")
+            .as_deref()
+            .map(Into::into)
+            .unwrap_or_default(),
+        window_id: properties
+            .get("WindowId")
+            .and_then(|value| value.downcast_ref::<u32>())
+            .copied()
+            .unwrap_or_default(),
+        icon_theme_path: string("IconThemePath"),
+        icon_name: string("IconName"),
+        icon_pixmap: pixmaps("IconPixmap"),
+        overlay_icon_name: string("OverlayIconName"),
+        overlay_icon_pixmap: pixmaps("OverlayIconPixmap"),
+        attention_icon_name: string("AttentionIconName"),
+        attention_icon_pixmap: pixmaps("AttentionIconPixmap"),
+        attention_movie_name: string("AttentionMovieName"),
+        item_is_menu: properties
+            .get("ItemIsMenu")
+            .and_then(|value| value.downcast_ref::<bool>())
+            .copied()
+            .unwrap_or_default(),
+        menu: properties
+            .get("Menu")
+            .and_then(|value| value.downcast_ref::<ObjectPath>())
+            .map(ToString::to_string),
+    })
+}
+
+/// Reads an `a(iiay)` array of icon pixmaps,
+/// skipping any which are malformed.
+fn parse_pixmaps(array: &Array) -> Vec<IconPixmap> {
+    array
+        .iter()
+        .filter_map(|pixmap| {
+            let [width, height, pixels] = pixmap.downcast_ref::<Structure>()?.fields() else {
+                return None;
+            };
+
+            Some(IconPixmap {
+                width: *width.downcast_ref::<i32>()?,
+                height: *height.downcast_ref::<i32>()?,
+                pixels: pixels
+                    .downcast_ref::<Array>()?
+                    .iter()
+                    .filter_map(|pixel| pixel.downcast_ref::<u8>().copied())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    #[test]
+    fn reads_items() {
+        assert_eq!(
+            split_address(":1.52/org/ayatana/NotificationItem/nextcloud"),
+            (":1.52", "/org/ayatana/NotificationItem/nextcloud")
+        );
+        assert_eq!(
+            split_address("org.kde.StatusNotifierItem-1234-1"),
+            ("org.kde.StatusNotifierItem-1234-1", "/StatusNotifierItem")
+        );
+
+        let properties = HashMap::from([
+            (
+                String::from("Id"),
+                OwnedValue::from(Value::from("nm-applet")),
+            ),
+            (
+                String::from("Status"),
+                OwnedValue::from(Value::from("NeedsAttention")),
+            ),
+            (
+                String::from("Menu"),
+                OwnedValue::from(Value::from(
+                    ObjectPath::try_from("/MenuBar").expect("valid object path"),
+                )),
+            ),
+            (
+                String::from("IconPixmap"),
+                OwnedValue::from(Value::from(vec![(1i32, 1i32, vec![0u8, 1, 2, 3])])),
+            ),
+        ]);
+
+        let item = parse_item(&properties).expect("item should have an ID");
+        assert_eq!(item.id, "nm-applet");
+        assert!(matches!(item.status, Status::NeedsAttention));
+        assert_eq!(item.menu.as_deref(), Some("/MenuBar"));
+
+        let pixmaps = item.icon_pixmap.expect("item should have a pixmap");
+        assert_eq!(pixmaps.len(), 1);
+        assert_eq!(pixmaps[0].pixels, [0, 1, 2, 3]);
+
+        assert!(parse_item(&HashMap::new()).is_none());
+
+        assert!(matches!(
+            changed_property("NewStatus").and_then(|property| update_event(property, None)),
+            Some(UpdateEvent::Status(Status::Unknown))
+        ));
+    }
+}
@@ -0,0 +1,488 @@
+use super::dbus::DBusMenuProxy;
+use crate::spawn;
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use std::collections::HashMap;
+use system_tray::client::{Event, UpdateEvent};
+use system_tray::menu::{Disposition, MenuItem, MenuType, ToggleState, ToggleType, TrayMenu};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use zbus::zvariant::{self, OwnedValue, Value};
+use zbus::Connection;
+
+/// A menu item's properties, by name.
+type Properties = HashMap<String, OwnedValue>;
+
+#[derive(Debug, Clone, Copy)]
+enum Command {
+    AboutToShow(i32),
+    Resend,
+}
+
+/// Watches an item's `DBusMenu` directly,
+/// sending a menu update whenever its layout or any item's properties change.
+///
+/// The `system-tray` client only reads the menu when the whole layout changes,
+/// and does not read item icon data.
+#[derive(Debug)]
+pub struct MenuWatcher {
+    path: String,
+    tx: mpsc::Sender<Command>,
+    handle: JoinHandle<()>,
+}
+
+impl MenuWatcher {
+    pub fn new(
+        connection: Connection,
+        address: String,
+        path: String,
+        tx: mpsc::Sender<Event>,
+    ) -> Self {
+        let (command_tx, command_rx) = mpsc::channel(8);
+
+        let handle = {
+            let path = path.clone();
+            spawn(async move {
+                if let Err(err) = watch(&connection, &address, &path, command_rx, &tx).await {
+                    warn!("Failed to watch menu for tray item '{address}': {err:?}");
+                }
+            })
+        };
+
+        Self {
+            path,
+            tx: command_tx,
+            handle,
+        }
+    }
+
+    /// The object path of the menu.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Tells the item that the menu or submenu with the ID is about to be shown,
+    /// reading the menu again if the item asks.
+    ///
+    /// Some items only fill in submenus once this is called.
+    pub fn about_to_show(&self, id: i32) {
+        self.tx.try_send(Command::AboutToShow(id)).ok();
+    }
+
+    /// Sends the current menu again.
+    pub fn resend(&self) {
+        self.tx.try_send(Command::Resend).ok();
+    }
+}
+
+impl Drop for MenuWatcher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn watch(
+    connection: &Connection,
+    address: &str,
+    path: &str,
+    mut rx: mpsc::Receiver<Command>,
+    tx: &mpsc::Sender<Event>,
+) -> Result<()> {
+    let proxy = DBusMenuProxy::builder(connection)
+        .destination(address)?
+        .path(path)?
+        .build()
+        .await?;
+
+    let mut layout_updated = proxy.receive_layout_updated().await?;
+    let mut properties_updated = proxy.receive_items_properties_updated().await?;
+
+    let mut items = get_items(&proxy).await?;
+
+    loop {
+        let menu = TrayMenu {
+            id: 0,
+            submenus: items.clone(),
+        };
+
+        // the subscriber has gone away
+        if tx
+            .send(Event::Update(address.to_string(), UpdateEvent::Menu(menu)))
+            .await
+            .is_err()
+        {
+            break;
+        }
+
+        tokio::select! {
+            Some(_) = layout_updated.next() => items = get_items(&proxy).await?,
+            Some(signal) = properties_updated.next() => {
+                let args = signal.args()?;
+                update_properties(&mut items, &args.updated_props, &args.removed_props);
+            }
+            Some(command) = rx.recv() => match command {
+                Command::AboutToShow(id) => match proxy.about_to_show(id).await {
+                    Ok(true) => items = get_items(&proxy).await?,
+                    Ok(false) => continue,
+                    // not every item implements this
+                    Err(err) => {
+                        debug!("AboutToShow failed for '{address}' (id {id}): {err}");
+                        continue;
+                    }
+                },
+                Command::Resend => {}
+            },
+            else => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads the full menu, including every level of submenus.
+async fn get_items(proxy: &DBusMenuProxy<'_>) -> Result<Vec<MenuItem>> {
+    let (_revision, (_id, _properties, children)) = proxy.get_layout(0, -1, &[]).await?;
+    Ok(parse_items(&children)?)
+}
+
+/// Parses the items of a menu layout, as returned by `GetLayout`.
+///
+/// Unlike the `system-tray` client, this reads item icon data,
+/// and keeps underscores written as `__` in labels.
+fn parse_items(children: &[OwnedValue]) -> zvariant::Result<Vec<MenuItem>> {
+    children.iter().map(|child| parse_item(child)).collect()
+}
+
+fn parse_item(value: &Value) -> zvariant::Result<MenuItem> {
+    let Value::Structure(structure) = unwrap_variant(value) else {
+        return Err(zvariant::Error::IncorrectType);
+    };
+
+    let [Value::I32(id), Value::Dict(properties), Value::Array(children)] = structure.fields()
+    else {
+        return Err(zvariant::Error::IncorrectType);
+    };
+
+    let mut item = MenuItem {
+        id: *id,
+        ..default_item()
+    };
+
+    let properties = Properties::try_from(properties.clone())?;
+    for (name, value) in &properties {
+        set_property(&mut item, name, Some(value));
+    }
+
+    item.submenu = children
+        .get()
+        .iter()
+        .map(parse_item)
+        .collect::<zvariant::Result<_>>()?;
+
+    Ok(item)
+}
+
+/// Applies an `ItemsPropertiesUpdated` signal to the items,
+/// searching submenus at any depth.
+///
+/// Updates for items which do not exist are ignored,
+/// as the layout may have changed since.
+fn update_properties(
+    items: &mut [MenuItem],
+    updated: &[(i32, Properties)],
+    removed: &[(i32, Vec<String>)],
+) {
+    for (id, properties) in updated {
+        if let Some(item) = find_mut(items, *id) {
+            for (name, value) in properties {
+                set_property(item, name, Some(value));
+            }
+        }
+    }
+
+    for (id, names) in removed {
+        if let Some(item) = find_mut(items, *id) {
+            for name in names {
+                set_property(item, name, None);
+            }
+        }
+    }
+}
+
+fn find_mut(items: &mut [MenuItem], id: i32) -> Option<&mut MenuItem> {
+    for item in items {
+        if item.id == id {
+            return Some(item);
+        }
+
+        if let Some(item) = find_mut(&mut item.submenu, id) {
+            return Some(item);
+        }
+    }
+
+    None
+}
+
+/// An item with every property at its default,
+/// as defined by the DBusMenu spec.
+fn default_item() -> MenuItem {
+    MenuItem {
+        enabled: true,
+        visible: true,
+        toggle_state: ToggleState::from(-1),
+        ..MenuItem::default()
+    }
+}
+
+/// Sets a single property by its DBusMenu name,
+/// or resets it to its default if the value is `None`.
+///
+/// Unknown properties, and values of the wrong type, are ignored.
+fn set_property(item: &mut MenuItem, name: &str, value: Option<&Value>) {
+    let value = value.map(unwrap_variant);
+    let default = default_item();
+
+    match (name, value) {
+        ("type", Some(Value::Str(menu_type))) => {
+            item.menu_type = MenuType::from(menu_type.as_str())
+        }
+        ("type", None) => item.menu_type = default.menu_type,
+        ("label", Some(Value::Str(label))) => item.label = Some(strip_mnemonic(label)),
+        ("label", None) => item.label = default.label,
+        ("enabled", Some(Value::Bool(enabled))) => item.enabled = *enabled,
+        ("enabled", None) => item.enabled = default.enabled,
+        ("visible", Some(Value::Bool(visible))) => item.visible = *visible,
+        ("visible", None) => item.visible = default.visible,
+        ("icon-name", Some(Value::Str(icon_name))) => {
+            item.icon_name = Some(icon_name.to_string()).filter(|name| !name.is_empty());
+        }
+        ("icon-name", None) => item.icon_name = default.icon_name,
+        ("icon-data", Some(Value::Array(data))) => {
+            let data = data
+                .get()
+                .iter()
+                .filter_map(|byte| match byte {
+                    Value::U8(byte) => Some(*byte),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            item.icon_data = Some(data).filter(|data| !data.is_empty());
+        }
+        ("icon-data", None) => item.icon_data = default.icon_data,
+        ("toggle-type", Some(Value::Str(toggle_type))) => {
+            item.toggle_type = ToggleType::from(toggle_type.as_str());
+        }
+        ("toggle-type", None) => item.toggle_type = default.toggle_type,
+        ("toggle-state", Some(Value::I32(state))) => item.toggle_state = ToggleState::from(*state),
+        ("toggle-state", None) => item.toggle_state = default.toggle_state,
+        ("children-display", Some(Value::Str(display))) => {
+            item.children_display = Some(display.to_string());
+        }
+        ("children-display", None) => item.children_display = default.children_display,
+        ("disposition", Some(Value::Str(disposition))) => {
+            item.disposition = Disposition::from(disposition.as_str());
+        }
+        ("disposition", None) => item.disposition = default.disposition,
+        _ => {}
+    }
+}
+
+/// Gets the value inside any number of variants.
+fn unwrap_variant<'a>(value: &'a Value<'a>) -> &'a Value<'a> {
+    match value {
+        Value::Value(value) => unwrap_variant(value),
+        value => value,
+    }
+}
+
+/// Removes the access key markers from a label.
+///
+/// A single underscore marks the following character as the access key,
+/// and two consecutive underscores are written as one.
+fn strip_mnemonic(label: &str) -> String {
+    let mut stripped = String::with_capacity(label.len());
+    let mut chars = label.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '_' => {
+                if let Some(next) = chars.next() {
+                    stripped.push(next);
+                }
+            }
+            char => stripped.push(char),
+        }
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zvariant::{Array, StructureBuilder};
+
+    /// Builds a single item as sent over D-Bus.
+    fn item<'a>(id: i32, properties: &[(&str, Value<'a>)], children: Vec<Value<'a>>) -> Value<'a> {
+        let properties = properties
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::Value(Box::new(value.clone()))))
+            .collect::<HashMap<_, _>>();
+
+        let mut array = Array::new(zvariant::Signature::from_static_str_unchecked("v"));
+        for child in children {
+            array.append(Value::Value(Box::new(child))).unwrap();
+        }
+
+        Value::Value(Box::new(
+            StructureBuilder::new()
+                .add_field(id)
+                .add_field(properties)
+                .append_field(Value::Array(array))
+                .build()
+                .into(),
+        ))
+    }
+
+    /// A fake status notifier item's menu,
+    /// with an example of every kind of item.
+    fn fixture() -> Vec<OwnedValue> {
+        let png = Value::from(vec![0x89_u8, b'P', b'N', b'G']);
+
+        vec![
+            item(
+                1,
+                &[
+                    ("label", "_Open__file".into()),
+                    ("icon-name", "document-open".into()),
+                ],
+                vec![],
+            ),
+            item(2, &[("label", "Record".into()), ("icon-data", png)], vec![]),
+            item(3, &[("type", "separator".into())], vec![]),
+            item(
+                4,
+                &[
+                    ("label", "Mute".into()),
+                    ("toggle-type", "checkmark".into()),
+                    ("toggle-state", 1.into()),
+                ],
+                vec![],
+            ),
+            item(
+                5,
+                &[
+                    ("label", "Low".into()),
+                    ("toggle-type", "radio".into()),
+                    ("toggle-state", 0.into()),
+                ],
+                vec![],
+            ),
+            item(
+                6,
+                &[("label", "Disabled".into()), ("enabled", false.into())],
+                vec![],
+            ),
+            item(
+                7,
+                &[("label", "Hidden".into()), ("visible", false.into())],
+                vec![],
+            ),
+            item(
+                8,
+                &[
+                    ("label", "Scenes".into()),
+                    ("children-display", "submenu".into()),
+                ],
+                vec![item(
+                    9,
+                    &[
+                        ("label", "Scene".into()),
+                        ("children-display", "submenu".into()),
+                    ],
+                    vec![item(10, &[("label", "Nested".into())], vec![])],
+                )],
+            ),
+            item(
+                11,
+                &[
+                    ("label", "Lazy".into()),
+                    ("children-display", "submenu".into()),
+                ],
+                vec![],
+            ),
+        ]
+        .into_iter()
+        .map(OwnedValue::from)
+        .collect()
+    }
+
+    #[test]
+    fn parses_every_item_type() {
+        let items = parse_items(&fixture()).unwrap();
+        assert_eq!(items.len(), 9);
+
+        assert_eq!(items[0].label.as_deref(), Some("Open_file"));
+        assert_eq!(items[0].icon_name.as_deref(), Some("document-open"));
+        assert_eq!(
+            items[1].icon_data.as_deref(),
+            Some(&[0x89, b'P', b'N', b'G'][..])
+        );
+        assert_eq!(items[2].menu_type, MenuType::Separator);
+
+        assert_eq!(items[3].toggle_type, ToggleType::Checkmark);
+        assert_eq!(items[3].toggle_state, ToggleState::On);
+        assert_eq!(items[4].toggle_type, ToggleType::Radio);
+        assert_eq!(items[4].toggle_state, ToggleState::Off);
+        assert_eq!(items[0].toggle_state, ToggleState::Indeterminate);
+
+        assert!(!items[5].enabled);
+        assert!(!items[6].visible);
+        assert!(items[0].enabled && items[0].visible);
+
+        assert_eq!(
+            items[7].submenu[0].submenu[0].label.as_deref(),
+            Some("Nested")
+        );
+        assert!(items[8].submenu.is_empty());
+        assert_eq!(items[8].children_display.as_deref(), Some("submenu"));
+    }
+
+    #[test]
+    fn updates_properties() {
+        let mut items = parse_items(&fixture()).unwrap();
+
+        let updated = vec![
+            (
+                4,
+                Properties::from([("toggle-state".to_string(), OwnedValue::from(0))]),
+            ),
+            (
+                10,
+                Properties::from([(
+                    "label".to_string(),
+                    OwnedValue::from(Value::from("Renamed")),
+                )]),
+            ),
+            (
+                99,
+                Properties::from([(
+                    "label".to_string(),
+                    OwnedValue::from(Value::from("Missing")),
+                )]),
+            ),
+        ];
+        let removed = vec![(6, vec!["enabled".to_string()])];
+
+        update_properties(&mut items, &updated, &removed);
+
+        assert_eq!(items[3].toggle_state, ToggleState::Off);
+        assert_eq!(
+            items[7].submenu[0].submenu[0].label.as_deref(),
+            Some("Renamed")
+        );
+        assert!(items[5].enabled);
+    }
+}
@@ -0,0 +1,362 @@
+mod client;
+mod dbus;
+mod host;
+mod menu;
+
+use crate::{lock, register_fallible_client, send_async, spawn};
+#[cfg(feature = "ipc")]
+use color_eyre::{Report, Result};
+use menu::MenuWatcher;
+#[cfg(feature = "ipc")]
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use system_tray::client::{Event, UpdateEvent};
+#[cfg(feature = "ipc")]
+use system_tray::item::Status;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+pub use client::{Client, TrayMode};
+
+register_fallible_client!(Client, tray);
+
+/// The object path used by items which register with only a service name.
+const DEFAULT_ITEM_PATH: &str = "/StatusNotifierItem";
+
+/// A tray item, as listed over IPC.
+#[cfg(feature = "ipc")]
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemInfo {
+    /// The item's service name and object path,
+    /// which stay the same for as long as the item exists.
+    pub id: String,
+    /// The address the client stores the item under,
+    /// which is its service name.
+    #[serde(skip)]
+    pub address: String,
+    /// The application-provided ID.
+    pub app_id: String,
+    pub title: Option<String>,
+    pub status: &'static str,
+    pub has_menu: bool,
+}
+
+/// Gets every current tray item, sorted by ID.
+#[cfg(feature = "ipc")]
+pub async fn list_items(client: &Client) -> Vec<ItemInfo> {
+    // the client only keeps the service name, so the full address is read from the watcher
+    let registered = match registered_items().await {
+        Ok(registered) => registered,
+        Err(err) => {
+            warn!("Failed to read registered tray items: {err:?}");
+            vec![]
+        }
+    };
+
+    let mut items = lock!(client.items())
+        .iter()
+        .map(|(address, (item, _))| ItemInfo {
+            id: item_id(address, &registered),
+            address: address.clone(),
+            app_id: item.id.clone(),
+            title: item.title.clone(),
+            status: status_name(item.status),
+            has_menu: item.menu.is_some(),
+        })
+        .collect::<Vec<_>>();
+
+    items.sort_by(|a, b| a.id.cmp(&b.id));
+    items
+}
+
+/// Calls the item's `Activate` method,
+/// or `SecondaryActivate` if `secondary` is set.
+///
+/// This is what happens when the icon is left or middle clicked in other trays.
+#[cfg(feature = "ipc")]
+pub async fn activate(id: &str, secondary: bool) -> Result<()> {
+    let (service, path) = id
+        .split_once('/')
+        .ok_or_else(|| Report::msg(format!("Invalid tray item ID '{id}'")))?;
+
+    let connection = zbus::Connection::session().await?;
+    let proxy = dbus::StatusNotifierItemProxy::builder(&connection)
+        .destination(service)?
+        .path(format!("/{path}"))?
+        .build()
+        .await?;
+
+    if secondary {
+        proxy.secondary_activate(0, 0).await?;
+    } else {
+        proxy.activate(0, 0).await?;
+    }
+
+    Ok(())
+}
+
+/// The tray client's state, as shown by `ironbar debug state`.
+#[cfg(feature = "ipc")]
+#[derive(Debug, Clone, Serialize)]
+pub struct DebugState {
+    pub mode: TrayMode,
+    /// The number of items the client has read.
+    pub items: usize,
+    /// The number of items registered with the watcher,
+    /// or `None` if it could not be read.
+    pub registered_items: Option<usize>,
+}
+
+#[cfg(feature = "ipc")]
+pub async fn debug_state(client: &Client) -> DebugState {
+    DebugState {
+        mode: client.mode(),
+        items: lock!(client.items()).len(),
+        registered_items: registered_items().await.ok().map(|items| items.len()),
+    }
+}
+
+#[cfg(feature = "ipc")]
+async fn registered_items() -> Result<Vec<String>> {
+    let connection = zbus::Connection::session().await?;
+    let watcher = dbus::StatusNotifierWatcherProxy::new(&connection).await?;
+
+    Ok(watcher.registered_status_notifier_items().await?)
+}
+
+/// Gets the item's service name and object path,
+/// from the addresses registered with the watcher.
+#[cfg(feature = "ipc")]
+fn item_id(address: &str, registered: &[String]) -> String {
+    registered
+        .iter()
+        .find(|registered| {
+            registered
+                .split_once('/')
+                .map_or(registered.as_str(), |(service, _)| service)
+                == address
+        })
+        .filter(|registered| registered.contains('/'))
+        .cloned()
+        .unwrap_or_else(|| format!("{address}{DEFAULT_ITEM_PATH}"))
+}
+
+#[cfg(feature = "ipc")]
+const fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::Unknown => "unknown",
+        Status::Passive => "passive",
+        Status::Active => "active",
+        Status::NeedsAttention => "needs_attention",
+    }
+}
+
+/// A notice that a tray item's menu, or one of its submenus, is about to be shown.
+#[derive(Debug, Clone)]
+pub struct AboutToShow {
+    /// The address of the item.
+    pub address: String,
+    /// The ID of the menu item whose submenu is opening,
+    /// or `0` for the root menu.
+    pub id: i32,
+}
+
+/// Subscribes to tray events for a single tray module instance.
+///
+/// There is only one tray client (and therefore one `StatusNotifierHost`)
+/// per Ironbar process, shared by every tray module.
+/// Each subscriber first receives an `Add` event for every existing item.
+///
+/// If the subscriber falls behind the shared event stream,
+/// the full current state is re-sent, including removals,
+/// so that every instance stays in sync.
+///
+/// Menus are read by the subscriber rather than the shared client,
+/// to pick up icons and property changes.
+/// Menu opens should be sent on the returned channel,
+/// for items which fill in their menus lazily.
+pub fn subscribe(client: Arc<Client>) -> (mpsc::Receiver<Event>, mpsc::Sender<AboutToShow>) {
+    let (tx, rx) = mpsc::channel(64);
+    let (about_to_show_tx, mut about_to_show_rx) = mpsc::channel(8);
+
+    spawn(async move {
+        let mut tray_rx = client.subscribe();
+        let mut state = Subscriber {
+            tx,
+            known: HashSet::new(),
+            menus: Menus::new().await,
+        };
+
+        state.send_state(&client).await;
+
+        loop {
+            tokio::select! {
+                event = tray_rx.recv() => match event {
+                    Ok(event) => state.send(event).await,
+                    Err(RecvError::Lagged(count)) => {
+                        warn!("Tray subscriber missed {count} events, re-syncing state");
+                        state.send_state(&client).await;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                Some(request) = about_to_show_rx.recv() => state.menus.about_to_show(&request),
+            }
+        }
+
+        debug!("Tray event stream closed");
+    });
+
+    (rx, about_to_show_tx)
+}
+
+/// The state of a single subscription.
+struct Subscriber {
+    tx: mpsc::Sender<Event>,
+    /// The address of every item sent to the subscriber.
+    known: HashSet<String>,
+    menus: Menus,
+}
+
+impl Subscriber {
+    /// Sends the event, replacing menu updates from the shared client
+    /// with those from the subscriber's own watchers.
+    async fn send(&mut self, event: Event) {
+        if let Event::Add(address, item) = &event {
+            let address = address.clone();
+            let path = item.menu.clone();
+
+            self.known.insert(address.clone());
+            send_async!(self.tx, event);
+
+            // after the item, as the menu is attached to it
+            self.menus.watch(&address, path.as_deref(), &self.tx);
+            return;
+        }
+
+        match &event {
+            Event::Remove(address) => {
+                self.known.remove(address);
+                self.menus.remove(address);
+            }
+            Event::Update(_, UpdateEvent::Menu(_)) if self.menus.is_watching() => return,
+            Event::Add(..) | Event::Update(..) => {}
+        }
+
+        send_async!(self.tx, event);
+    }
+
+    /// Sends the current state of every tray item,
+    /// and a removal for each previously known item which no longer exists.
+    async fn send_state(&mut self, client: &Client) {
+        let items = lock!(client.items()).clone();
+
+        let removed = self
+            .known
+            .iter()
+            .filter(|address| !items.contains_key(*address))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for address in removed {
+            self.send(Event::Remove(address)).await;
+        }
+
+        for (address, (item, menu)) in items {
+            self.send(Event::Add(address.clone(), item.into())).await;
+
+            if let Some(menu) = menu {
+                self.send(Event::Update(address, UpdateEvent::Menu(menu)))
+                    .await;
+            }
+        }
+    }
+}
+
+/// The menu watchers for a single subscription, by item address.
+struct Menus {
+    /// The session bus connection used to read menus,
+    /// or `None` if it could not be opened,
+    /// in which case menus from the shared client are used.
+    connection: Option<zbus::Connection>,
+    watchers: HashMap<String, MenuWatcher>,
+}
+
+impl Menus {
+    async fn new() -> Self {
+        let connection = match zbus::Connection::session().await {
+            Ok(connection) => Some(connection),
+            Err(err) => {
+                warn!("Failed to connect to session bus for tray menus: {err:?}");
+                None
+            }
+        };
+
+        Self {
+            connection,
+            watchers: HashMap::new(),
+        }
+    }
+
+    const fn is_watching(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Starts watching the item's menu, if it has one.
+    ///
+    /// If the same menu is already watched,
+    /// as when re-syncing, it is sent again instead.
+    fn watch(&mut self, address: &str, path: Option<&str>, tx: &mpsc::Sender<Event>) {
+        let Some(connection) = &self.connection else {
+            return;
+        };
+
+        match (self.watchers.get(address), path) {
+            (Some(watcher), Some(path)) if watcher.path() == path => watcher.resend(),
+            (_, Some(path)) => {
+                let watcher = MenuWatcher::new(
+                    connection.clone(),
+                    address.to_string(),
+                    path.to_string(),
+                    tx.clone(),
+                );
+                self.watchers.insert(address.to_string(), watcher);
+            }
+            (_, None) => self.remove(address),
+        }
+    }
+
+    fn remove(&mut self, address: &str) {
+        self.watchers.remove(address);
+    }
+
+    fn about_to_show(&self, request: &AboutToShow) {
+        if let Some(watcher) = self.watchers.get(&request.address) {
+            watcher.about_to_show(request.id);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ipc"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gets_item_ids() {
+        let registered = [
+            String::from(":1.52/org/ayatana/NotificationItem/nextcloud"),
+            String::from("org.kde.StatusNotifierItem-1234-1"),
+        ];
+
+        assert_eq!(
+            item_id(":1.52", &registered),
+            ":1.52/org/ayatana/NotificationItem/nextcloud"
+        );
+        assert_eq!(
+            item_id("org.kde.StatusNotifierItem-1234-1", &registered),
+            "org.kde.StatusNotifierItem-1234-1/StatusNotifierItem"
+        );
+        assert_eq!(item_id(":1.5", &registered), ":1.5/StatusNotifierItem");
+    }
+}
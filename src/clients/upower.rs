@@ -1,6 +1,7 @@
 use crate::register_client;
+use color_eyre::{Report, Result};
 use std::sync::Arc;
-use upower_dbus::UPowerProxy;
+use upower_dbus::{BatteryType, DeviceProxy, UPowerProxy};
 use zbus::fdo::PropertiesProxy;
 
 pub async fn create_display_proxy() -> Arc<PropertiesProxy<'static>> {
@@ -32,4 +33,71 @@ pub async fn create_display_proxy() -> Arc<PropertiesProxy<'static>> {
     Arc::new(proxy)
 }
 
+/// Creates a properties proxy for the first UPower device
+/// matching the provided type and model.
+///
+/// The model is matched case-insensitively against any part of the device model.
+pub async fn create_device_proxy(
+    device_type: Option<BatteryType>,
+    model: Option<&str>,
+) -> Result<PropertiesProxy<'static>> {
+    let dbus = Box::pin(zbus::Connection::system()).await?;
+    let upower = UPowerProxy::new(&dbus).await?;
+
+    let model = model.map(str::to_lowercase);
+
+    for path in upower.enumerate_devices().await? {
+        let device = DeviceProxy::builder(&dbus)
+            .path(path.clone())?
+            .build()
+            .await?;
+
+        if let Some(device_type) = device_type {
+            if device.type_().await? != device_type {
+                continue;
+            }
+        }
+
+        if let Some(model) = &model {
+            if !device.model().await?.to_lowercase().contains(model) {
+                continue;
+            }
+        }
+
+        let proxy = PropertiesProxy::builder(&dbus)
+            .destination("org.freedesktop.UPower")?
+            .path(path)?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?;
+
+        return Ok(proxy);
+    }
+
+    Err(Report::msg(
+        "No UPower device matches the configured type and model",
+    ))
+}
+
+/// Creates proxies for each battery powering the system,
+/// in the order UPower lists them.
+///
+/// Peripheral batteries, such as those in mice, are excluded.
+pub async fn create_battery_proxies() -> Result<Vec<DeviceProxy<'static>>> {
+    let dbus = Box::pin(zbus::Connection::system()).await?;
+    let upower = UPowerProxy::new(&dbus).await?;
+
+    let mut batteries = vec![];
+
+    for path in upower.enumerate_devices().await? {
+        let device = DeviceProxy::builder(&dbus).path(path)?.build().await?;
+
+        if device.type_().await? == BatteryType::Battery && device.power_supply().await? {
+            batteries.push(device);
+        }
+    }
+
+    Ok(batteries)
+}
+
 register_client!(PropertiesProxy<'static>, upower);
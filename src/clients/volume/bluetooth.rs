@@ -0,0 +1,155 @@
+use super::{ArcMutVec, Event, Sink};
+use crate::clients::bluetooth;
+use crate::{lock, send, spawn};
+use libpulse_binding::proplist::Proplist;
+use std::collections::HashMap;
+use tokio::sync::{broadcast, watch};
+use tracing::debug;
+
+/// Details of a sink which outputs to a Bluetooth device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bluetooth {
+    /// The device address, such as `AA:BB:CC:DD:EE:FF`.
+    pub address: String,
+    /// The codec in use, such as `SBC` or `AAC`, if the server reports it.
+    pub codec: Option<String>,
+    /// The device battery percentage, if BlueZ reports it.
+    pub battery: Option<u8>,
+}
+
+impl Bluetooth {
+    /// Reads the device details from a sink's properties,
+    /// or returns `None` if it is not a Bluetooth sink.
+    pub(super) fn from_proplist(proplist: &Proplist) -> Option<Self> {
+        from_properties(|key| proplist.get_str(key))
+    }
+}
+
+/// Reads the device details from sink properties.
+///
+/// PipeWire sets `api.bluez5.*` properties,
+/// while PulseAudio puts the address in `device.string`.
+fn from_properties(get: impl Fn(&str) -> Option<String>) -> Option<Bluetooth> {
+    let address = get("api.bluez5.address").or_else(|| {
+        get("device.bus")
+            .filter(|bus| bus == "bluetooth")
+            .and_then(|_| get("device.string"))
+    })?;
+
+    if !is_address(&address) {
+        return None;
+    }
+
+    Some(Bluetooth {
+        address: address.to_uppercase(),
+        codec: get("api.bluez5.codec")
+            .or_else(|| get("bluetooth.codec"))
+            .map(|codec| codec.to_uppercase()),
+        battery: None,
+    })
+}
+
+/// Checks whether the string is a device address,
+/// as six colon-separated hex bytes.
+fn is_address(address: &str) -> bool {
+    let bytes = address.split(':').collect::<Vec<_>>();
+    bytes.len() == 6
+        && bytes
+            .iter()
+            .all(|byte| byte.len() == 2 && byte.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Fills in the battery level of each Bluetooth sink's device,
+/// as reported by the shared Bluetooth client.
+#[derive(Debug, Default, Clone)]
+pub(super) struct Batteries {
+    /// The battery level of each device, by address.
+    levels: Option<watch::Receiver<HashMap<String, u8>>>,
+}
+
+impl Batteries {
+    pub fn new(client: Option<&bluetooth::Client>) -> Self {
+        Self {
+            levels: client.map(bluetooth::Client::subscribe_batteries),
+        }
+    }
+
+    /// Fills in the battery level of the sink's device.
+    pub fn track(&self, sink: &mut Sink) {
+        if let (Some(bluetooth), Some(levels)) = (&mut sink.bluetooth, &self.levels) {
+            bluetooth.battery = levels.borrow().get(&bluetooth.address).copied();
+        }
+    }
+
+    /// Updates the sinks whenever a battery level changes.
+    pub fn watch(&self, sinks: ArcMutVec<Sink>, tx: broadcast::Sender<Event>) {
+        let Some(mut levels) = self.levels.clone() else {
+            return;
+        };
+
+        spawn(async move {
+            while levels.changed().await.is_ok() {
+                let levels = levels.borrow_and_update().clone();
+
+                for sink in lock!(sinks).iter_mut() {
+                    let Some(bluetooth) = &mut sink.bluetooth else {
+                        continue;
+                    };
+
+                    let level = levels.get(&bluetooth.address).copied();
+                    if bluetooth.battery != level {
+                        debug!(
+                            "Battery of Bluetooth device {}: {level:?}",
+                            bluetooth.address
+                        );
+
+                        bluetooth.battery = level;
+                        send!(tx, Event::UpdateSink(sink.clone()));
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(properties: &[(&str, &str)]) -> Option<Bluetooth> {
+        let properties = properties.iter().copied().collect::<HashMap<_, _>>();
+        from_properties(|key| properties.get(key).map(ToString::to_string))
+    }
+
+    #[test]
+    fn reads_bluetooth_properties() {
+        let pipewire = read(&[
+            ("api.bluez5.address", "aa:bb:cc:dd:ee:ff"),
+            ("api.bluez5.codec", "aac"),
+            ("device.bus", "bluetooth"),
+        ]);
+
+        assert_eq!(
+            pipewire,
+            Some(Bluetooth {
+                address: String::from("AA:BB:CC:DD:EE:FF"),
+                codec: Some(String::from("AAC")),
+                battery: None,
+            })
+        );
+
+        let pulse = read(&[
+            ("device.bus", "bluetooth"),
+            ("device.string", "AA:BB:CC:DD:EE:FF"),
+            ("bluetooth.codec", "sbc"),
+        ]);
+
+        assert_eq!(
+            pulse.and_then(|bluetooth| bluetooth.codec).as_deref(),
+            Some("SBC")
+        );
+
+        assert!(read(&[("device.bus", "pci"), ("device.string", "front:0")]).is_none());
+        assert!(read(&[("device.bus", "bluetooth"), ("device.string", "hci0")]).is_none());
+    }
+}
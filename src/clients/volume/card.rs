@@ -0,0 +1,376 @@
+use super::{apply_default_sink, ArcMutVec, Client, ConnectionState, Event};
+use crate::{lock, send};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::introspect::{CardInfo, CardPortInfo, CardProfileInfo};
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::context::Context;
+use libpulse_binding::def::PortAvailable;
+use libpulse_binding::direction;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+
+#[derive(Debug, Clone)]
+pub struct Card {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    pub profiles: Vec<CardProfile>,
+    /// The name of the active profile.
+    pub active_profile: Option<String>,
+    /// The card's output ports, such as speakers or HDMI.
+    pub ports: Vec<CardPort>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CardProfile {
+    pub name: String,
+    pub description: String,
+    /// The number of sinks the card has with this profile active.
+    pub sinks: u32,
+    pub priority: u32,
+    pub available: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CardPort {
+    pub name: String,
+    pub description: String,
+    /// Whether the port may be usable,
+    /// ie is not known to be unplugged.
+    pub available: bool,
+    /// The names of the profiles which can use the port.
+    pub profiles: Vec<String>,
+}
+
+impl Card {
+    /// Gets the most preferred profile which can output to the port.
+    pub fn profile_for_port(&self, port: &CardPort) -> Option<&CardProfile> {
+        self.profiles
+            .iter()
+            .filter(|profile| {
+                profile.available && profile.sinks > 0 && port.profiles.contains(&profile.name)
+            })
+            .max_by_key(|profile| profile.priority)
+    }
+
+    /// Gets the profiles which differ from the active one only by codec,
+    /// including the active profile itself.
+    ///
+    /// PipeWire exposes a profile for each Bluetooth codec,
+    /// named for example `a2dp-sink-sbc` and `a2dp-sink-aac`.
+    pub fn codec_profiles(&self) -> Vec<&CardProfile> {
+        let family = |name: &str| name.rsplit_once('-').map(|(family, _)| family.to_string());
+
+        let Some(active) = self.active_profile.as_deref().and_then(family) else {
+            return vec![];
+        };
+
+        self.profiles
+            .iter()
+            .filter(|profile| {
+                profile.available
+                    && profile.sinks > 0
+                    && family(&profile.name).as_deref() == Some(active.as_str())
+            })
+            .collect()
+    }
+}
+
+impl From<&CardProfileInfo<'_>> for CardProfile {
+    fn from(value: &CardProfileInfo) -> Self {
+        Self {
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            sinks: value.n_sinks,
+            priority: value.priority,
+            available: value.available,
+        }
+    }
+}
+
+impl From<&CardPortInfo<'_>> for CardPort {
+    fn from(value: &CardPortInfo) -> Self {
+        Self {
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            available: value.available != PortAvailable::No,
+            profiles: value
+                .profiles
+                .iter()
+                .filter_map(|profile| profile.name.as_ref().map(ToString::to_string))
+                .collect(),
+        }
+    }
+}
+
+impl From<&CardInfo<'_>> for Card {
+    fn from(value: &CardInfo) -> Self {
+        let name = value
+            .name
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+
+        Self {
+            index: value.index,
+            description: value
+                .proplist
+                .get_str("device.description")
+                .unwrap_or_else(|| name.clone()),
+            name,
+            profiles: value.profiles.iter().map(CardProfile::from).collect(),
+            active_profile: value
+                .active_profile
+                .as_ref()
+                .and_then(|profile| profile.name.as_ref())
+                .map(ToString::to_string),
+            ports: value
+                .ports
+                .iter()
+                .filter(|port| port.direction.contains(direction::FlagSet::OUTPUT))
+                .map(CardPort::from)
+                .collect(),
+        }
+    }
+}
+
+impl Client {
+    pub fn cards(&self) -> Arc<Mutex<Vec<Card>>> {
+        self.data.cards.clone()
+    }
+
+    pub fn set_card_profile(&self, card: u32, profile: &str) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_card_profile_by_index(card, profile, None);
+        }
+    }
+
+    /// Switches the card to the profile,
+    /// then sets the first sink it creates as the default.
+    pub fn set_default_sink_with_profile(&self, card: u32, profile: &str, move_streams: bool) {
+        if let ConnectionState::Connected {
+            context,
+            introspector,
+        } = &mut *lock!(self.connection)
+        {
+            let context = context.clone();
+            let sink_inputs = self.data.sink_inputs.clone();
+            let profile_name = profile.to_string();
+
+            introspector.set_card_profile_by_index(
+                card,
+                profile,
+                Some(Box::new(move |success| {
+                    if !success {
+                        error!("Failed to set card {card} to profile '{profile_name}'");
+                        return;
+                    }
+
+                    let context = context.clone();
+                    let sink_inputs = sink_inputs.clone();
+                    let mut found = false;
+
+                    let introspect = lock!(context).introspect();
+                    introspect.get_sink_info_list(move |info| {
+                        let ListResult::Item(info) = info else {
+                            return;
+                        };
+
+                        if found || info.card != Some(card) {
+                            return;
+                        }
+
+                        if let Some(name) = &info.name {
+                            found = true;
+                            apply_default_sink(&context, &sink_inputs, name, move_streams);
+                        }
+                    });
+                })),
+            );
+        }
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    cards: &ArcMutVec<Card>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new card");
+            introspect.get_card_info_by_index(i, {
+                let cards = cards.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &cards, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("card changed");
+            introspect.get_card_info_by_index(i, {
+                let cards = cards.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &cards, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("card removed");
+            remove(i, cards, tx);
+        }
+    }
+}
+
+pub fn add(info: ListResult<&CardInfo>, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    lock!(cards).push(info.into());
+    send!(tx, Event::AddCard(info.into()));
+}
+
+fn update(info: ListResult<&CardInfo>, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    {
+        let mut cards = lock!(cards);
+        let Some(pos) = cards.iter().position(|card| card.index == info.index) else {
+            error!("received update to untracked card");
+            return;
+        };
+
+        cards[pos] = info.into();
+    }
+
+    send!(tx, Event::UpdateCard(info.into()));
+}
+
+fn remove(index: u32, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    let mut cards = lock!(cards);
+
+    if let Some(pos) = cards.iter().position(|card| card.index == index) {
+        let card = cards.remove(pos);
+        send!(tx, Event::RemoveCard(card.index));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, sinks: u32, priority: u32, available: bool) -> CardProfile {
+        CardProfile {
+            name: name.to_string(),
+            description: name.to_string(),
+            sinks,
+            priority,
+            available,
+        }
+    }
+
+    #[test]
+    fn profile_for_port_picks_best_usable_profile() {
+        let card = Card {
+            index: 0,
+            name: String::from("alsa_card.pci"),
+            description: String::from("Built-in Audio"),
+            profiles: vec![
+                profile("off", 0, 0, true),
+                profile("output:analog-stereo", 1, 6500, true),
+                profile("output:analog-stereo+input:analog-stereo", 1, 6565, true),
+                profile("output:analog-surround-71", 1, 7000, false),
+                profile("input:analog-stereo", 0, 65, true),
+            ],
+            active_profile: Some(String::from("output:hdmi-stereo")),
+            ports: vec![],
+        };
+
+        let port = CardPort {
+            name: String::from("analog-output-speaker"),
+            description: String::from("Speakers"),
+            available: true,
+            profiles: vec![
+                String::from("output:analog-stereo"),
+                String::from("output:analog-stereo+input:analog-stereo"),
+                String::from("output:analog-surround-71"),
+                String::from("input:analog-stereo"),
+            ],
+        };
+
+        assert_eq!(
+            card.profile_for_port(&port)
+                .map(|profile| profile.name.as_str()),
+            Some("output:analog-stereo+input:analog-stereo")
+        );
+
+        let port = CardPort {
+            profiles: vec![String::from("input:analog-stereo")],
+            ..port
+        };
+
+        assert!(card.profile_for_port(&port).is_none());
+    }
+
+    #[test]
+    fn codec_profiles_share_active_family() {
+        let card = Card {
+            index: 0,
+            name: String::from("bluez_card.AA_BB_CC_DD_EE_FF"),
+            description: String::from("Headphones"),
+            profiles: vec![
+                profile("off", 0, 0, true),
+                profile("a2dp-sink-sbc", 1, 18, true),
+                profile("a2dp-sink-sbc_xq", 1, 19, true),
+                profile("a2dp-sink-aac", 1, 20, true),
+                profile("a2dp-sink-ldac", 1, 21, false),
+                profile("headset-head-unit-msbc", 1, 3, true),
+            ],
+            active_profile: Some(String::from("a2dp-sink-aac")),
+            ports: vec![],
+        };
+
+        let names = |card: &Card| {
+            card.codec_profiles()
+                .into_iter()
+                .map(|profile| profile.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&card),
+            ["a2dp-sink-sbc", "a2dp-sink-sbc_xq", "a2dp-sink-aac"]
+        );
+
+        let card = Card {
+            active_profile: Some(String::from("off")),
+            ..card
+        };
+
+        assert!(names(&card).is_empty());
+    }
+}
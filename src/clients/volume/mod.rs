@@ -1,6 +1,11 @@
+mod bluetooth;
+mod card;
 mod sink;
 mod sink_input;
+mod source;
+mod source_output;
 
+use crate::clients::bluetooth::Client as BluetoothClient;
 use crate::{arc_mut, lock, register_client, send, spawn_blocking, APP_ID};
 use libpulse_binding::callbacks::ListResult;
 use libpulse_binding::context::introspect::{Introspector, ServerInfo};
@@ -14,8 +19,12 @@ use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 use tracing::{debug, error, info, warn};
 
+pub use bluetooth::Bluetooth;
+pub use card::{Card, CardProfile};
 pub use sink::Sink;
 pub use sink_input::SinkInput;
+pub use source::Source;
+pub use source_output::SourceOutput;
 
 type ArcMutVec<T> = Arc<Mutex<Vec<T>>>;
 
@@ -28,6 +37,18 @@ pub enum Event {
     AddInput(SinkInput),
     UpdateInput(SinkInput),
     RemoveInput(u32),
+
+    AddSource(Source),
+    UpdateSource(Source),
+    RemoveSource(String),
+
+    AddOutput(SourceOutput),
+    UpdateOutput(SourceOutput),
+    RemoveOutput(u32),
+
+    AddCard(Card),
+    UpdateCard(Card),
+    RemoveCard(u32),
 }
 
 #[derive(Debug)]
@@ -44,8 +65,14 @@ pub struct Client {
 struct Data {
     sinks: ArcMutVec<Sink>,
     sink_inputs: ArcMutVec<SinkInput>,
+    sources: ArcMutVec<Source>,
+    source_outputs: ArcMutVec<SourceOutput>,
+    cards: ArcMutVec<Card>,
 
     default_sink_name: Arc<Mutex<Option<String>>>,
+    default_source_name: Arc<Mutex<Option<String>>>,
+
+    batteries: bluetooth::Batteries,
 }
 
 pub enum ConnectionState {
@@ -70,12 +97,21 @@ impl Debug for ConnectionState {
 }
 
 impl Client {
-    pub fn new() -> Self {
+    /// Creates the client, using the Bluetooth client
+    /// for the battery level of Bluetooth sinks if there is one.
+    pub fn new(bluetooth: Option<&BluetoothClient>) -> Self {
         let (tx, rx) = broadcast::channel(32);
 
+        let data = Data {
+            batteries: bluetooth::Batteries::new(bluetooth),
+            ..Data::default()
+        };
+
+        data.batteries.watch(data.sinks.clone(), tx.clone());
+
         Self {
             connection: arc_mut!(ConnectionState::Disconnected),
-            data: Data::default(),
+            data,
             tx,
             _rx: rx,
         }
@@ -144,9 +180,118 @@ impl Client {
     }
 }
 
+/// A client for the audio server's devices and streams.
+///
+/// This is implemented by the Pulse client,
+/// and allows it to be swapped for a fake in tests.
+pub trait VolumeClient: Debug + Send + Sync {
+    /// Gets an event receiver.
+    fn subscribe(&self) -> broadcast::Receiver<Event>;
+
+    fn sinks(&self) -> ArcMutVec<Sink>;
+    /// Sets the default sink.
+    /// If `move_streams` is set, existing streams are moved to it.
+    fn set_default_sink(&self, name: &str, move_streams: bool);
+    fn set_sink_volume(&self, name: &str, volume_percent: f64);
+    fn set_sink_muted(&self, name: &str, muted: bool);
+
+    fn sink_inputs(&self) -> ArcMutVec<SinkInput>;
+    fn set_input_volume(&self, index: u32, volume_percent: f64);
+    fn set_input_muted(&self, index: u32, muted: bool);
+
+    fn sources(&self) -> ArcMutVec<Source>;
+    fn set_default_source(&self, name: &str);
+    fn set_source_volume(&self, name: &str, volume_percent: f64);
+    fn set_source_muted(&self, name: &str, muted: bool);
+
+    fn source_outputs(&self) -> ArcMutVec<SourceOutput>;
+    fn set_output_volume(&self, index: u32, volume_percent: f64);
+    fn set_output_muted(&self, index: u32, muted: bool);
+
+    fn cards(&self) -> ArcMutVec<Card>;
+    fn set_card_profile(&self, card: u32, profile: &str);
+    /// Switches the card to the profile,
+    /// then sets the first sink it creates as the default.
+    fn set_default_sink_with_profile(&self, card: u32, profile: &str, move_streams: bool);
+}
+
+impl VolumeClient for Client {
+    fn subscribe(&self) -> broadcast::Receiver<Event> {
+        Self::subscribe(self)
+    }
+
+    fn sinks(&self) -> ArcMutVec<Sink> {
+        Self::sinks(self)
+    }
+
+    fn set_default_sink(&self, name: &str, move_streams: bool) {
+        Self::set_default_sink(self, name, move_streams);
+    }
+
+    fn set_sink_volume(&self, name: &str, volume_percent: f64) {
+        Self::set_sink_volume(self, name, volume_percent);
+    }
+
+    fn set_sink_muted(&self, name: &str, muted: bool) {
+        Self::set_sink_muted(self, name, muted);
+    }
+
+    fn sink_inputs(&self) -> ArcMutVec<SinkInput> {
+        Self::sink_inputs(self)
+    }
+
+    fn set_input_volume(&self, index: u32, volume_percent: f64) {
+        Self::set_input_volume(self, index, volume_percent);
+    }
+
+    fn set_input_muted(&self, index: u32, muted: bool) {
+        Self::set_input_muted(self, index, muted);
+    }
+
+    fn sources(&self) -> ArcMutVec<Source> {
+        Self::sources(self)
+    }
+
+    fn set_default_source(&self, name: &str) {
+        Self::set_default_source(self, name);
+    }
+
+    fn set_source_volume(&self, name: &str, volume_percent: f64) {
+        Self::set_source_volume(self, name, volume_percent);
+    }
+
+    fn set_source_muted(&self, name: &str, muted: bool) {
+        Self::set_source_muted(self, name, muted);
+    }
+
+    fn source_outputs(&self) -> ArcMutVec<SourceOutput> {
+        Self::source_outputs(self)
+    }
+
+    fn set_output_volume(&self, index: u32, volume_percent: f64) {
+        Self::set_output_volume(self, index, volume_percent);
+    }
+
+    fn set_output_muted(&self, index: u32, muted: bool) {
+        Self::set_output_muted(self, index, muted);
+    }
+
+    fn cards(&self) -> ArcMutVec<Card> {
+        Self::cards(self)
+    }
+
+    fn set_card_profile(&self, card: u32, profile: &str) {
+        Self::set_card_profile(self, card, profile);
+    }
+
+    fn set_default_sink_with_profile(&self, card: u32, profile: &str, move_streams: bool) {
+        Self::set_default_sink_with_profile(self, card, profile, move_streams);
+    }
+}
+
 /// Creates a new Pulse volume client.
-pub fn create_client() -> Arc<Client> {
-    let client = Arc::new(Client::new());
+pub fn create_client(bluetooth: Option<Arc<BluetoothClient>>) -> Arc<dyn VolumeClient> {
+    let client = Arc::new(Client::new(bluetooth.as_deref()));
 
     {
         let client = client.clone();
@@ -171,26 +316,39 @@ fn on_state_change(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::S
             let introspect2 = lock!(context).introspect();
 
             introspect.get_sink_info_list({
-                let sinks = data.sinks.clone();
-                let default_sink = data.default_sink_name.clone();
-
+                let data = data.clone();
                 let tx = tx.clone();
 
                 move |info| match info {
-                    ListResult::Item(_) => sink::add(info, &sinks, &tx),
+                    ListResult::Item(_) => sink::add(info, &data.sinks, &data.batteries, &tx),
                     ListResult::End => {
                         introspect2.get_server_info({
-                            let sinks = sinks.clone();
-                            let default_sink = default_sink.clone();
+                            let data = data.clone();
                             let tx = tx.clone();
 
-                            move |info| set_default_sink(info, &sinks, &default_sink, &tx)
+                            move |info| set_defaults(info, &data, &tx)
                         });
                     }
                     ListResult::Error => error!("Error while receiving sinks"),
                 }
             });
 
+            // the default source is set from the server info
+            // requested once the sinks are received
+            introspect.get_source_info_list({
+                let sources = data.sources.clone();
+                let tx = tx.clone();
+
+                move |info| source::add(info, &sources, &tx)
+            });
+
+            introspect.get_source_output_info_list({
+                let outputs = data.source_outputs.clone();
+                let tx = tx.clone();
+
+                move |info| source_output::add(info, &outputs, &tx)
+            });
+
             introspect.get_sink_input_info_list({
                 let inputs = data.sink_inputs.clone();
                 let tx = tx.clone();
@@ -198,6 +356,13 @@ fn on_state_change(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::S
                 move |info| sink_input::add(info, &inputs, &tx)
             });
 
+            introspect.get_card_info_list({
+                let cards = data.cards.clone();
+                let tx = tx.clone();
+
+                move |info| card::add(info, &cards, &tx)
+            });
+
             let subscribe_callback = Box::new({
                 let context = context.clone();
                 let data = data.clone();
@@ -208,7 +373,12 @@ fn on_state_change(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::S
 
             lock!(context).set_subscribe_callback(Some(subscribe_callback));
             lock!(context).subscribe(
-                InterestMaskSet::SERVER | InterestMaskSet::SINK_INPUT | InterestMaskSet::SINK,
+                InterestMaskSet::SERVER
+                    | InterestMaskSet::SINK_INPUT
+                    | InterestMaskSet::SINK
+                    | InterestMaskSet::SOURCE_OUTPUT
+                    | InterestMaskSet::SOURCE
+                    | InterestMaskSet::CARD,
                 |_| (),
             );
         }
@@ -231,52 +401,103 @@ fn on_event(
     };
 
     match facility {
-        Facility::Server => on_server_event(context, &data.sinks, &data.default_sink_name, tx),
-        Facility::Sink => sink::on_event(context, &data.sinks, &data.default_sink_name, tx, op, i),
+        Facility::Server => on_server_event(context, data, tx),
+        Facility::Sink => sink::on_event(
+            context,
+            &data.sinks,
+            &data.default_sink_name,
+            &data.batteries,
+            tx,
+            op,
+            i,
+        ),
         Facility::SinkInput => sink_input::on_event(context, &data.sink_inputs, tx, op, i),
+        Facility::Source => {
+            source::on_event(context, &data.sources, &data.default_source_name, tx, op, i);
+        }
+        Facility::SourceOutput => {
+            source_output::on_event(context, &data.source_outputs, tx, op, i);
+        }
+        Facility::Card => card::on_event(context, &data.cards, tx, op, i),
         _ => error!("Received unhandled facility: {facility:?}"),
     }
 }
 
-fn on_server_event(
-    context: &Arc<Mutex<Context>>,
-    sinks: &ArcMutVec<Sink>,
-    default_sink: &Arc<Mutex<Option<String>>>,
-    tx: &broadcast::Sender<Event>,
-) {
+fn on_server_event(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::Sender<Event>) {
     lock!(context).introspect().get_server_info({
-        let sinks = sinks.clone();
-        let default_sink = default_sink.clone();
+        let data = data.clone();
         let tx = tx.clone();
 
-        move |info| set_default_sink(info, &sinks, &default_sink, &tx)
+        move |info| set_defaults(info, &data, &tx)
     });
 }
 
-fn set_default_sink(
-    info: &ServerInfo,
-    sinks: &ArcMutVec<Sink>,
-    default_sink: &Arc<Mutex<Option<String>>>,
-    tx: &broadcast::Sender<Event>,
+/// Marks the server's default sink and source as active.
+fn set_defaults(info: &ServerInfo, data: &Data, tx: &broadcast::Sender<Event>) {
+    set_default(
+        info.default_sink_name.as_deref(),
+        &data.sinks,
+        &data.default_sink_name,
+        |sink| {
+            send!(tx, Event::UpdateSink(sink));
+        },
+    );
+
+    set_default(
+        info.default_source_name.as_deref(),
+        &data.sources,
+        &data.default_source_name,
+        |source| {
+            send!(tx, Event::UpdateSource(source));
+        },
+    );
+}
+
+fn set_default(
+    name: Option<&str>,
+    devices: &ArcMutVec<Sink>,
+    default: &Arc<Mutex<Option<String>>>,
+    send: impl FnOnce(Sink),
 ) {
-    let default_sink_name = info.default_sink_name.as_ref().map(ToString::to_string);
+    let name = name.map(ToString::to_string);
 
-    if default_sink_name != *lock!(default_sink) {
-        if let Some(ref default_sink_name) = default_sink_name {
-            if let Some(sink) = lock!(sinks)
+    if name != *lock!(default) {
+        if let Some(ref name) = name {
+            if let Some(device) = lock!(devices)
                 .iter_mut()
-                .find(|s| s.name.as_str() == default_sink_name.as_str())
+                .find(|device| device.name.as_str() == name.as_str())
             {
-                sink.active = true;
-                debug!("Set sink active: {}", sink.name);
-                send!(tx, Event::UpdateSink(sink.clone()));
+                device.active = true;
+                debug!("Set device active: {}", device.name);
+                send(device.clone());
             } else {
-                warn!("Couldn't find sink: {}", default_sink_name);
+                warn!("Couldn't find device: {}", name);
             }
         }
     }
 
-    *lock!(default_sink) = default_sink_name;
+    *lock!(default) = name;
+}
+
+/// Sets the default sink on the server,
+/// and optionally moves every existing stream to it.
+fn apply_default_sink(
+    context: &Mutex<Context>,
+    sink_inputs: &ArcMutVec<SinkInput>,
+    name: &str,
+    move_streams: bool,
+) {
+    let mut context = lock!(context);
+    context.set_default_sink(name, |_| {});
+
+    if move_streams {
+        let mut introspect = context.introspect();
+
+        for input in lock!(sink_inputs).iter() {
+            debug!("Moving sink input {} to {name}", input.index);
+            introspect.move_sink_input_by_name(input.index, name, None);
+        }
+    }
 }
 
 /// Converts a Pulse `ChannelVolumes` struct into a single percentage value,
@@ -306,4 +527,4 @@ pub fn percent_to_volume(target_percent: f64) -> u32 {
     }
 }
 
-register_client!(Client, volume);
+register_client!(dyn VolumeClient, volume);
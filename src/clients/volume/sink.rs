@@ -1,4 +1,8 @@
-use super::{percent_to_volume, volume_to_percent, ArcMutVec, Client, ConnectionState, Event};
+use super::bluetooth::{Batteries, Bluetooth};
+use super::{
+    apply_default_sink, percent_to_volume, volume_to_percent, ArcMutVec, Client, ConnectionState,
+    Event,
+};
 use crate::{lock, send};
 use libpulse_binding::callbacks::ListResult;
 use libpulse_binding::context::introspect::SinkInfo;
@@ -11,12 +15,18 @@ use tracing::{debug, error};
 
 #[derive(Debug, Clone)]
 pub struct Sink {
-    index: u32,
+    pub(super) index: u32,
     pub name: String,
     pub description: String,
     pub volume: f64,
     pub muted: bool,
     pub active: bool,
+    /// The index of the card the sink belongs to, if any.
+    pub card: Option<u32>,
+    /// The names of the card ports the sink can output to.
+    pub ports: Vec<String>,
+    /// The Bluetooth device the sink outputs to, if any.
+    pub bluetooth: Option<Bluetooth>,
 }
 
 impl From<&SinkInfo<'_>> for Sink {
@@ -36,6 +46,13 @@ impl From<&SinkInfo<'_>> for Sink {
             muted: value.mute,
             volume: volume_to_percent(value.volume),
             active: value.state == SinkState::Running,
+            card: value.card,
+            ports: value
+                .ports
+                .iter()
+                .filter_map(|port| port.name.as_ref().map(ToString::to_string))
+                .collect(),
+            bluetooth: Bluetooth::from_proplist(&value.proplist),
         }
     }
 }
@@ -45,9 +62,11 @@ impl Client {
         self.data.sinks.clone()
     }
 
-    pub fn set_default_sink(&self, name: &str) {
+    /// Sets the default sink.
+    /// If `move_streams` is set, existing streams are moved to it.
+    pub fn set_default_sink(&self, name: &str, move_streams: bool) {
         if let ConnectionState::Connected { context, .. } = &*lock!(self.connection) {
-            lock!(context).set_default_sink(name, |_| {});
+            apply_default_sink(context, &self.data.sink_inputs, name, move_streams);
         }
     }
 
@@ -84,6 +103,7 @@ pub fn on_event(
     context: &Arc<Mutex<Context>>,
     sinks: &ArcMutVec<Sink>,
     default_sink: &Arc<Mutex<Option<String>>>,
+    batteries: &Batteries,
     tx: &broadcast::Sender<Event>,
     op: Operation,
     i: u32,
@@ -95,9 +115,10 @@ pub fn on_event(
             debug!("new sink");
             introspect.get_sink_info_by_index(i, {
                 let sinks = sinks.clone();
+                let batteries = batteries.clone();
                 let tx = tx.clone();
 
-                move |info| add(info, &sinks, &tx)
+                move |info| add(info, &sinks, &batteries, &tx)
             });
         }
         Operation::Changed => {
@@ -105,9 +126,10 @@ pub fn on_event(
             introspect.get_sink_info_by_index(i, {
                 let sinks = sinks.clone();
                 let default_sink = default_sink.clone();
+                let batteries = batteries.clone();
                 let tx = tx.clone();
 
-                move |info| update(info, &sinks, &default_sink, &tx)
+                move |info| update(info, &sinks, &default_sink, &batteries, &tx)
             });
         }
         Operation::Removed => {
@@ -117,25 +139,37 @@ pub fn on_event(
     }
 }
 
-pub fn add(info: ListResult<&SinkInfo>, sinks: &ArcMutVec<Sink>, tx: &broadcast::Sender<Event>) {
+pub fn add(
+    info: ListResult<&SinkInfo>,
+    sinks: &ArcMutVec<Sink>,
+    batteries: &Batteries,
+    tx: &broadcast::Sender<Event>,
+) {
     let ListResult::Item(info) = info else {
         return;
     };
 
-    lock!(sinks).push(info.into());
-    send!(tx, Event::AddSink(info.into()));
+    let mut sink: Sink = info.into();
+    batteries.track(&mut sink);
+
+    lock!(sinks).push(sink.clone());
+    send!(tx, Event::AddSink(sink));
 }
 
 fn update(
     info: ListResult<&SinkInfo>,
     sinks: &ArcMutVec<Sink>,
     default_sink: &Arc<Mutex<Option<String>>>,
+    batteries: &Batteries,
     tx: &broadcast::Sender<Event>,
 ) {
     let ListResult::Item(info) = info else {
         return;
     };
 
+    let mut sink: Sink = info.into();
+    batteries.track(&mut sink);
+
     {
         let mut sinks = lock!(sinks);
         let Some(pos) = sinks.iter().position(|sink| sink.index == info.index) else {
@@ -143,7 +177,7 @@ fn update(
             return;
         };
 
-        sinks[pos] = info.into();
+        sinks[pos] = sink.clone();
 
         // update in local copy
         if !sinks[pos].active {
@@ -153,8 +187,6 @@ fn update(
         }
     }
 
-    let mut sink: Sink = info.into();
-
     // update in broadcast copy
     if !sink.active {
         if let Some(default_sink) = &*lock!(default_sink) {
@@ -4,6 +4,7 @@ use libpulse_binding::callbacks::ListResult;
 use libpulse_binding::context::introspect::SinkInputInfo;
 use libpulse_binding::context::subscribe::Operation;
 use libpulse_binding::context::Context;
+use libpulse_binding::proplist::Proplist;
 use std::sync::{mpsc, Arc, Mutex};
 use tokio::sync::broadcast;
 use tracing::{debug, error};
@@ -14,6 +15,14 @@ pub struct SinkInput {
     pub name: String,
     pub volume: f64,
     pub muted: bool,
+    /// Whether the stream is paused.
+    pub corked: bool,
+
+    /// The name of the application playing the stream.
+    pub app_name: Option<String>,
+    /// The names to look the application's icon up by,
+    /// in order of preference.
+    pub app_icons: Vec<String>,
 
     pub can_set_volume: bool,
 }
@@ -28,12 +37,30 @@ impl From<&SinkInputInfo<'_>> for SinkInput {
                 .map(ToString::to_string)
                 .unwrap_or_default(),
             muted: value.mute,
+            corked: value.corked,
+            app_name: value.proplist.get_str("application.name"),
+            app_icons: app_icons(&value.proplist),
             volume: volume_to_percent(value.volume),
             can_set_volume: value.has_volume && value.volume_writable,
         }
     }
 }
 
+/// Gets the names to look the icon up by
+/// of the application which owns a stream.
+pub(super) fn app_icons(proplist: &Proplist) -> Vec<String> {
+    [
+        "application.icon_name",
+        "application.id",
+        "application.process.binary",
+        "application.name",
+    ]
+    .into_iter()
+    .filter_map(|key| proplist.get_str(key))
+    .filter(|name| !name.is_empty())
+    .collect()
+}
+
 impl Client {
     pub fn sink_inputs(&self) -> Arc<Mutex<Vec<SinkInput>>> {
         self.data.sink_inputs.clone()
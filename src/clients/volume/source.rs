@@ -0,0 +1,187 @@
+use super::{
+    percent_to_volume, volume_to_percent, ArcMutVec, Client, ConnectionState, Event, Sink,
+};
+use crate::{lock, send};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::introspect::SourceInfo;
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::context::Context;
+use libpulse_binding::def::SourceState;
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+
+/// An input device, such as a microphone.
+///
+/// This has the same properties as an output device,
+/// with `ports` listing the card ports it can capture from.
+pub type Source = Sink;
+
+impl From<&SourceInfo<'_>> for Source {
+    fn from(value: &SourceInfo) -> Self {
+        Self {
+            index: value.index,
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            muted: value.mute,
+            volume: volume_to_percent(value.volume),
+            active: value.state == SourceState::Running,
+            card: value.card,
+            ports: value
+                .ports
+                .iter()
+                .filter_map(|port| port.name.as_ref().map(ToString::to_string))
+                .collect(),
+            bluetooth: None,
+        }
+    }
+}
+
+impl Client {
+    pub fn sources(&self) -> Arc<Mutex<Vec<Source>>> {
+        self.data.sources.clone()
+    }
+
+    pub fn set_default_source(&self, name: &str) {
+        if let ConnectionState::Connected { context, .. } = &*lock!(self.connection) {
+            lock!(context).set_default_source(name, |_| {});
+        }
+    }
+
+    pub fn set_source_volume(&self, name: &str, volume_percent: f64) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let (tx, rx) = mpsc::channel();
+
+            introspector.get_source_info_by_name(name, move |info| {
+                let ListResult::Item(info) = info else {
+                    return;
+                };
+                send!(tx, info.volume);
+            });
+
+            let new_volume = percent_to_volume(volume_percent);
+
+            let mut volume = rx.recv().expect("to receive info");
+            for v in volume.get_mut() {
+                v.0 = new_volume;
+            }
+
+            introspector.set_source_volume_by_name(name, &volume, None);
+        }
+    }
+
+    pub fn set_source_muted(&self, name: &str, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_source_mute_by_name(name, muted, None);
+        }
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new source");
+            introspect.get_source_info_by_index(i, {
+                let sources = sources.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &sources, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("source changed");
+            introspect.get_source_info_by_index(i, {
+                let sources = sources.clone();
+                let default_source = default_source.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &sources, &default_source, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("source removed");
+            remove(i, sources, tx);
+        }
+    }
+}
+
+/// Adds the source, unless it is the monitor of a sink.
+///
+/// Monitors are not tracked, as they are not capture devices.
+pub fn add(
+    info: ListResult<&SourceInfo>,
+    sources: &ArcMutVec<Source>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    if info.monitor_of_sink.is_some() {
+        return;
+    }
+
+    lock!(sources).push(info.into());
+    send!(tx, Event::AddSource(info.into()));
+}
+
+fn update(
+    info: ListResult<&SourceInfo>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    if info.monitor_of_sink.is_some() {
+        return;
+    }
+
+    let mut source: Source = info.into();
+
+    if !source.active {
+        if let Some(default_source) = &*lock!(default_source) {
+            source.active = &source.name == default_source;
+        }
+    }
+
+    {
+        let mut sources = lock!(sources);
+        let Some(pos) = sources.iter().position(|s| s.index == info.index) else {
+            error!("received update to untracked source");
+            return;
+        };
+
+        sources[pos] = source.clone();
+    }
+
+    send!(tx, Event::UpdateSource(source));
+}
+
+fn remove(index: u32, sources: &ArcMutVec<Source>, tx: &broadcast::Sender<Event>) {
+    let mut sources = lock!(sources);
+
+    if let Some(pos) = sources.iter().position(|s| s.index == index) {
+        let info = sources.remove(pos);
+        send!(tx, Event::RemoveSource(info.name));
+    }
+}
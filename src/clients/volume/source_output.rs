@@ -0,0 +1,150 @@
+use super::sink_input::app_icons;
+use super::{
+    percent_to_volume, volume_to_percent, ArcMutVec, Client, ConnectionState, Event, SinkInput,
+};
+use crate::{lock, send};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::introspect::SourceOutputInfo;
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::context::Context;
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+
+/// A stream recording from a source,
+/// such as an application capturing a microphone.
+///
+/// This has the same properties as a playback stream.
+pub type SourceOutput = SinkInput;
+
+impl From<&SourceOutputInfo<'_>> for SourceOutput {
+    fn from(value: &SourceOutputInfo) -> Self {
+        Self {
+            index: value.index,
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            muted: value.mute,
+            corked: value.corked,
+            app_name: value.proplist.get_str("application.name"),
+            app_icons: app_icons(&value.proplist),
+            volume: volume_to_percent(value.volume),
+            can_set_volume: value.has_volume && value.volume_writable,
+        }
+    }
+}
+
+impl Client {
+    pub fn source_outputs(&self) -> Arc<Mutex<Vec<SourceOutput>>> {
+        self.data.source_outputs.clone()
+    }
+
+    pub fn set_output_volume(&self, index: u32, volume_percent: f64) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let (tx, rx) = mpsc::channel();
+
+            introspector.get_source_output_info(index, move |info| {
+                let ListResult::Item(info) = info else {
+                    return;
+                };
+                send!(tx, info.volume);
+            });
+
+            let new_volume = percent_to_volume(volume_percent);
+
+            let mut volume = rx.recv().expect("to receive info");
+            for v in volume.get_mut() {
+                v.0 = new_volume;
+            }
+
+            introspector.set_source_output_volume(index, &volume, None);
+        }
+    }
+
+    pub fn set_output_muted(&self, index: u32, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_source_output_mute(index, muted, None);
+        }
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new source output");
+            introspect.get_source_output_info(i, {
+                let outputs = outputs.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &outputs, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("source output changed");
+            introspect.get_source_output_info(i, {
+                let outputs = outputs.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &outputs, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("source output removed");
+            remove(i, outputs, tx);
+        }
+    }
+}
+
+pub fn add(
+    info: ListResult<&SourceOutputInfo>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    lock!(outputs).push(info.into());
+    send!(tx, Event::AddOutput(info.into()));
+}
+
+fn update(
+    info: ListResult<&SourceOutputInfo>,
+    outputs: &ArcMutVec<SourceOutput>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    {
+        let mut outputs = lock!(outputs);
+        let Some(pos) = outputs.iter().position(|output| output.index == info.index) else {
+            error!("received update to untracked source output");
+            return;
+        };
+
+        outputs[pos] = info.into();
+    }
+
+    send!(tx, Event::UpdateOutput(info.into()));
+}
+
+fn remove(index: u32, outputs: &ArcMutVec<SourceOutput>, tx: &broadcast::Sender<Event>) {
+    let mut outputs = lock!(outputs);
+
+    if let Some(pos) = outputs.iter().position(|s| s.index == index) {
+        let info = outputs.remove(pos);
+        send!(tx, Event::RemoveOutput(info.index));
+    }
+}
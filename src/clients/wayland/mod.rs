@@ -22,7 +22,7 @@ use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, trace};
 use wayland_client::globals::registry_queue_init;
 use wayland_client::{Connection, QueueHandle};
-pub use wl_output::{OutputEvent, OutputEventType};
+pub use wl_output::{OutputDetails, OutputEvent, OutputEventType, OutputValue};
 
 cfg_if! {
     if #[cfg(any(feature = "focused", feature = "launcher"))] {
@@ -34,6 +34,24 @@ cfg_if! {
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "launcher")] {
+        mod wlr_screencopy;
+        use self::wlr_screencopy::ScreencopyState;
+        use smithay_client_toolkit::delegate_shm;
+        use smithay_client_toolkit::shm::Shm;
+        pub use self::wlr_screencopy::ToplevelPreview;
+    }
+}
+
+cfg_if! {
+    if #[cfg(any(feature = "idle_inhibit", feature = "music"))] {
+        mod zwp_idle_inhibit;
+        use self::zwp_idle_inhibit::IdleInhibitRequest;
+        pub use self::zwp_idle_inhibit::IdleInhibitGuard;
+    }
+}
+
 cfg_if! {
     if #[cfg(feature = "clipboard")] {
         mod wlr_data_control;
@@ -43,9 +61,10 @@ cfg_if! {
         use self::wlr_data_control::manager::DataControlDeviceManagerState;
         use self::wlr_data_control::source::CopyPasteSource;
         use self::wlr_data_control::SelectionOfferItem;
+        use smithay_client_toolkit::reexports::calloop::RegistrationToken;
         use wayland_client::protocol::wl_seat::WlSeat;
 
-        pub use wlr_data_control::{ClipboardItem, ClipboardValue};
+        pub use wlr_data_control::{ClipboardItem, ClipboardValue, MimeFilter, Selection};
 
         #[derive(Debug)]
         pub struct DataControlDeviceEntry {
@@ -60,6 +79,8 @@ pub enum Event {
     Output(OutputEvent),
     #[cfg(any(feature = "focused", feature = "launcher"))]
     Toplevel(ToplevelEvent),
+    #[cfg(feature = "launcher")]
+    ToplevelPreview(ToplevelPreview),
     #[cfg(feature = "clipboard")]
     Clipboard(ClipboardItem),
 }
@@ -75,11 +96,19 @@ pub enum Request {
     ToplevelInfoAll,
     #[cfg(feature = "launcher")]
     ToplevelFocus(usize),
+    /// Starts capturing toplevel previews.
+    /// This does not send a response.
+    #[cfg(feature = "launcher")]
+    EnableToplevelPreviews,
 
     #[cfg(feature = "clipboard")]
-    CopyToClipboard(ClipboardItem),
+    CopyToClipboard(ClipboardItem, Vec<Selection>),
     #[cfg(feature = "clipboard")]
     ClipboardItem,
+    #[cfg(feature = "clipboard")]
+    SetClipboardFilter(MimeFilter),
+    #[cfg(feature = "clipboard")]
+    SetClipboardSelections(Vec<Selection>),
 }
 
 #[derive(Debug)]
@@ -115,8 +144,12 @@ pub struct Client {
     output_channel: BroadcastChannel<OutputEvent>,
     #[cfg(any(feature = "focused", feature = "launcher"))]
     toplevel_channel: BroadcastChannel<ToplevelEvent>,
+    #[cfg(feature = "launcher")]
+    toplevel_preview_channel: BroadcastChannel<ToplevelPreview>,
     #[cfg(feature = "clipboard")]
     clipboard_channel: BroadcastChannel<ClipboardItem>,
+    #[cfg(any(feature = "idle_inhibit", feature = "music"))]
+    idle_inhibit_tx: mpsc::UnboundedSender<IdleInhibitRequest>,
 }
 
 impl Client {
@@ -129,6 +162,8 @@ impl Client {
         let output_channel = broadcast::channel(32);
         #[cfg(any(feature = "focused", feature = "launcher"))]
         let toplevel_channel = broadcast::channel(32);
+        #[cfg(feature = "launcher")]
+        let toplevel_preview_channel = broadcast::channel(8);
 
         #[cfg(feature = "clipboard")]
         let clipboard_channel = broadcast::channel(32);
@@ -142,6 +177,8 @@ impl Client {
             let output_tx = output_channel.0.clone();
             #[cfg(any(feature = "focused", feature = "launcher"))]
             let toplevel_tx = toplevel_channel.0.clone();
+            #[cfg(feature = "launcher")]
+            let toplevel_preview_tx = toplevel_preview_channel.0.clone();
 
             #[cfg(feature = "clipboard")]
             let clipboard_tx = clipboard_channel.0.clone();
@@ -152,6 +189,8 @@ impl Client {
                         Event::Output(event) => send!(output_tx, event),
                         #[cfg(any(feature = "focused", feature = "launcher"))]
                         Event::Toplevel(event) => send!(toplevel_tx, event),
+                        #[cfg(feature = "launcher")]
+                        Event::ToplevelPreview(preview) => send!(toplevel_preview_tx, preview),
                         #[cfg(feature = "clipboard")]
                         Event::Clipboard(item) => send!(clipboard_tx, item),
                     };
@@ -166,8 +205,12 @@ impl Client {
             output_channel: output_channel.into(),
             #[cfg(any(feature = "focused", feature = "launcher"))]
             toplevel_channel: toplevel_channel.into(),
+            #[cfg(feature = "launcher")]
+            toplevel_preview_channel: toplevel_preview_channel.into(),
             #[cfg(feature = "clipboard")]
             clipboard_channel: clipboard_channel.into(),
+            #[cfg(any(feature = "idle_inhibit", feature = "music"))]
+            idle_inhibit_tx: zwp_idle_inhibit::spawn(),
         }
     }
 
@@ -201,6 +244,12 @@ pub struct Environment {
     #[cfg(any(feature = "focused", feature = "launcher"))]
     handles: Vec<ToplevelHandle>,
 
+    // -- screencopy --
+    #[cfg(feature = "launcher")]
+    shm: Shm,
+    #[cfg(feature = "launcher")]
+    screencopy_state: Option<ScreencopyState>,
+
     // -- clipboard --
     #[cfg(feature = "clipboard")]
     data_control_device_manager_state: DataControlDeviceManagerState,
@@ -215,6 +264,16 @@ pub struct Environment {
     // local state
     #[cfg(feature = "clipboard")]
     clipboard: Arc<Mutex<Option<ClipboardItem>>>,
+    #[cfg(feature = "clipboard")]
+    primary_selection: Arc<Mutex<Option<ClipboardItem>>>,
+    #[cfg(feature = "clipboard")]
+    clipboard_filter: MimeFilter,
+    /// The selections to read new values from.
+    #[cfg(feature = "clipboard")]
+    clipboard_selections: Vec<Selection>,
+    /// The pending read of the primary selection, while it is changing.
+    #[cfg(feature = "clipboard")]
+    primary_debounce: Option<RegistrationToken>,
 }
 
 delegate_registry!(Environment);
@@ -229,6 +288,9 @@ cfg_if! {
     }
 }
 
+#[cfg(feature = "launcher")]
+delegate_shm!(Environment);
+
 cfg_if! {
     if #[cfg(feature = "clipboard")] {
         delegate_data_control_device_manager!(Environment);
@@ -267,6 +329,17 @@ impl Environment {
         ToplevelManagerState::bind(&globals, &qh)
             .expect("to bind to wlr_foreign_toplevel_manager global");
 
+        #[cfg(feature = "launcher")]
+        let shm = Shm::bind(&globals, &qh).expect("to bind to wl_shm global");
+        #[cfg(feature = "launcher")]
+        let screencopy_state = match ScreencopyState::bind(&globals, &qh) {
+            Ok(state) => Some(state),
+            Err(err) => {
+                tracing::warn!("Failed to bind to screencopy manager global: {err:?}");
+                None
+            }
+        };
+
         #[cfg(feature = "clipboard")]
         let data_control_device_manager_state = DataControlDeviceManagerState::bind(&globals, &qh)
             .expect("to bind to wlr_data_control_device_manager global");
@@ -283,6 +356,10 @@ impl Environment {
             response_tx,
             #[cfg(any(feature = "focused", feature = "launcher"))]
             handles: vec![],
+            #[cfg(feature = "launcher")]
+            shm,
+            #[cfg(feature = "launcher")]
+            screencopy_state,
 
             #[cfg(feature = "clipboard")]
             data_control_devices: vec![],
@@ -292,6 +369,14 @@ impl Environment {
             selection_offers: vec![],
             #[cfg(feature = "clipboard")]
             clipboard: arc_mut!(None),
+            #[cfg(feature = "clipboard")]
+            primary_selection: arc_mut!(None),
+            #[cfg(feature = "clipboard")]
+            clipboard_filter: MimeFilter::default(),
+            #[cfg(feature = "clipboard")]
+            clipboard_selections: vec![Selection::Clipboard],
+            #[cfg(feature = "clipboard")]
+            primary_debounce: None,
         };
 
         loop_handle
@@ -347,9 +432,11 @@ impl Environment {
 
                 send!(env.response_tx, Response::Ok);
             }
+            #[cfg(feature = "launcher")]
+            Msg(Request::EnableToplevelPreviews) => env.enable_toplevel_previews(),
             #[cfg(feature = "clipboard")]
-            Msg(Request::CopyToClipboard(item)) => {
-                env.copy_to_clipboard(item);
+            Msg(Request::CopyToClipboard(item, selections)) => {
+                env.copy_to_clipboard(item, &selections);
                 send!(env.response_tx, Response::Ok);
             }
             #[cfg(feature = "clipboard")]
@@ -357,6 +444,16 @@ impl Environment {
                 let item = lock!(env.clipboard).clone();
                 send!(env.response_tx, Response::ClipboardItem(item));
             }
+            #[cfg(feature = "clipboard")]
+            Msg(Request::SetClipboardFilter(filter)) => {
+                env.clipboard_filter = filter;
+                send!(env.response_tx, Response::Ok);
+            }
+            #[cfg(feature = "clipboard")]
+            Msg(Request::SetClipboardSelections(selections)) => {
+                env.clipboard_selections = selections;
+                send!(env.response_tx, Response::Ok);
+            }
             calloop_channel::Event::Closed => error!("request channel unexpectedly closed"),
         }
     }
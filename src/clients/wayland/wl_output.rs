@@ -1,7 +1,10 @@
 use super::{Client, Environment, Event};
 use crate::try_send;
+use serde::Serialize;
 use smithay_client_toolkit::output::{OutputHandler, OutputInfo, OutputState};
-use tokio::sync::broadcast;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tokio::sync::{broadcast, watch};
 use tracing::{debug, error};
 use wayland_client::protocol::wl_output::WlOutput;
 use wayland_client::{Connection, QueueHandle};
@@ -9,9 +12,20 @@ use wayland_client::{Connection, QueueHandle};
 #[derive(Debug, Clone)]
 pub struct OutputEvent {
     pub output: OutputInfo,
+    pub details: OutputDetails,
     pub event_type: OutputEventType,
 }
 
+impl OutputEvent {
+    fn new(output: OutputInfo, event_type: OutputEventType) -> Self {
+        Self {
+            details: OutputDetails::from(&output),
+            output,
+            event_type,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum OutputEventType {
     New,
@@ -19,6 +33,115 @@ pub enum OutputEventType {
     Destroyed,
 }
 
+/// The details identifying an output to the user.
+///
+/// `wl_output` does not advertise serial numbers,
+/// so these are not included.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct OutputDetails {
+    /// The connector name, such as `DP-1`.
+    pub name: String,
+    pub make: String,
+    pub model: String,
+    /// A human-readable description set by the compositor,
+    /// such as `LG Electronics LG ULTRAGEAR (DP-1)`.
+    pub description: String,
+}
+
+impl From<&OutputInfo> for OutputDetails {
+    fn from(info: &OutputInfo) -> Self {
+        Self {
+            name: info.name.clone().unwrap_or_default(),
+            make: info.make.clone(),
+            model: info.model.clone(),
+            description: info.description.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// A single value from an output's details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputValue {
+    Name,
+    Make,
+    Model,
+    Description,
+}
+
+impl OutputValue {
+    /// The prefix used to reference output details in dynamic strings,
+    /// for example `#output.model`.
+    pub const PREFIX: &'static str = "output.";
+
+    /// Parses the value from its name,
+    /// without the `output.` prefix.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(Self::Name),
+            "make" => Some(Self::Make),
+            "model" => Some(Self::Model),
+            "description" => Some(Self::Description),
+            _ => None,
+        }
+    }
+}
+
+impl OutputDetails {
+    /// Gets the value formatted for display.
+    pub fn get(&self, value: OutputValue) -> String {
+        match value {
+            OutputValue::Name => self.name.clone(),
+            OutputValue::Make => self.make.clone(),
+            OutputValue::Model => self.model.clone(),
+            OutputValue::Description => self.description.clone(),
+        }
+    }
+
+    fn sender() -> &'static watch::Sender<HashMap<String, Self>> {
+        static TX: OnceLock<watch::Sender<HashMap<String, OutputDetails>>> = OnceLock::new();
+        TX.get_or_init(|| watch::channel(HashMap::new()).0)
+    }
+
+    /// Creates a receiver which is notified whenever an output is added,
+    /// removed or has its details changed.
+    /// Outputs are keyed by name.
+    pub fn subscribe() -> watch::Receiver<HashMap<String, Self>> {
+        Self::sender().subscribe()
+    }
+
+    /// Gets the details of every current output, sorted by name.
+    pub fn all() -> Vec<Self> {
+        let mut outputs = Self::sender()
+            .borrow()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        outputs.sort_by(|a, b| a.name.cmp(&b.name));
+        outputs
+    }
+
+    /// Updates the stored details from an output event.
+    fn apply(event: &OutputEvent) {
+        let details = &event.details;
+        if details.name.is_empty() {
+            return;
+        }
+
+        Self::sender().send_if_modified(|outputs| match event.event_type {
+            OutputEventType::New | OutputEventType::Update => {
+                if outputs.get(&details.name) == Some(details) {
+                    false
+                } else {
+                    outputs.insert(details.name.clone(), details.clone());
+                    true
+                }
+            }
+            OutputEventType::Destroyed => outputs.remove(&details.name).is_some(),
+        });
+    }
+}
+
 impl Client {
     /// Gets the information for all outputs.
     #[cfg(feature = "ipc")]
@@ -63,13 +186,10 @@ impl OutputHandler for Environment {
     fn new_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
         debug!("Handler received new output");
         if let Some(info) = self.output_state.info(&output) {
-            try_send!(
-                self.event_tx,
-                Event::Output(OutputEvent {
-                    output: info,
-                    event_type: OutputEventType::New
-                })
-            );
+            let event = OutputEvent::new(info, OutputEventType::New);
+            OutputDetails::apply(&event);
+
+            try_send!(self.event_tx, Event::Output(event));
         } else {
             error!("Output is missing information!");
         }
@@ -78,13 +198,10 @@ impl OutputHandler for Environment {
     fn update_output(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
         debug!("Handle received output update");
         if let Some(info) = self.output_state.info(&output) {
-            try_send!(
-                self.event_tx,
-                Event::Output(OutputEvent {
-                    output: info,
-                    event_type: OutputEventType::Update
-                })
-            );
+            let event = OutputEvent::new(info, OutputEventType::Update);
+            OutputDetails::apply(&event);
+
+            try_send!(self.event_tx, Event::Output(event));
         } else {
             error!("Output is missing information!");
         }
@@ -93,13 +210,10 @@ impl OutputHandler for Environment {
     fn output_destroyed(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, output: WlOutput) {
         debug!("Handle received output destruction");
         if let Some(info) = self.output_state.info(&output) {
-            try_send!(
-                self.event_tx,
-                Event::Output(OutputEvent {
-                    output: info,
-                    event_type: OutputEventType::Destroyed
-                })
-            );
+            let event = OutputEvent::new(info, OutputEventType::Destroyed);
+            OutputDetails::apply(&event);
+
+            try_send!(self.event_tx, Event::Output(event));
         } else {
             error!("Output is missing information!");
         }
@@ -2,6 +2,7 @@ use super::manager::DataControlDeviceManagerState;
 use super::offer::{
     DataControlOfferData, DataControlOfferDataExt, DataControlOfferHandler, SelectionOffer,
 };
+use super::Selection;
 use crate::error::ERR_WAYLAND_DATA;
 use crate::lock;
 use std::sync::{Arc, Mutex};
@@ -21,10 +22,21 @@ pub struct DataControlDevice {
 pub struct DataControlDeviceInner {
     /// the active selection offer and its data
     selection_offer: Arc<Mutex<Option<ZwlrDataControlOfferV1>>>,
+    /// the active primary selection offer and its data
+    primary_selection_offer: Arc<Mutex<Option<ZwlrDataControlOfferV1>>>,
     /// the active undetermined offers and their data
     pub undetermined_offers: Arc<Mutex<Vec<ZwlrDataControlOfferV1>>>,
 }
 
+impl DataControlDeviceInner {
+    fn offer(&self, selection: Selection) -> &Arc<Mutex<Option<ZwlrDataControlOfferV1>>> {
+        match selection {
+            Selection::Clipboard => &self.selection_offer,
+            Selection::Primary => &self.primary_selection_offer,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct DataControlDeviceData {
     pub(super) inner: Arc<Mutex<DataControlDeviceInner>>,
@@ -35,9 +47,9 @@ pub trait DataControlDeviceDataExt: Send + Sync {
 
     fn data_control_device_data(&self) -> &DataControlDeviceData;
 
-    fn selection_mime_types(&self) -> Vec<String> {
+    fn selection_mime_types(&self, selection: Selection) -> Vec<String> {
         let inner = self.data_control_device_data();
-        lock!(lock!(inner.inner).selection_offer)
+        lock!(lock!(inner.inner).offer(selection))
             .as_ref()
             .map(|offer| {
                 let data = offer
@@ -48,10 +60,10 @@ pub trait DataControlDeviceDataExt: Send + Sync {
             .unwrap_or_default()
     }
 
-    /// Get the active selection offer if it exists.
-    fn selection_offer(&self) -> Option<SelectionOffer> {
+    /// Get the active offer for the selection if it exists.
+    fn selection_offer(&self, selection: Selection) -> Option<SelectionOffer> {
         let inner = self.data_control_device_data();
-        lock!(lock!(inner.inner).selection_offer)
+        lock!(lock!(inner.inner).offer(selection))
             .as_ref()
             .and_then(|offer| {
                 let data = offer
@@ -80,12 +92,13 @@ impl DataControlDeviceDataExt for DataControlDeviceData {
 ///
 /// The functions defined in this trait are called as `DataDevice` events are received from the compositor.
 pub trait DataControlDeviceHandler: Sized {
-    /// Advertises a new selection.
+    /// Advertises a new clipboard or primary selection.
     fn selection(
         &mut self,
         conn: &Connection,
         qh: &QueueHandle<Self>,
         data_device: DataControlDevice,
+        selection: Selection,
     );
 }
 
@@ -129,34 +142,12 @@ where
                 drop(inner);
             }
             Event::Selection { id } => {
-                let mut selection_offer = lock!(inner.selection_offer);
-
-                if let Some(offer) = id {
-                    let mut undetermined = lock!(inner.undetermined_offers);
-                    if let Some(i) = undetermined.iter().position(|o| o == &offer) {
-                        undetermined.remove(i);
-                    }
-                    drop(undetermined);
-
-                    let data = offer
-                        .data::<V>()
-                        .expect(ERR_WAYLAND_DATA)
-                        .data_control_offer_data();
-                    data.to_selection_offer();
-                    // XXX Drop done here to prevent Mutex deadlocks.
-                    *selection_offer = Some(offer.clone());
-                    drop(selection_offer);
-                    drop(inner);
-                    state.selection(
-                        conn,
-                        qh,
-                        DataControlDevice {
-                            device: data_device.clone(),
-                        },
-                    );
-                } else {
-                    *selection_offer = None;
-                }
+                drop(inner);
+                set_selection::<D, V>(state, data_device, data, id, Selection::Clipboard, conn, qh);
+            }
+            Event::PrimarySelection { id } => {
+                drop(inner);
+                set_selection::<D, V>(state, data_device, data, id, Selection::Primary, conn, qh);
             }
             Event::Finished => {
                 warn!("Data control offer is no longer valid, but has not been dropped by client. This could cause clipboard issues.");
@@ -165,3 +156,49 @@ where
         }
     }
 }
+
+/// Stores the new offer for the selection,
+/// notifying the handler if there is one.
+fn set_selection<D, V>(
+    state: &mut D,
+    data_device: &ZwlrDataControlDeviceV1,
+    data: &DataControlDeviceData,
+    id: Option<ZwlrDataControlOfferV1>,
+    selection: Selection,
+    conn: &Connection,
+    qh: &QueueHandle<D>,
+) where
+    D: DataControlDeviceHandler,
+    V: DataControlOfferDataExt + 'static,
+{
+    let inner = lock!(data.inner);
+    let mut selection_offer = lock!(inner.offer(selection));
+
+    if let Some(offer) = id {
+        let mut undetermined = lock!(inner.undetermined_offers);
+        if let Some(i) = undetermined.iter().position(|o| o == &offer) {
+            undetermined.remove(i);
+        }
+        drop(undetermined);
+
+        let data = offer
+            .data::<V>()
+            .expect(ERR_WAYLAND_DATA)
+            .data_control_offer_data();
+        data.to_selection_offer();
+        // XXX Drop done here to prevent Mutex deadlocks.
+        *selection_offer = Some(offer.clone());
+        drop(selection_offer);
+        drop(inner);
+        state.selection(
+            conn,
+            qh,
+            DataControlDevice {
+                device: data_device.clone(),
+            },
+            selection,
+        );
+    } else {
+        *selection_offer = None;
+    }
+}
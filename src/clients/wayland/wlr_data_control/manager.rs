@@ -1,6 +1,7 @@
 use super::device::{DataControlDevice, DataControlDeviceData, DataControlDeviceDataExt};
 use super::offer::DataControlOfferData;
 use super::source::{CopyPasteSource, DataControlSourceData, DataControlSourceDataExt};
+use super::Selection;
 use smithay_client_toolkit::error::GlobalError;
 use smithay_client_toolkit::globals::{GlobalData, ProvidesBoundGlobal};
 use std::marker::PhantomData;
@@ -33,11 +34,12 @@ impl DataControlDeviceManagerState {
         })
     }
 
-    /// creates a data source for copy paste
+    /// creates a data source for copy paste to the given selection
     pub fn create_copy_paste_source<'s, D, I>(
         &self,
         qh: &QueueHandle<D>,
         mime_types: I,
+        selection: Selection,
     ) -> CopyPasteSource
     where
         D: Dispatch<ZwlrDataControlSourceV1, DataControlSourceData> + 'static,
@@ -45,6 +47,7 @@ impl DataControlDeviceManagerState {
     {
         CopyPasteSource {
             inner: self.create_data_control_source(qh, mime_types),
+            selection,
         }
     }
 
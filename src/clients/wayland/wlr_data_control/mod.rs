@@ -5,21 +5,26 @@ pub mod source;
 
 use self::device::{DataControlDeviceDataExt, DataControlDeviceHandler};
 use self::offer::{DataControlDeviceOffer, DataControlOfferHandler, SelectionOffer};
+use self::source::CopyPasteSource;
 use self::source::DataControlSourceHandler;
 use super::{Client, Environment, Event, Request, Response};
+use crate::glob::glob_match;
 use crate::{lock, try_send, Ironbar};
 use device::DataControlDevice;
 use glib::Bytes;
 use nix::fcntl::{fcntl, F_GETPIPE_SZ, F_SETPIPE_SZ};
 use nix::sys::epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags, EpollTimeout};
+use serde::{Deserialize, Serialize};
 use smithay_client_toolkit::data_device_manager::WritePipe;
+use smithay_client_toolkit::reexports::calloop::timer::{TimeoutAction, Timer};
 use smithay_client_toolkit::reexports::calloop::{PostAction, RegistrationToken};
 use std::cmp::min;
 use std::fmt::{Debug, Formatter};
 use std::fs::File;
 use std::io::{ErrorKind, Read, Write};
 use std::os::fd::{AsRawFd, OwnedFd, RawFd};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{fs, io};
 use tokio::sync::broadcast;
 use tracing::{debug, error, trace};
@@ -28,6 +33,28 @@ use wayland_protocols_wlr::data_control::v1::client::zwlr_data_control_source_v1
 
 const INTERNAL_MIME_TYPE: &str = "x-ironbar-internal";
 
+/// Offered by password managers alongside sensitive values,
+/// such as copied passwords.
+const PASSWORD_MANAGER_HINT: &str = "x-kde-passwordManagerHint";
+
+/// How long the primary selection must stay the same before it is read.
+///
+/// Dragging to select text changes the selection many times a second,
+/// so this avoids reading and storing every intermediate value.
+const PRIMARY_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// One of the seat's selections which can be copied to and read from.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Selection {
+    /// The regular clipboard, set by copying.
+    Clipboard,
+    /// The primary selection, set by selecting text
+    /// and usually pasted using the middle mouse button.
+    Primary,
+}
+
 #[derive(Debug)]
 pub struct SelectionOfferItem {
     offer: SelectionOffer,
@@ -43,6 +70,11 @@ pub struct ClipboardItem {
     pub id: usize,
     pub value: Arc<ClipboardValue>,
     pub mime_type: Arc<str>,
+    /// The selection the item was read from.
+    pub selection: Selection,
+    /// Whether the item was marked as sensitive by the app which copied it,
+    /// such as a password manager.
+    pub sensitive: bool,
 }
 
 impl PartialEq<Self> for ClipboardItem {
@@ -109,8 +141,77 @@ impl MimeType {
         }
     }
 
-    fn parse_multiple(mime_types: &[String]) -> Option<Self> {
-        mime_types.iter().find_map(|mime| Self::parse(mime))
+    /// Parses a mime type which has been explicitly accepted by the user.
+    ///
+    /// Unlike `parse`, this treats any `text/*` type as textual,
+    /// allowing types such as `text/html` to be stored.
+    fn parse_accepted(mime_type: &str) -> Option<Self> {
+        Self::parse(mime_type).or_else(|| {
+            mime_type.to_lowercase().starts_with("text/").then(|| Self {
+                value: mime_type.to_string(),
+                category: MimeTypeCategory::Text,
+            })
+        })
+    }
+
+    /// Checks whether this is one of the plain text types.
+    fn is_plain_text(&self) -> bool {
+        let value = self.value.to_lowercase();
+        matches!(self.category, MimeTypeCategory::Text)
+            && (matches!(value.as_str(), "text" | "string" | "utf8_string")
+                || value.starts_with("text/plain"))
+    }
+}
+
+/// Controls which of the mime types offered by a selection
+/// are read and stored in the clipboard history.
+#[derive(Debug, Clone, Default)]
+pub struct MimeFilter {
+    /// Globs of mime types to accept.
+    /// If empty, all supported types are accepted.
+    pub accept: Vec<String>,
+    /// Globs of mime types to reject.
+    /// This takes priority over `accept`.
+    pub deny: Vec<String>,
+    /// Whether to pick a plain text type
+    /// over any other offered type when available.
+    pub prefer_plain_text: bool,
+}
+
+impl MimeFilter {
+    /// Checks whether the mime type passes the accept and deny lists.
+    fn allows(&self, mime_type: &str) -> bool {
+        let mime_type = mime_type.to_lowercase();
+
+        let accepted =
+            self.accept.is_empty() || self.accept.iter().any(|glob| glob_match(glob, &mime_type));
+        let denied = self.deny.iter().any(|glob| glob_match(glob, &mime_type));
+
+        accepted && !denied
+    }
+
+    /// Picks the mime type to read from the list offered by a selection,
+    /// returning `None` if no offered type passes the filter.
+    fn select(&self, mime_types: &[String]) -> Option<MimeType> {
+        let candidates = mime_types
+            .iter()
+            .filter(|mime| self.allows(mime))
+            .filter_map(|mime| {
+                if self.accept.is_empty() {
+                    MimeType::parse(mime)
+                } else {
+                    MimeType::parse_accepted(mime)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let preferred = if self.prefer_plain_text {
+            candidates.iter().position(MimeType::is_plain_text)
+        } else {
+            None
+        };
+
+        candidates.into_iter().nth(preferred.unwrap_or_default())
     }
 }
 
@@ -124,9 +225,26 @@ impl Client {
         }
     }
 
-    /// Copies the provided value to the system clipboard.
-    pub fn copy_to_clipboard(&self, item: ClipboardItem) {
-        match self.send_request(Request::CopyToClipboard(item)) {
+    /// Copies the provided value to each of the selections.
+    pub fn copy_to_clipboard(&self, item: ClipboardItem, selections: Vec<Selection>) {
+        match self.send_request(Request::CopyToClipboard(item, selections)) {
+            Response::Ok => (),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Sets the filter used to decide which offered mime types
+    /// are read from new selections.
+    pub fn set_clipboard_filter(&self, filter: MimeFilter) {
+        match self.send_request(Request::SetClipboardFilter(filter)) {
+            Response::Ok => (),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Sets which selections are read and sent to subscribers.
+    pub fn set_clipboard_selections(&self, selections: Vec<Selection>) {
+        match self.send_request(Request::SetClipboardSelections(selections)) {
             Response::Ok => (),
             _ => unreachable!(),
         }
@@ -140,13 +258,21 @@ impl Client {
 }
 
 impl Environment {
+    /// Gets the current item for the selection.
+    fn selection_item(&self, selection: Selection) -> &Arc<Mutex<Option<ClipboardItem>>> {
+        match selection {
+            Selection::Clipboard => &self.clipboard,
+            Selection::Primary => &self.primary_selection,
+        }
+    }
+
     /// Creates a new copy/paste source on the
-    /// seat's data control device.
+    /// seat's data control device for each selection.
     ///
     /// This provides it as an offer,
     /// which the compositor will then treat as the current copied value.
-    pub fn copy_to_clipboard(&mut self, item: ClipboardItem) {
-        debug!("Copying item to clipboard: {item:?}");
+    pub fn copy_to_clipboard(&mut self, item: ClipboardItem, selections: &[Selection]) {
+        debug!("Copying item to {selections:?}: {item:?}");
 
         let seat = self.default_seat();
         let Some(device) = self
@@ -157,18 +283,29 @@ impl Environment {
             return;
         };
 
-        let source = self
-            .data_control_device_manager_state
-            .create_copy_paste_source(&self.queue_handle, [INTERNAL_MIME_TYPE, &item.mime_type]);
+        for &selection in selections {
+            let source = self
+                .data_control_device_manager_state
+                .create_copy_paste_source(
+                    &self.queue_handle,
+                    [INTERNAL_MIME_TYPE, &item.mime_type],
+                    selection,
+                );
 
-        source.set_selection(&device.device);
-        self.copy_paste_sources.push(source);
+            source.set_selection(&device.device);
+            self.copy_paste_sources.push(source);
 
-        lock!(self.clipboard).replace(item);
+            lock!(self.selection_item(selection)).replace(item.clone());
+        }
     }
 
     /// Reads an offer file handle into a new `ClipboardItem`.
-    fn read_file(mime_type: &MimeType, file: &mut File) -> io::Result<ClipboardItem> {
+    fn read_file(
+        mime_type: &MimeType,
+        file: &mut File,
+        selection: Selection,
+        sensitive: bool,
+    ) -> io::Result<ClipboardItem> {
         let value = match mime_type.category {
             MimeTypeCategory::Text => {
                 let mut txt = String::new();
@@ -192,28 +329,27 @@ impl Environment {
             id: Ironbar::unique_id(),
             value: Arc::new(value),
             mime_type: mime_type.value.clone().into(),
+            selection,
+            sensitive,
         })
     }
-}
-
-impl DataControlDeviceHandler for Environment {
-    /// Called when an offer for a new value is received
-    /// (ie something has copied to the clipboard)
-    fn selection(
-        &mut self,
-        _conn: &Connection,
-        _qh: &QueueHandle<Self>,
-        data_device: DataControlDevice,
-    ) {
-        debug!("Handler received selection event");
 
-        let mime_types = data_device.selection_mime_types();
+    /// Reads the current offer for the selection,
+    /// storing it as the current item and sending it as an event.
+    fn receive_selection(&mut self, data_device: &DataControlDevice, selection: Selection) {
+        let mime_types = data_device.selection_mime_types(selection);
 
         if mime_types.contains(&INTERNAL_MIME_TYPE.to_string()) {
             return;
         }
 
-        if let Some(offer) = data_device.selection_offer() {
+        let sensitive = mime_types
+            .iter()
+            .any(|mime_type| mime_type.eq_ignore_ascii_case(PASSWORD_MANAGER_HINT));
+
+        if let Some(offer) = data_device.selection_offer(selection) {
+            let current = self.selection_item(selection).clone();
+
             self.selection_offers
                 .push(SelectionOfferItem { offer, token: None });
 
@@ -223,17 +359,23 @@ impl DataControlDeviceHandler for Environment {
                 .expect("Failed to get current offer");
 
             // clear prev
-            let Some(mime_type) = MimeType::parse_multiple(&mime_types) else {
-                lock!(self.clipboard).take();
-                // send an event so the clipboard module is aware it's changed
-                try_send!(
-                    self.event_tx,
-                    Event::Clipboard(ClipboardItem {
-                        id: usize::MAX,
-                        mime_type: String::new().into(),
-                        value: Arc::new(ClipboardValue::Other)
-                    })
-                );
+            let Some(mime_type) = self.clipboard_filter.select(&mime_types) else {
+                lock!(current).take();
+
+                // send an event so the clipboard module is aware it's changed.
+                // the primary selection is not shown as active, so it does not need one.
+                if selection == Selection::Clipboard {
+                    try_send!(
+                        self.event_tx,
+                        Event::Clipboard(ClipboardItem {
+                            id: usize::MAX,
+                            mime_type: String::new().into(),
+                            value: Arc::new(ClipboardValue::Other),
+                            selection,
+                            sensitive,
+                        })
+                    );
+                }
                 return;
             };
 
@@ -243,7 +385,6 @@ impl DataControlDeviceHandler for Environment {
                 let offer_clone = cur_offer.offer.clone();
 
                 let tx = self.event_tx.clone();
-                let clipboard = self.clipboard.clone();
 
                 let token =
                     self.loop_handle
@@ -255,9 +396,10 @@ impl DataControlDeviceHandler for Environment {
                                 .map(|p| state.selection_offers.remove(p))
                                 .expect("Failed to find selection offer item");
 
-                            match Self::read_file(&mime_type, file.get_mut()) {
+                            match Self::read_file(&mime_type, file.get_mut(), selection, sensitive)
+                            {
                                 Ok(item) => {
-                                    lock!(clipboard).replace(item.clone());
+                                    lock!(current).replace(item.clone());
                                     try_send!(tx, Event::Clipboard(item));
                                 }
                                 Err(err) => error!("{err:?}"),
@@ -279,6 +421,51 @@ impl DataControlDeviceHandler for Environment {
             }
         }
     }
+
+    /// Reads the primary selection once it has stopped changing,
+    /// cancelling any read scheduled by a previous change.
+    fn debounce_primary_selection(&mut self, data_device: DataControlDevice) {
+        if let Some(token) = self.primary_debounce.take() {
+            self.loop_handle.remove(token);
+        }
+
+        let token = self.loop_handle.insert_source(
+            Timer::from_duration(PRIMARY_DEBOUNCE),
+            move |_, (), state| {
+                state.primary_debounce = None;
+                state.receive_selection(&data_device, Selection::Primary);
+                TimeoutAction::Drop
+            },
+        );
+
+        match token {
+            Ok(token) => self.primary_debounce = Some(token),
+            Err(err) => error!("Failed to insert primary selection timer: {err:?}"),
+        }
+    }
+}
+
+impl DataControlDeviceHandler for Environment {
+    /// Called when an offer for a new value is received
+    /// (ie something has copied to the clipboard or selected text)
+    fn selection(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        data_device: DataControlDevice,
+        selection: Selection,
+    ) {
+        debug!("Handler received {selection:?} selection event");
+
+        if !self.clipboard_selections.contains(&selection) {
+            return;
+        }
+
+        match selection {
+            Selection::Clipboard => self.receive_selection(&data_device, selection),
+            Selection::Primary => self.debounce_primary_selection(data_device),
+        }
+    }
 }
 
 impl DataControlOfferHandler for Environment {
@@ -316,13 +503,19 @@ impl DataControlSourceHandler for Environment {
     ) {
         debug!("Handler received source send request event ({mime})");
 
-        if let Some(item) = lock!(self.clipboard).clone() {
+        let Some(selection) = self
+            .copy_paste_sources
+            .iter()
+            .find(|s| s.inner() == source)
+            .map(CopyPasteSource::selection)
+        else {
+            error!("Failed to find source");
+            return;
+        };
+
+        if let Some(item) = lock!(self.selection_item(selection)).clone() {
             let fd = OwnedFd::from(write_pipe);
-            if self
-                .copy_paste_sources
-                .iter_mut()
-                .any(|s| s.inner() == source && MimeType::parse(&mime).is_some())
-            {
+            if mime == *item.mime_type {
                 trace!("Source found, writing to file");
 
                 let mut bytes = match item.value.as_ref() {
@@ -371,7 +564,7 @@ impl DataControlSourceHandler for Environment {
 
                 debug!("Done writing");
             } else {
-                error!("Failed to find source");
+                error!("Requested mime type '{mime}' does not match the copied item");
             }
         }
     }
@@ -429,3 +622,69 @@ fn set_pipe_size(fd: RawFd, size: usize) -> io::Result<i32> {
 
     Ok(new_size)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mime_types(types: &[&str]) -> Vec<String> {
+        types.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn default_filter_picks_first_supported() {
+        let filter = MimeFilter::default();
+        let selected = filter
+            .select(&mime_types(&["text/html", "image/png", "text/plain"]))
+            .expect("to select a type");
+
+        assert_eq!(selected.value, "image/png");
+    }
+
+    #[test]
+    fn deny_removes_types() {
+        let filter = MimeFilter {
+            deny: vec!["image/*".to_string()],
+            ..MimeFilter::default()
+        };
+
+        let selected = filter
+            .select(&mime_types(&["image/png", "text/plain"]))
+            .expect("to select a type");
+        assert_eq!(selected.value, "text/plain");
+
+        assert!(filter.select(&mime_types(&["image/png"])).is_none());
+    }
+
+    #[test]
+    fn accept_allows_other_text_types() {
+        let filter = MimeFilter {
+            accept: vec!["text/*".to_string()],
+            ..MimeFilter::default()
+        };
+
+        let selected = filter
+            .select(&mime_types(&["image/png", "text/html", "text/plain"]))
+            .expect("to select a type");
+        assert_eq!(selected.value, "text/html");
+    }
+
+    #[test]
+    fn prefer_plain_text() {
+        let filter = MimeFilter {
+            accept: vec!["text/*".to_string()],
+            prefer_plain_text: true,
+            ..MimeFilter::default()
+        };
+
+        let selected = filter
+            .select(&mime_types(&["text/html", "text/plain;charset=utf-8"]))
+            .expect("to select a type");
+        assert_eq!(selected.value, "text/plain;charset=utf-8");
+
+        let selected = filter
+            .select(&mime_types(&["text/html"]))
+            .expect("to select a type");
+        assert_eq!(selected.value, "text/html");
+    }
+}
@@ -1,6 +1,8 @@
 use super::device::DataControlDevice;
 use super::manager::DataControlDeviceManagerState;
+use super::Selection;
 use smithay_client_toolkit::data_device_manager::WritePipe;
+use tracing::warn;
 use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
 use wayland_protocols_wlr::data_control::v1::client::zwlr_data_control_source_v1::{
     Event, ZwlrDataControlSourceV1,
@@ -81,12 +83,25 @@ where
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct CopyPasteSource {
     pub(crate) inner: ZwlrDataControlSourceV1,
+    /// The selection this source is offered to.
+    pub(crate) selection: Selection,
 }
 
 impl CopyPasteSource {
-    /// Set the selection of the provided data device as a response to the event with with provided serial.
+    /// Set this source as the selection it is for on the provided data device.
     pub fn set_selection(&self, device: &DataControlDevice) {
-        device.device.set_selection(Some(&self.inner));
+        match self.selection {
+            Selection::Clipboard => device.device.set_selection(Some(&self.inner)),
+            // added in version 2
+            Selection::Primary if device.device.version() < 2 => {
+                warn!("Compositor does not support setting the primary selection");
+            }
+            Selection::Primary => device.device.set_primary_selection(Some(&self.inner)),
+        }
+    }
+
+    pub const fn selection(&self) -> Selection {
+        self.selection
     }
 
     pub const fn inner(&self) -> &ZwlrDataControlSourceV1 {
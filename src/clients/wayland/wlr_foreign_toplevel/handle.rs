@@ -29,6 +29,12 @@ impl ToplevelHandle {
         data.info()
     }
 
+    /// Gets the output the toplevel most recently entered.
+    pub fn output(&self) -> Option<WlOutput> {
+        let data = self.handle.data::<ToplevelHandleData>()?;
+        lock!(data.inner).output.clone()
+    }
+
     pub fn focus(&self, seat: &WlSeat) {
         trace!("Activating handle");
         self.handle.activate(seat);
@@ -57,6 +57,9 @@ impl ToplevelHandleHandler for Environment {
                 trace!("Adding new handle: {info:?}");
                 self.handles.push(handle.clone());
                 if let Some(info) = handle.info() {
+                    #[cfg(feature = "launcher")]
+                    self.update_toplevel_capture(&handle, &info);
+
                     try_send!(self.event_tx, Event::Toplevel(ToplevelEvent::New(info)));
                 }
             }
@@ -78,6 +81,9 @@ impl ToplevelHandleHandler for Environment {
             Some(info) => {
                 trace!("Updating handle: {info:?}");
                 if let Some(info) = handle.info() {
+                    #[cfg(feature = "launcher")]
+                    self.update_toplevel_capture(&handle, &info);
+
                     try_send!(self.event_tx, Event::Toplevel(ToplevelEvent::Update(info)));
                 }
             }
@@ -0,0 +1,419 @@
+use super::{Client, Environment, Event, Request, ToplevelHandle, ToplevelInfo};
+use crate::{send, try_send};
+use smithay_client_toolkit::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay_client_toolkit::shm::raw::RawPool;
+use smithay_client_toolkit::shm::{Shm, ShmHandler};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, error, trace};
+use wayland_client::globals::{BindError, GlobalList};
+use wayland_client::protocol::wl_buffer::WlBuffer;
+use wayland_client::protocol::wl_shm::Format;
+use wayland_client::{delegate_noop, Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use wayland_protocols_wlr::screencopy::v1::client::zwlr_screencopy_frame_v1::{
+    self, Flags, ZwlrScreencopyFrameV1,
+};
+use wayland_protocols_wlr::screencopy::v1::client::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1;
+
+/// How long to wait after a toplevel is focused before capturing it,
+/// giving the compositor time to raise and redraw it.
+const CAPTURE_DELAY: Duration = Duration::from_millis(300);
+
+/// The largest width or height of a preview, in pixels.
+const PREVIEW_SIZE: u32 = 256;
+
+/// A scaled-down capture of the output a toplevel was on when it was last focused.
+#[derive(Debug, Clone)]
+pub struct ToplevelPreview {
+    /// The ID of the toplevel.
+    pub id: usize,
+    pub width: i32,
+    pub height: i32,
+    /// Packed 8-bit RGB pixels, with no padding between rows.
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct ScreencopyState {
+    manager: ZwlrScreencopyManagerV1,
+    /// Whether previews have been requested.
+    enabled: bool,
+    /// The ID of the focused toplevel,
+    /// so that it is only captured once each time it gains focus.
+    focused: Option<usize>,
+    captures: Vec<Capture>,
+}
+
+/// An in-progress capture of a toplevel's output.
+#[derive(Debug)]
+struct Capture {
+    toplevel_id: usize,
+    frame: ZwlrScreencopyFrameV1,
+    buffer: Option<CaptureBuffer>,
+    y_invert: bool,
+}
+
+#[derive(Debug)]
+struct CaptureBuffer {
+    pool: RawPool,
+    buffer: WlBuffer,
+    width: u32,
+    height: u32,
+    stride: u32,
+    /// The offsets of the red, green and blue bytes in each pixel.
+    offsets: [usize; 3],
+}
+
+impl ScreencopyState {
+    pub fn bind<State>(globals: &GlobalList, qh: &QueueHandle<State>) -> Result<Self, BindError>
+    where
+        State: Dispatch<ZwlrScreencopyManagerV1, ()> + 'static,
+    {
+        let manager = globals.bind(qh, 1..=3, ())?;
+        debug!("Bound to ZwlrScreencopyManagerV1 global");
+
+        Ok(Self {
+            manager,
+            enabled: false,
+            focused: None,
+            captures: vec![],
+        })
+    }
+}
+
+/// Gets the offsets of the red, green and blue bytes in a pixel,
+/// for the 32-bit formats which can be read.
+///
+/// Formats are named from the most significant byte,
+/// so are stored in reverse order on little-endian systems.
+fn rgb_offsets(format: Format) -> Option<[usize; 3]> {
+    match format {
+        Format::Argb8888 | Format::Xrgb8888 => Some([2, 1, 0]),
+        Format::Abgr8888 | Format::Xbgr8888 => Some([0, 1, 2]),
+        _ => None,
+    }
+}
+
+/// Scales a captured frame down to fit within the preview size,
+/// keeping its aspect ratio, and packs it into RGB bytes.
+///
+/// Pixels are sampled by nearest neighbour.
+fn scale_frame(
+    data: &[u8],
+    (width, height, stride): (u32, u32, u32),
+    offsets: [usize; 3],
+    y_invert: bool,
+) -> (u32, u32, Vec<u8>) {
+    let largest = width.max(height).max(1);
+    let scale = |size: u32| {
+        (u64::from(size) * u64::from(PREVIEW_SIZE.min(largest)) / u64::from(largest)).max(1)
+    };
+
+    let (out_width, out_height) = (scale(width), scale(height));
+    let mut pixels = Vec::with_capacity((out_width * out_height * 3) as usize);
+
+    for y in 0..out_height {
+        let mut src_y = y * u64::from(height) / out_height;
+        if y_invert {
+            src_y = u64::from(height) - 1 - src_y;
+        }
+
+        for x in 0..out_width {
+            let src_x = x * u64::from(width) / out_width;
+            let index = (src_y * u64::from(stride) + src_x * 4) as usize;
+
+            for offset in offsets {
+                pixels.push(data.get(index + offset).copied().unwrap_or_default());
+            }
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    (out_width as u32, out_height as u32, pixels)
+}
+
+impl Client {
+    /// Starts capturing previews of toplevels as they are focused.
+    pub fn enable_toplevel_previews(&self) {
+        send!(self.tx, Request::EnableToplevelPreviews);
+    }
+
+    /// Subscribes to previews of toplevels,
+    /// which are sent shortly after each is focused.
+    pub fn subscribe_toplevel_previews(&self) -> broadcast::Receiver<ToplevelPreview> {
+        self.toplevel_preview_channel.0.subscribe()
+    }
+}
+
+impl Environment {
+    pub(super) fn enable_toplevel_previews(&mut self) {
+        match self.screencopy_state.as_mut() {
+            Some(state) => state.enabled = true,
+            None => {
+                debug!("Ignoring preview request as the compositor does not support screencopy")
+            }
+        }
+    }
+
+    /// Captures the toplevel's output when it gains focus,
+    /// once the compositor has had time to show it,
+    /// if previews are enabled.
+    pub(super) fn update_toplevel_capture(&mut self, handle: &ToplevelHandle, info: &ToplevelInfo) {
+        let Some(state) = self.screencopy_state.as_mut().filter(|state| state.enabled) else {
+            return;
+        };
+
+        if !info.focused {
+            if state.focused == Some(info.id) {
+                state.focused = None;
+            }
+
+            return;
+        }
+
+        if state.focused == Some(info.id) {
+            return;
+        }
+
+        state.focused = Some(info.id);
+
+        let handle = handle.clone();
+
+        let timer = self.loop_handle.insert_source(
+            Timer::from_duration(CAPTURE_DELAY),
+            move |_, (), state| {
+                // the toplevel may have lost focus or closed while waiting
+                if handle.info().is_some_and(|info| info.focused) {
+                    state.capture_toplevel(&handle);
+                }
+
+                TimeoutAction::Drop
+            },
+        );
+
+        if let Err(err) = timer {
+            error!("Failed to insert toplevel capture timer: {err:?}");
+        }
+    }
+
+    fn capture_toplevel(&mut self, handle: &ToplevelHandle) {
+        let (Some(info), Some(output)) = (handle.info(), handle.output()) else {
+            return;
+        };
+
+        let Some(state) = self.screencopy_state.as_mut() else {
+            return;
+        };
+
+        trace!("Capturing output for toplevel {}", info.id);
+
+        let frame = state
+            .manager
+            .capture_output(0, &output, &self.queue_handle, ());
+
+        state.captures.push(Capture {
+            toplevel_id: info.id,
+            frame,
+            buffer: None,
+            y_invert: false,
+        });
+    }
+
+    /// Finishes the capture for the frame,
+    /// sending its preview if it succeeded.
+    fn finish_capture(&mut self, frame: &ZwlrScreencopyFrameV1, success: bool) {
+        let Some(state) = self.screencopy_state.as_mut() else {
+            return;
+        };
+
+        let Some(index) = state
+            .captures
+            .iter()
+            .position(|capture| &capture.frame == frame)
+        else {
+            return;
+        };
+
+        let capture = state.captures.swap_remove(index);
+        capture.frame.destroy();
+
+        let Some(mut buffer) = capture.buffer else {
+            return;
+        };
+
+        if success {
+            let (width, height, pixels) = scale_frame(
+                buffer.pool.mmap(),
+                (buffer.width, buffer.height, buffer.stride),
+                buffer.offsets,
+                capture.y_invert,
+            );
+
+            #[allow(clippy::cast_possible_wrap)]
+            let preview = ToplevelPreview {
+                id: capture.toplevel_id,
+                width: width as i32,
+                height: height as i32,
+                pixels,
+            };
+
+            try_send!(self.event_tx, Event::ToplevelPreview(preview));
+        } else {
+            debug!("Failed to capture toplevel {}", capture.toplevel_id);
+        }
+
+        buffer.buffer.destroy();
+    }
+
+    /// Creates a buffer to copy the frame into, if it is in a readable format.
+    fn create_capture_buffer(
+        &mut self,
+        frame: &ZwlrScreencopyFrameV1,
+        format: Format,
+        (width, height, stride): (u32, u32, u32),
+    ) {
+        let Some(offsets) = rgb_offsets(format) else {
+            trace!("Ignoring unreadable capture format {format:?}");
+            return;
+        };
+
+        let Some(capture) = self
+            .screencopy_state
+            .as_mut()
+            .and_then(|state| state.captures.iter_mut().find(|c| &c.frame == frame))
+        else {
+            return;
+        };
+
+        if capture.buffer.is_some() {
+            return;
+        }
+
+        let mut pool = match RawPool::new((stride * height) as usize, &self.shm) {
+            Ok(pool) => pool,
+            Err(err) => {
+                error!("Failed to create capture buffer: {err:?}");
+                return;
+            }
+        };
+
+        #[allow(clippy::cast_possible_wrap)]
+        let buffer = pool.create_buffer(
+            0,
+            width as i32,
+            height as i32,
+            stride as i32,
+            format,
+            (),
+            &self.queue_handle,
+        );
+
+        capture.buffer = Some(CaptureBuffer {
+            pool,
+            buffer,
+            width,
+            height,
+            stride,
+            offsets,
+        });
+    }
+
+    /// Copies the frame into its buffer,
+    /// or gives up if no readable format was offered.
+    fn copy_capture(&mut self, frame: &ZwlrScreencopyFrameV1) {
+        let buffer = self
+            .screencopy_state
+            .as_ref()
+            .and_then(|state| state.captures.iter().find(|c| &c.frame == frame))
+            .and_then(|capture| capture.buffer.as_ref())
+            .map(|buffer| buffer.buffer.clone());
+
+        match buffer {
+            Some(buffer) => frame.copy(&buffer),
+            None => self.finish_capture(frame, false),
+        }
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, ()> for Environment {
+    fn event(
+        state: &mut Self,
+        frame: &ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_screencopy_frame_v1::Event::Buffer {
+                format: WEnum::Value(format),
+                width,
+                height,
+                stride,
+            } => {
+                state.create_capture_buffer(frame, format, (width, height, stride));
+
+                // before version 3, each frame offers a single buffer type,
+                // which must be copied into right away
+                if frame.version() < 3 {
+                    state.copy_capture(frame);
+                }
+            }
+            zwlr_screencopy_frame_v1::Event::BufferDone => state.copy_capture(frame),
+            zwlr_screencopy_frame_v1::Event::Flags {
+                flags: WEnum::Value(flags),
+            } => {
+                let capture = state
+                    .screencopy_state
+                    .as_mut()
+                    .and_then(|state| state.captures.iter_mut().find(|c| &c.frame == frame));
+
+                if let Some(capture) = capture {
+                    capture.y_invert = flags.contains(Flags::YInvert);
+                }
+            }
+            zwlr_screencopy_frame_v1::Event::Ready { .. } => state.finish_capture(frame, true),
+            zwlr_screencopy_frame_v1::Event::Failed => state.finish_capture(frame, false),
+            _ => {}
+        }
+    }
+}
+
+impl ShmHandler for Environment {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
+delegate_noop!(Environment: ZwlrScreencopyManagerV1);
+delegate_noop!(Environment: ignore WlBuffer);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_frames_to_preview() {
+        // 512x2 frame in BGRX order, red on the top row and blue on the bottom
+        let (width, height, stride) = (512, 2, 512 * 4);
+        let mut data = vec![];
+        data.extend([0, 0, 255, 0].repeat(width as usize));
+        data.extend([255, 0, 0, 0].repeat(width as usize));
+
+        let offsets = rgb_offsets(Format::Xrgb8888).expect("readable format");
+
+        let (w, h, pixels) = scale_frame(&data, (width, height, stride), offsets, false);
+        assert_eq!((w, h), (256, 1));
+        assert_eq!(pixels.len(), 256 * 3);
+        assert_eq!(&pixels[..3], &[255, 0, 0]);
+
+        let (_, _, pixels) = scale_frame(&data, (width, height, stride), offsets, true);
+        assert_eq!(&pixels[..3], &[0, 0, 255]);
+
+        // small frames are left at their size
+        let (w, h, _) = scale_frame(&data, (4, 2, stride), offsets, false);
+        assert_eq!((w, h), (4, 2));
+
+        assert_eq!(rgb_offsets(Format::Rgb565), None);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{glib_recv_mpsc, send, Ironbar};
+use color_eyre::{Report, Result};
+use glib::translate::ToGlibPtr;
+use glib::SignalHandlerId;
+use gtk::gdk;
+use gtk::prelude::*;
+use gtk_layer_shell::LayerShell;
+use smithay_client_toolkit::reexports::protocols::wp::idle_inhibit::zv1::client::{
+    zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1, zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1,
+};
+use std::collections::HashSet;
+use std::ffi::c_void;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
+use wayland_client::backend::{Backend, ObjectId};
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, Proxy, QueueHandle};
+
+/// How long to wait before trying again to inhibit
+/// when no bar is visible.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+extern "C" {
+    fn gdk_wayland_display_get_wl_display(display: *mut gdk::ffi::GdkDisplay) -> *mut c_void;
+    fn gdk_wayland_window_get_wl_surface(window: *mut gdk::ffi::GdkWindow) -> *mut c_void;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum IdleInhibitRequest {
+    /// Adds or removes a holder.
+    Set { id: usize, inhibit: bool },
+    /// Creates the inhibitor again if it is wanted,
+    /// as the bar it was on has been hidden or none was visible.
+    Refresh,
+}
+
+/// Dispatches events for the objects bound on GTK's connection,
+/// none of which need handling.
+struct Handler;
+
+impl Dispatch<WlRegistry, GlobalListContents> for Handler {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(Handler: ZwpIdleInhibitManagerV1);
+delegate_noop!(Handler: ZwpIdleInhibitorV1);
+
+/// An inhibitor, along with the bar window it was created on.
+struct Inhibitor {
+    inhibitor: ZwpIdleInhibitorV1,
+    window: gtk::Window,
+    unmap_handler: SignalHandlerId,
+}
+
+/// Holds the idle inhibitor while any guard wants it.
+///
+/// The protocol ties inhibitors to a surface,
+/// which only inhibits idle while it is visible,
+/// so the inhibitor is created on a bar's layer surface.
+/// This uses GTK's Wayland connection, as bar surfaces belong to it.
+struct IdleInhibitState {
+    connection: Connection,
+    queue: EventQueue<Handler>,
+    manager: ZwpIdleInhibitManagerV1,
+    inhibitor: Option<Inhibitor>,
+    /// The IDs of guards currently requesting inhibition.
+    holders: HashSet<usize>,
+    tx: mpsc::UnboundedSender<IdleInhibitRequest>,
+}
+
+impl IdleInhibitState {
+    /// Binds to the inhibit manager on GTK's Wayland connection.
+    ///
+    /// This must be called from the GTK thread.
+    fn bind(tx: mpsc::UnboundedSender<IdleInhibitRequest>) -> Result<Self> {
+        let display = gdk::Display::default().ok_or_else(|| Report::msg("No GDK display"))?;
+
+        // SAFETY: this returns null if the display is not a Wayland display.
+        let ptr = unsafe { gdk_wayland_display_get_wl_display(display.to_glib_none().0) };
+        if ptr.is_null() {
+            return Err(Report::msg("GDK display is not a Wayland display"));
+        }
+
+        // SAFETY: GDK keeps the display open for as long as Ironbar runs.
+        let backend = unsafe { Backend::from_foreign_display(ptr.cast()) };
+        let connection = Connection::from_backend(backend);
+
+        let (globals, queue) = registry_queue_init::<Handler>(&connection)?;
+        let manager = globals.bind(&queue.handle(), 1..=1, ())?;
+        debug!("Bound to ZwpIdleInhibitManagerV1 global");
+
+        Ok(Self {
+            connection,
+            queue,
+            manager,
+            inhibitor: None,
+            holders: HashSet::new(),
+            tx,
+        })
+    }
+
+    /// Adds or removes a holder,
+    /// creating or destroying the inhibitor as required.
+    fn handle(&mut self, request: IdleInhibitRequest) {
+        match request {
+            IdleInhibitRequest::Set { id, inhibit: true } => {
+                self.holders.insert(id);
+            }
+            IdleInhibitRequest::Set { id, inhibit: false } => {
+                self.holders.remove(&id);
+            }
+            IdleInhibitRequest::Refresh => self.release(),
+        }
+
+        if self.holders.is_empty() {
+            self.release();
+        } else if self.inhibitor.is_none() {
+            self.inhibit();
+        }
+
+        // none of the objects send events we use,
+        // but the queue is drained so they do not build up.
+        if let Err(err) = self.queue.dispatch_pending(&mut Handler) {
+            error!("{err:?}");
+        }
+
+        if let Err(err) = self.connection.flush() {
+            error!("{err:?}");
+        }
+    }
+
+    fn inhibit(&mut self) {
+        let Some((window, surface)) = self.bar_surface() else {
+            debug!("No bar is visible to inhibit idle on, trying again later");
+
+            let tx = self.tx.clone();
+            glib::timeout_add_local_once(RETRY_INTERVAL, move || {
+                tx.send(IdleInhibitRequest::Refresh).ok();
+            });
+
+            return;
+        };
+
+        debug!("Inhibiting idle");
+        let inhibitor = self
+            .manager
+            .create_inhibitor(&surface, &self.queue.handle(), ());
+
+        let tx = self.tx.clone();
+        let unmap_handler = window.connect_unmap(move |_| {
+            tx.send(IdleInhibitRequest::Refresh).ok();
+        });
+
+        self.inhibitor = Some(Inhibitor {
+            inhibitor,
+            window,
+            unmap_handler,
+        });
+    }
+
+    fn release(&mut self) {
+        if let Some(inhibitor) = self.inhibitor.take() {
+            debug!("Releasing idle inhibitor");
+            inhibitor.inhibitor.destroy();
+            inhibitor.window.disconnect(inhibitor.unmap_handler);
+        }
+    }
+
+    /// Gets the surface of a visible bar.
+    fn bar_surface(&self) -> Option<(gtk::Window, WlSurface)> {
+        gtk::Window::list_toplevels()
+            .into_iter()
+            .filter_map(|widget| widget.downcast::<gtk::Window>().ok())
+            .filter(|window| {
+                window.is_visible()
+                    && window.is_layer_window()
+                    && window.namespace().as_deref() == Some(env!("CARGO_PKG_NAME"))
+            })
+            .find_map(|window| {
+                let gdk_window = window.window()?;
+
+                // SAFETY: this returns null if the window has no surface.
+                let ptr = unsafe { gdk_wayland_window_get_wl_surface(gdk_window.to_glib_none().0) };
+                if ptr.is_null() {
+                    return None;
+                }
+
+                // SAFETY: the surface is only used to create the inhibitor,
+                // while the window is still mapped.
+                let id = unsafe { ObjectId::from_ptr(WlSurface::interface(), ptr.cast()) }.ok()?;
+                let surface = WlSurface::from_id(&self.connection, id).ok()?;
+
+                Some((window, surface))
+            })
+    }
+}
+
+/// Starts handling inhibit requests on the GTK thread.
+///
+/// The inhibit manager is bound when first requested.
+pub(super) fn spawn() -> mpsc::UnboundedSender<IdleInhibitRequest> {
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let mut state = None::<IdleInhibitState>;
+    let mut failed = false;
+
+    {
+        let tx = tx.clone();
+        glib_recv_mpsc!(rx, request => {
+            if state.is_none() && !failed {
+                match IdleInhibitState::bind(tx.clone()) {
+                    Ok(new_state) => state = Some(new_state),
+                    Err(err) => {
+                        warn!("Failed to bind to idle inhibit manager global: {err:?}");
+                        failed = true;
+                    }
+                }
+            }
+
+            match state.as_mut() {
+                Some(state) => state.handle(request),
+                None => debug!("Ignoring idle inhibit request as the compositor does not support it"),
+            }
+        });
+    }
+
+    tx
+}
+
+/// Prevents the compositor from going idle for as long as it is held.
+///
+/// The inhibitor is released when every guard has been dropped.
+/// The compositor also releases it if Ironbar exits for any reason,
+/// as this closes the Wayland connection.
+#[derive(Debug)]
+pub struct IdleInhibitGuard {
+    id: usize,
+    tx: mpsc::UnboundedSender<IdleInhibitRequest>,
+}
+
+impl Drop for IdleInhibitGuard {
+    fn drop(&mut self) {
+        send!(
+            self.tx,
+            IdleInhibitRequest::Set {
+                id: self.id,
+                inhibit: false,
+            }
+        );
+    }
+}
+
+impl super::Client {
+    /// Prevents the compositor from going idle
+    /// until the returned guard is dropped.
+    pub fn inhibit_idle(&self) -> IdleInhibitGuard {
+        let id = Ironbar::unique_id();
+
+        send!(
+            self.idle_inhibit_tx,
+            IdleInhibitRequest::Set { id, inhibit: true }
+        );
+
+        IdleInhibitGuard {
+            id,
+            tx: self.idle_inhibit_tx.clone(),
+        }
+    }
+}
@@ -1,10 +1,16 @@
 use crate::dynamic_value::{dynamic_string, DynamicBool};
-use crate::script::{Script, ScriptInput};
+use crate::gtk_helpers::{emit_click, IronbarGtkExt};
+use crate::script::{Script, ScriptEnv, ScriptInput};
 use glib::Propagation;
 use gtk::gdk::ScrollDirection;
 use gtk::prelude::*;
-use gtk::{EventBox, Orientation, Revealer, RevealerTransitionType};
+use gtk::{
+    EventBox, EventControllerScroll, EventControllerScrollFlags, EventSequenceState,
+    GestureLongPress, GestureMultiPress, Orientation, PropagationPhase, Revealer,
+    RevealerTransitionType,
+};
 use serde::Deserialize;
+use std::rc::Rc;
 use tracing::trace;
 
 /// The following are module-level options which are present on **all** modules.
@@ -31,10 +37,13 @@ pub struct CommonConfig {
     /// Sets one or more CSS classes,
     /// allowing you to target it in CSS using `.class`.
     ///
+    /// This can be a list of classes,
+    /// or a single string with the classes separated by spaces.
+    ///
     /// Unlike [name](#name), the `class` property is not expected to be unique.
     ///
     /// **Default**: `null`
-    pub class: Option<String>,
+    pub class: Option<ClassList>,
 
     /// Shows this text on hover.
     /// Supports embedding scripts between `{{double braces}}`.
@@ -154,9 +163,169 @@ pub struct CommonConfig {
     /// ```
     pub on_mouse_exit: Option<ScriptInput>,
 
+    /// Whether the click and scroll scripts above should take priority
+    /// over the module's own handling of that button or scroll.
+    ///
+    /// By default, scripts only run for input the module does not use itself,
+    /// such as right-clicking a module which only responds to left-clicks.
+    /// When enabled, input with a script set is captured before it reaches the module.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub override_default_action: bool,
+
     /// Prevents the popup from opening on-click for this widget.
     #[serde(default)]
     pub disable_popup: bool,
+
+    /// The names of the outputs to show the module on.
+    /// The module is not created at all on other outputs.
+    ///
+    /// Leave unset to show the module on all outputs.
+    ///
+    /// **Default**: `null`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// { outputs = [ "DP-1" "HDMI-A-1" ] }
+    /// ```
+    pub outputs: Option<Vec<String>>,
+
+    /// The order in which to move the module into the overflow menu,
+    /// when the bar's `overflow` option is enabled.
+    /// Modules with a higher priority stay on the bar for longest.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    pub priority: i32,
+
+    /// Whether the module and its scripts should use their own timers,
+    /// rather than the shared timer aligning intervals across modules.
+    ///
+    /// Intervals which are not a whole number of seconds always use their own timer.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub dedicated_timer: bool,
+
+    /// A condition which is checked once when the bar is created.
+    /// If it is not met, the module is not created at all.
+    ///
+    /// Supports `env:NAME` (set and not empty),
+    /// `env:NAME == "value"`, `host == "value"`,
+    /// `file:path` (exists) and `cmd:command` (exits with code zero).
+    /// These can be combined using `!`, `&&`, `||` and brackets.
+    /// A `cmd:` check always takes the rest of the expression.
+    ///
+    /// If the condition cannot be evaluated, the module is loaded.
+    ///
+    /// **Default**: `null`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// { load_if = "host == \"laptop\" && file:/sys/class/power_supply/BAT0" }
+    /// ```
+    pub load_if: Option<String>,
+
+    /// Options for using the module with a touchscreen.
+    /// See [touch](#touch) for details.
+    #[serde(default)]
+    pub touch: TouchConfig,
+}
+
+/// Tapping a module acts as a left-click,
+/// and holding a touch down acts as a right-click.
+///
+/// Modules which switch or adjust something on scroll,
+/// such as `workspaces` and `volume`, can also be swiped over.
+/// These only respond to touchscreens, and do not change mouse or touchpad input.
+///
+/// # Example
+///
+/// ```corn
+/// { touch.long_press_ms = 400 touch.swipe = false }
+/// ```
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TouchConfig {
+    /// The number of milliseconds a touch must be held down
+    /// to act as a right-click.
+    ///
+    /// GTK limits this to between half and double
+    /// the system long-press time, which is 500ms by default.
+    ///
+    /// **Default**: `500`
+    #[serde(default = "default_long_press_ms")]
+    pub long_press_ms: u32,
+
+    /// Whether swiping over the module acts like scrolling,
+    /// on modules which support it.
+    /// Swiping up or left acts like scrolling up.
+    ///
+    /// Each 32 pixels swiped counts as one notch of a mouse wheel,
+    /// so this follows the bar's `scroll` options.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    pub swipe: bool,
+}
+
+impl Default for TouchConfig {
+    fn default() -> Self {
+        Self {
+            long_press_ms: default_long_press_ms(),
+            swipe: true,
+        }
+    }
+}
+
+const fn default_long_press_ms() -> u32 {
+    500
+}
+
+impl TouchConfig {
+    /// Gets the GTK long-press delay factor for the configured time,
+    /// given the system long-press time.
+    fn delay_factor(self, system_ms: u32) -> f64 {
+        // the range GTK accepts for the factor
+        const MIN: f64 = 0.5;
+        const MAX: f64 = 2.0;
+
+        if system_ms == 0 {
+            return 1.0;
+        }
+
+        (f64::from(self.long_press_ms) / f64::from(system_ms)).clamp(MIN, MAX)
+    }
+}
+
+/// One or more CSS classes.
+///
+/// This can either be a list,
+/// or a single string with the classes separated by spaces.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ClassList {
+    List(Vec<String>),
+    Single(String),
+}
+
+impl ClassList {
+    /// Gets each class.
+    ///
+    /// Classes containing spaces are split,
+    /// as GTK would otherwise treat them as a single class.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        let classes = match self {
+            Self::List(classes) => classes.as_slice(),
+            Self::Single(class) => std::slice::from_ref(class),
+        };
+
+        classes.iter().flat_map(|class| class.split_whitespace())
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -214,52 +383,135 @@ impl TransitionType {
     }
 }
 
+/// Gets the script for a mouse button,
+/// from scripts ordered left, middle, right.
+fn button_script(scripts: &[Option<Script>; 3], button: u32) -> Option<&Script> {
+    button
+        .checked_sub(1)
+        .and_then(|index| scripts.get(index as usize))
+        .and_then(Option::as_ref)
+}
+
+/// Gets the script for a scroll delta, negative for up.
+/// Scrolls which do not move vertically have no script.
+fn scroll_script_for<'a>(
+    up: &'a Option<Script>,
+    down: &'a Option<Script>,
+    dy: f64,
+) -> Option<&'a Script> {
+    if dy < 0.0 {
+        up.as_ref()
+    } else if dy > 0.0 {
+        down.as_ref()
+    } else {
+        None
+    }
+}
+
 impl CommonConfig {
     /// Configures the module's container according to the common config options.
-    pub fn install_events(mut self, container: &EventBox, revealer: &Revealer) {
-        self.install_show_if(container, revealer);
-
-        let left_click_script = self.on_click_left.map(Script::new_polling);
-        let middle_click_script = self.on_click_middle.map(Script::new_polling);
-        let right_click_script = self.on_click_right.map(Script::new_polling);
-
-        container.connect_button_press_event(move |_, event| {
-            let script = match event.button() {
-                1 => left_click_script.as_ref(),
-                2 => middle_click_script.as_ref(),
-                3 => right_click_script.as_ref(),
-                _ => None,
-            };
+    pub fn install_events(mut self, container: &EventBox, revealer: &Revealer, env: &ScriptEnv) {
+        self.install_show_if(container, revealer, env);
 
-            if let Some(script) = script {
-                trace!("Running on-click script: {}", event.button());
-                script.run_as_oneshot(None);
-            }
+        let click_script = |input: Option<ScriptInput>, button: u32| {
+            input.map(|input| Script::new_polling(input).with_env(&env.with_button(button)))
+        };
 
-            Propagation::Proceed
-        });
+        let click_scripts = Rc::new([
+            click_script(self.on_click_left, 1),
+            click_script(self.on_click_middle, 2),
+            click_script(self.on_click_right, 3),
+        ]);
 
-        let scroll_up_script = self.on_scroll_up.map(Script::new_polling);
-        let scroll_down_script = self.on_scroll_down.map(Script::new_polling);
+        if click_scripts.iter().any(Option::is_some) {
+            container.set_interactive();
+        }
 
-        container.connect_scroll_event(move |_, event| {
-            let script = match event.direction() {
-                ScrollDirection::Up => scroll_up_script.as_ref(),
-                ScrollDirection::Down => scroll_down_script.as_ref(),
-                _ => None,
-            };
+        self.install_long_press(container, &click_scripts);
+
+        if self.override_default_action {
+            let gesture = GestureMultiPress::new(container);
+            gesture.set_button(0);
+            gesture.set_propagation_phase(PropagationPhase::Capture);
+
+            gesture.connect_pressed(move |gesture, _, _, _| {
+                let button = gesture.current_button();
+
+                if let Some(script) = button_script(&click_scripts, button) {
+                    trace!("Running on-click script: {button}");
+                    script.run_as_oneshot(None);
+
+                    // stops the press reaching the module
+                    gesture.set_state(EventSequenceState::Claimed);
+                }
+            });
+
+            container.set_tag("click-controller", gesture);
+        } else {
+            container.connect_button_press_event(move |_, event| {
+                if let Some(script) = button_script(&click_scripts, event.button()) {
+                    trace!("Running on-click script: {}", event.button());
+                    script.run_as_oneshot(None);
+                }
+
+                Propagation::Proceed
+            });
+        }
+
+        let scroll_script = |input: Option<ScriptInput>| {
+            input.map(|input| Script::new_polling(input).with_env(env))
+        };
+
+        let scroll_up_script = scroll_script(self.on_scroll_up);
+        let scroll_down_script = scroll_script(self.on_scroll_down);
+
+        if self.override_default_action {
+            if scroll_up_script.is_some() || scroll_down_script.is_some() {
+                let controller = EventControllerScroll::new(
+                    container,
+                    EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+                );
+                controller.set_propagation_phase(PropagationPhase::Capture);
+
+                // the controller claims each scroll it receives in the capture phase,
+                // so the module never sees it
+                controller.connect_scroll(move |_, _, dy| {
+                    let script = scroll_script_for(&scroll_up_script, &scroll_down_script, dy);
+
+                    if let Some(script) = script {
+                        trace!("Running on-scroll script: {dy}");
+                        script.run_as_oneshot(None);
+                    }
+                });
 
-            if let Some(script) = script {
-                trace!("Running on-scroll script: {}", event.direction());
-                script.run_as_oneshot(None);
+                container.set_tag("scroll-controller", controller);
             }
+        } else {
+            container.connect_scroll_event(move |_, event| {
+                let dy = match event.direction() {
+                    ScrollDirection::Up => -1.0,
+                    ScrollDirection::Down => 1.0,
+                    _ => 0.0,
+                };
+
+                let script = scroll_script_for(&scroll_up_script, &scroll_down_script, dy);
+
+                match script {
+                    Some(script) => {
+                        trace!("Running on-scroll script: {}", event.direction());
+                        script.run_as_oneshot(None);
 
-            Propagation::Proceed
-        });
+                        Propagation::Stop
+                    }
+                    None => Propagation::Proceed,
+                }
+            });
+        }
 
         macro_rules! install_oneshot {
             ($option:expr, $method:ident) => {
                 $option.map(Script::new_polling).map(|script| {
+                    let script = script.with_env(env);
                     container.$method(move |_, _| {
                         script.run_as_oneshot(None);
                         Propagation::Proceed
@@ -273,13 +525,49 @@ impl CommonConfig {
 
         if let Some(tooltip) = self.tooltip {
             let container = container.clone();
-            dynamic_string(&tooltip, move |string| {
+            dynamic_string(&tooltip, env, move |string| {
                 container.set_tooltip_text(Some(&string));
             });
         }
     }
 
-    fn install_show_if(&mut self, container: &EventBox, revealer: &Revealer) {
+    /// Turns holding a touch down on the module into a right-click.
+    ///
+    /// Taps are already sent to widgets as left-clicks by GTK.
+    fn install_long_press(&self, container: &EventBox, click_scripts: &Rc<[Option<Script>; 3]>) {
+        const BUTTON_RIGHT: u32 = 3;
+
+        let gesture = GestureLongPress::new(container);
+        gesture.set_touch_only(true);
+        gesture.set_propagation_phase(PropagationPhase::Capture);
+
+        if let Some(settings) = gtk::Settings::default() {
+            gesture.set_delay_factor(self.touch.delay_factor(settings.gtk_long_press_time()));
+        }
+
+        let override_default_action = self.override_default_action;
+        let click_scripts = click_scripts.clone();
+        let container = container.clone();
+
+        gesture.connect_pressed(move |gesture, x, y| {
+            // stops the touch also counting as a tap once released
+            gesture.set_state(EventSequenceState::Claimed);
+
+            let script = button_script(&click_scripts, BUTTON_RIGHT);
+
+            match script {
+                Some(script) if override_default_action => {
+                    trace!("Running on-click script from long press");
+                    script.run_as_oneshot(None);
+                }
+                _ => emit_click(&container, BUTTON_RIGHT, x, y),
+            }
+        });
+
+        container.set_tag("long-press-controller", gesture);
+    }
+
+    fn install_show_if(&mut self, container: &EventBox, revealer: &Revealer, env: &ScriptEnv) {
         self.show_if.take().map_or_else(
             || {
                 container.show_all();
@@ -291,7 +579,7 @@ impl CommonConfig {
                     let revealer = revealer.clone();
                     let container = container.clone();
 
-                    show_if.subscribe(move |success| {
+                    show_if.subscribe(env, move |success| {
                         if success {
                             container.show_all();
                         }
@@ -308,3 +596,42 @@ impl CommonConfig {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_class_lists() {
+        let classes = |json: &str| {
+            serde_json::from_str::<ClassList>(json)
+                .expect("class list to be valid")
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(classes(r#""primary""#), ["primary"]);
+        assert_eq!(classes(r#""primary  wide""#), ["primary", "wide"]);
+        assert_eq!(
+            classes(r#"["primary", "wide dim"]"#),
+            ["primary", "wide", "dim"]
+        );
+        assert!(classes("[]").is_empty());
+    }
+
+    #[test]
+    fn long_press_delay_factor() {
+        let touch = |long_press_ms| TouchConfig {
+            long_press_ms,
+            swipe: true,
+        };
+
+        assert!((touch(500).delay_factor(500) - 1.0).abs() < f64::EPSILON);
+        assert!((touch(750).delay_factor(500) - 1.5).abs() < f64::EPSILON);
+
+        // limited to what GTK accepts
+        assert!((touch(100).delay_factor(500) - 0.5).abs() < f64::EPSILON);
+        assert!((touch(5000).delay_factor(500) - 2.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,35 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+/// Writes bar state to a JSON file whenever it changes,
+/// for use by external programs such as desktop widgets.
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExportConfig {
+    /// The state to write.
+    ///
+    /// Each entry is either a whole section, such as `music`,
+    /// or a single field in a section, such as `music.title`.
+    /// Sources are only started for the sections used here.
+    ///
+    /// **Valid sections**: `ironvars`, `workspaces`, `music`, `volume`, `battery`
+    /// <br>
+    /// **Required**
+    pub fields: Vec<String>,
+
+    /// The file to write to.
+    ///
+    /// **Default**: `$XDG_RUNTIME_DIR/ironbar/state.json`
+    pub path: Option<PathBuf>,
+
+    /// The time in milliseconds to wait after a change before writing,
+    /// so that changes made together are written once.
+    ///
+    /// **Default**: `250`
+    #[serde(default = "default_debounce")]
+    pub debounce: u64,
+}
+
+const fn default_debounce() -> u64 {
+    250
+}
@@ -1,7 +1,8 @@
-use super::{BarConfig, BarPosition, MonitorConfig};
+use super::{BarConfig, BarHeight, BarLength, BarPosition, MonitorConfig};
 use color_eyre::{Help, Report};
 use gtk::Orientation;
 use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
 
 // Manually implement for better untagged enum error handling:
 // currently open pr: https://github.com/serde-rs/serde/pull/1544
@@ -66,6 +67,137 @@ pub fn schema_layer(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schem
     schema.into()
 }
 
+pub fn deserialize_height<'de, D>(deserializer: D) -> Result<BarHeight, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum HeightInput {
+        Pixels(i32),
+        String(String),
+    }
+
+    match HeightInput::deserialize(deserializer)? {
+        HeightInput::Pixels(pixels) => Ok(BarHeight::Pixels(pixels)),
+        HeightInput::String(str) => str.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(feature = "schema")]
+pub fn schema_height(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    use schemars::schema::{SchemaObject, SubschemaValidation};
+    use schemars::JsonSchema;
+
+    let schema = SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(vec![<i32>::json_schema(gen), <String>::json_schema(gen)]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+
+    schema.into()
+}
+
+pub fn deserialize_length<'de, D>(deserializer: D) -> Result<Option<BarLength>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LengthInput {
+        Pixels(i32),
+        String(String),
+    }
+
+    let value = Option::<LengthInput>::deserialize(deserializer)?;
+    value
+        .map(|v| match v {
+            LengthInput::Pixels(pixels) => Ok(BarLength::Pixels(pixels)),
+            LengthInput::String(str) => str.parse().map_err(serde::de::Error::custom),
+        })
+        .transpose()
+}
+
+#[cfg(feature = "schema")]
+pub fn schema_length(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    use schemars::schema::{SchemaObject, SubschemaValidation};
+    use schemars::JsonSchema;
+
+    let schema = SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(vec![
+                <i32>::json_schema(gen),
+                <String>::json_schema(gen),
+                <()>::json_schema(gen),
+            ]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+
+    schema.into()
+}
+
+impl FromStr for BarHeight {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.eq_ignore_ascii_case("auto") {
+            Ok(Self::Auto)
+        } else {
+            s.parse()
+                .map(Self::Pixels)
+                .map_err(|_| format!("invalid bar height '{s}', expected pixels or 'auto'"))
+        }
+    }
+}
+
+impl BarHeight {
+    /// Gets the size to request for the bar content,
+    /// or `-1` to leave it unset.
+    pub const fn request(self) -> i32 {
+        match self {
+            Self::Pixels(pixels) => pixels,
+            Self::Auto => -1,
+        }
+    }
+}
+
+impl FromStr for BarLength {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let length = match s.strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse()
+                .ok()
+                .filter(|percent| (0.0..=100.0).contains(percent))
+                .map(Self::Percent),
+            None => s.parse().ok().map(Self::Pixels),
+        };
+
+        length.ok_or_else(|| format!("invalid bar length '{s}', expected pixels or a percentage"))
+    }
+}
+
+impl BarLength {
+    /// Gets the length in pixels,
+    /// for an output of the given size along the bar's edge.
+    pub fn resolve(self, output_length: i32) -> i32 {
+        match self {
+            Self::Pixels(pixels) => pixels,
+            Self::Percent(percent) => (f64::from(output_length) * percent / 100.0).round() as i32,
+        }
+    }
+}
+
 impl BarPosition {
     /// Gets the orientation the bar and widgets should use
     /// based on this position.
@@ -87,3 +219,31 @@ impl BarPosition {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lengths() {
+        assert_eq!("500".parse(), Ok(BarLength::Pixels(500)));
+        assert_eq!("60%".parse(), Ok(BarLength::Percent(60.0)));
+        assert_eq!(" 12.5 % ".parse(), Ok(BarLength::Percent(12.5)));
+        assert!("150%".parse::<BarLength>().is_err());
+        assert!("wide".parse::<BarLength>().is_err());
+    }
+
+    #[test]
+    fn parse_heights() {
+        assert_eq!("42".parse(), Ok(BarHeight::Pixels(42)));
+        assert_eq!(" auto ".parse(), Ok(BarHeight::Auto));
+        assert!("tall".parse::<BarHeight>().is_err());
+    }
+
+    #[test]
+    fn resolve_lengths() {
+        assert_eq!(BarLength::Pixels(500).resolve(1920), 500);
+        assert_eq!(BarLength::Percent(60.0).resolve(1920), 1152);
+        assert_eq!(BarLength::Percent(33.3).resolve(1000), 333);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::dynamic_value::{Expression, Source, Value};
+use color_eyre::{Report, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use std::{env, fs, thread};
+use tracing::{error, trace};
+
+/// The maximum time a `cmd:` check can run for
+/// before it is killed and treated as a failure.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(1);
+
+thread_local! {
+    /// The result of each `cmd:` check since the config was loaded,
+    /// so that each command is only run once
+    /// however many modules and outputs check it.
+    static COMMAND_RESULTS: RefCell<HashMap<Box<str>, Result<bool, String>>> =
+        RefCell::default();
+}
+
+/// Clears the results of `cmd:` checks,
+/// so that commands are run again when next checked.
+///
+/// This should be called each time the config is loaded.
+pub fn clear_command_results() {
+    COMMAND_RESULTS.with(|results| results.borrow_mut().clear());
+}
+
+/// Reads the value of a source used in a condition.
+fn read(source: &Source) -> Result<Value> {
+    let value = match source {
+        Source::Env(name) => Value::Text(env::var(name.as_ref()).unwrap_or_default()),
+        Source::Host => Value::Text(
+            fs::read_to_string("/proc/sys/kernel/hostname")
+                .map(|host| host.trim().to_string())
+                .or_else(|_| env::var("HOSTNAME"))
+                .unwrap_or_default(),
+        ),
+        Source::File(path) => Value::Bool(expand_home(Path::new(path.as_ref())).exists()),
+        Source::Command(cmd) => Value::Bool(command_result(cmd)?),
+    };
+
+    Ok(value)
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Gets whether the command succeeded,
+/// running it if it has not run since the config was loaded.
+fn command_result(cmd: &str) -> Result<bool> {
+    let cached = COMMAND_RESULTS.with(|results| results.borrow().get(cmd).cloned());
+
+    let result = cached.unwrap_or_else(|| {
+        let result = run_command(cmd).map_err(|err| err.to_string());
+
+        COMMAND_RESULTS.with(|results| {
+            results.borrow_mut().insert(cmd.into(), result.clone());
+        });
+
+        result
+    });
+
+    result.map_err(Report::msg)
+}
+
+/// Runs the command to completion, blocking the calling thread,
+/// and checks whether it succeeded.
+fn run_command(cmd: &str) -> Result<bool> {
+    let mut child = Command::new("/bin/sh")
+        .args(["-c", cmd])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let start = Instant::now();
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status.success());
+        }
+
+        if start.elapsed() >= COMMAND_TIMEOUT {
+            child.kill().ok();
+            child.wait().ok();
+
+            return Err(Report::msg(format!(
+                "Command '{cmd}' did not exit within {}ms",
+                COMMAND_TIMEOUT.as_millis()
+            )));
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn check(condition: &str) -> Result<bool> {
+    let expression = Expression::parse_condition(condition)?;
+
+    if !expression.variables().is_empty() {
+        return Err(Report::msg("Conditions cannot reference ironvars"));
+    }
+
+    expression.check_with(&|_| None, &read)
+}
+
+/// Evaluates a `load_if` condition.
+///
+/// If the condition cannot be parsed or checked,
+/// the error is logged and the module is loaded anyway.
+pub fn should_load(condition: &str) -> bool {
+    let result = check(condition);
+
+    trace!("load_if '{condition}' evaluated to {result:?}");
+
+    result.unwrap_or_else(|err| {
+        error!("Failed to evaluate load_if condition '{condition}': {err:?}");
+        true
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_invalid() {
+        for condition in [
+            "",
+            "env:",
+            "env:A ==",
+            "file:\"/tmp",
+            "(env:A",
+            "env:A env:B",
+            "cmd:",
+            "battery",
+            "#ironvar",
+        ] {
+            assert!(check(condition).is_err(), "{condition}");
+        }
+    }
+
+    #[test]
+    fn check_conditions() {
+        env::set_var("IRONBAR_LOAD_IF_ZERO", "0");
+
+        let check = |condition| super::check(condition).ok();
+
+        assert_eq!(check("env:PATH"), Some(true));
+        assert_eq!(check("env:IRONBAR_LOAD_IF_UNSET"), Some(false));
+        assert_eq!(check("env:IRONBAR_LOAD_IF_ZERO"), Some(true));
+        assert_eq!(check("!env:IRONBAR_LOAD_IF_ZERO"), Some(false));
+        assert_eq!(check("env:IRONBAR_LOAD_IF_ZERO == 0"), Some(true));
+        assert_eq!(check("env:IRONBAR_LOAD_IF_UNSET != \"x\""), Some(true));
+        assert_eq!(check("!env:PATH || host != \"\""), Some(true));
+        assert_eq!(check("file:/"), Some(true));
+        assert_eq!(check("!file:/ironbar/does/not/exist"), Some(true));
+        assert_eq!(
+            check("(file:/ || env:A) && cmd:test -d / && true"),
+            Some(true)
+        );
+        assert_eq!(check("cmd:true"), Some(true));
+        assert_eq!(check("cmd:exit 1"), Some(false));
+        assert_eq!(check("cmd:sleep 5"), None);
+    }
+
+    #[test]
+    fn cache_command_results() {
+        let file = env::temp_dir().join(format!("ironbar-load-if-{}", std::process::id()));
+        let condition = format!("cmd:echo >> {}", file.display());
+
+        assert!(should_load(&condition));
+        assert!(should_load(&condition));
+        assert_eq!(fs::read_to_string(&file).ok().as_deref(), Some("\n"));
+
+        clear_command_results();
+        assert!(should_load(&condition));
+        assert_eq!(fs::read_to_string(&file).ok().as_deref(), Some("\n\n"));
+
+        fs::remove_file(file).ok();
+    }
+
+    #[test]
+    fn load_on_error() {
+        assert!(should_load("not a condition"));
+        assert!(!should_load("cmd:false"));
+    }
+}
@@ -1,7 +1,13 @@
 mod common;
+mod export;
 mod r#impl;
+mod load_if;
 mod truncate;
 
+#[cfg(feature = "bluetooth")]
+use crate::modules::bluetooth::BluetoothModule;
+#[cfg(feature = "brightness")]
+use crate::modules::brightness::BrightnessModule;
 #[cfg(feature = "cairo")]
 use crate::modules::cairo::CairoModule;
 #[cfg(feature = "clipboard")]
@@ -11,13 +17,20 @@ use crate::modules::clock::ClockModule;
 use crate::modules::custom::CustomModule;
 #[cfg(feature = "focused")]
 use crate::modules::focused::FocusedModule;
+use crate::modules::group::GroupModule;
+#[cfg(feature = "idle_inhibit")]
+use crate::modules::idle_inhibit::IdleInhibitModule;
+#[cfg(feature = "keyboard")]
+use crate::modules::keyboard::KeyboardModule;
 use crate::modules::label::LabelModule;
 #[cfg(feature = "launcher")]
-use crate::modules::launcher::LauncherModule;
+use crate::modules::launcher::{AppSearchModule, LauncherModule};
 #[cfg(feature = "music")]
 use crate::modules::music::MusicModule;
 #[cfg(feature = "notifications")]
 use crate::modules::notifications::NotificationsModule;
+#[cfg(feature = "privacy")]
+use crate::modules::privacy::PrivacyModule;
 use crate::modules::script::ScriptModule;
 #[cfg(feature = "sys_info")]
 use crate::modules::sysinfo::SysInfoModule;
@@ -30,22 +43,33 @@ use crate::modules::volume::VolumeModule;
 #[cfg(feature = "workspaces")]
 use crate::modules::workspaces::WorkspacesModule;
 
+#[cfg(feature = "ipc")]
+use crate::modules::boundary::ModuleRestartHandler;
+#[cfg(feature = "ipc")]
+use crate::modules::OwnedModuleInfo;
 use crate::modules::{AnyModuleFactory, ModuleFactory, ModuleInfo};
 use cfg_if::cfg_if;
-use color_eyre::Result;
 use serde::Deserialize;
 use std::collections::HashMap;
 
 #[cfg(feature = "schema")]
 use schemars::JsonSchema;
 
-pub use self::common::{CommonConfig, ModuleOrientation, TransitionType};
-pub use self::truncate::TruncateMode;
+pub use self::common::{ClassList, CommonConfig, ModuleOrientation, TouchConfig, TransitionType};
+pub use self::export::ExportConfig;
+pub use self::load_if::{clear_command_results, should_load};
+pub use self::truncate::{EllipsizeMode, TruncateMode};
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub enum ModuleConfig {
+    #[cfg(feature = "launcher")]
+    AppSearch(Box<AppSearchModule>),
+    #[cfg(feature = "bluetooth")]
+    Bluetooth(Box<BluetoothModule>),
+    #[cfg(feature = "brightness")]
+    Brightness(Box<BrightnessModule>),
     #[cfg(feature = "cairo")]
     Cairo(Box<CairoModule>),
     #[cfg(feature = "clipboard")]
@@ -55,6 +79,11 @@ pub enum ModuleConfig {
     Custom(Box<CustomModule>),
     #[cfg(feature = "focused")]
     Focused(Box<FocusedModule>),
+    Group(Box<GroupModule>),
+    #[cfg(feature = "idle_inhibit")]
+    IdleInhibit(Box<IdleInhibitModule>),
+    #[cfg(feature = "keyboard")]
+    Keyboard(Box<KeyboardModule>),
     Label(Box<LabelModule>),
     #[cfg(feature = "launcher")]
     Launcher(Box<LauncherModule>),
@@ -62,6 +91,8 @@ pub enum ModuleConfig {
     Music(Box<MusicModule>),
     #[cfg(feature = "notifications")]
     Notifications(Box<NotificationsModule>),
+    #[cfg(feature = "privacy")]
+    Privacy(Box<PrivacyModule>),
     Script(Box<ScriptModule>),
     #[cfg(feature = "sys_info")]
     SysInfo(Box<SysInfoModule>),
@@ -76,19 +107,33 @@ pub enum ModuleConfig {
 }
 
 impl ModuleConfig {
+    /// Creates the module and adds it to the container,
+    /// returning the widget it was added as.
+    ///
+    /// If the module fails to create, an error placeholder is added instead.
+    /// Returns `None` if the module is skipped on this bar.
     pub fn create(
         self,
         module_factory: &AnyModuleFactory,
         container: &gtk::Box,
         info: &ModuleInfo,
-    ) -> Result<()> {
+    ) -> Option<gtk::EventBox> {
+        #[cfg(feature = "ipc")]
+        let config = self.clone();
+
         macro_rules! create {
             ($module:expr) => {
                 module_factory.create(*$module, container, info)
             };
         }
 
-        match self {
+        let instance = match self {
+            #[cfg(feature = "launcher")]
+            Self::AppSearch(module) => create!(module),
+            #[cfg(feature = "bluetooth")]
+            Self::Bluetooth(module) => create!(module),
+            #[cfg(feature = "brightness")]
+            Self::Brightness(module) => create!(module),
             #[cfg(feature = "cairo")]
             Self::Cairo(module) => create!(module),
             #[cfg(feature = "clipboard")]
@@ -98,6 +143,11 @@ impl ModuleConfig {
             Self::Custom(module) => create!(module),
             #[cfg(feature = "focused")]
             Self::Focused(module) => create!(module),
+            Self::Group(module) => create!(module),
+            #[cfg(feature = "idle_inhibit")]
+            Self::IdleInhibit(module) => create!(module),
+            #[cfg(feature = "keyboard")]
+            Self::Keyboard(module) => create!(module),
             Self::Label(module) => create!(module),
             #[cfg(feature = "launcher")]
             Self::Launcher(module) => create!(module),
@@ -105,6 +155,8 @@ impl ModuleConfig {
             Self::Music(module) => create!(module),
             #[cfg(feature = "notifications")]
             Self::Notifications(module) => create!(module),
+            #[cfg(feature = "privacy")]
+            Self::Privacy(module) => create!(module),
             Self::Script(module) => create!(module),
             #[cfg(feature = "sys_info")]
             Self::SysInfo(module) => create!(module),
@@ -116,7 +168,29 @@ impl ModuleConfig {
             Self::Volume(module) => create!(module),
             #[cfg(feature = "workspaces")]
             Self::Workspaces(module) => create!(module),
+        }?;
+
+        let widget = instance.widget.clone();
+
+        #[cfg(feature = "ipc")]
+        {
+            let factory = module_factory.clone();
+            let info = OwnedModuleInfo::from(info);
+
+            let popup = module_factory.popup().clone();
+
+            let handler = ModuleRestartHandler::new(instance, container, popup, move |container| {
+                config.clone().create(&factory, container, &info.as_info())
+            });
+
+            module_factory
+                .ironbar()
+                .module_restarts
+                .borrow_mut()
+                .push(handler);
         }
+
+        Some(widget)
     }
 }
 
@@ -143,6 +217,36 @@ impl Default for BarPosition {
     }
 }
 
+/// What happens to the bar while the session is locked.
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum LockBehavior {
+    /// The bar is unchanged.
+    #[default]
+    Normal,
+    /// The bar is hidden.
+    Hide,
+    /// Modules showing private content, such as notifications,
+    /// are blanked and their popups closed.
+    Censor,
+}
+
+/// The cursor shown while hovering over clickable widgets,
+/// such as buttons and launcher items.
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum CursorStyle {
+    /// The cursor is left unchanged.
+    Default,
+    /// The pointing hand cursor.
+    #[default]
+    Pointer,
+    /// The cursor is hidden.
+    None,
+}
+
 #[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct MarginConfig {
@@ -156,6 +260,181 @@ pub struct MarginConfig {
     pub top: i32,
 }
 
+/// A region along the bar's edge which content should avoid,
+/// such as a camera notch.
+///
+/// The center section is split around the region,
+/// while the bar background still spans it.
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct CutoutConfig {
+    /// The distance in pixels from the start of the output
+    /// (its left or top edge) to the start of the region.
+    pub start: i32,
+
+    /// The distance in pixels from the start of the output
+    /// to the end of the region.
+    pub end: i32,
+
+    /// The number of center modules to place before the region.
+    /// The rest are placed after it.
+    ///
+    /// **Default**: half of the center modules, rounded up.
+    #[serde(default)]
+    pub split: Option<usize>,
+}
+
+/// The font used for the text on a bar and its popups.
+///
+/// These are applied beneath the user stylesheet,
+/// so any font set there still takes priority.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct FontConfig {
+    /// The font family, such as `Noto Sans`.
+    /// Leave unset to use the GTK default font.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    pub family: Option<String>,
+
+    /// The font size, in points.
+    /// Leave unset to use the GTK default font size.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    pub size: Option<f64>,
+
+    /// A multiplier for the font size.
+    /// Icon sizes set on modules are also scaled by this.
+    ///
+    /// **Default**: `1.0`
+    #[serde(default = "default_font_scale")]
+    pub scale: f64,
+
+    /// Whether to follow the system text scaling factor,
+    /// set using the `gtk-xft-dpi` setting.
+    /// When true, module icon sizes are also scaled by the system factor.
+    ///
+    /// When false, text is the same size regardless of the system factor.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "default_true")]
+    pub system_scale: bool,
+}
+
+/// How scrolling over modules is turned into steps,
+/// such as switching workspace or changing the volume.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct ScrollConfig {
+    /// The scroll distance needed for each step.
+    /// A mouse wheel moves `1.0` for each notch,
+    /// so lower values make touchpads more sensitive.
+    ///
+    /// **Default**: `1.0`
+    #[serde(default = "default_scroll_threshold")]
+    pub threshold: f64,
+
+    /// The minimum number of milliseconds between steps.
+    /// Steps scrolled faster than this are queued,
+    /// and cancelled on changing direction.
+    ///
+    /// **Default**: `150`
+    #[serde(default = "default_scroll_interval")]
+    pub interval: u64,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            threshold: default_scroll_threshold(),
+            interval: default_scroll_interval(),
+        }
+    }
+}
+
+/// The size of the bar across its edge.
+///
+/// This can either be a number of pixels,
+/// or `"auto"` to fit the bar's content.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BarHeight {
+    Pixels(i32),
+    Auto,
+}
+
+/// The length of the bar along its edge.
+///
+/// This can either be a number of pixels,
+/// or a percentage of the output's size as a string, such as `"60%"`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BarLength {
+    Pixels(i32),
+    Percent(f64),
+}
+
+/// Where to place the bar along its edge,
+/// when it does not span the full edge.
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum BarAlign {
+    Start,
+    #[default]
+    Center,
+    End,
+}
+
+/// The gap between modules in each section of the bar.
+///
+/// This can either be a single number,
+/// or an object with `start`, `center` and `end` keys
+/// to set each section separately.
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub enum SpacingConfig {
+    All(i32),
+    Sections {
+        #[serde(default)]
+        start: i32,
+        #[serde(default)]
+        center: i32,
+        #[serde(default)]
+        end: i32,
+    },
+}
+
+impl Default for SpacingConfig {
+    fn default() -> Self {
+        Self::All(0)
+    }
+}
+
+impl SpacingConfig {
+    pub const fn start(self) -> i32 {
+        match self {
+            Self::All(spacing) | Self::Sections { start: spacing, .. } => spacing,
+        }
+    }
+
+    pub const fn center(self) -> i32 {
+        match self {
+            Self::All(spacing)
+            | Self::Sections {
+                center: spacing, ..
+            } => spacing,
+        }
+    }
+
+    pub const fn end(self) -> i32 {
+        match self {
+            Self::All(spacing) | Self::Sections { end: spacing, .. } => spacing,
+        }
+    }
+}
+
 /// The following is a list of all top-level bar config options.
 ///
 /// These options can either be written at the very top object of your config,
@@ -186,15 +465,62 @@ pub struct BarConfig {
     #[serde(default = "default_true")]
     pub anchor_to_edges: bool,
 
-    /// The bar's height in pixels.
+    /// The bar's height in pixels,
+    /// or `auto` to fit the height of its content once styles are applied.
+    /// For vertical bars, this is the width, and can also be set as `width`.
     ///
-    /// Note that GTK treats this as a target minimum,
+    /// Note that GTK treats a number of pixels as a target minimum,
     /// and if content inside the bar is over this,
     /// it will automatically expand to fit.
     ///
+    /// An `auto` bar also shrinks when its content does.
+    ///
     /// **Default**: `42`
-    #[serde(default = "default_bar_height")]
-    pub height: i32,
+    #[serde(
+        default = "default_bar_height",
+        alias = "width",
+        deserialize_with = "r#impl::deserialize_height"
+    )]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "r#impl::schema_height"))]
+    pub height: BarHeight,
+
+    /// The smallest height in pixels to size an `auto` bar to.
+    /// For vertical bars, this is the width.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    pub min_height: Option<i32>,
+
+    /// The largest height in pixels to size an `auto` bar to.
+    /// For vertical bars, this is the width.
+    ///
+    /// Content larger than this is clipped.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    pub max_height: Option<i32>,
+
+    /// The bar's length along its edge.
+    /// This is either a number of pixels, or a percentage of the output such as `"60%"`.
+    /// Leave unset to span the full edge, depending on `anchor_to_edges`.
+    ///
+    /// As with `height`, content larger than this expands the bar to fit.
+    ///
+    /// **Default**: `null`
+    #[serde(default, deserialize_with = "r#impl::deserialize_length")]
+    #[cfg_attr(feature = "schema", schemars(schema_with = "r#impl::schema_length"))]
+    pub length: Option<BarLength>,
+
+    /// Where to place the bar along its edge when `length` is set.
+    /// For vertical bars, `start` is the top.
+    ///
+    /// The exclusive zone, if enabled, always reserves space across the whole edge.
+    ///
+    /// **Valid options**: `start`, `center`, `end`
+    /// <br>
+    /// **Default**: `center`
+    #[serde(default)]
+    pub align: BarAlign,
 
     /// The margin to use on each side of the bar, in pixels.
     /// Object which takes `top`, `bottom`, `left` and `right` keys.
@@ -216,6 +542,54 @@ pub struct BarConfig {
     #[serde(default)]
     pub margin: MarginConfig,
 
+    /// The space in pixels between the edges of the bar and its content.
+    /// This is applied at each end of the bar along its length,
+    /// so along the Y axis for vertical bars.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    pub padding: i32,
+
+    /// The gap in pixels between modules.
+    /// This can be a single number for all sections,
+    /// or an object with `start`, `center` and `end` keys.
+    ///
+    /// **Default**: `0`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// {
+    ///     spacing.start = 5
+    ///     spacing.center = 10
+    ///     spacing.end = 5
+    /// }
+    /// ```
+    #[serde(default)]
+    pub spacing: SpacingConfig,
+
+    /// A region of the edge for content to avoid, such as a camera notch.
+    /// Object which takes `start` and `end` keys,
+    /// and optionally `split`.
+    ///
+    /// **Default**: `null`
+    ///
+    /// # Example
+    ///
+    /// The following keeps content clear of a notch
+    /// between 880px and 1040px from the left of the output,
+    /// with two center modules to its left.
+    ///
+    /// ```corn
+    /// {
+    ///     cutout.start = 880
+    ///     cutout.end = 1040
+    ///     cutout.split = 2
+    /// }
+    /// ```
+    #[serde(default)]
+    pub cutout: Option<CutoutConfig>,
+
     /// The layer-shell layer to place the bar on.
     ///
     /// Taken from the
@@ -266,12 +640,80 @@ pub struct BarConfig {
     #[serde(default)]
     pub autohide: Option<u64>,
 
+    /// Whether to add the `.unfocused` class to the bar
+    /// while the focused window is on a different output.
+    /// Outputs with no windows on their focused workspace count as unfocused.
+    ///
+    /// This requires workspace support for your compositor.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub dim_unfocused: bool,
+
+    /// What to do with the bar while the session is locked,
+    /// so that a bar above the lock screen does not show private content.
+    ///
+    /// This uses logind's `LockedHint`,
+    /// so requires your screen locker or its launcher to set it.
+    ///
+    /// **Valid options**: `normal`, `hide`, `censor`
+    /// <br>
+    /// **Default**: `normal`
+    #[serde(default)]
+    pub lock_behavior: LockBehavior,
+
+    /// Whether to move modules at the start and end of the bar
+    /// into an overflow menu when there is not enough space for them all.
+    ///
+    /// Modules with the lowest `priority` are moved first,
+    /// followed by those nearest the center of the bar.
+    /// They are moved back automatically once space is available.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub overflow: bool,
+
+    /// Whether to show this bar on every output.
+    ///
+    /// For the top-level bar, this shows it even on outputs set in `monitors`.
+    /// For a bar in `monitors`, this also shows it on every other output.
+    /// Other bars for each output are shown alongside it.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub clone_to_all_outputs: bool,
+
     /// The name of the GTK icon theme to use.
     /// Leave unset to use the default Adwaita theme.
     ///
     /// **Default**: `null`
     pub icon_theme: Option<String>,
 
+    /// The font to use for the bar and its popups,
+    /// independent of the system-wide GTK font.
+    /// Object which takes `family`, `size`, `scale` and `system_scale` keys.
+    ///
+    /// See [font](#font) for more info.
+    ///
+    /// **Default**: `null`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// {
+    ///     font.family = "Noto Sans"
+    ///     font.size = 11
+    /// }
+    /// ```
+    #[serde(default)]
+    pub font: Option<FontConfig>,
+
+    /// How scrolling over modules is turned into steps,
+    /// such as switching workspace or changing the volume.
+    /// Object which takes `threshold` and `interval` keys.
+    #[serde(default)]
+    pub scroll: ScrollConfig,
+
     /// An array of modules to append to the start of the bar.
     /// Depending on the orientation, this is either the top of the left edge.
     ///
@@ -313,13 +755,26 @@ impl Default for BarConfig {
         Self {
             position: BarPosition::default(),
             margin: MarginConfig::default(),
+            padding: 0,
+            spacing: SpacingConfig::default(),
+            cutout: None,
             name: None,
             layer: default_layer(),
             exclusive_zone: None,
             height: default_bar_height(),
+            min_height: None,
+            max_height: None,
+            length: None,
+            align: BarAlign::default(),
             start_hidden: None,
             autohide: None,
+            dim_unfocused: false,
+            lock_behavior: LockBehavior::default(),
+            overflow: false,
+            clone_to_all_outputs: false,
             icon_theme: None,
+            font: None,
+            scroll: ScrollConfig::default(),
             start: Some(vec![ModuleConfig::Label(
                 LabelModule::new("ℹ️ Using default config".to_string()).into(),
             )]),
@@ -331,6 +786,28 @@ impl Default for BarConfig {
     }
 }
 
+impl BarConfig {
+    /// Gets a representation of the config
+    /// ignoring options which can be applied to an existing bar.
+    ///
+    /// If two configs have the same key,
+    /// a bar can be updated from one to the other
+    /// without needing to recreate its modules.
+    pub fn structure_key(&self) -> String {
+        let mut config = self.clone();
+        config.padding = 0;
+        config.spacing = SpacingConfig::default();
+
+        // the scale sets module icon sizes, so changing it must recreate them
+        if let Some(font) = &mut config.font {
+            font.family = None;
+            font.size = None;
+        }
+
+        format!("{config:?}")
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 pub struct Config {
@@ -356,6 +833,54 @@ pub struct Config {
     /// ```
     pub ironvar_defaults: Option<HashMap<Box<str>, String>>,
 
+    /// A map of [ironvar](ironvar) keys and expressions
+    /// calculating their values from other ironvars.
+    /// Each is re-evaluated whenever a variable it references changes,
+    /// and cannot be set directly.
+    ///
+    /// See [derived variables](ironvars#derived-variables) for the expression syntax.
+    ///
+    /// **Default**: `{}`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// {
+    ///     derived_variables.battery_low = "#battery_pct < 20"
+    ///     derived_variables.uptime_hours = "#uptime_sec / 3600 | round"
+    /// }
+    /// ```
+    pub derived_variables: Option<HashMap<Box<str>, String>>,
+
+    /// A map of action names to sequences of IPC commands,
+    /// which can be run together using `ironbar action <name>`.
+    ///
+    /// Each step is written as it would be for the CLI.
+    /// `sleep <ms>` waits between steps,
+    /// and `$1`, `$2` etc. are replaced with the action's arguments.
+    ///
+    /// **Default**: `{}`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// { actions.quiet = [ "var set dnd true" "bar hide aux" ] }
+    /// ```
+    pub actions: Option<HashMap<String, Vec<String>>>,
+
+    /// Writes selected state to a JSON file whenever it changes,
+    /// for use by external programs.
+    /// See [exporting state](#exporting-state) for available fields.
+    ///
+    /// **Default**: `null`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// { export.fields = [ "workspaces" "music.title" "music.artist" "battery.percentage" ] }
+    /// ```
+    pub export: Option<ExportConfig>,
+
     /// The configuration for the bar.
     /// Setting through this will enable a single identical bar on each monitor.
     #[serde(flatten)]
@@ -370,20 +895,97 @@ pub struct Config {
     ///
     /// Providing this option overrides the single, global `bar` option.
     pub monitors: Option<HashMap<String, MonitorConfig>>,
+
+    /// The number of seconds over which to spread
+    /// interval-driven modules and scripts with the same interval,
+    /// so they do not all run at once.
+    ///
+    /// By default, these are aligned so they run together,
+    /// to reduce the number of wakeups.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    pub timer_jitter: u64,
+
+    /// The cursor to show while hovering over clickable widgets,
+    /// such as buttons, launcher items and tray icons.
+    ///
+    /// **Valid options**: `default`, `pointer`, `none`
+    /// <br>
+    /// **Default**: `pointer`
+    #[serde(default)]
+    pub cursor: CursorStyle,
+
+    /// The locale to use for text Ironbar shows itself,
+    /// such as popup buttons and duration units, for example `de` or `fr_FR`.
+    ///
+    /// Leave unset to use the `LC_MESSAGES` and `LC_TIME` environment variables,
+    /// falling back to `LANG`.
+    /// English is used for any text without a translation.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Whether to count activity for each module instance,
+    /// to help find modules using excessive resources.
+    /// This can also be enabled by setting `IRONBAR_DIAGNOSTICS=1`.
+    ///
+    /// Counters can be read using `ironbar debug perf`,
+    /// and a summary is logged every minute at debug level.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub diagnostics: bool,
+
+    /// Whether to send a desktop notification
+    /// when Ironbar logs a warning or error,
+    /// such as a script failing or a client disconnecting.
+    ///
+    /// Notifications name the module or client responsible.
+    /// Repeated events from the same source are counted,
+    /// and sent together at most once per `error_notification_interval`.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub error_notifications: bool,
+
+    /// The minimum number of minutes between error notifications
+    /// from the same module or client.
+    ///
+    /// **Default**: `10`
+    #[serde(default = "default_error_notification_interval")]
+    pub error_notification_interval: u64,
 }
 
 const fn default_layer() -> gtk_layer_shell::Layer {
     gtk_layer_shell::Layer::Top
 }
 
-const fn default_bar_height() -> i32 {
-    42
+const fn default_bar_height() -> BarHeight {
+    BarHeight::Pixels(42)
+}
+
+const fn default_error_notification_interval() -> u64 {
+    10
 }
 
 const fn default_popup_gap() -> i32 {
     5
 }
 
+const fn default_font_scale() -> f64 {
+    1.0
+}
+
+const fn default_scroll_threshold() -> f64 {
+    1.0
+}
+
+const fn default_scroll_interval() -> u64 {
+    150
+}
+
 pub const fn default_false() -> bool {
     false
 }
@@ -21,14 +21,36 @@ fn desktop_files_look_out_keys() -> &'static HashSet<&'static str> {
         .get_or_init(|| HashSet::from(["Name", "StartupWMClass", "Exec", "Icon"]))
 }
 
+/// Finds the directories which flatpak and snap export installed apps to.
+/// Each has the same layout as `/usr/share`, with `applications` and `icons` directories.
+///
+/// These are not always included in `XDG_DATA_DIRS`,
+/// for example when Ironbar is started before the session environment is set up.
+/// They may not exist yet if nothing has been installed.
+pub fn find_export_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+
+    if let Some(user_dir) = dirs::data_local_dir() {
+        dirs.push(user_dir.join("flatpak/exports/share")); // user flatpak apps
+    }
+
+    dirs.push(PathBuf::from("/var/lib/flatpak/exports/share")); // system flatpak apps
+    dirs.push(PathBuf::from("/var/lib/snapd/desktop")); // snap apps
+
+    dirs
+}
+
 /// Finds directories that should contain `.desktop` files
 /// and exist on the filesystem.
 fn find_application_dirs() -> Vec<PathBuf> {
     let mut dirs = vec![
         PathBuf::from("/usr/share/applications"), // system installed apps
-        PathBuf::from("/var/lib/flatpak/exports/share/applications"), // flatpak apps
     ];
 
+    for export_dir in find_export_dirs() {
+        dirs.push(export_dir.join("applications"));
+    }
+
     let xdg_dirs = env::var_os("XDG_DATA_DIRS");
     if let Some(xdg_dirs) = xdg_dirs {
         for mut xdg_dir in env::split_paths(&xdg_dirs).map(PathBuf::from) {
@@ -43,7 +65,10 @@ fn find_application_dirs() -> Vec<PathBuf> {
         dirs.push(user_dir);
     }
 
-    dirs.into_iter().filter(|dir| dir.exists()).collect()
+    let mut seen = HashSet::new();
+    dirs.into_iter()
+        .filter(|dir| dir.exists() && seen.insert(dir.clone()))
+        .collect()
 }
 
 /// Finds all the desktop files
@@ -61,6 +86,84 @@ fn find_desktop_files() -> Vec<PathBuf> {
         .collect()
 }
 
+/// An application which can be launched,
+/// read from the main group of its `.desktop` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Application {
+    /// The path to the `.desktop` file.
+    pub path: PathBuf,
+    pub name: String,
+    pub generic_name: Option<String>,
+    pub icon: Option<String>,
+    pub keywords: Vec<String>,
+}
+
+/// Finds all the applications which should be shown in app menus.
+///
+/// Where several directories contain a desktop file with the same name,
+/// the one from the directory searched last is used,
+/// so that user-installed files override system ones.
+pub fn find_applications() -> Vec<Application> {
+    let mut applications = HashMap::new();
+
+    for path in find_desktop_files() {
+        let Some(file_name) = path.file_name().map(ToOwned::to_owned) else {
+            continue;
+        };
+
+        let Ok(contents) = fs::read_to_string(&path) else {
+            warn!("Couldn't Open File: {}", path.display());
+            continue;
+        };
+
+        // hidden files still override earlier ones, which is how apps are removed
+        applications.insert(file_name, parse_application(path, &contents));
+    }
+
+    applications.into_values().flatten().collect()
+}
+
+/// Parses the main group of a desktop file,
+/// returning `None` if the app should not be shown.
+fn parse_application(path: PathBuf, contents: &str) -> Option<Application> {
+    let mut entry = HashMap::new();
+    let mut in_main_group = false;
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+        } else if let Some((key, value)) = line.split_once('=').filter(|_| in_main_group) {
+            entry.insert(key.trim(), value.trim());
+        }
+    }
+
+    let is_true = |key| entry.get(key).is_some_and(|value| *value == "true");
+    if entry.get("Type") != Some(&"Application") || is_true("NoDisplay") || is_true("Hidden") {
+        return None;
+    }
+
+    let get = |key| {
+        entry
+            .get(key)
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+    };
+
+    Some(Application {
+        name: get("Name")?,
+        generic_name: get("GenericName"),
+        icon: get("Icon"),
+        keywords: entry
+            .get("Keywords")
+            .into_iter()
+            .flat_map(|keywords| keywords.split(';'))
+            .filter(|keyword| !keyword.is_empty())
+            .map(ToString::to_string)
+            .collect(),
+        path,
+    })
+}
+
 /// Attempts to locate a `.desktop` file for an app id
 pub fn find_desktop_file(app_id: &str) -> Option<PathBuf> {
     // this is necessary to invalidate the cache
@@ -197,3 +300,40 @@ pub fn get_desktop_icon_name(app_id: &str) -> Option<String> {
 
     icons.next().map(std::string::ToString::to_string)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_application_entry() {
+        let contents = "[Desktop Entry]
+Type=Application
+Name=Firefox
+GenericName=Web Browser
+Icon=firefox
+Keywords=internet;www;
+
+[Desktop Action new-window]
+Name=New Window
+";
+
+        let app = parse_application(PathBuf::from("firefox.desktop"), contents);
+        assert_eq!(
+            app,
+            Some(Application {
+                path: PathBuf::from("firefox.desktop"),
+                name: String::from("Firefox"),
+                generic_name: Some(String::from("Web Browser")),
+                icon: Some(String::from("firefox")),
+                keywords: vec![String::from("internet"), String::from("www")],
+            })
+        );
+
+        let hidden = contents.replace("Icon=", "NoDisplay=true\nIcon=");
+        assert_eq!(parse_application(PathBuf::new(), &hidden), None);
+
+        let link = contents.replace("Type=Application", "Type=Link");
+        assert_eq!(parse_application(PathBuf::new(), &link), None);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::{lock, spawn};
+use gtk::prelude::*;
+use serde::Serialize;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// The environment variable which enables diagnostics,
+/// in addition to the `diagnostics` config option.
+const ENV_VAR: &str = "IRONBAR_DIAGNOSTICS";
+
+/// The widget tag used to find the counters
+/// for the module an image belongs to.
+const WIDGET_TAG: &str = "diagnostics";
+
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+
+/// Activity counters for a single module instance.
+#[derive(Debug)]
+pub struct ModuleCounters {
+    id: usize,
+    module_type: &'static str,
+    name: String,
+    bar: String,
+
+    updates: AtomicU64,
+    redraws: AtomicU64,
+    scripts: AtomicU64,
+    image_bytes: AtomicU64,
+}
+
+impl ModuleCounters {
+    /// Records an update event sent from the controller to the widget.
+    pub fn record_update(&self) {
+        self.updates.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the widget being redrawn.
+    pub fn record_redraw(&self) {
+        self.redraws.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a script process being started.
+    pub fn record_script(&self) {
+        self.scripts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> ModuleStats {
+        ModuleStats {
+            id: self.id,
+            module_type: self.module_type,
+            name: self.name.clone(),
+            bar: self.bar.clone(),
+            updates: self.updates.load(Ordering::Relaxed),
+            redraws: self.redraws.load(Ordering::Relaxed),
+            scripts: self.scripts.load(Ordering::Relaxed),
+            image_bytes: self.image_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Tags the module's widget with the counters,
+    /// allowing images inside it to be attributed to the module.
+    pub fn tag_widget<W: IsA<gtk::Widget>>(self: &Arc<Self>, widget: &W) {
+        widget.set_tag(WIDGET_TAG, self.clone());
+
+        let counters = self.clone();
+        widget.connect_draw(move |_, _| {
+            counters.record_redraw();
+            glib::Propagation::Proceed
+        });
+    }
+}
+
+/// A snapshot of a module's counters.
+#[derive(Debug, Serialize)]
+pub struct ModuleStats {
+    id: usize,
+    #[serde(rename = "type")]
+    module_type: &'static str,
+    name: String,
+    bar: String,
+    updates: u64,
+    redraws: u64,
+    scripts: u64,
+    image_bytes: u64,
+}
+
+/// A snapshot of all counters.
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    modules: Vec<ModuleStats>,
+    /// Bytes of images loaded into widgets
+    /// which could not be matched to a module.
+    unattributed_image_bytes: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Registry {
+    modules: Mutex<Vec<Weak<ModuleCounters>>>,
+    unattributed_image_bytes: AtomicU64,
+}
+
+impl Registry {
+    /// Creates the counters for a new module instance.
+    pub fn register(
+        &self,
+        id: usize,
+        module_type: &'static str,
+        name: &str,
+        bar: &str,
+    ) -> Arc<ModuleCounters> {
+        let counters = Arc::new(ModuleCounters {
+            id,
+            module_type,
+            name: name.to_string(),
+            bar: bar.to_string(),
+            updates: AtomicU64::new(0),
+            redraws: AtomicU64::new(0),
+            scripts: AtomicU64::new(0),
+            image_bytes: AtomicU64::new(0),
+        });
+
+        let mut modules = lock!(self.modules);
+        modules.retain(|counters| counters.strong_count() > 0);
+        modules.push(Arc::downgrade(&counters));
+
+        counters
+    }
+
+    /// Removes the counters for a module instance which is being replaced.
+    pub fn unregister(&self, id: usize) {
+        lock!(self.modules)
+            .retain(|counters| counters.upgrade().is_some_and(|counters| counters.id != id));
+    }
+
+    /// Gets a snapshot of the counters for every live module.
+    pub fn stats(&self) -> Stats {
+        let modules = lock!(self.modules)
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|counters| counters.stats())
+            .collect();
+
+        Stats {
+            modules,
+            unattributed_image_bytes: self.unattributed_image_bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    fn log_summary(&self) {
+        let stats = self.stats();
+
+        for module in &stats.modules {
+            debug!(
+                "[diagnostics] {} '{}' [#{}] on {}: updates={} redraws={} scripts={} image_bytes={}",
+                module.module_type,
+                module.name,
+                module.id,
+                module.bar,
+                module.updates,
+                module.redraws,
+                module.scripts,
+                module.image_bytes
+            );
+        }
+
+        debug!(
+            "[diagnostics] unattributed image_bytes={}",
+            stats.unattributed_image_bytes
+        );
+    }
+}
+
+/// Enables diagnostics if set in the config or environment.
+/// This must be called before any modules are created.
+pub fn init(enabled: bool) {
+    let enabled = enabled || env::var(ENV_VAR).is_ok_and(|value| !value.is_empty() && value != "0");
+
+    if !enabled || REGISTRY.set(Registry::default()).is_err() {
+        return;
+    }
+
+    info!("Diagnostics enabled");
+
+    spawn(async move {
+        let mut interval = tokio::time::interval(SUMMARY_INTERVAL);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            if let Some(registry) = registry() {
+                registry.log_summary();
+            }
+        }
+    });
+}
+
+/// Gets the diagnostics registry,
+/// or `None` if diagnostics are disabled.
+pub fn registry() -> Option<&'static Registry> {
+    REGISTRY.get()
+}
+
+/// Records the size of an image loaded into a widget
+/// against the module containing it.
+pub fn record_image(image: &gtk::Image, bytes: usize) {
+    let Some(registry) = registry() else {
+        return;
+    };
+
+    let bytes = bytes as u64;
+
+    let mut widget = Some(image.clone().upcast::<gtk::Widget>());
+    while let Some(current) = widget {
+        if let Some(counters) = current.get_tag::<Arc<ModuleCounters>>(WIDGET_TAG) {
+            counters.image_bytes.fetch_add(bytes, Ordering::Relaxed);
+            return;
+        }
+
+        widget = current.parent();
+    }
+
+    registry
+        .unattributed_image_bytes
+        .fetch_add(bytes, Ordering::Relaxed);
+}
@@ -0,0 +1,291 @@
+// each helper is only used by some of the modules which need this
+#![cfg_attr(
+    not(all(
+        feature = "clock",
+        feature = "sys_info",
+        any(feature = "error_notifications", feature = "music", feature = "upower")
+    )),
+    allow(dead_code)
+)]
+
+use crate::i18n::{self, Category};
+use serde::Deserialize;
+use std::time::Duration;
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+/// How to write a duration, such as a track length or battery time remaining.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DurationStyle {
+    /// Like a digital clock, such as `1:02:17`.
+    /// Hours keep counting past a day.
+    Clock,
+    /// The largest two units, such as `2h 10m`.
+    Compact,
+    /// Every unit written out, such as `2 hours, 10 minutes`.
+    /// Seconds are only included under an hour.
+    Verbose,
+    /// The largest unit relative to now, such as `in 2 hours` or `yesterday`.
+    /// Negative durations are in the past.
+    Relative,
+}
+
+/// The unit names for a language.
+#[derive(Debug)]
+struct UnitLabels {
+    /// Days, hours, minutes and seconds in compact style.
+    short: [&'static str; 4],
+    singular: [&'static str; 4],
+    plural: [&'static str; 4],
+    /// Now, a time in the past and a time in the future,
+    /// with `{time}` replaced by the duration.
+    relative: [&'static str; 3],
+    /// Exactly one day ago and one day from now.
+    adjacent_days: [&'static str; 2],
+}
+
+impl UnitLabels {
+    /// Gets the unit names using the lookup function.
+    fn new(tr: impl Fn(&'static str) -> &'static str) -> Self {
+        Self {
+            short: [
+                tr("duration.day.short"),
+                tr("duration.hour.short"),
+                tr("duration.minute.short"),
+                tr("duration.second.short"),
+            ],
+            singular: [
+                tr("duration.day.one"),
+                tr("duration.hour.one"),
+                tr("duration.minute.one"),
+                tr("duration.second.one"),
+            ],
+            plural: [
+                tr("duration.day.other"),
+                tr("duration.hour.other"),
+                tr("duration.minute.other"),
+                tr("duration.second.other"),
+            ],
+            relative: [
+                tr("duration.relative.now"),
+                tr("duration.relative.past"),
+                tr("duration.relative.future"),
+            ],
+            adjacent_days: [tr("duration.yesterday"), tr("duration.tomorrow")],
+        }
+    }
+}
+
+/// Gets the unit names for the time locale, falling back to English.
+fn unit_labels() -> UnitLabels {
+    UnitLabels::new(|key| i18n::tr_category(Category::Time, key))
+}
+
+/// Formats the number of seconds in the style,
+/// using unit names for the current locale.
+///
+/// Negative durations, such as time over a deadline, are prefixed with `-`.
+pub fn format_duration(seconds: i64, style: DurationStyle) -> String {
+    format_with_labels(seconds, style, &unit_labels())
+}
+
+/// Formats the duration like a stopwatch, such as `01:05.43`,
+/// with minutes, seconds and hundredths, and hours once reached.
+pub fn format_precise(duration: Duration) -> String {
+    let hundredths = duration.subsec_millis() / 10;
+    let seconds = duration.as_secs();
+
+    let (hours, minutes, seconds) = (seconds / HOUR, seconds % HOUR / MINUTE, seconds % MINUTE);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}.{hundredths:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}.{hundredths:02}")
+    }
+}
+
+/// Formats the number of seconds as hours and minutes, such as `05:12`.
+/// Hours keep counting past a day.
+pub fn format_hours_minutes(seconds: u64) -> String {
+    format!("{:02}:{:02}", seconds / HOUR, seconds % HOUR / MINUTE)
+}
+
+fn format_with_labels(seconds: i64, style: DurationStyle, labels: &UnitLabels) -> String {
+    let past = seconds < 0;
+    let sign = if past { "-" } else { "" };
+    let seconds = seconds.unsigned_abs();
+
+    let units = [
+        seconds / DAY,
+        seconds % DAY / HOUR,
+        seconds % HOUR / MINUTE,
+        seconds % MINUTE,
+    ];
+
+    let formatted = match style {
+        DurationStyle::Clock => {
+            let hours = seconds / HOUR;
+            let [_, _, minutes, seconds] = units;
+
+            if hours > 0 {
+                format!("{hours}:{minutes:02}:{seconds:02}")
+            } else {
+                format!("{minutes}:{seconds:02}")
+            }
+        }
+        DurationStyle::Compact => {
+            // the largest unit, and the next one down if it is not zero
+            let largest = units.iter().position(|&value| value > 0).unwrap_or(3);
+
+            units[largest..]
+                .iter()
+                .zip(&labels.short[largest..])
+                .take(2)
+                .enumerate()
+                .filter(|&(i, (&value, _))| i == 0 || value > 0)
+                .map(|(_, (value, label))| format!("{value}{label}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+        DurationStyle::Verbose => {
+            let count = if seconds < HOUR { 4 } else { 3 };
+
+            let parts = units[..count]
+                .iter()
+                .enumerate()
+                .filter(|(_, &value)| value > 0)
+                .map(|(i, &value)| {
+                    let label = if value == 1 {
+                        labels.singular[i]
+                    } else {
+                        labels.plural[i]
+                    };
+
+                    format!("{value} {label}")
+                })
+                .collect::<Vec<_>>();
+
+            if parts.is_empty() {
+                format!("0 {}", labels.plural[3])
+            } else {
+                parts.join(", ")
+            }
+        }
+        DurationStyle::Relative => return format_relative(units, past, labels),
+    };
+
+    format!("{sign}{formatted}")
+}
+
+/// Formats the largest unit relative to now,
+/// as the direction replaces the sign.
+fn format_relative(units: [u64; 4], past: bool, labels: &UnitLabels) -> String {
+    let [now, past_label, future_label] = labels.relative;
+
+    let Some(largest) = units.iter().position(|&value| value > 0) else {
+        return now.to_string();
+    };
+
+    let value = units[largest];
+
+    if largest == 0 && value == 1 {
+        return labels.adjacent_days[usize::from(!past)].to_string();
+    }
+
+    let label = if value == 1 {
+        labels.singular[largest]
+    } else {
+        labels.plural[largest]
+    };
+
+    let time = format!("{value} {label}");
+
+    if past { past_label } else { future_label }.replace("{time}", &time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels_for(locale: &str) -> UnitLabels {
+        UnitLabels::new(|key| i18n::tr_locale(locale, key))
+    }
+
+    fn format(seconds: i64, style: DurationStyle) -> String {
+        format_with_labels(seconds, style, &labels_for("en"))
+    }
+
+    #[test]
+    fn formats_clock() {
+        assert_eq!(format(0, DurationStyle::Clock), "0:00");
+        assert_eq!(format(65, DurationStyle::Clock), "1:05");
+        assert_eq!(format(3737, DurationStyle::Clock), "1:02:17");
+        assert_eq!(format(26 * 3600 + 5, DurationStyle::Clock), "26:00:05");
+        assert_eq!(format(-90, DurationStyle::Clock), "-1:30");
+    }
+
+    #[test]
+    fn formats_compact() {
+        assert_eq!(format(0, DurationStyle::Compact), "0s");
+        assert_eq!(format(45, DurationStyle::Compact), "45s");
+        assert_eq!(format(7830, DurationStyle::Compact), "2h 10m");
+        assert_eq!(format(7200, DurationStyle::Compact), "2h");
+        assert_eq!(format(86400 + 300, DurationStyle::Compact), "1d");
+        assert_eq!(format(3 * 86400 + 7200, DurationStyle::Compact), "3d 2h");
+        assert_eq!(format(-600, DurationStyle::Compact), "-10m");
+    }
+
+    #[test]
+    fn formats_verbose() {
+        assert_eq!(format(0, DurationStyle::Verbose), "0 seconds");
+        assert_eq!(format(61, DurationStyle::Verbose), "1 minute, 1 second");
+        assert_eq!(format(7830, DurationStyle::Verbose), "2 hours, 10 minutes");
+        assert_eq!(
+            format(90061, DurationStyle::Verbose),
+            "1 day, 1 hour, 1 minute"
+        );
+        assert_eq!(format(-120, DurationStyle::Verbose), "-2 minutes");
+    }
+
+    #[test]
+    fn formats_relative() {
+        assert_eq!(format(0, DurationStyle::Relative), "now");
+        assert_eq!(format(1, DurationStyle::Relative), "in 1 second");
+        assert_eq!(format(7830, DurationStyle::Relative), "in 2 hours");
+        assert_eq!(format(-300, DurationStyle::Relative), "5 minutes ago");
+        assert_eq!(format(-86400 - 3600, DurationStyle::Relative), "yesterday");
+        assert_eq!(format(86400, DurationStyle::Relative), "tomorrow");
+        assert_eq!(format(-3 * 86400, DurationStyle::Relative), "3 days ago");
+    }
+
+    #[test]
+    fn formats_fixed_styles() {
+        assert_eq!(format_precise(Duration::ZERO), "00:00.00");
+        assert_eq!(format_precise(Duration::from_millis(65_432)), "01:05.43");
+        assert_eq!(format_precise(Duration::from_secs(3723)), "1:02:03.00");
+
+        assert_eq!(format_hours_minutes(0), "00:00");
+        assert_eq!(format_hours_minutes(3 * 3600 + 125), "03:02");
+        assert_eq!(format_hours_minutes(30 * 3600), "30:00");
+    }
+
+    #[test]
+    fn uses_locale_labels() {
+        assert_eq!(labels_for("de_DE.UTF-8").plural[1], "Stunden");
+        assert_eq!(labels_for("fr").short[0], "j");
+        assert_eq!(labels_for("POSIX").short[2], "m");
+        assert_eq!(labels_for("").singular[0], "day");
+        assert_eq!(
+            format_with_labels(3660, DurationStyle::Verbose, &labels_for("de")),
+            "1 Stunde, 1 Minute"
+        );
+        assert_eq!(
+            format_with_labels(-7200, DurationStyle::Relative, &labels_for("es")),
+            "hace 2 horas"
+        );
+    }
+}
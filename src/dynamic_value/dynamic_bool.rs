@@ -1,4 +1,4 @@
-use crate::script::Script;
+use crate::script::{Script, ScriptEnv};
 use crate::{glib_recv_mpsc, spawn, try_send};
 #[cfg(feature = "ipc")]
 use crate::{send_async, Ironbar};
@@ -18,7 +18,7 @@ pub enum DynamicBool {
 }
 
 impl DynamicBool {
-    pub fn subscribe<F>(self, mut f: F)
+    pub fn subscribe<F>(self, env: &ScriptEnv, mut f: F)
     where
         F: FnMut(bool) + 'static,
     {
@@ -44,10 +44,13 @@ impl DynamicBool {
 
         glib_recv_mpsc!(rx, val => f(val));
 
+        let env = env.clone();
+
         spawn(async move {
             match value {
                 DynamicBool::Script(script) => {
                     script
+                        .with_env(&env)
                         .run(None, |_, success| {
                             try_send!(tx, success);
                         })
@@ -1,8 +1,16 @@
-use crate::script::{OutputStream, Script};
+#[cfg(feature = "workspaces")]
+use crate::clients::compositor::{self, CompositorState, StateValue};
+use crate::clients::wayland::{OutputDetails, OutputValue};
+use crate::script::{OutputStream, Script, ScriptEnv};
 #[cfg(feature = "ipc")]
 use crate::Ironbar;
-use crate::{arc_mut, glib_recv_mpsc, lock, spawn, try_send};
+use crate::{arc_mut, glib_recv_mpsc, lock, send, spawn, try_send};
+use color_eyre::{Report, Result};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+use tracing::error;
 
 /// A segment of a dynamic string,
 /// containing either a static string
@@ -13,19 +21,158 @@ enum DynamicStringSegment {
     Script(Script),
     #[cfg(feature = "ipc")]
     Variable(Box<str>),
+    #[cfg(feature = "workspaces")]
+    Compositor(StateValue),
+    /// A detail of the output the bar is on.
+    Output(OutputValue),
+    /// A dynamic segment with a `throttle` or `debounce` modifier.
+    Limited(Box<DynamicStringSegment>, RateLimit),
+}
+
+/// Limits how often a segment updates the string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RateLimit {
+    /// Updates at most once per window.
+    /// The first value is shown straight away,
+    /// and the latest value received during the window is shown once it ends.
+    Throttle(Duration),
+    /// Updates once no values have been received for the period,
+    /// showing the latest value.
+    Debounce(Duration),
+}
+
+impl RateLimit {
+    /// Parses a `throttle:<ms>` or `debounce:<ms>` modifier.
+    /// Returns `None` if the text is not a rate limit modifier.
+    fn parse(modifier: &str) -> Option<Result<Self>> {
+        let (kind, value) = modifier.split_once(':')?;
+
+        let limit: fn(Duration) -> Self = match kind {
+            "throttle" => Self::Throttle,
+            "debounce" => Self::Debounce,
+            _ => return None,
+        };
+
+        let limit = value
+            .parse()
+            .map(|ms| limit(Duration::from_millis(ms)))
+            .map_err(|_| {
+                Report::msg(format!(
+                    "Invalid modifier '{modifier}': expected a number of milliseconds, such as '{kind}:500'. \
+                    'throttle' updates at most once per period, \
+                    while 'debounce' updates once values stop arriving for the period"
+                ))
+            });
+
+        Some(limit)
+    }
+
+    /// Passes values from `rx` to `f`,
+    /// dropping or holding back values to keep within the limit.
+    ///
+    /// The latest value is always passed on eventually.
+    async fn apply(self, mut rx: mpsc::UnboundedReceiver<String>, mut f: impl FnMut(String)) {
+        let mut pending = None;
+        let mut deadline = None;
+
+        loop {
+            tokio::select! {
+                value = rx.recv() => {
+                    let Some(value) = value else {
+                        break;
+                    };
+
+                    match self {
+                        Self::Throttle(_) if deadline.is_some() => pending = Some(value),
+                        Self::Throttle(window) => {
+                            f(value);
+                            deadline = Some(Instant::now() + window);
+                        }
+                        Self::Debounce(period) => {
+                            pending = Some(value);
+                            deadline = Some(Instant::now() + period);
+                        }
+                    }
+                }
+                () = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    deadline = None;
+
+                    if let Some(value) = pending.take() {
+                        f(value);
+
+                        // the held back value starts a new window
+                        if let Self::Throttle(window) = self {
+                            deadline = Some(Instant::now() + window);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(value) = pending {
+            f(value);
+        }
+    }
+}
+
+/// A segment's part of the string.
+struct Part {
+    index: usize,
+    label_parts: Arc<Mutex<Vec<String>>>,
+    tx: mpsc::Sender<String>,
+}
+
+impl Part {
+    /// Replaces the part's value, and sends the updated string.
+    fn set(&self, value: String) {
+        let mut label_parts = lock!(self.label_parts);
+
+        let _: String = std::mem::replace(&mut label_parts[self.index], value);
+
+        let string = label_parts.join("");
+        try_send!(self.tx, string);
+    }
+}
+
+/// Sets a segment's part of the string,
+/// passing values through its rate limit first if it has one.
+enum PartUpdater {
+    Direct(Part),
+    Limited(mpsc::UnboundedSender<String>),
+}
+
+impl PartUpdater {
+    fn new(part: Part, limit: Option<RateLimit>) -> Self {
+        match limit {
+            Some(limit) => {
+                let (tx, rx) = mpsc::unbounded_channel();
+                spawn(limit.apply(rx, move |value| part.set(value)));
+                Self::Limited(tx)
+            }
+            None => Self::Direct(part),
+        }
+    }
+
+    fn update(&self, value: String) {
+        match self {
+            Self::Direct(part) => part.set(value),
+            Self::Limited(tx) => send!(tx, value),
+        }
+    }
 }
 
 /// Creates a new dynamic string, based off the input template.
 /// Runs `f` with the compiled string each time one of the scripts or variables updates.
+/// Scripts are run with the bar context from `env`.
 ///
 /// # Example
 ///
 /// ```rs
-/// dynamic_string(&text, move |string| {
+/// dynamic_string(&text, &context.script_env, move |string| {
 ///     label.set_markup(&string);
 /// });
 /// ```
-pub fn dynamic_string<F>(input: &str, mut f: F)
+pub fn dynamic_string<F>(input: &str, env: &ScriptEnv, mut f: F)
 where
     F: FnMut(String) + 'static,
 {
@@ -35,27 +182,37 @@ where
     let (tx, rx) = mpsc::channel(32);
 
     for (i, segment) in tokens.into_iter().enumerate() {
+        let (segment, limit) = match segment {
+            DynamicStringSegment::Limited(segment, limit) => (*segment, Some(limit)),
+            segment => (segment, None),
+        };
+
+        if let DynamicStringSegment::Static(str) = segment {
+            lock!(label_parts).push(str);
+            continue;
+        }
+
+        // insert blank value to preserve segment order
+        lock!(label_parts).push(String::new());
+
+        let updater = PartUpdater::new(
+            Part {
+                index: i,
+                label_parts: label_parts.clone(),
+                tx: tx.clone(),
+            },
+            limit,
+        );
+
         match segment {
-            DynamicStringSegment::Static(str) => {
-                lock!(label_parts).push(str);
-            }
             DynamicStringSegment::Script(script) => {
-                let tx = tx.clone();
-                let label_parts = label_parts.clone();
-
-                // insert blank value to preserve segment order
-                lock!(label_parts).push(String::new());
+                let script = script.with_env(env);
 
                 spawn(async move {
                     script
                         .run(None, |out, _| {
                             if let OutputStream::Stdout(out) = out {
-                                let mut label_parts = lock!(label_parts);
-
-                                let _: String = std::mem::replace(&mut label_parts[i], out);
-
-                                let string = label_parts.join("");
-                                try_send!(tx, string);
+                                updater.update(out);
                             }
                         })
                         .await;
@@ -63,28 +220,59 @@ where
             }
             #[cfg(feature = "ipc")]
             DynamicStringSegment::Variable(name) => {
-                let tx = tx.clone();
-                let label_parts = label_parts.clone();
-
-                // insert blank value to preserve segment order
-                lock!(label_parts).push(String::new());
-
                 spawn(async move {
                     let variable_manager = Ironbar::variable_manager();
                     let mut rx = crate::write_lock!(variable_manager).subscribe(name);
 
                     while let Ok(value) = rx.recv().await {
                         if let Some(value) = value {
-                            let mut label_parts = lock!(label_parts);
+                            updater.update(value);
+                        }
+                    }
+                });
+            }
+            #[cfg(feature = "workspaces")]
+            DynamicStringSegment::Compositor(value) => {
+                let mut rx = CompositorState::subscribe();
 
-                            let _: String = std::mem::replace(&mut label_parts[i], value);
+                spawn(async move {
+                    loop {
+                        updater.update(rx.borrow_and_update().get(value));
 
-                            let string = label_parts.join("");
-                            try_send!(tx, string);
+                        if rx.changed().await.is_err() {
+                            break;
                         }
                     }
                 });
             }
+            DynamicStringSegment::Output(value) => {
+                let name = env.monitor_name().to_string();
+                let mut rx = OutputDetails::subscribe();
+
+                spawn(async move {
+                    let mut current = None;
+
+                    loop {
+                        let new = rx
+                            .borrow_and_update()
+                            .get(&name)
+                            .map(|details| details.get(value))
+                            .unwrap_or_default();
+
+                        // other outputs changing does not change this value
+                        if current.as_ref() != Some(&new) {
+                            current = Some(new.clone());
+                            updater.update(new);
+                        }
+
+                        if rx.changed().await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            // handled above, and modifiers cannot be nested
+            DynamicStringSegment::Static(_) | DynamicStringSegment::Limited(..) => {}
         }
     }
 
@@ -117,8 +305,7 @@ fn parse_input(input: &str) -> (Vec<DynamicStringSegment>, bool) {
         let (token, skip) = match char_pair {
             Some(['{', '{']) => parse_script(&chars),
             Some(['#', '#']) => (DynamicStringSegment::Static("#".to_string()), 2),
-            #[cfg(feature = "ipc")]
-            Some(['#', _]) => parse_variable(&chars),
+            Some(['#', _]) => parse_hash(&chars).unwrap_or_else(|| parse_static(&chars)),
             _ => parse_static(&chars),
         };
 
@@ -132,6 +319,24 @@ fn parse_input(input: &str) -> (Vec<DynamicStringSegment>, bool) {
     (tokens, false)
 }
 
+/// Parses a `#` reference to a compositor value, output value or ironvar,
+/// including any modifier.
+///
+/// Returns `None` if this build does not support the reference.
+fn parse_hash(chars: &[char]) -> Option<(DynamicStringSegment, usize)> {
+    #[cfg(feature = "workspaces")]
+    let reference = parse_compositor(chars);
+    #[cfg(not(feature = "workspaces"))]
+    let reference = None;
+
+    let reference = reference.or_else(|| parse_output(chars));
+
+    #[cfg(feature = "ipc")]
+    let reference = reference.or_else(|| Some(parse_variable(chars)));
+
+    reference.map(|(token, skip)| parse_modifier(chars, token, skip))
+}
+
 fn parse_script(chars: &[char]) -> (DynamicStringSegment, usize) {
     const SKIP_BRACKETS: usize = 4; // two braces either side
 
@@ -143,9 +348,61 @@ fn parse_script(chars: &[char]) -> (DynamicStringSegment, usize) {
         .collect::<String>();
 
     let len = str.chars().count() + SKIP_BRACKETS;
-    let script = Script::from(str.as_str());
 
-    (DynamicStringSegment::Script(script), len)
+    // a trailing modifier, such as `{{cava.sh | throttle:500}}`
+    let modifier = str
+        .rsplit_once('|')
+        .and_then(|(cmd, modifier)| Some((cmd, RateLimit::parse(modifier.trim())?)));
+
+    let segment = match modifier {
+        Some((cmd, limit)) => {
+            let script = DynamicStringSegment::Script(Script::from(cmd.trim_end()));
+            limit_segment(script, limit)
+        }
+        None => DynamicStringSegment::Script(Script::from(str.as_str())),
+    };
+
+    (segment, len)
+}
+
+/// Parses a rate limit modifier directly after a variable or compositor value,
+/// such as `#volume|debounce:200`.
+///
+/// `len` is the length of the segment before the modifier.
+fn parse_modifier(
+    chars: &[char],
+    segment: DynamicStringSegment,
+    len: usize,
+) -> (DynamicStringSegment, usize) {
+    if chars.get(len) != Some(&'|') {
+        return (segment, len);
+    }
+
+    let modifier = chars
+        .iter()
+        .skip(len + 1)
+        .take_while(|&c| c.is_ascii_alphanumeric() || c == &':')
+        .collect::<String>();
+
+    match RateLimit::parse(&modifier) {
+        Some(limit) => (
+            limit_segment(segment, limit),
+            len + 1 + modifier.chars().count(),
+        ),
+        None => (segment, len),
+    }
+}
+
+/// Applies the rate limit to the segment.
+/// If the modifier is invalid, the error is logged and the segment is left unlimited.
+fn limit_segment(segment: DynamicStringSegment, limit: Result<RateLimit>) -> DynamicStringSegment {
+    match limit {
+        Ok(limit) => DynamicStringSegment::Limited(Box::new(segment), limit),
+        Err(err) => {
+            error!("{err:?}");
+            segment
+        }
+    }
 }
 
 #[cfg(feature = "ipc")]
@@ -164,6 +421,46 @@ fn parse_variable(chars: &[char]) -> (DynamicStringSegment, usize) {
     (DynamicStringSegment::Variable(value), len)
 }
 
+/// Parses a `#<prefix><name>` reference,
+/// returning the name and the length of the reference.
+/// Returns `None` if the input does not start with the prefix.
+fn parse_reference(chars: &[char], prefix: &str) -> Option<(String, usize)> {
+    const SKIP_HASH: usize = 1;
+
+    let str = chars
+        .iter()
+        .skip(1)
+        .take_while(|&c| c.is_ascii_alphanumeric() || c == &'_' || c == &'.')
+        .collect::<String>();
+
+    // allow for a full stop directly after the reference
+    let str = str.trim_end_matches('.');
+
+    let name = str.strip_prefix(prefix)?.to_string();
+    let len = str.chars().count() + SKIP_HASH;
+
+    Some((name, len))
+}
+
+/// Parses a `#compositor.<value>` state reference.
+/// Returns `None` if the input does not reference a known value.
+#[cfg(feature = "workspaces")]
+fn parse_compositor(chars: &[char]) -> Option<(DynamicStringSegment, usize)> {
+    let (name, len) = parse_reference(chars, compositor::PREFIX)?;
+    let value = StateValue::parse(&name)?;
+
+    Some((DynamicStringSegment::Compositor(value), len))
+}
+
+/// Parses a `#output.<value>` reference to the bar's output.
+/// Returns `None` if the input does not reference a known value.
+fn parse_output(chars: &[char]) -> Option<(DynamicStringSegment, usize)> {
+    let (name, len) = parse_reference(chars, OutputValue::PREFIX)?;
+    let value = OutputValue::parse(&name)?;
+
+    Some((DynamicStringSegment::Output(value), len))
+}
+
 fn parse_static(chars: &[char]) -> (DynamicStringSegment, usize) {
     let mut str = chars
         .windows(2)
@@ -227,6 +524,33 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "workspaces")]
+    fn test_compositor() {
+        const INPUT: &str = "#compositor.window_count windows";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 2);
+        assert!(matches!(
+            &tokens[0],
+            DynamicStringSegment::Compositor(StateValue::WindowCount)
+        ));
+        assert!(matches!(&tokens[1], DynamicStringSegment::Static(str) if str == " windows"));
+    }
+
+    #[test]
+    fn test_output() {
+        const INPUT: &str = "#output.description.";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 2);
+        assert!(matches!(
+            &tokens[0],
+            DynamicStringSegment::Output(OutputValue::Description)
+        ));
+        assert!(matches!(&tokens[1], DynamicStringSegment::Static(str) if str == "."));
+    }
+
     #[test]
     fn test_static_script() {
         const INPUT: &str = "hello {{echo world}}";
@@ -317,6 +641,123 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_script_modifier() {
+        const INPUT: &str = "{{cava.sh | throttle:500}}";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 1);
+        assert!(matches!(
+            &tokens[0],
+            DynamicStringSegment::Limited(segment, RateLimit::Throttle(window))
+            if *window == Duration::from_millis(500)
+                && matches!(segment.as_ref(), DynamicStringSegment::Script(script) if script.cmd == "cava.sh")
+        ));
+    }
+
+    #[test]
+    fn test_script_pipe() {
+        const INPUT: &str = "{{echo hello | tr h j}}";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert!(
+            matches!(&tokens[0], DynamicStringSegment::Script(script) if script.cmd == "echo hello | tr h j")
+        );
+    }
+
+    #[test]
+    fn test_invalid_modifier() {
+        const INPUT: &str = "{{echo hello|throttle:soon}}";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert!(
+            matches!(&tokens[0], DynamicStringSegment::Script(script) if script.cmd == "echo hello")
+        );
+
+        let err = RateLimit::parse("debounce:-1")
+            .expect("to be a modifier")
+            .expect_err("to be invalid");
+        assert!(err.to_string().contains("'debounce:500'"));
+
+        assert!(RateLimit::parse("tr:a").is_none());
+    }
+
+    #[test]
+    fn test_variable_modifier() {
+        const INPUT: &str = "#volume|debounce:200 %";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 2);
+        assert!(matches!(
+            &tokens[0],
+            DynamicStringSegment::Limited(segment, RateLimit::Debounce(period))
+            if *period == Duration::from_millis(200)
+                && matches!(segment.as_ref(), DynamicStringSegment::Variable(name) if name.to_string() == "volume")
+        ));
+        assert!(matches!(&tokens[1], DynamicStringSegment::Static(str) if str == " %"));
+    }
+
+    /// Sends each value at its time in milliseconds,
+    /// returning the times values were passed on.
+    async fn rate_limit(limit: RateLimit, values: &[(u64, &str)]) -> Vec<(u64, String)> {
+        let start = Instant::now();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let mut updates = vec![];
+
+        let send = async move {
+            for (time, value) in values {
+                sleep_until(start + Duration::from_millis(*time)).await;
+                send!(tx, value.to_string());
+            }
+
+            sleep_until(start + Duration::from_secs(10)).await;
+        };
+
+        tokio::join!(
+            limit.apply(rx, |value| {
+                let time = start.elapsed().as_millis() as u64;
+                updates.push((time, value));
+            }),
+            send
+        );
+
+        updates
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle() {
+        let updates = rate_limit(
+            RateLimit::Throttle(Duration::from_millis(100)),
+            &[(0, "a"), (10, "b"), (20, "c"), (150, "d"), (400, "e")],
+        )
+        .await;
+
+        assert_eq!(
+            updates,
+            [
+                (0, String::from("a")),
+                (100, String::from("c")),
+                (200, String::from("d")),
+                (400, String::from("e")),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debounce() {
+        let updates = rate_limit(
+            RateLimit::Debounce(Duration::from_millis(100)),
+            &[(0, "a"), (50, "b"), (90, "c"), (300, "d")],
+        )
+        .await;
+
+        assert_eq!(
+            updates,
+            [(190, String::from("c")), (400, String::from("d"))]
+        );
+    }
+
     #[test]
     fn test_pango_attribute() {
         const INPUT: &str = "<span color='#color'>hello</span>";
@@ -0,0 +1,724 @@
+use color_eyre::{Report, Result};
+use std::fmt::{Display, Formatter};
+
+/// A value produced while evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn as_number(&self) -> Result<f64> {
+        match self {
+            Self::Number(number) => Ok(*number),
+            Self::Text(text) => text
+                .trim()
+                .parse()
+                .map_err(|_| Report::msg(format!("'{text}' is not a number"))),
+            Self::Bool(value) => Ok(f64::from(u8::from(*value))),
+        }
+    }
+
+    /// Text is 'truthy' unless empty, `0` or `false`,
+    /// matching how dynamic booleans treat variables.
+    pub fn as_bool(&self) -> bool {
+        match self {
+            Self::Number(number) => *number != 0.0,
+            Self::Text(text) => !(text.is_empty() || text == "0" || text == "false"),
+            Self::Bool(value) => *value,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            // whole numbers are written without a trailing `.0`
+            Self::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                write!(f, "{}", *number as i64)
+            }
+            Self::Number(number) => write!(f, "{number}"),
+            Self::Text(text) => write!(f, "{text}"),
+            Self::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnaryOp {
+    Negate,
+    Not,
+}
+
+/// A function applied to a value using `value | filter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    Round,
+    Floor,
+    Ceil,
+    Abs,
+    Upper,
+    Lower,
+    Trim,
+}
+
+impl Filter {
+    fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "round" => Ok(Self::Round),
+            "floor" => Ok(Self::Floor),
+            "ceil" => Ok(Self::Ceil),
+            "abs" => Ok(Self::Abs),
+            "upper" => Ok(Self::Upper),
+            "lower" => Ok(Self::Lower),
+            "trim" => Ok(Self::Trim),
+            _ => Err(Report::msg(format!("Unknown filter '{name}'"))),
+        }
+    }
+
+    fn apply(self, value: Value) -> Result<Value> {
+        let value = match self {
+            Self::Round => Value::Number(value.as_number()?.round()),
+            Self::Floor => Value::Number(value.as_number()?.floor()),
+            Self::Ceil => Value::Number(value.as_number()?.ceil()),
+            Self::Abs => Value::Number(value.as_number()?.abs()),
+            Self::Upper => Value::Text(value.to_string().to_uppercase()),
+            Self::Lower => Value::Text(value.to_string().to_lowercase()),
+            Self::Trim => Value::Text(value.to_string().trim().to_string()),
+        };
+
+        Ok(value)
+    }
+}
+
+/// A value read from outside Ironbar when evaluated,
+/// which can only be used in conditions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// `env:NAME` - the value of the environment variable.
+    Env(Box<str>),
+    /// `file:path` - whether the path exists.
+    File(Box<str>),
+    /// `cmd:command` - whether the command succeeds.
+    /// This takes the rest of the expression.
+    Command(Box<str>),
+    /// `host` - the hostname.
+    Host,
+}
+
+impl Source {
+    fn new(kind: &str, argument: String) -> Result<Self> {
+        let source = match kind {
+            "env" if argument.is_empty() => {
+                return Err(Report::msg("Missing variable name after 'env:'"))
+            }
+            "env"
+                if !argument
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+            {
+                return Err(Report::msg(format!("Invalid variable name '{argument}'")))
+            }
+            "env" => Self::Env(argument.into()),
+            "file" if argument.is_empty() => return Err(Report::msg("Missing path after 'file:'")),
+            "file" => Self::File(argument.into()),
+            "cmd" if argument.is_empty() => {
+                return Err(Report::msg("Missing command after 'cmd:'"))
+            }
+            "cmd" => Self::Command(argument.into()),
+            _ => return Err(Report::msg(format!("Unknown source '{kind}:'"))),
+        };
+
+        Ok(source)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Value(Value),
+    Variable(Box<str>),
+    Source(Source),
+    Unary(UnaryOp, Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Filter(Filter, Box<Node>),
+}
+
+/// An expression combining variables using arithmetic,
+/// comparisons and filters, such as `#uptime / 3600 | round`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    node: Node,
+    /// The name of each variable referenced, without the `#`.
+    variables: Vec<Box<str>>,
+}
+
+impl Expression {
+    /// Parses an expression which can only reference variables.
+    #[cfg_attr(not(feature = "ipc"), allow(dead_code))]
+    pub fn parse(input: &str) -> Result<Self> {
+        Self::parse_with(input, false)
+    }
+
+    /// Parses a condition, which can also read [sources](Source)
+    /// such as `env:NAME` when evaluated.
+    pub fn parse_condition(input: &str) -> Result<Self> {
+        Self::parse_with(input, true)
+    }
+
+    fn parse_with(input: &str, sources: bool) -> Result<Self> {
+        let tokens = tokenize(input)?;
+
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+            variables: vec![],
+            sources,
+        };
+
+        let node = parser.expression()?;
+
+        if let Some(token) = parser.peek() {
+            return Err(Report::msg(format!("Unexpected {token:?} in '{input}'")));
+        }
+
+        let mut variables = parser.variables;
+        variables.sort();
+        variables.dedup();
+
+        Ok(Self { node, variables })
+    }
+
+    /// Gets the names of the variables this depends on.
+    pub fn variables(&self) -> &[Box<str>] {
+        &self.variables
+    }
+
+    /// Evaluates the expression,
+    /// using `get` to look up each variable's current value.
+    #[cfg_attr(not(feature = "ipc"), allow(dead_code))]
+    pub fn evaluate(&self, get: &impl Fn(&str) -> Option<String>) -> Result<Value> {
+        // sources are rejected when parsing, so are never read
+        evaluate(&self.node, get, &|source| {
+            Err(Report::msg(format!("{source:?} is not supported")))
+        })
+    }
+
+    /// Checks whether the condition holds,
+    /// using `get` to look up each variable's current value
+    /// and `read` to read each source.
+    pub fn check_with(
+        &self,
+        get: &impl Fn(&str) -> Option<String>,
+        read: &impl Fn(&Source) -> Result<Value>,
+    ) -> Result<bool> {
+        truthy(&self.node, get, read)
+    }
+}
+
+/// Evaluates the node as a boolean.
+///
+/// Text read directly from a source only needs to be non-empty,
+/// so that `env:NAME` holds for a variable set to `0` or `false`.
+fn truthy(
+    node: &Node,
+    get: &impl Fn(&str) -> Option<String>,
+    read: &impl Fn(&Source) -> Result<Value>,
+) -> Result<bool> {
+    let value = evaluate(node, get, read)?;
+
+    Ok(match (node, value) {
+        (Node::Source(_), Value::Text(text)) => !text.is_empty(),
+        (_, value) => value.as_bool(),
+    })
+}
+
+fn evaluate(
+    node: &Node,
+    get: &impl Fn(&str) -> Option<String>,
+    read: &impl Fn(&Source) -> Result<Value>,
+) -> Result<Value> {
+    let value = match node {
+        Node::Value(value) => value.clone(),
+        Node::Variable(name) => get(name)
+            .map(Value::Text)
+            .ok_or_else(|| Report::msg(format!("'#{name}' is not set")))?,
+        Node::Source(source) => read(source)?,
+        Node::Unary(UnaryOp::Negate, node) => {
+            Value::Number(-evaluate(node, get, read)?.as_number()?)
+        }
+        Node::Unary(UnaryOp::Not, node) => Value::Bool(!truthy(node, get, read)?),
+        Node::Binary(BinaryOp::And, left, right) => {
+            Value::Bool(truthy(left, get, read)? && truthy(right, get, read)?)
+        }
+        Node::Binary(BinaryOp::Or, left, right) => {
+            Value::Bool(truthy(left, get, read)? || truthy(right, get, read)?)
+        }
+        Node::Binary(op, left, right) => {
+            binary(*op, evaluate(left, get, read)?, evaluate(right, get, read)?)?
+        }
+        Node::Filter(filter, node) => filter.apply(evaluate(node, get, read)?)?,
+    };
+
+    Ok(value)
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value> {
+    // equality compares numbers where both sides are numbers, and text otherwise
+    if matches!(op, BinaryOp::Equal | BinaryOp::NotEqual) {
+        let equal = match (left.as_number(), right.as_number()) {
+            (Ok(left), Ok(right)) => left == right,
+            _ => left.to_string() == right.to_string(),
+        };
+
+        return Ok(Value::Bool(equal == (op == BinaryOp::Equal)));
+    }
+
+    let (left, right) = (left.as_number()?, right.as_number()?);
+
+    let value = match op {
+        BinaryOp::Add => Value::Number(left + right),
+        BinaryOp::Subtract => Value::Number(left - right),
+        BinaryOp::Multiply => Value::Number(left * right),
+        BinaryOp::Divide | BinaryOp::Remainder if right == 0.0 => {
+            return Err(Report::msg("Division by zero"))
+        }
+        BinaryOp::Divide => Value::Number(left / right),
+        BinaryOp::Remainder => Value::Number(left % right),
+        BinaryOp::Less => Value::Bool(left < right),
+        BinaryOp::LessOrEqual => Value::Bool(left <= right),
+        BinaryOp::Greater => Value::Bool(left > right),
+        BinaryOp::GreaterOrEqual => Value::Bool(left >= right),
+        BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::And | BinaryOp::Or => unreachable!(),
+    };
+
+    Ok(value)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Variable(Box<str>),
+    Name(String),
+    /// A `kind:argument` source.
+    Source(String, String),
+    Op(&'static str),
+    Pipe,
+    OpenParen,
+    CloseParen,
+}
+
+/// Operators, longest first so that `<=` is not read as `<`.
+const OPERATORS: [&str; 15] = [
+    "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "=",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars = input.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    let take_while = |start: usize, f: fn(char) -> bool| {
+        let end = chars[start..]
+            .iter()
+            .position(|&c| !f(c))
+            .map_or(chars.len(), |len| start + len);
+
+        (chars[start..end].iter().collect::<String>(), end)
+    };
+
+    while i < chars.len() {
+        let char = chars[i];
+
+        match char {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token::OpenParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::CloseParen);
+                i += 1;
+            }
+            '#' => {
+                let (name, end) =
+                    take_while(i + 1, |c| c.is_alphanumeric() || c == '_' || c == '-');
+                if name.is_empty() {
+                    return Err(Report::msg("Expected a variable name after '#'"));
+                }
+
+                tokens.push(Token::Variable(name.into()));
+                i = end;
+            }
+            '"' => {
+                let Some(len) = chars[i + 1..].iter().position(|&c| c == '"') else {
+                    return Err(Report::msg("Unclosed string"));
+                };
+
+                let end = i + 1 + len;
+                tokens.push(Token::Text(chars[i + 1..end].iter().collect()));
+                i = end + 1;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let (number, end) = take_while(i, |c| c.is_ascii_digit() || c == '.');
+                let number = number
+                    .parse()
+                    .map_err(|_| Report::msg(format!("Invalid number '{number}'")))?;
+
+                tokens.push(Token::Number(number));
+                i = end;
+            }
+            c if c.is_alphabetic() => {
+                let (name, end) = take_while(i, |c| c.is_alphanumeric() || c == '_');
+
+                if chars.get(end) == Some(&':') {
+                    let (argument, end) = source_argument(&chars, &name, end + 1)?;
+                    tokens.push(Token::Source(name, argument));
+                    i = end;
+                } else {
+                    tokens.push(Token::Name(name));
+                    i = end;
+                }
+            }
+            _ => {
+                let rest = chars[i..].iter().take(2).collect::<String>();
+
+                if rest.starts_with('|') && rest != "||" {
+                    tokens.push(Token::Pipe);
+                    i += 1;
+                    continue;
+                }
+
+                let Some(op) = OPERATORS.into_iter().find(|op| rest.starts_with(op)) else {
+                    return Err(Report::msg(format!("Unexpected character '{char}'")));
+                };
+
+                // a single `=` is most likely a mistyped `==`
+                if op == "=" {
+                    return Err(Report::msg("Unexpected '=', use '==' for equality"));
+                }
+
+                tokens.push(Token::Op(op));
+                i += op.len();
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads the argument of a source starting at `start`,
+/// returning it and the position after it.
+///
+/// Commands can contain any character, so always take the rest of the input.
+/// Other arguments are either a `"quoted string"`,
+/// or a bare word ending at whitespace, a bracket or an operator.
+fn source_argument(chars: &[char], kind: &str, start: usize) -> Result<(String, usize)> {
+    if kind == "cmd" {
+        let command = chars[start..].iter().collect::<String>();
+        return Ok((command.trim().to_string(), chars.len()));
+    }
+
+    let start = chars[start..]
+        .iter()
+        .position(|c| !c.is_whitespace())
+        .map_or(chars.len(), |len| start + len);
+
+    if chars.get(start) == Some(&'"') {
+        let Some(len) = chars[start + 1..].iter().position(|&c| c == '"') else {
+            return Err(Report::msg("Unclosed string"));
+        };
+
+        let end = start + 1 + len;
+        return Ok((chars[start + 1..end].iter().collect(), end + 1));
+    }
+
+    let end = chars[start..]
+        .iter()
+        .position(|&c| c.is_whitespace() || "()&|=!<>".contains(c))
+        .map_or(chars.len(), |len| start + len);
+
+    Ok((chars[start..end].iter().collect(), end))
+}
+
+/// Parses tokens into a tree, in order of precedence from lowest to highest:
+/// filters, `||`, `&&`, comparisons, `+ -`, `* / %`, then `- !` prefixes.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    variables: Vec<Box<str>>,
+    /// Whether sources can be used.
+    sources: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Consumes the next token if it is one of the operators,
+    /// returning the operator.
+    fn take_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.position += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expression(&mut self) -> Result<Node> {
+        let mut node = self.or()?;
+
+        while self.peek() == Some(&Token::Pipe) {
+            self.position += 1;
+
+            let filter = match self.next() {
+                Some(Token::Name(name)) => Filter::from_name(&name)?,
+                _ => return Err(Report::msg("Expected a filter name after '|'")),
+            };
+
+            node = Node::Filter(filter, Box::new(node));
+        }
+
+        Ok(node)
+    }
+
+    fn or(&mut self) -> Result<Node> {
+        let mut node = self.and()?;
+
+        while self.take_op(&["||"]).is_some() {
+            node = Node::Binary(BinaryOp::Or, Box::new(node), Box::new(self.and()?));
+        }
+
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node> {
+        let mut node = self.comparison()?;
+
+        while self.take_op(&["&&"]).is_some() {
+            node = Node::Binary(BinaryOp::And, Box::new(node), Box::new(self.comparison()?));
+        }
+
+        Ok(node)
+    }
+
+    fn comparison(&mut self) -> Result<Node> {
+        let node = self.sum()?;
+
+        let op = match self.take_op(&["==", "!=", "<", "<=", ">", ">="]) {
+            Some("==") => BinaryOp::Equal,
+            Some("!=") => BinaryOp::NotEqual,
+            Some("<") => BinaryOp::Less,
+            Some("<=") => BinaryOp::LessOrEqual,
+            Some(">") => BinaryOp::Greater,
+            Some(">=") => BinaryOp::GreaterOrEqual,
+            _ => return Ok(node),
+        };
+
+        Ok(Node::Binary(op, Box::new(node), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Node> {
+        let mut node = self.product()?;
+
+        while let Some(op) = self.take_op(&["+", "-"]) {
+            let op = if op == "+" {
+                BinaryOp::Add
+            } else {
+                BinaryOp::Subtract
+            };
+
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+
+        Ok(node)
+    }
+
+    fn product(&mut self) -> Result<Node> {
+        let mut node = self.unary()?;
+
+        while let Some(op) = self.take_op(&["*", "/", "%"]) {
+            let op = match op {
+                "*" => BinaryOp::Multiply,
+                "/" => BinaryOp::Divide,
+                _ => BinaryOp::Remainder,
+            };
+
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        match self.take_op(&["-", "!"]) {
+            Some("-") => Ok(Node::Unary(UnaryOp::Negate, Box::new(self.unary()?))),
+            Some(_) => Ok(Node::Unary(UnaryOp::Not, Box::new(self.unary()?))),
+            None => self.atom(),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Node::Value(Value::Number(number))),
+            Some(Token::Text(text)) => Ok(Node::Value(Value::Text(text))),
+            Some(Token::Name(name)) if name == "true" => Ok(Node::Value(Value::Bool(true))),
+            Some(Token::Name(name)) if name == "false" => Ok(Node::Value(Value::Bool(false))),
+            Some(Token::Name(name)) if name == "host" && self.sources => {
+                Ok(Node::Source(Source::Host))
+            }
+            Some(Token::Source(kind, _)) if !self.sources => Err(Report::msg(format!(
+                "'{kind}:' can only be used in conditions"
+            ))),
+            Some(Token::Source(kind, argument)) => Source::new(&kind, argument).map(Node::Source),
+            Some(Token::Variable(name)) => {
+                self.variables.push(name.clone());
+                Ok(Node::Variable(name))
+            }
+            Some(Token::OpenParen) => {
+                let node = self.expression()?;
+
+                match self.next() {
+                    Some(Token::CloseParen) => Ok(node),
+                    _ => Err(Report::msg("Expected ')'")),
+                }
+            }
+            Some(token) => Err(Report::msg(format!("Unexpected {token:?}"))),
+            None => Err(Report::msg("Unexpected end of expression")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn eval(input: &str, variables: &[(&str, &str)]) -> Result<String> {
+        let variables = variables
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        let expression = Expression::parse(input)?;
+        let value = expression.evaluate(&|name| variables.get(name).cloned())?;
+
+        Ok(value.to_string())
+    }
+
+    #[test]
+    fn evaluates_arithmetic_and_filters() {
+        let vars = [("uptime_sec", "9000"), ("pct", "15")];
+
+        assert_eq!(eval("#uptime_sec / 3600", &vars).unwrap(), "2.5");
+        assert_eq!(eval("#uptime_sec / 3600 | round", &vars).unwrap(), "3");
+        assert_eq!(eval("(1 + 2) * -3 % 5", &[]).unwrap(), "-4");
+        assert_eq!(eval("\" hi \" | trim | upper", &[]).unwrap(), "HI");
+        assert!(eval("1 / 0", &[]).is_err());
+        assert!(eval("#missing + 1", &vars).is_err());
+    }
+
+    #[test]
+    fn evaluates_comparisons() {
+        let vars = [("pct", "15"), ("state", "charging")];
+
+        assert_eq!(eval("#pct < 20", &vars).unwrap(), "true");
+        assert_eq!(
+            eval("#pct >= 20 || #state == \"charging\"", &vars).unwrap(),
+            "true"
+        );
+        assert_eq!(eval("!(#pct < 20) && true", &vars).unwrap(), "false");
+        assert_eq!(eval("#pct == 15.0", &vars).unwrap(), "true");
+        assert!(eval("#state < 2", &vars).is_err());
+    }
+
+    #[test]
+    fn parses_variables_and_errors() {
+        let expression = Expression::parse("#b + #a * #b").unwrap();
+        assert_eq!(expression.variables(), [Box::from("a"), Box::from("b")]);
+
+        for input in ["", "1 +", "(1", "1 2", "#a | nope", "#a = 1", "\"open"] {
+            assert!(Expression::parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn parses_sources() {
+        let source = |source| Box::new(Node::Source(source));
+
+        assert_eq!(
+            Expression::parse_condition("!env:A || host != \"pc\" && file:/tmp")
+                .unwrap()
+                .node,
+            Node::Binary(
+                BinaryOp::Or,
+                Box::new(Node::Unary(UnaryOp::Not, source(Source::Env("A".into())))),
+                Box::new(Node::Binary(
+                    BinaryOp::And,
+                    Box::new(Node::Binary(
+                        BinaryOp::NotEqual,
+                        source(Source::Host),
+                        Box::new(Node::Value(Value::Text(String::from("pc"))))
+                    )),
+                    source(Source::File("/tmp".into()))
+                ))
+            )
+        );
+
+        assert_eq!(
+            Expression::parse_condition("file:\"/power supply\" && cmd:test -d /tmp && true")
+                .unwrap()
+                .node,
+            Node::Binary(
+                BinaryOp::And,
+                source(Source::File("/power supply".into())),
+                source(Source::Command("test -d /tmp && true".into()))
+            )
+        );
+
+        assert_eq!(
+            Expression::parse_condition("env:A==\"1\"").unwrap().node,
+            Node::Binary(
+                BinaryOp::Equal,
+                source(Source::Env("A".into())),
+                Box::new(Node::Value(Value::Text(String::from("1"))))
+            )
+        );
+
+        for input in ["env:", "env:A-B", "file:", "cmd:", "disk:/", "file:\"/tmp"] {
+            assert!(Expression::parse_condition(input).is_err(), "{input}");
+        }
+
+        assert!(Expression::parse("env:HOME").is_err());
+        assert!(Expression::parse("host == \"pc\"").is_err());
+    }
+}
@@ -3,6 +3,7 @@ pub enum ExitCode {
     GtkDisplay = 1,
     CreateBars = 2,
     IpcResponseError = 3,
+    IpcMismatch = 4,
 }
 
 pub const ERR_MUTEX_LOCK: &str = "Failed to get lock on Mutex";
@@ -0,0 +1,466 @@
+//! Writes selected bar state to a JSON file,
+//! so that external programs can read it without talking to each service.
+
+use crate::clients::Clients;
+use crate::config::ExportConfig;
+use crate::state::write_state;
+use crate::{lock, spawn, spawn_blocking};
+use color_eyre::Result;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::sleep;
+use tracing::{debug, error, warn};
+
+/// The sections which can be exported,
+/// each of which is a top-level key in the file.
+const SECTIONS: [&str; 5] = ["ironvars", "workspaces", "music", "volume", "battery"];
+
+/// The latest value of each started section,
+/// which the writer is notified of whenever it changes.
+#[derive(Debug, Clone, Default)]
+struct State {
+    sections: Arc<Mutex<Map<String, Value>>>,
+    changed: Arc<Notify>,
+}
+
+impl State {
+    fn set(&self, section: &str, value: Value) {
+        lock!(self.sections).insert(section.to_string(), value);
+        self.changed.notify_one();
+    }
+}
+
+/// Starts the source for each section used by the fields,
+/// and writes the selected fields whenever they change.
+pub fn start(config: ExportConfig, clients: &mut Clients) {
+    let state = State::default();
+
+    let mut sections = config
+        .fields
+        .iter()
+        .map(|field| field.split('.').next().unwrap_or_default())
+        .collect::<Vec<_>>();
+
+    sections.sort_unstable();
+    sections.dedup();
+
+    for section in sections {
+        if !SECTIONS.contains(&section) {
+            warn!("Unknown export section '{section}'");
+            continue;
+        }
+
+        if let Err(err) = start_section(section, &state, clients) {
+            error!("Failed to export {section}: {err:?}");
+        }
+    }
+
+    let path = config.path.unwrap_or_else(default_path);
+    let debounce = Duration::from_millis(config.debounce);
+    let fields = config.fields;
+
+    debug!("Exporting state to {}", path.display());
+
+    spawn(async move {
+        let mut written = None;
+
+        loop {
+            state.changed.notified().await;
+            sleep(debounce).await;
+
+            let output = select_fields(&lock!(state.sections), &fields);
+            if written.as_ref() == Some(&output) {
+                continue;
+            }
+
+            let json = match serde_json::to_string_pretty(&output) {
+                Ok(json) => json,
+                Err(err) => {
+                    error!("Failed to serialize exported state: {err:?}");
+                    continue;
+                }
+            };
+
+            let path = path.clone();
+            match spawn_blocking(move || write_state(&path, json)).await {
+                Ok(Ok(())) => written = Some(output),
+                Ok(Err(err)) => error!("Failed to write exported state: {err:?}"),
+                Err(err) => error!("Failed to write exported state: {err:?}"),
+            }
+        }
+    });
+}
+
+fn default_path() -> PathBuf {
+    std::env::var("XDG_RUNTIME_DIR")
+        .map_or_else(|_| PathBuf::from("/tmp"), PathBuf::from)
+        .join("ironbar")
+        .join("state.json")
+}
+
+/// Picks the fields from the sections.
+///
+/// A field is either a whole section, such as `music`,
+/// or a key within a section, such as `music.title`.
+/// Fields without a value yet are written as `null`,
+/// so the file keeps the same shape.
+fn select_fields(sections: &Map<String, Value>, fields: &[String]) -> Map<String, Value> {
+    let mut output = Map::new();
+
+    for field in fields {
+        match field.split_once('.') {
+            Some((section, key)) => {
+                let value = sections
+                    .get(section)
+                    .and_then(|section| section.get(key))
+                    .cloned()
+                    .unwrap_or_default();
+
+                let section = output.entry(section).or_insert(Value::Null);
+
+                // a whole section without a value yet is replaced
+                if section.is_null() {
+                    *section = Value::Object(Map::new());
+                }
+
+                // the whole section may already be selected
+                if let Value::Object(section) = section {
+                    section.entry(key).or_insert(value);
+                }
+            }
+            None => {
+                let value = sections.get(field).cloned().unwrap_or_default();
+
+                // a whole section replaces any single keys already picked
+                if !value.is_null() || !output.contains_key(field) {
+                    output.insert(field.clone(), value);
+                }
+            }
+        }
+    }
+
+    output
+}
+
+fn start_section(section: &str, state: &State, clients: &mut Clients) -> Result<()> {
+    let state = state.clone();
+
+    match section {
+        #[cfg(feature = "ipc")]
+        "ironvars" => sources::ironvars(state),
+        #[cfg(feature = "workspaces")]
+        "workspaces" => sources::workspaces(state, clients)?,
+        #[cfg(feature = "music+mpris")]
+        "music" => sources::music(state, clients),
+        #[cfg(feature = "volume")]
+        "volume" => sources::volume(state, clients),
+        #[cfg(feature = "upower")]
+        "battery" => sources::battery(state, clients),
+        _ => {
+            let _ = (state, clients);
+            warn!("Export section '{section}' is not enabled in this build");
+        }
+    }
+
+    Ok(())
+}
+
+/// The tasks keeping each section up to date.
+mod sources {
+    use super::State;
+    use crate::clients::Clients;
+    use crate::spawn;
+    use serde_json::{json, Map, Value};
+    use tokio::sync::broadcast::error::RecvError;
+    use tracing::error;
+
+    /// Every `ironvar`, by key.
+    #[cfg(feature = "ipc")]
+    pub fn ironvars(state: State) {
+        use crate::{read_lock, Ironbar};
+
+        let manager = Ironbar::variable_manager();
+        let mut rx = read_lock!(manager).subscribe_all();
+
+        spawn(async move {
+            loop {
+                let variables = read_lock!(manager)
+                    .get_all()
+                    .iter()
+                    .filter_map(|(key, var)| Some((key.to_string(), Value::String(var.get()?))))
+                    .collect::<Map<_, _>>();
+
+                state.set("ironvars", Value::Object(variables));
+
+                match rx.recv().await {
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// The focused workspace name, and every workspace sorted by ID.
+    #[cfg(feature = "workspaces")]
+    pub fn workspaces(state: State, clients: &mut Clients) -> color_eyre::Result<()> {
+        use crate::clients::compositor::{FocusState, Visibility};
+
+        let mut rx = clients.workspaces()?.subscribe_workspace_change();
+
+        spawn(async move {
+            let mut focus = FocusState::default();
+
+            loop {
+                match rx.recv().await {
+                    Ok(update) => focus.apply(update),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => {
+                        error!("Compositor workspace updates stopped");
+                        break;
+                    }
+                }
+
+                let focused = focus.focused_workspace().map(|workspace| workspace.id);
+
+                let mut workspaces = focus.workspaces().collect::<Vec<_>>();
+                workspaces.sort_by_key(|workspace| workspace.id);
+
+                let list = workspaces
+                    .into_iter()
+                    .map(|workspace| {
+                        json!({
+                            "id": workspace.id,
+                            "name": workspace.name,
+                            "monitor": workspace.monitor,
+                            "focused": Some(workspace.id) == focused,
+                            "visible": matches!(workspace.visibility, Visibility::Visible(_)),
+                            "windows": workspace.windows,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                state.set(
+                    "workspaces",
+                    json!({
+                        "focused": focus.focused_workspace().map(|workspace| &workspace.name),
+                        "list": list,
+                    }),
+                );
+            }
+        });
+
+        Ok(())
+    }
+
+    /// The current MPRIS track and player state.
+    ///
+    /// The elapsed time is left out,
+    /// as it would otherwise rewrite the file constantly while playing.
+    #[cfg(feature = "music+mpris")]
+    pub fn music(state: State, clients: &mut Clients) {
+        use crate::clients::music::{ClientType, PlayerState, PlayerUpdate};
+
+        let mut rx = clients.music(ClientType::Mpris).subscribe_change();
+
+        spawn(async move {
+            let mut music = Map::new();
+
+            loop {
+                match rx.recv().await {
+                    Ok(PlayerUpdate::Update(track, status)) => {
+                        let track = (*track).as_ref();
+                        let state_name = match status.state {
+                            PlayerState::Stopped => "stopped",
+                            PlayerState::Playing => "playing",
+                            PlayerState::Paused => "paused",
+                        };
+
+                        music.insert("state".to_string(), json!(state_name));
+                        music.insert("volume".to_string(), json!(status.volume_percent));
+
+                        for (key, value) in [
+                            ("title", track.and_then(|track| track.title.as_ref())),
+                            ("artist", track.and_then(|track| track.artist.as_ref())),
+                            ("album", track.and_then(|track| track.album.as_ref())),
+                            (
+                                "cover_path",
+                                track.and_then(|track| track.cover_path.as_ref()),
+                            ),
+                        ] {
+                            music.insert(key.to_string(), json!(value));
+                        }
+                    }
+                    Ok(PlayerUpdate::ProgressTick(tick)) => {
+                        let duration = tick.duration.map(|duration| duration.as_secs());
+                        if music.get("duration") == Some(&json!(duration)) {
+                            continue;
+                        }
+
+                        music.insert("duration".to_string(), json!(duration));
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => {
+                        error!("Music updates stopped");
+                        break;
+                    }
+                }
+
+                state.set("music", Value::Object(music.clone()));
+            }
+        });
+    }
+
+    /// The default output device.
+    #[cfg(feature = "volume")]
+    pub fn volume(state: State, clients: &mut Clients) {
+        use crate::lock;
+
+        let client = clients.volume();
+        let mut rx = client.subscribe();
+
+        spawn(async move {
+            loop {
+                let sink = {
+                    let sinks = client.sinks();
+                    let sinks = lock!(sinks);
+                    sinks.iter().find(|sink| sink.active).cloned()
+                };
+
+                let value = sink.map_or(Value::Null, |sink| {
+                    json!({
+                        "sink": sink.description,
+                        "level": sink.volume.round(),
+                        "muted": sink.muted,
+                    })
+                });
+
+                state.set("volume", value);
+
+                match rx.recv().await {
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// The UPower display device.
+    #[cfg(feature = "upower")]
+    pub fn battery(state: State, clients: &mut Clients) {
+        let proxy = clients.upower();
+
+        spawn(async move {
+            if let Err(err) = watch_battery(&state, &proxy).await {
+                error!("Failed to export battery: {err:?}");
+            }
+        });
+    }
+
+    #[cfg(feature = "upower")]
+    async fn watch_battery(
+        state: &State,
+        proxy: &zbus::fdo::PropertiesProxy<'_>,
+    ) -> color_eyre::Result<()> {
+        use futures_lite::stream::StreamExt;
+        use std::collections::HashMap;
+        use zbus::names::InterfaceName;
+        use zbus::zvariant::OwnedValue;
+
+        /// The names of UPower's `State` values, by their number.
+        const STATES: [&str; 7] = [
+            "unknown",
+            "charging",
+            "discharging",
+            "empty",
+            "fully_charged",
+            "pending_charge",
+            "pending_discharge",
+        ];
+
+        let interface = InterfaceName::from_static_str_unchecked("org.freedesktop.UPower.Device");
+
+        let mut changes = proxy.receive_properties_changed().await?;
+        let mut properties: HashMap<String, OwnedValue> = proxy.get_all(interface.clone()).await?;
+
+        loop {
+            let get_i64 = |name: &str| properties.get(name).and_then(|v| v.downcast_ref::<i64>());
+
+            let percentage = properties
+                .get("Percentage")
+                .and_then(|v| v.downcast_ref::<f64>());
+
+            let state_name = properties
+                .get("State")
+                .and_then(|v| v.downcast_ref::<u32>())
+                .and_then(|&state| STATES.get(state as usize))
+                .copied()
+                .unwrap_or("unknown");
+
+            state.set(
+                "battery",
+                json!({
+                    "percentage": percentage,
+                    "state": state_name,
+                    "time_to_empty": get_i64("TimeToEmpty"),
+                    "time_to_full": get_i64("TimeToFull"),
+                }),
+            );
+
+            let Some(signal) = changes.next().await else {
+                return Ok(());
+            };
+
+            let args = signal.args()?;
+            if args.interface_name != interface {
+                continue;
+            }
+
+            for (name, value) in args.changed_properties {
+                properties.insert(name.to_string(), OwnedValue::from(value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn selects_sections_and_keys() {
+        let Value::Object(sections) = json!({
+            "music": { "title": "Song", "artist": "Band", "album": "Record" },
+            "battery": { "percentage": 80.0, "state": "charging" },
+        }) else {
+            unreachable!()
+        };
+
+        let fields = [
+            "music.title",
+            "music.artist",
+            "battery",
+            "volume",
+            "volume.level",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            Value::Object(select_fields(&sections, &fields)),
+            json!({
+                "music": { "title": "Song", "artist": "Band" },
+                "battery": { "percentage": 80.0, "state": "charging" },
+                "volume": { "level": null },
+            })
+        );
+
+        let fields = ["music.title", "music"].map(String::from);
+        assert_eq!(
+            select_fields(&sections, &fields)["music"],
+            sections["music"]
+        );
+    }
+}
@@ -0,0 +1,50 @@
+/// Matches `value` against a simple glob `pattern`,
+/// where `*` matches any sequence of characters
+/// and `?` matches any single character.
+///
+/// Matching is case-insensitive.
+pub fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let value = value.to_lowercase().chars().collect::<Vec<_>>();
+
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack = None;
+
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, v));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bv)) => {
+                    p = bp + 1;
+                    v = bv + 1;
+                    backtrack = Some((bp, bv + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_wildcards() {
+        assert!(glob_match("text/*", "text/html"));
+        assert!(glob_match("image/p?g", "image/png"));
+        assert!(glob_match("*", "text/plain;charset=utf-8"));
+        assert!(glob_match("TEXT/PLAIN*", "text/plain;charset=utf-8"));
+        assert!(!glob_match("text/*", "image/png"));
+        assert!(!glob_match("image/p?g", "image/jpeg"));
+    }
+}
@@ -1,10 +1,28 @@
-use glib::IsA;
+use crate::config::{CursorStyle, TruncateMode};
+use glib::{IsA, Propagation};
+use gtk::gdk::{self, EventMask, NotifyType};
 use gtk::prelude::*;
 use gtk::{Orientation, Widget};
+use std::cell::Cell;
+
+const INTERACTIVE_TAG: &str = "interactive";
+
+thread_local! {
+    /// The cursor shown while hovering over interactive widgets.
+    static CURSOR_STYLE: Cell<CursorStyle> = const { Cell::new(CursorStyle::Pointer) };
+}
+
+/// Sets the cursor to show while hovering over interactive widgets.
+/// This takes effect the next time one is hovered.
+///
+/// This must be called from the GTK thread.
+pub fn set_cursor_style(style: CursorStyle) {
+    CURSOR_STYLE.with(|cursor_style| cursor_style.set(style));
+}
 
 /// Represents a widget's size
 /// and location relative to the bar's start edge.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct WidgetGeometry {
     /// Position of the start edge of the widget
     /// from the start edge of the bar.
@@ -18,6 +36,8 @@ pub struct WidgetGeometry {
 pub trait IronbarGtkExt {
     /// Adds a new CSS class to the widget.
     fn add_class(&self, class: &str);
+    /// Adds or removes a CSS class on the widget.
+    fn set_class(&self, class: &str, enabled: bool);
     /// Gets the geometry for the widget
     fn geometry(&self, orientation: Orientation) -> WidgetGeometry;
 
@@ -25,6 +45,14 @@ pub trait IronbarGtkExt {
     fn get_tag<V: 'static>(&self, key: &str) -> Option<&V>;
     /// Sets a data tag on a widget.
     fn set_tag<V: 'static>(&self, key: &str, value: V);
+
+    /// Marks the widget as clickable,
+    /// showing the configured cursor while it is hovered.
+    ///
+    /// The widget must receive its own crossing events,
+    /// such as a button or event box.
+    /// Entries and sliders are left alone, as GTK sets their cursors itself.
+    fn set_interactive(&self);
 }
 
 impl<W: IsA<Widget>> IronbarGtkExt for W {
@@ -32,6 +60,14 @@ impl<W: IsA<Widget>> IronbarGtkExt for W {
         self.style_context().add_class(class);
     }
 
+    fn set_class(&self, class: &str, enabled: bool) {
+        if enabled {
+            self.style_context().add_class(class);
+        } else {
+            self.style_context().remove_class(class);
+        }
+    }
+
     fn geometry(&self, orientation: Orientation) -> WidgetGeometry {
         let allocation = self.allocation();
 
@@ -74,4 +110,186 @@ impl<W: IsA<Widget>> IronbarGtkExt for W {
     fn set_tag<V: 'static>(&self, key: &str, value: V) {
         unsafe { self.set_data(key, value) }
     }
+
+    fn set_interactive(&self) {
+        if self.is::<gtk::Entry>()
+            || self.is::<gtk::Range>()
+            || self.get_tag::<bool>(INTERACTIVE_TAG).is_some()
+        {
+            return;
+        }
+
+        self.set_tag(INTERACTIVE_TAG, true);
+        self.add_events(EventMask::ENTER_NOTIFY_MASK | EventMask::LEAVE_NOTIFY_MASK);
+
+        // the cursor is set on the window receiving the event,
+        // which for buttons is their own input window rather than the bar's
+        self.connect_enter_notify_event(|widget, event| {
+            let name = match CURSOR_STYLE.with(Cell::get) {
+                CursorStyle::Default => return Propagation::Proceed,
+                CursorStyle::Pointer => "pointer",
+                CursorStyle::None => "none",
+            };
+
+            if let Some(window) = event.window() {
+                let cursor = gdk::Cursor::from_name(&widget.display(), name);
+                window.set_cursor(cursor.as_ref());
+            }
+
+            Propagation::Proceed
+        });
+
+        self.connect_leave_notify_event(|_, event| {
+            // moving onto a child still hovers the widget
+            if event.detail() != NotifyType::Inferior {
+                if let Some(window) = event.window() {
+                    window.set_cursor(None);
+                }
+            }
+
+            Propagation::Proceed
+        });
+    }
+}
+
+pub trait IronbarLabelExt {
+    /// Ellipsizes the label's text using the truncate mode.
+    ///
+    /// Lengths which are not set are reset to GTK's default,
+    /// so the label only reflects the given mode.
+    fn truncate(&self, mode: TruncateMode);
+}
+
+impl IronbarLabelExt for gtk::Label {
+    fn truncate(&self, mode: TruncateMode) {
+        self.set_ellipsize(mode.mode().into());
+        self.set_width_chars(mode.length().unwrap_or(-1));
+        self.set_max_width_chars(mode.max_length().unwrap_or(-1));
+    }
+}
+
+/// Gets the drag-and-drop target for dragging a window between modules,
+/// such as from a launcher item onto a workspace button.
+///
+/// The drag data is the app ID of the window.
+#[cfg(any(feature = "launcher", feature = "workspaces"))]
+pub fn window_drag_target() -> gtk::TargetEntry {
+    gtk::TargetEntry::new(
+        "application/x-ironbar-window",
+        gtk::TargetFlags::SAME_APP,
+        0,
+    )
+}
+
+/// Sends a synthetic scroll event to the widget,
+/// which bubbles up through its parents until handled.
+///
+/// This allows scroll actions to be triggered from the keyboard.
+pub fn emit_scroll(widget: &impl IsA<Widget>, direction: gtk::gdk::ScrollDirection) {
+    use glib::translate::{IntoGlib, ToGlibPtr, ToGlibPtrMut};
+    use gtk::gdk;
+
+    let Some(window) = widget.window() else {
+        return;
+    };
+
+    let mut event = gdk::Event::new(gdk::EventType::Scroll);
+
+    // SAFETY: the event was created as a scroll event.
+    // The event takes ownership of the window reference, and frees it with the event.
+    unsafe {
+        let scroll = &mut (*event.to_glib_none_mut().0).scroll;
+        scroll.direction = direction.into_glib();
+        scroll.window = window.to_glib_full();
+        scroll.send_event = 1;
+    }
+
+    if let Some(device) = widget
+        .display()
+        .default_seat()
+        .and_then(|seat| seat.pointer())
+    {
+        event.set_device(Some(&device));
+    }
+
+    gtk::propagate_event(widget, &mut event);
+}
+
+/// Sends a synthetic click to the deepest visible child of the widget
+/// at the position, which bubbles up through its parents until handled.
+///
+/// The position is relative to the widget.
+/// This allows long-pressing a touchscreen to act as a right-click.
+pub fn emit_click(widget: &impl IsA<Widget>, button: u32, x: f64, y: f64) {
+    use glib::translate::{ToGlibPtr, ToGlibPtrMut};
+
+    let (target, x, y) = widget_at(widget.upcast_ref(), x, y);
+
+    let Some(window) = target.window() else {
+        return;
+    };
+
+    // positions are relative to the widget's window,
+    // which widgets without their own window share with their parent.
+    let (x, y) = if target.has_window() {
+        (x, y)
+    } else {
+        let allocation = target.allocation();
+        (x + f64::from(allocation.x()), y + f64::from(allocation.y()))
+    };
+
+    let (_, origin_x, origin_y) = window.origin();
+    let device = widget
+        .display()
+        .default_seat()
+        .and_then(|seat| seat.pointer());
+
+    for event_type in [gdk::EventType::ButtonPress, gdk::EventType::ButtonRelease] {
+        let mut event = gdk::Event::new(event_type);
+
+        // SAFETY: the event was created as a button event.
+        // The event takes ownership of the window reference, and frees it with the event.
+        unsafe {
+            let ev = &mut (*event.to_glib_none_mut().0).button;
+            ev.button = button;
+            ev.x = x;
+            ev.y = y;
+            ev.x_root = x + f64::from(origin_x);
+            ev.y_root = y + f64::from(origin_y);
+            ev.time = gtk::current_event_time();
+            ev.window = window.to_glib_full();
+            ev.send_event = 1;
+        }
+
+        if let Some(device) = &device {
+            event.set_device(Some(device));
+        }
+
+        gtk::propagate_event(&target, &mut event);
+    }
+}
+
+/// Gets the deepest visible child of the widget at the position,
+/// along with the position relative to that child.
+fn widget_at(widget: &Widget, x: f64, y: f64) -> (Widget, f64, f64) {
+    let child = widget
+        .downcast_ref::<gtk::Container>()
+        .into_iter()
+        .flat_map(ContainerExt::children)
+        .filter(|child| child.is_mapped())
+        .find_map(|child| {
+            #[allow(clippy::cast_possible_truncation)]
+            let (child_x, child_y) = widget.translate_coordinates(&child, x as i32, y as i32)?;
+
+            let allocation = child.allocation();
+            let inside = (0..allocation.width()).contains(&child_x)
+                && (0..allocation.height()).contains(&child_y);
+
+            inside.then(|| (child, f64::from(child_x), f64::from(child_y)))
+        });
+
+    match child {
+        Some((child, x, y)) => widget_at(&child, x, y),
+        None => (widget.clone(), x, y),
+    }
 }
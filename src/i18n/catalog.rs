@@ -0,0 +1,222 @@
+/// The translated text for a language, keyed by `<area>.<name>`.
+#[derive(Debug)]
+pub struct Catalog {
+    /// The language code, such as `de`.
+    pub language: &'static str,
+    pub strings: &'static [(&'static str, &'static str)],
+}
+
+impl Catalog {
+    pub fn get(&self, key: &str) -> Option<&'static str> {
+        self.strings
+            .iter()
+            .find_map(|(k, value)| (*k == key).then_some(*value))
+    }
+}
+
+/// The fallback for keys missing from other catalogs.
+/// Every key must be in this catalog.
+pub static ENGLISH: Catalog = Catalog {
+    language: "en",
+    strings: &[
+        ("bluetooth.title", "Bluetooth"),
+        ("bluetooth.no_adapter", "No Bluetooth adapter found"),
+        ("bluetooth.off", "Bluetooth is off"),
+        ("bluetooth.no_devices", "No paired devices"),
+        ("bluetooth.battery", "Battery"),
+        ("bluetooth.connect", "Connect"),
+        ("bluetooth.disconnect", "Disconnect"),
+        ("clipboard.ocr", "Copy text (OCR)"),
+        ("clipboard.qr", "Decode QR code"),
+        ("clipboard.no_text", "No text found"),
+        ("clipboard.no_qr", "No QR code found"),
+        ("clock.calendar", "Calendar"),
+        ("clock.stopwatch", "Stopwatch"),
+        ("clock.alarms", "Alarms"),
+        ("clock.start", "Start"),
+        ("clock.pause", "Pause"),
+        ("clock.lap", "Lap"),
+        ("clock.reset", "Reset"),
+        ("clock.alarm_label", "Label"),
+        ("clock.add", "Add"),
+        ("duration.day.short", "d"),
+        ("duration.hour.short", "h"),
+        ("duration.minute.short", "m"),
+        ("duration.second.short", "s"),
+        ("duration.day.one", "day"),
+        ("duration.hour.one", "hour"),
+        ("duration.minute.one", "minute"),
+        ("duration.second.one", "second"),
+        ("duration.day.other", "days"),
+        ("duration.hour.other", "hours"),
+        ("duration.minute.other", "minutes"),
+        ("duration.second.other", "seconds"),
+        ("duration.relative.now", "now"),
+        ("duration.relative.past", "{time} ago"),
+        ("duration.relative.future", "in {time}"),
+        ("duration.yesterday", "yesterday"),
+        ("duration.tomorrow", "tomorrow"),
+        ("launcher.search", "Search applications"),
+        ("notifications.clear_all", "Clear all"),
+        ("notifications.open_center", "Open notification center"),
+        ("sysinfo.kill", "Kill"),
+        ("sysinfo.cancel", "Cancel"),
+        ("sysinfo.kill_confirm", "Kill {name} ({pid})?"),
+        ("upower.charge_limit", "Charge limit: {limit}%"),
+        ("volume.codec", "Codec"),
+        ("volume.battery", "Battery"),
+    ],
+};
+
+const GERMAN: Catalog = Catalog {
+    language: "de",
+    strings: &[
+        ("bluetooth.no_adapter", "Kein Bluetooth-Adapter gefunden"),
+        ("bluetooth.off", "Bluetooth ist aus"),
+        ("bluetooth.no_devices", "Keine gekoppelten Geräte"),
+        ("bluetooth.battery", "Akku"),
+        ("bluetooth.connect", "Verbinden"),
+        ("bluetooth.disconnect", "Trennen"),
+        ("clipboard.ocr", "Text kopieren (OCR)"),
+        ("clipboard.qr", "QR-Code lesen"),
+        ("clipboard.no_text", "Kein Text gefunden"),
+        ("clipboard.no_qr", "Kein QR-Code gefunden"),
+        ("clock.calendar", "Kalender"),
+        ("clock.stopwatch", "Stoppuhr"),
+        ("clock.alarms", "Wecker"),
+        ("clock.lap", "Runde"),
+        ("clock.reset", "Zurücksetzen"),
+        ("clock.alarm_label", "Bezeichnung"),
+        ("clock.add", "Hinzufügen"),
+        ("duration.minute.short", "min"),
+        ("duration.day.one", "Tag"),
+        ("duration.hour.one", "Stunde"),
+        ("duration.minute.one", "Minute"),
+        ("duration.second.one", "Sekunde"),
+        ("duration.day.other", "Tage"),
+        ("duration.hour.other", "Stunden"),
+        ("duration.minute.other", "Minuten"),
+        ("duration.second.other", "Sekunden"),
+        ("duration.relative.now", "jetzt"),
+        ("duration.relative.past", "{time} her"),
+        ("duration.relative.future", "in {time}"),
+        ("duration.yesterday", "gestern"),
+        ("duration.tomorrow", "morgen"),
+        ("launcher.search", "Anwendungen durchsuchen"),
+        ("notifications.clear_all", "Alle löschen"),
+        (
+            "notifications.open_center",
+            "Benachrichtigungszentrale öffnen",
+        ),
+        ("sysinfo.kill", "Beenden"),
+        ("sysinfo.cancel", "Abbrechen"),
+        ("sysinfo.kill_confirm", "{name} ({pid}) beenden?"),
+        ("upower.charge_limit", "Ladegrenze: {limit} %"),
+        ("volume.battery", "Akku"),
+    ],
+};
+
+const FRENCH: Catalog = Catalog {
+    language: "fr",
+    strings: &[
+        ("bluetooth.no_adapter", "Aucun adaptateur Bluetooth trouvé"),
+        ("bluetooth.off", "Le Bluetooth est désactivé"),
+        ("bluetooth.no_devices", "Aucun appareil associé"),
+        ("bluetooth.battery", "Batterie"),
+        ("bluetooth.connect", "Connecter"),
+        ("bluetooth.disconnect", "Déconnecter"),
+        ("clipboard.ocr", "Copier le texte (OCR)"),
+        ("clipboard.qr", "Lire le code QR"),
+        ("clipboard.no_text", "Aucun texte trouvé"),
+        ("clipboard.no_qr", "Aucun code QR trouvé"),
+        ("clock.calendar", "Calendrier"),
+        ("clock.stopwatch", "Chronomètre"),
+        ("clock.alarms", "Alarmes"),
+        ("clock.start", "Démarrer"),
+        ("clock.lap", "Tour"),
+        ("clock.reset", "Réinitialiser"),
+        ("clock.alarm_label", "Libellé"),
+        ("clock.add", "Ajouter"),
+        ("duration.day.short", "j"),
+        ("duration.minute.short", "min"),
+        ("duration.day.one", "jour"),
+        ("duration.hour.one", "heure"),
+        ("duration.second.one", "seconde"),
+        ("duration.day.other", "jours"),
+        ("duration.hour.other", "heures"),
+        ("duration.second.other", "secondes"),
+        ("duration.relative.now", "maintenant"),
+        ("duration.relative.past", "il y a {time}"),
+        ("duration.relative.future", "dans {time}"),
+        ("duration.yesterday", "hier"),
+        ("duration.tomorrow", "demain"),
+        ("launcher.search", "Rechercher des applications"),
+        ("notifications.clear_all", "Tout effacer"),
+        (
+            "notifications.open_center",
+            "Ouvrir le centre de notifications",
+        ),
+        ("sysinfo.kill", "Tuer"),
+        ("sysinfo.cancel", "Annuler"),
+        ("sysinfo.kill_confirm", "Tuer {name} ({pid}) ?"),
+        ("upower.charge_limit", "Limite de charge : {limit} %"),
+        ("volume.battery", "Batterie"),
+    ],
+};
+
+const SPANISH: Catalog = Catalog {
+    language: "es",
+    strings: &[
+        (
+            "bluetooth.no_adapter",
+            "No se encontró ningún adaptador Bluetooth",
+        ),
+        ("bluetooth.off", "El Bluetooth está desactivado"),
+        ("bluetooth.no_devices", "No hay dispositivos vinculados"),
+        ("bluetooth.battery", "Batería"),
+        ("bluetooth.connect", "Conectar"),
+        ("bluetooth.disconnect", "Desconectar"),
+        ("clipboard.ocr", "Copiar texto (OCR)"),
+        ("clipboard.qr", "Leer código QR"),
+        ("clipboard.no_text", "No se encontró texto"),
+        ("clipboard.no_qr", "No se encontró ningún código QR"),
+        ("clock.calendar", "Calendario"),
+        ("clock.stopwatch", "Cronómetro"),
+        ("clock.alarms", "Alarmas"),
+        ("clock.start", "Iniciar"),
+        ("clock.pause", "Pausa"),
+        ("clock.lap", "Vuelta"),
+        ("clock.reset", "Reiniciar"),
+        ("clock.alarm_label", "Etiqueta"),
+        ("clock.add", "Añadir"),
+        ("duration.minute.short", "min"),
+        ("duration.day.one", "día"),
+        ("duration.hour.one", "hora"),
+        ("duration.minute.one", "minuto"),
+        ("duration.second.one", "segundo"),
+        ("duration.day.other", "días"),
+        ("duration.hour.other", "horas"),
+        ("duration.minute.other", "minutos"),
+        ("duration.second.other", "segundos"),
+        ("duration.relative.now", "ahora"),
+        ("duration.relative.past", "hace {time}"),
+        ("duration.relative.future", "en {time}"),
+        ("duration.yesterday", "ayer"),
+        ("duration.tomorrow", "mañana"),
+        ("launcher.search", "Buscar aplicaciones"),
+        ("notifications.clear_all", "Borrar todo"),
+        (
+            "notifications.open_center",
+            "Abrir el centro de notificaciones",
+        ),
+        ("sysinfo.kill", "Finalizar"),
+        ("sysinfo.cancel", "Cancelar"),
+        ("sysinfo.kill_confirm", "¿Finalizar {name} ({pid})?"),
+        ("upower.charge_limit", "Límite de carga: {limit} %"),
+        ("volume.codec", "Códec"),
+        ("volume.battery", "Batería"),
+    ],
+};
+
+/// The catalogs for languages other than English.
+pub static CATALOGS: &[Catalog] = &[GERMAN, FRENCH, SPANISH];
@@ -0,0 +1,137 @@
+//! Translations for the text Ironbar shows itself,
+//! such as popup button labels and duration units.
+//!
+//! Text from the user's config is never translated.
+
+mod catalog;
+
+use self::catalog::{Catalog, CATALOGS, ENGLISH};
+use std::env;
+use std::sync::{OnceLock, RwLock};
+
+/// The locale set in the config,
+/// which takes priority over the environment.
+static LOCALE: RwLock<Option<String>> = RwLock::new(None);
+
+/// The kind of text being looked up,
+/// which decides the environment variable the locale is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// Labels and messages, read from `LC_MESSAGES`.
+    Messages,
+    /// Dates and durations, read from `LC_TIME`.
+    #[cfg_attr(
+        not(any(
+            feature = "clock",
+            feature = "error_notifications",
+            feature = "music",
+            feature = "sys_info",
+            feature = "upower"
+        )),
+        allow(dead_code)
+    )]
+    Time,
+}
+
+impl Category {
+    /// Gets the language from the environment,
+    /// using the first of `LC_ALL`, the category's variable and `LANG` which is set.
+    fn env_language(self) -> &'static str {
+        static MESSAGES: OnceLock<String> = OnceLock::new();
+        static TIME: OnceLock<String> = OnceLock::new();
+
+        let (cell, var) = match self {
+            Self::Messages => (&MESSAGES, "LC_MESSAGES"),
+            Self::Time => (&TIME, "LC_TIME"),
+        };
+
+        cell.get_or_init(|| {
+            let locale = ["LC_ALL", var, "LANG"]
+                .into_iter()
+                .find_map(|var| env::var(var).ok().filter(|locale| !locale.is_empty()));
+
+            language(locale.as_deref().unwrap_or_default()).to_string()
+        })
+    }
+}
+
+/// Sets the locale to use instead of the environment's,
+/// such as `de` or `fr_FR.UTF-8`.
+/// This takes effect for text created afterwards.
+pub fn set_locale(locale: Option<String>) {
+    *crate::write_lock!(LOCALE) = locale;
+}
+
+/// Gets the language code from a locale, such as `de` from `de_DE.UTF-8`.
+fn language(locale: &str) -> &str {
+    locale.split(['_', '.', '@']).next().unwrap_or_default()
+}
+
+/// Looks up the text for a key in the current locale's language,
+/// falling back to English.
+///
+/// Keys which are not in any catalog are returned as-is.
+pub fn tr(key: &'static str) -> &'static str {
+    tr_category(Category::Messages, key)
+}
+
+/// Looks up the text for a key using the locale for the category.
+/// See [`tr`].
+pub fn tr_category(category: Category, key: &'static str) -> &'static str {
+    let locale = crate::read_lock!(LOCALE);
+
+    let locale = locale.as_deref().unwrap_or_else(|| category.env_language());
+
+    tr_locale(locale, key)
+}
+
+/// Looks up the text for a key in the locale, such as `de_DE.UTF-8`.
+/// See [`tr`].
+pub fn tr_locale(locale: &str, key: &'static str) -> &'static str {
+    lookup(catalog_for(language(locale)), key)
+}
+
+fn catalog_for(language: &str) -> &'static Catalog {
+    CATALOGS
+        .iter()
+        .find(|catalog| catalog.language == language)
+        .unwrap_or(&ENGLISH)
+}
+
+fn lookup(catalog: &Catalog, key: &'static str) -> &'static str {
+    catalog.get(key).or_else(|| ENGLISH.get(key)).unwrap_or(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_translations() {
+        assert_eq!(language("de_DE.UTF-8"), "de");
+        assert_eq!(language("fr@euro"), "fr");
+
+        assert_eq!(
+            lookup(catalog_for("de"), "notifications.clear_all"),
+            "Alle löschen"
+        );
+        assert_eq!(
+            lookup(catalog_for("POSIX"), "notifications.clear_all"),
+            "Clear all"
+        );
+        assert_eq!(lookup(catalog_for("de"), "missing.key"), "missing.key");
+    }
+
+    #[test]
+    fn catalogs_match_english() {
+        for catalog in CATALOGS {
+            for (key, _) in catalog.strings {
+                assert!(
+                    ENGLISH.get(key).is_some(),
+                    "'{key}' in '{}' is not in the English catalog",
+                    catalog.language
+                );
+            }
+        }
+    }
+}
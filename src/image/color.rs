@@ -0,0 +1,116 @@
+use gtk::gdk_pixbuf::Pixbuf;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Rgb {
+    /// Gets the perceived brightness, from `0` to `1`.
+    pub fn luminance(self) -> f64 {
+        (0.2126 * f64::from(self.red)
+            + 0.7152 * f64::from(self.green)
+            + 0.0722 * f64::from(self.blue))
+            / 255.0
+    }
+
+    pub fn is_dark(self) -> bool {
+        self.luminance() < 0.5
+    }
+}
+
+impl Display for Rgb {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+/// Gets the average colour of the image's pixels.
+pub fn pixbuf_color(pixbuf: &Pixbuf) -> Option<Rgb> {
+    let pixels = pixbuf.read_pixel_bytes();
+
+    average_color(
+        &pixels,
+        pixbuf.width() as usize,
+        pixbuf.height() as usize,
+        pixbuf.rowstride() as usize,
+        pixbuf.n_channels() as usize,
+    )
+}
+
+/// Gets the average colour of packed RGB or RGBA pixels,
+/// weighted by their alpha so transparent areas are ignored.
+///
+/// Returns `None` if there are no visible pixels.
+fn average_color(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+    rowstride: usize,
+    n_channels: usize,
+) -> Option<Rgb> {
+    if n_channels < 3 {
+        return None;
+    }
+
+    let mut totals = [0u64; 3];
+    let mut weight = 0u64;
+
+    for y in 0..height {
+        // the last row is not padded to the full stride
+        let Some(row) = pixels.get(y * rowstride..) else {
+            break;
+        };
+
+        for pixel in row.chunks_exact(n_channels).take(width) {
+            let alpha = pixel.get(3).map_or(255, |alpha| u64::from(*alpha));
+
+            for (total, value) in totals.iter_mut().zip(pixel) {
+                *total += u64::from(*value) * alpha;
+            }
+
+            weight += alpha;
+        }
+    }
+
+    if weight == 0 {
+        return None;
+    }
+
+    let [red, green, blue] = totals.map(|total| (total / weight) as u8);
+    Some(Rgb { red, green, blue })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_visible_pixels() {
+        // 2x2 RGBA, with a padded row stride and a transparent pixel
+        let pixels = [
+            255, 0, 0, 255, 0, 0, 255, 255, 9, 9, //
+            0, 0, 255, 255, 255, 255, 255, 0,
+        ];
+
+        let color = average_color(&pixels, 2, 2, 10, 4);
+        assert_eq!(
+            color,
+            Some(Rgb {
+                red: 85,
+                green: 0,
+                blue: 170
+            })
+        );
+        assert_eq!(
+            color.map(|color| color.to_string()).as_deref(),
+            Some("#5500aa")
+        );
+        assert!(color.is_some_and(Rgb::is_dark));
+
+        assert_eq!(average_color(&[0, 0, 0, 0], 1, 1, 4, 4), None);
+    }
+}
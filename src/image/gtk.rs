@@ -30,7 +30,7 @@ pub fn new_icon_button(input: &str, icon_theme: &IconTheme, size: i32) -> Button
     button
 }
 
-#[cfg(feature = "music")]
+#[cfg(any(feature = "idle_inhibit", feature = "music", feature = "privacy"))]
 pub fn new_icon_label(input: &str, icon_theme: &IconTheme, size: i32) -> gtk::Box {
     let container = gtk::Box::new(Orientation::Horizontal, 0);
 
@@ -1,7 +1,29 @@
-#[cfg(any(feature = "music", feature = "workspaces", feature = "clipboard"))]
+#[cfg(feature = "music")]
+mod color;
+#[cfg(any(
+    feature = "clipboard",
+    feature = "idle_inhibit",
+    feature = "music",
+    feature = "privacy",
+    feature = "workspaces"
+))]
 mod gtk;
 mod provider;
+mod rescale;
+mod theme;
 
-#[cfg(any(feature = "music", feature = "workspaces"))]
+#[cfg(any(
+    feature = "idle_inhibit",
+    feature = "music",
+    feature = "privacy",
+    feature = "workspaces"
+))]
 pub use self::gtk::*;
+#[cfg(feature = "music")]
+pub use color::Rgb;
 pub use provider::ImageProvider;
+#[cfg(feature = "tray")]
+pub use rescale::{forget as forget_scalable, load_scalable, Scalable};
+pub use theme::add_export_search_paths;
+#[cfg(feature = "tray")]
+pub use theme::get_icon_theme_search_paths;
@@ -1,4 +1,9 @@
+#[cfg(feature = "music")]
+use super::color::{pixbuf_color, Rgb};
+use super::rescale::{self, Scalable};
+use super::theme::{find_exported_icon, is_reverse_dns};
 use crate::desktop_file::get_desktop_icon_name;
+use crate::diagnostics;
 #[cfg(feature = "http")]
 use crate::{glib_recv_mpsc, send_async, spawn};
 use cfg_if::cfg_if;
@@ -123,6 +128,14 @@ impl<'a> ImageProvider<'a> {
             }
             None if recurse_depth == MAX_RECURSE_DEPTH => fallback!(),
             None if should_parse_desktop_file => {
+                // flatpak icons may be missing from the theme
+                // if it was loaded before their directory existed
+                if is_reverse_dns(input_name) {
+                    if let Some(path) = find_exported_icon(input_name, size) {
+                        return Some(ImageLocation::Local(path));
+                    }
+                }
+
                 if let Some(location) = get_desktop_icon_name(input_name).map(|input| {
                     Self::get_location(&input, theme, size, use_fallback, recurse_depth + 1)
                 }) {
@@ -141,7 +154,16 @@ impl<'a> ImageProvider<'a> {
 
     /// Attempts to fetch the image from the location
     /// and load it into the provided `GTK::Image` widget.
+    ///
+    /// Icons and SVGs are rendered again if the scale factor
+    /// or CSS size of the image changes.
     pub fn load_into_image(&self, image: gtk::Image) -> Result<()> {
+        if let Some(source) = self.scalable() {
+            return rescale::load_scalable(&image, source, self.size);
+        }
+
+        rescale::forget(&image);
+
         // handle remote locations async to avoid blocking UI thread while downloading
         #[cfg(feature = "http")]
         if let ImageLocation::Remote(url) = &self.location {
@@ -190,22 +212,91 @@ impl<'a> ImageProvider<'a> {
         Ok(())
     }
 
+    /// Reads the average colour of the image off the main thread,
+    /// then calls `f` with it.
+    ///
+    /// Only files and remote images are supported,
+    /// with `None` passed for anything else or if the image cannot be read.
+    #[cfg(feature = "music")]
+    pub fn load_average_color<F>(&self, f: F)
+    where
+        F: FnOnce(Option<Rgb>) + 'static,
+    {
+        // downscaling first keeps this fast for large album art
+        const SAMPLE_SIZE: i32 = 32;
+
+        let handle = match &self.location {
+            ImageLocation::Local(path) => {
+                let path = path.clone();
+                crate::spawn_blocking(move || {
+                    Pixbuf::from_file_at_scale(path, SAMPLE_SIZE, SAMPLE_SIZE, true)
+                        .ok()
+                        .and_then(|pixbuf| pixbuf_color(&pixbuf))
+                })
+            }
+            #[cfg(feature = "http")]
+            ImageLocation::Remote(url) => {
+                let url = url.clone();
+                spawn(async move {
+                    let bytes = Self::get_bytes_from_http(url).await.ok()?;
+
+                    crate::spawn_blocking(move || {
+                        let stream = MemoryInputStream::from_bytes(&bytes);
+                        Pixbuf::from_stream_at_scale(
+                            &stream,
+                            SAMPLE_SIZE,
+                            SAMPLE_SIZE,
+                            true,
+                            None::<&Cancellable>,
+                        )
+                        .ok()
+                        .and_then(|pixbuf| pixbuf_color(&pixbuf))
+                    })
+                    .await
+                    .ok()
+                    .flatten()
+                })
+            }
+            _ => {
+                f(None);
+                return;
+            }
+        };
+
+        glib::spawn_future_local(async move {
+            f(handle.await.ok().flatten());
+        });
+    }
+
     /// Attempts to synchronously fetch an image from location
     /// and load into into the image.
     fn load_into_image_sync(&self, image: &gtk::Image) -> Result<()> {
         let scale = image.scale_factor();
 
         let pixbuf = match &self.location {
-            ImageLocation::Icon { name, theme } => self.get_from_icon(name, theme, scale),
             ImageLocation::Local(path) => self.get_from_file(path, scale),
             ImageLocation::Steam(steam_id) => self.get_from_steam_id(steam_id, scale),
-            #[cfg(feature = "http")]
             _ => unreachable!(), // handled above
         }?;
 
         Self::create_and_load_surface(&pixbuf, image)
     }
 
+    /// Gets the image as a source which can be rendered at any size,
+    /// if it is an icon or an SVG.
+    fn scalable(&self) -> Option<Scalable> {
+        match &self.location {
+            ImageLocation::Icon { name, theme } => Some(Scalable::Icon {
+                name: name.clone(),
+                theme: (*theme).clone(),
+            }),
+            ImageLocation::Local(path) if rescale::is_svg(path) => {
+                Some(Scalable::Svg(path.clone()))
+            }
+            _ => None,
+        }
+    }
+
     /// Attempts to create a Cairo surface from the provided `Pixbuf`,
     /// using the provided scaling factor.
     /// The surface is then loaded into the provided image.
@@ -222,24 +313,11 @@ impl<'a> ImageProvider<'a> {
         }?;
 
         image.set_from_surface(Some(&surface));
+        diagnostics::record_image(image, pixbuf.byte_length());
 
         Ok(())
     }
 
-    /// Attempts to get a `Pixbuf` from the GTK icon theme.
-    fn get_from_icon(&self, name: &str, theme: &IconTheme, scale: i32) -> Result<Pixbuf> {
-        let pixbuf =
-            match theme.lookup_icon_for_scale(name, self.size, scale, IconLookupFlags::empty()) {
-                Some(_) => theme.load_icon(name, self.size * scale, IconLookupFlags::FORCE_SIZE),
-                None => Ok(None),
-            }?;
-
-        pixbuf.map_or_else(
-            || Err(Report::msg("Icon theme does not contain icon '{name}'")),
-            Ok,
-        )
-    }
-
     /// Attempts to get a `Pixbuf` from a local file.
     fn get_from_file(&self, path: &Path, scale: i32) -> Result<Pixbuf> {
         let scaled_size = self.size * scale;
@@ -0,0 +1,153 @@
+use super::ImageProvider;
+use crate::gtk_helpers::IronbarGtkExt;
+use color_eyre::{Report, Result};
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::prelude::*;
+use gtk::{IconLookupFlags, IconTheme};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tracing::warn;
+
+const TAG: &str = "rescale";
+
+/// An image which can be rasterised at any size,
+/// rather than scaling an existing bitmap.
+#[derive(Debug, Clone)]
+pub enum Scalable {
+    Icon { name: String, theme: IconTheme },
+    Svg(PathBuf),
+}
+
+/// Checks whether the file is an SVG, going by its extension.
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"))
+}
+
+impl Scalable {
+    /// Loads the image at exactly `size * scale` pixels.
+    fn render(&self, size: i32, scale: i32) -> Result<Pixbuf> {
+        match self {
+            Self::Icon { name, theme } => theme
+                .load_icon_for_scale(name, size, scale, IconLookupFlags::FORCE_SIZE)?
+                .ok_or_else(|| Report::msg(format!("Icon theme does not contain icon '{name}'"))),
+            Self::Svg(path) => {
+                let scaled_size = size * scale;
+                Ok(Pixbuf::from_file_at_scale(
+                    path,
+                    scaled_size,
+                    scaled_size,
+                    true,
+                )?)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Rescale {
+    source: Scalable,
+    size: i32,
+    /// The size and scale factor last rendered at.
+    rendered: Option<(i32, i32)>,
+}
+
+/// The image last loaded into a widget,
+/// or `None` if that could not be rescaled.
+type State = Rc<RefCell<Option<Rescale>>>;
+
+/// Loads the image into the widget,
+/// rendering it again whenever the output scale factor
+/// or its CSS `min-width`/`min-height` change.
+///
+/// This keeps icons and SVGs sharp on HiDPI outputs,
+/// and when styles such as `:hover` rules grow them.
+pub fn load_scalable(image: &gtk::Image, source: Scalable, size: i32) -> Result<()> {
+    let rescale = Rescale {
+        source,
+        size,
+        rendered: None,
+    };
+
+    let state = if let Some(state) = image.get_tag::<State>(TAG) {
+        state.replace(Some(rescale));
+        state.clone()
+    } else {
+        let state = Rc::new(RefCell::new(Some(rescale)));
+        image.set_tag(TAG, state.clone());
+
+        // the signals are connected once per widget,
+        // and always render whatever was most recently loaded
+        let on_change = |image: &gtk::Image| {
+            if let Some(state) = image.get_tag::<State>(TAG) {
+                if let Err(err) = render(image, state) {
+                    warn!("{err:?}");
+                }
+            }
+        };
+
+        image.connect_scale_factor_notify(on_change);
+        image.connect_style_updated(on_change);
+
+        state
+    };
+
+    render(image, &state)
+}
+
+/// Stops rendering the image last loaded with [`load_scalable`],
+/// so that it does not replace a different image loaded since.
+pub fn forget(image: &gtk::Image) {
+    if let Some(state) = image.get_tag::<State>(TAG) {
+        state.replace(None);
+    }
+}
+
+/// Renders the image if its target size or scale factor
+/// has changed since it was last rendered.
+fn render(image: &gtk::Image, state: &State) -> Result<()> {
+    let scale = image.scale_factor();
+    let css_size = css_size(image);
+
+    let (source, size) = {
+        let mut state = state.borrow_mut();
+        let Some(rescale) = state.as_mut() else {
+            return Ok(());
+        };
+
+        let size = rescale.size.max(css_size);
+        if rescale.rendered == Some((size, scale)) {
+            return Ok(());
+        }
+
+        rescale.rendered = Some((size, scale));
+        (rescale.source.clone(), size)
+    };
+
+    // the state is not borrowed while loading,
+    // in case that causes a style update
+    let pixbuf = source.render(size, scale)?;
+    ImageProvider::create_and_load_surface(&pixbuf, image)
+}
+/// Gets the larger of the image's CSS `min-width` and `min-height`,
+/// for its current state.
+///
+/// The allocated size is not used, as that is derived from the image
+/// and containers may allocate more than it asks for.
+fn css_size(image: &gtk::Image) -> i32 {
+    let context = image.style_context();
+    let state = context.state();
+
+    ["min-width", "min-height"]
+        .into_iter()
+        .filter_map(|property| {
+            context
+                .style_property_for_state(property, state)
+                .get::<i32>()
+                .ok()
+        })
+        .max()
+        .unwrap_or_default()
+}
@@ -0,0 +1,169 @@
+use crate::desktop_file::find_export_dirs;
+use glib::ffi::g_strfreev;
+use glib::translate::ToGlibPtr;
+use gtk::ffi::gtk_icon_theme_get_search_path;
+use gtk::prelude::IconThemeExt;
+use gtk::IconTheme;
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::fs;
+use std::os::raw::{c_char, c_int};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use tracing::debug;
+
+/// The file types icon themes provide icons in.
+const ICON_EXTENSIONS: [&str; 2] = ["svg", "png"];
+
+/// Gets the GTK icon theme search paths by calling the FFI function.
+/// Conveniently returns the result as a `HashSet`.
+pub fn get_icon_theme_search_paths(icon_theme: &IconTheme) -> HashSet<String> {
+    let mut gtk_paths: *mut *mut c_char = ptr::null_mut();
+    let mut n_elements: c_int = 0;
+    let mut paths = HashSet::new();
+    unsafe {
+        gtk_icon_theme_get_search_path(
+            icon_theme.to_glib_none().0,
+            &mut gtk_paths,
+            &mut n_elements,
+        );
+        // n_elements is never negative (that would be weird)
+        for i in 0..n_elements as usize {
+            let c_str = CStr::from_ptr(*gtk_paths.add(i));
+            if let Ok(str) = c_str.to_str() {
+                paths.insert(str.to_owned());
+            }
+        }
+
+        g_strfreev(gtk_paths);
+    }
+
+    paths
+}
+
+/// Adds the flatpak and snap icon directories to the theme's search path
+/// if they are not already included.
+///
+/// Directories which do not exist yet are still added,
+/// as GTK picks up changes to the search path when apps are installed.
+pub fn add_export_search_paths(icon_theme: &IconTheme) {
+    let paths = get_icon_theme_search_paths(icon_theme);
+
+    for dir in find_export_dirs() {
+        let dir = dir.join("icons");
+
+        if !paths.contains(&*dir.to_string_lossy()) {
+            debug!("Adding icon search path: {}", dir.display());
+            icon_theme.append_search_path(dir);
+        }
+    }
+}
+
+/// Whether the name is a reverse-DNS app ID, such as `org.mozilla.firefox`.
+/// Flatpak apps use these as their icon names.
+pub fn is_reverse_dns(name: &str) -> bool {
+    let mut parts = name.split('.');
+
+    parts.clone().count() >= 3
+        && parts.all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+/// Finds an icon file exported by a flatpak or snap app.
+///
+/// This is used when the icon theme cannot find the icon,
+/// which happens when the theme is loaded before the export directories exist,
+/// or for snaps, which do not follow the icon theme layout.
+pub fn find_exported_icon(name: &str, size: i32) -> Option<PathBuf> {
+    let mut candidates = vec![];
+
+    for dir in find_export_dirs() {
+        let icons = dir.join("icons");
+
+        // snap exports icons directly, with no theme directories
+        candidates.extend(icon_files(&icons, name).map(|path| (None, path)));
+
+        let Ok(sizes) = fs::read_dir(icons.join("hicolor")) else {
+            continue;
+        };
+
+        for entry in sizes.filter_map(Result::ok) {
+            let icon_size = parse_icon_size(&entry.file_name().to_string_lossy());
+            let apps = entry.path().join("apps");
+
+            candidates.extend(icon_files(&apps, name).map(|path| (icon_size, path)));
+        }
+    }
+
+    best_icon(candidates, size)
+}
+
+/// Gets the existing icon files for the name in the directory.
+fn icon_files<'a>(dir: &'a Path, name: &'a str) -> impl Iterator<Item = PathBuf> + 'a {
+    ICON_EXTENSIONS
+        .iter()
+        .map(move |extension| dir.join(format!("{name}.{extension}")))
+        .filter(|path| path.is_file())
+}
+
+/// Parses an icon theme size directory name, such as `48x48` or `48x48@2`.
+/// Scalable icons have no size.
+fn parse_icon_size(dir_name: &str) -> Option<i32> {
+    let (size, scale) = dir_name.split_once('@').unwrap_or((dir_name, "1"));
+    let (width, _) = size.split_once('x')?;
+
+    Some(width.parse::<i32>().ok()? * scale.parse::<i32>().ok()?)
+}
+
+/// Picks the icon which will look best at the size.
+///
+/// Scalable icons are preferred,
+/// followed by the smallest icon at least as large as the size,
+/// so that icons are scaled down rather than up.
+fn best_icon(candidates: Vec<(Option<i32>, PathBuf)>, size: i32) -> Option<PathBuf> {
+    candidates
+        .into_iter()
+        .min_by_key(|(icon_size, _)| match *icon_size {
+            None => (0, 0),
+            Some(icon_size) if icon_size >= size => (1, icon_size),
+            Some(icon_size) => (2, -icon_size),
+        })
+        .map(|(_, path)| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_reverse_dns_ids() {
+        assert!(is_reverse_dns("org.mozilla.firefox"));
+        assert!(is_reverse_dns("com.github.tchx84.Flatseal"));
+        assert!(!is_reverse_dns("firefox"));
+        assert!(!is_reverse_dns("steam.desktop"));
+        assert!(!is_reverse_dns("/usr/share/icons/firefox.png"));
+        assert!(!is_reverse_dns("org..firefox"));
+    }
+
+    #[test]
+    fn picks_closest_icon_size() {
+        let icon = |size: Option<i32>| (size, PathBuf::from(format!("{size:?}")));
+
+        assert_eq!(parse_icon_size("48x48"), Some(48));
+        assert_eq!(parse_icon_size("32x32@2"), Some(64));
+        assert_eq!(parse_icon_size("scalable"), None);
+
+        let sizes = vec![icon(Some(16)), icon(Some(64)), icon(Some(128))];
+        assert_eq!(best_icon(sizes.clone(), 48), Some(icon(Some(64)).1));
+        assert_eq!(best_icon(sizes.clone(), 256), Some(icon(Some(128)).1));
+
+        let mut with_scalable = sizes;
+        with_scalable.push(icon(None));
+        assert_eq!(best_icon(with_scalable, 48), Some(icon(None).1));
+        assert_eq!(best_icon(vec![], 48), None);
+    }
+}
@@ -24,10 +24,12 @@ impl Ipc {
 
         stream.write_all(&write_buffer).await?;
 
-        let mut read_buffer = vec![0; 1024];
-        let bytes = stream.read(&mut read_buffer).await?;
+        // the server closes the connection once the response is written,
+        // so responses of any length can be read in full.
+        let mut read_buffer = vec![];
+        stream.read_to_end(&mut read_buffer).await?;
 
-        let response = serde_json::from_slice(&read_buffer[..bytes])?;
+        let response = serde_json::from_slice(&read_buffer)?;
         Ok(response)
     }
 }
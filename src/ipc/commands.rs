@@ -29,6 +29,29 @@ pub enum Command {
 
     /// Interact with a specific bar.
     Bar(BarCommand),
+
+    /// Send an action to a module.
+    /// Use `module restart <widget_name>` to rebuild a module in place.
+    Module(ModuleCommand),
+
+    /// Inspect Ironbar's internal state.
+    #[command(subcommand)]
+    Debug(DebugCommand),
+
+    /// Interact with the items in the system tray.
+    #[cfg(feature = "tray")]
+    #[command(subcommand)]
+    Tray(TrayCommand),
+
+    /// Run a sequence of commands defined in the `actions` config.
+    Action {
+        /// The name of the action.
+        name: String,
+
+        /// Arguments to substitute for `$1`, `$2` etc.
+        #[serde(default)]
+        args: Vec<String>,
+    },
 }
 
 #[derive(Subcommand, Debug, Serialize, Deserialize)]
@@ -45,6 +68,28 @@ pub enum IronvarCommand {
         value: String,
     },
 
+    /// Set several `ironvar` values at once.
+    /// No references are updated until every value is set,
+    /// so they never see some values from before the change and some from after.
+    SetMany {
+        /// Pairs of variable keys and values, written as `key=value`.
+        #[clap(required = true)]
+        values: Vec<String>,
+    },
+
+    /// Set an `ironvar` value, only if it currently has the expected value.
+    /// If it does not, nothing is set and the current value is returned
+    /// with exit code 4.
+    Cas {
+        /// Variable key.
+        key: Box<str>,
+        /// The value the variable must currently have.
+        /// An empty string matches a variable which is not set.
+        expected: String,
+        /// New variable value.
+        value: String,
+    },
+
     /// Get the current value of an `ironvar`.
     Get {
         /// Variable key.
@@ -55,6 +100,44 @@ pub enum IronvarCommand {
     List,
 }
 
+#[cfg(feature = "tray")]
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum TrayCommand {
+    /// List the current tray items as JSON.
+    List,
+
+    /// Activate an item, as if its icon was left-clicked in other trays.
+    Activate {
+        /// The item ID, as shown by `tray list`.
+        id: String,
+    },
+
+    /// Secondary-activate an item, as if its icon was middle-clicked in other trays.
+    SecondaryActivate {
+        /// The item ID, as shown by `tray list`.
+        id: String,
+    },
+
+    /// Open an item's menu from its icon on the bar.
+    ContextMenu {
+        /// The item ID, as shown by `tray list`.
+        id: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum DebugCommand {
+    /// Get the activity counters for each module instance as JSON.
+    /// Requires diagnostics to be enabled.
+    Perf,
+
+    /// Get the state of Ironbar's shared clients as JSON,
+    /// such as the tray mode and item count.
+    State,
+}
+
 #[derive(Args, Debug, Serialize, Deserialize)]
 pub struct BarCommand {
     /// The name of the bar.
@@ -65,6 +148,21 @@ pub struct BarCommand {
     pub subcommand: BarCommandType,
 }
 
+#[derive(Args, Debug, Serialize, Deserialize)]
+pub struct ModuleCommand {
+    /// The configured name of the module.
+    /// If no name is configured, this is the module type.
+    pub name: String,
+
+    /// The action to perform.
+    /// Available actions depend on the module.
+    pub action: String,
+
+    /// Arguments to pass to the action.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
 #[derive(Subcommand, Debug, Serialize, Deserialize)]
 #[serde(tag = "subcommand", rename_all = "snake_case")]
 pub enum BarCommandType {
@@ -129,4 +227,9 @@ pub enum BarCommandType {
         )]
         exclusive: bool,
     },
+
+    // == Focus == \\
+    /// Grant the bar keyboard focus, allowing its widgets to be navigated using the keyboard.
+    /// Focus is returned to the compositor on escape.
+    Focus,
 }
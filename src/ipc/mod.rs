@@ -8,6 +8,7 @@ use tracing::warn;
 
 pub use commands::*;
 pub use responses::Response;
+pub use server::check_actions;
 
 #[derive(Debug)]
 pub struct Ipc {
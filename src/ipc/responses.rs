@@ -4,8 +4,17 @@ use serde::{Deserialize, Serialize};
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Response {
     Ok,
-    OkValue { value: String },
-    Err { message: Option<String> },
+    OkValue {
+        value: String,
+    },
+    /// A compare-and-set did not match, so nothing was set.
+    /// Contains the current value.
+    Mismatch {
+        value: Option<String>,
+    },
+    Err {
+        message: Option<String>,
+    },
 }
 
 impl Response {
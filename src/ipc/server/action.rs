@@ -0,0 +1,335 @@
+use crate::ipc::{Command, Ipc, Response};
+use crate::Ironbar;
+use clap::Parser;
+use gtk::Application;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, error};
+
+const SLEEP: &str = "sleep";
+
+const ERR_NESTED: &str = "actions cannot run other actions";
+
+/// A single step of an action.
+#[derive(Debug)]
+enum Step {
+    Command(Command),
+    /// Waits for the number of milliseconds.
+    Sleep(u64),
+}
+
+/// Parses a step as an IPC command, as it would be written for the CLI.
+#[derive(Parser, Debug)]
+#[command(no_binary_name = true)]
+struct StepArgs {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Runs each step of the named action in order,
+/// stopping at the first to fail.
+///
+/// The steps run in the background on the main thread.
+/// The response is sent once the action finishes,
+/// or as soon as it reaches a `sleep` step,
+/// so that waiting does not hold up other IPC commands.
+/// Failures after the response has been sent are logged instead.
+///
+/// Responds with the value of the last step to return one.
+pub fn run(
+    name: &str,
+    args: &[String],
+    application: &Application,
+    ironbar: &Rc<Ironbar>,
+) -> oneshot::Receiver<Response> {
+    let (tx, rx) = oneshot::channel();
+
+    let steps = ironbar
+        .config
+        .borrow()
+        .actions
+        .as_ref()
+        .and_then(|actions| actions.get(name).cloned());
+
+    let Some(steps) = steps else {
+        tx.send(Response::error("Action not found")).ok();
+        return rx;
+    };
+
+    // every step is checked first, so a mistake never leaves an action half-run
+    let steps = match parse_steps(&steps, args) {
+        Ok(steps) => steps,
+        Err(err) => {
+            tx.send(Response::error(&err)).ok();
+            return rx;
+        }
+    };
+
+    debug!("Running action '{name}'");
+
+    let name = name.to_string();
+    let application = application.clone();
+    let ironbar = ironbar.clone();
+
+    glib::spawn_future_local(async move {
+        let mut reply = Some(tx);
+        let mut value = None;
+
+        for (i, step) in steps.into_iter().enumerate() {
+            let command = match step {
+                Step::Sleep(millis) => {
+                    if let Some(reply) = reply.take() {
+                        reply.send(value_response(value.clone())).ok();
+                    }
+
+                    glib::timeout_future(Duration::from_millis(millis)).await;
+                    continue;
+                }
+                Step::Command(command) => command,
+            };
+
+            let response = match Ipc::handle_command(command, &application, &ironbar).await {
+                Response::Ok => continue,
+                Response::OkValue { value: step_value } => {
+                    value = Some(step_value);
+                    continue;
+                }
+                // a failed compare-and-set stops the action, like an error
+                response @ Response::Mismatch { .. } => response,
+                Response::Err { message } => Response::error(&format!(
+                    "Step {} failed: {}",
+                    i + 1,
+                    message.unwrap_or_else(|| String::from("unknown error"))
+                )),
+            };
+
+            match reply.take() {
+                Some(reply) => {
+                    reply.send(response).ok();
+                }
+                None => error!("Action '{name}' stopped: {response:?}"),
+            }
+
+            return;
+        }
+
+        if let Some(reply) = reply.take() {
+            reply.send(value_response(value)).ok();
+        }
+    });
+
+    rx
+}
+
+fn value_response(value: Option<String>) -> Response {
+    match value {
+        Some(value) => Response::OkValue { value },
+        None => Response::Ok,
+    }
+}
+
+/// Removes any action with a step which runs another action,
+/// logging an error for each.
+///
+/// Other mistakes can depend on the arguments,
+/// so are only checked when the action runs.
+pub fn check_actions(actions: &mut HashMap<String, Vec<String>>) {
+    actions.retain(|name, steps| {
+        let nested = steps.iter().enumerate().find(|(_, step)| {
+            split_words(step).is_ok_and(|words| {
+                matches!(
+                    StepArgs::try_parse_from(words),
+                    Ok(StepArgs {
+                        command: Command::Action { .. }
+                    })
+                )
+            })
+        });
+
+        match nested {
+            Some((i, step)) => {
+                error!(
+                    "Ignoring action '{name}': Step {} ({step}): {ERR_NESTED}",
+                    i + 1
+                );
+                false
+            }
+            None => true,
+        }
+    });
+}
+
+fn parse_steps(steps: &[String], args: &[String]) -> Result<Vec<Step>, String> {
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            parse_step(step, args).map_err(|err| format!("Step {} ({step}): {err}", i + 1))
+        })
+        .collect()
+}
+
+fn parse_step(step: &str, args: &[String]) -> Result<Step, String> {
+    let words = split_words(step)?
+        .into_iter()
+        .map(|word| substitute(&word, args))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let [command, millis] = words.as_slice() {
+        if command == SLEEP {
+            return millis
+                .parse()
+                .map(Step::Sleep)
+                .map_err(|_| format!("invalid sleep duration '{millis}'"));
+        }
+    }
+
+    match StepArgs::try_parse_from(words) {
+        Ok(StepArgs {
+            command: Command::Action { .. },
+        }) => Err(String::from(ERR_NESTED)),
+        Ok(args) => Ok(Step::Command(args.command)),
+        Err(err) => Err({
+            // only the first line is kept, as the rest is usage help
+            err.to_string()
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches("error: ")
+                .to_string()
+        }),
+    }
+}
+
+/// Splits the step into words on whitespace.
+/// Words can be wrapped in single or double quotes to include spaces.
+fn split_words(step: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word = None::<String>;
+    let mut quote = None;
+
+    for char in step.chars() {
+        match (quote, char) {
+            (None, '"' | '\'') => {
+                quote = Some(char);
+                word.get_or_insert_with(String::new);
+            }
+            (Some(open), _) if open == char => quote = None,
+            (None, _) if char.is_whitespace() => words.extend(word.take()),
+            _ => word.get_or_insert_with(String::new).push(char),
+        }
+    }
+
+    if quote.is_some() {
+        return Err(String::from("unclosed quote"));
+    }
+
+    words.extend(word);
+    Ok(words)
+}
+
+/// Replaces `$1`, `$2` etc. with the action's arguments.
+fn substitute(word: &str, args: &[String]) -> Result<String, String> {
+    let mut output = String::new();
+    let mut rest = word;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let len = rest
+            .find(|char: char| !char.is_ascii_digit())
+            .unwrap_or(rest.len());
+
+        // a `$` without a number is kept as-is
+        let Ok(index) = rest[..len].parse::<usize>() else {
+            output.push('$');
+            continue;
+        };
+
+        let arg = index
+            .checked_sub(1)
+            .and_then(|index| args.get(index))
+            .ok_or_else(|| format!("missing argument ${index}"))?;
+
+        output.push_str(arg);
+        rest = &rest[len..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::IronvarCommand;
+
+    #[test]
+    fn splits_quoted_words() {
+        assert_eq!(
+            split_words(r#"var set  msg "hello world" 'it"s'"#),
+            Ok(vec![
+                String::from("var"),
+                String::from("set"),
+                String::from("msg"),
+                String::from("hello world"),
+                String::from("it\"s"),
+            ])
+        );
+        assert_eq!(split_words("var set x ''").map(|words| words.len()), Ok(4));
+        assert!(split_words("var set x \"open").is_err());
+    }
+
+    #[test]
+    fn substitutes_arguments() {
+        let args = [String::from("aux"), String::from("true")];
+
+        assert_eq!(substitute("bar-$1", &args).as_deref(), Ok("bar-aux"));
+        assert_eq!(substitute("$2$1", &args).as_deref(), Ok("trueaux"));
+        assert_eq!(substitute("$", &args).as_deref(), Ok("$"));
+        assert!(substitute("$3", &args).is_err());
+        assert!(substitute("$0", &args).is_err());
+    }
+
+    #[test]
+    fn parses_steps() {
+        let steps = [String::from("var set dnd $1"), String::from("sleep 500")];
+        let parsed = parse_steps(&steps, &[String::from("true")]);
+
+        assert!(matches!(
+            parsed.as_deref(),
+            Ok([
+                Step::Command(Command::Var(IronvarCommand::Set { key, value })),
+                Step::Sleep(500),
+            ]) if &**key == "dnd" && value == "true"
+        ));
+
+        assert!(parse_steps(&[String::from("sleep soon")], &[]).is_err());
+        assert!(
+            parse_steps(&[String::from("action $1")], &[String::from("peek")])
+                .is_err_and(|err| err.ends_with(ERR_NESTED))
+        );
+        assert!(parse_steps(&[String::from("not a command")], &[])
+            .is_err_and(|err| err.starts_with("Step 1 (not a command): ")));
+    }
+
+    #[test]
+    fn rejects_nested_actions() {
+        let mut actions = HashMap::from([
+            (
+                String::from("peek"),
+                vec![String::from("bar show $1"), String::from("sleep 500")],
+            ),
+            (
+                String::from("nested"),
+                vec![String::from("sleep 500"), String::from("action peek aux")],
+            ),
+        ]);
+
+        check_actions(&mut actions);
+        assert_eq!(actions.keys().collect::<Vec<_>>(), [&String::from("peek")]);
+    }
+}
@@ -46,6 +46,11 @@ pub fn handle_command(command: BarCommand, ironbar: &Rc<Ironbar>) -> Response {
         SetExclusive { exclusive } => {
             bar.set_exclusive(exclusive);
 
+            Response::Ok
+        }
+        Focus => {
+            bar.focus();
+
             Response::Ok
         }
     }
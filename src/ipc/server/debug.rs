@@ -0,0 +1,53 @@
+#[cfg(feature = "tray")]
+use crate::clients::tray;
+use crate::clients::wayland::OutputDetails;
+use crate::diagnostics;
+use crate::ipc::commands::DebugCommand;
+use crate::ipc::Response;
+use crate::Ironbar;
+use serde::Serialize;
+use std::rc::Rc;
+
+/// The state of the shared clients, as shown by `ironbar debug state`.
+#[derive(Debug, Serialize)]
+struct State {
+    /// The details of each connected output.
+    outputs: Vec<OutputDetails>,
+    /// The tray client's state, or `None` if no tray has been loaded.
+    #[cfg(feature = "tray")]
+    tray: Option<tray::DebugState>,
+}
+
+#[cfg_attr(not(feature = "tray"), allow(unused_variables))]
+pub async fn handle_command(command: DebugCommand, ironbar: &Rc<Ironbar>) -> Response {
+    match command {
+        DebugCommand::Perf => {
+            let Some(registry) = diagnostics::registry() else {
+                return Response::error("Diagnostics are not enabled");
+            };
+
+            match serde_json::to_string(&registry.stats()) {
+                Ok(value) => Response::OkValue { value },
+                Err(err) => Response::error(&format!("{err}")),
+            }
+        }
+        DebugCommand::State => {
+            #[cfg(feature = "tray")]
+            let tray = ironbar.clients.borrow().existing_tray();
+
+            let state = State {
+                outputs: OutputDetails::all(),
+                #[cfg(feature = "tray")]
+                tray: match tray {
+                    Some(client) => Some(tray::debug_state(&client).await),
+                    None => None,
+                },
+            };
+
+            match serde_json::to_string(&state) {
+                Ok(value) => Response::OkValue { value },
+                Err(err) => Response::error(&format!("{err}")),
+            }
+        }
+    }
+}
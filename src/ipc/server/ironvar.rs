@@ -1,5 +1,6 @@
 use crate::ipc::commands::IronvarCommand;
 use crate::ipc::Response;
+use crate::ironvar::CompareAndSet;
 use crate::{read_lock, write_lock, Ironbar};
 
 pub fn handle_command(command: IronvarCommand) -> Response {
@@ -12,6 +13,40 @@ pub fn handle_command(command: IronvarCommand) -> Response {
                 Err(err) => Response::error(&format!("{err}")),
             }
         }
+        IronvarCommand::SetMany { values } => {
+            let values = match values
+                .iter()
+                .map(|pair| {
+                    pair.split_once('=')
+                        .map(|(key, value)| (key.into(), value.to_string()))
+                        .ok_or_else(|| format!("Expected 'key=value', found '{pair}'"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(values) => values,
+                Err(err) => return Response::error(&err),
+            };
+
+            let variable_manager = Ironbar::variable_manager();
+            let mut variable_manager = write_lock!(variable_manager);
+            match variable_manager.set_many(values) {
+                Ok(()) => Response::Ok,
+                Err(err) => Response::error(&format!("{err}")),
+            }
+        }
+        IronvarCommand::Cas {
+            key,
+            expected,
+            value,
+        } => {
+            let variable_manager = Ironbar::variable_manager();
+            let mut variable_manager = write_lock!(variable_manager);
+            match variable_manager.compare_and_set(key, &expected, value) {
+                Ok(CompareAndSet::Set) => Response::Ok,
+                Ok(CompareAndSet::Mismatch(value)) => Response::Mismatch { value },
+                Err(err) => Response::error(&format!("{err}")),
+            }
+        }
         IronvarCommand::Get { key } => {
             let variable_manager = Ironbar::variable_manager();
             let value = read_lock!(variable_manager).get(&key);
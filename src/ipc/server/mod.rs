@@ -1,5 +1,10 @@
+mod action;
 mod bar;
+mod debug;
 mod ironvar;
+mod module;
+#[cfg(feature = "tray")]
+mod tray;
 
 use std::fs;
 use std::path::Path;
@@ -19,6 +24,8 @@ use crate::{glib_recv_mpsc, send_async, spawn, try_send, Ironbar};
 
 use super::Ipc;
 
+pub use action::check_actions;
+
 impl Ipc {
     /// Starts the IPC server on its socket.
     ///
@@ -67,7 +74,14 @@ impl Ipc {
 
         let application = application.clone();
         glib_recv_mpsc!(cmd_rx, command => {
-            let res = Self::handle_command(command, &application, &ironbar);
+            // actions run other commands, so cannot be handled by `handle_command` itself
+            let res = match command {
+                Command::Action { name, args } => action::run(&name, &args, &application, &ironbar)
+                    .await
+                    .unwrap_or_else(|_| Response::error("Action stopped unexpectedly")),
+                command => Self::handle_command(command, &application, &ironbar).await,
+            };
+
             try_send!(res_tx, res);
         });
     }
@@ -107,7 +121,8 @@ impl Ipc {
     /// Takes an input command, runs it and returns with the appropriate response.
     ///
     /// This runs on the main thread, allowing commands to interact with GTK.
-    fn handle_command(
+    /// Commands which wait on other processes are awaited without blocking it.
+    async fn handle_command(
         command: Command,
         application: &Application,
         ironbar: &Rc<Ironbar>,
@@ -119,19 +134,26 @@ impl Ipc {
                 Response::Ok
             }
             Command::Reload => {
+                let wl = ironbar.clients.borrow_mut().wayland();
+                let outputs = wl.output_info_all();
+
+                ironbar.reload_config();
+
+                if ironbar.reload_bars_in_place(&outputs) {
+                    info!("Applied config to existing bars");
+                    return Response::Ok;
+                }
+
                 info!("Closing existing bars");
                 ironbar.bars.borrow_mut().clear();
+                ironbar.module_actions.borrow_mut().clear();
+                ironbar.module_restarts.borrow_mut().clear();
 
                 let windows = application.windows();
                 for window in windows {
                     window.close();
                 }
 
-                let wl = ironbar.clients.borrow_mut().wayland();
-                let outputs = wl.output_info_all();
-
-                ironbar.reload_config();
-
                 for output in outputs {
                     match crate::load_output_bars(ironbar, application, &output) {
                         Ok(mut bars) => ironbar.bars.borrow_mut().append(&mut bars),
@@ -151,6 +173,11 @@ impl Ipc {
             }
             Command::Var(cmd) => ironvar::handle_command(cmd),
             Command::Bar(cmd) => bar::handle_command(cmd, ironbar),
+            Command::Module(cmd) => module::handle_command(cmd, ironbar),
+            Command::Debug(cmd) => debug::handle_command(cmd, ironbar).await,
+            #[cfg(feature = "tray")]
+            Command::Tray(cmd) => tray::handle_command(cmd, application, ironbar).await,
+            Command::Action { .. } => Response::error("Actions cannot run other actions"),
         }
     }
 
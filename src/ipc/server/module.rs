@@ -0,0 +1,40 @@
+use crate::config::ClassList;
+use crate::ipc::{ModuleCommand, Response};
+use crate::Ironbar;
+use color_eyre::{Report, Result};
+use std::rc::Rc;
+
+const RESTART: &str = "restart";
+const CLASS: &str = "class";
+
+pub fn handle_command(command: ModuleCommand, ironbar: &Rc<Ironbar>) -> Response {
+    // `module restart <widget_name>` and `module class <add|remove> <widget_name> <class>`
+    // are handled here for any module,
+    // rather than being passed to ones named `restart` or `class`.
+    let result = if command.name == RESTART && command.args.is_empty() {
+        ironbar.restart_module(&command.action)
+    } else if command.name == CLASS && command.args.len() == 2 {
+        set_class(&command.action, &command.args[0], &command.args[1], ironbar)
+    } else {
+        ironbar.module_action(&command.name, &command.action, &command.args)
+    };
+
+    match result {
+        Ok(()) => Response::Ok,
+        Err(err) => Response::error(&format!("{err}")),
+    }
+}
+
+fn set_class(action: &str, name: &str, class: &str, ironbar: &Ironbar) -> Result<()> {
+    let enabled = match action {
+        "add" => true,
+        "remove" => false,
+        _ => {
+            return Err(Report::msg(format!(
+                "Invalid class action '{action}', expected 'add' or 'remove'"
+            )))
+        }
+    };
+
+    ironbar.set_module_class(name, &ClassList::Single(class.to_string()), enabled)
+}
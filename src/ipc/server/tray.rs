@@ -0,0 +1,76 @@
+use crate::clients::tray::{self, ItemInfo};
+use crate::ipc::commands::TrayCommand;
+use crate::ipc::Response;
+use crate::{modules, Ironbar};
+use gtk::Application;
+use std::rc::Rc;
+
+pub async fn handle_command(
+    command: TrayCommand,
+    application: &Application,
+    ironbar: &Rc<Ironbar>,
+) -> Response {
+    let client = match ironbar.clients.borrow_mut().tray() {
+        Ok(client) => client,
+        Err(err) => return Response::error(&format!("{err}")),
+    };
+
+    let items = tray::list_items(&client).await;
+
+    let id = match &command {
+        TrayCommand::List => {
+            return match serde_json::to_string(&items) {
+                Ok(value) => Response::OkValue { value },
+                Err(err) => Response::error(&format!("{err}")),
+            };
+        }
+        TrayCommand::Activate { id }
+        | TrayCommand::SecondaryActivate { id }
+        | TrayCommand::ContextMenu { id } => id,
+    };
+
+    let Some(item) = items.iter().find(|item| &item.id == id) else {
+        return Response::error(&unknown_item(id, &items));
+    };
+
+    let result = match &command {
+        TrayCommand::Activate { .. } => tray::activate(&item.id, false).await,
+        TrayCommand::SecondaryActivate { .. } => tray::activate(&item.id, true).await,
+        TrayCommand::ContextMenu { .. } => {
+            if !item.has_menu {
+                return Response::error("Item has no menu");
+            }
+
+            if !modules::tray::open_menu(application, &item.address) {
+                return Response::error("Item is not shown on any bar");
+            }
+
+            Ok(())
+        }
+        TrayCommand::List => unreachable!(), // handled above
+    };
+
+    match result {
+        Ok(()) => Response::Ok,
+        Err(err) => Response::error(&format!("{err}")),
+    }
+}
+
+/// Creates the error message for an unknown ID,
+/// listing the current items.
+fn unknown_item(id: &str, items: &[ItemInfo]) -> String {
+    if items.is_empty() {
+        return format!("Tray item '{id}' not found. There are no tray items.");
+    }
+
+    let items = items
+        .iter()
+        .map(|item| {
+            let name = item.title.as_deref().unwrap_or(&item.app_id);
+            format!("  {} ({name})", item.id)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("Tray item '{id}' not found. Current items:\n{items}")
+}
@@ -1,13 +1,20 @@
 #![doc = include_str!("../docs/Ironvars.md")]
 
+use crate::dynamic_value::Expression;
 use crate::send;
 use color_eyre::{Report, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tokio::sync::broadcast;
+use tracing::debug;
 
 /// Global singleton manager for `IronVar` variables.
 pub struct VariableManager {
     variables: HashMap<Box<str>, IronVar>,
+    /// Sends the key of any variable when it is set.
+    changes: broadcast::Sender<Box<str>>,
+    _changes_rx: broadcast::Receiver<Box<str>>,
+    /// Variables calculated from others, by key.
+    derived: HashMap<Box<str>, Expression>,
 }
 
 impl Default for VariableManager {
@@ -18,26 +25,96 @@ impl Default for VariableManager {
 
 impl VariableManager {
     pub fn new() -> Self {
+        let (changes, changes_rx) = broadcast::channel(32);
+
         Self {
             variables: HashMap::new(),
+            changes,
+            _changes_rx: changes_rx,
+            derived: HashMap::new(),
         }
     }
 
     /// Sets the value for a variable,
     /// creating it if it does not exist.
+    ///
+    /// Derived variables cannot be set.
     pub fn set(&mut self, key: Box<str>, value: String) -> Result<()> {
-        if Self::key_is_valid(&key) {
-            if let Some(var) = self.variables.get_mut(&key) {
-                var.set(Some(value));
-            } else {
-                let var = IronVar::new(Some(value));
-                self.variables.insert(key, var);
-            }
+        self.set_many(vec![(key, value)])
+    }
 
-            Ok(())
+    /// Sets the values for several variables at once,
+    /// creating any which do not exist.
+    ///
+    /// Every value is set before any subscribers are notified,
+    /// so reading another variable from the batch during a notification
+    /// always gives its new value.
+    ///
+    /// If any key is invalid or derived, no variables are set.
+    pub fn set_many(&mut self, values: Vec<(Box<str>, String)>) -> Result<()> {
+        if let Some((key, _)) = values
+            .iter()
+            .find(|(key, _)| self.derived.contains_key(key))
+        {
+            return Err(Report::msg(format!(
+                "'{key}' is a derived variable, and cannot be set"
+            )));
+        }
+
+        self.set_values(values)
+    }
+
+    /// Sets the variable only if its current value is `expected`.
+    /// An unset variable matches an empty string.
+    ///
+    /// Returns the current value if it does not match,
+    /// in which case nothing is set.
+    pub fn compare_and_set(
+        &mut self,
+        key: Box<str>,
+        expected: &str,
+        value: String,
+    ) -> Result<CompareAndSet> {
+        let current = self.get(&key);
+
+        if current.as_deref().unwrap_or_default() == expected {
+            self.set(key, value)?;
+            Ok(CompareAndSet::Set)
         } else {
-            Err(Report::msg("Invalid key"))
+            Ok(CompareAndSet::Mismatch(current))
+        }
+    }
+
+    fn set_values(&mut self, values: Vec<(Box<str>, String)>) -> Result<()> {
+        if let Some((key, _)) = values.iter().find(|(key, _)| !Self::key_is_valid(key)) {
+            return Err(Report::msg(format!("Invalid key '{key}'")));
         }
+
+        let mut keys = Vec::<Box<str>>::with_capacity(values.len());
+        for (key, _) in &values {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+
+        for (key, value) in values {
+            self.variables
+                .entry(key)
+                .or_insert_with(|| IronVar::new(None))
+                .value = Some(value);
+        }
+
+        for key in &keys {
+            if let Some(var) = self.variables.get(key) {
+                var.notify();
+            }
+
+            send!(self.changes, key.clone());
+        }
+
+        self.update_dependents(&keys);
+
+        Ok(())
     }
 
     /// Gets the current value of an `ironvar`.
@@ -59,6 +136,85 @@ impl VariableManager {
             .subscribe()
     }
 
+    /// Subscribes to every `ironvar`.
+    /// Any time a var is set, its key is sent on the channel.
+    pub fn subscribe_all(&self) -> broadcast::Receiver<Box<str>> {
+        self.changes.subscribe()
+    }
+
+    /// Replaces the derived variables with these expressions, by key,
+    /// and evaluates each one.
+    ///
+    /// Returns an error naming the variables if any expression is invalid
+    /// or if the variables depend on each other in a cycle,
+    /// in which case no variables are derived.
+    pub fn set_derived(&mut self, derived: HashMap<Box<str>, String>) -> Result<()> {
+        self.derived.clear();
+
+        let mut expressions = HashMap::new();
+        for (key, input) in derived {
+            if !Self::key_is_valid(&key) {
+                return Err(Report::msg(format!("Invalid derived ironvar key '{key}'")));
+            }
+
+            let expression = Expression::parse(&input)
+                .map_err(|err| Report::msg(format!("Invalid derived ironvar '{key}': {err}")))?;
+
+            expressions.insert(key, expression);
+        }
+
+        let dependencies = expressions
+            .iter()
+            .map(|(key, expression)| (key.clone(), expression.variables().to_vec()))
+            .collect();
+
+        let order = evaluation_order(&dependencies).map_err(|cycle| {
+            Report::msg(format!(
+                "Derived ironvars depend on each other in a cycle: {}",
+                cycle.join(" -> ")
+            ))
+        })?;
+
+        self.derived = expressions;
+
+        for key in order {
+            self.evaluate(key);
+        }
+
+        Ok(())
+    }
+
+    /// Re-evaluates each derived variable referencing any of the keys.
+    /// Each is evaluated once, after all the keys are set.
+    fn update_dependents(&mut self, keys: &[Box<str>]) {
+        let dependents = self
+            .derived
+            .iter()
+            .filter(|(_, expression)| expression.variables().iter().any(|var| keys.contains(var)))
+            .map(|(dependent, _)| dependent.clone())
+            .collect::<Vec<_>>();
+
+        for dependent in dependents {
+            self.evaluate(dependent);
+        }
+    }
+
+    /// Evaluates a derived variable, setting its value.
+    /// Failures such as a referenced variable being unset leave it unchanged.
+    fn evaluate(&mut self, key: Box<str>) {
+        let Some(expression) = self.derived.get(&key) else {
+            return;
+        };
+
+        match expression.evaluate(&|name| self.get(name)) {
+            Ok(value) => {
+                // the key is already checked, so this cannot fail
+                let _ = self.set_values(vec![(key, value.to_string())]);
+            }
+            Err(err) => debug!("Not updating derived ironvar '{key}': {err}"),
+        }
+    }
+
     fn key_is_valid(key: &str) -> bool {
         !key.is_empty()
             && key
@@ -67,6 +223,70 @@ impl VariableManager {
     }
 }
 
+/// Orders the derived variables so that each comes after those it depends on.
+///
+/// If they depend on each other in a cycle,
+/// the keys in the cycle are returned instead, starting and ending with the same key.
+fn evaluation_order(
+    dependencies: &HashMap<Box<str>, Vec<Box<str>>>,
+) -> std::result::Result<Vec<Box<str>>, Vec<Box<str>>> {
+    fn visit(
+        key: &str,
+        dependencies: &HashMap<Box<str>, Vec<Box<str>>>,
+        path: &mut Vec<Box<str>>,
+        done: &mut HashSet<Box<str>>,
+        order: &mut Vec<Box<str>>,
+    ) -> std::result::Result<(), Vec<Box<str>>> {
+        if done.contains(key) {
+            return Ok(());
+        }
+
+        if let Some(start) = path.iter().position(|visiting| &**visiting == key) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(key.into());
+            return Err(cycle);
+        }
+
+        // plain variables have no dependencies of their own
+        let Some(keys) = dependencies.get(key) else {
+            return Ok(());
+        };
+
+        path.push(key.into());
+        for dependency in keys {
+            visit(dependency, dependencies, path, done, order)?;
+        }
+        path.pop();
+
+        done.insert(key.into());
+        order.push(key.into());
+
+        Ok(())
+    }
+
+    // sorted so that the same cycle is always reported
+    let mut keys = dependencies.keys().collect::<Vec<_>>();
+    keys.sort();
+
+    let mut order = vec![];
+    let mut done = HashSet::new();
+
+    for key in keys {
+        visit(key, dependencies, &mut vec![], &mut done, &mut order)?;
+    }
+
+    Ok(order)
+}
+
+/// The outcome of [`VariableManager::compare_and_set`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareAndSet {
+    /// The value matched, and the new value was set.
+    Set,
+    /// The value did not match. Contains the current value.
+    Mismatch(Option<String>),
+}
+
 /// Ironbar dynamic variable representation.
 /// Interact with them through the `VARIABLE_MANAGER` `VariableManager` singleton.
 #[derive(Debug)]
@@ -90,11 +310,9 @@ impl IronVar {
         self.value.clone()
     }
 
-    /// Sets the current variable value.
-    /// The change is broadcast to all receivers.
-    fn set(&mut self, value: Option<String>) {
-        self.value.clone_from(&value);
-        send!(self.tx, value);
+    /// Broadcasts the current variable value to all receivers.
+    fn notify(&self) {
+        send!(self.tx, self.value.clone());
     }
 
     /// Subscribes to the variable.
@@ -105,3 +323,82 @@ impl IronVar {
         rx
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies(entries: &[(&str, &[&str])]) -> HashMap<Box<str>, Vec<Box<str>>> {
+        entries
+            .iter()
+            .map(|(key, deps)| ((*key).into(), deps.iter().map(|&dep| dep.into()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn sets_batches_before_notifying() {
+        let mut manager = VariableManager::new();
+        manager.set("a".into(), String::from("1")).unwrap();
+        manager.set("b".into(), String::from("1")).unwrap();
+        manager
+            .set_derived(HashMap::from([("sum".into(), String::from("#a + #b"))]))
+            .unwrap();
+
+        let mut sum = manager.subscribe("sum".into());
+        assert_eq!(sum.try_recv().unwrap().as_deref(), Some("2"));
+
+        manager
+            .set_many(vec![
+                ("a".into(), String::from("2")),
+                ("b".into(), String::from("3")),
+            ])
+            .unwrap();
+
+        // the derived value is only updated once, with both new values
+        assert_eq!(sum.try_recv().unwrap().as_deref(), Some("5"));
+        assert!(sum.try_recv().is_err());
+
+        let err = manager
+            .set_many(vec![
+                ("a".into(), String::from("4")),
+                ("sum".into(), String::from("4")),
+            ])
+            .unwrap_err();
+        assert!(err.to_string().contains("derived"));
+        assert_eq!(manager.get("a").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn compares_before_setting() {
+        let mut manager = VariableManager::new();
+
+        let result = manager.compare_and_set("dnd".into(), "", String::from("on"));
+        assert_eq!(result.unwrap(), CompareAndSet::Set);
+
+        let result = manager.compare_and_set("dnd".into(), "off", String::from("on"));
+        assert_eq!(
+            result.unwrap(),
+            CompareAndSet::Mismatch(Some(String::from("on")))
+        );
+        assert_eq!(manager.get("dnd").as_deref(), Some("on"));
+    }
+
+    #[test]
+    fn orders_derived_after_dependencies() {
+        let deps = dependencies(&[("c", &["b", "plain"]), ("b", &["a"]), ("a", &["plain"])]);
+        let order = evaluation_order(&deps).unwrap();
+
+        assert_eq!(order, [Box::from("a"), Box::from("b"), Box::from("c")]);
+    }
+
+    #[test]
+    fn finds_dependency_cycles() {
+        let deps = dependencies(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"]), ("d", &[])]);
+        let cycle = evaluation_order(&deps).unwrap_err();
+
+        assert_eq!(cycle.join(" -> "), "a -> b -> c -> a");
+
+        let deps = dependencies(&[("a", &["a"])]);
+        assert_eq!(evaluation_order(&deps).unwrap_err().join(" -> "), "a -> a");
+    }
+}
@@ -1,8 +1,11 @@
+#[cfg(feature = "error_notifications")]
+mod notify;
+
 use color_eyre::Result;
 use dirs::data_dir;
 use std::{env, panic};
 use strip_ansi_escapes::Writer;
-use tracing::error;
+use tracing::{error, error_span, Span};
 use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
 use tracing_appender::rolling::Rotation;
 use tracing_error::ErrorLayer;
@@ -10,6 +13,23 @@ use tracing_subscriber::fmt::{Layer, MakeWriter};
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::{fmt, EnvFilter};
 
+#[cfg(feature = "error_notifications")]
+pub use notify::configure as configure_notifications;
+
+/// The name of the span each module's tasks run in.
+/// This must match the name used in [`module_span`].
+#[cfg(feature = "error_notifications")]
+const MODULE_SPAN: &str = "module";
+
+/// Creates the span for a module instance's tasks to run in,
+/// so that events they log can be traced back to it.
+///
+/// This is created at error level,
+/// so that it is not disabled by stricter log filters.
+pub fn module_span(name: &str) -> Span {
+    error_span!("module", name)
+}
+
 struct MakeFileWriter {
     file_writer: NonBlocking,
 }
@@ -77,7 +97,7 @@ fn install_tracing() -> Result<WorkerGuard> {
 
     let (file_writer, guard) = tracing_appender::non_blocking(appender);
 
-    tracing_subscriber::registry()
+    let registry = tracing_subscriber::registry()
         .with(filter_layer)
         .with(fmt_layer)
         .with(ErrorLayer::default())
@@ -86,8 +106,12 @@ fn install_tracing() -> Result<WorkerGuard> {
                 .with_writer(MakeFileWriter::new(file_writer))
                 .with_ansi(false)
                 .with_filter(file_filter_layer),
-        )
-        .init();
+        );
+
+    #[cfg(feature = "error_notifications")]
+    let registry = registry.with(notify::NotifyLayer);
+
+    registry.init();
 
     Ok(guard)
 }
@@ -0,0 +1,507 @@
+use crate::duration::{format_duration, DurationStyle};
+use crate::spawn;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::pending;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::time::sleep_until;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{error, Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use zbus::zvariant::Value;
+
+#[zbus::dbus_proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The minimum number of seconds between notifications for each source.
+static INTERVAL: AtomicU64 = AtomicU64::new(600);
+
+/// Sends records to the notifier task, once it has been started.
+static TX: OnceLock<mpsc::UnboundedSender<Record>> = OnceLock::new();
+
+/// Enables or disables desktop notifications for warnings and errors,
+/// sending at most one per source every `interval_minutes`.
+///
+/// The notifier task is started the first time this enables them,
+/// and keeps running if they are disabled again.
+pub fn configure(enabled: bool, interval_minutes: u64) {
+    INTERVAL.store(interval_minutes.max(1) * 60, Ordering::Relaxed);
+    ENABLED.store(enabled, Ordering::Relaxed);
+
+    if enabled && TX.get().is_none() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        if TX.set(tx).is_ok() {
+            spawn(run(rx));
+        }
+    }
+}
+
+/// The module instance name stored on each module span.
+struct ModuleName(String);
+
+/// A tracing layer which forwards warnings and errors
+/// to the notifier task while notifications are enabled.
+pub struct NotifyLayer;
+
+impl<S> tracing_subscriber::Layer<S> for NotifyLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != super::MODULE_SPAN {
+            return;
+        }
+
+        let mut visitor = FieldVisitor::new("name");
+        attrs.record(&mut visitor);
+
+        if let (Some(name), Some(span)) = (visitor.value, ctx.span(id)) {
+            span.extensions_mut().insert(ModuleName(name));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let metadata = event.metadata();
+        let target = metadata.target();
+
+        // events from dependencies are not ours to report,
+        // and our own would loop if sending fails
+        if *metadata.level() > Level::WARN
+            || !target.starts_with("ironbar")
+            || target.starts_with(module_path!())
+        {
+            return;
+        }
+
+        let Some(tx) = TX.get() else {
+            return;
+        };
+
+        let module = ctx.event_scope(event).and_then(|scope| {
+            scope.from_root().find_map(|span| {
+                span.extensions()
+                    .get::<ModuleName>()
+                    .map(|name| name.0.clone())
+            })
+        });
+
+        let mut visitor = FieldVisitor::new("message");
+        event.record(&mut visitor);
+
+        tx.send(Record {
+            source: Source::identify(module.as_deref(), target),
+            level: *metadata.level(),
+            // reports are coloured for the terminal
+            message: strip_ansi_escapes::strip_str(visitor.value.unwrap_or_default()),
+        })
+        .ok();
+    }
+}
+
+/// Reads a single field from a span or event.
+struct FieldVisitor {
+    name: &'static str,
+    value: Option<String>,
+}
+
+impl FieldVisitor {
+    const fn new(name: &'static str) -> Self {
+        Self { name, value: None }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == self.name {
+            self.value = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == self.name {
+            self.value = Some(format!("{value:?}"));
+        }
+    }
+}
+
+/// What logged an event,
+/// so that the notification says what needs fixing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Source {
+    /// A module instance, by its name.
+    Module(String),
+    /// A shared client, such as `volume` or `tray`.
+    Client(String),
+    /// Anything else, by its Rust module path.
+    Other(String),
+}
+
+impl Source {
+    fn identify(module: Option<&str>, target: &str) -> Self {
+        if let Some(name) = module {
+            return Self::Module(name.to_string());
+        }
+
+        let path = target.strip_prefix("ironbar::").unwrap_or(target);
+
+        match path.strip_prefix("clients::") {
+            Some(client) => Self::Client(client.split("::").next().unwrap_or(client).to_string()),
+            None => Self::Other(path.to_string()),
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Module(name) => write!(f, "module '{name}'"),
+            Self::Client(name) => write!(f, "client '{name}'"),
+            Self::Other(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Record {
+    source: Source,
+    level: Level,
+    message: String,
+}
+
+#[derive(Debug, PartialEq)]
+struct Notification {
+    source: Source,
+    level: Level,
+    /// The number of events this covers.
+    count: u32,
+    /// The time since the source was last notified for,
+    /// if this covers more than one event.
+    period: Option<Duration>,
+    /// The most recent message.
+    message: String,
+}
+
+impl Notification {
+    fn summary(&self) -> String {
+        format!("Ironbar: {}", self.source)
+    }
+
+    fn body(&self) -> String {
+        let kind = if self.level == Level::ERROR {
+            "errors"
+        } else {
+            "warnings"
+        };
+
+        match self.period {
+            Some(period) if self.count > 1 => format!(
+                "{} {kind} in the last {}, most recently:\n{}",
+                self.count,
+                format_duration(period.as_secs() as i64, DurationStyle::Verbose),
+                self.message
+            ),
+            _ => self.message.clone(),
+        }
+    }
+
+    fn icon(&self) -> &'static str {
+        if self.level == Level::ERROR {
+            "dialog-error"
+        } else {
+            "dialog-warning"
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    last_sent: Instant,
+    /// The number of events since the last notification.
+    count: u32,
+    /// The most severe level since the last notification.
+    level: Level,
+    message: String,
+}
+
+/// Rate-limits notifications for each source,
+/// counting the events between them.
+#[derive(Debug)]
+struct Aggregator {
+    interval: Duration,
+    sources: HashMap<Source, Pending>,
+}
+
+impl Aggregator {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Records an event, returning a notification for it
+    /// if its source has not been notified for within the interval.
+    /// Otherwise, it is counted towards the next.
+    fn record(&mut self, record: Record, now: Instant) -> Option<Notification> {
+        match self.sources.get_mut(&record.source) {
+            Some(pending)
+                if pending.count > 0 || now.duration_since(pending.last_sent) < self.interval =>
+            {
+                if pending.count == 0 || record.level < pending.level {
+                    pending.level = record.level;
+                }
+
+                pending.count += 1;
+                pending.message = record.message;
+                None
+            }
+            _ => {
+                self.sources.insert(
+                    record.source.clone(),
+                    Pending {
+                        last_sent: now,
+                        count: 0,
+                        level: record.level,
+                        message: String::new(),
+                    },
+                );
+
+                Some(Notification {
+                    source: record.source,
+                    level: record.level,
+                    count: 1,
+                    period: None,
+                    message: record.message,
+                })
+            }
+        }
+    }
+
+    /// Gets the time the next counted events are due to be notified for.
+    fn next_due(&self) -> Option<Instant> {
+        self.sources
+            .values()
+            .filter(|pending| pending.count > 0)
+            .map(|pending| pending.last_sent + self.interval)
+            .min()
+    }
+
+    /// Gets a notification for each source with counted events
+    /// which has not been notified for within the interval.
+    ///
+    /// Sources which have been quiet for the interval are forgotten.
+    fn flush(&mut self, now: Instant) -> Vec<Notification> {
+        let interval = self.interval;
+        let mut notifications = vec![];
+
+        self.sources.retain(|source, pending| {
+            let elapsed = now.duration_since(pending.last_sent);
+            if elapsed < interval {
+                return true;
+            }
+
+            if pending.count == 0 {
+                return false;
+            }
+
+            notifications.push(Notification {
+                source: source.clone(),
+                level: pending.level,
+                count: pending.count,
+                period: Some(elapsed),
+                message: std::mem::take(&mut pending.message),
+            });
+
+            pending.last_sent = now;
+            pending.count = 0;
+            true
+        });
+
+        notifications
+    }
+}
+
+async fn run(mut rx: mpsc::UnboundedReceiver<Record>) {
+    let proxy = match connect().await {
+        Ok(proxy) => proxy,
+        Err(err) => {
+            error!("Failed to connect to notification server: {err:?}");
+            return;
+        }
+    };
+
+    let mut aggregator = Aggregator::new(Duration::from_secs(INTERVAL.load(Ordering::Relaxed)));
+
+    // notifications from the same source replace the last
+    let mut ids = HashMap::<Source, u32>::new();
+
+    loop {
+        let next = aggregator.next_due();
+
+        let sleep = async {
+            match next {
+                Some(next) => sleep_until(next.into()).await,
+                None => pending().await,
+            }
+        };
+
+        let notifications = tokio::select! {
+            Some(record) = rx.recv() => {
+                aggregator.interval = Duration::from_secs(INTERVAL.load(Ordering::Relaxed));
+                aggregator.record(record, Instant::now()).into_iter().collect()
+            },
+            () = sleep => aggregator.flush(Instant::now()),
+            else => break,
+        };
+
+        if !ENABLED.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        for notification in notifications {
+            let replaces_id = ids.get(&notification.source).copied().unwrap_or_default();
+
+            match proxy
+                .notify(
+                    "Ironbar",
+                    replaces_id,
+                    notification.icon(),
+                    &notification.summary(),
+                    &notification.body(),
+                    &[],
+                    HashMap::new(),
+                    -1,
+                )
+                .await
+            {
+                Ok(id) => {
+                    ids.insert(notification.source, id);
+                }
+                Err(err) => error!("Failed to send error notification: {err:?}"),
+            }
+        }
+    }
+}
+
+async fn connect() -> zbus::Result<NotificationsProxy<'static>> {
+    let dbus = Box::pin(zbus::Connection::session()).await?;
+    NotificationsProxy::new(&dbus).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(source: &str, level: Level, message: &str) -> Record {
+        Record {
+            source: Source::Module(source.to_string()),
+            level,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn identifies_sources() {
+        assert_eq!(
+            Source::identify(Some("weather"), "ironbar::script"),
+            Source::Module(String::from("weather"))
+        );
+        assert_eq!(
+            Source::identify(None, "ironbar::clients::volume::sink"),
+            Source::Client(String::from("volume"))
+        );
+        assert_eq!(
+            Source::identify(None, "ironbar::clients::upower"),
+            Source::Client(String::from("upower"))
+        );
+        assert_eq!(
+            Source::identify(None, "ironbar::config"),
+            Source::Other(String::from("config"))
+        );
+    }
+
+    #[test]
+    fn aggregates_events_per_source() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(600);
+        let mut aggregator = Aggregator::new(interval);
+
+        let first = aggregator.record(record("weather", Level::WARN, "failed"), start);
+        assert_eq!(first.map(|n| n.count), Some(1));
+
+        // other sources are not held back
+        assert!(aggregator
+            .record(record("clock", Level::ERROR, "bad format"), start)
+            .is_some());
+
+        for i in 0..13 {
+            let now = start + Duration::from_secs(i * 10);
+            assert!(aggregator
+                .record(record("weather", Level::WARN, "failed again"), now)
+                .is_none());
+        }
+
+        assert!(aggregator
+            .record(record("weather", Level::ERROR, "failed badly"), start)
+            .is_none());
+
+        assert_eq!(aggregator.next_due(), Some(start + interval));
+        assert!(aggregator.flush(start + Duration::from_secs(60)).is_empty());
+
+        let notifications = aggregator.flush(start + interval);
+        assert_eq!(
+            notifications,
+            [Notification {
+                source: Source::Module(String::from("weather")),
+                level: Level::ERROR,
+                count: 14,
+                period: Some(interval),
+                message: String::from("failed badly"),
+            }]
+        );
+        assert!(notifications[0]
+            .body()
+            .starts_with("14 errors in the last "));
+        assert!(notifications[0]
+            .body()
+            .ends_with("most recently:\nfailed badly"));
+
+        // quiet sources are forgotten, so notify straight away next time
+        assert!(aggregator.flush(start + interval * 2).is_empty());
+        assert_eq!(aggregator.next_due(), None);
+        assert!(aggregator
+            .record(
+                record("weather", Level::WARN, "failed"),
+                start + interval * 2
+            )
+            .is_some());
+    }
+}
@@ -30,10 +30,19 @@ use universal_config::ConfigLoader;
 use crate::bar::{create_bar, Bar};
 use crate::clients::wayland::OutputEventType;
 use crate::clients::Clients;
-use crate::config::{Config, MonitorConfig};
+#[cfg(feature = "ipc")]
+use crate::config::ClassList;
+use crate::config::{BarConfig, Config, MonitorConfig};
 use crate::error::ExitCode;
 #[cfg(feature = "ipc")]
+use crate::gtk_helpers::IronbarGtkExt;
+#[cfg(feature = "ipc")]
 use crate::ironvar::VariableManager;
+#[cfg(feature = "ipc")]
+use crate::modules::boundary::ModuleRestartHandler;
+#[cfg(feature = "ipc")]
+use crate::modules::ModuleActionHandler;
+use crate::popup::ReservedEdges;
 use crate::style::load_css;
 
 mod bar;
@@ -42,9 +51,23 @@ mod cli;
 mod clients;
 mod config;
 mod desktop_file;
+mod diagnostics;
+#[cfg(any(
+    feature = "clock",
+    feature = "error_notifications",
+    feature = "music",
+    feature = "sys_info",
+    feature = "upower"
+))]
+mod duration;
 mod dynamic_value;
 mod error;
+#[cfg(feature = "export")]
+mod export;
+#[cfg(any(feature = "clipboard", feature = "workspaces"))]
+mod glob;
 mod gtk_helpers;
+mod i18n;
 mod image;
 #[cfg(feature = "ipc")]
 mod ipc;
@@ -53,8 +76,24 @@ mod ironvar;
 mod logging;
 mod macros;
 mod modules;
+mod overflow;
 mod popup;
 mod script;
+#[cfg(any(
+    feature = "brightness",
+    feature = "clock",
+    feature = "volume",
+    feature = "workspaces"
+))]
+mod scroll;
+#[cfg(any(
+    feature = "clipboard",
+    feature = "clock",
+    feature = "export",
+    feature = "launcher",
+    feature = "tray"
+))]
+mod state;
 mod style;
 
 pub const APP_ID: &str = "dev.jstanger.ironbar";
@@ -114,17 +153,30 @@ pub struct Ironbar {
     clients: Rc<RefCell<Clients>>,
     config: Rc<RefCell<Config>>,
     config_dir: PathBuf,
+    #[cfg(feature = "ipc")]
+    module_actions: Rc<RefCell<Vec<ModuleActionHandler>>>,
+    #[cfg(feature = "ipc")]
+    module_restarts: Rc<RefCell<Vec<ModuleRestartHandler>>>,
+    #[cfg(feature = "workspaces")]
+    output_focus_watched: std::cell::Cell<bool>,
 }
 
 impl Ironbar {
     fn new() -> Self {
         let (config, config_dir) = load_config();
+        diagnostics::init(config.diagnostics);
 
         Self {
             bars: Rc::new(RefCell::new(vec![])),
             clients: Rc::new(RefCell::new(Clients::new())),
             config: Rc::new(RefCell::new(config)),
             config_dir,
+            #[cfg(feature = "ipc")]
+            module_actions: Rc::new(RefCell::new(vec![])),
+            #[cfg(feature = "ipc")]
+            module_restarts: Rc::new(RefCell::new(vec![])),
+            #[cfg(feature = "workspaces")]
+            output_focus_watched: std::cell::Cell::new(false),
         }
     }
 
@@ -162,6 +214,11 @@ impl Ironbar {
                 }
             }
 
+            #[cfg(feature = "export")]
+            if let Some(config) = instance.config.borrow().export.clone() {
+                export::start(config, &mut instance.clients.borrow_mut());
+            }
+
             let style_path = env::var("IRONBAR_CSS").ok().map_or_else(
                 || {
                     config_dir().map_or_else(
@@ -215,7 +272,15 @@ impl Ironbar {
                         OutputEventType::New => {
                             match load_output_bars(&instance, &app, &event.output) {
                                 Ok(mut new_bars) => {
+                                    #[cfg(feature = "workspaces")]
+                                    let dim_unfocused = new_bars.iter().any(Bar::dim_unfocused);
+
                                     instance.bars.borrow_mut().append(&mut new_bars);
+
+                                    #[cfg(feature = "workspaces")]
+                                    if dim_unfocused {
+                                        instance.watch_output_focus();
+                                    }
                                 }
                                 Err(err) => error!("{err:?}"),
                             }
@@ -229,7 +294,19 @@ impl Ironbar {
                                 .borrow_mut()
                                 .retain(|bar| bar.monitor_name() != name);
                         }
-                        OutputEventType::Update => {}
+                        OutputEventType::Update => {
+                            let (Some(name), Some((width, height))) =
+                                (event.output.name, event.output.logical_size)
+                            else {
+                                continue;
+                            };
+
+                            for bar in instance.bars.borrow().iter() {
+                                if bar.monitor_name() == name {
+                                    bar.set_output_size(width, height);
+                                }
+                            }
+                        }
                     }
                 }
             });
@@ -278,12 +355,213 @@ impl Ironbar {
             .cloned()
     }
 
+    /// Gets the space reserved at each edge of the output
+    /// by the exclusive zones of its bars, other than the named bar.
+    #[must_use]
+    pub fn reserved_edges(&self, output_name: &str, bar_name: &str) -> ReservedEdges {
+        let mut reserved = ReservedEdges::default();
+
+        // the bars may be being replaced, in which case nothing is reserved yet
+        let Ok(bars) = self.bars.try_borrow() else {
+            return reserved;
+        };
+
+        for bar in bars
+            .iter()
+            .filter(|bar| bar.monitor_name() == output_name && bar.name() != bar_name)
+        {
+            reserved.reserve(bar.position(), bar.exclusive_zone());
+        }
+
+        reserved
+    }
+
+    /// Sends an action to the first live module instance with the given name.
+    ///
+    /// Handlers for modules which no longer exist are discarded.
+    #[cfg(feature = "ipc")]
+    pub fn module_action(&self, name: &str, action: &str, args: &[String]) -> Result<()> {
+        let mut handlers = self.module_actions.borrow_mut();
+        handlers.retain(ModuleActionHandler::is_alive);
+
+        handlers
+            .iter()
+            .find(|handler| handler.name == name)
+            .ok_or_else(|| Report::msg("Invalid module name"))?
+            .handle(action, args)
+    }
+
+    /// Adds or removes CSS classes on every live module instance with the given name,
+    /// such as the same module on each output.
+    ///
+    /// Classes are reset to the config's if the module is rebuilt.
+    #[cfg(feature = "ipc")]
+    pub fn set_module_class(&self, name: &str, class: &ClassList, enabled: bool) -> Result<()> {
+        let mut handlers = self.module_actions.borrow_mut();
+        handlers.retain(ModuleActionHandler::is_alive);
+
+        let widgets = handlers
+            .iter()
+            .filter(|handler| handler.name == name)
+            .filter_map(ModuleActionHandler::widget)
+            .collect::<Vec<_>>();
+
+        if widgets.is_empty() {
+            return Err(Report::msg("Invalid module name"));
+        }
+
+        for widget in widgets {
+            for class in class.iter() {
+                widget.set_class(class, enabled);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds the first live module instance with the given name in place.
+    ///
+    /// The old instance's controller tasks are stopped
+    /// and its handlers are discarded.
+    #[cfg(feature = "ipc")]
+    pub fn restart_module(&self, name: &str) -> Result<()> {
+        let handler = {
+            let mut handlers = self.module_restarts.borrow_mut();
+            handlers.retain(ModuleRestartHandler::is_alive);
+
+            let pos = handlers
+                .iter()
+                .position(|handler| handler.name == name)
+                .ok_or_else(|| Report::msg("Invalid module name"))?;
+
+            handlers.remove(pos)
+        };
+
+        if let Some(widget) = handler.widget() {
+            self.module_actions
+                .borrow_mut()
+                .retain(|action| !action.is_inside(&widget));
+
+            // nested modules are rebuilt along with their parent
+            self.module_restarts.borrow_mut().retain(|restart| {
+                let nested = restart.is_inside(&widget);
+                if nested {
+                    restart.unregister();
+                }
+                !nested
+            });
+        }
+
+        info!("Restarting module '{name}'");
+
+        // re-check its `load_if` condition in case it has changed
+        config::clear_command_results();
+        handler.restart()
+    }
+
+    /// Starts tracking which output has focus,
+    /// updating every bar when it changes.
+    ///
+    /// If already tracking, the current state is re-applied,
+    /// so that newly created bars are updated.
+    #[cfg(feature = "workspaces")]
+    fn watch_output_focus(self: &Rc<Self>) {
+        let output_focus = match self.clients.borrow_mut().output_focus() {
+            Ok(output_focus) => output_focus,
+            Err(err) => {
+                error!("{:?}", err.wrap_err("Failed to track focused output"));
+                return;
+            }
+        };
+
+        if self.output_focus_watched.replace(true) {
+            self.apply_output_focus(output_focus.current().as_deref());
+            return;
+        }
+
+        let mut rx = output_focus.subscribe();
+        let instance = self.clone();
+
+        glib::spawn_future_local(async move {
+            loop {
+                let focused = rx.borrow_and_update().clone();
+                instance.apply_output_focus(focused.as_deref());
+
+                if rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Updates every bar at once for the output which has focus.
+    #[cfg(feature = "workspaces")]
+    fn apply_output_focus(&self, focused: Option<&str>) {
+        for bar in self.bars.borrow().iter() {
+            bar.set_output_focused(Some(bar.monitor_name()) == focused);
+        }
+    }
+
     /// Re-reads the config file from disk and replaces the active config.
     /// Note this does *not* reload bars, which must be performed separately.
     #[cfg(feature = "ipc")]
     fn reload_config(&self) {
         self.config.replace(load_config().0);
     }
+
+    /// Applies the active config to the existing bars
+    /// if it only differs from theirs by options which can be changed in place,
+    /// such as padding, spacing and font.
+    ///
+    /// Returns `false` without making changes
+    /// if the bars need to be recreated instead,
+    /// or if nothing has changed.
+    #[cfg(feature = "ipc")]
+    fn reload_bars_in_place(&self, outputs: &[OutputInfo]) -> bool {
+        let config = self.config.borrow();
+        let bars = self.bars.borrow();
+
+        let mut updates = vec![];
+
+        for output in outputs {
+            let Some(monitor_name) = &output.name else {
+                return false;
+            };
+
+            let configs = output_bar_configs(&config, monitor_name);
+            let output_bars = bars
+                .iter()
+                .filter(|bar| bar.monitor_name() == monitor_name)
+                .collect::<Vec<_>>();
+
+            if configs.len() != output_bars.len() {
+                return false;
+            }
+
+            for (bar, bar_config) in output_bars.into_iter().zip(configs) {
+                if bar.structure_key() != bar_config.structure_key() {
+                    return false;
+                }
+
+                updates.push((bar, bar_config));
+            }
+        }
+
+        let unchanged = updates.iter().all(|(bar, config)| {
+            bar.has_layout(config.padding, config.spacing) && bar.has_font(config.font.as_ref())
+        });
+
+        if updates.len() != bars.len() || unchanged {
+            return false;
+        }
+
+        for (bar, bar_config) in updates {
+            bar.apply_layout(bar_config.padding, bar_config.spacing);
+            bar.apply_font(bar_config.font.as_ref());
+        }
+
+        true
+    }
 }
 
 fn start_ironbar() {
@@ -326,6 +604,23 @@ fn load_config() -> (Config, PathBuf) {
 
     debug!("Loaded config file");
 
+    config::clear_command_results();
+
+    clients::timer::set_jitter(config.timer_jitter);
+    gtk_helpers::set_cursor_style(config.cursor);
+    i18n::set_locale(config.locale.clone());
+
+    #[cfg(feature = "error_notifications")]
+    logging::configure_notifications(
+        config.error_notifications,
+        config.error_notification_interval,
+    );
+
+    #[cfg(not(feature = "error_notifications"))]
+    if config.error_notifications {
+        warn!("Error notifications are not enabled in this build");
+    }
+
     #[cfg(feature = "ipc")]
     if let Some(ironvars) = config.ironvar_defaults.take() {
         let variable_manager = Ironbar::variable_manager();
@@ -336,6 +631,21 @@ fn load_config() -> (Config, PathBuf) {
         }
     }
 
+    // always set so that derived variables removed on reload stop updating
+    #[cfg(feature = "ipc")]
+    {
+        let derived = config.derived_variables.take().unwrap_or_default();
+        if let Err(err) = write_lock!(Ironbar::variable_manager()).set_derived(derived) {
+            error!("{err:?}");
+            warn!("Ignoring derived ironvars");
+        }
+    }
+
+    #[cfg(feature = "ipc")]
+    if let Some(actions) = config.actions.as_mut() {
+        ipc::check_actions(actions);
+    }
+
     (config, directory)
 }
 
@@ -388,48 +698,73 @@ fn load_output_bars(
 
     let monitor = display.monitor(index as i32).expect("monitor to exist");
 
-    let show_default_bar =
-        config.bar.start.is_some() || config.bar.center.is_some() || config.bar.end.is_some();
-
-    let bars = match config
-        .monitors
-        .as_ref()
-        .and_then(|config| config.get(monitor_name))
-    {
-        Some(MonitorConfig::Single(config)) => {
-            vec![create_bar(
+    let bars = output_bar_configs(&config, monitor_name)
+        .into_iter()
+        .map(|config| {
+            create_bar(
                 app,
                 &monitor,
                 monitor_name.to_string(),
                 config.clone(),
                 ironbar.clone(),
-            )?]
+            )
+        })
+        .collect::<Result<_>>()?;
+
+    // only query the compositor once a dynamic string needs its state
+    #[cfg(feature = "workspaces")]
+    if clients::compositor::CompositorState::is_requested() {
+        if let Err(err) = ironbar.clients.borrow_mut().watch_compositor_state() {
+            error!("{:?}", err.wrap_err("Failed to track compositor state"));
         }
-        Some(MonitorConfig::Multiple(configs)) => configs
-            .iter()
-            .map(|config| {
-                create_bar(
-                    app,
-                    &monitor,
-                    monitor_name.to_string(),
-                    config.clone(),
-                    ironbar.clone(),
-                )
-            })
-            .collect::<Result<_>>()?,
-        None if show_default_bar => vec![create_bar(
-            app,
-            &monitor,
-            monitor_name.to_string(),
-            config.bar.clone(),
-            ironbar.clone(),
-        )?],
-        None => vec![],
-    };
+    }
 
     Ok(bars)
 }
 
+/// Gets the configs for each bar to show on the named output.
+fn output_bar_configs<'a>(config: &'a Config, monitor_name: &str) -> Vec<&'a BarConfig> {
+    let show_default_bar =
+        config.bar.start.is_some() || config.bar.center.is_some() || config.bar.end.is_some();
+
+    let monitor_config = config
+        .monitors
+        .as_ref()
+        .and_then(|config| config.get(monitor_name));
+
+    let mut configs = monitor_config.map(monitor_bar_configs).unwrap_or_default();
+
+    if show_default_bar && (monitor_config.is_none() || config.bar.clone_to_all_outputs) {
+        configs.insert(0, &config.bar);
+    }
+
+    // bars cloned from other outputs, sorted so they are always created in the same order
+    let mut other_monitors = config
+        .monitors
+        .iter()
+        .flatten()
+        .filter(|(name, _)| name.as_str() != monitor_name)
+        .collect::<Vec<_>>();
+
+    other_monitors.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    configs.extend(
+        other_monitors
+            .into_iter()
+            .flat_map(|(_, config)| monitor_bar_configs(config))
+            .filter(|config| config.clone_to_all_outputs),
+    );
+
+    configs
+}
+
+fn monitor_bar_configs(config: &MonitorConfig) -> Vec<&BarConfig> {
+    match config {
+        MonitorConfig::Single(config) => vec![config],
+        MonitorConfig::Multiple(configs) => configs.iter().collect(),
+    }
+}
+
 fn create_runtime() -> Runtime {
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -0,0 +1,397 @@
+use crate::clients::bluetooth::{self, BluetoothState, Device};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::i18n::tr;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use color_eyre::{Report, Result};
+use glib::Propagation;
+use gtk::pango::EllipsizeMode;
+use gtk::prelude::*;
+use gtk::{Button, Label, Orientation, Switch};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+const BUTTON_MIDDLE: u32 = 2;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BluetoothModule {
+    /// Adapter state icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show while the adapter is on, with no devices connected.
+    ///
+    /// **Default**: `󰂯`
+    #[serde(default = "default_icon_enabled")]
+    enabled: String,
+
+    /// Icon to show while the adapter is off.
+    ///
+    /// **Default**: `󰂲`
+    #[serde(default = "default_icon_disabled")]
+    disabled: String,
+
+    /// Icon to show while a device is connected.
+    ///
+    /// **Default**: `󰂱`
+    #[serde(default = "default_icon_connected")]
+    connected: String,
+
+    /// Icon to show when there is no adapter,
+    /// or BlueZ is not running.
+    ///
+    /// **Default**: `󰂲`
+    #[serde(default = "default_icon_disabled")]
+    no_adapter: String,
+}
+
+impl Icons {
+    fn icon(&self, state: &BluetoothState) -> &str {
+        match &state.adapter {
+            None => &self.no_adapter,
+            Some(adapter) if !adapter.powered => &self.disabled,
+            Some(_) if state.is_connected() => &self.connected,
+            Some(_) => &self.enabled,
+        }
+    }
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            enabled: default_icon_enabled(),
+            disabled: default_icon_disabled(),
+            connected: default_icon_connected(),
+            no_adapter: default_icon_disabled(),
+        }
+    }
+}
+
+fn default_icon_enabled() -> String {
+    String::from("󰂯")
+}
+
+fn default_icon_disabled() -> String {
+    String::from("󰂲")
+}
+
+fn default_icon_connected() -> String {
+    String::from("󰂱")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BluetoothCommand {
+    /// Turns the adapter on or off, or toggles it if `None`.
+    Power(Option<bool>),
+    Connect(String),
+    Disconnect(String),
+}
+
+impl Module<Button> for BluetoothModule {
+    type SendMessage = BluetoothState;
+    type ReceiveMessage = BluetoothCommand;
+
+    module_impl!("bluetooth");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+
+        let client = match context.try_client::<bluetooth::Client>() {
+            Ok(client) => client,
+            Err(err) => {
+                error!("{:?}", err.wrap_err("Failed to connect to BlueZ"));
+
+                // shown as having no adapter, rather than failing to load
+                context.spawn(async move {
+                    send_async!(tx, ModuleUpdateEvent::Update(BluetoothState::default()));
+                    while rx.recv().await.is_some() {}
+                });
+
+                return Ok(());
+            }
+        };
+
+        {
+            let tx = tx.clone();
+            let mut state = client.subscribe();
+
+            context.spawn(async move {
+                loop {
+                    let current = state.borrow_and_update().clone();
+                    send_async!(tx, ModuleUpdateEvent::Update(current));
+
+                    if state.changed().await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        context.spawn(async move {
+            while let Some(command) = rx.recv().await {
+                debug!("Received Bluetooth command: {command:?}");
+
+                let client = client.clone();
+                let tx = tx.clone();
+
+                // each command runs separately,
+                // as connecting to a device can take several seconds
+                spawn(async move {
+                    let res = match command {
+                        BluetoothCommand::Power(powered) => {
+                            let powered = powered.unwrap_or_else(|| {
+                                !client
+                                    .state()
+                                    .adapter
+                                    .is_some_and(|adapter| adapter.powered)
+                            });
+
+                            client.set_powered(powered).await
+                        }
+                        BluetoothCommand::Connect(path) => client.connect(&path).await,
+                        BluetoothCommand::Disconnect(path) => client.disconnect(&path).await,
+                    };
+
+                    if let Err(err) = res {
+                        error!("{:?}", err.wrap_err("Bluetooth command failed"));
+                    }
+
+                    // buttons waiting on the command are reset from the state,
+                    // which may not have changed if it failed.
+                    // the module may have been reloaded while the command ran.
+                    tx.send(ModuleUpdateEvent::Update(client.state()))
+                        .await
+                        .ok();
+                });
+            }
+        });
+
+        Ok(())
+    }
+
+    fn parse_action(action: &str, _args: &[String]) -> Result<Self::ReceiveMessage> {
+        match action {
+            "power-toggle" => Ok(BluetoothCommand::Power(None)),
+            "power-on" => Ok(BluetoothCommand::Power(Some(true))),
+            "power-off" => Ok(BluetoothCommand::Power(Some(false))),
+            _ => Err(Report::msg(format!("Unknown action '{action}'"))),
+        }
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+
+        {
+            let tx = context.tx.clone();
+
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let tx = context.controller_tx.clone();
+
+            button.connect_button_press_event(move |_, event| {
+                if event.button() == BUTTON_MIDDLE {
+                    try_send!(tx, BluetoothCommand::Power(None));
+                    Propagation::Stop
+                } else {
+                    Propagation::Proceed
+                }
+            });
+        }
+
+        {
+            let button = button.clone();
+            let icons = self.icons.clone();
+
+            glib_recv!(context.subscribe(), state => {
+                button.set_label(icons.icon(&state));
+
+                let powered = state.adapter.as_ref().is_some_and(|adapter| adapter.powered);
+                button.set_class("no-adapter", state.adapter.is_none());
+                button.set_class("disabled", state.adapter.is_some() && !powered);
+                button.set_class("connected", powered && state.is_connected());
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: tokio::sync::broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let header = gtk::Box::new(Orientation::Horizontal, 10);
+        header.add_class("header");
+
+        let title = Label::new(Some(tr("bluetooth.title")));
+        title.add_class("title");
+        title.set_hexpand(true);
+        title.set_halign(gtk::Align::Start);
+
+        let power = Switch::new();
+        power.add_class("power");
+        power.set_valign(gtk::Align::Center);
+
+        header.add(&title);
+        header.add(&power);
+        container.add(&header);
+
+        let status = Label::new(None);
+        status.add_class("status");
+        container.add(&status);
+
+        let devices = gtk::Box::new(Orientation::Vertical, 5);
+        devices.add_class("devices");
+        container.add(&devices);
+
+        let handler = {
+            let tx = tx.clone();
+
+            power.connect_state_set(move |_, powered| {
+                try_send!(tx, BluetoothCommand::Power(Some(powered)));
+                Propagation::Proceed
+            })
+        };
+
+        glib_recv!(rx, state => {
+            let powered = state.adapter.as_ref().is_some_and(|adapter| adapter.powered);
+
+            // changes from the server should not be sent back to it
+            power.block_signal(&handler);
+            power.set_active(powered);
+            power.unblock_signal(&handler);
+            power.set_sensitive(state.adapter.is_some());
+
+            let message = match &state.adapter {
+                None => Some(tr("bluetooth.no_adapter")),
+                Some(_) if !powered => Some(tr("bluetooth.off")),
+                Some(_) if state.devices.is_empty() => Some(tr("bluetooth.no_devices")),
+                Some(_) => None,
+            };
+
+            if let Some(message) = message {
+                status.set_label(message);
+            }
+            status.set_visible(message.is_some());
+
+            for child in devices.children() {
+                devices.remove(&child);
+            }
+
+            if powered {
+                for device in &state.devices {
+                    devices.add(&device_row(device, &tx));
+                }
+            }
+
+            devices.show_all();
+        });
+
+        container.show_all();
+
+        Some(container)
+    }
+}
+
+/// Creates a popup row for the device,
+/// with its name, battery and a button to connect or disconnect.
+fn device_row(device: &Device, tx: &mpsc::Sender<BluetoothCommand>) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_class("device");
+    row.set_class("connected", device.connected);
+
+    let name = Label::new(Some(&device.name));
+    name.add_class("name");
+    name.set_hexpand(true);
+    name.set_halign(gtk::Align::Start);
+    name.set_ellipsize(EllipsizeMode::End);
+    name.set_max_width_chars(30);
+    name.set_tooltip_text(Some(&device.address));
+    row.add(&name);
+
+    if let Some(battery) = device.battery {
+        let label = Label::new(Some(&format!("{battery}%")));
+        label.add_class("battery");
+        label.set_tooltip_text(Some(tr("bluetooth.battery")));
+        row.add(&label);
+    }
+
+    let button = Button::with_label(if device.connected {
+        tr("bluetooth.disconnect")
+    } else {
+        tr("bluetooth.connect")
+    });
+    button.add_class(if device.connected {
+        "btn-disconnect"
+    } else {
+        "btn-connect"
+    });
+
+    {
+        let tx = tx.clone();
+        let path = device.path.clone();
+        let connected = device.connected;
+
+        button.connect_clicked(move |button| {
+            // re-enabled once the row is rebuilt from the new state
+            button.set_sensitive(false);
+            button.set_label(if connected {
+                "Disconnecting…"
+            } else {
+                "Connecting…"
+            });
+
+            let command = if connected {
+                BluetoothCommand::Disconnect(path.clone())
+            } else {
+                BluetoothCommand::Connect(path.clone())
+            };
+
+            try_send!(tx, command);
+        });
+    }
+
+    row.add(&button);
+    row
+}
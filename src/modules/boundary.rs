@@ -0,0 +1,279 @@
+use std::any::Any;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use gtk::prelude::*;
+use gtk::{EventBox, Label, Revealer};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tracing::{Instrument, Span};
+
+use super::set_identifiers;
+use crate::config::ClassList;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::{lock, logging, spawn};
+
+#[cfg(feature = "ipc")]
+use crate::{diagnostics, popup::Popup};
+#[cfg(feature = "ipc")]
+use color_eyre::{Report, Result};
+#[cfg(feature = "ipc")]
+use std::rc::Rc;
+
+/// The tasks spawned by a module's controller.
+///
+/// Each task is watched, so that if it panics
+/// the module can be swapped for an error placeholder.
+///
+/// Tasks run inside the module's span,
+/// so that anything they log names the module.
+#[derive(Debug, Clone)]
+pub struct ModuleTasks {
+    handles: Arc<Mutex<Vec<AbortHandle>>>,
+    error_tx: mpsc::Sender<String>,
+    span: Span,
+}
+
+impl ModuleTasks {
+    pub fn new(error_tx: mpsc::Sender<String>, instance_name: &str) -> Self {
+        Self {
+            handles: Arc::default(),
+            error_tx,
+            span: logging::module_span(instance_name),
+        }
+    }
+
+    /// Gets the module's span,
+    /// to enter when logging from outside its tasks.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Spawns the future on the Tokio runtime,
+    /// reporting the panic message if it panics.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let handle = spawn(future.instrument(self.span.clone()));
+        lock!(self.handles).push(handle.abort_handle());
+
+        let error_tx = self.error_tx.clone();
+        spawn(async move {
+            if let Err(err) = handle.await {
+                if err.is_panic() {
+                    let message = panic_message(err.into_panic().as_ref());
+                    error_tx.send(message).await.ok();
+                }
+            }
+        });
+    }
+
+    /// Stops all running tasks.
+    pub fn abort(&self) {
+        for handle in lock!(self.handles).drain(..) {
+            handle.abort();
+        }
+    }
+}
+
+/// Gets the message from a caught panic payload.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("Module panicked"))
+}
+
+/// A module which has been created,
+/// or failed to create and was replaced by an error placeholder.
+pub struct ModuleInstance {
+    /// The unique ID of the module,
+    /// which its popup content and diagnostics are registered under.
+    #[cfg_attr(not(feature = "ipc"), allow(dead_code))]
+    pub id: usize,
+    /// The module instance name.
+    /// This is the configured `name`, or the module type if unset.
+    pub name: String,
+    /// The outermost widget, which has been added to the container.
+    pub widget: EventBox,
+    /// The module's controller tasks.
+    pub tasks: ModuleTasks,
+}
+
+/// Creates a container holding an error placeholder,
+/// in place of a module which failed to create.
+pub fn error_container(
+    module_name: &str,
+    message: &str,
+    name: Option<&str>,
+    class: Option<&ClassList>,
+) -> EventBox {
+    let revealer = Revealer::builder().reveal_child(true).build();
+
+    let container = EventBox::new();
+    container.add_class("widget-container");
+    container.add(&revealer);
+
+    show_error(&container, module_name, message, name, class);
+    container.show_all();
+
+    container
+}
+
+/// Replaces the module widget inside the container with an error placeholder.
+///
+/// The placeholder shows a warning sign,
+/// with the error message in its tooltip.
+/// It is given the module's configured name and classes,
+/// so that it is placed and styled like the module.
+pub fn show_error(
+    container: &EventBox,
+    module_name: &str,
+    message: &str,
+    name: Option<&str>,
+    class: Option<&ClassList>,
+) {
+    let Some(revealer) = container
+        .child()
+        .and_then(|child| child.downcast::<Revealer>().ok())
+    else {
+        return;
+    };
+
+    if let Some(widget) = revealer.child() {
+        revealer.remove(&widget);
+    }
+
+    let placeholder = Label::new(Some("⚠"));
+    placeholder.add_class("widget");
+    placeholder.add_class("error");
+    placeholder.add_class(module_name);
+    placeholder.set_tooltip_text(Some(message));
+    set_identifiers(&placeholder, name, class, "");
+
+    revealer.add(&placeholder);
+    placeholder.show();
+}
+
+/// Rebuilds a module instance in place over IPC.
+///
+/// The handler holds a weak reference to the module container,
+/// so that it can be discarded once the module is destroyed.
+#[cfg(feature = "ipc")]
+pub struct ModuleRestartHandler {
+    /// The module instance name.
+    pub name: String,
+    id: usize,
+    widget: glib::WeakRef<EventBox>,
+    container: glib::WeakRef<gtk::Box>,
+    /// The popup the module's content is registered with.
+    popup: Rc<Popup>,
+    tasks: ModuleTasks,
+    rebuild: Box<dyn Fn(&gtk::Box) -> Option<EventBox>>,
+}
+
+#[cfg(feature = "ipc")]
+impl ModuleRestartHandler {
+    pub fn new<F>(
+        instance: ModuleInstance,
+        container: &gtk::Box,
+        popup: Rc<Popup>,
+        rebuild: F,
+    ) -> Self
+    where
+        F: Fn(&gtk::Box) -> Option<EventBox> + 'static,
+    {
+        Self {
+            name: instance.name,
+            id: instance.id,
+            widget: instance.widget.downgrade(),
+            container: container.downgrade(),
+            popup,
+            tasks: instance.tasks,
+            rebuild: Box::new(rebuild),
+        }
+    }
+
+    /// Checks whether the module container still exists.
+    pub fn is_alive(&self) -> bool {
+        self.widget.upgrade().is_some() && self.container.upgrade().is_some()
+    }
+
+    /// Checks whether the module is nested inside the widget.
+    pub fn is_inside(&self, ancestor: &impl IsA<gtk::Widget>) -> bool {
+        self.widget
+            .upgrade()
+            .is_some_and(|widget| widget.is_ancestor(ancestor))
+    }
+
+    /// Gets the module container, if it still exists.
+    pub fn widget(&self) -> Option<EventBox> {
+        self.widget.upgrade()
+    }
+
+    /// Removes the module's popup content and diagnostics,
+    /// so that nothing refers to the old instance once it is replaced.
+    ///
+    /// This is also required for modules nested inside one being restarted.
+    pub fn unregister(&self) {
+        self.popup.unregister_content(self.id);
+
+        if let Some(registry) = diagnostics::registry() {
+            registry.unregister(self.id);
+        }
+    }
+
+    /// Stops the module and removes it,
+    /// then creates it again at the same position.
+    pub fn restart(self) -> Result<()> {
+        let (Some(widget), Some(container)) = (self.widget.upgrade(), self.container.upgrade())
+        else {
+            return Err(Report::msg("Module no longer exists"));
+        };
+
+        let position = container
+            .children()
+            .iter()
+            .position(|child| child == widget.upcast_ref::<gtk::Widget>())
+            .ok_or_else(|| Report::msg("Module is no longer in its container"))?;
+
+        self.tasks.abort();
+        self.unregister();
+
+        container.remove(&widget);
+
+        // the module may now be skipped if its `load_if` condition changed.
+        if let Some(widget) = (self.rebuild)(&container) {
+            container.reorder_child(&widget, position as i32);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ipc")]
+impl std::fmt::Debug for ModuleRestartHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleRestartHandler")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+
+    #[test]
+    fn panic_messages() {
+        let message = |f: fn()| panic_message(panic::catch_unwind(f).unwrap_err().as_ref());
+
+        assert_eq!(message(|| panic!("static")), "static");
+        assert_eq!(message(|| panic!("formatted {}", 1)), "formatted 1");
+        assert_eq!(message(|| panic::panic_any(1_i32)), "Module panicked");
+    }
+}
@@ -0,0 +1,476 @@
+use crate::clients::timer::Ticker;
+use crate::config::CommonConfig;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
+use crate::scroll::{ScrollStep, Scroller};
+use crate::{glib_recv, module_impl, send_async, try_send};
+use color_eyre::{Help, Report, Result};
+use gtk::prelude::*;
+use gtk::Button;
+use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Notify};
+use tracing::{debug, error, warn};
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// How often to read the brightness,
+/// to pick up changes which the file watcher cannot see.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[zbus::dbus_proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BrightnessModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {percentage}%`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The name of the backlight device to show and control,
+    /// as listed in `/sys/class/backlight`, such as `intel_backlight`.
+    ///
+    /// If unset, firmware devices are preferred over platform and raw devices,
+    /// as they usually control the built-in screen.
+    ///
+    /// **Default**: `null`
+    device: Option<String>,
+
+    /// The percentage to change the brightness by on each scroll step.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_step")]
+    step: u32,
+
+    /// Brightness level icons.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{icon} {percentage}%")
+}
+
+const fn default_step() -> u32 {
+    5
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show for high brightness levels.
+    ///
+    /// **Default**: `󰃠`
+    #[serde(default = "default_icon_brightness_high")]
+    brightness_high: String,
+
+    /// Icon to show for medium brightness levels.
+    ///
+    /// **Default**: `󰃟`
+    #[serde(default = "default_icon_brightness_medium")]
+    brightness_medium: String,
+
+    /// Icon to show for low brightness levels.
+    ///
+    /// **Default**: `󰃞`
+    #[serde(default = "default_icon_brightness_low")]
+    brightness_low: String,
+}
+
+impl Icons {
+    fn brightness_icon(&self, percentage: u32) -> &str {
+        match percentage {
+            0..=33 => &self.brightness_low,
+            34..=66 => &self.brightness_medium,
+            67.. => &self.brightness_high,
+        }
+    }
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            brightness_high: default_icon_brightness_high(),
+            brightness_medium: default_icon_brightness_medium(),
+            brightness_low: default_icon_brightness_low(),
+        }
+    }
+}
+
+fn default_icon_brightness_high() -> String {
+    String::from("󰃠")
+}
+
+fn default_icon_brightness_medium() -> String {
+    String::from("󰃟")
+}
+
+fn default_icon_brightness_low() -> String {
+    String::from("󰃞")
+}
+
+/// A change requested from the widget or an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrightnessCommand {
+    /// Changes the brightness by a relative percentage.
+    Step(i32),
+    /// Sets the brightness percentage.
+    Set(u32),
+}
+
+/// A backlight device in `/sys/class/backlight`.
+#[derive(Debug, Clone)]
+struct Backlight {
+    name: String,
+    path: PathBuf,
+    max: u32,
+}
+
+impl Backlight {
+    fn new(name: &str) -> Result<Self> {
+        let path = Path::new(BACKLIGHT_DIR).join(name);
+        let max = read_value(&path.join("max_brightness")).map_err(|err| {
+            err.wrap_err(format!("Failed to read backlight device '{name}'"))
+                .suggestion(format!("Check the device is listed in {BACKLIGHT_DIR}"))
+        })?;
+
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            max,
+        })
+    }
+
+    /// Finds the device most likely to control the built-in screen.
+    fn detect() -> Result<Self> {
+        let devices = fs::read_dir(BACKLIGHT_DIR)?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                let kind = fs::read_to_string(entry.path().join("type")).unwrap_or_default();
+
+                Some((name, kind.trim().to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        let name = pick_device(&devices)
+            .ok_or_else(|| Report::msg(format!("No backlight devices found in {BACKLIGHT_DIR}")))?;
+
+        debug!("Using backlight device '{name}'");
+        Self::new(name)
+    }
+
+    fn brightness(&self) -> Result<u32> {
+        read_value(&self.path.join("brightness"))
+    }
+
+    fn percentage(&self) -> Result<u32> {
+        Ok(to_percentage(self.brightness()?, self.max))
+    }
+
+    /// Sets the raw brightness through logind,
+    /// which does not need write access to the device.
+    ///
+    /// The device file is written directly if that fails,
+    /// such as when there is no logind session.
+    async fn set_brightness(
+        &self,
+        session: Option<&SessionProxy<'_>>,
+        brightness: u32,
+    ) -> Result<()> {
+        if let Some(session) = session {
+            match session
+                .set_brightness("backlight", &self.name, brightness)
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(err) => debug!("Failed to set brightness through logind: {err}"),
+            }
+        }
+
+        fs::write(self.path.join("brightness"), brightness.to_string())?;
+        Ok(())
+    }
+}
+
+fn read_value(path: &Path) -> Result<u32> {
+    Ok(fs::read_to_string(path)?.trim().parse()?)
+}
+
+/// Picks a device from its name and type,
+/// preferring firmware devices, then platform, then raw.
+fn pick_device(devices: &[(String, String)]) -> Option<&str> {
+    let priority = |kind: &str| match kind {
+        "firmware" => 0,
+        "platform" => 1,
+        _ => 2,
+    };
+
+    devices
+        .iter()
+        .min_by(|(a_name, a_kind), (b_name, b_kind)| {
+            priority(a_kind)
+                .cmp(&priority(b_kind))
+                .then_with(|| a_name.cmp(b_name))
+        })
+        .map(|(name, _)| name.as_str())
+}
+
+fn to_percentage(brightness: u32, max: u32) -> u32 {
+    if max == 0 {
+        return 0;
+    }
+
+    (f64::from(brightness) * 100.0 / f64::from(max)).round() as u32
+}
+
+/// Gets the raw brightness for the command.
+///
+/// Steps always change the brightness by at least one,
+/// so that devices with few levels can still be adjusted.
+fn target_brightness(command: BrightnessCommand, brightness: u32, max: u32) -> u32 {
+    let from_percentage =
+        |percentage: f64| (percentage.clamp(0.0, 100.0) * f64::from(max) / 100.0).round() as u32;
+
+    match command {
+        BrightnessCommand::Set(percentage) => from_percentage(f64::from(percentage)),
+        BrightnessCommand::Step(step) => {
+            let current = f64::from(to_percentage(brightness, max));
+            let target = from_percentage(current + f64::from(step));
+
+            match target.cmp(&brightness) {
+                std::cmp::Ordering::Equal if step > 0 => (brightness + 1).min(max),
+                std::cmp::Ordering::Equal if step < 0 => brightness.saturating_sub(1),
+                _ => target,
+            }
+        }
+    }
+}
+
+/// The step used by the `up` and `down` actions
+/// when none is provided.
+const DEFAULT_ACTION_STEP: u32 = 5;
+
+fn parse_percentage_arg(args: &[String], default: Option<u32>) -> Result<u32> {
+    match (args.first(), default) {
+        (Some(arg), _) => arg
+            .parse()
+            .map_err(|_| Report::msg(format!("Invalid brightness value '{arg}'"))),
+        (None, Some(default)) => Ok(default),
+        (None, None) => Err(Report::msg("Missing brightness value")),
+    }
+}
+
+impl Module<Button> for BrightnessModule {
+    type SendMessage = u32;
+    type ReceiveMessage = BrightnessCommand;
+
+    module_impl!("brightness");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let backlight = match &self.device {
+            Some(name) => Backlight::new(name)?,
+            None => Backlight::detect()?,
+        };
+
+        let changed = Arc::new(Notify::new());
+
+        // inotify sees writes to `brightness` from userspace,
+        // but not changes the kernel makes itself, such as from laptop hotkeys,
+        // so the brightness is also polled
+        let mut watcher = recommended_watcher({
+            let changed = changed.clone();
+
+            move |res: notify::Result<Event>| match res {
+                Ok(event) if matches!(event.kind, EventKind::Modify(_)) => {
+                    let is_brightness = event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name().is_some_and(|name| name == "brightness"));
+
+                    if is_brightness {
+                        changed.notify_one();
+                    }
+                }
+                Err(err) => error!("Error occurred when watching backlight device: {err:?}"),
+                _ => {}
+            }
+        })?;
+
+        watcher.watch(&backlight.path, RecursiveMode::NonRecursive)?;
+
+        {
+            let tx = context.tx.clone();
+            let backlight = backlight.clone();
+
+            context.spawn(async move {
+                // avoid watcher from dropping
+                let _watcher = watcher;
+                let mut last = None;
+                let mut ticker = Ticker::new(POLL_INTERVAL, false);
+
+                loop {
+                    match backlight.percentage() {
+                        Ok(percentage) if last != Some(percentage) => {
+                            last = Some(percentage);
+                            send_async!(tx, ModuleUpdateEvent::Update(percentage));
+                        }
+                        Ok(_) => {}
+                        Err(err) => warn!("Failed to read brightness: {err:?}"),
+                    }
+
+                    tokio::select! {
+                        () = changed.notified() => {},
+                        () = ticker.tick() => {},
+                    }
+                }
+            });
+        }
+
+        context.spawn(async move {
+            let session = match zbus::Connection::system().await {
+                Ok(connection) => SessionProxy::new(&connection).await.ok(),
+                Err(err) => {
+                    warn!("Failed to connect to system bus for logind: {err}");
+                    None
+                }
+            };
+
+            while let Some(command) = rx.recv().await {
+                let brightness = match backlight.brightness() {
+                    Ok(brightness) => brightness,
+                    Err(err) => {
+                        error!("{:?}", err.wrap_err("Failed to read brightness"));
+                        continue;
+                    }
+                };
+
+                let target = target_brightness(command, brightness, backlight.max);
+
+                if target != brightness {
+                    if let Err(err) = backlight.set_brightness(session.as_ref(), target).await {
+                        error!("{:?}", err.wrap_err("Failed to set brightness"));
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn parse_action(action: &str, args: &[String]) -> Result<Self::ReceiveMessage> {
+        match action {
+            "up" => parse_percentage_arg(args, Some(DEFAULT_ACTION_STEP))
+                .map(|step| BrightnessCommand::Step(step as i32)),
+            "down" => parse_percentage_arg(args, Some(DEFAULT_ACTION_STEP))
+                .map(|step| BrightnessCommand::Step(-(step as i32))),
+            "set" => parse_percentage_arg(args, None).map(BrightnessCommand::Set),
+            _ => Err(Report::msg(format!("Unknown action '{action}'"))),
+        }
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+
+        {
+            let tx = context.controller_tx.clone();
+            let step = self.step as i32;
+
+            Scroller::new(info.scroll).connect(&button, move |_, scroll| {
+                let step = match scroll {
+                    ScrollStep::Up => step,
+                    ScrollStep::Down => -step,
+                };
+
+                try_send!(tx, BrightnessCommand::Step(step));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let icons = self.icons;
+            let format = self.format;
+
+            glib_recv!(context.subscribe(), percentage => {
+                let label = format
+                    .replace("{icon}", icons.brightness_icon(percentage))
+                    .replace("{percentage}", &percentage.to_string());
+
+                button.set_label(&label);
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_devices() {
+        let devices = [
+            (String::from("nvidia_0"), String::from("raw")),
+            (String::from("intel_backlight"), String::from("platform")),
+            (String::from("acpi_video0"), String::from("firmware")),
+        ];
+
+        assert_eq!(pick_device(&devices), Some("acpi_video0"));
+        assert_eq!(pick_device(&devices[..2]), Some("intel_backlight"));
+        assert_eq!(pick_device(&[]), None);
+    }
+
+    #[test]
+    fn gets_target_brightness() {
+        assert_eq!(to_percentage(96_000, 96_000), 100);
+        assert_eq!(to_percentage(0, 0), 0);
+
+        assert_eq!(
+            target_brightness(BrightnessCommand::Step(5), 4_800, 96_000),
+            9_600
+        );
+        assert_eq!(
+            target_brightness(BrightnessCommand::Step(-5), 2_000, 96_000),
+            0
+        );
+        assert_eq!(
+            target_brightness(BrightnessCommand::Set(150), 0, 96_000),
+            96_000
+        );
+
+        // devices with few levels still change on each step
+        assert_eq!(target_brightness(BrightnessCommand::Step(5), 3, 7), 4);
+        assert_eq!(target_brightness(BrightnessCommand::Step(-5), 3, 7), 2);
+        assert_eq!(target_brightness(BrightnessCommand::Step(5), 7, 7), 7);
+    }
+}
@@ -1,12 +1,14 @@
 use crate::config::CommonConfig;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, spawn, try_send};
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
+use crate::{glib_recv, module_impl, try_send};
 use cairo::{Format, ImageSurface};
 use glib::translate::IntoGlibPtr;
 use glib::Propagation;
 use gtk::prelude::*;
-use gtk::DrawingArea;
-use mlua::{Error, Function, LightUserData};
+use gtk::{DrawingArea, Orientation};
+use mlua::{Error, Function, LightUserData, Lua, Table};
 use notify::event::ModifyKind;
 use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Watcher};
 use serde::Deserialize;
@@ -59,6 +61,29 @@ const fn default_frequency() -> u64 {
     200
 }
 
+/// Creates the table passed to the draw function after the Cairo context,
+/// describing the canvas being drawn to.
+fn draw_context<'lua>(
+    lua: &'lua Lua,
+    area: &DrawingArea,
+    orientation: Orientation,
+    monitor: &str,
+) -> mlua::Result<Table<'lua>> {
+    let orientation = match orientation {
+        Orientation::Vertical => "vertical",
+        _ => "horizontal",
+    };
+
+    let context = lua.create_table()?;
+    context.set("width", area.allocated_width())?;
+    context.set("height", area.allocated_height())?;
+    context.set("orientation", orientation)?;
+    context.set("monitor", monitor)?;
+    context.set("scale", area.scale_factor())?;
+
+    Ok(context)
+}
+
 impl Module<gtk::Box> for CairoModule {
     type SendMessage = ();
     type ReceiveMessage = ();
@@ -67,8 +92,7 @@ impl Module<gtk::Box> for CairoModule {
 
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         _rx: Receiver<Self::ReceiveMessage>,
     ) -> color_eyre::Result<()>
     where
@@ -77,7 +101,7 @@ impl Module<gtk::Box> for CairoModule {
         let path = self.path.to_path_buf();
 
         let tx = context.tx.clone();
-        spawn(async move {
+        context.spawn(async move {
             let parent = path.parent().expect("to have parent path");
 
             let mut watcher = recommended_watcher({
@@ -145,8 +169,10 @@ impl Module<gtk::Box> for CairoModule {
             let id = id.clone();
 
             let path = self.path.clone();
+            let orientation = info.bar_position.orientation();
+            let monitor = info.output_name.to_string();
 
-            area.connect_draw(move |_, cr| {
+            area.connect_draw(move |area, cr| {
                 let function: Function = lua
                     .load(include_str!("../../lua/draw.lua"))
                     .eval()
@@ -157,13 +183,23 @@ impl Module<gtk::Box> for CairoModule {
                     return Propagation::Stop;
                 }
 
+                let draw_context = match draw_context(&lua, area, orientation, &monitor) {
+                    Ok(draw_context) => draw_context,
+                    Err(err) => {
+                        error!("{err}");
+                        return Propagation::Stop;
+                    }
+                };
+
                 let ptr = unsafe { cr.clone().into_glib_ptr().cast() };
 
                 // mlua needs a valid return type, even if we don't return anything
 
-                if let Err(err) =
-                    function.call::<_, Option<bool>>((id.as_str(), LightUserData(ptr)))
-                {
+                if let Err(err) = function.call::<_, Option<bool>>((
+                    id.as_str(),
+                    LightUserData(ptr),
+                    draw_context,
+                )) {
                     match err {
                         Error::RuntimeError(message) => {
                             let message = message.split_once("]:").expect("to exist").1;
@@ -182,6 +218,9 @@ impl Module<gtk::Box> for CairoModule {
         area.set_size_request(self.width as i32, self.height as i32);
         container.add(&area);
 
+        // redraw straight away on resize, rather than waiting for the next frame
+        area.connect_size_allocate(|area, _| area.queue_draw());
+
         glib::spawn_future_local(async move {
             loop {
                 area.queue_draw();
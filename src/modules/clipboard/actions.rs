@@ -0,0 +1,223 @@
+use crate::clients::wayland::{ClipboardItem, ClipboardValue};
+use crate::i18n::tr;
+use crate::Ironbar;
+use color_eyre::{Report, Result};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::{env, fs};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// An action which reads text from an image entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageAction {
+    /// Recognises text in the image.
+    Ocr,
+    /// Decodes QR codes and barcodes in the image.
+    Qr,
+}
+
+impl ImageAction {
+    pub const ALL: [Self; 2] = [Self::Ocr, Self::Qr];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Ocr => "OCR",
+            Self::Qr => "QR",
+        }
+    }
+
+    pub fn tooltip(self) -> &'static str {
+        match self {
+            Self::Ocr => tr("clipboard.ocr"),
+            Self::Qr => tr("clipboard.qr"),
+        }
+    }
+
+    pub const fn class(self) -> &'static str {
+        match self {
+            Self::Ocr => "btn-ocr",
+            Self::Qr => "btn-qr",
+        }
+    }
+
+    /// The error shown when the command finds nothing in the image.
+    fn empty_message(self) -> &'static str {
+        match self {
+            Self::Ocr => tr("clipboard.no_text"),
+            Self::Qr => tr("clipboard.no_qr"),
+        }
+    }
+}
+
+/// Checks whether the program the command runs can be found,
+/// either as a path or on `PATH`.
+pub fn program_exists(command: &str) -> bool {
+    let Some(program) = command.split_whitespace().next() else {
+        return false;
+    };
+
+    let is_executable = |path: &Path| {
+        fs::metadata(path)
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    };
+
+    if program.contains('/') {
+        return is_executable(Path::new(program));
+    }
+
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| is_executable(&dir.join(program))))
+}
+
+/// Runs the action's command on the image entry,
+/// returning the text it outputs.
+///
+/// The image is written to a temporary file,
+/// which replaces `{file}` in the command.
+pub async fn run(action: ImageAction, command: &str, item: &ClipboardItem) -> Result<String> {
+    let ClipboardValue::Image(bytes) = item.value.as_ref() else {
+        return Err(Report::msg("Entry is not an image"));
+    };
+
+    let path = temp_path(item);
+    let command = with_file(command, &path);
+
+    {
+        let path = path.clone();
+        let bytes = bytes.clone();
+        crate::spawn_blocking(move || write_private(&path, &bytes)).await??;
+    }
+
+    debug!("Running clipboard image command: {command}");
+
+    let output = Command::new("sh")
+        .args(["-c", &command])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await;
+
+    if let Err(err) = fs::remove_file(&path) {
+        warn!("Failed to remove {}: {err}", path.display());
+    }
+
+    let output = output?;
+
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+    if output.status.success() && !text.is_empty() {
+        Ok(text)
+    } else if output.status.success() || error.is_empty() {
+        Err(Report::msg(action.empty_message()))
+    } else {
+        // the last line usually explains the failure
+        Err(Report::msg(
+            error.lines().next_back().unwrap_or_default().to_string(),
+        ))
+    }
+}
+
+/// Gets a unique temporary path for the image,
+/// with an extension matching its type.
+///
+/// This is in the user's runtime directory where set,
+/// as other users cannot see into it.
+fn temp_path(item: &ClipboardItem) -> PathBuf {
+    let dir = env::var_os("XDG_RUNTIME_DIR").map_or_else(env::temp_dir, PathBuf::from);
+
+    dir.join(format!(
+        "ironbar-clipboard-{}-{}-{}.{}",
+        std::process::id(),
+        item.id,
+        Ironbar::unique_id(),
+        file_extension(&item.mime_type)
+    ))
+}
+
+/// Writes the bytes to a new file only the user can read.
+///
+/// This fails if the path already exists,
+/// so a file or link planted there is never written through.
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+
+    file.write_all(bytes)
+}
+
+/// Gets the file extension for an image mime type, such as `png` for `image/png`.
+fn file_extension(mime_type: &str) -> String {
+    let extension = mime_type
+        .split_once('/')
+        .map_or("", |(_, subtype)| subtype)
+        .split(';')
+        .next()
+        .unwrap_or_default();
+
+    let extension = extension.strip_prefix("x-").unwrap_or(extension);
+
+    if !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+        extension.to_string()
+    } else {
+        String::from("img")
+    }
+}
+
+/// Replaces `{file}` in the command with the quoted path,
+/// or appends the path if the command does not contain `{file}`.
+fn with_file(command: &str, path: &Path) -> String {
+    let quoted = format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"));
+
+    if command.contains("{file}") {
+        command.replace("{file}", &quoted)
+    } else {
+        format!("{command} {quoted}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_commands() {
+        assert_eq!(file_extension("image/png"), "png");
+        assert_eq!(file_extension("image/x-bmp"), "bmp");
+        assert_eq!(file_extension("image/svg+xml"), "img");
+
+        let path = Path::new("/tmp/it's.png");
+        assert_eq!(
+            with_file("tesseract {file} -", path),
+            r"tesseract '/tmp/it'\''s.png' -"
+        );
+        assert_eq!(
+            with_file("zbarimg --raw", path),
+            r"zbarimg --raw '/tmp/it'\''s.png'"
+        );
+
+        let path = env::temp_dir().join(format!("ironbar-clipboard-test-{}", std::process::id()));
+        assert!(write_private(&path, b"image").is_ok());
+        assert!(write_private(&path, b"image").is_err());
+        assert_eq!(
+            fs::metadata(&path)
+                .map(|metadata| metadata.permissions().mode() & 0o777)
+                .ok(),
+            Some(0o600)
+        );
+        fs::remove_file(path).ok();
+
+        assert!(program_exists("sh -c true"));
+        assert!(!program_exists("ironbar-missing-program {file}"));
+        assert!(!program_exists(""));
+    }
+}
@@ -0,0 +1,677 @@
+mod actions;
+mod thumbnail;
+
+use self::actions::ImageAction;
+use self::thumbnail::Thumbnail;
+use crate::clients::clipboard::{self, ClipboardEvent, Persistence};
+use crate::clients::wayland::{ClipboardItem, ClipboardValue, MimeFilter, Selection};
+use crate::config::{CommonConfig, TruncateMode};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::image::new_icon_button;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, spawn, try_send};
+use glib::Propagation;
+use gtk::prelude::*;
+use gtk::{Button, EventBox, Image, Label, Orientation, RadioButton, Spinner, Widget};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+/// How long an image action's error is shown on its entry.
+const ACTION_ERROR_DURATION: Duration = Duration::from_secs(4);
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ClipboardModule {
+    /// The icon to show on the bar widget button.
+    /// Supports [image](images) icons.
+    ///
+    /// **Default**: `󰨸`
+    #[serde(default = "default_icon")]
+    icon: String,
+
+    /// The size to render the icon at.
+    /// Note this only applies to image-type icons.
+    ///
+    /// **Default**: `32`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// The maximum number of items to keep in the history,
+    /// and to show in the popup.
+    /// This also applies to the saved history when `persistent` is enabled.
+    ///
+    /// **Default**: `10`
+    #[serde(default = "default_max_items")]
+    max_items: usize,
+
+    /// Whether to save the history to `$XDG_STATE_HOME/ironbar/clipboard.json`,
+    /// so that it is restored after restarting Ironbar.
+    ///
+    /// Text entries are saved, along with images up to `persistent_image_size_kb`.
+    /// Entries marked as sensitive by the app which copied them,
+    /// such as passwords from a password manager, are never saved.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    persistent: bool,
+
+    /// The maximum size of image entries to save when `persistent` is enabled,
+    /// in kilobytes.
+    ///
+    /// Set to `0` to only save text entries.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    persistent_image_size_kb: usize,
+
+    /// The maximum height in pixels of image previews in the popup.
+    /// Previews can be up to twice as wide as this.
+    /// Larger images are scaled down to fit.
+    ///
+    /// **Default**: `64`
+    #[serde(default = "default_image_max_height")]
+    image_max_height: i32,
+
+    /// A list of mime type globs to store in the history.
+    /// When set, only offered types matching one of these are stored,
+    /// which also allows storing textual types such as `text/html`.
+    ///
+    /// Leave empty to accept all supported text and image types.
+    ///
+    /// **Default**: `[]`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// { accept_mime = [ "text/*" "image/png" ] }
+    /// ```
+    #[serde(default)]
+    accept_mime: Vec<String>,
+
+    /// A list of mime type globs to never store in the history.
+    /// This takes priority over `accept_mime`.
+    ///
+    /// Entries offering only denied types are ignored entirely.
+    ///
+    /// **Default**: `[]`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// { deny_mime = [ "text/html" "image/*" ] }
+    /// ```
+    #[serde(default)]
+    deny_mime: Vec<String>,
+
+    /// Whether to store only the plain text value
+    /// when a selection offers plain text alongside other types,
+    /// such as HTML copied from a browser.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    prefer_plain_text: bool,
+
+    /// The selections to store in the history.
+    /// The primary selection is set by selecting text,
+    /// and is only stored once it has stopped changing.
+    ///
+    /// When more than one is set,
+    /// each entry in the popup shows which selection it came from.
+    ///
+    /// **Valid options**: `clipboard`, `primary`
+    /// <br>
+    /// **Default**: `["clipboard"]`
+    #[serde(default = "default_selections")]
+    selections: Vec<Selection>,
+
+    /// Whether choosing an entry copies it to both the clipboard and primary selection,
+    /// rather than only the selection it came from.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    copy_to_both: bool,
+
+    /// The command to run to read text from an image entry,
+    /// which is then copied to the clipboard.
+    /// `{file}` is replaced with the path to the image.
+    ///
+    /// The button is only shown if the command's program is installed.
+    /// Set to an empty string to hide it.
+    ///
+    /// **Default**: `tesseract {file} -`
+    #[serde(default = "default_ocr_command")]
+    ocr_command: String,
+
+    /// The command to run to decode a QR code in an image entry,
+    /// which is then copied to the clipboard.
+    /// `{file}` is replaced with the path to the image.
+    ///
+    /// The button is only shown if the command's program is installed.
+    /// Set to an empty string to hide it.
+    ///
+    /// **Default**: `zbarimg --quiet --raw {file}`
+    #[serde(default = "default_qr_command")]
+    qr_command: String,
+
+    // -- Common --
+    /// See [truncate options](module-level-options#truncate-mode).
+    ///
+    /// **Default**: `null`
+    truncate: Option<TruncateMode>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon() -> String {
+    String::from("󰨸")
+}
+
+const fn default_icon_size() -> i32 {
+    32
+}
+
+const fn default_max_items() -> usize {
+    10
+}
+
+const fn default_image_max_height() -> i32 {
+    64
+}
+
+fn default_selections() -> Vec<Selection> {
+    vec![Selection::Clipboard]
+}
+
+fn default_ocr_command() -> String {
+    String::from("tesseract {file} -")
+}
+
+fn default_qr_command() -> String {
+    String::from("zbarimg --quiet --raw {file}")
+}
+
+impl ClipboardModule {
+    /// Gets the command to run for the image action.
+    fn command(&self, action: ImageAction) -> &str {
+        match action {
+            ImageAction::Ocr => &self.ocr_command,
+            ImageAction::Qr => &self.qr_command,
+        }
+    }
+}
+
+/// Gets the indicator shown on entries from the selection.
+const fn selection_icon(selection: Selection) -> &'static str {
+    match selection {
+        Selection::Clipboard => "󰅍",
+        Selection::Primary => "󰗧",
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ControllerEvent {
+    /// An item was added, with its preview if it is an image.
+    Add(usize, ClipboardItem, Option<Thumbnail>),
+    Remove(usize),
+    Activate(usize),
+    Deactivate,
+    /// An image action finished on the entry,
+    /// with the error message if it failed.
+    ActionDone(usize, Option<String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum UIEvent {
+    Copy(usize),
+    Remove(usize),
+    Action(usize, ImageAction),
+}
+
+impl Module<Button> for ClipboardModule {
+    type SendMessage = ControllerEvent;
+    type ReceiveMessage = UIEvent;
+
+    module_impl!("clipboard");
+
+    fn is_sensitive() -> bool {
+        true
+    }
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> color_eyre::Result<()> {
+        let max_items = self.max_items;
+        let image_max_height = self.image_max_height;
+
+        let tx = context.tx.clone();
+        let client = context.client::<clipboard::Client>();
+
+        client.set_mime_filter(MimeFilter {
+            accept: self.accept_mime.clone(),
+            deny: self.deny_mime.clone(),
+            prefer_plain_text: self.prefer_plain_text,
+        });
+        client.set_selections(self.selections.clone());
+
+        if self.persistent {
+            client.set_persistence(Persistence {
+                max_items,
+                image_max_size: self.persistent_image_size_kb * 1024,
+            });
+        }
+
+        // listen to clipboard events
+        context.spawn(async move {
+            let mut rx = client.subscribe(max_items);
+
+            while let Some(event) = rx.recv().await {
+                match event {
+                    ClipboardEvent::Add(item) => {
+                        let msg = match item.value.as_ref() {
+                            ClipboardValue::Other => {
+                                ModuleUpdateEvent::Update(ControllerEvent::Deactivate)
+                            }
+                            ClipboardValue::Image(bytes) => {
+                                let thumbnail =
+                                    Thumbnail::load(bytes.clone(), image_max_height).await;
+                                ModuleUpdateEvent::Update(ControllerEvent::Add(
+                                    item.id, item, thumbnail,
+                                ))
+                            }
+                            ClipboardValue::Text(_) => {
+                                ModuleUpdateEvent::Update(ControllerEvent::Add(item.id, item, None))
+                            }
+                        };
+                        try_send!(tx, msg);
+                    }
+                    ClipboardEvent::Remove(id) => {
+                        try_send!(tx, ModuleUpdateEvent::Update(ControllerEvent::Remove(id)));
+                    }
+                    ClipboardEvent::Activate(id) => {
+                        try_send!(tx, ModuleUpdateEvent::Update(ControllerEvent::Activate(id)));
+                    }
+                }
+            }
+
+            error!("Clipboard client unexpectedly closed");
+        });
+
+        let client = context.client::<clipboard::Client>();
+        let copy_to_both = self.copy_to_both;
+        let commands = ImageAction::ALL.map(|action| (action, self.command(action).to_string()));
+        let tx = context.tx.clone();
+
+        // listen to ui events
+        context.spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UIEvent::Copy(id) => client.copy(id, copy_to_both),
+                    UIEvent::Remove(id) => client.remove(id),
+                    UIEvent::Action(id, action) => {
+                        let Some(item) = client.item(id) else {
+                            continue;
+                        };
+
+                        let command = commands
+                            .iter()
+                            .find_map(|(a, command)| (*a == action).then(|| command.clone()))
+                            .unwrap_or_default();
+
+                        let client = client.clone();
+                        let tx = tx.clone();
+
+                        spawn(async move {
+                            let error = match actions::run(action, &command, &item).await {
+                                Ok(text) => {
+                                    client.copy_text(text);
+                                    None
+                                }
+                                Err(err) => {
+                                    debug!("Clipboard image action failed: {err:?}");
+                                    Some(err.to_string())
+                                }
+                            };
+
+                            try_send!(
+                                tx,
+                                ModuleUpdateEvent::Update(ControllerEvent::ActionDone(id, error))
+                            );
+                        });
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> color_eyre::Result<ModuleParts<Button>> {
+        let button = new_icon_button(&self.icon, info.icon_theme, info.scaled(self.icon_size));
+        button.style_context().add_class("btn");
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        let rx = context.subscribe();
+        let popup = self
+            .into_popup(context.controller_tx.clone(), rx, context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        let entries = gtk::Box::new(Orientation::Vertical, 5);
+        container.add(&entries);
+
+        let hidden_option = RadioButton::new();
+        entries.add(&hidden_option);
+
+        let mut items = HashMap::new();
+        let mut image_actions = HashMap::new();
+        let show_selection = self.selections.len() > 1;
+
+        let available_actions = ImageAction::ALL
+            .into_iter()
+            .filter(|&action| actions::program_exists(self.command(action)))
+            .collect::<Vec<_>>();
+
+        {
+            let hidden_option = hidden_option.clone();
+            glib_recv!(rx, event => {
+                match event {
+                    ControllerEvent::Add(id, item, thumbnail) => {
+                        debug!("Adding new value with ID {}", id);
+
+                        let row = gtk::Box::new(Orientation::Horizontal, 0);
+                        row.style_context().add_class("item");
+
+                        let button = match item.value.as_ref() {
+                            ClipboardValue::Text(value) => {
+                                let button = RadioButton::from_widget(&hidden_option);
+
+                                let label = Label::new(Some(value));
+                                button.add(&label);
+
+                                if let Some(truncate) = self.truncate {
+                                    label.truncate(truncate);
+                                }
+
+                                button.style_context().add_class("text");
+                                button
+                            }
+                            ClipboardValue::Image(_) => {
+                                let button = RadioButton::from_widget(&hidden_option);
+
+                                // the type is shown if the image could not be read
+                                match thumbnail {
+                                    Some(thumbnail) => {
+                                        let image = Image::from_pixbuf(Some(&thumbnail.to_pixbuf()));
+                                        button.set_image(Some(&image));
+                                        button.set_always_show_image(true);
+                                    }
+                                    None => button.add(&Label::new(Some(&item.mime_type))),
+                                }
+
+                                button.style_context().add_class("image");
+                                button
+                            }
+                            ClipboardValue::Other => unreachable!(),
+                        };
+
+                        button.style_context().add_class("btn");
+
+                        // if just added, should be on clipboard.
+                        // new primary selections leave the clipboard unchanged.
+                        if item.selection == Selection::Clipboard {
+                            button.set_active(true);
+                        }
+
+                        let button_wrapper = EventBox::new();
+                        button_wrapper.add(&button);
+
+                        button_wrapper.set_widget_name(&format!("copy-{id}"));
+                        button_wrapper.set_above_child(true);
+
+                        {
+                            let tx = tx.clone();
+                            button_wrapper.connect_button_press_event(
+                                move |button_wrapper, event| {
+                                    // left click
+                                    if event.button() == 1 {
+                                        let id = get_button_id(button_wrapper)
+                                            .expect("Failed to get id from button name");
+
+                                        debug!("Copying item with id: {id}");
+                                        try_send!(tx, UIEvent::Copy(id));
+                                    }
+
+                                    Propagation::Stop
+                                },
+                            );
+                        }
+
+                        let remove_button = Button::with_label("x");
+                        remove_button.set_widget_name(&format!("remove-{id}"));
+                        remove_button.style_context().add_class("btn-remove");
+
+                        {
+                            let tx = tx.clone();
+                            let entries = entries.clone();
+                            let row = row.clone();
+
+                            remove_button.connect_clicked(move |button| {
+                                let id = get_button_id(button)
+                                    .expect("Failed to get id from button name");
+
+                                debug!("Removing item with id: {id}");
+                                try_send!(tx, UIEvent::Remove(id));
+
+                                entries.remove(&row);
+                            });
+                        }
+
+                        if show_selection {
+                            let indicator = Label::new(Some(selection_icon(item.selection)));
+                            indicator.add_class("selection");
+                            indicator.add_class(match item.selection {
+                                Selection::Clipboard => "clipboard",
+                                Selection::Primary => "primary",
+                            });
+
+                            row.add(&indicator);
+                        }
+
+                        row.add(&button_wrapper);
+                        row.pack_end(&remove_button, false, false, 0);
+
+                        if matches!(item.value.as_ref(), ClipboardValue::Image(_))
+                            && !available_actions.is_empty()
+                        {
+                            let actions = ActionsRow::new(id, &available_actions, &tx);
+                            row.pack_end(&actions.container, false, false, 0);
+                            image_actions.insert(id, actions);
+                        }
+
+                        entries.add(&row);
+                        entries.reorder_child(&row, 0);
+                        row.show_all();
+
+                        if let Some(actions) = image_actions.get(&id) {
+                            actions.set_running(false);
+                        }
+
+                        items.insert(id, (row, button));
+                    }
+                    ControllerEvent::Remove(id) => {
+                        debug!("Removing option with ID {id}");
+                        image_actions.remove(&id);
+
+                        let row = items.remove(&id);
+                        if let Some((row, button)) = row {
+                            if button.is_active() {
+                                hidden_option.set_active(true);
+                            }
+
+                            entries.remove(&row);
+                        }
+                    }
+                    ControllerEvent::Activate(id) => {
+                        debug!("Activating option with ID {id}");
+
+                        hidden_option.set_active(false);
+                        let row = items.get(&id);
+                        if let Some((_, button)) = row {
+                            button.set_active(true);
+                        }
+                    }
+                    ControllerEvent::Deactivate => {
+                        debug!("Deactivating current option");
+                        hidden_option.set_active(true);
+                    }
+                    ControllerEvent::ActionDone(id, error) => {
+                        if let Some(actions) = image_actions.get(&id) {
+                            actions.set_running(false);
+
+                            if let Some(error) = error {
+                                actions.show_error(&error);
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        container.show_all();
+        hidden_option.hide();
+
+        Some(container)
+    }
+}
+
+/// The image action buttons on an entry,
+/// with a spinner shown while an action runs
+/// and a label for showing errors.
+struct ActionsRow {
+    container: gtk::Box,
+    buttons: gtk::Box,
+    spinner: Spinner,
+    error: Label,
+}
+
+impl ActionsRow {
+    fn new(id: usize, actions: &[ImageAction], tx: &mpsc::Sender<UIEvent>) -> Self {
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+        container.add_class("actions");
+
+        let buttons = gtk::Box::new(Orientation::Horizontal, 0);
+        let spinner = Spinner::new();
+
+        let error = Label::new(None);
+        error.add_class("error");
+
+        container.add(&error);
+        container.add(&spinner);
+        container.add(&buttons);
+
+        let row = Self {
+            container,
+            buttons,
+            spinner,
+            error,
+        };
+
+        for &action in actions {
+            let button = Button::with_label(action.label());
+            button.set_tooltip_text(Some(action.tooltip()));
+            button.add_class(action.class());
+
+            let tx = tx.clone();
+            let spinner = row.spinner.clone();
+            let buttons = row.buttons.clone();
+            let error = row.error.clone();
+
+            button.connect_clicked(move |_| {
+                debug!("Running {action:?} on item with id: {id}");
+                try_send!(tx, UIEvent::Action(id, action));
+
+                error.hide();
+                buttons.set_sensitive(false);
+                spinner.show();
+                spinner.start();
+            });
+
+            row.buttons.add(&button);
+        }
+
+        row
+    }
+
+    /// Shows or hides the spinner,
+    /// disabling the buttons while an action runs.
+    fn set_running(&self, running: bool) {
+        self.buttons.set_sensitive(!running);
+        self.spinner.set_active(running);
+        self.spinner.set_visible(running);
+
+        if !running {
+            self.error.hide();
+        }
+    }
+
+    /// Shows the error on the entry for a few seconds.
+    fn show_error(&self, error: &str) {
+        self.error.set_text(error);
+        self.error.show();
+
+        let label = self.error.clone();
+        let text = error.to_string();
+        glib::timeout_add_local_once(ACTION_ERROR_DURATION, move || {
+            // a newer error may have replaced this one
+            if label.text() == text {
+                label.hide();
+            }
+        });
+    }
+}
+
+/// Gets the ID from a widget's name.
+///
+/// This expects the button name to be
+/// in the format `<purpose>-<id>`.
+fn get_button_id<W>(button_wrapper: &W) -> Option<usize>
+where
+    W: IsA<Widget>,
+{
+    button_wrapper
+        .widget_name()
+        .split_once('-')
+        .and_then(|(_, id)| id.parse().ok())
+}
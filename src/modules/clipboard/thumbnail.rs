@@ -0,0 +1,129 @@
+use color_eyre::{Report, Result};
+use glib::Bytes;
+use gtk::gdk_pixbuf::{Colorspace, Pixbuf, PixbufLoader};
+use gtk::prelude::*;
+use tracing::error;
+
+/// How many times wider than `max_height` a thumbnail can be,
+/// so that very wide images do not stretch the popup.
+const MAX_ASPECT_RATIO: i32 = 2;
+
+/// A downscaled copy of an image entry, shown in the popup.
+///
+/// This is decoded in the controller,
+/// so that large images do not block the GTK thread.
+/// Copying the entry always uses the original image.
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    width: i32,
+    height: i32,
+    rowstride: i32,
+    has_alpha: bool,
+    pixels: Bytes,
+}
+
+impl Thumbnail {
+    /// Decodes the image on a blocking thread.
+    /// Returns `None` if the image could not be read.
+    pub async fn load(bytes: Bytes, max_height: i32) -> Option<Self> {
+        let thumbnail = crate::spawn_blocking(move || Self::decode(&bytes, max_height))
+            .await
+            .map_err(Report::new)
+            .and_then(|thumbnail| thumbnail);
+
+        match thumbnail {
+            Ok(thumbnail) => Some(thumbnail),
+            Err(err) => {
+                error!("Failed to read clipboard image: {err:?}");
+                None
+            }
+        }
+    }
+
+    /// Decodes the image, scaled down to fit within `max_height`
+    /// and twice that in width.
+    /// Images smaller than this are left at their original size.
+    ///
+    /// The size is set before the image is decoded,
+    /// so that large images are never held in memory at full size.
+    ///
+    /// This blocks while decoding, so should be run on a blocking thread.
+    fn decode(bytes: &Bytes, max_height: i32) -> Result<Self> {
+        let loader = PixbufLoader::new();
+
+        loader.connect_size_prepared(move |loader, width, height| {
+            let max_width = max_height.saturating_mul(MAX_ASPECT_RATIO);
+            let size = thumbnail_size(width, height, max_width, max_height);
+
+            if size != (width, height) {
+                loader.set_size(size.0, size.1);
+            }
+        });
+
+        loader.write_bytes(bytes)?;
+        loader.close()?;
+
+        let pixbuf = loader
+            .pixbuf()
+            .ok_or_else(|| Report::msg("Image has no pixels"))?;
+
+        Ok(Self {
+            width: pixbuf.width(),
+            height: pixbuf.height(),
+            rowstride: pixbuf.rowstride(),
+            has_alpha: pixbuf.has_alpha(),
+            pixels: pixbuf.read_pixel_bytes(),
+        })
+    }
+
+    pub fn to_pixbuf(&self) -> Pixbuf {
+        Pixbuf::from_bytes(
+            &self.pixels,
+            Colorspace::Rgb,
+            self.has_alpha,
+            8,
+            self.width,
+            self.height,
+            self.rowstride,
+        )
+    }
+}
+
+/// Gets the size to scale an image to so that it fits within
+/// `max_width` and `max_height`, keeping its aspect ratio.
+fn thumbnail_size(width: i32, height: i32, max_width: i32, max_height: i32) -> (i32, i32) {
+    if (width <= max_width && height <= max_height) || width <= 0 || height <= 0 {
+        return (width, height);
+    }
+
+    let max_width = i64::from(max_width.max(1));
+    let max_height = i64::from(max_height.max(1));
+    let (width, height) = (i64::from(width), i64::from(height));
+
+    // compare the aspect ratios to find which side limits the size
+    let (width, height) = if width * max_height > height * max_width {
+        (max_width, height * max_width / width)
+    } else {
+        (width * max_height / height, max_height)
+    };
+
+    #[allow(clippy::cast_possible_truncation)]
+    (width.max(1) as i32, height.max(1) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_to_max_size() {
+        assert_eq!(thumbnail_size(1920, 1080, 128, 64), (113, 64));
+        assert_eq!(thumbnail_size(10, 4000, 128, 64), (1, 64));
+        assert_eq!(thumbnail_size(4000, 100, 128, 64), (128, 3));
+        assert_eq!(thumbnail_size(200, 10, 128, 64), (128, 6));
+
+        // small images are not scaled up
+        assert_eq!(thumbnail_size(32, 32, 128, 64), (32, 32));
+        assert_eq!(thumbnail_size(128, 64, 128, 64), (128, 64));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::clients::resume;
+use crate::state::{state_file, write_state};
+use crate::{lock, spawn};
+use chrono::{DateTime, Days, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, error, info};
+
+/// How often to check whether an alarm is due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An alarm set from the clock popup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alarm {
+    pub id: usize,
+    pub time: DateTime<Local>,
+    pub label: String,
+}
+
+/// An alarm as saved to the state file.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedAlarm {
+    /// The time, in RFC 3339 format.
+    time: String,
+    label: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum AlarmEvent {
+    /// The alarms waiting to fire changed.
+    Changed(Vec<Alarm>),
+    /// The alarm's time was reached.
+    Fired(Alarm),
+    /// Fired alarms were acknowledged.
+    Acknowledged,
+}
+
+#[derive(Debug)]
+struct State {
+    alarms: Vec<Alarm>,
+    next_id: usize,
+    path: Option<PathBuf>,
+}
+
+/// The alarms shared between all clock modules.
+///
+/// Alarms are saved to the state directory whenever they change,
+/// so they survive restarts.
+/// Any which passed while Ironbar was not running fire as soon as it starts.
+#[derive(Debug)]
+pub struct Alarms {
+    state: Arc<Mutex<State>>,
+    tx: broadcast::Sender<AlarmEvent>,
+}
+
+impl Alarms {
+    fn new() -> Self {
+        let path = state_file("alarms.json");
+
+        let alarms = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| parse_alarms(&contents))
+            .unwrap_or_default();
+
+        let state = Arc::new(Mutex::new(State {
+            next_id: alarms.len(),
+            alarms,
+            path,
+        }));
+
+        let (tx, _) = broadcast::channel(16);
+
+        {
+            let state = state.clone();
+            let tx = tx.clone();
+
+            spawn(async move {
+                let mut resume_rx = resume::client().subscribe();
+
+                loop {
+                    fire_due(&state, &tx, Local::now());
+
+                    // timers do not advance while suspended,
+                    // so check straight away for any alarms passed while asleep.
+                    if resume::wait(CHECK_INTERVAL, &mut resume_rx).await {
+                        debug!("Checking for alarms passed while suspended");
+                    }
+                }
+            });
+        }
+
+        Self { state, tx }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AlarmEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Gets the alarms waiting to fire, soonest first.
+    pub fn list(&self) -> Vec<Alarm> {
+        lock!(self.state).alarms.clone()
+    }
+
+    /// Adds an alarm for the next time the clock reaches `time`.
+    pub fn add(&self, time: NaiveTime, label: String) {
+        let mut state = lock!(self.state);
+
+        let alarm = Alarm {
+            id: state.next_id,
+            time: next_occurrence(time, Local::now()),
+            label,
+        };
+
+        info!("Adding alarm for {}", alarm.time);
+
+        state.next_id += 1;
+        state.alarms.push(alarm);
+        state.alarms.sort_by_key(|alarm| alarm.time);
+
+        self.changed(&state);
+    }
+
+    pub fn remove(&self, id: usize) {
+        let mut state = lock!(self.state);
+        state.alarms.retain(|alarm| alarm.id != id);
+
+        self.changed(&state);
+    }
+
+    /// Stops any fired alarms from being shown.
+    pub fn acknowledge(&self) {
+        self.tx.send(AlarmEvent::Acknowledged).ok();
+    }
+
+    fn changed(&self, state: &State) {
+        save(state);
+        self.tx.send(AlarmEvent::Changed(state.alarms.clone())).ok();
+    }
+}
+
+/// Gets the shared alarms, loading them on first use.
+pub fn alarms() -> &'static Alarms {
+    static ALARMS: OnceLock<Alarms> = OnceLock::new();
+    ALARMS.get_or_init(Alarms::new)
+}
+
+fn fire_due(state: &Mutex<State>, tx: &broadcast::Sender<AlarmEvent>, now: DateTime<Local>) {
+    let mut state = lock!(state);
+
+    let due = take_due(&mut state.alarms, now);
+    if due.is_empty() {
+        return;
+    }
+
+    save(&state);
+
+    for alarm in due {
+        info!("Alarm for {} fired", alarm.time);
+        tx.send(AlarmEvent::Fired(alarm)).ok();
+    }
+
+    tx.send(AlarmEvent::Changed(state.alarms.clone())).ok();
+}
+
+/// Removes and returns the alarms whose time has been reached.
+fn take_due(alarms: &mut Vec<Alarm>, now: DateTime<Local>) -> Vec<Alarm> {
+    let (due, waiting) = alarms.drain(..).partition(|alarm| alarm.time <= now);
+    *alarms = waiting;
+    due
+}
+
+/// Gets the next time after `now` that the clock shows `time`.
+fn next_occurrence(time: NaiveTime, now: DateTime<Local>) -> DateTime<Local> {
+    let at_date = |days| {
+        now.date_naive()
+            .checked_add_days(Days::new(days))
+            .and_then(|date| date.and_time(time).and_local_timezone(Local).earliest())
+    };
+
+    at_date(0)
+        .filter(|today| *today > now)
+        .or_else(|| at_date(1))
+        .or_else(|| at_date(2)) // time skipped by a DST change
+        .unwrap_or(now)
+}
+
+fn save(state: &State) {
+    let Some(path) = &state.path else {
+        return;
+    };
+
+    let result = format_alarms(&state.alarms)
+        .map_err(Into::into)
+        .and_then(|contents| write_state(path, contents));
+
+    if let Err(err) = result {
+        error!("Failed to save alarms: {err:?}");
+    }
+}
+
+fn format_alarms(alarms: &[Alarm]) -> serde_json::Result<String> {
+    let alarms = alarms
+        .iter()
+        .map(|alarm| SavedAlarm {
+            time: alarm.time.to_rfc3339(),
+            label: alarm.label.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string(&alarms)
+}
+
+/// Parses the saved alarms, skipping any with an invalid time.
+fn parse_alarms(contents: &str) -> Vec<Alarm> {
+    let saved = serde_json::from_str::<Vec<SavedAlarm>>(contents).unwrap_or_else(|err| {
+        error!("Failed to read saved alarms: {err:?}");
+        vec![]
+    });
+
+    let mut alarms = saved
+        .into_iter()
+        .filter_map(|alarm| {
+            DateTime::parse_from_rfc3339(&alarm.time)
+                .ok()
+                .map(|time| (time.with_timezone(&Local), alarm.label))
+        })
+        .enumerate()
+        .map(|(id, (time, label))| Alarm { id, time, label })
+        .collect::<Vec<_>>();
+
+    alarms.sort_by_key(|alarm| alarm.time);
+    alarms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn local(hour: u32, min: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 6, 15, hour, min, 0)
+            .single()
+            .expect("time to exist")
+    }
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).expect("time to be valid")
+    }
+
+    #[test]
+    fn schedules_next_occurrence() {
+        let now = local(12, 0);
+
+        assert_eq!(next_occurrence(time(13, 30), now), local(13, 30));
+
+        let tomorrow = next_occurrence(time(7, 0), now);
+        assert!(tomorrow > now);
+        assert_eq!(tomorrow.time(), time(7, 0));
+        assert_eq!(tomorrow.date_naive(), now.date_naive().succ_opt().unwrap());
+
+        assert!(next_occurrence(time(12, 0), now) > now);
+    }
+
+    #[test]
+    fn fires_alarms_passed_while_asleep() {
+        let mut alarms = vec![
+            Alarm {
+                id: 0,
+                time: local(8, 0),
+                label: String::from("missed"),
+            },
+            Alarm {
+                id: 1,
+                time: local(18, 0),
+                label: String::new(),
+            },
+        ];
+
+        let due = take_due(&mut alarms, local(9, 0));
+
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].label, "missed");
+        assert_eq!(alarms.len(), 1);
+        assert_eq!(alarms[0].id, 1);
+    }
+
+    #[test]
+    fn saves_and_loads_alarms() {
+        let alarms = vec![
+            Alarm {
+                id: 0,
+                time: local(7, 30),
+                label: String::from("wake\nup"),
+            },
+            Alarm {
+                id: 1,
+                time: local(18, 0),
+                label: String::from("\tgo home"),
+            },
+        ];
+
+        let contents = format_alarms(&alarms).expect("alarms to serialize");
+        assert_eq!(parse_alarms(&contents), alarms);
+        assert_eq!(
+            parse_alarms(r#"[{"time":"not a time","label":""}]"#),
+            vec![]
+        );
+        assert_eq!(parse_alarms("not json"), vec![]);
+    }
+}
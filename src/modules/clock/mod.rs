@@ -0,0 +1,718 @@
+mod alarms;
+mod stopwatch;
+
+use std::cell::{Cell, RefCell};
+use std::env;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Datelike, Local, Locale, NaiveDate, NaiveTime, Timelike};
+use color_eyre::Result;
+use glib::{ControlFlow, Propagation};
+use gtk::prelude::*;
+use gtk::{Align, Button, Calendar, Entry, Label, Notebook, Orientation, SpinButton};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+
+use self::alarms::{Alarm, AlarmEvent};
+use self::stopwatch::Stopwatch;
+use crate::clients::resume;
+use crate::config::{CommonConfig, ModuleOrientation, ScrollConfig};
+use crate::duration::format_precise;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::i18n::tr;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    PopupButton, WidgetContext,
+};
+use crate::script::{Script, ScriptInput};
+use crate::scroll::{ScrollStep, Scroller};
+use crate::{glib_recv, module_impl, send_async, try_send};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ClockModule {
+    /// The format string to use for the date/time shown on the bar.
+    /// Pango markup is supported.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%d/%m/%Y %H:%M`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The format string to use for the date/time shown in the popup header.
+    /// Pango markup is supported.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%H:%M:%S`
+    #[serde(default = "default_popup_format")]
+    format_popup: String,
+
+    /// The format string to use for the month shown above the calendar.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%B %Y`
+    #[serde(default = "default_calendar_format")]
+    format_calendar: String,
+
+    /// The locale to use when formatting dates.
+    ///
+    /// Note this only controls the month above the calendar,
+    /// not the calendar's day names - for those you must set `LC_TIME`.
+    ///
+    /// **Valid options**: See [here](https://docs.rs/pure-rust-locales/0.8.1/pure_rust_locales/enum.Locale.html#variants)
+    /// <br>
+    /// **Default**: `$LC_TIME` or `$LANG` or `'POSIX'`
+    #[serde(default = "default_locale")]
+    locale: String,
+
+    /// The orientation to display the widget contents.
+    /// Setting to vertical will rotate text 90 degrees.
+    ///
+    /// **Valid options**: `horizontal`, `vertical`
+    /// <br>
+    /// **Default**: `horizontal`
+    #[serde(default)]
+    orientation: ModuleOrientation,
+
+    /// Whether to show the stopwatch and alarms in the popup,
+    /// alongside the calendar.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    show_tools: bool,
+
+    /// A [script](scripts) to run when an alarm fires.
+    /// The alarm's label is passed as an argument, and can be accessed using `$0`.
+    ///
+    /// Alarms are shared between all clocks,
+    /// so this should only be set on one of them.
+    ///
+    /// **Default**: `null`
+    alarm_command: Option<ScriptInput>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for ClockModule {
+    fn default() -> Self {
+        ClockModule {
+            format: default_format(),
+            format_popup: default_popup_format(),
+            format_calendar: default_calendar_format(),
+            locale: default_locale(),
+            orientation: ModuleOrientation::Horizontal,
+            show_tools: true,
+            alarm_command: None,
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+fn default_format() -> String {
+    String::from("%d/%m/%Y %H:%M")
+}
+
+fn default_popup_format() -> String {
+    String::from("%H:%M:%S")
+}
+
+fn default_calendar_format() -> String {
+    String::from("%B %Y")
+}
+
+fn default_locale() -> String {
+    env::var("LC_TIME")
+        .or_else(|_| env::var("LANG"))
+        .map_or_else(|_| "POSIX".to_string(), strip_tail)
+}
+
+fn strip_tail(string: String) -> String {
+    string
+        .split_once('.')
+        .map(|(head, _)| head.to_string())
+        .unwrap_or(string)
+}
+
+#[derive(Debug, Clone)]
+pub enum ClockEvent {
+    Time(DateTime<Local>),
+    Alarm(AlarmEvent),
+}
+
+#[derive(Debug, Clone)]
+pub enum ClockCommand {
+    AddAlarm {
+        time: NaiveTime,
+        label: String,
+    },
+    RemoveAlarm(usize),
+    /// Stops showing fired alarms.
+    Acknowledge,
+}
+
+impl Module<Button> for ClockModule {
+    type SendMessage = ClockEvent;
+    type ReceiveMessage = ClockCommand;
+
+    module_impl!("clock");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let mut resume_rx = context.subscribe_resume();
+        context.spawn(async move {
+            loop {
+                let date = Local::now();
+                send_async!(tx, ModuleUpdateEvent::Update(ClockEvent::Time(date)));
+                resume::wait(Duration::from_millis(500), &mut resume_rx).await;
+            }
+        });
+
+        if !self.show_tools {
+            return Ok(());
+        }
+
+        let alarms = alarms::alarms();
+        let mut alarm_rx = alarms.subscribe();
+
+        let alarm_command = self
+            .alarm_command
+            .clone()
+            .map(|script| Script::new_polling(script).with_env(&context.script_env));
+
+        let tx = context.tx.clone();
+        context.spawn(async move {
+            send_async!(
+                tx,
+                ModuleUpdateEvent::Update(ClockEvent::Alarm(AlarmEvent::Changed(alarms.list())))
+            );
+
+            loop {
+                tokio::select! {
+                    event = alarm_rx.recv() => match event {
+                        Ok(event) => {
+                            if let (AlarmEvent::Fired(alarm), Some(script)) = (&event, &alarm_command) {
+                                script.run_as_oneshot(Some(&[alarm.label.clone()]));
+                            }
+
+                            send_async!(tx, ModuleUpdateEvent::Update(ClockEvent::Alarm(event)));
+                        }
+                        Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    },
+                    Some(command) = rx.recv() => match command {
+                        ClockCommand::AddAlarm { time, label } => alarms.add(time, label),
+                        ClockCommand::RemoveAlarm(id) => alarms.remove(id),
+                        ClockCommand::Acknowledge => alarms.acknowledge(),
+                    },
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder()
+            .angle(self.orientation.to_angle())
+            .use_markup(true)
+            .build();
+        button.add(&label);
+
+        let tx = context.tx.clone();
+        let controller_tx = context.controller_tx.clone();
+        button.connect_clicked(move |button| {
+            // the first click after an alarm fires acknowledges it
+            if button.style_context().has_class("alarm") {
+                try_send!(controller_tx, ClockCommand::Acknowledge);
+            } else {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            }
+        });
+
+        let format = self.format.clone();
+        let locale = Locale::try_from(self.locale.as_str()).unwrap_or(Locale::POSIX);
+
+        let rx = context.subscribe();
+        {
+            let button = button.clone();
+            glib_recv!(rx, event => match event {
+                ClockEvent::Time(date) => {
+                    let date_string = format!("{}", date.format_localized(&format, locale));
+                    label.set_label(&date_string);
+                }
+                ClockEvent::Alarm(AlarmEvent::Fired(_)) => button.add_class("alarm"),
+                ClockEvent::Alarm(AlarmEvent::Acknowledged) => button.set_class("alarm", false),
+                ClockEvent::Alarm(AlarmEvent::Changed(_)) => {}
+            });
+        }
+
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+
+        let clock = Label::builder()
+            .halign(Align::Center)
+            .use_markup(true)
+            .build();
+        clock.add_class("calendar-clock");
+
+        container.add(&clock);
+
+        let locale = Locale::try_from(self.locale.as_str()).unwrap_or(Locale::POSIX);
+        let calendar = calendar_page(self.format_calendar, locale, info.scroll);
+
+        // reopening returns to the current month
+        {
+            let calendar = calendar.clone();
+            container.connect_map(move |_| calendar.show_today());
+        }
+
+        let alarm_list = gtk::Box::new(Orientation::Vertical, 0);
+        alarm_list.add_class("alarm-list");
+
+        if self.show_tools {
+            let tools = Notebook::new();
+            tools.add_class("tools");
+
+            tools.append_page(
+                &calendar.container,
+                Some(&Label::new(Some(tr("clock.calendar")))),
+            );
+            tools.append_page(
+                &stopwatch_page(),
+                Some(&Label::new(Some(tr("clock.stopwatch")))),
+            );
+            tools.append_page(
+                &alarms_page(&alarm_list, &tx),
+                Some(&Label::new(Some(tr("clock.alarms")))),
+            );
+
+            container.add(&tools);
+        } else {
+            container.add(&calendar.container);
+        }
+
+        let format = self.format_popup;
+
+        glib_recv!(rx, event => match event {
+            ClockEvent::Time(date) => {
+                let date_string = format!("{}", date.format_localized(&format, locale));
+                clock.set_label(&date_string);
+                calendar.set_today(date.date_naive());
+            }
+            ClockEvent::Alarm(AlarmEvent::Changed(alarms)) => {
+                update_alarm_list(&alarm_list, &alarms, &tx);
+            }
+            ClockEvent::Alarm(_) => {}
+        });
+
+        container.show_all();
+
+        Some(container)
+    }
+}
+
+/// A calendar with a header showing the month,
+/// and buttons to page between months.
+#[derive(Debug, Clone)]
+struct CalendarPage {
+    container: gtk::Box,
+    calendar: Calendar,
+    /// The date marked as today.
+    today: Rc<Cell<NaiveDate>>,
+}
+
+/// Creates the calendar popup page.
+///
+/// Scrolling over the calendar also pages between months.
+fn calendar_page(format: String, locale: Locale, scroll: ScrollConfig) -> CalendarPage {
+    let container = gtk::Box::new(Orientation::Vertical, 0);
+
+    let header = gtk::Box::new(Orientation::Horizontal, 0);
+    header.add_class("calendar-header");
+
+    let btn_prev = Button::with_label("‹");
+    btn_prev.add_class("btn-prev");
+    let month = Label::new(None);
+    month.add_class("month");
+    let btn_next = Button::with_label("›");
+    btn_next.add_class("btn-next");
+
+    header.pack_start(&btn_prev, false, false, 0);
+    header.set_center_widget(Some(&month));
+    header.pack_end(&btn_next, false, false, 0);
+    container.add(&header);
+
+    // the month is shown in the header instead,
+    // so that it uses the configured format and locale
+    let calendar = Calendar::builder().show_heading(false).build();
+    calendar.add_class("calendar");
+    container.add(&calendar);
+
+    let page = CalendarPage {
+        container,
+        calendar: calendar.clone(),
+        today: Rc::new(Cell::new(Local::now().date_naive())),
+    };
+
+    {
+        let page = page.clone();
+        calendar.connect_month_changed(move |calendar| {
+            let (year, month_index, _) = calendar.date();
+
+            if let Some(first) = NaiveDate::from_ymd_opt(year as i32, month_index + 1, 1) {
+                month.set_label(&format!("{}", first.format_localized(&format, locale)));
+            }
+
+            page.mark_today();
+        });
+    }
+
+    {
+        let page = page.clone();
+        btn_prev.connect_clicked(move |_| page.shift_month(-1));
+    }
+
+    {
+        let page = page.clone();
+        btn_next.connect_clicked(move |_| page.shift_month(1));
+    }
+
+    {
+        let page = page.clone();
+        Scroller::new(scroll).connect(&calendar, move |_, step| match step {
+            ScrollStep::Up => page.shift_month(-1),
+            ScrollStep::Down => page.shift_month(1),
+        });
+    }
+
+    page.show_today();
+    page
+}
+
+impl CalendarPage {
+    /// Shows the current month, with today selected.
+    fn show_today(&self) {
+        let today = self.today.get();
+
+        self.calendar
+            .select_month(today.month0(), today.year() as u32);
+        self.calendar.select_day(today.day());
+        self.mark_today();
+    }
+
+    /// Updates the date marked as today, such as at midnight.
+    fn set_today(&self, date: NaiveDate) {
+        if self.today.replace(date) != date {
+            self.mark_today();
+        }
+    }
+
+    /// Marks today, if it is in the month shown.
+    fn mark_today(&self) {
+        let today = self.today.get();
+        let (year, month_index, _) = self.calendar.date();
+
+        self.calendar.clear_marks();
+        if year as i32 == today.year() && month_index == today.month0() {
+            self.calendar.mark_day(today.day());
+        }
+    }
+
+    fn shift_month(&self, months: i32) {
+        let (year, month_index, _) = self.calendar.date();
+        let (year, month_index) = shift_month(year, month_index, months);
+
+        self.calendar.select_month(month_index, year);
+    }
+}
+
+/// Moves forward or back a number of months,
+/// with months counted from zero as GTK does.
+fn shift_month(year: u32, month_index: u32, months: i32) -> (u32, u32) {
+    let total = i64::from(year) * 12 + i64::from(month_index) + i64::from(months);
+    let total = u32::try_from(total.max(0)).unwrap_or_default();
+
+    (total / 12, total % 12)
+}
+
+/// Creates the stopwatch popup page.
+fn stopwatch_page() -> gtk::Box {
+    let container = gtk::Box::new(Orientation::Vertical, 0);
+    container.add_class("stopwatch");
+
+    let time = Label::new(Some(&format_precise(Duration::ZERO)));
+    time.add_class("time");
+    container.add(&time);
+
+    let controls = gtk::Box::new(Orientation::Horizontal, 0);
+    controls.add_class("controls");
+
+    let btn_start = Button::with_label(tr("clock.start"));
+    btn_start.add_class("btn-start");
+    let btn_lap = Button::with_label(tr("clock.lap"));
+    btn_lap.add_class("btn-lap");
+    let btn_reset = Button::with_label(tr("clock.reset"));
+    btn_reset.add_class("btn-reset");
+
+    controls.add(&btn_start);
+    controls.add(&btn_lap);
+    controls.add(&btn_reset);
+    container.add(&controls);
+
+    let laps = gtk::Box::new(Orientation::Vertical, 0);
+    laps.add_class("laps");
+    container.add(&laps);
+
+    let stopwatch = Rc::new(RefCell::new(Stopwatch::default()));
+    let ticking = Rc::new(Cell::new(false));
+
+    {
+        let stopwatch = stopwatch.clone();
+        let time = time.clone();
+        btn_start.connect_clicked(move |button| {
+            let now = Instant::now();
+            let mut sw = stopwatch.borrow_mut();
+
+            if sw.is_running() {
+                sw.pause(now);
+                button.set_label(tr("clock.start"));
+                return;
+            }
+
+            sw.start(now);
+            button.set_label(tr("clock.pause"));
+
+            // only redraw while running
+            if !ticking.replace(true) {
+                let stopwatch = stopwatch.clone();
+                let time = time.clone();
+                let ticking = ticking.clone();
+                glib::timeout_add_local(Duration::from_millis(30), move || {
+                    let sw = stopwatch.borrow();
+                    time.set_label(&format_precise(sw.elapsed(Instant::now())));
+
+                    if sw.is_running() {
+                        ControlFlow::Continue
+                    } else {
+                        ticking.set(false);
+                        ControlFlow::Break
+                    }
+                });
+            }
+        });
+    }
+
+    {
+        let stopwatch = stopwatch.clone();
+        let laps = laps.clone();
+        btn_lap.connect_clicked(move |_| {
+            let mut sw = stopwatch.borrow_mut();
+            if !sw.is_running() {
+                return;
+            }
+
+            let now = Instant::now();
+            let (number, lap) = sw.lap(now);
+
+            let label = Label::new(Some(&format!(
+                "Lap {number}\t{}\t{}",
+                format_precise(lap),
+                format_precise(sw.elapsed(now))
+            )));
+            label.add_class("lap");
+            label.show();
+
+            laps.add(&label);
+            laps.reorder_child(&label, 0);
+        });
+    }
+
+    btn_reset.connect_clicked(move |_| {
+        stopwatch.borrow_mut().reset();
+
+        btn_start.set_label(tr("clock.start"));
+        time.set_label(&format_precise(Duration::ZERO));
+
+        for lap in laps.children() {
+            laps.remove(&lap);
+        }
+    });
+
+    container
+}
+
+/// Creates a spinner for one part of the alarm time.
+fn time_spinner(max: f64, value: i32) -> SpinButton {
+    let spinner = SpinButton::with_range(0.0, max, 1.0);
+    spinner.set_wrap(true);
+    spinner.set_numeric(true);
+    spinner.set_value(f64::from(value));
+
+    spinner.connect_output(|spinner| {
+        spinner.set_text(&format!("{:02}", spinner.value_as_int()));
+        Propagation::Stop
+    });
+
+    spinner
+}
+
+/// Creates the alarms popup page,
+/// with a row to add alarms above the list of alarms.
+fn alarms_page(alarm_list: &gtk::Box, tx: &mpsc::Sender<ClockCommand>) -> gtk::Box {
+    let container = gtk::Box::new(Orientation::Vertical, 0);
+    container.add_class("alarms");
+
+    let add_row = gtk::Box::new(Orientation::Horizontal, 0);
+    add_row.add_class("add");
+
+    let now = Local::now();
+    let hour = time_spinner(23.0, now.hour() as i32);
+    let minute = time_spinner(59.0, now.minute() as i32);
+
+    let label = Entry::new();
+    label.set_placeholder_text(Some(tr("clock.alarm_label")));
+    label.add_class("label");
+
+    let btn_add = Button::with_label(tr("clock.add"));
+    btn_add.add_class("btn-add");
+
+    add_row.add(&hour);
+    add_row.add(&Label::new(Some(":")));
+    add_row.add(&minute);
+    add_row.add(&label);
+    add_row.add(&btn_add);
+
+    {
+        let tx = tx.clone();
+        btn_add.connect_clicked(move |_| {
+            let time = NaiveTime::from_hms_opt(
+                hour.value_as_int() as u32,
+                minute.value_as_int() as u32,
+                0,
+            );
+
+            if let Some(time) = time {
+                try_send!(
+                    tx,
+                    ClockCommand::AddAlarm {
+                        time,
+                        label: label.text().to_string(),
+                    }
+                );
+                label.set_text("");
+            }
+        });
+    }
+
+    container.add(&add_row);
+    container.add(alarm_list);
+
+    container
+}
+
+/// Replaces the rows in the alarm list.
+fn update_alarm_list(alarm_list: &gtk::Box, alarms: &[Alarm], tx: &mpsc::Sender<ClockCommand>) {
+    for row in alarm_list.children() {
+        alarm_list.remove(&row);
+    }
+
+    for alarm in alarms {
+        let row = gtk::Box::new(Orientation::Horizontal, 0);
+        row.add_class("alarm");
+
+        let time = Label::new(Some(&alarm.time.format("%H:%M").to_string()));
+        time.add_class("time");
+
+        let label = Label::new(Some(&alarm.label));
+        label.add_class("label");
+        label.set_hexpand(true);
+        label.set_halign(Align::Start);
+
+        let btn_remove = Button::with_label("✕");
+        btn_remove.add_class("btn-remove");
+
+        {
+            let tx = tx.clone();
+            let id = alarm.id;
+            btn_remove.connect_clicked(move |_| {
+                try_send!(tx, ClockCommand::RemoveAlarm(id));
+            });
+        }
+
+        row.add(&time);
+        row.add(&label);
+        row.add(&btn_remove);
+        row.show_all();
+
+        alarm_list.add(&row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::testing::{config, ControllerHarness};
+
+    #[test]
+    fn shifts_months_across_years() {
+        assert_eq!(shift_month(2024, 0, -1), (2023, 11));
+        assert_eq!(shift_month(2024, 11, 1), (2025, 0));
+        assert_eq!(shift_month(2024, 5, 14), (2025, 7));
+        assert_eq!(shift_month(2024, 2, 0), (2024, 2));
+    }
+
+    #[test]
+    fn sends_current_time() {
+        let module: ClockModule = config(r#"{ "show_tools": false }"#);
+
+        let mut harness = ControllerHarness::new();
+        harness.start(&module).expect("controller to start");
+
+        let ClockEvent::Time(first) = harness.next_update() else {
+            panic!("expected time");
+        };
+        assert!((Local::now() - first).num_seconds().abs() < 5);
+
+        let ClockEvent::Time(second) = harness.next_update() else {
+            panic!("expected time");
+        };
+        assert!(second >= first);
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+/// Measures elapsed time while running,
+/// keeping a list of lap times.
+#[derive(Debug, Default)]
+pub struct Stopwatch {
+    /// Time elapsed before the current run started.
+    elapsed: Duration,
+    /// When the current run started, if running.
+    started: Option<Instant>,
+    /// The total elapsed time at the end of each lap.
+    laps: Vec<Duration>,
+}
+
+impl Stopwatch {
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        self.elapsed
+            + self
+                .started
+                .map_or(Duration::ZERO, |started| now.duration_since(started))
+    }
+
+    pub fn start(&mut self, now: Instant) {
+        self.started.get_or_insert(now);
+    }
+
+    pub fn pause(&mut self, now: Instant) {
+        self.elapsed = self.elapsed(now);
+        self.started = None;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Ends the current lap,
+    /// returning its number and duration.
+    pub fn lap(&mut self, now: Instant) -> (usize, Duration) {
+        let total = self.elapsed(now);
+        let previous = self.laps.last().copied().unwrap_or_default();
+
+        self.laps.push(total);
+        (self.laps.len(), total - previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_and_records_laps() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let mut stopwatch = Stopwatch::default();
+        stopwatch.start(start);
+        assert_eq!(stopwatch.lap(at(10)), (1, Duration::from_secs(10)));
+
+        stopwatch.pause(at(15));
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed(at(100)), Duration::from_secs(15));
+
+        stopwatch.start(at(100));
+        stopwatch.start(at(200));
+        assert_eq!(stopwatch.lap(at(105)), (2, Duration::from_secs(10)));
+        assert_eq!(stopwatch.elapsed(at(105)), Duration::from_secs(20));
+
+        stopwatch.reset();
+        assert_eq!(stopwatch.elapsed(at(300)), Duration::ZERO);
+    }
+}
@@ -0,0 +1,258 @@
+use super::{Widget, WidgetConfig, WidgetOrModule};
+use glib::Propagation;
+use gtk::gdk::keys::{self, Key};
+use gtk::gdk::{EventKey, ModifierType};
+use gtk::prelude::*;
+use gtk::Button;
+use serde::{Deserialize, Deserializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// The buttons in a popup which have an accel, and their parsed accels.
+pub type AccelButtons = Rc<RefCell<Vec<(Accel, Button)>>>;
+
+/// A key which clicks a button while its popup has keyboard focus,
+/// such as `s` or `<Ctrl>r`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Accel {
+    /// The key name, or a single character in lowercase.
+    key: String,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    super_key: bool,
+}
+
+impl FromStr for Accel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut accel = Self::default();
+        let mut rest = s.trim();
+
+        while let Some(modifier) = rest.strip_prefix('<') {
+            let (modifier, after) = modifier
+                .split_once('>')
+                .ok_or_else(|| format!("Unclosed modifier in accel '{s}'"))?;
+
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "primary" => accel.ctrl = true,
+                "shift" => accel.shift = true,
+                "alt" | "mod1" => accel.alt = true,
+                "super" | "mod4" => accel.super_key = true,
+                _ => return Err(format!("Unknown modifier '{modifier}' in accel '{s}'")),
+            }
+
+            rest = after;
+        }
+
+        let mut chars = rest.chars();
+        accel.key = match (chars.next(), chars.next()) {
+            (None, _) => return Err(format!("Missing key in accel '{s}'")),
+            (Some(char), None) => {
+                // an uppercase letter is typed with shift
+                if char.is_uppercase() {
+                    accel.shift = true;
+                }
+
+                char.to_lowercase().collect()
+            }
+            _ => {
+                if Key::from_name(rest) == keys::constants::VoidSymbol {
+                    return Err(format!("Unknown key '{rest}' in accel '{s}'"));
+                }
+
+                rest.to_string()
+            }
+        };
+
+        if accel.keyval().to_lower() == keys::constants::Escape {
+            return Err(String::from("Escape is reserved for closing the popup"));
+        }
+
+        Ok(accel)
+    }
+}
+
+impl Accel {
+    fn keyval(&self) -> Key {
+        let mut chars = self.key.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(char), None) => Key::from_unicode(char),
+            _ => Key::from_name(&self.key),
+        }
+    }
+
+    fn matches(&self, event: &EventKey) -> bool {
+        let keyval = self.keyval();
+        let pressed = event.keyval().to_lower();
+
+        if pressed != keyval.to_lower() {
+            return false;
+        }
+
+        let state = event.state();
+
+        // symbols such as `?` may need shift to type,
+        // so it is only checked for keys with an uppercase form or names
+        let has_case = keyval.to_lower() != keyval.to_upper() || self.key.chars().count() > 1;
+        let shift_matches = !has_case || state.contains(ModifierType::SHIFT_MASK) == self.shift;
+
+        shift_matches
+            && state.contains(ModifierType::CONTROL_MASK) == self.ctrl
+            && state.contains(ModifierType::MOD1_MASK) == self.alt
+            && state.contains(ModifierType::SUPER_MASK) == self.super_key
+    }
+}
+
+/// Clicks the button bound to each pressed key,
+/// so long as it is currently shown.
+///
+/// Key presses reach the container from the focused widget inside it,
+/// so this only applies while the popup has keyboard focus.
+pub fn connect_accels(container: &gtk::Box, buttons: AccelButtons) {
+    container.connect_key_press_event(move |_, event| {
+        let button = buttons
+            .borrow()
+            .iter()
+            .find(|(accel, button)| {
+                accel.matches(event) && button.is_mapped() && button.is_sensitive()
+            })
+            .map(|(_, button)| button.clone());
+
+        match button {
+            Some(button) => {
+                button.clicked();
+                Propagation::Stop
+            }
+            None => Propagation::Proceed,
+        }
+    });
+}
+
+/// Gets the accels of the buttons in the widgets,
+/// including those nested inside boxes and other buttons.
+fn collect_accels<'a>(widgets: &'a [WidgetConfig], accels: &mut Vec<&'a str>) {
+    for widget in widgets {
+        let WidgetOrModule::Widget(widget) = &widget.widget else {
+            continue;
+        };
+
+        match widget {
+            Widget::Button(button) => {
+                accels.extend(button.accel());
+                collect_accels(button.widgets(), accels);
+            }
+            Widget::Box(container) => collect_accels(container.widgets(), accels),
+            _ => {}
+        }
+    }
+}
+
+/// Checks every accel in the widgets is valid,
+/// and that no two buttons share the same accel.
+fn check_accels(widgets: &[WidgetConfig]) -> Result<(), String> {
+    let mut accels = vec![];
+    collect_accels(widgets, &mut accels);
+
+    let mut seen = HashMap::new();
+
+    for accel in accels {
+        if let Some(existing) = seen.insert(accel.parse::<Accel>()?, accel) {
+            return Err(format!(
+                "Accel '{accel}' conflicts with '{existing}' in the same popup"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Deserializes the top-level popup widgets,
+/// rejecting conflicting accels.
+pub fn deserialize_popup<'de, D>(deserializer: D) -> Result<Option<Vec<WidgetConfig>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let widgets = Option::<Vec<WidgetConfig>>::deserialize(deserializer)?;
+
+    if let Some(widgets) = &widgets {
+        check_accels(widgets).map_err(serde::de::Error::custom)?;
+    }
+
+    Ok(widgets)
+}
+
+/// Deserializes the widgets of a popup page,
+/// rejecting conflicting accels.
+///
+/// Each page is checked on its own,
+/// as only the buttons on the visible page can be clicked.
+pub fn deserialize_page<'de, D>(deserializer: D) -> Result<Vec<WidgetConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let widgets = Vec::<WidgetConfig>::deserialize(deserializer)?;
+    check_accels(&widgets).map_err(serde::de::Error::custom)?;
+
+    Ok(widgets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::custom::CustomModule;
+    use crate::modules::testing::config;
+
+    #[test]
+    fn parse_accels() {
+        let accel = "<Ctrl>r".parse::<Accel>().expect("valid accel");
+        assert_eq!(accel.key, "r");
+        assert!(accel.ctrl && !accel.shift);
+
+        assert_eq!("S".parse::<Accel>(), "<Shift>s".parse::<Accel>());
+        assert!("F5".parse::<Accel>().is_ok());
+
+        assert!("".parse::<Accel>().is_err());
+        assert!("<Hyper>s".parse::<Accel>().is_err());
+        assert!("NotAKey".parse::<Accel>().is_err());
+        assert!("Escape".parse::<Accel>().is_err());
+    }
+
+    #[test]
+    fn rejects_conflicting_accels() {
+        let module = serde_json::from_str::<CustomModule>(
+            r#"{
+              "bar": [],
+              "popup": [
+                { "type": "button", "label": "Shutdown", "accel": "s" },
+                { "type": "box", "widgets": [{ "type": "button", "label": "Suspend", "accel": "S" }] }
+              ]
+            }"#,
+        );
+        assert!(module.is_ok());
+
+        let module = serde_json::from_str::<CustomModule>(
+            r#"{
+              "bar": [],
+              "popup": [
+                { "type": "button", "label": "Shutdown", "accel": "s" },
+                { "type": "box", "widgets": [{ "type": "button", "label": "Suspend", "accel": "s" }] }
+              ]
+            }"#,
+        );
+        assert!(module.is_err());
+
+        // pages are checked separately
+        let _: CustomModule = config(
+            r#"{
+              "bar": [],
+              "popup": [{ "type": "button", "label": "Power", "accel": "p", "on_click": "goto_page:power" }],
+              "pages": [{ "name": "power", "widgets": [{ "type": "button", "label": "Poweroff", "accel": "p" }] }]
+            }"#,
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use super::{CustomWidget, CustomWidgetContext};
 use crate::build;
-use crate::config::ModuleOrientation;
+use crate::config::{ClassList, ModuleOrientation};
 use crate::modules::custom::WidgetConfig;
 use gtk::prelude::*;
 use serde::Deserialize;
@@ -16,7 +16,7 @@ pub struct BoxWidget {
     /// Widget class name.
     ///
     /// **Default**: `null`
-    class: Option<String>,
+    class: Option<ClassList>,
 
     /// Whether child widgets should be horizontally or vertically added.
     ///
@@ -31,6 +31,12 @@ pub struct BoxWidget {
     widgets: Option<Vec<WidgetConfig>>,
 }
 
+impl BoxWidget {
+    pub fn widgets(&self) -> &[WidgetConfig] {
+        self.widgets.as_deref().unwrap_or_default()
+    }
+}
+
 impl CustomWidget for BoxWidget {
     type Widget = gtk::Box;
 
@@ -1,12 +1,15 @@
 use gtk::prelude::*;
 use gtk::{Button, Label, Orientation};
 use serde::Deserialize;
+use tracing::error;
 
-use crate::config::ModuleOrientation;
+use crate::config::{ClassList, ModuleOrientation};
 use crate::dynamic_value::dynamic_string;
+use crate::gtk_helpers::IronbarGtkExt;
 use crate::modules::PopupButton;
 use crate::{build, try_send};
 
+use super::accel::Accel;
 use super::{CustomWidget, CustomWidgetContext, ExecEvent, WidgetConfig};
 
 #[derive(Debug, Deserialize, Clone)]
@@ -20,7 +23,7 @@ pub struct ButtonWidget {
     /// Widget class name.
     ///
     /// **Default**: `null`
-    class: Option<String>,
+    class: Option<ClassList>,
 
     /// Widget text label. Pango markup and embedded scripts are supported.
     ///
@@ -49,6 +52,25 @@ pub struct ButtonWidget {
     ///
     /// **Default**: `null`
     widgets: Option<Vec<WidgetConfig>>,
+
+    /// Key which clicks the button while its popup is open and has keyboard focus,
+    /// such as `s` or `<Ctrl>r`.
+    /// More on this [below](#accels).
+    ///
+    /// Only applies to buttons inside the popup.
+    ///
+    /// **Default**: `null`
+    accel: Option<String>,
+}
+
+impl ButtonWidget {
+    pub fn accel(&self) -> Option<&str> {
+        self.accel.as_deref()
+    }
+
+    pub fn widgets(&self) -> &[WidgetConfig] {
+        self.widgets.as_deref().unwrap_or_default()
+    }
 }
 
 impl CustomWidget for ButtonWidget {
@@ -56,30 +78,87 @@ impl CustomWidget for ButtonWidget {
 
     fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
         let button = build!(self, Self::Widget);
+        button.set_interactive();
         context.popup_buttons.borrow_mut().push(button.clone());
 
-        if let Some(widgets) = self.widgets {
+        let child: Option<gtk::Widget> = if let Some(widgets) = self.widgets {
             let container = gtk::Box::new(Orientation::Horizontal, 0);
 
             for widget in widgets {
                 widget.widget.add_to(&container, &context, widget.common);
             }
 
-            button.add(&container);
+            Some(container.upcast())
         } else if let Some(text) = self.label {
             let label = Label::new(None);
             label.set_use_markup(true);
 
             label.set_angle(self.orientation.to_angle());
 
-            button.add(&label);
+            {
+                let label = label.clone();
+                dynamic_string(&text, context.script_env, move |string| {
+                    label.set_markup(&string);
+                });
+            }
 
-            dynamic_string(&text, move |string| {
-                label.set_markup(&string);
-            });
+            Some(label.upcast())
+        } else {
+            None
+        };
+
+        let accel = self.accel.and_then(|text| match text.parse::<Accel>() {
+            Ok(accel) => Some((accel, text)),
+            Err(err) => {
+                error!("{err}");
+                None
+            }
+        });
+
+        let hint = match (&context.accels, accel) {
+            (Some(accels), Some((accel, text))) => {
+                accels.borrow_mut().push((accel, button.clone()));
+                context.show_accels.then_some(text)
+            }
+            (None, Some((_, text))) => {
+                error!("Cannot use accel '{text}' outside of a popup");
+                None
+            }
+            (_, None) => None,
+        };
+
+        match (child, hint) {
+            (child, Some(hint)) => {
+                let container = gtk::Box::new(Orientation::Horizontal, 0);
+
+                if let Some(child) = child {
+                    container.add(&child);
+                }
+
+                let hint = Label::new(Some(&hint));
+                hint.add_class("accel");
+                container.add(&hint);
+
+                button.add(&container);
+            }
+            (Some(child), None) => button.add(&child),
+            (None, None) => {}
         }
 
-        if let Some(exec) = self.on_click {
+        if let Some(page) = self
+            .on_click
+            .as_deref()
+            .and_then(|exec| exec.strip_prefix("goto_page:"))
+        {
+            // pages are switched on the UI side, as they belong to the popup widget tree
+            match context.pages.clone() {
+                Some(pages) => {
+                    let page = page.to_string();
+                    button.connect_clicked(move |_| pages.goto(&page));
+                }
+                None => error!("Cannot use 'goto_page' outside of a popup with pages"),
+            }
+        } else if let Some(exec) = self.on_click {
             let tx = context.tx.clone();
 
             button.connect_clicked(move |button| {
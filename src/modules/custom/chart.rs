@@ -0,0 +1,306 @@
+use glib::Propagation;
+use gtk::prelude::*;
+use gtk::DrawingArea;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use tracing::warn;
+
+use crate::build;
+use crate::config::ClassList;
+use crate::dynamic_value::dynamic_string;
+
+use super::{CustomWidget, CustomWidgetContext};
+
+/// The opacity of the area under the line,
+/// relative to the line colour.
+const FILL_OPACITY: f64 = 0.35;
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ChartStyle {
+    /// Draws only the line.
+    #[default]
+    Line,
+    /// Draws the line and fills the area beneath it.
+    Area,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ChartWidget {
+    /// Widget name.
+    ///
+    /// **Default**: `null`
+    name: Option<String>,
+
+    /// Widget class name.
+    ///
+    /// **Default**: `null`
+    class: Option<ClassList>,
+
+    /// The value to plot. Each time this changes, a new sample is added.
+    /// This must output a number.
+    ///
+    /// This is a [Dynamic String](dynamic-values#dynamic-string).
+    ///
+    /// **Required**
+    value: String,
+
+    /// The number of samples to keep and show.
+    ///
+    /// **Default**: `60`
+    #[serde(default = "default_samples")]
+    samples: usize,
+
+    /// How to draw the samples.
+    ///
+    /// **Valid options**: `line`, `area`
+    /// <br />
+    /// **Default**: `line`
+    #[serde(default)]
+    style: ChartStyle,
+
+    /// The value at the bottom of the chart.
+    /// If unset, the lowest sample is used.
+    ///
+    /// **Default**: `null`
+    min: Option<f64>,
+
+    /// The value at the top of the chart.
+    /// If unset, the highest sample is used.
+    ///
+    /// **Default**: `null`
+    max: Option<f64>,
+
+    /// The chart width, in pixels.
+    ///
+    /// **Default**: `60`
+    #[serde(default = "default_width")]
+    width: i32,
+
+    /// The chart height, in pixels.
+    ///
+    /// **Default**: `20`
+    #[serde(default = "default_height")]
+    height: i32,
+
+    /// The width of the line, in pixels.
+    ///
+    /// **Default**: `1.5`
+    #[serde(default = "default_line_width")]
+    line_width: f64,
+}
+
+const fn default_samples() -> usize {
+    60
+}
+
+const fn default_width() -> i32 {
+    60
+}
+
+const fn default_height() -> i32 {
+    20
+}
+
+const fn default_line_width() -> f64 {
+    1.5
+}
+
+impl CustomWidget for ChartWidget {
+    type Widget = DrawingArea;
+
+    fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
+        let area = build!(self, Self::Widget);
+        area.set_size_request(self.width, self.height);
+
+        let samples = Rc::new(RefCell::new(Samples::new(self.samples)));
+
+        {
+            let samples = samples.clone();
+            let range = (self.min, self.max);
+            let style = self.style;
+            let line_width = self.line_width;
+
+            area.connect_draw(move |area, cr| {
+                let style_context = area.style_context();
+
+                let width = f64::from(area.allocated_width());
+                let height = f64::from(area.allocated_height());
+
+                gtk::render_background(&style_context, cr, 0.0, 0.0, width, height);
+
+                let samples = samples.borrow();
+                let (min, max) = samples.range(range.0, range.1);
+                let points = samples.points(width, height, min, max);
+
+                let (Some(first), Some(last)) = (points.first(), points.last()) else {
+                    return Propagation::Proceed;
+                };
+
+                // values outside a fixed range would otherwise draw over neighbouring widgets
+                cr.rectangle(0.0, 0.0, width, height);
+                cr.clip();
+
+                let color = style_context.color(style_context.state());
+
+                let trace_line = || {
+                    cr.move_to(first.0, first.1);
+                    for (x, y) in &points[1..] {
+                        cr.line_to(*x, *y);
+                    }
+                };
+
+                if style == ChartStyle::Area {
+                    trace_line();
+                    cr.line_to(last.0, height);
+                    cr.line_to(first.0, height);
+                    cr.close_path();
+
+                    cr.set_source_rgba(
+                        color.red(),
+                        color.green(),
+                        color.blue(),
+                        color.alpha() * FILL_OPACITY,
+                    );
+
+                    if let Err(err) = cr.fill() {
+                        warn!("Failed to draw chart: {err}");
+                    }
+                }
+
+                trace_line();
+                cr.set_source_rgba(color.red(), color.green(), color.blue(), color.alpha());
+                cr.set_line_width(line_width);
+
+                if let Err(err) = cr.stroke() {
+                    warn!("Failed to draw chart: {err}");
+                }
+
+                Propagation::Proceed
+            });
+        }
+
+        {
+            let area = area.clone();
+            dynamic_string(&self.value, context.script_env, move |string| {
+                let Ok(value) = string.trim().parse::<f64>() else {
+                    warn!("Chart value '{string}' is not a number");
+                    return;
+                };
+
+                samples.borrow_mut().push(value);
+
+                // hidden charts are redrawn once shown
+                if area.is_mapped() {
+                    area.queue_draw();
+                }
+            });
+        }
+
+        area
+    }
+}
+
+/// The most recent values, oldest first.
+#[derive(Debug)]
+struct Samples {
+    values: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl Samples {
+    fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(2);
+
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Adds a value, removing the oldest once full.
+    fn push(&mut self, value: f64) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+
+        self.values.push_back(value);
+    }
+
+    /// Gets the values at the bottom and top of the chart,
+    /// using the lowest and highest samples where they are not fixed.
+    fn range(&self, min: Option<f64>, max: Option<f64>) -> (f64, f64) {
+        let min = min.unwrap_or_else(|| self.values.iter().copied().fold(f64::INFINITY, f64::min));
+        let max = max.unwrap_or_else(|| {
+            self.values
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max)
+        });
+
+        if min.is_finite() && max.is_finite() {
+            (min, max)
+        } else {
+            (0.0, 0.0)
+        }
+    }
+
+    /// Gets the position of each sample within the size.
+    ///
+    /// The newest sample is at the right edge,
+    /// so the chart fills from the right until full.
+    fn points(&self, width: f64, height: f64, min: f64, max: f64) -> Vec<(f64, f64)> {
+        let step = width / (self.capacity - 1) as f64;
+        let offset = (self.capacity - self.values.len()) as f64 * step;
+
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                // a flat line is drawn in the middle
+                let fraction = if max > min {
+                    (value - min) / (max - min)
+                } else {
+                    0.5
+                };
+
+                (offset + i as f64 * step, height - fraction * height)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_latest_samples() {
+        let mut samples = Samples::new(3);
+        for value in [1.0, 5.0, 3.0, 2.0] {
+            samples.push(value);
+        }
+
+        assert_eq!(samples.values, [5.0, 3.0, 2.0]);
+        assert_eq!(samples.range(None, None), (2.0, 5.0));
+        assert_eq!(samples.range(Some(0.0), Some(100.0)), (0.0, 100.0));
+        assert_eq!(Samples::new(3).range(None, None), (0.0, 0.0));
+    }
+
+    #[test]
+    fn scales_points_from_right() {
+        let mut samples = Samples::new(5);
+        samples.push(0.0);
+        samples.push(10.0);
+
+        assert_eq!(
+            samples.points(40.0, 20.0, 0.0, 10.0),
+            [(30.0, 20.0), (40.0, 0.0)]
+        );
+        assert_eq!(samples.points(40.0, 20.0, 5.0, 5.0)[0].1, 10.0);
+    }
+}
@@ -3,6 +3,7 @@ use gtk::Image;
 use serde::Deserialize;
 
 use crate::build;
+use crate::config::ClassList;
 use crate::dynamic_value::dynamic_string;
 use crate::image::ImageProvider;
 
@@ -19,7 +20,7 @@ pub struct ImageWidget {
     /// Widget class name.
     ///
     /// **Default**: `null`
-    class: Option<String>,
+    class: Option<ClassList>,
 
     /// Image source.
     ///
@@ -49,9 +50,10 @@ impl CustomWidget for ImageWidget {
         {
             let gtk_image = gtk_image.clone();
             let icon_theme = context.icon_theme.clone();
+            let size = context.info.scaled(self.size);
 
-            dynamic_string(&self.src, move |src| {
-                ImageProvider::parse(&src, &icon_theme, false, self.size)
+            dynamic_string(&self.src, context.script_env, move |src| {
+                ImageProvider::parse(&src, &icon_theme, false, size)
                     .map(|image| image.load_into_image(gtk_image.clone()));
             });
         }
@@ -3,7 +3,7 @@ use gtk::Label;
 use serde::Deserialize;
 
 use crate::build;
-use crate::config::ModuleOrientation;
+use crate::config::{ClassList, ModuleOrientation};
 use crate::dynamic_value::dynamic_string;
 
 use super::{CustomWidget, CustomWidgetContext};
@@ -19,7 +19,7 @@ pub struct LabelWidget {
     /// Widget class name.
     ///
     /// **Default**: `null`
-    class: Option<String>,
+    class: Option<ClassList>,
 
     /// Widget text label. Pango markup and embedded scripts are supported.
     ///
@@ -41,7 +41,7 @@ pub struct LabelWidget {
 impl CustomWidget for LabelWidget {
     type Widget = Label;
 
-    fn into_widget(self, _context: CustomWidgetContext) -> Self::Widget {
+    fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
         let label = build!(self, Self::Widget);
 
         label.set_angle(self.orientation.to_angle());
@@ -49,7 +49,7 @@ impl CustomWidget for LabelWidget {
 
         {
             let label = label.clone();
-            dynamic_string(&self.label, move |string| {
+            dynamic_string(&self.label, context.script_env, move |string| {
                 label.set_markup(&string);
             });
         }
@@ -1,23 +1,31 @@
+mod accel;
 mod r#box;
 mod button;
+mod chart;
 mod image;
 mod label;
+mod pages;
 mod progress;
 mod slider;
+mod toggle;
 
+use self::accel::AccelButtons;
+use self::chart::ChartWidget;
 use self::image::ImageWidget;
 use self::label::LabelWidget;
+use self::pages::{PageConfig, Pages};
 use self::r#box::BoxWidget;
 use self::slider::SliderWidget;
+use self::toggle::ToggleWidget;
 use crate::config::{CommonConfig, ModuleConfig};
 use crate::modules::custom::button::ButtonWidget;
 use crate::modules::custom::progress::ProgressWidget;
 use crate::modules::{
-    wrap_widget, AnyModuleFactory, BarModuleFactory, Module, ModuleInfo, ModuleParts, ModulePopup,
-    ModuleUpdateEvent, PopupButton, PopupModuleFactory, WidgetContext,
+    wrap_widget, AnyModuleFactory, BarModuleFactory, ControllerContext, Module, ModuleInfo,
+    ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, PopupModuleFactory, WidgetContext,
 };
-use crate::script::Script;
-use crate::{module_impl, send_async, spawn};
+use crate::script::{Script, ScriptEnv};
+use crate::{module_impl, send_async};
 use color_eyre::Result;
 use gtk::prelude::*;
 use gtk::{Button, IconTheme, Orientation};
@@ -37,9 +45,35 @@ pub struct CustomModule {
 
     /// Modules and widgets to add to the popup container.
     ///
+    /// Buttons in the popup with the same `accel` are rejected.
+    ///
     /// **Default**: `null`
+    #[serde(default, deserialize_with = "accel::deserialize_popup")]
     popup: Option<Vec<WidgetConfig>>,
 
+    /// Additional named pages to show inside the popup.
+    /// The `popup` widgets are shown on the first page, called `main`.
+    ///
+    /// Buttons can switch page using the `goto_page:<name>` command,
+    /// and each page has a button to return to the previous page.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    pages: Vec<PageConfig>,
+
+    /// Whether to keep showing the last page when the popup is reopened.
+    /// If disabled, the popup always reopens on the `main` page.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    remember_page: bool,
+
+    /// Whether to show each popup button's `accel` as a hint on the button.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    show_accels: bool,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -82,8 +116,12 @@ pub enum Widget {
     Image(ImageWidget),
     /// A draggable slider.
     Slider(SliderWidget),
+    /// An on/off switch or checkbox.
+    Toggle(ToggleWidget),
     /// A progress bar.
     Progress(ProgressWidget),
+    /// A sparkline chart of recent values.
+    Chart(ChartWidget),
 }
 
 #[derive(Clone)]
@@ -94,6 +132,12 @@ struct CustomWidgetContext<'a> {
     icon_theme: &'a IconTheme,
     popup_buttons: Rc<RefCell<Vec<Button>>>,
     module_factory: AnyModuleFactory,
+    script_env: &'a ScriptEnv,
+    /// The popup pages, if the widget is inside a paged popup.
+    pages: Option<Pages>,
+    /// The popup buttons with an accel, if the widget is inside a popup.
+    accels: Option<AccelButtons>,
+    show_accels: bool,
 }
 
 trait CustomWidget {
@@ -117,7 +161,9 @@ macro_rules! build {
         let widget = builder.build();
 
         if let Some(class) = &$self.class {
-            widget.style_context().add_class(class);
+            for class in class.iter() {
+                widget.style_context().add_class(class);
+            }
         }
 
         widget
@@ -140,9 +186,7 @@ impl WidgetOrModule {
         match self {
             WidgetOrModule::Widget(widget) => widget.add_to(parent, context, common),
             WidgetOrModule::Module(config) => {
-                if let Err(err) = config.create(&context.module_factory, parent, context.info) {
-                    error!("{err:?}");
-                }
+                config.create(&context.module_factory, parent, context.info);
             }
         }
     }
@@ -157,6 +201,7 @@ impl Widget {
                     &$widget.into_widget(context.clone()),
                     common,
                     context.bar_orientation,
+                    context.script_env,
                 )
             };
         }
@@ -167,7 +212,9 @@ impl Widget {
             Self::Button(widget) => create!(widget),
             Self::Image(widget) => create!(widget),
             Self::Slider(widget) => create!(widget),
+            Self::Toggle(widget) => create!(widget),
             Self::Progress(widget) => create!(widget),
+            Self::Chart(widget) => create!(widget),
         };
 
         parent.add(&event_box);
@@ -189,15 +236,16 @@ impl Module<gtk::Box> for CustomModule {
 
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
-        spawn(async move {
+        let script_env = context.script_env.clone();
+
+        context.spawn(async move {
             while let Some(event) = rx.recv().await {
                 if event.cmd.starts_with('!') {
-                    let script = Script::from(&event.cmd[1..]);
+                    let script = Script::from(&event.cmd[1..]).with_env(&script_env);
 
                     debug!("executing command: '{}'", script.cmd);
 
@@ -239,6 +287,10 @@ impl Module<gtk::Box> for CustomModule {
             popup_buttons: popup_buttons.clone(),
             module_factory: BarModuleFactory::new(context.ironbar.clone(), context.popup.clone())
                 .into(),
+            script_env: &context.script_env,
+            pages: None,
+            accels: None,
+            show_accels: false,
         };
 
         self.bar.clone().into_iter().for_each(|widget| {
@@ -283,7 +335,26 @@ impl Module<gtk::Box> for CustomModule {
     {
         let container = gtk::Box::new(Orientation::Horizontal, 0);
 
-        if let Some(popup) = self.popup {
+        let pages = (!self.pages.is_empty()).then(Pages::new);
+
+        let content = if let Some(pages) = &pages {
+            let content = gtk::Box::new(Orientation::Horizontal, 0);
+            pages.add_main(&content);
+            container.add(pages.stack());
+
+            if !self.remember_page {
+                let pages = pages.clone();
+                container.connect_unmap(move |_| pages.reset());
+            }
+
+            content
+        } else {
+            container.clone()
+        };
+
+        if self.popup.is_some() || pages.is_some() {
+            let accels = AccelButtons::default();
+
             let custom_context = CustomWidgetContext {
                 info,
                 tx: &tx,
@@ -296,13 +367,25 @@ impl Module<gtk::Box> for CustomModule {
                     context.button_id,
                 )
                 .into(),
+                script_env: &context.script_env,
+                pages: pages.clone(),
+                accels: Some(accels.clone()),
+                show_accels: self.show_accels,
             };
 
-            for widget in popup {
+            for widget in self.popup.unwrap_or_default() {
                 widget
                     .widget
-                    .add_to(&container, &custom_context, widget.common);
+                    .add_to(&content, &custom_context, widget.common);
             }
+
+            if let Some(pages) = &pages {
+                for page in self.pages {
+                    pages.add(page, &custom_context);
+                }
+            }
+
+            accel::connect_accels(&container, accels);
         }
 
         container.show_all();
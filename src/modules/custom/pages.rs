@@ -0,0 +1,139 @@
+use super::{accel, CustomWidgetContext, WidgetConfig};
+use crate::gtk_helpers::IronbarGtkExt;
+use gtk::prelude::*;
+use gtk::{Button, Orientation, Stack, StackTransitionType};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::error;
+
+/// The name of the page holding the top-level popup widgets.
+pub const MAIN_PAGE: &str = "main";
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PageConfig {
+    /// The unique name of the page, used to navigate to it.
+    /// This cannot be `main`.
+    ///
+    /// **Required**
+    name: String,
+
+    /// The label of the button which returns to the previous page.
+    ///
+    /// **Default**: `󰁍`
+    #[serde(default = "default_back_label")]
+    back_label: String,
+
+    /// Modules and widgets to add to the page.
+    ///
+    /// **Default**: `[]`
+    #[serde(default, deserialize_with = "accel::deserialize_page")]
+    widgets: Vec<WidgetConfig>,
+}
+
+fn default_back_label() -> String {
+    String::from("󰁍")
+}
+
+/// Navigation state for a popup split into pages.
+#[derive(Debug, Clone)]
+pub struct Pages {
+    stack: Stack,
+    /// The names of the pages navigated away from,
+    /// with the most recent last.
+    history: Rc<RefCell<Vec<String>>>,
+}
+
+impl Pages {
+    pub fn new() -> Self {
+        let stack = Stack::new();
+        stack.set_homogeneous(false);
+        stack.set_interpolate_size(true);
+
+        Self {
+            stack,
+            history: Rc::default(),
+        }
+    }
+
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    /// Adds the page holding the top-level popup widgets.
+    pub fn add_main(&self, content: &gtk::Box) {
+        content.add_class("page");
+        self.stack.add_named(content, MAIN_PAGE);
+    }
+
+    /// Creates the page and its widgets, and adds it to the stack.
+    pub fn add(&self, page: PageConfig, context: &CustomWidgetContext) {
+        if page.name == MAIN_PAGE || self.stack.child_by_name(&page.name).is_some() {
+            error!("Duplicate popup page name '{}'", page.name);
+            return;
+        }
+
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+        container.add_class("page");
+
+        let back = Button::with_label(&page.back_label);
+        back.add_class("back");
+        back.set_halign(gtk::Align::Start);
+
+        {
+            let pages = self.clone();
+            back.connect_clicked(move |_| pages.back());
+        }
+
+        let content = gtk::Box::new(Orientation::Horizontal, 0);
+        content.add_class("content");
+
+        for widget in page.widgets {
+            widget.widget.add_to(&content, context, widget.common);
+        }
+
+        container.add(&back);
+        container.add(&content);
+
+        self.stack.add_named(&container, &page.name);
+    }
+
+    /// Shows the named page, remembering the current page
+    /// so that the back button can return to it.
+    pub fn goto(&self, name: &str) {
+        if self.stack.child_by_name(name).is_none() {
+            error!("Popup page '{name}' does not exist");
+            return;
+        }
+
+        let current = self.stack.visible_child_name();
+        if current.as_deref() == Some(name) {
+            return;
+        }
+
+        if let Some(current) = current {
+            self.history.borrow_mut().push(current.to_string());
+        }
+
+        self.stack
+            .set_visible_child_full(name, StackTransitionType::SlideLeft);
+    }
+
+    /// Returns to the previously shown page.
+    pub fn back(&self) {
+        let previous = self.history.borrow_mut().pop();
+        let previous = previous.as_deref().unwrap_or(MAIN_PAGE);
+
+        self.stack
+            .set_visible_child_full(previous, StackTransitionType::SlideRight);
+    }
+
+    /// Returns straight to the main page without animating,
+    /// and forgets the navigation history.
+    pub fn reset(&self) {
+        self.history.borrow_mut().clear();
+        self.stack
+            .set_visible_child_full(MAIN_PAGE, StackTransitionType::None);
+    }
+}
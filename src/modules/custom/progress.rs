@@ -4,7 +4,7 @@ use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::error;
 
-use crate::config::ModuleOrientation;
+use crate::config::{ClassList, ModuleOrientation};
 use crate::dynamic_value::dynamic_string;
 use crate::modules::custom::set_length;
 use crate::script::{OutputStream, Script, ScriptInput};
@@ -23,7 +23,7 @@ pub struct ProgressWidget {
     /// Widget class name.
     ///
     /// **Default**: `null`
-    class: Option<String>,
+    class: Option<ClassList>,
 
     /// Orientation of the progress bar.
     ///
@@ -78,7 +78,7 @@ impl CustomWidget for ProgressWidget {
         }
 
         if let Some(value) = self.value {
-            let script = Script::from(value);
+            let script = Script::from(value).with_env(context.script_env);
             let progress = progress.clone();
 
             let (tx, rx) = mpsc::channel(128);
@@ -102,7 +102,7 @@ impl CustomWidget for ProgressWidget {
             let progress = progress.clone();
             progress.set_show_text(true);
 
-            dynamic_string(&text, move |string| {
+            dynamic_string(&text, context.script_env, move |string| {
                 progress.set_text(Some(&string));
             });
         }
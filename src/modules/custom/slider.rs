@@ -1,6 +1,7 @@
 use glib::Propagation;
 use std::cell::Cell;
 use std::ops::Neg;
+use std::rc::Rc;
 
 use gtk::prelude::*;
 use gtk::Scale;
@@ -8,7 +9,8 @@ use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::error;
 
-use crate::config::ModuleOrientation;
+use crate::config::{ClassList, ModuleOrientation};
+use crate::dynamic_value::dynamic_string;
 use crate::modules::custom::set_length;
 use crate::script::{OutputStream, Script, ScriptInput};
 use crate::{build, glib_recv_mpsc, spawn, try_send};
@@ -26,19 +28,23 @@ pub struct SliderWidget {
     /// Widget class name.
     ///
     /// **Default**: `null`
-    class: Option<String>,
+    class: Option<ClassList>,
 
     /// Orientation of the slider.
+    /// If unset, this follows the bar orientation.
     ///
     /// **Valid options**: `horizontal`, `vertical`, `h`, `v`
     /// <br />
-    /// **Default**: `horizontal`
-    #[serde(default)]
-    orientation: ModuleOrientation,
+    /// **Default**: `null`
+    orientation: Option<ModuleOrientation>,
 
     /// Script to run to get the slider value.
     /// Output must be a valid number.
     ///
+    /// This can also be a [Dynamic String](dynamic-values#dynamic-string)
+    /// when it contains a `{{script}}` or starts with a `#variable`,
+    /// which must produce a valid number.
+    ///
     /// **Default**: `null`
     value: Option<ScriptInput>,
 
@@ -51,6 +57,14 @@ pub struct SliderWidget {
     /// **Default**: `null`
     on_change: Option<String>,
 
+    /// Whether to only run `on_change` once the slider is released,
+    /// rather than for every value while it is dragged.
+    /// Scrolling and keyboard changes still run it straight away.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    change_on_release: bool,
+
     /// Minimum slider value.
     ///
     /// **Default**: `0`
@@ -97,7 +111,7 @@ impl CustomWidget for SliderWidget {
     fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
         let scale = build!(self, Self::Widget);
 
-        scale.set_orientation(self.orientation.into());
+        scale.set_orientation(self.orientation.map_or(context.bar_orientation, Into::into));
 
         if let Some(length) = self.length {
             set_length(&scale, length, context.bar_orientation);
@@ -106,6 +120,17 @@ impl CustomWidget for SliderWidget {
         scale.set_range(self.min, self.max);
         scale.set_draw_value(self.show_label);
 
+        // set while the slider is held, so that `value` updates do not fight the user
+        let dragging = Rc::new(Cell::new(false));
+
+        {
+            let dragging = dragging.clone();
+            scale.connect_button_press_event(move |_, _| {
+                dragging.set(true);
+                Propagation::Proceed
+            });
+        }
+
         if let Some(on_change) = self.on_change {
             let min = self.min;
             let max = self.max;
@@ -115,21 +140,7 @@ impl CustomWidget for SliderWidget {
             // GTK will spam the same value over and over
             let prev_value = Cell::new(scale.value());
 
-            scale.connect_scroll_event(move |scale, event| {
-                let value = scale.value();
-                let delta = event.delta().1.neg();
-
-                let delta = match (step, delta.is_sign_positive()) {
-                    (Some(step), true) => step,
-                    (Some(step), false) => -step,
-                    (None, _) => delta,
-                };
-
-                scale.set_value(value + delta);
-                Propagation::Proceed
-            });
-
-            scale.connect_change_value(move |_, _, val| {
+            let run_on_change = Rc::new(move |val: f64| {
                 // GTK will send values outside min/max range
                 let val = val.clamp(min, max);
 
@@ -145,30 +156,87 @@ impl CustomWidget for SliderWidget {
 
                     prev_value.set(val);
                 }
+            });
 
+            scale.connect_scroll_event(move |scale, event| {
+                let value = scale.value();
+                let delta = event.delta().1.neg();
+
+                let delta = match (step, delta.is_sign_positive()) {
+                    (Some(step), true) => step,
+                    (Some(step), false) => -step,
+                    (None, _) => delta,
+                };
+
+                scale.set_value(value + delta);
                 Propagation::Proceed
             });
+
+            {
+                let run_on_change = run_on_change.clone();
+                let dragging = dragging.clone();
+                let change_on_release = self.change_on_release;
+
+                scale.connect_change_value(move |_, _, val| {
+                    if !(change_on_release && dragging.get()) {
+                        run_on_change(val);
+                    }
+
+                    Propagation::Proceed
+                });
+            }
+
+            if self.change_on_release {
+                scale.connect_button_release_event(move |scale, _| {
+                    run_on_change(scale.value());
+                    Propagation::Proceed
+                });
+            }
         }
 
-        if let Some(value) = self.value {
-            let script = Script::from(value);
-            let scale = scale.clone();
+        {
+            let dragging = dragging.clone();
+            scale.connect_button_release_event(move |_, _| {
+                dragging.set(false);
+                Propagation::Proceed
+            });
+        }
 
+        if let Some(value) = self.value {
             let (tx, rx) = mpsc::channel(128);
 
-            spawn(async move {
-                script
-                    .run(None, move |stream, _success| match stream {
-                        OutputStream::Stdout(out) => match out.parse() {
+            match value {
+                ScriptInput::String(input) if input.contains("{{") || input.starts_with('#') => {
+                    dynamic_string(&input, context.script_env, move |value| {
+                        match value.trim().parse() {
                             Ok(value) => try_send!(tx, value),
                             Err(err) => error!("{err:?}"),
-                        },
-                        OutputStream::Stderr(err) => error!("{err:?}"),
-                    })
-                    .await;
-            });
+                        }
+                    });
+                }
+                value => {
+                    let script = Script::from(value).with_env(context.script_env);
+
+                    spawn(async move {
+                        script
+                            .run(None, move |stream, _success| match stream {
+                                OutputStream::Stdout(out) => match out.parse() {
+                                    Ok(value) => try_send!(tx, value),
+                                    Err(err) => error!("{err:?}"),
+                                },
+                                OutputStream::Stderr(err) => error!("{err:?}"),
+                            })
+                            .await;
+                    });
+                }
+            }
 
-            glib_recv_mpsc!(rx, value => scale.set_value(value));
+            let scale = scale.clone();
+            glib_recv_mpsc!(rx, value => {
+                if !dragging.get() {
+                    scale.set_value(value);
+                }
+            });
         }
 
         scale
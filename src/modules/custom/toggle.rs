@@ -0,0 +1,115 @@
+use gtk::prelude::*;
+use gtk::{CheckButton, Switch};
+use serde::Deserialize;
+
+use crate::config::ClassList;
+use crate::dynamic_value::DynamicBool;
+use crate::{build, try_send};
+
+use super::{CustomWidget, CustomWidgetContext, ExecEvent};
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ToggleStyle {
+    /// A sliding on/off switch.
+    #[default]
+    Switch,
+    /// A checkbox.
+    Checkbox,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ToggleWidget {
+    /// Widget name.
+    ///
+    /// **Default**: `null`
+    name: Option<String>,
+
+    /// Widget class name.
+    ///
+    /// **Default**: `null`
+    class: Option<ClassList>,
+
+    /// How to draw the toggle.
+    ///
+    /// **Valid options**: `switch`, `checkbox`
+    /// <br />
+    /// **Default**: `switch`
+    #[serde(default)]
+    style: ToggleStyle,
+
+    /// Whether the toggle is on.
+    /// The toggle follows this as it changes,
+    /// without running `on_toggle`.
+    ///
+    /// This is a [Dynamic Boolean](dynamic-values#dynamic-boolean).
+    ///
+    /// **Default**: `null`
+    value: Option<DynamicBool>,
+
+    /// Command to execute when the toggle is clicked.
+    /// More on this [below](#commands).
+    ///
+    /// Note that this will provide `on` or `off` as an argument.
+    ///
+    /// **Default**: `null`
+    on_toggle: Option<String>,
+}
+
+impl CustomWidget for ToggleWidget {
+    type Widget = gtk::Widget;
+
+    fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
+        let toggle: gtk::Widget = match self.style {
+            ToggleStyle::Switch => {
+                let switch = build!(self, Switch);
+                // switches otherwise stretch to fill the bar height
+                switch.set_valign(gtk::Align::Center);
+                switch.upcast()
+            }
+            ToggleStyle::Checkbox => build!(self, CheckButton).upcast(),
+        };
+
+        let handler = self.on_toggle.map(|on_toggle| {
+            let tx = context.tx.clone();
+
+            toggle.connect_notify_local(Some("active"), move |toggle, _| {
+                let state = if toggle.property::<bool>("active") {
+                    "on"
+                } else {
+                    "off"
+                };
+
+                try_send!(
+                    tx,
+                    ExecEvent {
+                        cmd: on_toggle.clone(),
+                        args: Some(vec![state.to_string()]),
+                        id: usize::MAX // ignored
+                    }
+                );
+            })
+        });
+
+        if let Some(value) = self.value {
+            let toggle = toggle.clone();
+
+            value.subscribe(context.script_env, move |active| {
+                // external changes should not run `on_toggle`
+                if let Some(handler) = &handler {
+                    toggle.block_signal(handler);
+                }
+
+                toggle.set_property("active", active);
+
+                if let Some(handler) = &handler {
+                    toggle.unblock_signal(handler);
+                }
+            });
+        }
+
+        toggle
+    }
+}
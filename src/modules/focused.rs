@@ -1,13 +1,16 @@
 use crate::clients::wayland::{self, ToplevelEvent};
 use crate::config::{CommonConfig, TruncateMode};
-use crate::gtk_helpers::IronbarGtkExt;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
 use crate::image::ImageProvider;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, try_send};
 use color_eyre::Result;
 use gtk::prelude::*;
 use gtk::Label;
 use serde::Deserialize;
+use std::collections::HashMap;
 use tokio::sync::mpsc;
 use tracing::debug;
 
@@ -31,6 +34,14 @@ pub struct FocusedModule {
     #[serde(default = "default_icon_size")]
     icon_size: i32,
 
+    /// The [image](images) to show when no icon can be found for the app,
+    /// such as for games with no desktop entry.
+    /// Set to `null` to hide the icon instead.
+    ///
+    /// **Default**: `icon:application-x-executable`
+    #[serde(default = "default_fallback_icon")]
+    fallback_icon: Option<String>,
+
     // -- common --
     /// See [truncate options](module-level-options#truncate-mode).
     ///
@@ -48,6 +59,7 @@ impl Default for FocusedModule {
             show_icon: crate::config::default_true(),
             show_title: crate::config::default_true(),
             icon_size: default_icon_size(),
+            fallback_icon: default_fallback_icon(),
             truncate: None,
             common: Some(CommonConfig::default()),
         }
@@ -58,22 +70,29 @@ const fn default_icon_size() -> i32 {
     32
 }
 
+fn default_fallback_icon() -> Option<String> {
+    Some("icon:application-x-executable".to_string())
+}
+
 impl Module<gtk::Box> for FocusedModule {
     type SendMessage = Option<(String, String)>;
     type ReceiveMessage = ();
 
     module_impl!("focused");
 
+    fn is_sensitive() -> bool {
+        true
+    }
+
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         _rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
         let wl = context.client::<wayland::Client>();
 
-        spawn(async move {
+        context.spawn(async move {
             let mut current = None;
 
             let mut wlrx = wl.subscribe_toplevels();
@@ -132,6 +151,7 @@ impl Module<gtk::Box> for FocusedModule {
         info: &ModuleInfo,
     ) -> Result<ModuleParts<gtk::Box>> {
         let icon_theme = info.icon_theme;
+        let icon_size = info.scaled(self.icon_size);
 
         let container = gtk::Box::new(info.bar_position.orientation(), 5);
 
@@ -145,21 +165,44 @@ impl Module<gtk::Box> for FocusedModule {
         label.add_class("label");
 
         if let Some(truncate) = self.truncate {
-            truncate.truncate_label(&label);
+            label.truncate(truncate);
         }
 
         container.add(&label);
 
         {
             let icon_theme = icon_theme.clone();
+
+            // the image input which loaded for each app ID,
+            // so that apps without an icon are not looked up again on each focus
+            let mut icon_inputs = HashMap::<String, Option<String>>::new();
+
             glib_recv!(context.subscribe(), data => {
                 if let Some((name, id)) = data {
                     if self.show_icon {
-                        match ImageProvider::parse(&id, &icon_theme, true, self.icon_size)
-                            .map(|image| image.load_into_image(icon.clone()))
-                        {
-                            Some(Ok(())) => icon.show(),
-                            _ => icon.hide(),
+                        let load = |input: &str| {
+                            ImageProvider::parse(input, &icon_theme, false, icon_size)
+                                .is_some_and(|image| image.load_into_image(icon.clone()).is_ok())
+                        };
+
+                        let loaded = match icon_inputs.get(&id) {
+                            Some(input) => input.as_deref().is_some_and(load),
+                            None => {
+                                let input = std::iter::once(id.as_str())
+                                    .chain(self.fallback_icon.as_deref())
+                                    .find(|input| load(input))
+                                    .map(ToString::to_string);
+
+                                let loaded = input.is_some();
+                                icon_inputs.insert(id.clone(), input);
+                                loaded
+                            }
+                        };
+
+                        if loaded {
+                            icon.show();
+                        } else {
+                            icon.hide();
                         }
                     }
 
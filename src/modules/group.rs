@@ -0,0 +1,146 @@
+use crate::config::{CommonConfig, ModuleConfig, TransitionType};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::module_impl;
+use crate::modules::{
+    AnyModuleFactory, BarModuleFactory, ControllerContext, Module, ModuleInfo, ModuleParts,
+    WidgetContext,
+};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Revealer};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GroupModule {
+    /// The modules to place inside the group.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    modules: Vec<ModuleConfig>,
+
+    /// The gap in pixels between modules inside the group.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    spacing: i32,
+
+    /// Whether to show a button which collapses the group,
+    /// hiding its modules.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    collapsible: bool,
+
+    /// Whether the group should start collapsed.
+    /// Has no effect unless `collapsible` is set.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    start_collapsed: bool,
+
+    /// The icon to show on the toggle button while the group is expanded.
+    ///
+    /// **Default**: `󰅁`
+    #[serde(default = "default_icon_expanded")]
+    icon_expanded: String,
+
+    /// The icon to show on the toggle button while the group is collapsed.
+    ///
+    /// **Default**: `󰅂`
+    #[serde(default = "default_icon_collapsed")]
+    icon_collapsed: String,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon_expanded() -> String {
+    String::from("󰅁")
+}
+
+fn default_icon_collapsed() -> String {
+    String::from("󰅂")
+}
+
+impl Module<gtk::Box> for GroupModule {
+    type SendMessage = ();
+    type ReceiveMessage = ();
+
+    module_impl!("group");
+
+    fn spawn_controller(
+        &self,
+        _context: &ControllerContext<Self::SendMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let orientation = info.bar_position.orientation();
+
+        let container = gtk::Box::new(orientation, 0);
+
+        let content = gtk::Box::new(orientation, self.spacing);
+        content.add_class("contents");
+
+        // nested modules are created individually with the bar's info,
+        // so their popups are still positioned against their own widget.
+        let module_factory: AnyModuleFactory =
+            BarModuleFactory::new(context.ironbar.clone(), context.popup.clone()).into();
+
+        for module in self.modules {
+            module.create(&module_factory, &content, info);
+        }
+
+        if self.collapsible {
+            let revealer = Revealer::builder()
+                .transition_type(
+                    TransitionType::SlideStart.to_revealer_transition_type(orientation),
+                )
+                .reveal_child(!self.start_collapsed)
+                .build();
+
+            revealer.add(&content);
+
+            let (icon_expanded, icon_collapsed) = (self.icon_expanded, self.icon_collapsed);
+            let icon = move |revealed| {
+                if revealed {
+                    icon_expanded.clone()
+                } else {
+                    icon_collapsed.clone()
+                }
+            };
+
+            let button = Button::with_label(&icon(!self.start_collapsed));
+            button.add_class("toggle");
+
+            {
+                let revealer = revealer.clone();
+
+                button.connect_clicked(move |button| {
+                    let revealed = !revealer.reveals_child();
+                    revealer.set_reveal_child(revealed);
+                    button.set_label(&icon(revealed));
+                });
+            }
+
+            container.add(&button);
+            container.add(&revealer);
+        } else {
+            container.add(&content);
+        }
+
+        Ok(ModuleParts {
+            widget: container,
+            popup: None,
+        })
+    }
+}
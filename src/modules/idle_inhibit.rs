@@ -0,0 +1,203 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::new_icon_label;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, try_send};
+use color_eyre::{Report, Result};
+use gtk::prelude::*;
+use gtk::{Button, Orientation};
+use serde::Deserialize;
+use std::future;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+use tracing::debug;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IdleInhibitModule {
+    /// Icon to show while idle is inhibited.
+    ///
+    /// **Default**: `󰅶`
+    #[serde(default = "default_icon_active")]
+    icon_active: String,
+
+    /// Icon to show while idle is not inhibited.
+    ///
+    /// **Default**: `󰾪`
+    #[serde(default = "default_icon_inactive")]
+    icon_inactive: String,
+
+    /// The size to render the icons at.
+    /// Note this only applies to image-type icons.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// The number of seconds after which the inhibitor is automatically released.
+    /// The countdown restarts each time the inhibitor is turned on.
+    ///
+    /// Leave null to keep it until it is turned off.
+    ///
+    /// **Default**: `null`
+    timeout: Option<u64>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon_active() -> String {
+    String::from("󰅶")
+}
+
+fn default_icon_inactive() -> String {
+    String::from("󰾪")
+}
+
+const fn default_icon_size() -> i32 {
+    24
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleInhibitAction {
+    Toggle,
+    Set(bool),
+}
+
+impl IdleInhibitAction {
+    /// Gets whether idle should be inhibited after the action.
+    const fn apply(self, inhibited: bool) -> bool {
+        match self {
+            Self::Toggle => !inhibited,
+            Self::Set(inhibit) => inhibit,
+        }
+    }
+}
+
+impl Module<Button> for IdleInhibitModule {
+    type SendMessage = bool;
+    type ReceiveMessage = IdleInhibitAction;
+
+    module_impl!("idle_inhibit");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let wayland = context.clients.borrow_mut().wayland();
+        let timeout = self.timeout.map(Duration::from_secs);
+
+        context.spawn(async move {
+            // dropping this releases the inhibitor
+            let mut guard = None;
+            let mut deadline = None;
+
+            send_async!(tx, ModuleUpdateEvent::Update(false));
+
+            loop {
+                let action = select! {
+                    action = rx.recv() => {
+                        let Some(action) = action else {
+                            break;
+                        };
+                        action
+                    }
+                    () = async {
+                        match deadline {
+                            Some(deadline) => sleep_until(deadline).await,
+                            None => future::pending().await,
+                        }
+                    } => {
+                        debug!("Idle inhibitor timed out");
+                        IdleInhibitAction::Set(false)
+                    }
+                };
+
+                let inhibit = action.apply(guard.is_some());
+
+                if inhibit {
+                    guard.get_or_insert_with(|| wayland.inhibit_idle());
+                    deadline = timeout.map(|timeout| Instant::now() + timeout);
+                } else {
+                    guard = None;
+                    deadline = None;
+                }
+
+                send_async!(tx, ModuleUpdateEvent::Update(inhibit));
+            }
+        });
+
+        Ok(())
+    }
+
+    fn parse_action(action: &str, _args: &[String]) -> Result<Self::ReceiveMessage> {
+        match action {
+            "toggle" => Ok(IdleInhibitAction::Toggle),
+            "on" => Ok(IdleInhibitAction::Set(true)),
+            "off" => Ok(IdleInhibitAction::Set(false)),
+            _ => Err(Report::msg(format!("Unknown action '{action}'"))),
+        }
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let button_contents = gtk::Box::new(Orientation::Horizontal, 0);
+        button.add(&button_contents);
+
+        let icon_size = info.scaled(self.icon_size);
+        let icon_active = new_icon_label(&self.icon_active, info.icon_theme, icon_size);
+        let icon_inactive = new_icon_label(&self.icon_inactive, info.icon_theme, icon_size);
+
+        button_contents.add(&icon_active);
+        button_contents.add(&icon_inactive);
+
+        {
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| {
+                try_send!(tx, IdleInhibitAction::Toggle);
+            });
+        }
+
+        {
+            let button = button.clone();
+
+            glib_recv!(context.subscribe(), inhibited => {
+                icon_active.set_visible(inhibited);
+                icon_inactive.set_visible(!inhibited);
+                button.set_class("inhibited", inhibited);
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_actions() {
+        assert!(IdleInhibitAction::Toggle.apply(false));
+        assert!(!IdleInhibitAction::Toggle.apply(true));
+        assert!(IdleInhibitAction::Set(true).apply(true));
+        assert!(!IdleInhibitAction::Set(false).apply(true));
+
+        assert_eq!(
+            IdleInhibitModule::parse_action("on", &[]).ok(),
+            Some(IdleInhibitAction::Set(true))
+        );
+        assert!(IdleInhibitModule::parse_action("sleep", &[]).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::clients::compositor::{KeyboardLayoutUpdate, WorkspaceClient};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, try_send};
+use color_eyre::{Report, Result};
+use gtk::prelude::*;
+use gtk::{Button, Label};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct KeyboardModule {
+    /// A map of layout names to the text to show for them.
+    ///
+    /// Names can be given in full, such as `English (US)`,
+    /// or without the variant, such as `English`.
+    /// Any variant is then shown after the mapped name.
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    layout_map: HashMap<String, String>,
+
+    /// Whether to show the layout variant,
+    /// such as the `(US)` in `English (US)`.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    show_variant: bool,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+/// Gets the text to show for the layout.
+///
+/// The full name is looked up in the map first,
+/// falling back to the name without its variant.
+fn display_name(layout: &str, layout_map: &HashMap<String, String>, show_variant: bool) -> String {
+    if let Some(name) = layout_map.get(layout) {
+        return name.clone();
+    }
+
+    let (base, variant) = layout
+        .strip_suffix(')')
+        .and_then(|layout| layout.rsplit_once(" ("))
+        .map_or((layout, None), |(base, variant)| (base, Some(variant)));
+
+    let base = layout_map.get(base).map_or(base, String::as_str);
+
+    match variant {
+        Some(variant) if show_variant => format!("{base} ({variant})"),
+        _ => base.to_string(),
+    }
+}
+
+impl Module<Button> for KeyboardModule {
+    type SendMessage = KeyboardLayoutUpdate;
+    type ReceiveMessage = ();
+
+    module_impl!("keyboard");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let client = context.clients.borrow_mut().workspaces()?;
+
+        context.spawn(async move {
+            let mut srx = client.subscribe_keyboard_layout();
+
+            while let Ok(update) = srx.recv().await {
+                debug!("Received keyboard layout: {}", update.0);
+                send_async!(tx, ModuleUpdateEvent::Update(update));
+            }
+        });
+
+        let client = context.try_client::<dyn WorkspaceClient>()?;
+
+        context.spawn(async move {
+            while let Some(()) = rx.recv().await {
+                // a failed switch should not stop the module
+                if let Err(err) = client.next_keyboard_layout() {
+                    error!("{:?}", err.wrap_err("Failed to switch keyboard layout"));
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn parse_action(action: &str, _args: &[String]) -> Result<Self::ReceiveMessage> {
+        match action {
+            "next" => Ok(()),
+            _ => Err(Report::msg(format!("Unknown action '{action}'"))),
+        }
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::new(None);
+        label.add_class("layout");
+        button.add(&label);
+
+        {
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| {
+                try_send!(tx, ());
+            });
+        }
+
+        {
+            let layout_map = self.layout_map;
+            let show_variant = self.show_variant;
+
+            glib_recv!(context.subscribe(), update => {
+                let KeyboardLayoutUpdate(layout) = update;
+
+                label.set_label(&display_name(&layout, &layout_map, show_variant));
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_layout_names() {
+        let layout_map = HashMap::from([
+            (String::from("English (US)"), String::from("EN")),
+            (String::from("German"), String::from("DE")),
+        ]);
+
+        assert_eq!(display_name("English (US)", &layout_map, true), "EN");
+        assert_eq!(
+            display_name("German (Neo 2)", &layout_map, true),
+            "DE (Neo 2)"
+        );
+        assert_eq!(display_name("German (Neo 2)", &layout_map, false), "DE");
+        assert_eq!(display_name("French", &layout_map, false), "French");
+        assert_eq!(
+            display_name("English (UK) (Dvorak)", &HashMap::new(), false),
+            "English (UK)"
+        );
+    }
+}
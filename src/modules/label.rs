@@ -1,6 +1,8 @@
 use crate::config::CommonConfig;
 use crate::dynamic_value::dynamic_string;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
 use crate::{glib_recv, module_impl, try_send};
 use color_eyre::Result;
 use gtk::prelude::*;
@@ -39,12 +41,11 @@ impl Module<Label> for LabelModule {
 
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         _rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
-        dynamic_string(&self.label, move |string| {
+        dynamic_string(&self.label, &context.script_env, move |string| {
             try_send!(tx, ModuleUpdateEvent::Update(string));
         });
 
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Matches app IDs to the favourite they belong to.
+///
+/// Favourites can be given by desktop file ID,
+/// which does not always match the app ID of its windows,
+/// so apps are also matched by their desktop file.
+#[derive(Debug, Default)]
+pub struct Favorites {
+    /// The favourite IDs, and their desktop file if one was found.
+    files: Vec<(String, Option<PathBuf>)>,
+    /// Each app ID seen so far, and the key of the item it belongs to.
+    keys: HashMap<String, String>,
+}
+
+impl Favorites {
+    pub fn new<F>(favorites: Vec<String>, find_desktop_file: F) -> Self
+    where
+        F: Fn(&str) -> Option<PathBuf>,
+    {
+        let files = favorites
+            .into_iter()
+            .map(|favorite| {
+                let file = find_desktop_file(&favorite);
+                (favorite, file)
+            })
+            .collect();
+
+        Self {
+            files,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Gets the key of the item which the app's windows belong to.
+    /// This is the favourite sharing the app's desktop file if there is one,
+    /// or the app ID otherwise.
+    ///
+    /// The desktop file is only looked up the first time an app ID is seen.
+    pub fn key<F>(&mut self, app_id: &str, find_desktop_file: F) -> String
+    where
+        F: Fn(&str) -> Option<PathBuf>,
+    {
+        if let Some(key) = self.keys.get(app_id) {
+            return key.clone();
+        }
+
+        let key = if self.files.iter().any(|(favorite, _)| favorite == app_id) {
+            app_id.to_string()
+        } else {
+            let file = find_desktop_file(app_id);
+
+            self.files
+                .iter()
+                .find(|(_, favorite_file)| file.is_some() && *favorite_file == file)
+                .map_or_else(|| app_id.to_string(), |(favorite, _)| favorite.clone())
+        };
+
+        self.keys.insert(app_id.to_string(), key.clone());
+        key
+    }
+
+    /// Gets the key of the item for an app ID already seen by [`Self::key`],
+    /// or the app ID otherwise.
+    pub fn get(&self, app_id: &str) -> String {
+        self.keys
+            .get(app_id)
+            .cloned()
+            .unwrap_or_else(|| app_id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_desktop_file(app_id: &str) -> Option<PathBuf> {
+        match app_id {
+            "firefox" | "org.mozilla.firefox" => Some(PathBuf::from("firefox.desktop")),
+            "org.wezfurlong.wezterm" => Some(PathBuf::from("org.wezfurlong.wezterm.desktop")),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn matches_favorites_by_desktop_file() {
+        let mut favorites = Favorites::new(
+            vec![
+                String::from("firefox"),
+                String::from("org.wezfurlong.wezterm"),
+                String::from("missing"),
+            ],
+            find_desktop_file,
+        );
+
+        assert_eq!(
+            favorites.key("org.mozilla.firefox", find_desktop_file),
+            "firefox"
+        );
+        assert_eq!(favorites.get("org.mozilla.firefox"), "firefox");
+        assert_eq!(
+            favorites.key("org.wezfurlong.wezterm", find_desktop_file),
+            "org.wezfurlong.wezterm"
+        );
+
+        // apps without a desktop file do not match favourites without one
+        assert_eq!(favorites.key("foot", find_desktop_file), "foot");
+        assert_eq!(favorites.get("unseen"), "unseen");
+    }
+}
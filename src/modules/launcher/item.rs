@@ -1,14 +1,16 @@
 use super::open_state::OpenState;
-use crate::clients::wayland::ToplevelInfo;
-use crate::config::BarPosition;
-use crate::gtk_helpers::IronbarGtkExt;
+use crate::clients::wayland::{ToplevelInfo, ToplevelPreview};
+use crate::config::{BarPosition, TruncateMode};
+use crate::gtk_helpers::{window_drag_target, IronbarGtkExt, IronbarLabelExt};
 use crate::image::ImageProvider;
 use crate::modules::launcher::{ItemEvent, LauncherUpdate};
 use crate::modules::ModuleUpdateEvent;
 use crate::{read_lock, try_send};
 use glib::Propagation;
+use gtk::gdk::{DragAction, ModifierType};
+use gtk::gdk_pixbuf::{Colorspace, InterpType, Pixbuf};
 use gtk::prelude::*;
-use gtk::{Button, IconTheme};
+use gtk::{Button, IconTheme, Image, Label, Orientation};
 use indexmap::IndexMap;
 use std::rc::Rc;
 use std::sync::RwLock;
@@ -66,15 +68,53 @@ impl Item {
         }
     }
 
+    /// Sets the window's focus state.
+    /// Focusing a window clears its urgency.
     pub fn set_window_focused(&mut self, window_id: usize, focused: bool) {
         if let Some(window) = self.windows.get_mut(&window_id) {
             window.open_state =
                 OpenState::merge_states(&[&window.open_state, &OpenState::focused(focused)]);
 
+            if focused {
+                window.urgent = false;
+            }
+
             self.recalculate_open_state();
         }
     }
 
+    /// Marks the window with the title as urgent or not urgent.
+    ///
+    /// Compositors do not share window IDs with the toplevel protocol,
+    /// so if no window has the title, an unfocused window is marked instead,
+    /// or when clearing urgency, any urgent window.
+    pub fn set_window_urgent(&mut self, title: &str, urgent: bool) {
+        let window = self
+            .windows
+            .values()
+            .position(|window| window.name == title)
+            .or_else(|| {
+                self.windows.values().position(|window| {
+                    if urgent {
+                        !window.open_state.is_focused()
+                    } else {
+                        window.urgent
+                    }
+                })
+            })
+            .and_then(|index| self.windows.get_index_mut(index));
+
+        if let Some((_, window)) = window {
+            // focused windows cannot demand attention
+            window.urgent = urgent && !window.open_state.is_focused();
+        }
+    }
+
+    /// Whether any of the item's windows are urgent.
+    pub fn is_urgent(&self) -> bool {
+        self.windows.values().any(|window| window.urgent)
+    }
+
     /// Sets this item's open state
     /// to the merged result of its windows' open states
     fn recalculate_open_state(&mut self) {
@@ -115,6 +155,7 @@ pub struct Window {
     pub id: usize,
     pub name: String,
     pub open_state: OpenState,
+    pub urgent: bool,
 }
 
 impl From<ToplevelInfo> for Window {
@@ -125,12 +166,152 @@ impl From<ToplevelInfo> for Window {
             id: info.id,
             name: info.title,
             open_state,
+            urgent: false,
+        }
+    }
+}
+
+/// The width previews are scaled down to fit in the popup.
+const PREVIEW_WIDTH: i32 = 200;
+
+/// A window's button in the popup, which focuses the window when clicked.
+#[derive(Debug, Clone)]
+pub struct WindowButton {
+    pub button: Button,
+    label: Label,
+    preview: Option<Image>,
+}
+
+impl WindowButton {
+    /// Creates the button for the window.
+    ///
+    /// If `icon` is set, the button shows a preview of the window above its title,
+    /// which is the app icon until a preview has been captured.
+    pub fn new(
+        window: &Window,
+        icon: Option<&ImageProvider>,
+        truncate: TruncateMode,
+        controller_tx: &Sender<ItemEvent>,
+    ) -> Self {
+        let button = Button::builder().height_request(40).build();
+
+        let label = Label::new(Some(&window.name));
+        label.truncate(truncate);
+
+        let preview = icon.map(|icon| {
+            let container = gtk::Box::new(Orientation::Vertical, 5);
+
+            let image = Image::new();
+            image.add_class("preview");
+
+            if let Err(err) = icon.load_into_image(image.clone()) {
+                error!("{err:?}");
+            }
+
+            container.add(&image);
+            container.add(&label);
+            button.add(&container);
+
+            image
+        });
+
+        if preview.is_none() {
+            button.add(&label);
+        }
+
+        {
+            let tx = controller_tx.clone();
+            let id = window.id;
+            button.connect_clicked(move |_| {
+                try_send!(tx, ItemEvent::FocusWindow(id));
+            });
         }
+
+        Self {
+            button,
+            label,
+            preview,
+        }
+    }
+
+    pub fn set_title(&self, title: &str) {
+        self.label.set_label(title);
+    }
+
+    /// Replaces the app icon with the captured preview,
+    /// if the button shows previews.
+    pub fn set_preview(&self, preview: &ToplevelPreview) {
+        let Some(image) = &self.preview else {
+            return;
+        };
+
+        let pixbuf = Pixbuf::from_bytes(
+            &glib::Bytes::from(&preview.pixels),
+            Colorspace::Rgb,
+            false,
+            8,
+            preview.width,
+            preview.height,
+            preview.width * 3,
+        );
+
+        let height = preview.height * PREVIEW_WIDTH / preview.width.max(1);
+        let pixbuf = pixbuf
+            .scale_simple(PREVIEW_WIDTH, height.max(1), InterpType::Bilinear)
+            .unwrap_or(pixbuf);
+
+        image.set_from_pixbuf(Some(&pixbuf));
     }
 }
 
 pub struct MenuState {
-    pub num_windows: usize,
+    /// The IDs of the item's windows, in the order they were opened.
+    pub windows: Vec<usize>,
+    /// The item's focused window, if any.
+    pub focused: Option<usize>,
+}
+
+impl MenuState {
+    pub fn new(item: &Item) -> Self {
+        Self {
+            windows: item.windows.keys().copied().collect(),
+            focused: item
+                .windows
+                .values()
+                .find(|window| window.open_state.is_focused())
+                .map(|window| window.id),
+        }
+    }
+
+    /// Gets the window to move when the item is dragged,
+    /// which is the focused window, or otherwise the first.
+    fn drag_window(&self) -> Option<usize> {
+        self.focused.or_else(|| self.windows.first().copied())
+    }
+
+    pub fn add_window(&mut self, window: &Window) {
+        self.windows.push(window.id);
+
+        if window.open_state.is_focused() {
+            self.focused = Some(window.id);
+        }
+    }
+
+    pub fn remove_window(&mut self, id: usize) {
+        self.windows.retain(|&window| window != id);
+
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
+    }
+
+    pub fn set_focused(&mut self, id: usize, focused: bool) {
+        if focused {
+            self.focused = Some(id);
+        } else if self.focused == Some(id) {
+            self.focused = None;
+        }
+    }
 }
 
 pub struct ItemButton {
@@ -144,6 +325,7 @@ pub struct ItemButton {
 pub struct AppearanceOptions {
     pub show_names: bool,
     pub show_icons: bool,
+    pub show_previews: bool,
     pub icon_size: i32,
 }
 
@@ -163,6 +345,7 @@ impl ItemButton {
         }
 
         let button = button.build();
+        button.set_interactive();
 
         if appearance.show_icons {
             let gtk_image = gtk::Image::new();
@@ -194,6 +377,9 @@ impl ItemButton {
         if item.open_state.is_focused() {
             style_context.add_class("focused");
         }
+        if item.is_urgent() {
+            style_context.add_class("urgent");
+        }
 
         {
             let app_id = item.app_id.clone();
@@ -209,9 +395,26 @@ impl ItemButton {
             });
         }
 
-        let menu_state = Rc::new(RwLock::new(MenuState {
-            num_windows: item.windows.len(),
-        }));
+        set_draggable(&button, item.open_state.is_open());
+
+        {
+            let tx = tx.clone();
+            button.connect_drag_begin(move |_, _| {
+                try_send!(tx, ModuleUpdateEvent::ClosePopup);
+            });
+        }
+
+        let menu_state = Rc::new(RwLock::new(MenuState::new(item)));
+
+        {
+            let menu_state = menu_state.clone();
+            button.connect_drag_data_get(move |_, _, data, _, _| {
+                // workspaces look the window up by its toplevel ID
+                let id = read_lock!(menu_state).drag_window();
+                let id = id.map(|id| id.to_string()).unwrap_or_default();
+                data.set(&data.target(), 8, id.as_bytes());
+            });
+        }
 
         {
             let app_id = item.app_id.clone();
@@ -221,7 +424,10 @@ impl ItemButton {
             button.connect_enter_notify_event(move |button, _| {
                 let menu_state = read_lock!(menu_state);
 
-                if menu_state.num_windows > 1 {
+                // with previews, the popup is worth showing for a single window
+                let min_windows = if appearance.show_previews { 1 } else { 2 };
+
+                if menu_state.windows.len() >= min_windows {
                     try_send!(
                         tx,
                         ModuleUpdateEvent::Update(LauncherUpdate::Hover(app_id.clone(),))
@@ -276,9 +482,11 @@ impl ItemButton {
 
     pub fn set_open(&self, open: bool) {
         self.update_class("open", open);
+        set_draggable(&self.button, open);
 
         if !open {
             self.set_focused(false);
+            self.set_urgent(false);
         }
     }
 
@@ -286,6 +494,14 @@ impl ItemButton {
         self.update_class("focused", focused);
     }
 
+    pub fn set_urgent(&self, urgent: bool) {
+        self.update_class("urgent", urgent);
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        self.button.style_context().has_class("urgent")
+    }
+
     /// Adds or removes a class to the button based on `toggle`.
     fn update_class(&self, class: &str, toggle: bool) {
         let style_context = self.button.style_context();
@@ -297,3 +513,77 @@ impl ItemButton {
         }
     }
 }
+
+/// Allows the button to be dragged onto a workspace
+/// to move its window there.
+/// Only open items have a window to move.
+fn set_draggable(button: &Button, draggable: bool) {
+    if draggable {
+        button.drag_source_set(
+            ModifierType::BUTTON1_MASK,
+            &[window_drag_target()],
+            DragAction::MOVE,
+        );
+    } else {
+        button.drag_source_unset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: usize, title: &str, focused: bool) -> ToplevelInfo {
+        ToplevelInfo {
+            id,
+            app_id: String::from("firefox"),
+            title: title.to_string(),
+            focused,
+            ..ToplevelInfo::default()
+        }
+    }
+
+    #[test]
+    fn tracks_window_urgency() {
+        let mut item = Item::from(window(1, "Inbox", true));
+        item.merge_toplevel(window(2, "Docs", false));
+        item.merge_toplevel(window(3, "Chat", false));
+
+        item.set_window_urgent("Chat", true);
+        assert!(item.windows[&3].urgent);
+        assert!(item.is_urgent());
+
+        // focused windows are never urgent
+        item.set_window_urgent("Inbox", true);
+        assert!(!item.windows[&1].urgent);
+
+        // falls back to an unfocused window when the title is unknown
+        item.set_window_urgent("Renamed", true);
+        assert!(item.windows[&2].urgent);
+
+        item.set_window_focused(3, true);
+        assert!(!item.windows[&3].urgent);
+
+        item.set_window_urgent("Unknown", false);
+        assert!(!item.is_urgent());
+    }
+
+    #[test]
+    fn drags_focused_window() {
+        let mut item = Item::from(window(1, "Inbox", false));
+        item.merge_toplevel(window(2, "Docs", true));
+
+        let mut state = MenuState::new(&item);
+        assert_eq!(state.drag_window(), Some(2));
+
+        state.set_focused(2, false);
+        assert_eq!(state.drag_window(), Some(1));
+
+        state.add_window(&Window::from(window(3, "Chat", true)));
+        assert_eq!(state.drag_window(), Some(3));
+
+        state.remove_window(3);
+        state.remove_window(1);
+        assert_eq!(state.drag_window(), Some(2));
+    }
+}
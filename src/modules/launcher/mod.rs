@@ -1,20 +1,37 @@
+mod favorites;
 mod item;
 mod open_state;
+mod search;
 
-use self::item::{AppearanceOptions, Item, ItemButton, Window};
+use self::favorites::Favorites;
+use self::item::{AppearanceOptions, Item, ItemButton, MenuState, Window, WindowButton};
 use self::open_state::OpenState;
-use super::{Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, WidgetContext};
-use crate::clients::wayland::{self, ToplevelEvent};
-use crate::config::CommonConfig;
+pub use self::search::AppSearchModule;
+use super::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    WidgetContext,
+};
+#[cfg(feature = "workspaces")]
+use crate::clients::compositor::{StateUpdate, WorkspaceClient};
+use crate::clients::wayland::{self, ToplevelEvent, ToplevelPreview};
+use crate::config::{CommonConfig, EllipsizeMode, TruncateMode};
 use crate::desktop_file::find_desktop_file;
-use crate::{arc_mut, glib_recv, lock, module_impl, send_async, spawn, try_send, write_lock};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::ImageProvider;
+use crate::{arc_mut, glib_recv, lock, module_impl, send_async, try_send, write_lock};
 use color_eyre::{Help, Report};
 use gtk::prelude::*;
 use gtk::{Button, Orientation};
 use indexmap::IndexMap;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
 use std::process::{Command, Stdio};
 use std::sync::Arc;
+#[cfg(feature = "workspaces")]
+use std::sync::Mutex;
+#[cfg(feature = "workspaces")]
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, trace};
 
@@ -24,6 +41,9 @@ pub struct LauncherModule {
     /// List of app IDs (or classes) to always show regardless of open state,
     /// in the order specified.
     ///
+    /// Desktop file IDs can also be used,
+    /// with windows grouped under the favourite sharing their desktop file.
+    ///
     /// **Default**: `null`
     favorites: Option<Vec<String>>,
 
@@ -45,6 +65,23 @@ pub struct LauncherModule {
     #[serde(default = "default_icon_size")]
     icon_size: i32,
 
+    /// Whether to show a preview of each window in the popup.
+    ///
+    /// Previews are captured from the screen as each window is focused,
+    /// using the wlr screencopy protocol.
+    /// The app icon is shown until a window has been captured.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    show_previews: bool,
+
+    /// See [truncate options](module-level-options#truncate-mode).
+    /// This applies to window titles in the popup.
+    ///
+    /// **Default**: `{ mode = "end", max_length = 24 }`
+    #[serde(default = "default_truncate_popup")]
+    truncate_popup: TruncateMode,
+
     /// Whether items should be added from right-to-left
     /// instead of left-to-right.
     ///
@@ -54,6 +91,13 @@ pub struct LauncherModule {
     #[serde(default = "crate::config::default_false")]
     reversed: bool,
 
+    /// Whether to move items with an urgent window to the front of the launcher,
+    /// until the window is focused.
+    ///
+    /// **Default**: `false`
+    #[serde(default = "crate::config::default_false")]
+    urgent_on_top: bool,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -63,6 +107,15 @@ const fn default_icon_size() -> i32 {
     32
 }
 
+/// Fits window titles inside the 250px popup.
+const fn default_truncate_popup() -> TruncateMode {
+    TruncateMode::Length {
+        mode: EllipsizeMode::End,
+        length: None,
+        max_length: Some(24),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum LauncherUpdate {
     /// Adds item
@@ -75,10 +128,14 @@ pub enum LauncherUpdate {
     RemoveWindow(String, usize),
     /// Sets title for `app_id`
     Title(String, usize, String),
-    /// Marks the item with `app_id` as focused or not focused
-    Focus(String, bool),
+    /// Marks the window of the item with `app_id` as focused or not focused
+    Focus(String, usize, bool),
+    /// Marks the item with `app_id` as having an urgent window or not
+    Urgent(String, bool),
     /// Declares the item with `app_id` has been hovered over
     Hover(String),
+    /// Sets the preview for a window
+    Preview(ToplevelPreview),
 }
 
 #[derive(Debug)]
@@ -95,7 +152,7 @@ enum ItemOrWindow {
 
 enum ItemOrWindowId {
     Item,
-    Window,
+    Window { urgency_cleared: bool },
 }
 
 impl Module<gtk::Box> for LauncherModule {
@@ -106,49 +163,57 @@ impl Module<gtk::Box> for LauncherModule {
 
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> crate::Result<()> {
-        let items = self
+        let favorite_ids = self
             .favorites
-            .as_ref()
-            .map_or_else(IndexMap::new, |favorites| {
-                favorites
-                    .iter()
-                    .map(|app_id| {
-                        (
-                            app_id.to_string(),
-                            Item::new(app_id.to_string(), OpenState::Closed, true),
-                        )
-                    })
-                    .collect::<IndexMap<_, _>>()
-            });
+            .iter()
+            .flatten()
+            .map(|app_id| app_id.trim_end_matches(".desktop").to_string())
+            .collect::<Vec<_>>();
+
+        let items = favorite_ids
+            .iter()
+            .map(|app_id| {
+                (
+                    app_id.clone(),
+                    Item::new(app_id.clone(), OpenState::Closed, true),
+                )
+            })
+            .collect::<IndexMap<_, _>>();
 
         let items = arc_mut!(items);
+        let favorites = arc_mut!(Favorites::default());
 
         let items2 = Arc::clone(&items);
+        let favorites2 = Arc::clone(&favorites);
 
         let tx = context.tx.clone();
         let tx2 = context.tx.clone();
 
         let wl = context.client::<wayland::Client>();
-        spawn(async move {
+        context.spawn(async move {
             let items = items2;
+            let favorites = favorites2;
             let tx = tx2;
 
+            *lock!(favorites) = Favorites::new(favorite_ids, find_desktop_file);
+
             let mut wlrx = wl.subscribe_toplevels();
             let handles = wl.toplevel_info_all();
 
             for info in handles {
+                let key = lock!(favorites).key(&info.app_id, find_desktop_file);
+
                 let mut items = lock!(items);
-                let item = items.get_mut(&info.app_id);
+                let item = items.get_mut(&key);
                 match item {
                     Some(item) => {
                         item.merge_toplevel(info.clone());
                     }
                     None => {
-                        items.insert(info.app_id.clone(), Item::from(info.clone()));
+                        items.insert(key, Item::from(info.clone()));
                     }
                 }
             }
@@ -171,11 +236,11 @@ impl Module<gtk::Box> for LauncherModule {
 
                 match event {
                     ToplevelEvent::New(info) => {
-                        let app_id = info.app_id.clone();
+                        let app_id = lock!(favorites).key(&info.app_id, find_desktop_file);
 
                         let new_item = {
                             let mut items = lock!(items);
-                            let item = items.get_mut(&info.app_id);
+                            let item = items.get_mut(&app_id);
                             match item {
                                 None => {
                                     let item: Item = info.into();
@@ -184,6 +249,11 @@ impl Module<gtk::Box> for LauncherModule {
 
                                     ItemOrWindow::Item(item)
                                 }
+                                // closed favourites are re-added, replacing their old windows
+                                Some(item) if item.windows.is_empty() => {
+                                    item.merge_toplevel(info);
+                                    ItemOrWindow::Item(item.clone())
+                                }
                                 Some(item) => {
                                     let window = item.merge_toplevel(info);
                                     ItemOrWindow::Window(window)
@@ -201,42 +271,57 @@ impl Module<gtk::Box> for LauncherModule {
                         }?;
                     }
                     ToplevelEvent::Update(info) => {
+                        let app_id = lock!(favorites).get(&info.app_id);
+
                         // check if open, as updates can be sent as program closes
                         // if it's a focused favourite closing, it otherwise incorrectly re-focuses.
-                        let is_open = if let Some(item) = lock!(items).get_mut(&info.app_id) {
-                            item.set_window_focused(info.id, info.focused);
-                            item.set_window_name(info.id, info.title.clone());
+                        let (is_open, urgency_cleared) =
+                            if let Some(item) = lock!(items).get_mut(&app_id) {
+                                let was_urgent = item.is_urgent();
 
-                            item.open_state.is_open()
-                        } else {
-                            false
-                        };
+                                item.set_window_focused(info.id, info.focused);
+                                item.set_window_name(info.id, info.title.clone());
+
+                                (item.open_state.is_open(), was_urgent && !item.is_urgent())
+                            } else {
+                                (false, false)
+                            };
+
+                        if urgency_cleared {
+                            send_update(LauncherUpdate::Urgent(app_id.clone(), false)).await?;
+                        }
 
                         send_update(LauncherUpdate::Focus(
-                            info.app_id.clone(),
-                            is_open && info.focused,
-                        ))
-                        .await?;
-                        send_update(LauncherUpdate::Title(
-                            info.app_id.clone(),
+                            app_id.clone(),
                             info.id,
-                            info.title.clone(),
+                            is_open && info.focused,
                         ))
                         .await?;
+                        send_update(LauncherUpdate::Title(app_id, info.id, info.title.clone()))
+                            .await?;
                     }
                     ToplevelEvent::Remove(info) => {
+                        let app_id = lock!(favorites).get(&info.app_id);
+
                         let remove_item = {
                             let mut items = lock!(items);
-                            let item = items.get_mut(&info.app_id);
+                            let item = items.get_mut(&app_id);
                             match item {
                                 Some(item) => {
+                                    let was_urgent = item.is_urgent();
                                     item.unmerge_toplevel(&info);
 
                                     if item.windows.is_empty() {
-                                        items.shift_remove(&info.app_id);
+                                        // favourites stay, so their windows merge into them again
+                                        if !item.favorite {
+                                            items.shift_remove(&app_id);
+                                        }
+
                                         Some(ItemOrWindowId::Item)
                                     } else {
-                                        Some(ItemOrWindowId::Window)
+                                        Some(ItemOrWindowId::Window {
+                                            urgency_cleared: was_urgent && !item.is_urgent(),
+                                        })
                                     }
                                 }
                                 None => None,
@@ -245,15 +330,15 @@ impl Module<gtk::Box> for LauncherModule {
 
                         match remove_item {
                             Some(ItemOrWindowId::Item) => {
-                                send_update(LauncherUpdate::RemoveItem(info.app_id.clone()))
-                                    .await?;
+                                send_update(LauncherUpdate::RemoveItem(app_id)).await?;
                             }
-                            Some(ItemOrWindowId::Window) => {
-                                send_update(LauncherUpdate::RemoveWindow(
-                                    info.app_id.clone(),
-                                    info.id,
-                                ))
-                                .await?;
+                            Some(ItemOrWindowId::Window { urgency_cleared }) => {
+                                send_update(LauncherUpdate::RemoveWindow(app_id.clone(), info.id))
+                                    .await?;
+
+                                if urgency_cleared {
+                                    send_update(LauncherUpdate::Urgent(app_id, false)).await?;
+                                }
                             }
                             None => {}
                         };
@@ -264,31 +349,21 @@ impl Module<gtk::Box> for LauncherModule {
             Ok::<(), Report>(())
         });
 
+        if self.show_previews {
+            Self::listen_previews(context);
+        }
+
+        #[cfg(feature = "workspaces")]
+        Self::listen_urgency(context, items.clone(), favorites.clone());
+
         // listen to ui events
         let wl = context.client::<wayland::Client>();
-        spawn(async move {
+        context.spawn(async move {
             while let Some(event) = rx.recv().await {
                 if let ItemEvent::OpenItem(app_id) = event {
                     find_desktop_file(&app_id).map_or_else(
                         || error!("Could not find desktop file for {}", app_id),
-                        |file| {
-                            if let Err(err) = Command::new("gtk-launch")
-                                .arg(
-                                    file.file_name()
-                                        .expect("File segment missing from path to desktop file"),
-                                )
-                                .stdout(Stdio::null())
-                                .stderr(Stdio::null())
-                                .spawn()
-                            {
-                                error!(
-                                    "{:?}",
-                                    Report::new(err)
-                                        .wrap_err("Failed to run gtk-launch command.")
-                                        .suggestion("Perhaps the desktop file is invalid?")
-                                );
-                            }
-                        },
+                        |file| launch(&file),
                     );
                 } else {
                     send_async!(tx, ModuleUpdateEvent::ClosePopup);
@@ -298,7 +373,12 @@ impl Module<gtk::Box> for LauncherModule {
                             lock!(items).get(&app_id).and_then(|item| {
                                 item.windows
                                     .iter()
-                                    .find(|(_, win)| !win.open_state.is_focused())
+                                    .find(|(_, win)| win.urgent)
+                                    .or_else(|| {
+                                        item.windows
+                                            .iter()
+                                            .find(|(_, win)| !win.open_state.is_focused())
+                                    })
                                     .or_else(|| item.windows.first())
                                     .map(|(_, win)| win.id)
                             })
@@ -341,10 +421,12 @@ impl Module<gtk::Box> for LauncherModule {
             let appearance_options = AppearanceOptions {
                 show_names: self.show_names,
                 show_icons: self.show_icons,
-                icon_size: self.icon_size,
+                icon_size: info.scaled(self.icon_size),
+                show_previews: self.show_previews,
             };
 
             let show_names = self.show_names;
+            let urgent_on_top = self.urgent_on_top;
             let bar_position = info.bar_position;
 
             let mut buttons = IndexMap::<String, ItemButton>::new();
@@ -359,6 +441,8 @@ impl Module<gtk::Box> for LauncherModule {
                         if let Some(button) = buttons.get(&item.app_id) {
                             button.set_open(true);
                             button.set_focused(item.open_state.is_focused());
+
+                            *write_lock!(button.menu_state) = MenuState::new(&item);
                         } else {
                             let button = ItemButton::new(
                                 &item,
@@ -383,7 +467,7 @@ impl Module<gtk::Box> for LauncherModule {
                             button.set_open(true);
                             button.set_focused(win.open_state.is_focused());
 
-                            write_lock!(button.menu_state).num_windows += 1;
+                            write_lock!(button.menu_state).add_window(&win);
                         }
                     }
                     LauncherUpdate::RemoveItem(app_id) => {
@@ -400,6 +484,10 @@ impl Module<gtk::Box> for LauncherModule {
                                 buttons.shift_remove(&app_id);
                             }
                         }
+
+                        if urgent_on_top {
+                            order_urgent_first(&container, &buttons);
+                        }
                     }
                     LauncherUpdate::RemoveWindow(app_id, win_id) => {
                         debug!("Removing window {win_id} with id {app_id}");
@@ -407,15 +495,26 @@ impl Module<gtk::Box> for LauncherModule {
                         if let Some(button) = buttons.get(&app_id) {
                             button.set_focused(false);
 
-                            let mut menu_state = write_lock!(button.menu_state);
-                            menu_state.num_windows -= 1;
+                            write_lock!(button.menu_state).remove_window(win_id);
                         }
                     }
-                    LauncherUpdate::Focus(app_id, focus) => {
+                    LauncherUpdate::Focus(app_id, win_id, focus) => {
                         debug!("Changing focus to {} on item with id {}", focus, app_id);
 
                         if let Some(button) = buttons.get(&app_id) {
                             button.set_focused(focus);
+                            write_lock!(button.menu_state).set_focused(win_id, focus);
+                        }
+                    }
+                    LauncherUpdate::Urgent(app_id, urgent) => {
+                        debug!("Changing urgency to {urgent} on item with id {app_id}");
+
+                        if let Some(button) = buttons.get(&app_id) {
+                            button.set_urgent(urgent);
+                        }
+
+                        if urgent_on_top {
+                            order_urgent_first(&container, &buttons);
                         }
                     }
                     LauncherUpdate::Title(app_id, _, name) => {
@@ -427,7 +526,7 @@ impl Module<gtk::Box> for LauncherModule {
                             }
                         }
                     }
-                    LauncherUpdate::Hover(_) => {}
+                    LauncherUpdate::Hover(_) | LauncherUpdate::Preview(_) => {}
                 };
             });
         }
@@ -448,7 +547,7 @@ impl Module<gtk::Box> for LauncherModule {
         controller_tx: mpsc::Sender<Self::ReceiveMessage>,
         rx: broadcast::Receiver<Self::SendMessage>,
         _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _info: &ModuleInfo,
+        info: &ModuleInfo,
     ) -> Option<gtk::Box> {
         const MAX_WIDTH: i32 = 250;
 
@@ -459,7 +558,23 @@ impl Module<gtk::Box> for LauncherModule {
         placeholder.set_width_request(MAX_WIDTH);
         container.add(&placeholder);
 
-        let mut buttons = IndexMap::<String, IndexMap<usize, Button>>::new();
+        let mut buttons = IndexMap::<String, IndexMap<usize, WindowButton>>::new();
+        // previews are kept separately to the buttons,
+        // as a window can be captured before its button is created
+        let mut previews = HashMap::<usize, ToplevelPreview>::new();
+
+        let icon_theme = info.icon_theme.clone();
+        let icon_size = info.scaled(self.icon_size);
+        let show_previews = self.show_previews;
+        let truncate = self.truncate_popup;
+
+        let window_button = move |app_id: &str, window: &Window| {
+            let icon = show_previews
+                .then(|| ImageProvider::parse(app_id, &icon_theme, true, icon_size))
+                .flatten();
+
+            WindowButton::new(window, icon.as_ref(), truncate, &controller_tx)
+        };
 
         {
             let container = container.clone();
@@ -471,18 +586,12 @@ impl Module<gtk::Box> for LauncherModule {
 
                         let window_buttons = item
                             .windows
-                            .into_iter()
-                            .map(|(_, win)| {
-                                let button = Button::builder()
-                                    .label(clamp(&win.name))
-                                    .height_request(40)
-                                    .build();
-
-                                {
-                                    let tx = controller_tx.clone();
-                                    button.connect_clicked(move |_| {
-                                        try_send!(tx, ItemEvent::FocusWindow(win.id));
-                                    });
+                            .values()
+                            .map(|win| {
+                                let button = window_button(&app_id, win);
+
+                                if let Some(preview) = previews.get(&win.id) {
+                                    button.set_preview(preview);
                                 }
 
                                 (win.id, button)
@@ -498,16 +607,10 @@ impl Module<gtk::Box> for LauncherModule {
                         );
 
                         if let Some(buttons) = buttons.get_mut(&app_id) {
-                            let button = Button::builder()
-                                .height_request(40)
-                                .label(clamp(&win.name))
-                                .build();
-
-                            {
-                                let tx = controller_tx.clone();
-                                button.connect_clicked(move |_button| {
-                                    try_send!(tx, ItemEvent::FocusWindow(win.id));
-                                });
+                            let button = window_button(&app_id, &win);
+
+                            if let Some(preview) = previews.get(&win.id) {
+                                button.set_preview(preview);
                             }
 
                             buttons.insert(win.id, button);
@@ -519,6 +622,15 @@ impl Module<gtk::Box> for LauncherModule {
                         if let Some(buttons) = buttons.get_mut(&app_id) {
                             buttons.shift_remove(&win_id);
                         }
+
+                        previews.remove(&win_id);
+                    }
+                    LauncherUpdate::RemoveItem(app_id) => {
+                        if let Some(buttons) = buttons.get(&app_id) {
+                            for win_id in buttons.keys() {
+                                previews.remove(win_id);
+                            }
+                        }
                     }
                     LauncherUpdate::Title(app_id, win_id, title) => {
                         debug!(
@@ -527,10 +639,23 @@ impl Module<gtk::Box> for LauncherModule {
 
                         if let Some(buttons) = buttons.get_mut(&app_id) {
                             if let Some(button) = buttons.get(&win_id) {
-                                button.set_label(&title);
+                                button.set_title(&title);
                             }
                         }
                     }
+                    LauncherUpdate::Preview(preview) => {
+                        trace!("Updating preview on popup for window {}", preview.id);
+
+                        let button = buttons
+                            .values()
+                            .find_map(|buttons| buttons.get(&preview.id));
+
+                        if let Some(button) = button {
+                            button.set_preview(&preview);
+                        }
+
+                        previews.insert(preview.id, preview);
+                    }
                     LauncherUpdate::Hover(app_id) => {
                         // empty current buttons
                         for child in container.children() {
@@ -539,9 +664,9 @@ impl Module<gtk::Box> for LauncherModule {
 
                         // add app's buttons
                         if let Some(buttons) = buttons.get(&app_id) {
-                            for (_, button) in buttons {
-                                button.style_context().add_class("popup-item");
-                                container.add(button);
+                            for button in buttons.values() {
+                                button.button.add_class("popup-item");
+                                container.add(&button.button);
                             }
 
                             container.show_all();
@@ -557,20 +682,107 @@ impl Module<gtk::Box> for LauncherModule {
     }
 }
 
-/// Clamps a string at 24 characters.
-///
-/// This is a hacky number derived from
-/// "what fits inside the 250px popup"
-/// and probably won't hold up with wide fonts.
-///
-/// TODO: Migrate this to truncate system
-///
-fn clamp(str: &str) -> String {
-    const MAX_CHARS: usize = 24;
-
-    if str.len() > MAX_CHARS {
-        str.chars().take(MAX_CHARS - 3).collect::<String>() + "..."
-    } else {
-        str.to_string()
+impl LauncherModule {
+    /// Captures previews of windows as they are focused,
+    /// passing them on to the popup.
+    fn listen_previews(context: &ControllerContext<LauncherUpdate>) {
+        let wl = context.client::<wayland::Client>();
+        wl.enable_toplevel_previews();
+
+        let tx = context.tx.clone();
+        let mut rx = wl.subscribe_toplevel_previews();
+
+        context.spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(preview) => {
+                        send_async!(
+                            tx,
+                            ModuleUpdateEvent::Update(LauncherUpdate::Preview(preview))
+                        );
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Marks windows as urgent when the compositor reports them demanding attention.
+    ///
+    /// Urgency is not part of the toplevel protocol,
+    /// so this is only available on compositors with a workspace client.
+    #[cfg(feature = "workspaces")]
+    fn listen_urgency(
+        context: &ControllerContext<LauncherUpdate>,
+        items: Arc<Mutex<IndexMap<String, Item>>>,
+        favorites: Arc<Mutex<Favorites>>,
+    ) {
+        let client = match context.try_client::<dyn WorkspaceClient>() {
+            Ok(client) => client,
+            Err(err) => {
+                debug!("Window urgency is unavailable: {err}");
+                return;
+            }
+        };
+
+        let tx = context.tx.clone();
+        let mut rx = client.subscribe_state_change();
+
+        context.spawn(async move {
+            loop {
+                let (app_id, title, urgent) = match rx.recv().await {
+                    Ok(StateUpdate::Urgent {
+                        app_id,
+                        title,
+                        urgent,
+                    }) => (lock!(favorites).get(&app_id), title, urgent),
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+
+                let urgent = lock!(items).get_mut(&app_id).map(|item| {
+                    item.set_window_urgent(&title, urgent);
+                    item.is_urgent()
+                });
+
+                if let Some(urgent) = urgent {
+                    send_async!(
+                        tx,
+                        ModuleUpdateEvent::Update(LauncherUpdate::Urgent(app_id, urgent))
+                    );
+                }
+            }
+        });
+    }
+}
+
+/// Moves items with an urgent window to the front,
+/// keeping the order of items otherwise.
+fn order_urgent_first(container: &gtk::Box, buttons: &IndexMap<String, ItemButton>) {
+    let (urgent, rest): (Vec<_>, Vec<_>) = buttons.values().partition(|button| button.is_urgent());
+
+    for (i, button) in urgent.into_iter().chain(rest).enumerate() {
+        container.reorder_child(&button.button, i as i32);
+    }
+}
+
+/// Launches the app using its desktop file.
+fn launch(file: &Path) {
+    if let Err(err) = Command::new("gtk-launch")
+        .arg(
+            file.file_name()
+                .expect("File segment missing from path to desktop file"),
+        )
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        error!(
+            "{:?}",
+            Report::new(err)
+                .wrap_err("Failed to run gtk-launch command.")
+                .suggestion("Perhaps the desktop file is invalid?")
+        );
     }
 }
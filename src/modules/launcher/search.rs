@@ -0,0 +1,573 @@
+use super::launch;
+use crate::config::CommonConfig;
+use crate::desktop_file::{find_applications, Application};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::i18n::tr;
+use crate::image::{new_icon_button, ImageProvider};
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    PopupButton, WidgetContext,
+};
+use crate::state::{state_file, write_state};
+use crate::{glib_recv, module_impl, rc_mut, send_async, try_send};
+use glib::Propagation;
+use gtk::gdk::keys;
+use gtk::prelude::*;
+use gtk::{Button, Entry, IconTheme, Label, ListBox, ListBoxRow, Orientation};
+use gtk_layer_shell::{KeyboardMode, LayerShell};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, error};
+
+/// The most an app's launch count can add to its score,
+/// so that a frequently used app cannot outrank a much better match.
+const MAX_LAUNCH_BONUS: u32 = 10;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AppSearchModule {
+    /// The icon to show on the bar widget button.
+    /// Supports [image](images) icons.
+    ///
+    /// **Default**: `󰀻`
+    #[serde(default = "default_icon")]
+    icon: String,
+
+    /// The size to render the icon at.
+    /// Note this only applies to image-type icons.
+    ///
+    /// **Default**: `32`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// The size to render app icons at in the results.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_app_icon_size")]
+    app_icon_size: i32,
+
+    /// The maximum number of apps to show in the results.
+    ///
+    /// **Default**: `10`
+    #[serde(default = "default_max_results")]
+    max_results: usize,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_icon() -> String {
+    String::from("󰀻")
+}
+
+const fn default_icon_size() -> i32 {
+    32
+}
+
+const fn default_app_icon_size() -> i32 {
+    24
+}
+
+const fn default_max_results() -> usize {
+    10
+}
+
+/// An installed app, with how often it has been launched.
+#[derive(Debug, Clone)]
+pub struct AppEntry {
+    app: Application,
+    launches: u32,
+}
+
+impl AppEntry {
+    /// Scores how well the app matches the query,
+    /// or `None` if it does not match.
+    ///
+    /// Matches on the name are preferred over the generic name or keywords.
+    fn score(&self, query: &str) -> Option<u32> {
+        let name_score = fuzzy_score(query, &self.app.name);
+
+        let other_score = self
+            .app
+            .generic_name
+            .iter()
+            .chain(&self.app.keywords)
+            .filter_map(|text| fuzzy_score(query, text))
+            .max()
+            .map(|score| score / 2);
+
+        name_score.max(other_score)
+    }
+}
+
+#[derive(Debug)]
+pub enum SearchCommand {
+    /// Reloads the installed apps.
+    Refresh,
+    /// Launches the app with the desktop file.
+    Launch(PathBuf),
+}
+
+impl Module<Button> for AppSearchModule {
+    type SendMessage = Vec<AppEntry>;
+    type ReceiveMessage = SearchCommand;
+
+    module_impl!("app_search");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> color_eyre::Result<()> {
+        let tx = context.tx.clone();
+
+        context.spawn(async move {
+            let mut launches = Launches::load();
+
+            send_async!(tx, ModuleUpdateEvent::Update(launches.entries()));
+
+            while let Some(command) = rx.recv().await {
+                match command {
+                    SearchCommand::Refresh => {}
+                    SearchCommand::Launch(path) => {
+                        debug!("Launching {}", path.display());
+
+                        send_async!(tx, ModuleUpdateEvent::ClosePopup);
+                        launch(&path);
+                        launches.add(&path);
+                    }
+                }
+
+                send_async!(tx, ModuleUpdateEvent::Update(launches.entries()));
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> color_eyre::Result<ModuleParts<Button>> {
+        let button = new_icon_button(&self.icon, info.icon_theme, info.scaled(self.icon_size));
+        button.add_class("btn");
+
+        {
+            let tx = context.tx.clone();
+            let controller_tx = context.controller_tx.clone();
+
+            button.connect_clicked(move |button| {
+                // apps may have been installed since the popup was last opened
+                try_send!(controller_tx, SearchCommand::Refresh);
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        let rx = context.subscribe();
+        let popup = self
+            .into_popup(context.controller_tx.clone(), rx, context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let entry = Entry::builder()
+            .placeholder_text(tr("launcher.search"))
+            .build();
+        entry.add_class("search");
+        container.add(&entry);
+
+        let results = ListBox::new();
+        results.add_class("results");
+        container.add(&results);
+
+        let results = SearchResults {
+            list: results,
+            entries: rc_mut!(vec![]),
+            shown: rc_mut!(vec![]),
+            icon_theme: info.icon_theme.clone(),
+            icon_size: info.scaled(self.app_icon_size),
+            max_results: self.max_results,
+        };
+
+        {
+            let results = results.clone();
+            entry.connect_changed(move |entry| results.update(&entry.text()));
+        }
+
+        {
+            let shown = results.shown.clone();
+            results.list.connect_row_activated(move |_, row| {
+                let path = usize::try_from(row.index())
+                    .ok()
+                    .and_then(|index| shown.borrow().get(index).cloned());
+
+                if let Some(path) = path {
+                    try_send!(tx, SearchCommand::Launch(path));
+                }
+            });
+        }
+
+        {
+            let list = results.list.clone();
+            entry.connect_activate(move |_| {
+                if let Some(row) = list.selected_row().or_else(|| list.row_at_index(0)) {
+                    row.activate();
+                }
+            });
+        }
+
+        {
+            let list = results.list.clone();
+            entry.connect_key_press_event(move |_, ev| match ev.keyval() {
+                keys::constants::Down => {
+                    move_selection(&list, 1);
+                    Propagation::Stop
+                }
+                keys::constants::Up => {
+                    move_selection(&list, -1);
+                    Propagation::Stop
+                }
+                _ => Propagation::Proceed,
+            });
+        }
+
+        {
+            let entry = entry.clone();
+            container.connect_map(move |container| {
+                entry.set_text("");
+                entry.grab_focus();
+
+                // the popup only takes keyboard focus while the bar is being navigated,
+                // so it is requested here to allow typing straight away.
+                // this is reset by the popup once hidden.
+                if let Some(window) = container
+                    .toplevel()
+                    .and_then(|window| window.downcast::<gtk::Window>().ok())
+                {
+                    window.set_keyboard_mode(KeyboardMode::Exclusive);
+                    window.present();
+                }
+            });
+        }
+
+        glib_recv!(rx, entries => {
+            results.entries.replace(entries);
+            results.update(&entry.text());
+        });
+
+        Some(container)
+    }
+}
+
+/// The list of apps matching the search.
+#[derive(Debug, Clone)]
+struct SearchResults {
+    list: ListBox,
+    /// All installed apps.
+    entries: Rc<RefCell<Vec<AppEntry>>>,
+    /// The desktop files of the apps currently shown.
+    shown: Rc<RefCell<Vec<PathBuf>>>,
+    icon_theme: IconTheme,
+    icon_size: i32,
+    max_results: usize,
+}
+
+impl SearchResults {
+    /// Replaces the results with the best matches for the query,
+    /// and selects the first.
+    fn update(&self, query: &str) {
+        for row in self.list.children() {
+            self.list.remove(&row);
+        }
+
+        let entries = self.entries.borrow();
+        let ranked = rank(&entries, query);
+
+        let mut shown = self.shown.borrow_mut();
+        shown.clear();
+
+        for entry in ranked.into_iter().take(self.max_results) {
+            self.list.add(&self.row(&entry.app));
+            shown.push(entry.app.path.clone());
+        }
+
+        self.list.show_all();
+        self.list.select_row(self.list.row_at_index(0).as_ref());
+    }
+
+    fn row(&self, app: &Application) -> ListBoxRow {
+        let row = ListBoxRow::new();
+        row.add_class("result");
+
+        let container = gtk::Box::new(Orientation::Horizontal, 10);
+
+        if let Some(icon) = &app.icon {
+            // prevent the provider from searching every desktop file for names not in the theme
+            let input = if ImageProvider::is_definitely_image_input(icon) {
+                icon.clone()
+            } else {
+                format!("icon:{icon}")
+            };
+
+            let image = gtk::Image::new();
+            image.add_class("icon");
+
+            if let Some(provider) =
+                ImageProvider::parse(&input, &self.icon_theme, true, self.icon_size)
+            {
+                if let Err(err) = provider.load_into_image(image.clone()) {
+                    error!("{err:?}");
+                }
+            }
+
+            container.add(&image);
+        }
+
+        let labels = gtk::Box::new(Orientation::Vertical, 0);
+
+        let name = Label::new(Some(&app.name));
+        name.add_class("name");
+        name.set_halign(gtk::Align::Start);
+        labels.add(&name);
+
+        if let Some(generic_name) = &app.generic_name {
+            let generic_name = Label::new(Some(generic_name));
+            generic_name.add_class("generic-name");
+            generic_name.set_halign(gtk::Align::Start);
+            labels.add(&generic_name);
+        }
+
+        container.add(&labels);
+        row.add(&container);
+
+        row
+    }
+}
+
+/// Moves the selected row up or down by `offset`,
+/// stopping at the start and end of the list.
+fn move_selection(list: &ListBox, offset: i32) {
+    let len = list.children().len() as i32;
+    if len == 0 {
+        return;
+    }
+
+    let index = list.selected_row().map_or(-1, |row| row.index());
+    let index = (index + offset).clamp(0, len - 1);
+
+    list.select_row(list.row_at_index(index).as_ref());
+}
+
+/// Scores how well the characters of the query appear in order in the text,
+/// or `None` if they do not all appear.
+///
+/// Consecutive characters and characters at the start of a word score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    const MATCH: u32 = 1;
+    const CONSECUTIVE_BONUS: u32 = 5;
+    const WORD_START_BONUS: u32 = 3;
+
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous = None;
+
+    for char in query
+        .to_lowercase()
+        .chars()
+        .filter(|char| !char.is_whitespace())
+    {
+        let index = pos + text.get(pos..)?.iter().position(|&c| c == char)?;
+
+        score += MATCH;
+
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += CONSECUTIVE_BONUS;
+        }
+
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+
+        previous = Some(index);
+        pos = index + 1;
+    }
+
+    Some(score)
+}
+
+/// Gets the apps matching the query, best first.
+///
+/// Apps which have been launched more often rank higher,
+/// which also orders all apps when the query is empty.
+fn rank<'a>(entries: &'a [AppEntry], query: &str) -> Vec<&'a AppEntry> {
+    let mut matches = entries
+        .iter()
+        .filter_map(|entry| {
+            entry
+                .score(query)
+                .map(|score| (score + entry.launches.min(MAX_LAUNCH_BONUS), entry))
+        })
+        .collect::<Vec<_>>();
+
+    matches.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .cmp(score_a)
+            .then_with(|| b.launches.cmp(&a.launches))
+            .then_with(|| a.app.name.to_lowercase().cmp(&b.app.name.to_lowercase()))
+    });
+
+    matches.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// How many times each app has been launched from the search,
+/// by desktop file name.
+///
+/// These are saved to the state directory whenever an app is launched.
+#[derive(Debug)]
+struct Launches {
+    counts: HashMap<String, u32>,
+    path: Option<PathBuf>,
+}
+
+impl Launches {
+    fn load() -> Self {
+        let path = state_file("launch-counts");
+
+        let counts = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| parse_launches(&contents))
+            .unwrap_or_default();
+
+        Self { counts, path }
+    }
+
+    /// Gets the installed apps with their launch counts.
+    fn entries(&self) -> Vec<AppEntry> {
+        find_applications()
+            .into_iter()
+            .map(|app| AppEntry {
+                launches: self.counts.get(&file_name(&app.path)).copied().unwrap_or(0),
+                app,
+            })
+            .collect()
+    }
+
+    fn add(&mut self, path: &Path) {
+        *self.counts.entry(file_name(path)).or_default() += 1;
+        self.save();
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        if let Err(err) = write_state(path, format_launches(&self.counts)) {
+            error!("Failed to save launch counts: {err:?}");
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Formats the launch counts to save, with one app per line.
+fn format_launches(counts: &HashMap<String, u32>) -> String {
+    counts
+        .iter()
+        .map(|(name, count)| format!("{count}\t{name}\n"))
+        .collect()
+}
+
+fn parse_launches(contents: &str) -> HashMap<String, u32> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (count, name) = line.split_once('\t')?;
+            Some((name.to_string(), count.parse().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, generic_name: Option<&str>, launches: u32) -> AppEntry {
+        AppEntry {
+            app: Application {
+                path: PathBuf::from(format!("{name}.desktop")),
+                name: name.to_string(),
+                generic_name: generic_name.map(ToString::to_string),
+                icon: None,
+                keywords: vec![],
+            },
+            launches,
+        }
+    }
+
+    fn names(ranked: Vec<&AppEntry>) -> Vec<&str> {
+        ranked
+            .into_iter()
+            .map(|entry| entry.app.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn scores_subsequences() {
+        assert!(fuzzy_score("ffx", "Firefox").is_some());
+        assert_eq!(fuzzy_score("xff", "Firefox"), None);
+        assert_eq!(fuzzy_score("", "Firefox"), Some(0));
+
+        // consecutive and word start matches are preferred
+        assert!(fuzzy_score("fire", "Firefox") > fuzzy_score("fire", "Files Terminal Editor"));
+        assert!(fuzzy_score("lo", "Libre Office") > fuzzy_score("lo", "Color Picker"));
+    }
+
+    #[test]
+    fn ranks_by_match_then_launches() {
+        let entries = vec![
+            entry("Files", None, 0),
+            entry("Firefox", Some("Web Browser"), 2),
+            entry("Terminal", None, 5),
+            entry("Chromium", Some("Web Browser"), 0),
+        ];
+
+        assert_eq!(
+            names(rank(&entries, "")),
+            ["Terminal", "Firefox", "Chromium", "Files"]
+        );
+        assert_eq!(names(rank(&entries, "fi")), ["Firefox", "Files"]);
+        assert_eq!(names(rank(&entries, "browser")), ["Firefox", "Chromium"]);
+
+        let counts = parse_launches(&format_launches(&HashMap::from([(
+            String::from("firefox.desktop"),
+            3,
+        )])));
+        assert_eq!(counts.get("firefox.desktop"), Some(&3));
+    }
+}
@@ -1,21 +1,38 @@
+use std::cell::RefCell;
 use std::fmt::Debug;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
-use color_eyre::Result;
+use color_eyre::{Report, Result};
 use glib::IsA;
 use gtk::gdk::{EventMask, Monitor};
 use gtk::prelude::*;
 use gtk::{Application, Button, EventBox, IconTheme, Orientation, Revealer, Widget};
 use tokio::sync::{broadcast, mpsc};
-use tracing::debug;
-
-use crate::clients::{ClientResult, ProvidesClient, ProvidesFallibleClient};
-use crate::config::{BarPosition, CommonConfig, TransitionType};
+use tracing::{debug, error};
+
+use crate::clients::timer::Ticker;
+use crate::clients::{resume, ClientResult, Clients, ProvidesClient, ProvidesFallibleClient};
+use crate::config::{
+    self, BarPosition, ClassList, CommonConfig, ScrollConfig, TouchConfig, TransitionType,
+};
+use crate::diagnostics::{self, ModuleCounters};
 use crate::gtk_helpers::{IronbarGtkExt, WidgetGeometry};
-use crate::popup::Popup;
+use crate::overflow::PRIORITY_TAG;
+use crate::popup::{Popup, PopupRequest};
+use crate::script::ScriptEnv;
 use crate::{glib_recv_mpsc, send, Ironbar};
 
+use self::boundary::{ModuleInstance, ModuleTasks};
+
+#[cfg(feature = "bluetooth")]
+pub mod bluetooth;
+pub mod boundary;
+#[cfg(feature = "brightness")]
+pub mod brightness;
 #[cfg(feature = "cairo")]
 pub mod cairo;
 #[cfg(feature = "clipboard")]
@@ -31,6 +48,11 @@ pub mod clock;
 pub mod custom;
 #[cfg(feature = "focused")]
 pub mod focused;
+pub mod group;
+#[cfg(feature = "idle_inhibit")]
+pub mod idle_inhibit;
+#[cfg(feature = "keyboard")]
+pub mod keyboard;
 pub mod label;
 #[cfg(feature = "launcher")]
 pub mod launcher;
@@ -38,9 +60,13 @@ pub mod launcher;
 pub mod music;
 #[cfg(feature = "notifications")]
 pub mod notifications;
+#[cfg(feature = "privacy")]
+pub mod privacy;
 pub mod script;
 #[cfg(feature = "sys_info")]
 pub mod sysinfo;
+#[cfg(test)]
+pub mod testing;
 #[cfg(feature = "tray")]
 pub mod tray;
 #[cfg(feature = "upower")]
@@ -50,6 +76,10 @@ pub mod volume;
 #[cfg(feature = "workspaces")]
 pub mod workspaces;
 
+/// The widget tag set on modules which show private content,
+/// as declared by [`Module::is_sensitive`].
+pub const SENSITIVE_TAG: &str = "sensitive";
+
 #[derive(Clone)]
 pub enum ModuleLocation {
     Left,
@@ -62,9 +92,78 @@ pub struct ModuleInfo<'a> {
     pub app: &'a Application,
     pub location: ModuleLocation,
     pub bar_position: BarPosition,
+    pub bar_name: &'a str,
     pub monitor: &'a Monitor,
     pub output_name: &'a str,
     pub icon_theme: &'a IconTheme,
+    /// The factor to scale pixel sizes by,
+    /// so that they stay in proportion to the bar's text.
+    /// See [`ModuleInfo::scaled`].
+    pub scale: f64,
+    /// How scrolling over the bar's modules is turned into steps.
+    pub scroll: ScrollConfig,
+    /// The module's touchscreen options.
+    pub touch: TouchConfig,
+}
+
+impl ModuleInfo<'_> {
+    /// Scales a size in pixels, such as an icon size,
+    /// by the bar's font scale.
+    pub fn scaled(&self, size: i32) -> i32 {
+        (f64::from(size) * self.scale).round() as i32
+    }
+}
+
+/// An owned copy of [`ModuleInfo`],
+/// for rebuilding a module after the bar has loaded.
+#[cfg(feature = "ipc")]
+pub struct OwnedModuleInfo {
+    app: Application,
+    location: ModuleLocation,
+    bar_position: BarPosition,
+    bar_name: String,
+    monitor: Monitor,
+    output_name: String,
+    icon_theme: IconTheme,
+    scale: f64,
+    scroll: ScrollConfig,
+    touch: TouchConfig,
+}
+
+#[cfg(feature = "ipc")]
+impl From<&ModuleInfo<'_>> for OwnedModuleInfo {
+    fn from(info: &ModuleInfo) -> Self {
+        Self {
+            app: info.app.clone(),
+            location: info.location.clone(),
+            bar_position: info.bar_position,
+            bar_name: info.bar_name.to_string(),
+            monitor: info.monitor.clone(),
+            output_name: info.output_name.to_string(),
+            icon_theme: info.icon_theme.clone(),
+            scale: info.scale,
+            scroll: info.scroll,
+            touch: info.touch,
+        }
+    }
+}
+
+#[cfg(feature = "ipc")]
+impl OwnedModuleInfo {
+    pub fn as_info(&self) -> ModuleInfo<'_> {
+        ModuleInfo {
+            app: &self.app,
+            location: self.location.clone(),
+            bar_position: self.bar_position,
+            bar_name: &self.bar_name,
+            monitor: &self.monitor,
+            output_name: &self.output_name,
+            icon_theme: &self.icon_theme,
+            scale: self.scale,
+            scroll: self.scroll,
+            touch: self.touch,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -83,6 +182,72 @@ pub enum ModuleUpdateEvent<T: Clone> {
     ClosePopup,
 }
 
+/// The context passed to a module's controller.
+///
+/// Unlike [`WidgetContext`], this holds no GTK objects,
+/// so controllers can run without a display.
+/// This allows them to be tested using [`testing::ControllerHarness`].
+pub struct ControllerContext<TSend>
+where
+    TSend: Clone,
+{
+    pub tx: mpsc::Sender<ModuleUpdateEvent<TSend>>,
+    /// Bar context to pass to any scripts the controller runs.
+    pub script_env: ScriptEnv,
+    pub clients: Rc<RefCell<Clients>>,
+
+    tasks: ModuleTasks,
+}
+
+impl<TSend> ControllerContext<TSend>
+where
+    TSend: Clone,
+{
+    /// Gets client `T` from the context.
+    pub fn client<T: ?Sized>(&self) -> Arc<T>
+    where
+        ControllerContext<TSend>: ProvidesClient<T>,
+    {
+        ProvidesClient::provide(self)
+    }
+
+    pub fn try_client<T: ?Sized>(&self) -> ClientResult<T>
+    where
+        ControllerContext<TSend>: ProvidesFallibleClient<T>,
+    {
+        ProvidesFallibleClient::try_provide(self)
+    }
+
+    /// Subscribes to the system resuming from suspend.
+    ///
+    /// Controllers which refresh on an interval should use this
+    /// to refresh immediately after resuming,
+    /// for example by waiting using [`resume::wait`] instead of sleeping.
+    pub fn subscribe_resume(&self) -> broadcast::Receiver<()> {
+        resume::client().subscribe()
+    }
+
+    /// Creates a ticker for refreshing at the interval.
+    ///
+    /// This ticks straight away when the system resumes,
+    /// and uses the shared timer unless the module has `dedicated_timer` set.
+    pub fn ticker(&self, interval: Duration) -> Ticker {
+        self.script_env.ticker(interval)
+    }
+
+    /// Spawns a controller task on the Tokio runtime.
+    ///
+    /// If the task panics, the module is replaced by an error placeholder.
+    /// The task is stopped if the module is restarted.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tasks.spawn(future);
+    }
+}
+
 pub struct WidgetContext<TSend, TReceive>
 where
     TSend: Clone,
@@ -93,12 +258,15 @@ where
     pub tx: mpsc::Sender<ModuleUpdateEvent<TSend>>,
     pub update_tx: broadcast::Sender<TSend>,
     pub controller_tx: mpsc::Sender<TReceive>,
+    /// Bar context to pass to any scripts the widget runs.
+    pub script_env: ScriptEnv,
 
     // TODO: Don't like this - need some serious refactoring to deal with it
     //  This is a hack to be able to pass data from module -> popup creation
     //  for custom widget only.
     pub button_id: usize,
 
+    tasks: ModuleTasks,
     _update_rx: broadcast::Receiver<TSend>,
 }
 
@@ -128,6 +296,18 @@ where
     pub fn subscribe(&self) -> broadcast::Receiver<TSend> {
         self.update_tx.subscribe()
     }
+
+    /// Spawns a controller task on the Tokio runtime.
+    ///
+    /// If the task panics, the module is replaced by an error placeholder.
+    /// The task is stopped if the module is restarted.
+    pub fn spawn<F>(&self, future: F)
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tasks.spawn(future);
+    }
 }
 
 pub struct ModuleParts<W: IsA<Widget>> {
@@ -141,29 +321,38 @@ impl<W: IsA<Widget>> ModuleParts<W> {
     }
 
     pub fn setup_identifiers(&self, common: &CommonConfig) {
-        if let Some(ref name) = common.name {
-            self.widget.set_widget_name(name);
+        set_identifiers(
+            &self.widget,
+            common.name.as_deref(),
+            common.class.as_ref(),
+            "",
+        );
 
-            if let Some(ref popup) = self.popup {
-                popup.container.set_widget_name(&format!("popup-{name}"));
-            }
+        if let Some(ref popup) = self.popup {
+            set_identifiers(
+                &popup.container,
+                common.name.as_deref(),
+                common.class.as_ref(),
+                "popup-",
+            );
         }
+    }
+}
 
-        if let Some(ref class) = common.class {
-            // gtk counts classes with spaces as the same class
-            for part in class.split(' ') {
-                self.widget.style_context().add_class(part);
-            }
+/// Sets the configured widget name and CSS classes on the widget,
+/// each starting with `prefix`.
+fn set_identifiers(
+    widget: &impl IsA<Widget>,
+    name: Option<&str>,
+    class: Option<&ClassList>,
+    prefix: &str,
+) {
+    if let Some(name) = name {
+        widget.set_widget_name(&format!("{prefix}{name}"));
+    }
 
-            if let Some(ref popup) = self.popup {
-                for part in class.split(' ') {
-                    popup
-                        .container
-                        .style_context()
-                        .add_class(&format!("popup-{part}"));
-                }
-            }
-        }
+    for class in class.iter().flat_map(|class| class.iter()) {
+        widget.add_class(&format!("{prefix}{class}"));
     }
 }
 
@@ -236,10 +425,16 @@ where
 
     fn name() -> &'static str;
 
+    /// Whether the module shows private content, such as message text.
+    /// This is blanked while the session is locked
+    /// if the bar's `lock_behavior` is `censor`.
+    fn is_sensitive() -> bool {
+        false
+    }
+
     fn spawn_controller(
         &self,
-        info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()>
     where
@@ -267,29 +462,232 @@ where
         None
     }
 
+    /// Parses an action sent to the module over IPC
+    /// into a message for the module controller.
+    ///
+    /// Modules which do not support actions can leave the default,
+    /// which always returns an error.
+    fn parse_action(action: &str, _args: &[String]) -> Result<Self::ReceiveMessage> {
+        Err(Report::msg(format!(
+            "Module does not support action '{action}'"
+        )))
+    }
+
     fn take_common(&mut self) -> CommonConfig;
 }
 
+/// Handles actions sent to a module instance over IPC.
+///
+/// The handler holds a weak reference to the module widget,
+/// so that it can be discarded once the widget is destroyed.
+#[cfg(feature = "ipc")]
+pub struct ModuleActionHandler {
+    /// The module instance name.
+    /// This is the configured `name`, or the module type if unset.
+    pub name: String,
+    widget: glib::WeakRef<Widget>,
+    handler: Box<dyn Fn(&str, &[String]) -> Result<()>>,
+}
+
+#[cfg(feature = "ipc")]
+impl ModuleActionHandler {
+    fn new<W, F>(name: String, widget: &W, handler: F) -> Self
+    where
+        W: IsA<Widget>,
+        F: Fn(&str, &[String]) -> Result<()> + 'static,
+    {
+        Self {
+            name,
+            widget: widget.upcast_ref::<Widget>().downgrade(),
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Checks whether the module widget still exists.
+    pub fn is_alive(&self) -> bool {
+        self.widget.upgrade().is_some()
+    }
+
+    /// Gets the module widget, if it still exists.
+    pub fn widget(&self) -> Option<Widget> {
+        self.widget.upgrade()
+    }
+
+    /// Checks whether the module widget is nested inside the other widget.
+    pub fn is_inside(&self, ancestor: &impl IsA<Widget>) -> bool {
+        self.widget
+            .upgrade()
+            .is_some_and(|widget| widget.is_ancestor(ancestor))
+    }
+
+    /// Passes the action to the module.
+    pub fn handle(&self, action: &str, args: &[String]) -> Result<()> {
+        (self.handler)(action, args)
+    }
+}
+
+#[cfg(feature = "ipc")]
+impl Debug for ModuleActionHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleActionHandler")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
 pub trait ModuleFactory {
+    /// Creates the module and adds it to the container.
+    ///
+    /// If the module fails to create, or panics while doing so,
+    /// an error placeholder is added in its place instead.
+    ///
+    /// Returns `None` if the module is skipped on this bar.
     fn create<TModule, TWidget, TSend, TRev>(
         &self,
         mut module: TModule,
         container: &gtk::Box,
         info: &ModuleInfo,
-    ) -> Result<()>
+    ) -> Option<ModuleInstance>
     where
         TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev>,
         TWidget: IsA<Widget>,
         TSend: Debug + Clone + Send + 'static,
+        TRev: 'static,
     {
-        let id = Ironbar::unique_id();
         let common = module.take_common();
 
+        if let Some(outputs) = &common.outputs {
+            if !outputs.iter().any(|output| output == info.output_name) {
+                debug!(
+                    "Skipping {} module on output {}",
+                    TModule::name(),
+                    info.output_name
+                );
+                return None;
+            }
+        }
+
+        if let Some(load_if) = &common.load_if {
+            if !config::should_load(load_if) {
+                debug!(
+                    "Skipping {} module as load_if condition '{load_if}' is not met",
+                    TModule::name()
+                );
+                return None;
+            }
+        }
+
+        let module_name = TModule::name();
+        let instance_name = common
+            .name
+            .clone()
+            .unwrap_or_else(|| module_name.to_string());
+        let priority = common.priority;
+
+        // for the error placeholder, which is styled like the module
+        let name = common.name.clone();
+        let class = common.class.clone();
+
+        let id = Ironbar::unique_id();
+
+        let (error_tx, error_rx) = mpsc::channel(1);
+        let tasks = ModuleTasks::new(error_tx, &instance_name);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            self.build(module, common, id, &instance_name, tasks.clone(), info)
+        }))
+        .unwrap_or_else(|payload| Err(Report::msg(boundary::panic_message(payload.as_ref()))));
+
+        let widget = match result {
+            Ok(widget) => {
+                let container = widget.clone();
+                let tasks = tasks.clone();
+                let instance_name = instance_name.clone();
+
+                glib_recv_mpsc!(error_rx, message => {
+                    tasks.span().in_scope(|| {
+                        error!("{module_name} module '{instance_name}' panicked: {message}");
+                    });
+                    tasks.abort();
+                    boundary::show_error(
+                        &container,
+                        module_name,
+                        &message,
+                        name.as_deref(),
+                        class.as_ref(),
+                    );
+                });
+
+                widget
+            }
+            Err(err) => {
+                let message = format!("{err:#}");
+
+                tasks.span().in_scope(|| {
+                    error!(
+                        "{:?}",
+                        err.wrap_err(format!(
+                            "Failed to create {module_name} module '{instance_name}'"
+                        ))
+                    );
+                });
+
+                tasks.abort();
+                boundary::error_container(module_name, &message, name.as_deref(), class.as_ref())
+            }
+        };
+
+        widget.set_tag(PRIORITY_TAG, priority);
+        if TModule::is_sensitive() {
+            widget.set_tag(SENSITIVE_TAG, true);
+        }
+        container.add(&widget);
+
+        Some(ModuleInstance {
+            id,
+            name: instance_name,
+            widget,
+            tasks,
+        })
+    }
+
+    fn build<TModule, TWidget, TSend, TRev>(
+        &self,
+        module: TModule,
+        common: CommonConfig,
+        id: usize,
+        instance_name: &str,
+        tasks: ModuleTasks,
+        info: &ModuleInfo,
+    ) -> Result<EventBox>
+    where
+        TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev>,
+        TWidget: IsA<Widget>,
+        TSend: Debug + Clone + Send + 'static,
+        TRev: 'static,
+    {
         let (ui_tx, ui_rx) = mpsc::channel::<ModuleUpdateEvent<TSend>>(64);
         let (controller_tx, controller_rx) = mpsc::channel::<TRev>(64);
 
         let (tx, rx) = broadcast::channel(64);
 
+        let module_name = TModule::name();
+
+        let diagnostics = diagnostics::registry()
+            .map(|registry| registry.register(id, module_name, instance_name, info.bar_name));
+
+        let script_env = ScriptEnv::new(info.bar_name, info.output_name, instance_name)
+            .with_popup(self.popup().open_state(id))
+            .with_diagnostics(diagnostics.clone())
+            .with_dedicated_timer(common.dedicated_timer);
+
+        let controller_context = ControllerContext {
+            tx: ui_tx.clone(),
+            script_env: script_env.clone(),
+            clients: self.ironbar().clients.clone(),
+            tasks: tasks.clone(),
+        };
+
         let context = WidgetContext {
             id,
             ironbar: self.ironbar().clone(),
@@ -297,17 +695,21 @@ pub trait ModuleFactory {
             tx: ui_tx,
             update_tx: tx.clone(),
             controller_tx,
+            script_env: script_env.clone(),
+            tasks,
             _update_rx: rx,
             button_id: usize::MAX, // hack :(
         };
 
-        module.spawn_controller(info, &context, controller_rx)?;
+        module.spawn_controller(&controller_context, controller_rx)?;
 
-        let module_name = TModule::name();
-        let instance_name = common
-            .name
-            .clone()
-            .unwrap_or_else(|| module_name.to_string());
+        let info = &ModuleInfo {
+            touch: common.touch,
+            ..info.clone()
+        };
+
+        #[cfg(feature = "ipc")]
+        let action_tx = context.controller_tx.clone();
 
         let module_parts = module.into_widget(context, info)?;
         module_parts.widget.add_class("widget");
@@ -320,21 +722,48 @@ pub trait ModuleFactory {
                 .add_class(&format!("popup-{module_name}"));
 
             self.popup()
-                .register_content(id, instance_name, popup_content);
+                .register_content(id, instance_name.to_string(), popup_content);
+        }
+
+        if let Some(diagnostics) = &diagnostics {
+            diagnostics.tag_widget(&module_parts.widget);
         }
 
-        self.setup_receiver(tx, ui_rx, module_name, id, common.disable_popup);
+        self.setup_receiver(
+            tx,
+            ui_rx,
+            module_name,
+            id,
+            common.disable_popup,
+            diagnostics,
+        );
+
+        #[cfg(feature = "ipc")]
+        {
+            let parse_action = TModule::parse_action as fn(&str, &[String]) -> Result<TRev>;
+
+            let handler = ModuleActionHandler::new(
+                instance_name.to_string(),
+                &module_parts.widget,
+                move |action, args| {
+                    let message = parse_action(action, args)?;
+                    action_tx
+                        .try_send(message)
+                        .map_err(|_| Report::msg("Module is not currently accepting actions"))
+                },
+            );
+
+            self.ironbar().module_actions.borrow_mut().push(handler);
+        }
 
         module_parts.setup_identifiers(&common);
 
-        let ev_container = wrap_widget(
+        Ok(wrap_widget(
             &module_parts.widget,
             common,
             info.bar_position.orientation(),
-        );
-        container.add(&ev_container);
-
-        Ok(())
+            &script_env,
+        ))
     }
 
     fn setup_receiver<TSend>(
@@ -344,6 +773,7 @@ pub trait ModuleFactory {
         name: &'static str,
         id: usize,
         disable_popup: bool,
+        diagnostics: Option<Arc<ModuleCounters>>,
     ) where
         TSend: Debug + Clone + Send + 'static;
 
@@ -371,6 +801,7 @@ impl ModuleFactory for BarModuleFactory {
         name: &'static str,
         id: usize,
         disable_popup: bool,
+        diagnostics: Option<Arc<ModuleCounters>>,
     ) where
         TSend: Debug + Clone + Send + 'static,
     {
@@ -378,32 +809,18 @@ impl ModuleFactory for BarModuleFactory {
         glib_recv_mpsc!(rx, ev => {
             match ev {
                 ModuleUpdateEvent::Update(update) => {
+                    if let Some(diagnostics) = &diagnostics {
+                        diagnostics.record_update();
+                    }
+
                     send!(tx, update);
                 }
-                ModuleUpdateEvent::TogglePopup(button_id) if !disable_popup => {
-                    debug!("Toggling popup for {} [#{}] (button id: {button_id})", name, id);
-                    if popup.visible() && popup.current_widget().unwrap_or_default() == id {
-                        popup.hide();
-                    } else {
-                        popup.show(id, button_id);
+                ev if !disable_popup => {
+                    if let Some(request) = PopupRequest::from_event(&ev, id, None) {
+                        debug!("Popup request for {} [#{}]: {request:?}", name, id);
+                        popup.request(request);
                     }
                 }
-                ModuleUpdateEvent::OpenPopup(button_id) if !disable_popup => {
-                    debug!("Opening popup for {} [#{}] (button id: {button_id})", name, id);
-                    popup.hide();
-                    popup.show(id, button_id);
-                }
-                #[cfg(feature = "launcher")]
-                ModuleUpdateEvent::OpenPopupAt(geometry) if !disable_popup => {
-                    debug!("Opening popup for {} [#{}]", name, id);
-
-                    popup.hide();
-                    popup.show_at(id, geometry);
-                }
-                ModuleUpdateEvent::ClosePopup if !disable_popup => {
-                    debug!("Closing popup for {} [#{}]", name, id);
-                    popup.hide();
-                },
                 _ => {}
             }
         });
@@ -443,6 +860,7 @@ impl ModuleFactory for PopupModuleFactory {
         name: &'static str,
         id: usize,
         disable_popup: bool,
+        diagnostics: Option<Arc<ModuleCounters>>,
     ) where
         TSend: Debug + Clone + Send + 'static,
     {
@@ -451,32 +869,18 @@ impl ModuleFactory for PopupModuleFactory {
         glib_recv_mpsc!(rx, ev => {
             match ev {
                 ModuleUpdateEvent::Update(update) => {
+                    if let Some(diagnostics) = &diagnostics {
+                        diagnostics.record_update();
+                    }
+
                     send!(tx, update);
                 }
-                ModuleUpdateEvent::TogglePopup(_) if !disable_popup => {
-                    debug!("Toggling popup for {} [#{}] (button id: {button_id})", name, id);
-                    if popup.visible() && popup.current_widget().unwrap_or_default() == id {
-                        popup.hide();
-                    } else {
-                        popup.show(id, button_id);
+                ev if !disable_popup => {
+                    if let Some(request) = PopupRequest::from_event(&ev, id, Some(button_id)) {
+                        debug!("Popup request for {} [#{}]: {request:?}", name, id);
+                        popup.request(request);
                     }
                 }
-                ModuleUpdateEvent::OpenPopup(_) if !disable_popup => {
-                    debug!("Opening popup for {} [#{}] (button id: {button_id})", name, id);
-                    popup.hide();
-                    popup.show(id, button_id);
-                }
-                #[cfg(feature = "launcher")]
-                ModuleUpdateEvent::OpenPopupAt(geometry) if !disable_popup => {
-                    debug!("Opening popup for {} [#{}]", name, id);
-
-                    popup.hide();
-                    popup.show_at(id, geometry);
-                }
-                ModuleUpdateEvent::ClosePopup if !disable_popup => {
-                    debug!("Closing popup for {} [#{}]", name, id);
-                    popup.hide();
-                },
                 _ => {}
             }
         });
@@ -505,12 +909,17 @@ impl ModuleFactory for AnyModuleFactory {
         name: &'static str,
         id: usize,
         disable_popup: bool,
+        diagnostics: Option<Arc<ModuleCounters>>,
     ) where
         TSend: Debug + Clone + Send + 'static,
     {
         match self {
-            AnyModuleFactory::Bar(bar) => bar.setup_receiver(tx, rx, name, id, disable_popup),
-            AnyModuleFactory::Popup(popup) => popup.setup_receiver(tx, rx, name, id, disable_popup),
+            AnyModuleFactory::Bar(bar) => {
+                bar.setup_receiver(tx, rx, name, id, disable_popup, diagnostics);
+            }
+            AnyModuleFactory::Popup(popup) => {
+                popup.setup_receiver(tx, rx, name, id, disable_popup, diagnostics);
+            }
         }
     }
 
@@ -547,6 +956,7 @@ pub fn wrap_widget<W: IsA<Widget>>(
     widget: &W,
     common: CommonConfig,
     orientation: Orientation,
+    script_env: &ScriptEnv,
 ) -> EventBox {
     let transition_type = common
         .transition_type
@@ -568,7 +978,7 @@ pub fn wrap_widget<W: IsA<Widget>>(
     container.add_events(EventMask::SCROLL_MASK);
     container.add(&revealer);
 
-    common.install_events(&container, &revealer);
+    common.install_events(&container, &revealer, script_env);
 
     container
 }
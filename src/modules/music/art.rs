@@ -0,0 +1,290 @@
+// without http support, remote covers are never downloaded
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+#[cfg(feature = "http")]
+use crate::lock;
+#[cfg(feature = "http")]
+use color_eyre::{Report, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tracing::debug;
+#[cfg(feature = "http")]
+use tracing::error;
+
+/// Where to load a cover from.
+#[derive(Debug)]
+pub enum Cover {
+    /// The cover can be loaded from this path straight away.
+    /// Remote covers are only returned here if the cache is disabled.
+    Ready(String),
+    /// The cover is remote, and needs downloading into the cache first.
+    #[cfg(feature = "http")]
+    Download(reqwest::Url),
+}
+
+/// Downloads remote album art into a cache on disk,
+/// so that covers are only ever loaded from local files.
+#[derive(Debug, Clone)]
+pub struct AlbumArt {
+    /// The cache directory, or `None` if the cache is disabled.
+    dir: Option<PathBuf>,
+    /// The maximum total size of the cache, in bytes.
+    max_size: u64,
+    /// URLs currently being downloaded.
+    downloading: Arc<Mutex<HashSet<String>>>,
+}
+
+impl AlbumArt {
+    pub fn new(cache_size_mb: u64) -> Self {
+        let dir = dirs::cache_dir()
+            .filter(|_| cache_size_mb > 0)
+            .map(|dir| dir.join("ironbar").join("album-art"));
+
+        Self {
+            dir,
+            max_size: cache_size_mb * 1024 * 1024,
+            downloading: Arc::default(),
+        }
+    }
+
+    /// Finds where to load the cover from, without touching the network.
+    ///
+    /// `file://` URIs are converted to paths,
+    /// and remote covers are loaded from the cache if they have been downloaded before.
+    pub fn locate(&self, cover: &str) -> Cover {
+        if let Some(path) = file_uri_path(cover) {
+            return Cover::Ready(path.to_string_lossy().to_string());
+        }
+
+        #[cfg(feature = "http")]
+        if let Some(dir) = &self.dir {
+            if let Some(url) = reqwest::Url::parse(cover)
+                .ok()
+                .filter(|url| matches!(url.scheme(), "http" | "https"))
+            {
+                let path = dir.join(cache_file_name(url.as_str()));
+
+                if !path.is_file() {
+                    return Cover::Download(url);
+                }
+
+                // keeps recently played covers from being evicted
+                if let Err(err) = fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()))
+                {
+                    debug!("Failed to update album art modified time: {err}");
+                }
+
+                return Cover::Ready(path.to_string_lossy().to_string());
+            }
+        }
+
+        Cover::Ready(cover.to_string())
+    }
+
+    /// Downloads the cover into the cache, returning its path.
+    ///
+    /// Returns `None` if the cover is already being downloaded,
+    /// or if the download fails.
+    #[cfg(feature = "http")]
+    pub async fn download(&self, url: reqwest::Url) -> Option<String> {
+        let dir = self.dir.as_ref()?;
+
+        if !lock!(self.downloading).insert(url.to_string()) {
+            return None;
+        }
+
+        debug!("Downloading album art from {url}");
+        let res = self.fetch(dir, &url).await;
+
+        lock!(self.downloading).remove(url.as_str());
+
+        match res {
+            Ok(path) => Some(path.to_string_lossy().to_string()),
+            Err(err) => {
+                error!("Failed to download album art from {url}: {err:?}");
+                None
+            }
+        }
+    }
+
+    #[cfg(feature = "http")]
+    async fn fetch(&self, dir: &Path, url: &reqwest::Url) -> Result<PathBuf> {
+        let res = reqwest::get(url.clone()).await?;
+
+        let status = res.status();
+        if !status.is_success() {
+            return Err(Report::msg(format!(
+                "Received non-success HTTP code ({status})"
+            )));
+        }
+
+        let bytes = res.bytes().await?;
+
+        let dir = dir.to_path_buf();
+        let path = dir.join(cache_file_name(url.as_str()));
+        let max_size = self.max_size;
+
+        crate::spawn_blocking(move || -> Result<PathBuf> {
+            fs::create_dir_all(&dir)?;
+
+            // written in full before being renamed into place,
+            // so a partial file is never loaded
+            let partial = path.with_extension("part");
+            fs::write(&partial, &bytes)?;
+            fs::rename(&partial, &path)?;
+
+            evict(&dir, max_size, &path);
+            Ok(path)
+        })
+        .await?
+    }
+}
+
+/// Gets the local path from a `file://` URI,
+/// decoding any percent-encoded characters.
+///
+/// Returns `None` if the input is not a `file://` URI.
+fn file_uri_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+
+    // skips the host, which is usually empty or `localhost`
+    let path = &path.as_bytes()[path.find('/')?..];
+
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+
+    while i < path.len() {
+        let escaped = (path[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(path[i]);
+                i += 1;
+            }
+        }
+    }
+
+    Some(PathBuf::from(OsString::from_vec(decoded)))
+}
+
+/// Gets the name of the cache file for the URL.
+fn cache_file_name(url: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Removes the least recently used files
+/// until the cache fits within the maximum size.
+/// The `keep` file is never removed.
+fn evict(dir: &Path, max_size: u64, keep: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let files = entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata.is_file().then(|| {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                (entry.path(), metadata.len(), modified)
+            })
+        })
+        .collect();
+
+    for path in files_to_evict(files, max_size, keep) {
+        debug!("Removing cached album art {}", path.display());
+
+        if let Err(err) = fs::remove_file(&path) {
+            debug!("Failed to remove cached album art: {err}");
+        }
+    }
+}
+
+/// Picks the oldest files to remove, given each file's size and modified time,
+/// so that the rest fit within the maximum size.
+fn files_to_evict(
+    mut files: Vec<(PathBuf, u64, SystemTime)>,
+    max_size: u64,
+    keep: &Path,
+) -> Vec<PathBuf> {
+    let mut total = files.iter().map(|(_, size, _)| size).sum::<u64>();
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut evicted = vec![];
+
+    for (path, size, _) in files {
+        if total <= max_size {
+            break;
+        }
+
+        if path != keep {
+            total -= size;
+            evicted.push(path);
+        }
+    }
+
+    evicted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn reads_file_uris() {
+        assert_eq!(
+            file_uri_path("file:///tmp/cover%20art.jpg"),
+            Some(PathBuf::from("/tmp/cover art.jpg"))
+        );
+        assert_eq!(
+            file_uri_path("file://localhost/home/user/%E2%99%AA.png"),
+            Some(PathBuf::from("/home/user/♪.png"))
+        );
+        assert_eq!(
+            file_uri_path("file:///tmp/100%.png"),
+            Some(PathBuf::from("/tmp/100%.png"))
+        );
+        assert_eq!(file_uri_path("https://example.com/cover.jpg"), None);
+        assert_eq!(file_uri_path("/tmp/cover.jpg"), None);
+    }
+
+    #[test]
+    fn evicts_oldest_files() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        let files = vec![
+            (PathBuf::from("new"), 40, at(30)),
+            (PathBuf::from("oldest"), 40, at(10)),
+            (PathBuf::from("old"), 40, at(20)),
+        ];
+
+        let evicted = files_to_evict(files.clone(), 100, Path::new("new"));
+        assert_eq!(evicted, [PathBuf::from("oldest")]);
+
+        let evicted = files_to_evict(files.clone(), 40, Path::new("oldest"));
+        assert_eq!(evicted, [PathBuf::from("old"), PathBuf::from("new")]);
+
+        assert!(files_to_evict(files, 120, Path::new("new")).is_empty());
+    }
+}
@@ -1,4 +1,6 @@
 use crate::config::{CommonConfig, TruncateMode};
+use crate::duration::DurationStyle;
+use crate::script::ScriptInput;
 use dirs::{audio_dir, home_dir};
 use serde::Deserialize;
 use std::path::PathBuf;
@@ -53,6 +55,24 @@ pub struct Icons {
     /// **Default**: `󰠃`
     #[serde(default = "default_icon_artist")]
     pub(crate) artist: String,
+
+    /// Icon to display on the bar while idle is inhibited.
+    ///
+    /// **Default**: `󰅶`
+    #[serde(default = "default_icon_idle_inhibit")]
+    pub(crate) idle_inhibit: String,
+
+    /// Icon to display for each filled star of the rating.
+    ///
+    /// **Default**: `󰓎`
+    #[serde(default = "default_icon_rating_on")]
+    pub(crate) rating_on: String,
+
+    /// Icon to display for each empty star of the rating.
+    ///
+    /// **Default**: `󰓒`
+    #[serde(default = "default_icon_rating_off")]
+    pub(crate) rating_off: String,
 }
 
 impl Default for Icons {
@@ -66,6 +86,9 @@ impl Default for Icons {
             track: default_icon_track(),
             album: default_icon_album(),
             artist: default_icon_artist(),
+            idle_inhibit: default_icon_idle_inhibit(),
+            rating_on: default_icon_rating_on(),
+            rating_off: default_icon_rating_off(),
         }
     }
 }
@@ -124,6 +147,77 @@ pub struct MusicModule {
     #[serde(default = "default_cover_image_size")]
     pub(crate) cover_image_size: i32,
 
+    /// The maximum size of the album art cache, in megabytes.
+    /// Remote album art is downloaded into `$XDG_CACHE_HOME/ironbar/album-art`,
+    /// removing the least recently played covers once this is exceeded.
+    ///
+    /// Set to `0` to disable the cache,
+    /// in which case remote album art is downloaded for each track change.
+    ///
+    /// **Default**: `50`
+    #[serde(default = "default_cache_size_mb")]
+    pub(crate) cache_size_mb: u64,
+
+    /// How to write the elapsed time and track length inside the popup.
+    ///
+    /// **Valid options**: `clock`, `compact`, `verbose`
+    /// <br>
+    /// **Default**: `clock`
+    #[serde(default = "default_duration_style")]
+    pub(crate) duration_style: DurationStyle,
+
+    /// Whether to read the average colour of the album art,
+    /// for styling the widget and popup to match.
+    ///
+    /// See [adaptive colour](#adaptive-colour).
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub(crate) adaptive_color: bool,
+
+    /// Whether to prevent the compositor going idle,
+    /// such as turning off the screen, while the player is playing.
+    ///
+    /// The inhibitor is released when playback is paused or stopped,
+    /// or the player closes.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub(crate) inhibit_idle_while_playing: bool,
+
+    /// Whether to show an icon on the bar while idle is inhibited.
+    /// Has no effect unless `inhibit_idle_while_playing` is set.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    pub(crate) show_idle_inhibit_icon: bool,
+
+    /// Whether to show the current track's rating inside the popup.
+    ///
+    /// See [rating](#rating) for which players support this.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub(crate) show_rating: bool,
+
+    /// The number of stars to show the rating with.
+    /// Set to `1` for a single favourite toggle.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_rating_stars")]
+    pub(crate) rating_stars: u8,
+
+    /// A [script](scripts) to run to rate the current track,
+    /// for players which cannot set ratings themselves.
+    ///
+    /// The script is passed the number of stars as `$0`,
+    /// and the track's title, artist and album as `$1`, `$2` and `$3`.
+    ///
+    /// **Supported script types**: `oneshot`.
+    /// <br>
+    /// **Default**: `null`
+    pub(crate) on_rate: Option<ScriptInput>,
+
     // -- MPD --
     /// *[MPD Only]*
     /// TCP or Unix socket address of the MPD server.
@@ -141,6 +235,14 @@ pub struct MusicModule {
     #[serde(default = "default_music_dir")]
     pub(crate) music_dir: PathBuf,
 
+    /// *[MPD Only]*
+    /// Name of the sticker to read and write the rating to.
+    /// The sticker's value is from `0` to `10`.
+    ///
+    /// **Default**: `rating`
+    #[serde(default = "default_rating_sticker")]
+    pub(crate) rating_sticker: String,
+
     // -- Common --
     /// See [truncate options](module-level-options#truncate-mode).
     ///
@@ -192,6 +294,22 @@ fn default_icon_artist() -> String {
     String::from("󰠃")
 }
 
+fn default_icon_idle_inhibit() -> String {
+    String::from("󰅶")
+}
+
+fn default_icon_rating_on() -> String {
+    String::from("󰓎")
+}
+
+fn default_icon_rating_off() -> String {
+    String::from("󰓒")
+}
+
+fn default_rating_sticker() -> String {
+    String::from("rating")
+}
+
 fn default_music_dir() -> PathBuf {
     audio_dir().unwrap_or_else(|| home_dir().map(|dir| dir.join("Music")).unwrap_or_default())
 }
@@ -203,3 +321,15 @@ const fn default_icon_size() -> i32 {
 const fn default_cover_image_size() -> i32 {
     128
 }
+
+const fn default_rating_stars() -> u8 {
+    5
+}
+
+const fn default_cache_size_mb() -> u64 {
+    50
+}
+
+const fn default_duration_style() -> DurationStyle {
+    DurationStyle::Clock
+}
@@ -1,32 +1,39 @@
-use std::cell::RefMut;
+use std::cell::{Cell, RefMut};
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use color_eyre::Result;
+use color_eyre::{Report, Result};
 use glib::{markup_escape_text, Propagation, PropertySet};
+use gtk::ffi::GTK_STYLE_PROVIDER_PRIORITY_USER;
 use gtk::prelude::*;
-use gtk::{Button, IconTheme, Label, Orientation, Scale};
+use gtk::{gdk, Button, CssProvider, IconTheme, Label, Orientation, Scale, StyleContext};
 use regex::Regex;
 use tokio::sync::{broadcast, mpsc};
 use tracing::error;
 
 use crate::clients::music::{
-    self, MusicClient, PlayerState, PlayerUpdate, ProgressTick, Status, Track,
+    self, MusicClient, PlayerState, PlayerUpdate, ProgressTick, Rating, Status, Track,
 };
 use crate::clients::Clients;
-use crate::gtk_helpers::IronbarGtkExt;
-use crate::image::{new_icon_button, new_icon_label, ImageProvider};
+use crate::duration::format_duration;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::image::{new_icon_button, new_icon_label, ImageProvider, Rgb};
 use crate::modules::PopupButton;
 use crate::modules::{
-    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, WidgetContext,
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    WidgetContext,
 };
-use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use crate::script::Script;
+use crate::{arc_mut, glib_recv, lock, module_impl, send_async, try_send};
 
+use self::art::{AlbumArt, Cover};
 pub use self::config::MusicModule;
 use self::config::PlayerType;
 
+mod art;
 mod config;
 
 #[derive(Debug)]
@@ -37,16 +44,8 @@ pub enum PlayerCommand {
     Next,
     Volume(u8),
     Seek(Duration),
-}
-
-/// Formats a duration given in seconds
-/// in hh:mm format
-fn format_time(duration: Duration) -> String {
-    let time = duration.as_secs();
-    let minutes = (time / 60) % 60;
-    let seconds = time % 60;
-
-    format!("{minutes:0>2}:{seconds:0>2}")
+    /// Rates the current track, from `0.0` to `1.0`.
+    Rate(f64),
 }
 
 /// Extracts the formatting tokens from a formatting string
@@ -56,10 +55,52 @@ fn get_tokens(re: &Regex, format_string: &str) -> Vec<String> {
         .collect::<Vec<_>>()
 }
 
+/// Downloads the cover in the background,
+/// then re-sends the pending update with it if the cover is still current.
+#[cfg(feature = "http")]
+fn download_cover(
+    url: reqwest::Url,
+    album_art: AlbumArt,
+    pending_cover: Arc<std::sync::Mutex<Option<(String, SongUpdate)>>>,
+    tx: mpsc::Sender<ModuleUpdateEvent<ControllerEvent>>,
+) {
+    crate::spawn(async move {
+        let Some(path) = album_art.download(url.clone()).await else {
+            return;
+        };
+
+        let update = {
+            let mut pending_cover = lock!(pending_cover);
+            if pending_cover
+                .as_ref()
+                .is_some_and(|(pending_url, _)| pending_url == url.as_str())
+            {
+                pending_cover.take()
+            } else {
+                None
+            }
+        };
+
+        if let Some((_, mut update)) = update {
+            update.song.cover_path = Some(path);
+
+            // the module may have closed during the download
+            let _ = tx
+                .send(ModuleUpdateEvent::Update(ControllerEvent::Update(Some(
+                    update,
+                ))))
+                .await;
+        }
+    });
+}
+
 #[derive(Clone, Debug)]
 pub enum ControllerEvent {
     Update(Option<SongUpdate>),
     UpdateProgress(ProgressTick),
+    /// The current track's rating changed,
+    /// after rating it with the `on_rate` script.
+    UpdateRating(Option<Rating>),
 }
 
 #[derive(Clone, Debug)]
@@ -69,14 +110,141 @@ pub struct SongUpdate {
     display_string: String,
 }
 
+/// Styles the widget to match the colour of the album art.
+///
+/// The colour is defined as `@musicaccent` for all stylesheets,
+/// with a class added to the widget for whether it is dark or light.
+#[derive(Debug, Clone)]
+struct AdaptiveColor {
+    widget: Button,
+    provider: CssProvider,
+    /// Incremented for each cover,
+    /// so that colours read for previous covers can be ignored.
+    generation: Rc<Cell<u64>>,
+}
+
+impl AdaptiveColor {
+    fn new(widget: &Button) -> Self {
+        let provider = CssProvider::new();
+
+        // above the user stylesheet, so any fallback colour defined there is overridden
+        if let Some(screen) = gdk::Screen::default() {
+            StyleContext::add_provider_for_screen(
+                &screen,
+                &provider,
+                GTK_STYLE_PROVIDER_PRIORITY_USER as u32 + 1,
+            );
+        }
+
+        Self {
+            widget: widget.clone(),
+            provider,
+            generation: Rc::default(),
+        }
+    }
+
+    /// Reads the colour of the new cover,
+    /// or clears it if there is none.
+    fn update(&self, cover: Option<ImageProvider>) {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+
+        match cover {
+            Some(cover) => {
+                let adaptive_color = self.clone();
+                cover.load_average_color(move |color| {
+                    if adaptive_color.generation.get() == generation {
+                        adaptive_color.set(color);
+                    }
+                });
+            }
+            None => self.set(None),
+        }
+    }
+
+    fn set(&self, color: Option<Rgb>) {
+        let css = color
+            .map(|color| format!("@define-color musicaccent {color};"))
+            .unwrap_or_default();
+
+        if let Err(err) = self.provider.load_from_data(css.as_bytes()) {
+            error!("Failed to load album art colour: {err:?}");
+        }
+
+        self.widget
+            .set_class("music-dark", color.is_some_and(Rgb::is_dark));
+        self.widget
+            .set_class("music-light", color.is_some_and(|color| !color.is_dark()));
+    }
+}
+
+/// The track's title, artist and album,
+/// used to tell which track a rating set with the `on_rate` script is for.
+type TrackKey = (Option<String>, Option<String>, Option<String>);
+
+fn track_key(track: &Track) -> TrackKey {
+    (
+        track.title.clone(),
+        track.artist.clone(),
+        track.album.clone(),
+    )
+}
+
+/// The current track's rating state,
+/// shared between the update and command loops.
+#[derive(Debug, Default)]
+struct RatingState {
+    track: Option<TrackKey>,
+    /// Whether the player can set the current track's rating itself.
+    player_writable: bool,
+    /// The last rating set with the `on_rate` script, and the track it was for.
+    ///
+    /// The script's ratings cannot be read back,
+    /// so this is shown until the track changes.
+    script_rating: Option<(TrackKey, f64)>,
+}
+
+/// Gets the rating to show for a track.
+///
+/// A rating which the player can set is always used.
+/// Otherwise, the track can be rated with the `on_rate` script if there is one,
+/// starting from the player's read-only rating.
+/// With neither, there is no rating to show.
+fn resolve_rating(
+    rating: Option<Rating>,
+    has_script: bool,
+    script_rating: Option<f64>,
+) -> Option<Rating> {
+    match rating {
+        Some(rating) if rating.writable => Some(rating),
+        _ if has_script => Some(Rating {
+            value: script_rating
+                .or(rating.map(|rating| rating.value))
+                .unwrap_or_default(),
+            writable: true,
+        }),
+        rating => rating,
+    }
+}
+
+/// Converts a rating from `0.0` to `1.0` into a number of stars.
+fn rating_to_stars(rating: f64, stars: u8) -> u8 {
+    (rating.clamp(0.0, 1.0) * f64::from(stars)).round() as u8
+}
+
 fn get_client(
     mut clients: RefMut<'_, Clients>,
     player_type: PlayerType,
     host: String,
     music_dir: PathBuf,
+    rating_sticker: Option<String>,
 ) -> Arc<dyn MusicClient> {
     let client_type = match player_type {
-        PlayerType::Mpd => music::ClientType::Mpd { host, music_dir },
+        PlayerType::Mpd => music::ClientType::Mpd {
+            host,
+            music_dir,
+            rating_sticker,
+        },
         PlayerType::Mpris => music::ClientType::Mpris,
     };
 
@@ -89,10 +257,13 @@ impl Module<Button> for MusicModule {
 
     module_impl!("music");
 
+    fn is_sensitive() -> bool {
+        true
+    }
+
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let format = self.format.clone();
@@ -101,46 +272,140 @@ impl Module<Button> for MusicModule {
         let tokens = get_tokens(&re, self.format.as_str());
 
         let client = get_client(
-            context.ironbar.clients.borrow_mut(),
+            context.clients.borrow_mut(),
             self.player_type,
             self.host.clone(),
             self.music_dir.clone(),
+            self.show_rating.then(|| self.rating_sticker.clone()),
         );
 
+        let show_rating = self.show_rating;
+        let rating_stars = self.rating_stars.max(1);
+        let on_rate = self
+            .on_rate
+            .clone()
+            .filter(|_| show_rating)
+            .map(|script| Script::new_polling(script).with_env(&context.script_env));
+
+        let rating_state = arc_mut!(RatingState::default());
+
+        let wayland = self
+            .inhibit_idle_while_playing
+            .then(|| context.clients.borrow_mut().wayland());
+
+        let album_art = AlbumArt::new(self.cache_size_mb);
+
         // receive player updates
         {
             let tx = context.tx.clone();
             let client = client.clone();
+            let rating_state = rating_state.clone();
+            let has_script = on_rate.is_some();
+
+            context.spawn(async move {
+                // held while playing, dropping it releases the inhibitor
+                let mut idle_inhibit = None;
+
+                // the latest update, while its cover is downloading, with the cover URL.
+                // this is re-sent with the downloaded cover once it finishes.
+                let pending_cover = arc_mut!(None::<(String, SongUpdate)>);
 
-            spawn(async move {
                 loop {
                     let mut rx = client.subscribe_change();
 
                     while let Ok(update) = rx.recv().await {
                         match update {
-                            PlayerUpdate::Update(track, status) => match *track {
-                                Some(track) => {
-                                    let display_string =
-                                        replace_tokens(format.as_str(), &tokens, &track);
-
-                                    let update = SongUpdate {
-                                        song: track,
-                                        status,
-                                        display_string,
-                                    };
+                            PlayerUpdate::Update(track, status) => {
+                                if let Some(wayland) = &wayland {
+                                    let playing = track.is_some()
+                                        && matches!(status.state, PlayerState::Playing);
+
+                                    if playing && idle_inhibit.is_none() {
+                                        idle_inhibit = Some(wayland.inhibit_idle());
+                                    } else if !playing {
+                                        idle_inhibit = None;
+                                    }
+                                }
 
-                                    send_async!(
-                                        tx,
-                                        ModuleUpdateEvent::Update(ControllerEvent::Update(Some(
-                                            update
-                                        )))
-                                    );
+                                lock!(pending_cover).take();
+
+                                match *track {
+                                    Some(mut track) => {
+                                        if show_rating {
+                                            let key = track_key(&track);
+                                            let mut rating_state = lock!(rating_state);
+
+                                            rating_state.player_writable =
+                                                track.rating.is_some_and(|rating| rating.writable);
+
+                                            let script_rating = rating_state
+                                                .script_rating
+                                                .as_ref()
+                                                .filter(|(track, _)| *track == key)
+                                                .map(|(_, rating)| *rating);
+
+                                            track.rating = resolve_rating(
+                                                track.rating,
+                                                has_script,
+                                                script_rating,
+                                            );
+                                            rating_state.track = Some(key);
+                                        } else {
+                                            track.rating = None;
+                                        }
+
+                                        let display_string =
+                                            replace_tokens(format.as_str(), &tokens, &track);
+
+                                        let cover = track
+                                            .cover_path
+                                            .take()
+                                            .map(|cover| album_art.locate(&cover));
+
+                                        let mut update = SongUpdate {
+                                            song: track,
+                                            status,
+                                            display_string,
+                                        };
+
+                                        match cover {
+                                            Some(Cover::Ready(path)) => {
+                                                update.song.cover_path = Some(path);
+                                            }
+                                            #[cfg(feature = "http")]
+                                            Some(Cover::Download(url)) => {
+                                                *lock!(pending_cover) =
+                                                    Some((url.to_string(), update.clone()));
+
+                                                download_cover(
+                                                    url,
+                                                    album_art.clone(),
+                                                    pending_cover.clone(),
+                                                    tx.clone(),
+                                                );
+                                            }
+                                            None => {}
+                                        }
+
+                                        send_async!(
+                                            tx,
+                                            ModuleUpdateEvent::Update(ControllerEvent::Update(
+                                                Some(update)
+                                            ))
+                                        );
+                                    }
+                                    None => {
+                                        lock!(rating_state).track = None;
+
+                                        send_async!(
+                                            tx,
+                                            ModuleUpdateEvent::Update(ControllerEvent::Update(
+                                                None
+                                            ))
+                                        );
+                                    }
                                 }
-                                None => send_async!(
-                                    tx,
-                                    ModuleUpdateEvent::Update(ControllerEvent::Update(None))
-                                ),
-                            },
+                            }
                             PlayerUpdate::ProgressTick(progress_tick) => send_async!(
                                 tx,
                                 ModuleUpdateEvent::Update(ControllerEvent::UpdateProgress(
@@ -155,7 +420,9 @@ impl Module<Button> for MusicModule {
 
         // listen to ui events
         {
-            spawn(async move {
+            let tx = context.tx.clone();
+
+            context.spawn(async move {
                 while let Some(event) = rx.recv().await {
                     let res = match event {
                         PlayerCommand::Previous => client.prev(),
@@ -164,6 +431,31 @@ impl Module<Button> for MusicModule {
                         PlayerCommand::Next => client.next(),
                         PlayerCommand::Volume(vol) => client.set_volume_percent(vol),
                         PlayerCommand::Seek(duration) => client.seek(duration),
+                        PlayerCommand::Rate(rating) => {
+                            let player_writable = lock!(rating_state).player_writable;
+
+                            if player_writable {
+                                client.set_rating(rating)
+                            } else {
+                                let res = rate_with_script(
+                                    on_rate.as_ref(),
+                                    &rating_state,
+                                    rating,
+                                    rating_stars,
+                                );
+
+                                if let Ok(rating) = res {
+                                    send_async!(
+                                        tx,
+                                        ModuleUpdateEvent::Update(ControllerEvent::UpdateRating(
+                                            Some(rating)
+                                        ))
+                                    );
+                                }
+
+                                res.map(|_| ())
+                            }
+                        }
                     };
 
                     if let Err(err) = res {
@@ -187,19 +479,25 @@ impl Module<Button> for MusicModule {
 
         button.add(&button_contents);
 
-        let icon_play = new_icon_label(&self.icons.play, info.icon_theme, self.icon_size);
-        let icon_pause = new_icon_label(&self.icons.pause, info.icon_theme, self.icon_size);
+        let icon_size = info.scaled(self.icon_size);
+        let icon_play = new_icon_label(&self.icons.play, info.icon_theme, icon_size);
+        let icon_pause = new_icon_label(&self.icons.pause, info.icon_theme, icon_size);
+        let icon_idle_inhibit =
+            new_icon_label(&self.icons.idle_inhibit, info.icon_theme, icon_size);
+        icon_idle_inhibit.add_class("idle-inhibit");
+
         let label = Label::new(None);
 
         label.set_use_markup(true);
         label.set_angle(info.bar_position.get_angle());
 
         if let Some(truncate) = self.truncate {
-            truncate.truncate_label(&label);
+            label.truncate(truncate);
         }
 
         button_contents.add(&icon_pause);
         button_contents.add(&icon_play);
+        button_contents.add(&icon_idle_inhibit);
         button_contents.add(&label);
 
         {
@@ -216,11 +514,26 @@ impl Module<Button> for MusicModule {
             let tx = context.tx.clone();
             let rx = context.subscribe();
 
+            let adaptive_color = self.adaptive_color.then(|| AdaptiveColor::new(&button));
+            let icon_theme = info.icon_theme.clone();
+            let image_size = info.scaled(self.cover_image_size);
+            let mut prev_cover = None;
+
             glib_recv!(rx, event => {
                 let ControllerEvent::Update(mut event) = event else {
                     continue;
                 };
 
+                if let Some(adaptive_color) = &adaptive_color {
+                    let new_cover = event.as_ref().and_then(|event| event.song.cover_path.clone());
+                    if prev_cover != new_cover {
+                        adaptive_color.update(new_cover.as_ref().and_then(|cover_path| {
+                            ImageProvider::parse(cover_path, &icon_theme, false, image_size)
+                        }));
+                        prev_cover = new_cover;
+                    }
+                }
+
                 if let Some(event) = event.take() {
                     label.set_label(&event.display_string);
 
@@ -245,6 +558,12 @@ impl Module<Button> for MusicModule {
                         icon_pause.hide();
                         icon_play.hide();
                     }
+
+                    icon_idle_inhibit.set_visible(
+                        self.inhibit_idle_while_playing
+                            && self.show_idle_inhibit_icon
+                            && matches!(event.status.state, PlayerState::Playing),
+                    );
                 } else {
                     button.hide();
                     try_send!(tx, ModuleUpdateEvent::ClosePopup);
@@ -268,6 +587,7 @@ impl Module<Button> for MusicModule {
         info: &ModuleInfo,
     ) -> Option<gtk::Box> {
         let icon_theme = info.icon_theme;
+        let icon_size = info.scaled(self.icon_size);
 
         let container = gtk::Box::new(Orientation::Vertical, 10);
         let main_container = gtk::Box::new(Orientation::Horizontal, 10);
@@ -293,19 +613,23 @@ impl Module<Button> for MusicModule {
         info_box.add(&album_label.container);
         info_box.add(&artist_label.container);
 
+        let rating =
+            RatingWidget::new(&icons, self.rating_stars.max(1), icon_theme, icon_size, &tx);
+        info_box.add(&rating.container);
+
         let controls_box = gtk::Box::new(Orientation::Horizontal, 0);
         controls_box.add_class("controls");
 
-        let btn_prev = new_icon_button(&icons.prev, icon_theme, self.icon_size);
+        let btn_prev = new_icon_button(&icons.prev, icon_theme, icon_size);
         btn_prev.add_class("btn-prev");
 
-        let btn_play = new_icon_button(&icons.play, icon_theme, self.icon_size);
+        let btn_play = new_icon_button(&icons.play, icon_theme, icon_size);
         btn_play.add_class("btn-play");
 
-        let btn_pause = new_icon_button(&icons.pause, icon_theme, self.icon_size);
+        let btn_pause = new_icon_button(&icons.pause, icon_theme, icon_size);
         btn_pause.add_class("btn-pause");
 
-        let btn_next = new_icon_button(&icons.next, icon_theme, self.icon_size);
+        let btn_next = new_icon_button(&icons.next, icon_theme, icon_size);
         btn_next.add_class("btn-next");
 
         controls_box.add(&btn_prev);
@@ -322,7 +646,7 @@ impl Module<Button> for MusicModule {
         volume_slider.set_inverted(true);
         volume_slider.add_class("slider");
 
-        let volume_icon = new_icon_label(&icons.volume, icon_theme, self.icon_size);
+        let volume_icon = new_icon_label(&icons.volume, icon_theme, icon_size);
         volume_icon.add_class("icon");
 
         volume_box.pack_start(&volume_slider, true, true, 0);
@@ -397,10 +721,12 @@ impl Module<Button> for MusicModule {
         }
 
         container.show_all();
+        rating.container.hide();
 
         {
             let icon_theme = icon_theme.clone();
-            let image_size = self.cover_image_size;
+            let image_size = info.scaled(self.cover_image_size);
+            let duration_style = self.duration_style;
 
             let mut prev_cover = None;
             glib_recv!(rx, event =>  {
@@ -429,6 +755,7 @@ impl Module<Button> for MusicModule {
                         update_popup_metadata_label(update.song.title, &title_label);
                         update_popup_metadata_label(update.song.album, &album_label);
                         update_popup_metadata_label(update.song.artist, &artist_label);
+                        rating.set(update.song.rating);
 
                         match update.status.state {
                             PlayerState::Stopped => {
@@ -470,22 +797,36 @@ impl Module<Button> for MusicModule {
                     ControllerEvent::UpdateProgress(progress_tick)
                         if !drag_lock.load(Ordering::Relaxed) =>
                     {
-                        if let (Some(elapsed), Some(duration)) =
-                            (progress_tick.elapsed, progress_tick.duration)
-                        {
-                            progress_label.set_label(&format!(
-                                "{}/{}",
-                                format_time(elapsed),
-                                format_time(duration)
-                            ));
-
-                            progress.set_value(elapsed.as_secs_f64());
-                            progress.set_range(0.0, duration.as_secs_f64());
-                            progress_box.show_all();
-                        } else {
-                            progress_box.hide();
+                        match (progress_tick.elapsed, progress_tick.duration) {
+                            (Some(elapsed), Some(duration)) => {
+                                progress_label.set_label(&format!(
+                                    "{}/{}",
+                                    format_duration(elapsed.as_secs() as i64, duration_style),
+                                    format_duration(duration.as_secs() as i64, duration_style)
+                                ));
+
+                                // range first, so the new value is not clamped to the old track
+                                progress.set_range(0.0, duration.as_secs_f64());
+                                progress.set_value(elapsed.as_secs_f64());
+                                progress.set_sensitive(progress_tick.seekable);
+                                progress_box.show_all();
+                            }
+                            // streams have no length to seek within
+                            (Some(elapsed), None) => {
+                                progress_label.set_label(&format_duration(
+                                    elapsed.as_secs() as i64,
+                                    duration_style,
+                                ));
+
+                                progress.set_range(0.0, 1.0);
+                                progress.set_value(0.0);
+                                progress.set_sensitive(false);
+                                progress_box.show_all();
+                            }
+                            _ => progress_box.hide(),
                         }
                     }
+                    ControllerEvent::UpdateRating(new_rating) => rating.set(new_rating),
                     _ => {}
                 };
             });
@@ -495,6 +836,38 @@ impl Module<Button> for MusicModule {
     }
 }
 
+/// Runs the `on_rate` script for the current track,
+/// returning the rating to show for it.
+fn rate_with_script(
+    script: Option<&Script>,
+    rating_state: &std::sync::Mutex<RatingState>,
+    rating: f64,
+    stars: u8,
+) -> Result<Rating> {
+    let script = script.ok_or_else(|| Report::msg("The current track cannot be rated"))?;
+
+    let mut rating_state = lock!(rating_state);
+    let track = rating_state
+        .track
+        .clone()
+        .ok_or_else(|| Report::msg("No track is playing"))?;
+
+    let (title, artist, album) = &track;
+    script.run_as_oneshot(Some(&[
+        rating_to_stars(rating, stars).to_string(),
+        title.clone().unwrap_or_default(),
+        artist.clone().unwrap_or_default(),
+        album.clone().unwrap_or_default(),
+    ]));
+
+    rating_state.script_rating = Some((track, rating));
+
+    Ok(Rating {
+        value: rating,
+        writable: true,
+    })
+}
+
 fn update_popup_metadata_label(text: Option<String>, label: &IconLabel) {
     match text {
         Some(value) => {
@@ -535,6 +908,92 @@ fn get_token_value(song: &Track, token: &str) -> String {
     .unwrap_or_default()
 }
 
+/// A row of star buttons showing the current track's rating.
+///
+/// Clicking a star rates the track with that many stars,
+/// or clears the rating if it already has that many.
+#[derive(Debug)]
+struct RatingWidget {
+    container: gtk::Box,
+    /// Each star's button, with its filled and empty icons.
+    stars: Vec<(Button, gtk::Box, gtk::Box)>,
+    current: Rc<Cell<u8>>,
+}
+
+impl RatingWidget {
+    fn new(
+        icons: &config::Icons,
+        stars: u8,
+        icon_theme: &IconTheme,
+        icon_size: i32,
+        tx: &mpsc::Sender<PlayerCommand>,
+    ) -> Self {
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+        container.add_class("rating");
+
+        let current = Rc::new(Cell::new(0));
+
+        let stars = (1..=stars)
+            .map(|star| {
+                let button = Button::new();
+                button.add_class("btn-rating");
+
+                let contents = gtk::Box::new(Orientation::Horizontal, 0);
+                let icon_on = new_icon_label(&icons.rating_on, icon_theme, icon_size);
+                let icon_off = new_icon_label(&icons.rating_off, icon_theme, icon_size);
+
+                contents.add(&icon_on);
+                contents.add(&icon_off);
+                button.add(&contents);
+
+                {
+                    let current = current.clone();
+                    let tx = tx.clone();
+
+                    button.connect_clicked(move |_| {
+                        let rating = if current.get() == star { 0 } else { star };
+                        try_send!(
+                            tx,
+                            PlayerCommand::Rate(f64::from(rating) / f64::from(stars))
+                        );
+                    });
+                }
+
+                container.add(&button);
+                (button, icon_on, icon_off)
+            })
+            .collect();
+
+        Self {
+            container,
+            stars,
+            current,
+        }
+    }
+
+    /// Shows the rating, or hides the widget if there is none.
+    fn set(&self, rating: Option<Rating>) {
+        let Some(rating) = rating else {
+            self.container.hide();
+            return;
+        };
+
+        let filled = rating_to_stars(rating.value, self.stars.len() as u8);
+        self.current.set(filled);
+
+        for (star, (button, icon_on, icon_off)) in self.stars.iter().enumerate() {
+            let active = star < usize::from(filled);
+
+            button.set_class("active", active);
+            button.set_sensitive(rating.writable);
+            icon_on.set_visible(active);
+            icon_off.set_visible(!active);
+        }
+
+        self.container.show();
+    }
+}
+
 #[derive(Clone, Debug)]
 struct IconLabel {
     label: Label,
@@ -564,3 +1023,113 @@ impl IconLabel {
         Self { label, container }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::testing::{config, ControllerHarness, FakeMusicClient};
+
+    fn track(title: &str, artist: &str) -> Track {
+        Track {
+            title: Some(title.to_string()),
+            album: None,
+            artist: Some(artist.to_string()),
+            date: None,
+            disc: None,
+            genre: None,
+            track: None,
+            cover_path: None,
+            rating: None,
+        }
+    }
+
+    fn song(event: ControllerEvent) -> Option<SongUpdate> {
+        match event {
+            ControllerEvent::Update(song) => song,
+            ControllerEvent::UpdateProgress(_) | ControllerEvent::UpdateRating(_) => {
+                panic!("expected track update")
+            }
+        }
+    }
+
+    #[test]
+    fn formats_tracks_and_forwards_commands() {
+        let client = FakeMusicClient::new();
+        let module: MusicModule =
+            config(r#"{ "player_type": "mpris", "format": "{artist} - {title}" }"#);
+
+        let mut harness = ControllerHarness::new();
+        harness
+            .clients()
+            .set_music(music::ClientType::Mpris, client.clone());
+        harness.start(&module).expect("controller to start");
+
+        assert!(song(harness.next_update()).is_none());
+
+        client.set_track(Some(track("Song", "Artist")));
+        let update = song(harness.next_update()).expect("track to be set");
+        assert_eq!(update.display_string, "Artist - Song");
+        assert!(matches!(update.status.state, PlayerState::Stopped));
+
+        harness.send(PlayerCommand::Play);
+        let update = song(harness.next_update()).expect("track to be set");
+        assert!(matches!(update.status.state, PlayerState::Playing));
+    }
+
+    #[test]
+    fn resolves_ratings() {
+        let rating = |value, writable| Some(Rating { value, writable });
+
+        // the player's rating is preferred if it can set it
+        assert_eq!(
+            resolve_rating(rating(0.4, true), true, Some(1.0)),
+            rating(0.4, true)
+        );
+        assert_eq!(
+            resolve_rating(rating(0.4, false), false, None),
+            rating(0.4, false)
+        );
+        assert_eq!(
+            resolve_rating(rating(0.4, false), true, None),
+            rating(0.4, true)
+        );
+        assert_eq!(
+            resolve_rating(rating(0.4, false), true, Some(0.8)),
+            rating(0.8, true)
+        );
+        assert_eq!(resolve_rating(None, true, None), rating(0.0, true));
+        assert_eq!(resolve_rating(None, false, None), None);
+
+        assert_eq!(rating_to_stars(0.7, 5), 4);
+        assert_eq!(rating_to_stars(1.0, 1), 1);
+        assert_eq!(rating_to_stars(2.0, 5), 5);
+    }
+
+    #[test]
+    fn rates_tracks_with_player() {
+        let client = FakeMusicClient::new();
+        let module: MusicModule = config(r#"{ "player_type": "mpris", "show_rating": true }"#);
+
+        let mut harness = ControllerHarness::new();
+        harness
+            .clients()
+            .set_music(music::ClientType::Mpris, client.clone());
+        harness.start(&module).expect("controller to start");
+
+        assert!(song(harness.next_update()).is_none());
+
+        client.set_track(Some(Track {
+            rating: Some(Rating {
+                value: 0.2,
+                writable: true,
+            }),
+            ..track("Song", "Artist")
+        }));
+        let update = song(harness.next_update()).expect("track to be set");
+        assert_eq!(update.song.rating.map(|rating| rating.value), Some(0.2));
+
+        harness.send(PlayerCommand::Rate(0.6));
+        let update = song(harness.next_update()).expect("track to be set");
+        assert_eq!(update.song.rating.map(|rating| rating.value), Some(0.6));
+    }
+}
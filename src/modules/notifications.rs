@@ -1,13 +1,28 @@
-use crate::clients::swaync;
-use crate::config::CommonConfig;
+use crate::clients::swaync::{self, Notification, NotificationEvent, Urgency};
+use crate::config::{CommonConfig, TransitionType};
 use crate::gtk_helpers::IronbarGtkExt;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, send_async, spawn, try_send};
+use crate::i18n::tr;
+use crate::image::ImageProvider;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    PopupButton, WidgetContext,
+};
+use crate::{arc_mut, glib_recv, lock, module_impl, send_async, try_send};
+use gtk::pango::EllipsizeMode;
 use gtk::prelude::*;
-use gtk::{Align, Button, Label, Overlay};
+use gtk::{Align, Button, IconTheme, Image, Label, Orientation, Overlay, Revealer};
 use serde::Deserialize;
-use tokio::sync::mpsc::Receiver;
-use tracing::error;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::future;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::{self, Receiver};
+use tracing::{debug, error};
 
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -18,12 +33,33 @@ pub struct NotificationsModule {
     #[serde(default = "crate::config::default_true")]
     show_count: bool,
 
+    /// Format string for the count label.
+    /// See [formatting tokens](#formatting-tokens).
+    ///
+    /// The label is hidden while all the counts it shows are zero.
+    ///
+    /// **Default**: `{count}`
+    #[serde(default = "default_format")]
+    format: String,
+
     /// SwayNC state icons.
     ///
     /// See [icons](#icons).
     #[serde(default)]
     icons: Icons,
 
+    /// Inline toasts, showing each new notification next to the widget.
+    ///
+    /// See [toasts](#toasts).
+    #[serde(default)]
+    toasts: Toasts,
+
+    /// Popup showing the unread notifications, or the count for each app.
+    ///
+    /// See [popup](#popup).
+    #[serde(default)]
+    popup: Popup,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -71,6 +107,10 @@ struct Icons {
     open_dnd: String,
 }
 
+fn default_format() -> String {
+    String::from("{count}")
+}
+
 impl Default for Icons {
     fn default() -> Self {
         Self {
@@ -108,6 +148,114 @@ fn default_icon_open_dnd() -> String {
     String::from("󱅮")
 }
 
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+struct Toasts {
+    /// Whether to show a toast when a notification is received.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    enabled: bool,
+
+    /// The number of seconds to show each toast for.
+    /// Critical notifications are shown until dismissed.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_toast_timeout")]
+    timeout: u64,
+
+    /// The maximum width of the toast text, in characters.
+    /// Longer text is ellipsized.
+    ///
+    /// **Default**: `40`
+    #[serde(default = "default_toast_max_width")]
+    max_width: i32,
+
+    /// The size of the app icon in pixels.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_toast_icon_size")]
+    icon_size: i32,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout: default_toast_timeout(),
+            max_width: default_toast_max_width(),
+            icon_size: default_toast_icon_size(),
+        }
+    }
+}
+
+const fn default_toast_timeout() -> u64 {
+    5
+}
+
+const fn default_toast_max_width() -> i32 {
+    40
+}
+
+const fn default_toast_icon_size() -> i32 {
+    24
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+struct Popup {
+    /// Whether to show a popup with the unread notifications
+    /// when the widget is clicked, instead of opening the SwayNC panel.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    enabled: bool,
+
+    /// What to list in the popup.
+    /// `apps` shows the unread count for each app,
+    /// and `notifications` shows each notification.
+    ///
+    /// **Valid options**: `apps`, `notifications`
+    /// <br>
+    /// **Default**: `apps`
+    #[serde(default)]
+    mode: PopupMode,
+
+    /// The size of the app icons in pixels.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_toast_icon_size")]
+    icon_size: i32,
+
+    /// The maximum width of the notification text, in characters.
+    /// Longer summaries are ellipsized, and longer bodies are wrapped.
+    /// This only applies to the `notifications` mode.
+    ///
+    /// **Default**: `40`
+    #[serde(default = "default_toast_max_width")]
+    max_width: i32,
+}
+
+impl Default for Popup {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: PopupMode::default(),
+            icon_size: default_toast_icon_size(),
+            max_width: default_toast_max_width(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+enum PopupMode {
+    #[default]
+    Apps,
+    Notifications,
+}
+
 impl Icons {
     fn icon(&self, value: swaync::Event) -> &str {
         match (value.cc_open, value.count > 0, value.dnd) {
@@ -122,51 +270,487 @@ impl Icons {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl NotificationsModule {
+    /// Whether notifications need to be watched to count them by app.
+    fn tracks_apps(&self) -> bool {
+        self.popup.enabled || self.format.contains("{count_app#")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    State(swaync::Event),
+    /// The unread notification counts,
+    /// sent after each change to the state or notifications.
+    Counts(AppCounts),
+    Toast(NotificationEvent),
+}
+
+#[derive(Debug, Clone)]
 pub enum UiEvent {
     ToggleVisibility,
+    CloseNotification(u32),
+    /// Closes all the notifications from the app.
+    CloseApp(String),
+    /// Closes every notification.
+    CloseAll,
+}
+
+/// The notifications from a single app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppGroup {
+    pub name: String,
+    pub icon: String,
+    pub count: u32,
+}
+
+/// Tracks which app each unread notification came from.
+///
+/// Only notifications sent since Ironbar started can be tracked,
+/// so the SwayNC total is also kept to count the rest.
+/// The tracked notifications never outnumber the total.
+#[derive(Debug, Clone, Default)]
+pub struct AppCounts {
+    /// The tracked notifications, oldest first.
+    notifications: Vec<Notification>,
+    total: u32,
+}
+
+impl AppCounts {
+    fn apply(&mut self, event: NotificationEvent) {
+        match event {
+            NotificationEvent::New(notification) => {
+                match self
+                    .notifications
+                    .iter_mut()
+                    .find(|existing| existing.id == notification.id)
+                {
+                    Some(existing) => *existing = notification,
+                    None => self.notifications.push(notification),
+                }
+            }
+            NotificationEvent::Closed(id) => {
+                self.notifications
+                    .retain(|notification| notification.id != id);
+            }
+        }
+    }
+
+    /// Sets the SwayNC total.
+    ///
+    /// Notifications can be removed without a signal being seen,
+    /// for example when the daemon restarts,
+    /// so the oldest are forgotten if there are more than the total.
+    fn set_total(&mut self, total: u32) {
+        self.total = total;
+
+        let excess = self.notifications.len().saturating_sub(total as usize);
+        self.notifications.drain(..excess);
+    }
+
+    /// Gets the number of unread notifications from the app,
+    /// ignoring case.
+    fn app(&self, name: &str) -> u32 {
+        self.notifications
+            .iter()
+            .filter(|notification| notification.app_name.eq_ignore_ascii_case(name))
+            .count() as u32
+    }
+
+    /// Gets the number of unread notifications not from a tracked app,
+    /// such as those sent before Ironbar started.
+    fn untracked(&self) -> u32 {
+        self.total.saturating_sub(self.notifications.len() as u32)
+    }
+
+    /// Gets the notifications grouped by app, most first.
+    fn groups(&self) -> Vec<AppGroup> {
+        let mut groups = Vec::<AppGroup>::new();
+
+        for notification in &self.notifications {
+            match groups
+                .iter_mut()
+                .find(|group| group.name == notification.app_name)
+            {
+                Some(group) => {
+                    group.count += 1;
+
+                    // use the latest icon, in case it has changed
+                    if !notification.app_icon.is_empty() {
+                        group.icon.clone_from(&notification.app_icon);
+                    }
+                }
+                None => groups.push(AppGroup {
+                    name: notification.app_name.clone(),
+                    icon: notification.app_icon.clone(),
+                    count: 1,
+                }),
+            }
+        }
+
+        groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+        groups
+    }
+
+    /// Gets a tracked notification by its ID.
+    fn get(&self, id: u32) -> Option<&Notification> {
+        self.notifications
+            .iter()
+            .find(|notification| notification.id == id)
+    }
+
+    /// Gets the IDs of the unread notifications from the app.
+    fn ids(&self, name: &str) -> Vec<u32> {
+        self.notifications
+            .iter()
+            .filter(|notification| notification.app_name == name)
+            .map(|notification| notification.id)
+            .collect()
+    }
+}
+
+/// Replaces the `{count}` and `{count_app#<name>}` tokens in the format.
+///
+/// Also returns whether any of the counts are above zero.
+fn format_counts(format: &str, counts: &AppCounts) -> (String, bool) {
+    let mut output = String::new();
+    let mut any_unread = false;
+    let mut rest = format;
+
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+
+        let token = &rest[start + 1..start + len];
+        let count = match token {
+            "count" => Some(counts.total),
+            _ => token
+                .strip_prefix("count_app#")
+                .map(|name| counts.app(name)),
+        };
+
+        output.push_str(&rest[..start]);
+
+        match count {
+            Some(count) => {
+                any_unread |= count > 0;
+                output.push_str(&count.to_string());
+            }
+            // unknown tokens are kept as-is
+            None => output.push_str(&rest[start..=start + len]),
+        }
+
+        rest = &rest[start + len + 1..];
+    }
+
+    output.push_str(rest);
+    (output, any_unread)
+}
+
+/// Sets the total and gets a copy of the counts to send.
+fn set_total(counts: &Mutex<AppCounts>, total: u32) -> AppCounts {
+    let mut counts = lock!(counts);
+    counts.set_total(total);
+    counts.clone()
+}
+
+/// Waits for the next notification event,
+/// or forever if notifications are not being watched.
+///
+/// Returns `None` once the channel closes.
+async fn next_notification(
+    rx: &mut Option<broadcast::Receiver<NotificationEvent>>,
+) -> Option<NotificationEvent> {
+    let Some(rx) = rx else {
+        return future::pending().await;
+    };
+
+    loop {
+        match rx.recv().await {
+            Ok(event) => return Some(event),
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// The inline toast widget,
+/// and the queue of notifications waiting to be shown in it.
+#[derive(Clone)]
+struct ToastQueue {
+    revealer: Revealer,
+    button: Button,
+    image: Image,
+    summary: Label,
+    body: Label,
+
+    icon_theme: IconTheme,
+    icon_size: i32,
+    timeout: Duration,
+
+    current: Rc<RefCell<Option<Notification>>>,
+    queue: Rc<RefCell<VecDeque<Notification>>>,
+    /// Incremented each time a toast is shown,
+    /// so that timers for previous toasts can be ignored.
+    generation: Rc<Cell<u64>>,
+}
+
+impl ToastQueue {
+    fn new(config: &Toasts, orientation: Orientation, info: &ModuleInfo) -> Self {
+        let revealer = Revealer::builder()
+            .transition_type(TransitionType::SlideStart.to_revealer_transition_type(orientation))
+            .reveal_child(false)
+            .build();
+
+        let button = Button::new();
+        button.add_class("toast");
+
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+
+        let image = Image::new();
+        image.add_class("icon");
+
+        let text = gtk::Box::new(Orientation::Vertical, 0);
+
+        let label = || {
+            Label::builder()
+                .xalign(0.0)
+                .ellipsize(EllipsizeMode::End)
+                .max_width_chars(config.max_width)
+                .build()
+        };
+
+        let summary = label();
+        summary.add_class("summary");
+
+        let body = label();
+        body.add_class("body");
+
+        text.add(&summary);
+        text.add(&body);
+
+        container.add(&image);
+        container.add(&text);
+        button.add(&container);
+        revealer.add(&button);
+        revealer.show_all();
+
+        Self {
+            revealer,
+            button,
+            image,
+            summary,
+            body,
+            icon_theme: info.icon_theme.clone(),
+            icon_size: info.scaled(config.icon_size),
+            timeout: Duration::from_secs(config.timeout),
+            current: Rc::default(),
+            queue: Rc::default(),
+            generation: Rc::default(),
+        }
+    }
+
+    /// Shows the notification if nothing else is shown,
+    /// otherwise adds it to the queue.
+    /// Replaced notifications are updated in place.
+    fn push(&self, notification: Notification) {
+        let is_current = self
+            .current
+            .borrow()
+            .as_ref()
+            .is_some_and(|current| current.id == notification.id);
+
+        if is_current || self.current.borrow().is_none() {
+            self.show(notification);
+            return;
+        }
+
+        let mut queue = self.queue.borrow_mut();
+        if let Some(queued) = queue.iter_mut().find(|queued| queued.id == notification.id) {
+            *queued = notification;
+        } else {
+            queue.push_back(notification);
+        }
+    }
+
+    /// Removes the notification, whether shown or queued.
+    fn close(&self, id: u32) {
+        let is_current = self
+            .current
+            .borrow()
+            .as_ref()
+            .is_some_and(|current| current.id == id);
+
+        if is_current {
+            self.next();
+        } else {
+            self.queue.borrow_mut().retain(|queued| queued.id != id);
+        }
+    }
+
+    /// Gets the ID of the currently shown notification.
+    fn current_id(&self) -> Option<u32> {
+        self.current.borrow().as_ref().map(|current| current.id)
+    }
+
+    /// Shows the next queued notification,
+    /// or hides the toast if the queue is empty.
+    fn next(&self) {
+        let next = self.queue.borrow_mut().pop_front();
+
+        match next {
+            Some(notification) => self.show(notification),
+            None => {
+                self.current.replace(None);
+                self.generation.set(self.generation.get() + 1);
+                self.revealer.set_reveal_child(false);
+            }
+        }
+    }
+
+    fn show(&self, notification: Notification) {
+        self.summary.set_label(&notification.summary);
+        self.body.set_label(&notification.body);
+        self.body.set_visible(!notification.body.is_empty());
+
+        let icon = if notification.app_icon.is_empty() {
+            &notification.app_name
+        } else {
+            &notification.app_icon
+        };
+
+        let loaded = ImageProvider::parse(icon, &self.icon_theme, false, self.icon_size)
+            .is_some_and(|provider| provider.load_into_image(self.image.clone()).is_ok());
+        self.image.set_visible(loaded);
+
+        for urgency in [Urgency::Low, Urgency::Normal, Urgency::Critical] {
+            self.button
+                .set_class(urgency.class(), urgency == notification.urgency);
+        }
+
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+
+        // critical notifications stay until dismissed
+        if notification.urgency != Urgency::Critical {
+            let toasts = self.clone();
+            glib::timeout_add_local_once(self.timeout, move || {
+                if toasts.generation.get() == generation {
+                    toasts.next();
+                }
+            });
+        }
+
+        self.current.replace(Some(notification));
+        self.revealer.set_reveal_child(true);
+    }
 }
 
-impl Module<Overlay> for NotificationsModule {
-    type SendMessage = swaync::Event;
+impl Module<gtk::Box> for NotificationsModule {
+    type SendMessage = Update;
     type ReceiveMessage = UiEvent;
 
     module_impl!("notifications");
 
+    fn is_sensitive() -> bool {
+        true
+    }
+
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         mut rx: Receiver<Self::ReceiveMessage>,
     ) -> color_eyre::Result<()>
     where
-        <Self as Module<Overlay>>::SendMessage: Clone,
+        <Self as Module<gtk::Box>>::SendMessage: Clone,
     {
         let client = context.try_client::<swaync::Client>()?;
 
+        let counts = arc_mut!(AppCounts::default());
+
         {
             let client = client.clone();
+            let counts = counts.clone();
             let mut rx = client.subscribe();
             let tx = context.tx.clone();
 
-            spawn(async move {
+            // notifications are only watched when needed, as this monitors the session bus
+            let mut notifications_rx = self.tracks_apps().then(|| client.subscribe_notifications());
+
+            context.spawn(async move {
                 let initial_state = client.state().await;
 
                 match initial_state {
-                    Ok(ev) => send_async!(tx, ModuleUpdateEvent::Update(ev)),
+                    Ok(ev) => {
+                        send_async!(tx, ModuleUpdateEvent::Update(Update::State(ev)));
+
+                        let counts = set_total(&counts, ev.count);
+                        send_async!(tx, ModuleUpdateEvent::Update(Update::Counts(counts)));
+                    }
                     Err(err) => error!("{err:?}"),
                 };
 
-                while let Ok(ev) = rx.recv().await {
-                    send_async!(tx, ModuleUpdateEvent::Update(ev));
+                loop {
+                    select! {
+                        ev = rx.recv() => {
+                            let Ok(ev) = ev else {
+                                break;
+                            };
+
+                            send_async!(tx, ModuleUpdateEvent::Update(Update::State(ev)));
+
+                            let counts = set_total(&counts, ev.count);
+                            send_async!(tx, ModuleUpdateEvent::Update(Update::Counts(counts)));
+                        }
+                        ev = next_notification(&mut notifications_rx) => {
+                            let Some(ev) = ev else {
+                                notifications_rx = None;
+                                continue;
+                            };
+
+                            let counts = {
+                                let mut counts = lock!(counts);
+                                counts.apply(ev);
+                                counts.clone()
+                            };
+
+                            send_async!(tx, ModuleUpdateEvent::Update(Update::Counts(counts)));
+                        }
+                    }
+                }
+            });
+        }
+
+        if self.toasts.enabled {
+            let mut rx = client.subscribe_notifications();
+            let tx = context.tx.clone();
+
+            context.spawn(async move {
+                loop {
+                    match rx.recv().await {
+                        Ok(ev) => send_async!(tx, ModuleUpdateEvent::Update(Update::Toast(ev))),
+                        Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    }
                 }
             });
         }
 
-        spawn(async move {
+        context.spawn(async move {
             while let Some(event) = rx.recv().await {
                 match event {
                     UiEvent::ToggleVisibility => client.toggle_visibility().await,
+                    UiEvent::CloseNotification(id) => client.close_notification(id).await,
+                    UiEvent::CloseApp(name) => {
+                        debug!("Closing notifications from {name}");
+
+                        let ids = lock!(counts).ids(&name);
+                        for id in ids {
+                            client.close_notification(id).await;
+                        }
+                    }
+                    UiEvent::CloseAll => client.close_all_notifications().await,
                 }
             }
         });
@@ -177,14 +761,18 @@ impl Module<Overlay> for NotificationsModule {
     fn into_widget(
         self,
         context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _info: &ModuleInfo,
-    ) -> color_eyre::Result<ModuleParts<Overlay>>
+        info: &ModuleInfo,
+    ) -> color_eyre::Result<ModuleParts<gtk::Box>>
     where
-        <Self as Module<Overlay>>::SendMessage: Clone,
+        <Self as Module<gtk::Box>>::SendMessage: Clone,
     {
+        let orientation = info.bar_position.orientation();
+        let container = gtk::Box::new(orientation, 0);
+
         let overlay = Overlay::new();
         let button = Button::with_label(&self.icons.closed_none);
         overlay.add(&button);
+        container.add(&overlay);
 
         let label = Label::builder()
             .label("0")
@@ -198,26 +786,470 @@ impl Module<Overlay> for NotificationsModule {
             overlay.set_overlay_pass_through(&label, true);
         }
 
-        let ctx = context.controller_tx.clone();
-        button.connect_clicked(move |_| {
-            try_send!(ctx, UiEvent::ToggleVisibility);
+        {
+            let ctx = context.controller_tx.clone();
+            let tx = context.tx.clone();
+            let popup = self.popup.enabled;
+
+            button.connect_clicked(move |button| {
+                if popup {
+                    try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                } else {
+                    try_send!(ctx, UiEvent::ToggleVisibility);
+                }
+            });
+        }
+
+        let toasts = self.toasts.enabled.then(|| {
+            let toasts = ToastQueue::new(&self.toasts, orientation, info);
+            container.add(&toasts.revealer);
+
+            {
+                let toasts = toasts.clone();
+                let ctx = context.controller_tx.clone();
+
+                toasts.button.clone().connect_clicked(move |_| {
+                    if let Some(id) = toasts.current_id() {
+                        try_send!(ctx, UiEvent::CloseNotification(id));
+                        toasts.next();
+                    }
+                });
+            }
+
+            toasts
         });
 
         {
             let button = button.clone();
+            let icons = self.icons.clone();
+            let format = self.format.clone();
+            let show_count = self.show_count;
 
             glib_recv!(context.subscribe(), ev => {
-                let icon = self.icons.icon(ev);
-                button.set_label(icon);
-
-                label.set_label(&ev.count.to_string());
-                label.set_visible(self.show_count && ev.count > 0);
+                match ev {
+                    Update::State(ev) => {
+                        let icon = icons.icon(ev);
+                        button.set_label(icon);
+                    }
+                    Update::Counts(counts) => {
+                        let (text, any_unread) = format_counts(&format, &counts);
+                        label.set_label(&text);
+                        label.set_visible(show_count && any_unread);
+                    }
+                    Update::Toast(ev) => {
+                        if let Some(toasts) = &toasts {
+                            match ev {
+                                NotificationEvent::New(notification) => toasts.push(notification),
+                                NotificationEvent::Closed(id) => toasts.close(id),
+                            }
+                        }
+                    }
+                }
             });
         }
 
+        let popup = self
+            .into_popup(
+                context.controller_tx.clone(),
+                context.subscribe(),
+                context,
+                info,
+            )
+            .into_popup_parts(vec![&button]);
+
         Ok(ModuleParts {
-            widget: overlay,
-            popup: None,
+            widget: container,
+            popup,
         })
     }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        if !self.popup.enabled {
+            return None;
+        }
+
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        let icon_theme = info.icon_theme.clone();
+        let icon_size = info.scaled(self.popup.icon_size);
+
+        match self.popup.mode {
+            PopupMode::Apps => container.add(&app_list(&tx, rx, &icon_theme, icon_size)),
+            PopupMode::Notifications => {
+                let list = notification_list(&tx, rx, &icon_theme, icon_size, self.popup.max_width);
+                container.add(&list);
+
+                let clear = Button::with_label(tr("notifications.clear_all"));
+                clear.add_class("btn-clear");
+                container.add(&clear);
+
+                let tx = tx.clone();
+                clear.connect_clicked(move |_| {
+                    try_send!(tx, UiEvent::CloseAll);
+                });
+            }
+        }
+
+        let open = Button::with_label(tr("notifications.open_center"));
+        open.add_class("btn-open");
+        container.add(&open);
+
+        open.connect_clicked(move |_| {
+            try_send!(context.tx, ModuleUpdateEvent::ClosePopup);
+            try_send!(tx, UiEvent::ToggleVisibility);
+        });
+
+        container.show_all();
+
+        Some(container)
+    }
+}
+
+/// Creates the popup list of unread counts for each app.
+fn app_list(
+    tx: &mpsc::Sender<UiEvent>,
+    rx: broadcast::Receiver<Update>,
+    icon_theme: &IconTheme,
+    icon_size: i32,
+) -> gtk::Box {
+    let apps = gtk::Box::new(Orientation::Vertical, 5);
+    apps.add_class("apps");
+
+    let tx = tx.clone();
+    let icon_theme = icon_theme.clone();
+
+    {
+        let apps = apps.clone();
+
+        glib_recv!(rx, ev => {
+            if let Update::Counts(counts) = ev {
+                for row in apps.children() {
+                    apps.remove(&row);
+                }
+
+                for group in counts.groups() {
+                    let row = app_row(&group.name, &group.icon, group.count, &icon_theme, icon_size);
+
+                    let tx = tx.clone();
+                    row.connect_clicked(move |_| {
+                        try_send!(tx, UiEvent::CloseApp(group.name.clone()));
+                    });
+
+                    apps.add(&row);
+                }
+
+                let untracked = counts.untracked();
+                if untracked > 0 {
+                    // these cannot be dismissed, as which notifications they are is unknown
+                    let row = app_row("Other", "", untracked, &icon_theme, icon_size);
+                    row.add_class("untracked");
+                    row.set_sensitive(false);
+                    apps.add(&row);
+                }
+
+                apps.show_all();
+            }
+        });
+    }
+
+    apps
+}
+
+/// Creates the popup list of unread notifications, newest first.
+///
+/// Rows are kept between updates,
+/// so that new notifications are added to the top of the list while it is open.
+fn notification_list(
+    tx: &mpsc::Sender<UiEvent>,
+    rx: broadcast::Receiver<Update>,
+    icon_theme: &IconTheme,
+    icon_size: i32,
+    max_width: i32,
+) -> gtk::Box {
+    let container = gtk::Box::new(Orientation::Vertical, 5);
+    container.add_class("notification-list");
+
+    let list = gtk::Box::new(Orientation::Vertical, 5);
+    container.add(&list);
+
+    // notifications sent before Ironbar started cannot be shown
+    let untracked = Label::new(None);
+    untracked.add_class("untracked");
+    untracked.set_no_show_all(true);
+    container.add(&untracked);
+
+    let tx = tx.clone();
+    let icon_theme = icon_theme.clone();
+    let mut rows = HashMap::<u32, NotificationRow>::new();
+
+    glib_recv!(rx, ev => {
+        if let Update::Counts(counts) = ev {
+            rows.retain(|&id, row| {
+                let open = counts.get(id).is_some();
+                if !open {
+                    list.remove(&row.container);
+                }
+
+                open
+            });
+
+            // oldest first, so that each new row goes above the one before
+            for notification in &counts.notifications {
+                match rows.get_mut(&notification.id) {
+                    Some(row) => {
+                        if row.notification != *notification {
+                            row.set(notification.clone(), &icon_theme, icon_size);
+                        }
+                    }
+                    None => {
+                        let row = NotificationRow::new(notification.clone(), &tx, &icon_theme, icon_size, max_width);
+
+                        list.add(&row.container);
+                        list.reorder_child(&row.container, 0);
+                        row.container.show_all();
+                        row.refresh_visible();
+
+                        rows.insert(notification.id, row);
+                    }
+                }
+            }
+
+            let count = counts.untracked();
+            untracked.set_label(&format!("+{count} more"));
+            untracked.set_visible(count > 0);
+        }
+    });
+
+    container
+}
+
+/// A popup row showing a single notification.
+struct NotificationRow {
+    container: gtk::Box,
+    content: gtk::Box,
+    image: Image,
+    summary: Label,
+    body: Label,
+    notification: Notification,
+}
+
+impl NotificationRow {
+    fn new(
+        notification: Notification,
+        tx: &mpsc::Sender<UiEvent>,
+        icon_theme: &IconTheme,
+        icon_size: i32,
+        max_width: i32,
+    ) -> Self {
+        let id = notification.id;
+
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+        container.add_class("notification");
+
+        let content = gtk::Box::new(Orientation::Horizontal, 10);
+        content.add_class("content");
+        content.set_hexpand(true);
+
+        let image = Image::new();
+        image.add_class("icon");
+
+        let text = gtk::Box::new(Orientation::Vertical, 0);
+
+        let summary = Label::builder()
+            .use_markup(true)
+            .xalign(0.0)
+            .ellipsize(EllipsizeMode::End)
+            .max_width_chars(max_width)
+            .build();
+        summary.add_class("summary");
+
+        let body = Label::builder()
+            .use_markup(true)
+            .xalign(0.0)
+            .wrap(true)
+            .max_width_chars(max_width)
+            .build();
+        body.add_class("body");
+
+        text.add(&summary);
+        text.add(&body);
+        content.add(&image);
+        content.add(&text);
+
+        let dismiss = Button::with_label("󰅖");
+        dismiss.add_class("btn-dismiss");
+        dismiss.set_valign(Align::Start);
+
+        container.add(&content);
+        container.add(&dismiss);
+
+        {
+            let tx = tx.clone();
+            dismiss.connect_clicked(move |_| {
+                try_send!(tx, UiEvent::CloseNotification(id));
+            });
+        }
+
+        let mut row = Self {
+            container,
+            content,
+            image,
+            summary,
+            body,
+            notification: notification.clone(),
+        };
+
+        row.set(notification, icon_theme, icon_size);
+        row
+    }
+
+    fn set(&mut self, notification: Notification, icon_theme: &IconTheme, icon_size: i32) {
+        // notification text is plain, so must not be parsed as markup
+        self.summary
+            .set_markup(&glib::markup_escape_text(&notification.summary));
+        self.body
+            .set_markup(&glib::markup_escape_text(&notification.body));
+
+        let icon = if notification.app_icon.is_empty() {
+            &notification.app_name
+        } else {
+            &notification.app_icon
+        };
+
+        let loaded = ImageProvider::parse(icon, icon_theme, false, icon_size)
+            .is_some_and(|provider| provider.load_into_image(self.image.clone()).is_ok());
+        self.image.set_no_show_all(!loaded);
+        self.image.set_visible(loaded);
+
+        for urgency in [Urgency::Low, Urgency::Normal, Urgency::Critical] {
+            self.container
+                .set_class(urgency.class(), urgency == notification.urgency);
+        }
+
+        self.content.set_tooltip_text(Some(&notification.app_name));
+
+        self.notification = notification;
+        self.refresh_visible();
+    }
+
+    /// Hides the body while it is empty,
+    /// which `show_all` would otherwise override.
+    fn refresh_visible(&self) {
+        self.body.set_visible(!self.notification.body.is_empty());
+    }
+}
+
+/// Creates a popup row showing the app's unread count.
+fn app_row(name: &str, icon: &str, count: u32, icon_theme: &IconTheme, icon_size: i32) -> Button {
+    let button = Button::new();
+    button.add_class("app");
+
+    let container = gtk::Box::new(Orientation::Horizontal, 10);
+
+    let image = Image::new();
+    image.add_class("icon");
+
+    let icon = if icon.is_empty() { name } else { icon };
+    let loaded = ImageProvider::parse(icon, icon_theme, false, icon_size)
+        .is_some_and(|provider| provider.load_into_image(image.clone()).is_ok());
+
+    let name = Label::builder()
+        .label(name)
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(EllipsizeMode::End)
+        .build();
+    name.add_class("name");
+
+    let count = Label::new(Some(&count.to_string()));
+    count.add_class("count");
+
+    if loaded {
+        container.add(&image);
+    }
+
+    container.add(&name);
+    container.add(&count);
+    button.add(&container);
+
+    button
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(id: u32, app_name: &str) -> NotificationEvent {
+        NotificationEvent::New(Notification {
+            id,
+            app_name: app_name.to_string(),
+            app_icon: String::new(),
+            summary: String::new(),
+            body: String::new(),
+            urgency: Urgency::Normal,
+        })
+    }
+
+    #[test]
+    fn counts_notifications_by_app() {
+        let mut counts = AppCounts::default();
+        counts.set_total(2);
+
+        counts.apply(notification(1, "Chat"));
+        counts.apply(notification(2, "Mail"));
+        counts.apply(notification(3, "Chat"));
+        counts.apply(notification(3, "Chat"));
+        counts.set_total(5);
+
+        assert_eq!(counts.app("chat"), 2);
+        assert_eq!(counts.untracked(), 2);
+        assert_eq!(counts.ids("Chat"), [1, 3]);
+        assert_eq!(
+            counts
+                .get(2)
+                .map(|notification| notification.app_name.as_str()),
+            Some("Mail")
+        );
+        assert!(counts.get(4).is_none());
+        assert_eq!(
+            counts
+                .groups()
+                .iter()
+                .map(|group| (group.name.as_str(), group.count))
+                .collect::<Vec<_>>(),
+            [("Chat", 2), ("Mail", 1)]
+        );
+
+        counts.apply(NotificationEvent::Closed(1));
+        counts.set_total(1);
+
+        assert_eq!(counts.app("Chat"), 1);
+        assert_eq!(counts.app("Mail"), 0);
+        assert_eq!(counts.untracked(), 0);
+    }
+
+    #[test]
+    fn formats_counts() {
+        let mut counts = AppCounts::default();
+        counts.apply(notification(1, "Chat"));
+        counts.set_total(3);
+
+        assert_eq!(
+            format_counts("{count} ({count_app#chat})", &counts),
+            (String::from("3 (1)"), true)
+        );
+        assert_eq!(
+            format_counts("{count_app#Mail} {unknown} {", &counts),
+            (String::from("0 {unknown} {"), false)
+        );
+    }
 }
@@ -0,0 +1,327 @@
+mod pipewire;
+
+use self::pipewire::Captures;
+use crate::clients::volume::{self, Event, SourceOutput};
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::new_icon_label;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
+use crate::{glib_recv, lock, module_impl, send_async};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::Orientation;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Icons {
+    /// Icon to show while the microphone is in use.
+    ///
+    /// **Default**: `󰍬`
+    #[serde(default = "default_icon_microphone")]
+    microphone: String,
+
+    /// Icon to show while a camera is in use.
+    ///
+    /// **Default**: `󰄀`
+    #[serde(default = "default_icon_camera")]
+    camera: String,
+
+    /// Icon to show while the screen is being shared.
+    ///
+    /// **Default**: `󰍹`
+    #[serde(default = "default_icon_screenshare")]
+    screenshare: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            microphone: default_icon_microphone(),
+            camera: default_icon_camera(),
+            screenshare: default_icon_screenshare(),
+        }
+    }
+}
+
+fn default_icon_microphone() -> String {
+    String::from("󰍬")
+}
+
+fn default_icon_camera() -> String {
+    String::from("󰄀")
+}
+
+fn default_icon_screenshare() -> String {
+    String::from("󰍹")
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PrivacyModule {
+    /// Whether to show an indicator while an application is recording a microphone.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    microphone: bool,
+
+    /// Whether to show an indicator while an application is capturing a camera.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    camera: bool,
+
+    /// Whether to show an indicator while an application is capturing the screen.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    screenshare: bool,
+
+    /// Names of applications to ignore captures from,
+    /// such as volume meters. Names are not case-sensitive.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    ignore: Vec<String>,
+
+    /// Icons to show for each capture type.
+    ///
+    /// See [icons](#icons).
+    #[serde(default)]
+    icons: Icons,
+
+    /// The size to render the icons at.
+    /// Note this only applies to image-type icons.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+const fn default_icon_size() -> i32 {
+    24
+}
+
+#[derive(Debug, Clone)]
+pub enum PrivacyUpdate {
+    /// The applications recording from a microphone.
+    Microphone(Vec<String>),
+    /// The applications capturing video.
+    Video(Captures),
+}
+
+fn is_ignored(name: &str, ignore: &[String]) -> bool {
+    ignore
+        .iter()
+        .any(|ignored| ignored.eq_ignore_ascii_case(name))
+}
+
+/// Gets the names of applications with an unpaused recording stream.
+fn microphone_apps(outputs: &[SourceOutput], ignore: &[String]) -> Vec<String> {
+    let mut apps = outputs
+        .iter()
+        .filter(|output| !output.corked)
+        .map(|output| output.app_name.as_ref().unwrap_or(&output.name))
+        .filter(|name| !is_ignored(name, ignore))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    apps.sort();
+    apps.dedup();
+    apps
+}
+
+impl Module<gtk::Box> for PrivacyModule {
+    type SendMessage = PrivacyUpdate;
+    type ReceiveMessage = ();
+
+    module_impl!("privacy");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        if self.microphone {
+            let client = context.client::<dyn volume::VolumeClient>();
+            let ignore = self.ignore.clone();
+            let tx = context.tx.clone();
+
+            context.spawn(async move {
+                let mut rx = client.subscribe();
+
+                let get_apps = || {
+                    let outputs = client.source_outputs();
+                    let outputs = lock!(outputs);
+                    microphone_apps(&outputs, &ignore)
+                };
+
+                let mut apps = get_apps();
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(PrivacyUpdate::Microphone(apps.clone()))
+                );
+
+                while let Ok(event) = rx.recv().await {
+                    if !matches!(
+                        event,
+                        Event::AddOutput(_) | Event::UpdateOutput(_) | Event::RemoveOutput(_)
+                    ) {
+                        continue;
+                    }
+
+                    let new_apps = get_apps();
+                    if new_apps != apps {
+                        apps = new_apps;
+                        send_async!(
+                            tx,
+                            ModuleUpdateEvent::Update(PrivacyUpdate::Microphone(apps.clone()))
+                        );
+                    }
+                }
+            });
+        }
+
+        if self.camera || self.screenshare {
+            let ignore = self.ignore.clone();
+            let tx = context.tx.clone();
+
+            context.spawn(async move {
+                if let Err(err) = pipewire::watch(ignore, tx).await {
+                    error!("{err:?}");
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+
+        let icon_size = info.scaled(self.icon_size);
+        let indicator = |icon: &str, class: &str| {
+            let indicator = new_icon_label(icon, info.icon_theme, icon_size);
+            indicator.add_class(class);
+
+            // only shown while capturing
+            indicator.show_all();
+            indicator.set_no_show_all(true);
+            indicator.hide();
+
+            container.add(&indicator);
+            indicator
+        };
+
+        let microphone = indicator(&self.icons.microphone, "privacy-mic");
+        let camera = indicator(&self.icons.camera, "privacy-camera");
+        let screenshare = indicator(&self.icons.screenshare, "privacy-screenshare");
+
+        let show_camera = self.camera;
+        let show_screenshare = self.screenshare;
+
+        glib_recv!(context.subscribe(), update => {
+            match update {
+                PrivacyUpdate::Microphone(apps) => update_indicator(&microphone, &apps),
+                PrivacyUpdate::Video(Captures { camera: cameras, screenshare: screenshares }) => {
+                    if show_camera {
+                        update_indicator(&camera, &cameras);
+                    }
+
+                    if show_screenshare {
+                        update_indicator(&screenshare, &screenshares);
+                    }
+                }
+            }
+        });
+
+        Ok(ModuleParts::new(container, None))
+    }
+}
+
+/// Shows the indicator while any application is capturing,
+/// listing the applications in its tooltip.
+fn update_indicator(indicator: &gtk::Box, apps: &[String]) {
+    indicator.set_visible(!apps.is_empty());
+
+    if apps.is_empty() {
+        indicator.set_tooltip_text(None);
+    } else {
+        indicator.set_tooltip_text(Some(&apps.join("\n")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::testing::{config, ControllerHarness, FakeVolumeClient};
+
+    fn output(app_name: Option<&str>, corked: bool) -> SourceOutput {
+        SourceOutput {
+            index: 0,
+            name: String::from("stream"),
+            volume: 100.0,
+            muted: false,
+            corked,
+            app_name: app_name.map(ToString::to_string),
+            app_icons: vec![],
+            can_set_volume: true,
+        }
+    }
+
+    #[test]
+    fn finds_microphone_apps() {
+        let outputs = [
+            output(Some("Firefox"), false),
+            output(Some("Firefox"), false),
+            output(Some("Discord"), true),
+            output(Some("PulseAudio Volume Control"), false),
+            output(None, false),
+        ];
+
+        assert_eq!(
+            microphone_apps(&outputs, &[String::from("pulseaudio volume control")]),
+            vec![String::from("Firefox"), String::from("stream")]
+        );
+    }
+
+    #[test]
+    fn reports_microphone_use() {
+        let module: PrivacyModule = config(r#"{ "camera": false, "screenshare": false }"#);
+
+        let client = FakeVolumeClient::new();
+
+        let mut harness = ControllerHarness::new();
+        harness.clients().set_volume(client.clone());
+        harness.start(&module).expect("controller to start");
+
+        assert!(matches!(
+            harness.next_update(),
+            PrivacyUpdate::Microphone(apps) if apps.is_empty()
+        ));
+
+        client.add_source_output(output(Some("Firefox"), false));
+        assert!(matches!(
+            harness.next_update(),
+            PrivacyUpdate::Microphone(apps) if apps == [String::from("Firefox")]
+        ));
+
+        client.remove_source_output(0);
+        assert!(matches!(
+            harness.next_update(),
+            PrivacyUpdate::Microphone(apps) if apps.is_empty()
+        ));
+    }
+}
@@ -0,0 +1,381 @@
+use super::{is_ignored, PrivacyUpdate};
+use crate::clients::timer::Ticker;
+use crate::modules::ModuleUpdateEvent;
+use crate::{send_async, spawn_blocking};
+use color_eyre::{Report, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+const TYPE_NODE: &str = "PipeWire:Interface:Node";
+const TYPE_LINK: &str = "PipeWire:Interface:Link";
+
+/// Video devices APIs which belong to cameras.
+/// Any other video source, such as a compositor's screencast, is treated as a screenshare.
+const CAMERA_APIS: [&str; 2] = ["v4l2", "libcamera"];
+
+/// Processes which open cameras on behalf of PipeWire clients,
+/// whose captures are already found through the graph.
+const PIPEWIRE_PROCESSES: [&str; 2] = ["pipewire", "wireplumber"];
+
+/// How often to check for processes with a camera open directly.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An object from `pw-dump`.
+/// Objects without info have been removed.
+#[derive(Debug, Deserialize)]
+struct Object {
+    id: u32,
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    info: Option<Info>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Info {
+    state: Option<String>,
+    props: Option<HashMap<String, Value>>,
+    output_node_id: Option<u32>,
+    input_node_id: Option<u32>,
+}
+
+impl Info {
+    /// Applies changed fields from an update.
+    fn merge(&mut self, info: Self) {
+        if info.state.is_some() {
+            self.state = info.state;
+        }
+
+        if info.props.is_some() {
+            self.props = info.props;
+        }
+
+        if info.output_node_id.is_some() {
+            self.output_node_id = info.output_node_id;
+        }
+
+        if info.input_node_id.is_some() {
+            self.input_node_id = info.input_node_id;
+        }
+    }
+
+    fn prop(&self, key: &str) -> Option<&str> {
+        self.props.as_ref()?.get(key)?.as_str()
+    }
+
+    /// Gets the name of the application owning the node.
+    fn app_name(&self) -> Option<&str> {
+        self.prop("application.name")
+            .or_else(|| self.prop("application.process.binary"))
+            .or_else(|| self.prop("node.name"))
+    }
+}
+
+/// The names of applications capturing video, by type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Captures {
+    pub camera: Vec<String>,
+    pub screenshare: Vec<String>,
+}
+
+/// The nodes and links of the PipeWire graph.
+#[derive(Debug, Default)]
+struct Graph {
+    nodes: HashMap<u32, Info>,
+    links: HashMap<u32, Info>,
+}
+
+impl Graph {
+    fn apply(&mut self, objects: Vec<Object>) {
+        for object in objects {
+            let Some(info) = object.info else {
+                self.nodes.remove(&object.id);
+                self.links.remove(&object.id);
+                continue;
+            };
+
+            let objects = match object.kind.as_deref() {
+                Some(TYPE_NODE) => &mut self.nodes,
+                Some(TYPE_LINK) => &mut self.links,
+                _ => continue,
+            };
+
+            objects.entry(object.id).or_default().merge(info);
+        }
+    }
+
+    /// Gets the applications with a running video input stream,
+    /// split by whether any of the stream's sources is a camera.
+    fn captures(&self, ignore: &[String]) -> Captures {
+        let mut captures = Captures::default();
+
+        let streams = self.nodes.iter().filter(|(_, node)| {
+            node.prop("media.class") == Some("Stream/Input/Video")
+                && node.state.as_deref() == Some("running")
+        });
+
+        for (id, stream) in streams {
+            let mut sources = self
+                .links
+                .values()
+                .filter(|link| link.input_node_id == Some(*id))
+                .filter_map(|link| link.output_node_id)
+                .filter_map(|id| self.nodes.get(&id))
+                .peekable();
+
+            if sources.peek().is_none() {
+                continue;
+            }
+
+            let is_camera = sources.any(|source| {
+                source
+                    .prop("device.api")
+                    .is_some_and(|api| CAMERA_APIS.contains(&api))
+            });
+
+            let Some(name) = stream.app_name().filter(|name| !is_ignored(name, ignore)) else {
+                continue;
+            };
+
+            let apps = if is_camera {
+                &mut captures.camera
+            } else {
+                &mut captures.screenshare
+            };
+
+            if !apps.iter().any(|app| app == name) {
+                apps.push(name.to_string());
+            }
+        }
+
+        captures.camera.sort();
+        captures.screenshare.sort();
+
+        captures
+    }
+}
+
+/// Gets the names of processes with a V4L2 device open,
+/// from the open files of each process.
+///
+/// Only processes belonging to the same user can be checked.
+fn device_users(ignore: &[String]) -> Vec<String> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+
+    let own_pid = std::process::id();
+
+    let mut apps = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| *pid != own_pid && has_video_device(*pid))
+        .filter_map(|pid| fs::read_to_string(format!("/proc/{pid}/comm")).ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !PIPEWIRE_PROCESSES.contains(&name.as_str()) && !is_ignored(name, ignore))
+        .collect::<Vec<_>>();
+
+    apps.sort();
+    apps.dedup();
+    apps
+}
+
+fn has_video_device(pid: u32) -> bool {
+    fs::read_dir(format!("/proc/{pid}/fd")).is_ok_and(|fds| {
+        fds.filter_map(Result::ok)
+            .any(|fd| fs::read_link(fd.path()).is_ok_and(|target| is_video_device(&target)))
+    })
+}
+
+/// Checks whether the path is a V4L2 device node, such as `/dev/video0`.
+fn is_video_device(path: &Path) -> bool {
+    path.to_str()
+        .and_then(|path| path.strip_prefix("/dev/video"))
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Adds processes using a camera directly to the captures.
+fn with_device_users(mut captures: Captures, users: &[String]) -> Captures {
+    for user in users {
+        if !captures.camera.contains(user) {
+            captures.camera.push(user.clone());
+        }
+    }
+
+    captures.camera.sort();
+    captures
+}
+
+/// Follows the PipeWire graph through `pw-dump`,
+/// sending an update each time the video captures change.
+///
+/// Processes opening a camera directly are also checked for periodically,
+/// as these do not appear in the graph.
+///
+/// This only returns if `pw-dump` cannot be started or exits.
+pub async fn watch(
+    ignore: Vec<String>,
+    tx: mpsc::Sender<ModuleUpdateEvent<PrivacyUpdate>>,
+) -> Result<()> {
+    let mut child = Command::new("pw-dump")
+        .args(["--monitor", "--no-colors"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| Report::new(err).wrap_err("Failed to start pw-dump"))?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| Report::msg("Failed to read pw-dump output"))?;
+
+    let mut lines = BufReader::new(stdout).lines();
+    let mut ticker = Ticker::new(DEVICE_POLL_INTERVAL, false);
+
+    let mut graph = Graph::default();
+    let mut direct_users = Vec::new();
+    let mut captures = Captures::default();
+    let mut buffer = String::new();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    break;
+                };
+
+                buffer.push_str(&line);
+                buffer.push('\n');
+
+                // each batch of changes is written as a pretty-printed array,
+                // so an unindented bracket closes it.
+                if line != "]" {
+                    continue;
+                }
+
+                match serde_json::from_str(&buffer) {
+                    Ok(objects) => graph.apply(objects),
+                    Err(err) => warn!("Failed to parse pw-dump output: {err:?}"),
+                }
+
+                buffer.clear();
+            }
+            () = ticker.tick() => {
+                let ignore = ignore.clone();
+                direct_users = spawn_blocking(move || device_users(&ignore)).await?;
+            }
+        }
+
+        let new_captures = with_device_users(graph.captures(&ignore), &direct_users);
+        if new_captures != captures {
+            captures = new_captures;
+            send_async!(
+                tx,
+                ModuleUpdateEvent::Update(PrivacyUpdate::Video(captures.clone()))
+            );
+        }
+    }
+
+    Err(Report::msg("pw-dump exited"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Vec<Object> {
+        serde_json::from_str(json).expect("valid json")
+    }
+
+    #[test]
+    fn finds_captures() {
+        let mut graph = Graph::default();
+
+        graph.apply(parse(
+            r#"[
+              { "id": 40, "type": "PipeWire:Interface:Node", "info": {
+                "state": "idle",
+                "props": { "media.class": "Video/Source", "device.api": "v4l2", "node.name": "webcam" }
+              } },
+              { "id": 41, "type": "PipeWire:Interface:Node", "info": {
+                "state": "running",
+                "props": { "media.class": "Video/Source", "node.name": "xdpw_stream" }
+              } },
+              { "id": 50, "type": "PipeWire:Interface:Node", "info": {
+                "state": "running",
+                "props": { "media.class": "Stream/Input/Video", "application.name": "OBS", "object.serial": 12 }
+              } },
+              { "id": 51, "type": "PipeWire:Interface:Node", "info": {
+                "state": "paused",
+                "props": { "media.class": "Stream/Input/Video", "application.name": "Firefox" }
+              } },
+              { "id": 60, "type": "PipeWire:Interface:Link", "info": {
+                "output-node-id": 41, "input-node-id": 50, "state": "active"
+              } },
+              { "id": 61, "type": "PipeWire:Interface:Link", "info": {
+                "output-node-id": 40, "input-node-id": 51, "state": "paused"
+              } },
+              { "id": 70, "type": "PipeWire:Interface:Module", "info": { "props": {} } }
+            ]"#,
+        ));
+
+        assert_eq!(
+            graph.captures(&[]),
+            Captures {
+                camera: vec![],
+                screenshare: vec![String::from("OBS")],
+            }
+        );
+
+        // updates only include changed fields
+        graph.apply(parse(
+            r#"[{ "id": 51, "type": "PipeWire:Interface:Node", "info": { "state": "running" } }]"#,
+        ));
+
+        assert_eq!(
+            graph.captures(&[]),
+            Captures {
+                camera: vec![String::from("Firefox")],
+                screenshare: vec![String::from("OBS")],
+            }
+        );
+
+        assert_eq!(
+            graph.captures(&[String::from("obs")]).screenshare,
+            Vec::<String>::new()
+        );
+
+        graph.apply(parse(r#"[{ "id": 60, "info": null }]"#));
+        assert!(graph.captures(&[]).screenshare.is_empty());
+    }
+
+    #[test]
+    fn finds_device_users() {
+        assert!(is_video_device(Path::new("/dev/video0")));
+        assert!(is_video_device(Path::new("/dev/video12")));
+        assert!(!is_video_device(Path::new("/dev/video")));
+        assert!(!is_video_device(Path::new("/dev/video0p1")));
+        assert!(!is_video_device(Path::new("/dev/null")));
+
+        let captures = Captures {
+            camera: vec![String::from("Firefox")],
+            screenshare: vec![],
+        };
+
+        assert_eq!(
+            with_device_users(captures, &[String::from("zoom"), String::from("Firefox")]).camera,
+            vec![String::from("Firefox"), String::from("zoom")]
+        );
+    }
+}
@@ -1,14 +1,36 @@
-use crate::config::CommonConfig;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::config::{CommonConfig, ModuleOrientation};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
 use crate::script::{OutputStream, Script, ScriptMode};
-use crate::{glib_recv, module_impl, spawn, try_send};
+use crate::{glib_recv, module_impl, try_send};
 use color_eyre::{Help, Report, Result};
 use gtk::prelude::*;
-use gtk::Label;
+use gtk::{Label, Orientation};
 use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::error;
 
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Mode {
+    Poll,
+    Watch,
+    /// Polls the script, showing each line of output on its own label.
+    Lines,
+}
+
+impl From<Mode> for ScriptMode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Poll | Mode::Lines => Self::Poll,
+            Mode::Watch => Self::Watch,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ScriptModule {
@@ -23,11 +45,11 @@ pub struct ScriptModule {
     /// Script execution mode.
     /// See [modes](#modes) for more info.
     ///
-    /// **Valid options**: `poll`, `watch`
+    /// **Valid options**: `poll`, `watch`, `lines`
     /// <br />
     /// **Default**: `poll`
     #[serde(default = "default_mode")]
-    mode: ScriptMode,
+    mode: Mode,
 
     /// Time in milliseconds between executions.
     ///
@@ -35,14 +57,27 @@ pub struct ScriptModule {
     #[serde(default = "default_interval")]
     interval: u64,
 
+    /// The maximum number of lines to show in `lines` mode.
+    /// Any further lines are ignored.
+    ///
+    /// **Default**: `null`
+    max_lines: Option<usize>,
+
+    /// The direction to stack the labels in `lines` mode.
+    ///
+    /// **Valid options**: `horizontal`, `vertical`, `h`, `v`
+    /// <br />
+    /// **Default**: the bar's orientation
+    orientation: Option<ModuleOrientation>,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
 }
 
 /// `Mode::Poll`
-const fn default_mode() -> ScriptMode {
-    ScriptMode::Poll
+const fn default_mode() -> Mode {
+    Mode::Poll
 }
 
 /// 5000ms
@@ -53,14 +88,15 @@ const fn default_interval() -> u64 {
 impl From<&ScriptModule> for Script {
     fn from(module: &ScriptModule) -> Self {
         Self {
-            mode: module.mode,
+            mode: module.mode.into(),
             cmd: module.cmd.clone(),
             interval: module.interval,
+            ..Self::default()
         }
     }
 }
 
-impl Module<Label> for ScriptModule {
+impl Module<gtk::Box> for ScriptModule {
     type SendMessage = String;
     type ReceiveMessage = ();
 
@@ -68,14 +104,13 @@ impl Module<Label> for ScriptModule {
 
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         _rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
-        let script: Script = self.into();
+        let script = Script::from(self).with_env(&context.script_env);
 
         let tx = context.tx.clone();
-        spawn(async move {
+        context.spawn(async move {
             script.run(None, move |out, _| match out {
                OutputStream::Stdout(stdout) => {
                    try_send!(tx, ModuleUpdateEvent::Update(stdout));
@@ -97,17 +132,58 @@ impl Module<Label> for ScriptModule {
         self,
         context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         info: &ModuleInfo,
-    ) -> Result<ModuleParts<Label>> {
-        let label = Label::builder().use_markup(true).build();
-        label.set_angle(info.bar_position.get_angle());
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let orientation = self
+            .orientation
+            .map_or_else(|| info.bar_position.orientation(), Orientation::from);
+
+        let container = gtk::Box::new(orientation, 0);
+        let angle = info.bar_position.get_angle();
+
+        let create_label = move || {
+            let label = Label::builder().use_markup(true).build();
+            label.set_angle(angle);
+            label
+        };
+
+        if self.mode == Mode::Lines {
+            let container = container.clone();
+            let max_lines = self.max_lines.unwrap_or(usize::MAX);
+
+            let mut labels: Vec<Label> = vec![];
+
+            glib_recv!(context.subscribe(), s => {
+                let lines = s.lines().take(max_lines).collect::<Vec<_>>();
+
+                // remove labels for lines no longer present
+                for label in labels.drain(lines.len().min(labels.len())..) {
+                    container.remove(&label);
+                }
+
+                for (i, line) in lines.into_iter().enumerate() {
+                    if let Some(label) = labels.get(i) {
+                        label.set_markup(line);
+                    } else {
+                        let label = create_label();
+                        label.add_class(&format!("line-{}", i + 1));
+                        label.set_markup(line);
+
+                        container.add(&label);
+                        label.show();
+
+                        labels.push(label);
+                    }
+                }
+            });
+        } else {
+            let label = create_label();
+            container.add(&label);
 
-        {
-            let label = label.clone();
             glib_recv!(context.subscribe(), s => label.set_markup(s.as_str()));
         }
 
         Ok(ModuleParts {
-            widget: label,
+            widget: container,
             popup: None,
         })
     }
@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::Path;
+use tracing::trace;
+
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+
+/// The readings for a single system battery.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Battery {
+    /// The rate energy is being drawn at, in watts.
+    /// This is negative while charging.
+    pub power_draw: Option<f64>,
+    /// The current full capacity as a percentage of the design capacity.
+    pub health: Option<f64>,
+}
+
+/// Reads every battery powering the system, by its kernel name (for example `BAT0`).
+///
+/// Batteries in peripherals such as mice are not included.
+pub fn read_all() -> Vec<(String, Battery)> {
+    let Ok(entries) = fs::read_dir(POWER_SUPPLY_PATH) else {
+        return vec![];
+    };
+
+    let mut batteries = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let battery = parse(|attribute| read_attribute(&entry.path(), attribute))?;
+
+            trace!("Read battery {name}: {battery:?}");
+            Some((name, battery))
+        })
+        .collect::<Vec<_>>();
+
+    batteries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    batteries
+}
+
+fn read_attribute(path: &Path, attribute: &str) -> Option<String> {
+    fs::read_to_string(path.join(attribute))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Parses a power supply's attributes,
+/// returning `None` if it is not a system battery.
+///
+/// Some drivers report current and voltage instead of power,
+/// and charge instead of energy.
+fn parse(get: impl Fn(&str) -> Option<String>) -> Option<Battery> {
+    if get("type")? != "Battery" || get("scope").is_some_and(|scope| scope == "Device") {
+        return None;
+    }
+
+    let number = |attribute| get(attribute).and_then(|value| value.parse::<f64>().ok());
+
+    // microwatts, or microamps and microvolts.
+    // the current is signed on some drivers, so is only used for its size
+    let power = number("power_now")
+        .map(|power| power / 1e6)
+        .or_else(|| Some(number("current_now")? * number("voltage_now")? / 1e12))
+        .map(f64::abs);
+
+    let charging = get("status").is_some_and(|status| status == "Charging");
+    let power_draw = power.map(|power| if charging { -power } else { power });
+
+    let health = |full, design| {
+        let design = number(design).filter(|design| *design > 0.0)?;
+        Some(number(full)? / design * 100.0)
+    };
+
+    Some(Battery {
+        power_draw,
+        health: health("energy_full", "energy_full_design")
+            .or_else(|| health("charge_full", "charge_full_design")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn read(attributes: &[(&str, &str)]) -> Option<Battery> {
+        let attributes = attributes.iter().copied().collect::<HashMap<_, _>>();
+        parse(|attribute| attributes.get(attribute).map(ToString::to_string))
+    }
+
+    #[test]
+    fn reads_power_and_health() {
+        let battery = read(&[
+            ("type", "Battery"),
+            ("status", "Discharging"),
+            ("power_now", "8500000"),
+            ("energy_full", "45000000"),
+            ("energy_full_design", "50000000"),
+        ]);
+
+        assert_eq!(
+            battery,
+            Some(Battery {
+                power_draw: Some(8.5),
+                health: Some(90.0),
+            })
+        );
+    }
+
+    #[test]
+    fn converts_current_and_charge() {
+        let battery = read(&[
+            ("type", "Battery"),
+            ("status", "Charging"),
+            ("current_now", "-2000000"),
+            ("voltage_now", "12000000"),
+            ("charge_full", "3000000"),
+            ("charge_full_design", "4000000"),
+        ])
+        .expect("to be a battery");
+
+        assert_eq!(battery.power_draw, Some(-24.0));
+        assert_eq!(battery.health, Some(75.0));
+    }
+
+    #[test]
+    fn skips_other_supplies() {
+        assert!(read(&[("type", "Mains")]).is_none());
+        assert!(read(&[("type", "Battery"), ("scope", "Device")]).is_none());
+
+        let battery = read(&[("type", "Battery"), ("scope", "System")]);
+        assert_eq!(
+            battery,
+            Some(Battery {
+                power_draw: None,
+                health: None,
+            })
+        );
+    }
+}
@@ -0,0 +1,1068 @@
+mod battery;
+mod processes;
+mod remote;
+mod token;
+
+use self::processes::{ProcessesConfig, TopProcesses};
+use self::remote::RemoteConfig;
+use self::token::{Format, TokenValue, CORE_SUFFIX, PLACEHOLDER, SENSOR_SEPARATOR};
+use crate::config::{CommonConfig, ModuleOrientation};
+use crate::duration::format_hours_minutes;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    PopupButton, WidgetContext,
+};
+use crate::{glib_recv, module_impl, send_async, try_send};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Label};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use sysinfo::{ComponentExt, CpuExt, DiskExt, NetworkExt, RefreshKind, System, SystemExt};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::interval;
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SysInfoModule {
+    /// List of strings including formatting tokens.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// Tokens can be followed by a [format spec](#number-formatting)
+    /// to control their units and precision.
+    ///
+    /// **Required**
+    #[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+    format: Format,
+
+    /// Whether to use 1000-based (SI) units when auto-scaling values,
+    /// instead of 1024-based (binary) units.
+    ///
+    /// This only affects tokens using the `auto` format spec.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    si_units: bool,
+
+    /// Number of seconds between refresh.
+    ///
+    /// This can be set as a global interval,
+    /// or passed as an object to customize the interval per-system.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "Interval::default")]
+    interval: Interval,
+
+    /// Text to show before the `power_draw` tokens while charging.
+    ///
+    /// When set, the power draw is always positive.
+    /// Otherwise, it is negative while charging.
+    ///
+    /// **Default**: `null`
+    charging_indicator: Option<String>,
+
+    /// The network interfaces to add together
+    /// for the `net_down` and `net_up` tokens without an interface.
+    ///
+    /// **Default**: `null` (all interfaces except `lo`)
+    interfaces: Option<Vec<String>>,
+
+    /// The mount points to add together
+    /// for the disk tokens without a mount point.
+    /// Each filesystem is only counted once,
+    /// even if it is mounted at several of them.
+    ///
+    /// **Default**: `null` (all mounts backed by a device in `/dev`)
+    disks: Option<Vec<String>>,
+
+    /// Names for temperature sensors, mapped to the sensor's label,
+    /// for use in the `temp_c@name` and `temp_f@name` tokens.
+    ///
+    /// Labels are matched ignoring case,
+    /// and treating spaces, dashes and underscores the same.
+    /// If no label matches exactly, the first one containing the name's label is used.
+    /// The available labels are logged at debug level on startup.
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    temperature_sensors: HashMap<String, String>,
+
+    /// The orientation of text for the labels.
+    ///
+    /// **Valid options**: `horizontal`, `vertical, `h`, `v`
+    /// <br>
+    /// **Default** : `horizontal`
+    #[serde(default)]
+    orientation: ModuleOrientation,
+
+    /// The orientation by which the labels are laid out.
+    ///
+    /// **Valid options**: `horizontal`, `vertical, `h`, `v`
+    /// <br>
+    /// **Default** : `horizontal`
+    direction: Option<ModuleOrientation>,
+
+    /// Collects metrics from a remote host over SSH,
+    /// instead of from the local system.
+    /// See [remote hosts](#remote-hosts) for more info.
+    ///
+    /// **Default**: `null`
+    remote: Option<RemoteConfig>,
+
+    /// Shows the processes using the most CPU and memory in a popup,
+    /// opened by clicking the module.
+    /// See [processes](#processes) for more info.
+    ///
+    /// This is not available when using a remote host.
+    ///
+    /// **Default**: `null`
+    processes: Option<ProcessesConfig>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+#[derive(Debug, Deserialize, Copy, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Intervals {
+    /// The number of seconds between refreshing memory data.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    memory: u64,
+
+    /// The number of seconds between refreshing CPU data.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    cpu: u64,
+
+    /// The number of seconds between refreshing temperature data.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    temps: u64,
+
+    /// The number of seconds between refreshing disk data.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    disks: u64,
+
+    /// The number of seconds between refreshing network data.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    networks: u64,
+
+    /// The number of seconds between refreshing system data.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    system: u64,
+
+    /// The number of seconds between refreshing battery data.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    battery: u64,
+}
+
+#[derive(Debug, Deserialize, Copy, Clone)]
+#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Interval {
+    All(u64),
+    Individual(Intervals),
+}
+
+impl Default for Interval {
+    fn default() -> Self {
+        Self::All(default_interval())
+    }
+}
+
+impl Interval {
+    const fn memory(self) -> u64 {
+        match self {
+            Self::All(n) => n,
+            Self::Individual(intervals) => intervals.memory,
+        }
+    }
+
+    const fn cpu(self) -> u64 {
+        match self {
+            Self::All(n) => n,
+            Self::Individual(intervals) => intervals.cpu,
+        }
+    }
+
+    const fn temps(self) -> u64 {
+        match self {
+            Self::All(n) => n,
+            Self::Individual(intervals) => intervals.temps,
+        }
+    }
+
+    const fn disks(self) -> u64 {
+        match self {
+            Self::All(n) => n,
+            Self::Individual(intervals) => intervals.disks,
+        }
+    }
+
+    const fn networks(self) -> u64 {
+        match self {
+            Self::All(n) => n,
+            Self::Individual(intervals) => intervals.networks,
+        }
+    }
+
+    const fn system(self) -> u64 {
+        match self {
+            Self::All(n) => n,
+            Self::Individual(intervals) => intervals.system,
+        }
+    }
+
+    const fn battery(self) -> u64 {
+        match self {
+            Self::All(n) => n,
+            Self::Individual(intervals) => intervals.battery,
+        }
+    }
+}
+
+const fn default_interval() -> u64 {
+    5
+}
+
+/// The opacity of labels showing values from a host
+/// which is no longer reachable.
+const STALE_OPACITY: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    Tokens(HashMap<String, TokenValue>),
+    /// Whether the remote host is reachable.
+    /// This is only sent when using a remote host.
+    Reachable(bool),
+    /// The processes using the most resources.
+    /// This is only sent while the popup is open.
+    Processes(TopProcesses),
+}
+
+#[derive(Debug)]
+pub enum SysInfoCommand {
+    /// Sets whether the processes popup is open,
+    /// and so whether the process table should be refreshed.
+    WatchProcesses(bool),
+    /// Sends `SIGTERM` to the process with the PID.
+    Kill(u32),
+}
+
+#[derive(Debug)]
+enum RefreshType {
+    Memory,
+    Cpu,
+    Temps,
+    Disks,
+    Network,
+    System,
+    Battery,
+    Processes,
+    /// Kills the process with the PID.
+    /// This uses the process table, so is handled alongside refreshes.
+    Kill(u32),
+}
+
+impl Module<gtk::Box> for SysInfoModule {
+    type SendMessage = Update;
+    type ReceiveMessage = SysInfoCommand;
+
+    module_impl!("sysinfo");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        if let Some(remote) = self.remote.clone() {
+            context.spawn(remote::run(
+                remote,
+                self.interfaces.clone(),
+                self.disks.clone(),
+                context.tx.clone(),
+            ));
+            return Ok(());
+        }
+
+        let interval = self.interval;
+        let interfaces = self.interfaces.clone();
+        let disks = self.disks.clone();
+        let temperature_sensors = self.temperature_sensors.clone();
+        let charging_indicator = self.charging_indicator.clone();
+
+        let refresh_kind = RefreshKind::everything()
+            .without_processes()
+            .without_users_list();
+
+        let mut sys = System::new_with_specifics(refresh_kind);
+        sys.refresh_components_list();
+        sys.refresh_disks_list();
+
+        log_temperature_sensors(&sys, &temperature_sensors);
+        sys.refresh_networks_list();
+
+        let (refresh_tx, mut refresh_rx) = mpsc::channel(16);
+
+        macro_rules! spawn_refresh {
+            ($refresh_type:expr, $func:ident) => {{
+                let tx = refresh_tx.clone();
+                let mut ticker = context.ticker(Duration::from_secs(interval.$func()));
+                context.spawn(async move {
+                    loop {
+                        send_async!(tx, $refresh_type);
+                        ticker.tick().await;
+                    }
+                });
+            }};
+        }
+
+        spawn_refresh!(RefreshType::Memory, memory);
+        spawn_refresh!(RefreshType::Cpu, cpu);
+        spawn_refresh!(RefreshType::Temps, temps);
+        spawn_refresh!(RefreshType::Disks, disks);
+        spawn_refresh!(RefreshType::Network, networks);
+        spawn_refresh!(RefreshType::System, system);
+        spawn_refresh!(RefreshType::Battery, battery);
+
+        let process_count = self.processes.as_ref().map_or(0, |config| config.count);
+
+        if let Some(config) = &self.processes {
+            let duration = Duration::from_secs(config.interval.max(1));
+            let tx = refresh_tx.clone();
+
+            context.spawn(async move {
+                let mut interval = interval(duration);
+                let mut watching = false;
+
+                loop {
+                    tokio::select! {
+                        _ = interval.tick(), if watching => send_async!(tx, RefreshType::Processes),
+                        command = rx.recv() => match command {
+                            Some(SysInfoCommand::WatchProcesses(watch)) => {
+                                watching = watch;
+                                interval.reset_immediately();
+                            }
+                            Some(SysInfoCommand::Kill(pid)) => send_async!(tx, RefreshType::Kill(pid)),
+                            None => break,
+                        },
+                    }
+                }
+            });
+        }
+
+        let tx = context.tx.clone();
+        context.spawn(async move {
+            let mut format_info = HashMap::new();
+
+            while let Some(refresh) = refresh_rx.recv().await {
+                match refresh {
+                    RefreshType::Memory => refresh_memory_tokens(&mut format_info, &mut sys),
+                    RefreshType::Cpu => refresh_cpu_tokens(&mut format_info, &mut sys),
+                    RefreshType::Temps => {
+                        refresh_temp_tokens(&mut format_info, &mut sys, &temperature_sensors);
+                    }
+                    RefreshType::Disks => {
+                        refresh_disk_tokens(&mut format_info, &mut sys, disks.as_deref());
+                    }
+                    RefreshType::Network => {
+                        refresh_network_tokens(
+                            &mut format_info,
+                            &mut sys,
+                            interval.networks(),
+                            interfaces.as_deref(),
+                        );
+                    }
+                    RefreshType::System => refresh_system_tokens(&mut format_info, &sys),
+                    RefreshType::Battery => {
+                        refresh_battery_tokens(&mut format_info, charging_indicator.as_deref());
+                    }
+                    RefreshType::Processes => {
+                        let top = processes::refresh(&mut sys, process_count);
+                        send_async!(tx, ModuleUpdateEvent::Update(Update::Processes(top)));
+                        continue;
+                    }
+                    RefreshType::Kill(pid) => {
+                        processes::kill(&sys, pid);
+                        continue;
+                    }
+                };
+
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(Update::Tokens(format_info.clone()))
+                );
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let re = Regex::new(PLACEHOLDER)?;
+
+        let layout = match self.direction {
+            Some(orientation) => orientation,
+            None => self.orientation,
+        };
+
+        let container = gtk::Box::new(layout.into(), 10);
+
+        // the labels are wrapped in a button to open the popup when enabled
+        let button = self.has_popup().then(|| {
+            let button = Button::new();
+            button.add_class("processes-button");
+
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+
+            container.add(&button);
+            button
+        });
+
+        let labels_container = match &button {
+            Some(button) => {
+                let labels_container = gtk::Box::new(layout.into(), 10);
+                button.add(&labels_container);
+                labels_container
+            }
+            None => container.clone(),
+        };
+
+        let mut labels = Vec::new();
+
+        for format in &self.format.strings {
+            let label = Label::builder().label(format).use_markup(true).build();
+
+            label.add_class("item");
+            label.set_angle(self.orientation.to_angle());
+
+            labels_container.add(&label);
+            labels.push(label);
+        }
+
+        {
+            let Format {
+                strings: formats,
+                tokens,
+            } = self.format.clone();
+            let si_units = self.si_units;
+
+            let container = container.clone();
+
+            glib_recv!(context.subscribe(), update => {
+                let info = match update {
+                    Update::Tokens(info) => info,
+                    Update::Reachable(reachable) => {
+                        container.set_class("unreachable", !reachable);
+
+                        let opacity = if reachable { 1.0 } else { STALE_OPACITY };
+                        for label in &labels {
+                            label.set_opacity(opacity);
+                        }
+
+                        return;
+                    }
+                };
+
+                for (format, label) in formats.iter().zip(labels.clone()) {
+                    let format_compiled = re.replace_all(format, |caps: &Captures| {
+                        let Some(token) = tokens.get(&caps[1]) else {
+                            return caps[0].to_string();
+                        };
+
+                        match info.get(&token.key) {
+                            Some(value) => value.format(token.spec.as_ref(), si_units),
+                            // cores are only known to be missing once the cpu is read
+                            None if token.is_per_core() && info.contains_key("cpu_cores") => {
+                                String::from("N/A")
+                            }
+                            // mounts may be removed, such as when unplugging a drive
+                            None if token.is_per_mount() && info.contains_key("disk_total") => {
+                                String::new()
+                            }
+                            None => caps[0].to_string(),
+                        }
+                    });
+
+                    label.set_markup(format_compiled.as_ref());
+                }
+            });
+        }
+
+        let popup = match &button {
+            Some(button) => {
+                let rx = context.subscribe();
+                self.into_popup(context.controller_tx.clone(), rx, context, info)
+                    .into_popup_parts(vec![button])
+            }
+            None => None,
+        };
+
+        Ok(ModuleParts {
+            widget: container,
+            popup,
+        })
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        Some(processes::popup(tx, rx, self.si_units))
+    }
+}
+
+impl SysInfoModule {
+    /// Whether the processes popup is shown.
+    /// Processes are only read from the local system.
+    fn has_popup(&self) -> bool {
+        self.processes.is_some() && self.remote.is_none()
+    }
+}
+
+fn refresh_memory_tokens(format_info: &mut HashMap<String, TokenValue>, sys: &mut System) {
+    sys.refresh_memory();
+
+    let total_memory = sys.total_memory();
+    let available_memory = sys.available_memory();
+
+    let actual_used_memory = total_memory - available_memory;
+    let memory_percent = actual_used_memory as f64 / total_memory as f64 * 100.0;
+
+    format_info.insert(
+        String::from("memory_free"),
+        TokenValue::bytes(available_memory),
+    );
+    format_info.insert(
+        String::from("memory_used"),
+        TokenValue::bytes(actual_used_memory),
+    );
+    format_info.insert(
+        String::from("memory_total"),
+        TokenValue::bytes(total_memory),
+    );
+    format_info.insert(
+        String::from("memory_percent"),
+        TokenValue::number(memory_percent, format!("{memory_percent:0>2.0}")),
+    );
+
+    let used_swap = sys.used_swap();
+    let total_swap = sys.total_swap();
+    let swap_percent = used_swap as f64 / total_swap as f64 * 100.0;
+
+    format_info.insert(
+        String::from("swap_free"),
+        TokenValue::bytes(sys.free_swap()),
+    );
+    format_info.insert(String::from("swap_used"), TokenValue::bytes(used_swap));
+    format_info.insert(String::from("swap_total"), TokenValue::bytes(total_swap));
+    format_info.insert(
+        String::from("swap_percent"),
+        TokenValue::number(swap_percent, format!("{swap_percent:0>2.0}")),
+    );
+}
+
+fn refresh_cpu_tokens(format_info: &mut HashMap<String, TokenValue>, sys: &mut System) {
+    sys.refresh_cpu();
+
+    let cpu_info = sys.global_cpu_info();
+    let cpu_percent = cpu_info.cpu_usage();
+
+    format_info.insert(
+        String::from("cpu_percent"),
+        TokenValue::number(f64::from(cpu_percent), format!("{cpu_percent:0>2.0}")),
+    );
+
+    let cpus = sys.cpus();
+
+    format_info.insert(
+        String::from("cpu_cores"),
+        TokenValue::number(cpus.len() as f64, cpus.len().to_string()),
+    );
+
+    for (index, cpu) in cpus.iter().enumerate() {
+        let cpu_percent = cpu.cpu_usage();
+
+        format_info.insert(
+            format!("cpu_percent{CORE_SUFFIX}{index}"),
+            TokenValue::number(f64::from(cpu_percent), format!("{cpu_percent:0>2.0}")),
+        );
+    }
+}
+
+fn refresh_temp_tokens(
+    format_info: &mut HashMap<String, TokenValue>,
+    sys: &mut System,
+    sensors: &HashMap<String, String>,
+) {
+    sys.refresh_components();
+
+    let components = sys.components();
+    for component in components {
+        let key = component.label().replace(' ', "-");
+        insert_temp(format_info, &format!(":{key}"), component.temperature());
+    }
+
+    for (name, label) in sensors {
+        let component = find_sensor(components.iter().map(ComponentExt::label), label)
+            .and_then(|label| components.iter().find(|c| c.label() == label));
+
+        if let Some(component) = component {
+            let suffix = format!("{SENSOR_SEPARATOR}{name}");
+            insert_temp(format_info, &suffix, component.temperature());
+        }
+    }
+}
+
+fn insert_temp(format_info: &mut HashMap<String, TokenValue>, suffix: &str, temp: f32) {
+    let temp_f = c_to_f(temp);
+
+    format_info.insert(
+        format!("temp_c{suffix}"),
+        TokenValue::number(f64::from(temp), format!("{temp:.0}")),
+    );
+    format_info.insert(
+        format!("temp_f{suffix}"),
+        TokenValue::number(f64::from(temp_f), format!("{temp_f:.0}")),
+    );
+}
+
+/// Logs the available temperature sensor labels,
+/// and warns about any named sensors which do not match one.
+fn log_temperature_sensors(sys: &System, sensors: &HashMap<String, String>) {
+    let labels = sys
+        .components()
+        .iter()
+        .map(ComponentExt::label)
+        .collect::<Vec<_>>();
+
+    debug!("Available temperature sensors: {labels:?}");
+
+    for (name, label) in sensors {
+        match find_sensor(labels.iter().copied(), label) {
+            Some(found) => debug!("Using temperature sensor '{found}' for '{name}'"),
+            None => warn!("No temperature sensor matches '{label}' for '{name}'"),
+        }
+    }
+}
+
+/// Normalizes a sensor label for matching,
+/// ignoring case and treating spaces, dashes and underscores the same.
+fn normalize_sensor_label(label: &str) -> String {
+    label
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Finds the sensor label which best matches the configured label.
+/// An exact match is preferred, followed by the first label containing it.
+fn find_sensor<'a>(labels: impl IntoIterator<Item = &'a str>, label: &str) -> Option<&'a str> {
+    let label = normalize_sensor_label(label);
+    if label.is_empty() {
+        return None;
+    }
+
+    let mut contains = None;
+
+    for candidate in labels {
+        let normalized = normalize_sensor_label(candidate);
+
+        if normalized == label {
+            return Some(candidate);
+        }
+
+        if contains.is_none() && normalized.contains(&label) {
+            contains = Some(candidate);
+        }
+    }
+
+    contains
+}
+
+fn refresh_disk_tokens(
+    format_info: &mut HashMap<String, TokenValue>,
+    sys: &mut System,
+    mounts: Option<&[String]>,
+) {
+    // the list is re-read to pick up added and removed mounts
+    sys.refresh_disks_list();
+
+    let disks = sys
+        .disks()
+        .iter()
+        .filter_map(|disk| {
+            Some(DiskUsage {
+                device: disk.name().to_string_lossy().into_owned(),
+                mount: disk.mount_point().to_str()?.to_string(),
+                total: disk.total_space(),
+                available: disk.available_space(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    insert_disk_tokens(format_info, &disks, mounts);
+}
+
+/// The space on a mounted filesystem.
+#[derive(Debug)]
+struct DiskUsage {
+    /// The device or source of the filesystem, such as `/dev/sda1`.
+    device: String,
+    mount: String,
+    total: u64,
+    available: u64,
+}
+
+/// Replaces the disk tokens with those for the given mounts,
+/// and adds the totals across the summed mounts.
+fn insert_disk_tokens(
+    format_info: &mut HashMap<String, TokenValue>,
+    disks: &[DiskUsage],
+    mounts: Option<&[String]>,
+) {
+    // mounts missing from this reading are forgotten,
+    // rather than showing their last value
+    format_info.retain(|key, _| !key.starts_with("disk_"));
+
+    let mut devices = HashSet::new();
+    let mut totals = (0, 0);
+
+    for disk in disks {
+        // replace braces to avoid conflict with regex
+        let key = disk.mount.replace(['{', '}'], "");
+        insert_disk_usage(format_info, &format!(":{key}"), disk.total, disk.available);
+
+        // bind mounts and subvolumes share the space of a single device
+        if is_summed_disk(disk, mounts) && devices.insert(disk.device.as_str()) {
+            totals.0 += disk.total;
+            totals.1 += disk.available;
+        }
+    }
+
+    insert_disk_usage(format_info, "", totals.0, totals.1);
+}
+
+fn insert_disk_usage(
+    format_info: &mut HashMap<String, TokenValue>,
+    suffix: &str,
+    total: u64,
+    available: u64,
+) {
+    let used = total.saturating_sub(available);
+    let percent = if total > 0 {
+        used as f64 / total as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    format_info.insert(format!("disk_free{suffix}"), TokenValue::bytes(available));
+    format_info.insert(format!("disk_used{suffix}"), TokenValue::bytes(used));
+    format_info.insert(format!("disk_total{suffix}"), TokenValue::bytes(total));
+    format_info.insert(
+        format!("disk_percent{suffix}"),
+        TokenValue::number(percent, format!("{percent:0>2.0}")),
+    );
+}
+
+/// Whether the mount is included in the disk tokens without a mount point.
+fn is_summed_disk(disk: &DiskUsage, mounts: Option<&[String]>) -> bool {
+    mounts.map_or(disk.device.starts_with("/dev/"), |mounts| {
+        mounts.iter().any(|mount| *mount == disk.mount)
+    })
+}
+
+fn refresh_network_tokens(
+    format_info: &mut HashMap<String, TokenValue>,
+    sys: &mut System,
+    interval: u64,
+    interfaces: Option<&[String]>,
+) {
+    sys.refresh_networks();
+
+    let mut totals = (0, 0);
+
+    for (iface, network) in sys.networks() {
+        // these are clamped at zero if the counters reset
+        let received = network.received();
+        let transmitted = network.transmitted();
+
+        if is_summed_interface(iface, interfaces) {
+            totals.0 += received;
+            totals.1 += transmitted;
+        }
+
+        format_info.insert(
+            format!("net_down:{iface}"),
+            TokenValue::bit_rate(
+                bytes_to_bits_per_second(received, interval),
+                format!("{:0>2.0}", bytes_to_megabits(received) / interval),
+            ),
+        );
+
+        format_info.insert(
+            format!("net_up:{iface}"),
+            TokenValue::bit_rate(
+                bytes_to_bits_per_second(transmitted, interval),
+                format!("{:0>2.0}", bytes_to_megabits(transmitted) / interval),
+            ),
+        );
+    }
+
+    insert_network_totals(format_info, totals, interval);
+}
+
+/// Whether the interface is included in the `net_down` and `net_up` totals.
+fn is_summed_interface(iface: &str, interfaces: Option<&[String]>) -> bool {
+    interfaces.map_or(iface != "lo", |interfaces| {
+        interfaces.iter().any(|name| name == iface)
+    })
+}
+
+/// Adds the tokens for the total bytes transferred
+/// across the summed interfaces over `interval` seconds.
+fn insert_network_totals(
+    format_info: &mut HashMap<String, TokenValue>,
+    (received, transmitted): (u64, u64),
+    interval: u64,
+) {
+    for (name, bytes) in [("net_down", received), ("net_up", transmitted)] {
+        format_info.insert(
+            name.to_string(),
+            TokenValue::byte_rate(bytes as f64 / interval as f64),
+        );
+
+        format_info.insert(
+            format!("{name}_bits"),
+            TokenValue::bit_rate(
+                bytes_to_bits_per_second(bytes, interval),
+                format!("{:0>2.0}", bytes_to_megabits(bytes) / interval),
+            ),
+        );
+    }
+}
+
+fn refresh_system_tokens(format_info: &mut HashMap<String, TokenValue>, sys: &System) {
+    // no refresh required for these tokens
+
+    let load_average = sys.load_average();
+    format_info.insert(
+        String::from("load_average:1"),
+        TokenValue::number(load_average.one, format!("{:.2}", load_average.one)),
+    );
+
+    format_info.insert(
+        String::from("load_average:5"),
+        TokenValue::number(load_average.five, format!("{:.2}", load_average.five)),
+    );
+
+    format_info.insert(
+        String::from("load_average:15"),
+        TokenValue::number(load_average.fifteen, format!("{:.2}", load_average.fifteen)),
+    );
+
+    format_info.insert(
+        String::from("uptime"),
+        TokenValue::text(format_hours_minutes(sys.uptime())),
+    );
+}
+
+fn refresh_battery_tokens(
+    format_info: &mut HashMap<String, TokenValue>,
+    charging_indicator: Option<&str>,
+) {
+    // batteries may stop reporting a value, or be removed
+    format_info.retain(|key, _| {
+        !key.starts_with("power_draw") && !key.starts_with("energy_full_design_pct")
+    });
+
+    let batteries = battery::read_all();
+
+    let power_value = |watts: f64| match charging_indicator {
+        Some(indicator) if watts < 0.0 => {
+            TokenValue::number(-watts, format!("{:.1}", -watts)).with_prefix(indicator.to_string())
+        }
+        _ => TokenValue::number(watts, format!("{watts:.1}")),
+    };
+
+    let health_value = |health: f64| TokenValue::number(health, format!("{health:0>2.0}"));
+
+    for (name, battery) in &batteries {
+        if let Some(power_draw) = battery.power_draw {
+            format_info.insert(format!("power_draw:{name}"), power_value(power_draw));
+        }
+
+        if let Some(health) = battery.health {
+            format_info.insert(
+                format!("energy_full_design_pct:{name}"),
+                health_value(health),
+            );
+        }
+    }
+
+    let draws = batteries
+        .iter()
+        .filter_map(|(_, battery)| battery.power_draw)
+        .collect::<Vec<_>>();
+
+    if !draws.is_empty() {
+        format_info.insert(String::from("power_draw"), power_value(draws.iter().sum()));
+    }
+
+    let healths = batteries
+        .iter()
+        .filter_map(|(_, battery)| battery.health)
+        .collect::<Vec<_>>();
+
+    if !healths.is_empty() {
+        let health = healths.iter().sum::<f64>() / healths.len() as f64;
+        format_info.insert(String::from("energy_full_design_pct"), health_value(health));
+    }
+}
+
+/// Converts celsius to fahrenheit.
+fn c_to_f(c: f32) -> f32 {
+    c * 9.0 / 5.0 + 32.0
+}
+
+const fn bytes_to_megabits(b: u64) -> u64 {
+    const BYTES_IN_MEGABIT: u64 = 125_000;
+    b / BYTES_IN_MEGABIT
+}
+
+/// Converts a number of bytes transferred over `interval` seconds
+/// into an average rate in bits per second.
+fn bytes_to_bits_per_second(b: u64, interval: u64) -> f64 {
+    (b * 8) as f64 / interval as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::testing::{config, ControllerHarness};
+
+    fn disk(device: &str, mount: &str, total: u64, available: u64) -> DiskUsage {
+        DiskUsage {
+            device: device.to_string(),
+            mount: mount.to_string(),
+            total,
+            available,
+        }
+    }
+
+    #[test]
+    fn finds_temperature_sensors() {
+        let labels = [
+            "nvme Composite",
+            "k10temp Tctl",
+            "k10temp Tccd1",
+            "amdgpu edge",
+        ];
+
+        assert_eq!(find_sensor(labels, "k10temp-Tctl"), Some("k10temp Tctl"));
+        assert_eq!(find_sensor(labels, "K10TEMP_TCCD1"), Some("k10temp Tccd1"));
+        assert_eq!(find_sensor(labels, "k10temp"), Some("k10temp Tctl"));
+        assert_eq!(find_sensor(labels, "edge"), Some("amdgpu edge"));
+        assert_eq!(find_sensor(labels, "coretemp"), None);
+        assert_eq!(find_sensor(labels, " - "), None);
+    }
+
+    #[test]
+    fn inserts_disk_tokens() {
+        let mut info = HashMap::new();
+
+        let disks = [
+            disk("/dev/sda1", "/", 100, 75),
+            disk("/dev/sda1", "/srv", 100, 75),
+            disk("/dev/sdb1", "/mnt/{usb}", 100, 25),
+            disk("server:/export", "/mnt/nfs", 1000, 0),
+        ];
+        insert_disk_tokens(&mut info, &disks, None);
+
+        let percent = |info: &HashMap<String, TokenValue>, key: &str| {
+            info.get(key).map(|value| value.format(None, false))
+        };
+
+        assert_eq!(percent(&info, "disk_percent").as_deref(), Some("50"));
+        assert_eq!(percent(&info, "disk_percent:/srv").as_deref(), Some("25"));
+        assert_eq!(
+            percent(&info, "disk_percent:/mnt/usb").as_deref(),
+            Some("75")
+        );
+
+        // the drive is removed
+        insert_disk_tokens(&mut info, &disks[..2], None);
+        assert_eq!(percent(&info, "disk_percent").as_deref(), Some("25"));
+        assert!(!info.contains_key("disk_percent:/mnt/usb"));
+
+        let mounts = [String::from("/mnt/nfs")];
+        insert_disk_tokens(&mut info, &disks, Some(&mounts));
+        assert_eq!(percent(&info, "disk_percent").as_deref(), Some("100"));
+    }
+
+    #[test]
+    fn sends_local_tokens() {
+        let module: SysInfoModule = config(r#"{ "format": ["{memory_total}"] }"#);
+
+        let mut harness = ControllerHarness::new();
+        harness.start(&module).expect("controller to start");
+
+        // each refresh sends all tokens collected so far,
+        // and the refreshes may arrive in any order.
+        let has_memory = (0..6).any(|_| {
+            matches!(
+                harness.next_update(),
+                Update::Tokens(tokens) if tokens.contains_key("memory_total")
+            )
+        });
+        assert!(has_memory);
+    }
+
+    #[test]
+    fn sends_processes_while_watched() {
+        let module: SysInfoModule = config(r#"{ "format": [], "processes": { "count": 3 } }"#);
+
+        let mut harness = ControllerHarness::new();
+        harness.start(&module).expect("controller to start");
+        harness.send(SysInfoCommand::WatchProcesses(true));
+
+        let top = (0..20)
+            .find_map(|_| match harness.next_update() {
+                Update::Processes(top) => Some(top),
+                _ => None,
+            })
+            .expect("processes to be sent");
+
+        assert!(!top.memory.is_empty());
+        assert!(top.memory.len() <= 3);
+    }
+}
@@ -0,0 +1,347 @@
+use super::token::{FormatSpec, TokenValue};
+use super::{SysInfoCommand, Update};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::i18n::tr;
+use crate::{glib_recv, try_send};
+use glib::Propagation;
+use gtk::pango::EllipsizeMode;
+use gtk::prelude::*;
+use gtk::{Button, EventBox, Label, Orientation};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::rc::Rc;
+use sysinfo::{Pid, PidExt, ProcessExt, ProcessRefreshKind, Signal, System, SystemExt};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProcessesConfig {
+    /// The number of processes to show in each list.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_count")]
+    pub count: usize,
+
+    /// The number of seconds between refreshing the process lists
+    /// while the popup is open.
+    ///
+    /// **Default**: `1`
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+const fn default_count() -> usize {
+    5
+}
+
+const fn default_interval() -> u64 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pid: u32,
+    name: String,
+    /// Usage as a percentage of a single core,
+    /// so this can exceed 100 for multithreaded processes.
+    cpu: f32,
+    /// Resident memory, in bytes.
+    memory: u64,
+}
+
+/// The processes using the most resources.
+#[derive(Debug, Clone)]
+pub struct TopProcesses {
+    pub cpu: Vec<ProcessInfo>,
+    pub memory: Vec<ProcessInfo>,
+}
+
+/// Refreshes the process table and gets the top processes.
+///
+/// The table is only refreshed when this is called,
+/// so that processes are never read while the popup is closed.
+pub fn refresh(sys: &mut System, count: usize) -> TopProcesses {
+    sys.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu());
+
+    let processes = sys
+        .processes()
+        .values()
+        .map(|process| ProcessInfo {
+            pid: process.pid().as_u32(),
+            name: process.name().to_string(),
+            cpu: process.cpu_usage(),
+            memory: process.memory(),
+        })
+        .collect();
+
+    top_processes(processes, count)
+}
+
+/// Sends `SIGTERM` to the process.
+pub fn kill(sys: &System, pid: u32) {
+    let Some(process) = sys.process(Pid::from_u32(pid)) else {
+        warn!("Process {pid} no longer exists");
+        return;
+    };
+
+    debug!("Killing process {pid} ({})", process.name());
+
+    if process.kill_with(Signal::Term) != Some(true) {
+        warn!("Failed to kill process {pid}");
+    }
+}
+
+fn top_processes(processes: Vec<ProcessInfo>, count: usize) -> TopProcesses {
+    let mut cpu = processes.clone();
+    cpu.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+    cpu.truncate(count);
+
+    let mut memory = processes;
+    memory.sort_by_key(|process| Reverse(process.memory));
+    memory.truncate(count);
+
+    TopProcesses { cpu, memory }
+}
+
+/// Creates the popup showing the top processes,
+/// with a confirmation to kill a process when it is right-clicked.
+///
+/// The controller is told to refresh the processes only while this is shown.
+pub fn popup(
+    tx: mpsc::Sender<SysInfoCommand>,
+    rx: broadcast::Receiver<Update>,
+    si_units: bool,
+) -> gtk::Box {
+    let container = gtk::Box::new(Orientation::Vertical, 10);
+
+    let columns = gtk::Box::new(Orientation::Horizontal, 20);
+    container.add(&columns);
+
+    let confirm = KillConfirm::new(&tx);
+
+    let cpu = ProcessColumn::new("CPU", "cpu", &confirm);
+    let memory = ProcessColumn::new("Memory", "memory", &confirm);
+
+    columns.add(&cpu.container);
+    columns.add(&memory.container);
+    container.add(&confirm.container);
+
+    {
+        let tx = tx.clone();
+        container.connect_map(move |_| {
+            try_send!(tx, SysInfoCommand::WatchProcesses(true));
+        });
+    }
+
+    {
+        let confirm = confirm.clone();
+        container.connect_unmap(move |_| {
+            confirm.hide();
+            try_send!(tx, SysInfoCommand::WatchProcesses(false));
+        });
+    }
+
+    glib_recv!(rx, update => {
+        if let Update::Processes(top) = update {
+            cpu.update(&top.cpu, |process| format!("{:.1}%", process.cpu));
+
+            memory.update(&top.memory, |process| {
+                TokenValue::bytes(process.memory).format(Some(&FormatSpec::auto(1)), si_units)
+            });
+        }
+    });
+
+    container
+}
+
+/// A list of processes, sorted by one of their values.
+struct ProcessColumn {
+    container: gtk::Box,
+    rows: gtk::Box,
+    confirm: KillConfirm,
+}
+
+impl ProcessColumn {
+    fn new(title: &str, class: &str, confirm: &KillConfirm) -> Self {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.add_class("processes");
+        container.add_class(class);
+
+        let title = Label::new(Some(title));
+        title.add_class("title");
+        container.add(&title);
+
+        let rows = gtk::Box::new(Orientation::Vertical, 0);
+        container.add(&rows);
+
+        Self {
+            container,
+            rows,
+            confirm: confirm.clone(),
+        }
+    }
+
+    fn update(&self, processes: &[ProcessInfo], usage: impl Fn(&ProcessInfo) -> String) {
+        for row in self.rows.children() {
+            self.rows.remove(&row);
+        }
+
+        for process in processes {
+            let row = EventBox::new();
+            row.add_class("process");
+
+            let container = gtk::Box::new(Orientation::Horizontal, 10);
+
+            let name = Label::builder()
+                .label(&process.name)
+                .width_chars(16)
+                .max_width_chars(16)
+                .ellipsize(EllipsizeMode::End)
+                .xalign(0.0)
+                .build();
+            name.add_class("name");
+
+            let pid = Label::builder()
+                .label(process.pid.to_string())
+                .width_chars(7)
+                .xalign(1.0)
+                .build();
+            pid.add_class("pid");
+
+            let value = Label::builder()
+                .label(usage(process))
+                .width_chars(9)
+                .xalign(1.0)
+                .build();
+            value.add_class("usage");
+
+            container.add(&name);
+            container.add(&pid);
+            container.add(&value);
+            row.add(&container);
+
+            {
+                let confirm = self.confirm.clone();
+                let process = process.clone();
+
+                row.connect_button_press_event(move |_, ev| {
+                    const BUTTON_RIGHT: u32 = 3;
+
+                    if ev.button() == BUTTON_RIGHT {
+                        confirm.show(&process);
+                        Propagation::Stop
+                    } else {
+                        Propagation::Proceed
+                    }
+                });
+            }
+
+            self.rows.add(&row);
+        }
+
+        self.rows.show_all();
+    }
+}
+
+/// Asks whether to kill the right-clicked process.
+#[derive(Debug, Clone)]
+struct KillConfirm {
+    container: gtk::Box,
+    label: Label,
+    pid: Rc<Cell<Option<u32>>>,
+}
+
+impl KillConfirm {
+    fn new(tx: &mpsc::Sender<SysInfoCommand>) -> Self {
+        let container = gtk::Box::new(Orientation::Horizontal, 10);
+        container.add_class("kill-confirm");
+
+        let label = Label::new(None);
+        label.add_class("message");
+        container.add(&label);
+
+        let kill = Button::with_label(tr("sysinfo.kill"));
+        kill.add_class("btn-kill");
+        container.pack_end(&kill, false, false, 0);
+
+        let cancel = Button::with_label(tr("sysinfo.cancel"));
+        cancel.add_class("btn-cancel");
+        container.pack_end(&cancel, false, false, 0);
+
+        // only shown once a process is right-clicked
+        container.show_all();
+        container.set_no_show_all(true);
+        container.hide();
+
+        let confirm = Self {
+            container,
+            label,
+            pid: Rc::new(Cell::new(None)),
+        };
+
+        {
+            let confirm = confirm.clone();
+            let tx = tx.clone();
+            kill.connect_clicked(move |_| {
+                if let Some(pid) = confirm.pid.get() {
+                    try_send!(tx, SysInfoCommand::Kill(pid));
+                }
+
+                confirm.hide();
+            });
+        }
+
+        {
+            let confirm = confirm.clone();
+            cancel.connect_clicked(move |_| confirm.hide());
+        }
+
+        confirm
+    }
+
+    fn show(&self, process: &ProcessInfo) {
+        self.pid.set(Some(process.pid));
+        self.label.set_label(
+            &tr("sysinfo.kill_confirm")
+                .replace("{name}", &process.name)
+                .replace("{pid}", &process.pid.to_string()),
+        );
+        self.container.show();
+    }
+
+    fn hide(&self) {
+        self.pid.set(None);
+        self.container.hide();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, cpu: f32, memory: u64) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: format!("process-{pid}"),
+            cpu,
+            memory,
+        }
+    }
+
+    #[test]
+    fn sorts_top_processes() {
+        let processes = vec![
+            process(1, 0.5, 4000),
+            process(2, 150.0, 1000),
+            process(3, 20.0, 9000),
+        ];
+
+        let top = top_processes(processes, 2);
+
+        let pids = |processes: &[ProcessInfo]| processes.iter().map(|p| p.pid).collect::<Vec<_>>();
+        assert_eq!(pids(&top.cpu), [2, 3]);
+        assert_eq!(pids(&top.memory), [3, 1]);
+    }
+}
@@ -0,0 +1,639 @@
+use super::token::{TokenValue, CORE_SUFFIX};
+use super::{
+    bytes_to_bits_per_second, bytes_to_megabits, insert_disk_tokens, insert_network_totals,
+    is_summed_interface, DiskUsage, Update,
+};
+use crate::clients::resume;
+use crate::duration::format_hours_minutes;
+use crate::modules::ModuleUpdateEvent;
+use crate::send_async;
+use color_eyre::{Report, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::{debug, warn};
+
+/// The longest time to wait between attempts to reach an unresponsive host.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// The shell script run on the remote host.
+/// Each file is preceded by a `@@ <name>` marker line.
+const PROBE: &str = "for f in loadavg meminfo uptime stat net/dev; do \
+    echo \"@@ $f\"; cat /proc/$f; \
+    done; \
+    echo '@@ df'; df -kP";
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RemoteConfig {
+    /// The SSH destination to collect metrics from, such as `user@server`.
+    /// Any options set in your SSH config for the host are used.
+    ///
+    /// Key-based authentication is required,
+    /// as password prompts are disabled.
+    ///
+    /// **Required**
+    host: String,
+
+    /// Number of seconds between refreshes.
+    /// This is independent of the local `interval` setting.
+    ///
+    /// **Default**: `10`
+    #[serde(default = "default_interval")]
+    interval: u64,
+
+    /// Number of seconds to wait for the host to respond
+    /// before marking it as unreachable.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_timeout")]
+    timeout: u64,
+}
+
+const fn default_interval() -> u64 {
+    10
+}
+
+const fn default_timeout() -> u64 {
+    5
+}
+
+/// Previous readings from the host,
+/// used to calculate rates between refreshes.
+#[derive(Debug, Default)]
+struct Previous {
+    time: Option<Instant>,
+    /// The total and idle CPU time,
+    /// for the aggregate line and each core.
+    cpu: HashMap<String, (u64, u64)>,
+    /// The received and transmitted bytes per interface.
+    networks: HashMap<String, (u64, u64)>,
+}
+
+/// Polls the remote host,
+/// sending token updates and reachability changes to the widget.
+pub async fn run(
+    config: RemoteConfig,
+    interfaces: Option<Vec<String>>,
+    disks: Option<Vec<String>>,
+    tx: mpsc::Sender<ModuleUpdateEvent<Update>>,
+) {
+    poll(config, interfaces, disks, tx, probe).await;
+}
+
+/// Polls the remote host using `probe` to read its metrics.
+async fn poll<F, Fut>(
+    config: RemoteConfig,
+    interfaces: Option<Vec<String>>,
+    disks: Option<Vec<String>>,
+    tx: mpsc::Sender<ModuleUpdateEvent<Update>>,
+    probe: F,
+) where
+    F: Fn(String, u64) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let interval = Duration::from_secs(config.interval.max(1));
+    let probe_timeout = Duration::from_secs(config.timeout.max(1));
+
+    let mut previous = Previous::default();
+    let mut format_info = HashMap::new();
+    let mut failures = 0;
+
+    let mut resume_rx = resume::client().subscribe();
+
+    loop {
+        match timeout(probe_timeout, probe(config.host.clone(), config.timeout)).await {
+            Ok(Ok(output)) => {
+                if failures > 0 {
+                    debug!("Remote host '{}' reachable again", config.host);
+                    send_async!(tx, ModuleUpdateEvent::Update(Update::Reachable(true)));
+                }
+
+                failures = 0;
+
+                parse_output(
+                    &output,
+                    &mut format_info,
+                    &mut previous,
+                    interfaces.as_deref(),
+                    disks.as_deref(),
+                );
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(Update::Tokens(format_info.clone()))
+                );
+            }
+            res => {
+                match res {
+                    Ok(Err(err)) => warn!("Failed to reach '{}': {err:?}", config.host),
+                    _ => warn!("Timed out waiting for '{}'", config.host),
+                }
+
+                if failures == 0 {
+                    send_async!(tx, ModuleUpdateEvent::Update(Update::Reachable(false)));
+                }
+
+                failures += 1;
+
+                // rates cannot be calculated across the gap
+                previous = Previous::default();
+            }
+        }
+
+        if resume::wait(backoff(interval, failures), &mut resume_rx).await {
+            // the local clock did not advance while suspended
+            previous = Previous::default();
+        }
+    }
+}
+
+/// Gets the delay before the next refresh,
+/// doubling the interval for each consecutive failure up to `MAX_BACKOFF`.
+fn backoff(interval: Duration, failures: u32) -> Duration {
+    if failures == 0 {
+        return interval;
+    }
+
+    interval
+        .saturating_mul(2u32.saturating_pow(failures.min(16)))
+        .min(MAX_BACKOFF.max(interval))
+}
+
+/// Runs the probe script over SSH, returning its output.
+async fn probe(host: String, connect_timeout: u64) -> Result<String> {
+    let output = Command::new("ssh")
+        .args([
+            "-T",
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            &format!("ConnectTimeout={connect_timeout}"),
+            &host,
+            PROBE,
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(Report::msg(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// Splits the probe output into its sections, by name.
+fn sections(output: &str) -> HashMap<&str, Vec<&str>> {
+    let mut sections = HashMap::new();
+    let mut current = None;
+
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("@@ ") {
+            current = Some(name);
+            sections.insert(name, vec![]);
+        } else if let Some(lines) = current.and_then(|name| sections.get_mut(name)) {
+            lines.push(line);
+        }
+    }
+
+    sections
+}
+
+fn parse_output(
+    output: &str,
+    format_info: &mut HashMap<String, TokenValue>,
+    previous: &mut Previous,
+    interfaces: Option<&[String]>,
+    disks: Option<&[String]>,
+) {
+    let sections = sections(output);
+
+    let now = Instant::now();
+    let elapsed = previous
+        .time
+        .replace(now)
+        .map(|time| now.duration_since(time).as_secs().max(1));
+
+    if let Some(lines) = sections.get("loadavg") {
+        parse_loadavg(lines, format_info);
+    }
+
+    if let Some(lines) = sections.get("meminfo") {
+        parse_meminfo(lines, format_info);
+    }
+
+    if let Some(lines) = sections.get("uptime") {
+        parse_uptime(lines, format_info);
+    }
+
+    if let Some(lines) = sections.get("stat") {
+        parse_stat(lines, format_info, &mut previous.cpu);
+    }
+
+    if let Some(lines) = sections.get("net/dev") {
+        parse_net_dev(
+            lines,
+            format_info,
+            &mut previous.networks,
+            elapsed,
+            interfaces,
+        );
+    }
+
+    if let Some(lines) = sections.get("df") {
+        parse_df(lines, format_info, disks);
+    }
+}
+
+fn parse_loadavg(lines: &[&str], format_info: &mut HashMap<String, TokenValue>) {
+    let Some(line) = lines.first() else {
+        return;
+    };
+
+    for (value, period) in line.split_whitespace().zip(["1", "5", "15"]) {
+        if let Ok(value) = value.parse::<f64>() {
+            format_info.insert(
+                format!("load_average:{period}"),
+                TokenValue::number(value, format!("{value:.2}")),
+            );
+        }
+    }
+}
+
+fn parse_meminfo(lines: &[&str], format_info: &mut HashMap<String, TokenValue>) {
+    // values are given in kibibytes
+    let values = lines
+        .iter()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let value = value.split_whitespace().next()?.parse::<u64>().ok()?;
+            Some((key, value * 1024))
+        })
+        .collect::<HashMap<_, _>>();
+
+    if let (Some(&total), Some(&available)) = (values.get("MemTotal"), values.get("MemAvailable")) {
+        let used = total.saturating_sub(available);
+        let percent = used as f64 / total as f64 * 100.0;
+
+        format_info.insert(String::from("memory_free"), TokenValue::bytes(available));
+        format_info.insert(String::from("memory_used"), TokenValue::bytes(used));
+        format_info.insert(String::from("memory_total"), TokenValue::bytes(total));
+        format_info.insert(
+            String::from("memory_percent"),
+            TokenValue::number(percent, format!("{percent:0>2.0}")),
+        );
+    }
+
+    if let (Some(&total), Some(&free)) = (values.get("SwapTotal"), values.get("SwapFree")) {
+        let used = total.saturating_sub(free);
+        let percent = used as f64 / total as f64 * 100.0;
+
+        format_info.insert(String::from("swap_free"), TokenValue::bytes(free));
+        format_info.insert(String::from("swap_used"), TokenValue::bytes(used));
+        format_info.insert(String::from("swap_total"), TokenValue::bytes(total));
+        format_info.insert(
+            String::from("swap_percent"),
+            TokenValue::number(percent, format!("{percent:0>2.0}")),
+        );
+    }
+}
+
+fn parse_uptime(lines: &[&str], format_info: &mut HashMap<String, TokenValue>) {
+    let uptime = lines
+        .first()
+        .and_then(|line| line.split_whitespace().next())
+        .and_then(|uptime| uptime.parse::<f64>().ok());
+
+    if let Some(uptime) = uptime {
+        format_info.insert(
+            String::from("uptime"),
+            TokenValue::text(format_hours_minutes(uptime as u64)),
+        );
+    }
+}
+
+/// Reads the aggregate and per-core CPU lines,
+/// calculating usage since the previous reading.
+fn parse_stat(
+    lines: &[&str],
+    format_info: &mut HashMap<String, TokenValue>,
+    previous: &mut HashMap<String, (u64, u64)>,
+) {
+    let mut cores = 0;
+
+    for line in lines {
+        let mut fields = line.split_whitespace();
+
+        let (key, is_core) = match fields.next() {
+            Some("cpu") => (String::from("cpu_percent"), false),
+            Some(name) => match name.strip_prefix("cpu") {
+                Some(index) if index.parse::<usize>().is_ok() => {
+                    (format!("cpu_percent{CORE_SUFFIX}{index}"), true)
+                }
+                _ => continue,
+            },
+            None => continue,
+        };
+
+        let times = fields
+            .filter_map(|time| time.parse::<u64>().ok())
+            .collect::<Vec<_>>();
+
+        // idle + iowait
+        let idle =
+            times.get(3).copied().unwrap_or_default() + times.get(4).copied().unwrap_or_default();
+        let total = times.iter().sum::<u64>();
+
+        if let Some((prev_total, prev_idle)) = previous.insert(key.clone(), (total, idle)) {
+            if is_core {
+                cores += 1;
+            }
+
+            let total = total.saturating_sub(prev_total);
+            let idle = idle.saturating_sub(prev_idle);
+
+            if total > 0 {
+                let percent = (total - idle.min(total)) as f64 / total as f64 * 100.0;
+
+                format_info.insert(key, TokenValue::number(percent, format!("{percent:0>2.0}")));
+            }
+        }
+    }
+
+    // like the usage, cores are only counted from the second reading,
+    // so that their tokens are not shown as missing in the meantime
+    if cores > 0 {
+        format_info.insert(
+            String::from("cpu_cores"),
+            TokenValue::number(f64::from(cores), cores.to_string()),
+        );
+    }
+}
+
+/// Reads the bytes transferred by each interface,
+/// calculating rates since the previous reading.
+fn parse_net_dev(
+    lines: &[&str],
+    format_info: &mut HashMap<String, TokenValue>,
+    previous: &mut HashMap<String, (u64, u64)>,
+    elapsed: Option<u64>,
+    interfaces: Option<&[String]>,
+) {
+    let mut totals = (0, 0);
+
+    // interfaces missing from this reading are forgotten,
+    // so a delta is never taken across several intervals
+    let mut previous_readings = std::mem::take(previous);
+
+    for line in lines {
+        let Some((iface, values)) = line.split_once(':') else {
+            continue;
+        };
+
+        let values = values
+            .split_whitespace()
+            .filter_map(|value| value.parse::<u64>().ok())
+            .collect::<Vec<_>>();
+
+        // received bytes is the first column, transmitted bytes the ninth
+        let (Some(&received), Some(&transmitted)) = (values.first(), values.get(8)) else {
+            continue;
+        };
+
+        let iface = iface.trim().to_string();
+        let prev = previous_readings.remove(&iface);
+        previous.insert(iface.clone(), (received, transmitted));
+
+        let (Some((prev_received, prev_transmitted)), Some(elapsed)) = (prev, elapsed) else {
+            continue;
+        };
+
+        // counters reset if the interface goes down and comes back
+        let received = received.saturating_sub(prev_received);
+        let transmitted = transmitted.saturating_sub(prev_transmitted);
+
+        if is_summed_interface(&iface, interfaces) {
+            totals.0 += received;
+            totals.1 += transmitted;
+        }
+
+        format_info.insert(
+            format!("net_down:{iface}"),
+            TokenValue::bit_rate(
+                bytes_to_bits_per_second(received, elapsed),
+                format!("{:0>2.0}", bytes_to_megabits(received) / elapsed),
+            ),
+        );
+
+        format_info.insert(
+            format!("net_up:{iface}"),
+            TokenValue::bit_rate(
+                bytes_to_bits_per_second(transmitted, elapsed),
+                format!("{:0>2.0}", bytes_to_megabits(transmitted) / elapsed),
+            ),
+        );
+    }
+
+    if let Some(elapsed) = elapsed {
+        insert_network_totals(format_info, totals, elapsed);
+    }
+}
+
+fn parse_df(
+    lines: &[&str],
+    format_info: &mut HashMap<String, TokenValue>,
+    mounts: Option<&[String]>,
+) {
+    // skip header
+    let disks = lines
+        .iter()
+        .skip(1)
+        .filter_map(|line| {
+            let columns = line.split_whitespace().collect::<Vec<_>>();
+
+            // the mount point is last, and may contain spaces
+            if columns.len() < 6 {
+                return None;
+            }
+
+            let total = columns[1].parse::<u64>().ok()?;
+            let available = columns[3].parse::<u64>().ok()?;
+
+            Some(DiskUsage {
+                device: columns[0].to_string(),
+                mount: columns[5..].join(" "),
+                total: total * 1024,
+                available: available * 1024,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    insert_disk_tokens(format_info, &disks, mounts);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ironbar;
+
+    const OUTPUT: &str = "@@ loadavg
+0.52 0.58 0.59 1/467 12345
+@@ meminfo
+MemTotal:       16000000 kB
+MemFree:         1000000 kB
+MemAvailable:    4000000 kB
+SwapTotal:       2000000 kB
+SwapFree:        2000000 kB
+@@ uptime
+93784.12 350000.00
+@@ stat
+cpu  100 0 100 700 100 0 0 0 0 0
+cpu0 50 0 50 350 50 0 0 0 0 0
+@@ net/dev
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+  eth0: 1000 10 0 0 0 0 0 0 2000 20 0 0 0 0 0 0
+@@ df
+Filesystem     1024-blocks      Used Available Capacity Mounted on
+/dev/sda1        100000000  25000000  75000000      25% /
+/dev/sdb1          1000000    500000    500000      50% /mnt/my disk";
+
+    fn default(info: &HashMap<String, TokenValue>, key: &str) -> Option<String> {
+        info.get(key).map(|value| value.format(None, false))
+    }
+
+    #[test]
+    fn parse_first_output() {
+        let mut info = HashMap::new();
+        parse_output(OUTPUT, &mut info, &mut Previous::default(), None, None);
+
+        assert_eq!(default(&info, "load_average:5").as_deref(), Some("0.58"));
+        assert_eq!(default(&info, "memory_percent").as_deref(), Some("75"));
+        assert_eq!(default(&info, "swap_percent").as_deref(), Some("00"));
+        assert_eq!(default(&info, "uptime").as_deref(), Some("26:03"));
+        assert_eq!(default(&info, "disk_percent:/").as_deref(), Some("25"));
+        assert_eq!(
+            default(&info, "disk_percent:/mnt/my disk").as_deref(),
+            Some("50")
+        );
+        assert_eq!(default(&info, "disk_total").as_deref(), Some("103"));
+
+        // rates need a previous reading
+        assert!(!info.contains_key("cpu_percent"));
+        assert!(!info.contains_key("net_down:eth0"));
+    }
+
+    #[test]
+    fn parse_cpu_usage() {
+        let mut info = HashMap::new();
+        let mut previous = HashMap::new();
+
+        parse_stat(
+            &[
+                "cpu  100 0 100 700 100 0 0 0 0 0",
+                "cpu0 50 0 50 350 50 0 0 0 0 0",
+            ],
+            &mut info,
+            &mut previous,
+        );
+        parse_stat(
+            &[
+                "cpu  150 0 150 800 100 0 0 0 0 0",
+                "cpu0 100 0 50 350 50 0 0 0 0 0",
+            ],
+            &mut info,
+            &mut previous,
+        );
+
+        assert_eq!(default(&info, "cpu_percent").as_deref(), Some("50"));
+        assert_eq!(default(&info, "cpu_percent@core0").as_deref(), Some("100"));
+        assert_eq!(default(&info, "cpu_cores").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn parse_network_totals() {
+        let mut info = HashMap::new();
+        let mut previous = HashMap::new();
+
+        parse_net_dev(
+            &[
+                "lo: 1000 0 0 0 0 0 0 0 1000 0 0 0 0 0 0 0",
+                "eth0: 1000 0 0 0 0 0 0 0 9000 0 0 0 0 0 0 0",
+                "wlan0: 1000 0 0 0 0 0 0 0 1000 0 0 0 0 0 0 0",
+            ],
+            &mut info,
+            &mut previous,
+            None,
+            None,
+        );
+
+        // eth0 transmit counter has reset
+        parse_net_dev(
+            &[
+                "lo: 9000 0 0 0 0 0 0 0 9000 0 0 0 0 0 0 0",
+                "eth0: 3000 0 0 0 0 0 0 0 500 0 0 0 0 0 0 0",
+                "wlan0: 1000 0 0 0 0 0 0 0 3000 0 0 0 0 0 0 0",
+            ],
+            &mut info,
+            &mut previous,
+            Some(2),
+            None,
+        );
+
+        assert_eq!(default(&info, "net_down").as_deref(), Some("1000.0 B/s"));
+        assert_eq!(default(&info, "net_up").as_deref(), Some("1000.0 B/s"));
+        assert_eq!(default(&info, "net_down_bits").as_deref(), Some("00"));
+
+        let interfaces = [String::from("eth0")];
+        parse_net_dev(
+            &["eth0: 5000 0 0 0 0 0 0 0 500 0 0 0 0 0 0 0"],
+            &mut info,
+            &mut previous,
+            Some(2),
+            Some(&interfaces),
+        );
+
+        assert_eq!(default(&info, "net_down").as_deref(), Some("1000.0 B/s"));
+        assert_eq!(default(&info, "net_up").as_deref(), Some("0.0 B/s"));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let interval = Duration::from_secs(10);
+
+        assert_eq!(backoff(interval, 0), interval);
+        assert_eq!(backoff(interval, 1), Duration::from_secs(20));
+        assert_eq!(backoff(interval, 3), Duration::from_secs(80));
+        assert_eq!(backoff(interval, 100), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn reports_unreachable_host() {
+        let config: RemoteConfig =
+            serde_json::from_str(r#"{ "host": "server", "timeout": 1 }"#).expect("valid config");
+        let (tx, mut rx) = mpsc::channel(4);
+
+        let task = Ironbar::runtime().spawn(poll(config, None, None, tx, |host, _| async move {
+            Err(Report::msg(format!("{host} is unreachable")))
+        }));
+
+        let event =
+            Ironbar::runtime().block_on(async { timeout(Duration::from_secs(5), rx.recv()).await });
+        task.abort();
+
+        assert!(matches!(
+            event,
+            Ok(Some(ModuleUpdateEvent::Update(Update::Reachable(false))))
+        ));
+    }
+}
@@ -0,0 +1,712 @@
+use color_eyre::{Report, Result};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+
+/// Matches a `{token}` placeholder in a format string,
+/// capturing its contents.
+pub const PLACEHOLDER: &str = r"\{([^}]+)}";
+
+/// The type of quantity a token value represents.
+/// This determines which units it can be formatted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    /// A size in bytes.
+    Bytes,
+    /// A transfer rate in bits per second.
+    BitRate,
+    /// A transfer rate in bytes per second.
+    ByteRate,
+    /// A plain number, such as a percentage or temperature.
+    Number,
+    /// Pre-formatted text, which cannot be re-formatted.
+    Text,
+}
+
+impl ValueKind {
+    /// Gets the kind of value provided by the token with the given base name,
+    /// or `None` if the token is not known.
+    fn from_token_name(name: &str) -> Option<Self> {
+        match name {
+            "memory_free" | "memory_used" | "memory_total" | "swap_free" | "swap_used"
+            | "swap_total" | "disk_free" | "disk_used" | "disk_total" => Some(Self::Bytes),
+            "net_down" | "net_up" | "net_down_bits" | "net_up_bits" => Some(Self::BitRate),
+            "cpu_percent"
+            | "cpu_cores"
+            | "memory_percent"
+            | "swap_percent"
+            | "disk_percent"
+            | "temp_c"
+            | "temp_f"
+            | "load_average"
+            | "power_draw"
+            | "energy_full_design_pct" => Some(Self::Number),
+            "uptime" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    /// Gets the fixed units the value can be formatted in,
+    /// and the number of base units in each.
+    const fn units(self) -> &'static [(&'static str, f64)] {
+        match self {
+            Self::Bytes => &[
+                ("B", 1.0),
+                ("KB", 1e3),
+                ("MB", 1e6),
+                ("GB", 1e9),
+                ("TB", 1e12),
+                ("KiB", KIBI),
+                ("MiB", KIBI * KIBI),
+                ("GiB", KIBI * KIBI * KIBI),
+                ("TiB", KIBI * KIBI * KIBI * KIBI),
+            ],
+            Self::BitRate => &[
+                ("bps", 1.0),
+                ("Kbps", 1e3),
+                ("Mbps", 1e6),
+                ("Gbps", 1e9),
+                ("Kibps", KIBI),
+                ("Mibps", KIBI * KIBI),
+                ("Gibps", KIBI * KIBI * KIBI),
+            ],
+            Self::ByteRate => &[
+                ("B/s", 1.0),
+                ("KB/s", 1e3),
+                ("MB/s", 1e6),
+                ("GB/s", 1e9),
+                ("KiB/s", KIBI),
+                ("MiB/s", KIBI * KIBI),
+                ("GiB/s", KIBI * KIBI * KIBI),
+            ],
+            Self::Number | Self::Text => &[],
+        }
+    }
+
+    /// Gets the unit used when a precision is given without a unit.
+    /// This matches the unit used by the default output.
+    const fn default_unit(self) -> Option<(&'static str, f64)> {
+        match self {
+            Self::Bytes => Some(("GB", 1e9)),
+            Self::BitRate => Some(("Mbps", 1e6)),
+            Self::ByteRate | Self::Number | Self::Text => None,
+        }
+    }
+
+    /// Gets the spec used when none is given.
+    /// Rates in bytes vary over several units, so are always scaled.
+    const fn default_spec(self) -> Option<FormatSpec> {
+        match self {
+            Self::ByteRate => Some(FormatSpec::auto(1)),
+            _ => None,
+        }
+    }
+
+    /// Gets the units to pick between when auto-scaling.
+    fn auto_units(self, si_units: bool) -> Vec<(&'static str, f64)> {
+        let units = self.units();
+        let base = units.first().copied();
+
+        base.into_iter()
+            .chain(units.iter().skip(1).copied().filter(|(name, _)| {
+                let is_binary = name.contains('i');
+                is_binary != si_units
+            }))
+            .collect()
+    }
+
+    /// Whether the token takes an argument,
+    /// such as a mount point or network interface.
+    fn takes_argument(name: &str) -> bool {
+        matches!(
+            name,
+            "temp_c"
+                | "temp_f"
+                | "disk_free"
+                | "disk_used"
+                | "disk_total"
+                | "disk_percent"
+                | "net_down"
+                | "net_up"
+                | "load_average"
+                | "power_draw"
+                | "energy_full_design_pct"
+        )
+    }
+}
+
+const KIBI: f64 = 1024.0;
+
+/// The suffix selecting a single core for `cpu_percent`,
+/// followed by its index.
+pub const CORE_SUFFIX: &str = "@core";
+
+/// The separator between `temp_c` or `temp_f`
+/// and the name of a configured temperature sensor.
+pub const SENSOR_SEPARATOR: char = '@';
+
+/// A single value provided by the module controller.
+#[derive(Debug, Clone)]
+pub struct TokenValue {
+    kind: ValueKind,
+    raw: f64,
+    /// The value formatted as shown when no format spec is given.
+    default: String,
+    /// Text shown before the value however it is formatted.
+    prefix: String,
+}
+
+impl TokenValue {
+    /// Creates a new size value from a number of bytes.
+    pub fn bytes(bytes: u64) -> Self {
+        const BYTES_IN_GIGABYTE: u64 = 1_000_000_000;
+
+        Self {
+            kind: ValueKind::Bytes,
+            raw: bytes as f64,
+            default: (bytes / BYTES_IN_GIGABYTE).to_string(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Creates a new rate value from a number of bits per second.
+    pub fn bit_rate(bits_per_second: f64, default: String) -> Self {
+        Self {
+            kind: ValueKind::BitRate,
+            raw: bits_per_second,
+            default,
+            prefix: String::new(),
+        }
+    }
+
+    /// Creates a new rate value from a number of bytes per second.
+    /// This is always scaled to the most suitable unit.
+    pub fn byte_rate(bytes_per_second: f64) -> Self {
+        Self {
+            kind: ValueKind::ByteRate,
+            raw: bytes_per_second,
+            default: String::new(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Creates a new plain number value.
+    pub fn number(value: f64, default: String) -> Self {
+        Self {
+            kind: ValueKind::Number,
+            raw: value,
+            default,
+            prefix: String::new(),
+        }
+    }
+
+    /// Creates a new pre-formatted text value.
+    pub fn text(value: String) -> Self {
+        Self {
+            kind: ValueKind::Text,
+            raw: 0.0,
+            default: value,
+            prefix: String::new(),
+        }
+    }
+
+    /// Shows the text before the value, whichever format spec is used.
+    pub fn with_prefix(self, prefix: String) -> Self {
+        Self { prefix, ..self }
+    }
+
+    /// Formats the value according to the spec.
+    ///
+    /// When auto-scaling, `si_units` selects between
+    /// 1000-based and 1024-based units.
+    pub fn format(&self, spec: Option<&FormatSpec>, si_units: bool) -> String {
+        format!("{}{}", self.prefix, self.format_value(spec, si_units))
+    }
+
+    fn format_value(&self, spec: Option<&FormatSpec>, si_units: bool) -> String {
+        let Some(spec) = spec.copied().or(self.kind.default_spec()) else {
+            return self.default.clone();
+        };
+
+        let precision = spec.precision.unwrap_or_default();
+
+        let unit = match spec.unit {
+            None if self.kind == ValueKind::ByteRate => Some(Unit::Auto),
+            unit => unit,
+        };
+
+        match unit {
+            Some(Unit::Auto) => {
+                let units = self.kind.auto_units(si_units);
+                let (name, factor) = units
+                    .iter()
+                    .rev()
+                    .find(|(_, factor)| self.raw.abs() >= *factor)
+                    .or_else(|| units.first())
+                    .copied()
+                    .unwrap_or(("", 1.0));
+
+                format!("{:.precision$} {name}", self.raw / factor)
+            }
+            Some(Unit::Fixed(_, factor)) => format!("{:.precision$}", self.raw / factor),
+            None => match self.kind.default_unit() {
+                Some((_, factor)) => format!("{:.precision$}", self.raw / factor),
+                None if self.kind == ValueKind::Text => self.default.clone(),
+                None => format!("{:.precision$}", self.raw),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Unit {
+    /// Scale to the largest unit with a value of at least one.
+    Auto,
+    /// Always use the named unit, with the given number of base units in it.
+    Fixed(&'static str, f64),
+}
+
+/// Controls how a token value is formatted.
+///
+/// Written after the token as `unit.precision`,
+/// where either part can be omitted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatSpec {
+    unit: Option<Unit>,
+    precision: Option<usize>,
+}
+
+impl FormatSpec {
+    /// Creates a spec which scales the value to the most suitable unit.
+    pub const fn auto(precision: usize) -> Self {
+        Self {
+            unit: Some(Unit::Auto),
+            precision: Some(precision),
+        }
+    }
+
+    /// Parses a spec for a value of the given kind.
+    fn parse(spec: &str, kind: ValueKind) -> Option<Self> {
+        let (unit, precision) = spec.split_once('.').unwrap_or((spec, ""));
+
+        let precision = if precision.is_empty() {
+            if spec.contains('.') {
+                return None;
+            }
+            None
+        } else {
+            Some(precision.parse().ok()?)
+        };
+
+        let unit = match unit {
+            "" => None,
+            _ if kind == ValueKind::Number || kind == ValueKind::Text => return None,
+            unit if unit.eq_ignore_ascii_case("auto") => Some(Unit::Auto),
+            unit => {
+                let (name, factor) = kind
+                    .units()
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+                    .copied()?;
+
+                Some(Unit::Fixed(name, factor))
+            }
+        };
+
+        if kind == ValueKind::Text || (unit.is_none() && precision.is_none()) {
+            return None;
+        }
+
+        Some(Self { unit, precision })
+    }
+}
+
+/// A token in a format string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// The key of the value in the controller output,
+    /// including any argument.
+    pub key: String,
+    /// The formatting to apply to the value.
+    /// If unset, the default output is used.
+    pub spec: Option<FormatSpec>,
+}
+
+impl Token {
+    /// Parses the contents of a `{token}` placeholder,
+    /// without the surrounding braces.
+    ///
+    /// Unknown tokens are kept as-is.
+    ///
+    /// # Errors
+    ///
+    /// If the token is known but
+    /// its format spec is invalid for the token type.
+    pub fn parse(token: &str) -> Result<Self> {
+        let name = token.split(':').next().unwrap_or_default();
+        let mut is_named_sensor = false;
+
+        let name = match name.split_once('@') {
+            Some(("cpu_percent", core)) => {
+                let is_core = core
+                    .strip_prefix("core")
+                    .is_some_and(|index| index.parse::<usize>().is_ok());
+
+                if !is_core {
+                    return Err(Report::msg(format!(
+                        "Invalid core '{core}' for token '{{{token}}}', expected 'core' followed by its index"
+                    )));
+                }
+
+                "cpu_percent"
+            }
+            Some((name @ ("temp_c" | "temp_f"), sensor)) => {
+                if sensor.is_empty() {
+                    return Err(Report::msg(format!(
+                        "Missing sensor name for token '{{{token}}}'"
+                    )));
+                }
+
+                is_named_sensor = true;
+                name
+            }
+            _ => name,
+        };
+
+        let Some(kind) = ValueKind::from_token_name(name) else {
+            return Ok(Self {
+                key: token.to_string(),
+                spec: None,
+            });
+        };
+
+        // network, battery and disk tokens without an interface, battery or mount point
+        // are the totals across all of them, so their only argument may be a format spec
+        let total_kind = match name {
+            "net_down" | "net_up" => Some(ValueKind::ByteRate),
+            "power_draw"
+            | "energy_full_design_pct"
+            | "disk_free"
+            | "disk_used"
+            | "disk_total"
+            | "disk_percent" => Some(kind),
+            _ => None,
+        };
+
+        if let Some(total_kind) = total_kind {
+            let spec = token
+                .split_once(':')
+                .and_then(|(_, spec)| FormatSpec::parse(spec, total_kind));
+
+            if let Some(spec) = spec {
+                return Ok(Self {
+                    key: name.to_string(),
+                    spec: Some(spec),
+                });
+            }
+        }
+
+        // named sensors take the place of the argument
+        let key_parts = if ValueKind::takes_argument(name) && !is_named_sensor {
+            2
+        } else {
+            1
+        };
+
+        let parts = token.splitn(key_parts + 1, ':').collect::<Vec<_>>();
+
+        if parts.len() <= key_parts {
+            return Ok(Self {
+                key: token.to_string(),
+                spec: None,
+            });
+        }
+
+        let spec = parts[key_parts];
+        let spec = FormatSpec::parse(spec, kind).ok_or_else(|| {
+            Report::msg(format!(
+                "Invalid format spec '{spec}' for token '{{{token}}}'"
+            ))
+        })?;
+
+        Ok(Self {
+            key: parts[..key_parts].join(":"),
+            spec: Some(spec),
+        })
+    }
+
+    /// Whether the token is the usage of a single core,
+    /// which may not exist.
+    pub fn is_per_core(&self) -> bool {
+        self.key.contains(CORE_SUFFIX)
+    }
+
+    /// Whether the token is the usage of a single mount point,
+    /// which may not exist.
+    pub fn is_per_mount(&self) -> bool {
+        self.key.starts_with("disk_") && self.key.contains(':')
+    }
+}
+
+/// The format strings, with the tokens they contain.
+///
+/// Tokens are parsed when the config is loaded,
+/// so that an invalid format spec is reported as a config error.
+#[derive(Debug, Clone)]
+pub struct Format {
+    pub strings: Vec<String>,
+    /// Each token, by the contents of its placeholder.
+    pub tokens: HashMap<String, Token>,
+}
+
+impl Format {
+    fn parse(strings: Vec<String>) -> Result<Self> {
+        let re = Regex::new(PLACEHOLDER)?;
+
+        let tokens = strings
+            .iter()
+            .flat_map(|format| re.captures_iter(format))
+            .map(|caps| Token::parse(&caps[1]).map(|token| (caps[1].to_string(), token)))
+            .collect::<Result<_>>()?;
+
+        Ok(Self { strings, tokens })
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let strings = Vec::<String>::deserialize(deserializer)?;
+        Self::parse(strings).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_without_spec() {
+        let token = Token::parse("memory_used").expect("to parse");
+        assert_eq!(token.key, "memory_used");
+        assert_eq!(token.spec, None);
+
+        let token = Token::parse("disk_used:/").expect("to parse");
+        assert_eq!(token.key, "disk_used:/");
+        assert_eq!(token.spec, None);
+
+        let token = Token::parse("net_down:eth0.100").expect("to parse");
+        assert_eq!(token.key, "net_down:eth0.100");
+        assert_eq!(token.spec, None);
+    }
+
+    #[test]
+    fn parse_with_spec() {
+        let token = Token::parse("memory_used:GiB.1").expect("to parse");
+        assert_eq!(token.key, "memory_used");
+        assert_eq!(
+            token.spec,
+            Some(FormatSpec {
+                unit: Some(Unit::Fixed("GiB", KIBI * KIBI * KIBI)),
+                precision: Some(1)
+            })
+        );
+
+        let token = Token::parse("net_down:enp39s0:auto.0").expect("to parse");
+        assert_eq!(token.key, "net_down:enp39s0");
+        assert_eq!(
+            token.spec,
+            Some(FormatSpec {
+                unit: Some(Unit::Auto),
+                precision: Some(0)
+            })
+        );
+
+        let token = Token::parse("cpu_percent:.1").expect("to parse");
+        assert_eq!(token.key, "cpu_percent");
+    }
+
+    #[test]
+    fn parse_network_total() {
+        let token = Token::parse("net_down").expect("to parse");
+        assert_eq!(token.key, "net_down");
+        assert_eq!(token.spec, None);
+
+        let token = Token::parse("net_up:MB/s.2").expect("to parse");
+        assert_eq!(token.key, "net_up");
+        assert_eq!(
+            token.spec,
+            Some(FormatSpec {
+                unit: Some(Unit::Fixed("MB/s", 1e6)),
+                precision: Some(2)
+            })
+        );
+
+        let token = Token::parse("net_down:eth0").expect("to parse");
+        assert_eq!(token.key, "net_down:eth0");
+
+        let token = Token::parse("net_down_bits:Mbps.1").expect("to parse");
+        assert_eq!(token.key, "net_down_bits");
+    }
+
+    #[test]
+    fn parse_battery_total() {
+        let token = Token::parse("power_draw:.2").expect("to parse");
+        assert_eq!(token.key, "power_draw");
+        assert!(token.spec.is_some());
+
+        let token = Token::parse("power_draw:BAT0").expect("to parse");
+        assert_eq!(token.key, "power_draw:BAT0");
+        assert_eq!(token.spec, None);
+
+        let token = Token::parse("energy_full_design_pct:BAT1:.0").expect("to parse");
+        assert_eq!(token.key, "energy_full_design_pct:BAT1");
+        assert!(token.spec.is_some());
+    }
+
+    #[test]
+    fn parse_disk_total() {
+        let token = Token::parse("disk_used:GiB.1").expect("to parse");
+        assert_eq!(token.key, "disk_used");
+        assert!(token.spec.is_some());
+        assert!(!token.is_per_mount());
+
+        let token = Token::parse("disk_percent:/home").expect("to parse");
+        assert_eq!(token.key, "disk_percent:/home");
+        assert_eq!(token.spec, None);
+        assert!(token.is_per_mount());
+
+        let token = Token::parse("disk_free:/mnt/data:auto.1").expect("to parse");
+        assert_eq!(token.key, "disk_free:/mnt/data");
+        assert!(token.spec.is_some());
+    }
+
+    #[test]
+    fn parse_named_sensor() {
+        let token = Token::parse("temp_c@cpu").expect("to parse");
+        assert_eq!(token.key, "temp_c@cpu");
+        assert_eq!(token.spec, None);
+
+        let token = Token::parse("temp_f@gpu:.1").expect("to parse");
+        assert_eq!(token.key, "temp_f@gpu");
+        assert!(token.spec.is_some());
+
+        let token = Token::parse("temp_c:k10temp-Tctl").expect("to parse");
+        assert_eq!(token.key, "temp_c:k10temp-Tctl");
+
+        assert!(Token::parse("temp_c@").is_err());
+    }
+
+    #[test]
+    fn parse_per_core() {
+        let token = Token::parse("cpu_percent@core3").expect("to parse");
+        assert_eq!(token.key, "cpu_percent@core3");
+        assert!(token.is_per_core());
+
+        let token = Token::parse("cpu_percent@core12:.1").expect("to parse");
+        assert_eq!(token.key, "cpu_percent@core12");
+        assert_eq!(
+            token.spec,
+            Some(FormatSpec {
+                unit: None,
+                precision: Some(1)
+            })
+        );
+
+        assert!(!Token::parse("cpu_percent").expect("to parse").is_per_core());
+
+        for token in ["cpu_percent@core", "cpu_percent@corex", "cpu_percent@3"] {
+            let err = Token::parse(token).expect_err(token);
+            assert!(err.to_string().contains(token), "{err}");
+        }
+    }
+
+    #[test]
+    fn parse_unknown_token() {
+        let token = Token::parse("foo:bar.baz").expect("to parse");
+        assert_eq!(token.key, "foo:bar.baz");
+        assert_eq!(token.spec, None);
+    }
+
+    #[test]
+    fn reject_invalid_spec() {
+        for token in [
+            "memory_used:XiB.1",
+            "memory_used:GiB.x",
+            "memory_used:GiB.",
+            "memory_used:",
+            "cpu_percent:GiB",
+            "uptime:.1",
+            "net_down:eth0:GiB",
+            "disk_used:/:auto.1:extra",
+        ] {
+            let err = Token::parse(token).expect_err(token);
+            assert!(err.to_string().contains(token), "{err}");
+        }
+    }
+
+    #[test]
+    fn deserialize_format() {
+        let format = serde_json::from_str::<Format>(r#"["{memory_used:GiB.1} / {foo}"]"#)
+            .expect("to deserialize");
+
+        assert_eq!(format.tokens.len(), 2);
+        assert_eq!(format.tokens["memory_used:GiB.1"].key, "memory_used");
+
+        let err = serde_json::from_str::<Format>(r#"["{cpu_percent}", "{memory_used:XiB}"]"#)
+            .expect_err("spec to be invalid");
+        assert!(err.to_string().contains("{memory_used:XiB}"), "{err}");
+    }
+
+    #[test]
+    fn format_default() {
+        let value = TokenValue::bytes(3_500_000_000);
+        assert_eq!(value.format(None, false), "3");
+    }
+
+    #[test]
+    fn format_fixed_unit() {
+        let value = TokenValue::bytes(3 * 1024 * 1024 * 1024 / 2);
+        let token = Token::parse("memory_used:GiB.1").expect("to parse");
+        assert_eq!(value.format(token.spec.as_ref(), false), "1.5");
+
+        let token = Token::parse("memory_used:.2").expect("to parse");
+        assert_eq!(value.format(token.spec.as_ref(), false), "1.61");
+    }
+
+    #[test]
+    fn format_auto_unit() {
+        let value = TokenValue::bit_rate(2_500_000.0, String::from("02"));
+        let token = Token::parse("net_down:eth0:auto.1").expect("to parse");
+        assert_eq!(value.format(token.spec.as_ref(), true), "2.5 Mbps");
+        assert_eq!(value.format(token.spec.as_ref(), false), "2.4 Mibps");
+
+        let value = TokenValue::bytes(512);
+        let token = Token::parse("memory_free:auto").expect("to parse");
+        assert_eq!(value.format(token.spec.as_ref(), false), "512 B");
+    }
+
+    #[test]
+    fn format_byte_rate() {
+        let value = TokenValue::byte_rate(1_250_000.0);
+        assert_eq!(value.format(None, true), "1.2 MB/s");
+        assert_eq!(value.format(None, false), "1.2 MiB/s");
+
+        let token = Token::parse("net_down:.0").expect("to parse");
+        assert_eq!(value.format(token.spec.as_ref(), true), "1 MB/s");
+
+        let token = Token::parse("net_down:KB/s.0").expect("to parse");
+        assert_eq!(value.format(token.spec.as_ref(), true), "1250");
+    }
+
+    #[test]
+    fn format_prefix() {
+        let value = TokenValue::number(12.34, String::from("12.3")).with_prefix(String::from("+"));
+        assert_eq!(value.format(None, false), "+12.3");
+
+        let token = Token::parse("power_draw:.0").expect("to parse");
+        assert_eq!(value.format(token.spec.as_ref(), false), "+12");
+    }
+}
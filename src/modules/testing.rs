@@ -0,0 +1,708 @@
+//! Helpers for testing module controllers without a display.
+//!
+//! A [`ControllerHarness`] runs a module's controller
+//! using in-memory channels in place of its widget,
+//! so that tests can send it commands and check the updates it sends back.
+//!
+//! Clients can be swapped for fakes using [`ControllerHarness::clients`]
+//! before the controller is started.
+
+use super::boundary::ModuleTasks;
+use super::{ControllerContext, Module, ModuleUpdateEvent};
+use crate::clients::Clients;
+use crate::script::ScriptEnv;
+use crate::Ironbar;
+use color_eyre::Result;
+use glib::IsA;
+use gtk::Widget;
+use serde::de::DeserializeOwned;
+use std::cell::{RefCell, RefMut};
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+#[cfg(feature = "music")]
+pub use self::music::FakeMusicClient;
+#[cfg(feature = "workspaces+sway")]
+pub use self::sway::FakeSwayIpc;
+#[cfg(feature = "volume")]
+pub use self::volume::FakeVolumeClient;
+#[cfg(feature = "workspaces")]
+pub use self::workspaces::FakeWorkspaceClient;
+
+/// How long to wait for the controller to send an event
+/// before failing the test.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Parses a module config from JSON.
+///
+/// # Panics
+/// Will panic if the config is invalid.
+pub fn config<T: DeserializeOwned>(json: &str) -> T {
+    serde_json::from_str(json).expect("config to be valid")
+}
+
+/// Runs a module's controller, recording the events it sends.
+///
+/// The controller's tasks are stopped when the harness is dropped.
+pub struct ControllerHarness<TSend, TReceive>
+where
+    TSend: Clone,
+{
+    context: ControllerContext<TSend>,
+    rx: mpsc::Receiver<ModuleUpdateEvent<TSend>>,
+    controller_tx: mpsc::Sender<TReceive>,
+    controller_rx: Option<mpsc::Receiver<TReceive>>,
+    error_rx: mpsc::Receiver<String>,
+}
+
+impl<TSend, TReceive> ControllerHarness<TSend, TReceive>
+where
+    TSend: Clone,
+{
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel(64);
+        let (controller_tx, controller_rx) = mpsc::channel(64);
+        let (error_tx, error_rx) = mpsc::channel(1);
+
+        let context = ControllerContext {
+            tx,
+            script_env: ScriptEnv::new("test", "TEST-1", "test"),
+            clients: Rc::new(RefCell::new(Clients::new())),
+            tasks: ModuleTasks::new(error_tx, "test"),
+        };
+
+        Self {
+            context,
+            rx,
+            controller_tx,
+            controller_rx: Some(controller_rx),
+            error_rx,
+        }
+    }
+
+    /// Gets the clients available to the controller,
+    /// so that they can be replaced with fakes.
+    pub fn clients(&self) -> RefMut<'_, Clients> {
+        self.context.clients.borrow_mut()
+    }
+
+    /// Spawns the module's controller.
+    ///
+    /// # Panics
+    /// Will panic if a controller has already been started.
+    pub fn start<W, M>(&mut self, module: &M) -> Result<()>
+    where
+        W: IsA<Widget>,
+        M: Module<W, SendMessage = TSend, ReceiveMessage = TReceive>,
+    {
+        let rx = self
+            .controller_rx
+            .take()
+            .expect("controller to not be started");
+
+        module.spawn_controller(&self.context, rx)
+    }
+
+    /// Sends a command to the controller, as its widget would.
+    ///
+    /// # Panics
+    /// Will panic if the controller is not accepting commands.
+    pub fn send(&self, command: TReceive) {
+        self.controller_tx
+            .try_send(command)
+            .unwrap_or_else(|_| panic!("controller to accept command"));
+    }
+
+    /// Waits for the next event from the controller.
+    ///
+    /// # Panics
+    /// Will panic if no event is sent in time,
+    /// or if one of the controller's tasks panics.
+    pub fn next_event(&mut self) -> ModuleUpdateEvent<TSend> {
+        Ironbar::runtime().block_on(async {
+            tokio::select! {
+                Some(message) = self.error_rx.recv() => panic!("controller panicked: {message}"),
+                event = timeout(TIMEOUT, self.rx.recv()) => event
+                    .expect("controller to send an event in time")
+                    .expect("controller to be running"),
+            }
+        })
+    }
+
+    /// Waits for the next update from the controller,
+    /// skipping any popup events.
+    ///
+    /// # Panics
+    /// See [`Self::next_event`].
+    pub fn next_update(&mut self) -> TSend {
+        loop {
+            if let ModuleUpdateEvent::Update(update) = self.next_event() {
+                return update;
+            }
+        }
+    }
+}
+
+impl<TSend, TReceive> Default for ControllerHarness<TSend, TReceive>
+where
+    TSend: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TSend, TReceive> Drop for ControllerHarness<TSend, TReceive>
+where
+    TSend: Clone,
+{
+    fn drop(&mut self) {
+        self.context.tasks.abort();
+    }
+}
+
+#[cfg(feature = "music")]
+mod music {
+    use crate::clients::music::{MusicClient, PlayerState, PlayerUpdate, Rating, Status, Track};
+    use crate::lock;
+    use color_eyre::Result;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+
+    /// A music player which is controlled by the test.
+    ///
+    /// Like the MPRIS client, the current state is sent to each new subscriber.
+    #[derive(Debug)]
+    pub struct FakeMusicClient {
+        tx: broadcast::Sender<PlayerUpdate>,
+        current: Mutex<(Option<Track>, Status)>,
+    }
+
+    impl FakeMusicClient {
+        pub fn new() -> Arc<Self> {
+            let (tx, _) = broadcast::channel(16);
+
+            Arc::new(Self {
+                tx,
+                current: Mutex::default(),
+            })
+        }
+
+        /// Changes the current track, as the player would.
+        pub fn set_track(&self, track: Option<Track>) {
+            let mut current = lock!(self.current);
+            current.0 = track;
+            self.send(&current);
+        }
+
+        fn set_status(&self, update: impl FnOnce(&mut Status)) -> Result<()> {
+            let mut current = lock!(self.current);
+            update(&mut current.1);
+            self.send(&current);
+            Ok(())
+        }
+
+        fn send(&self, (track, status): &(Option<Track>, Status)) {
+            self.tx
+                .send(PlayerUpdate::Update(Box::new(track.clone()), *status))
+                .ok();
+        }
+    }
+
+    impl MusicClient for FakeMusicClient {
+        fn play(&self) -> Result<()> {
+            self.set_status(|status| status.state = PlayerState::Playing)
+        }
+
+        fn pause(&self) -> Result<()> {
+            self.set_status(|status| status.state = PlayerState::Paused)
+        }
+
+        fn next(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn prev(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_volume_percent(&self, vol: u8) -> Result<()> {
+            self.set_status(|status| status.volume_percent = Some(vol))
+        }
+
+        fn seek(&self, _duration: Duration) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_rating(&self, rating: f64) -> Result<()> {
+            let mut current = lock!(self.current);
+            if let Some(track) = &mut current.0 {
+                track.rating = Some(Rating {
+                    value: rating,
+                    writable: true,
+                });
+            }
+            self.send(&current);
+            Ok(())
+        }
+
+        fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
+            let rx = self.tx.subscribe();
+            self.send(&lock!(self.current));
+            rx
+        }
+    }
+}
+
+#[cfg(feature = "workspaces+sway")]
+mod sway {
+    use crate::clients::compositor::sway::{Client, SwayIpc};
+    use futures_lite::{stream, Stream};
+    use serde_json::{json, Value};
+    use std::future::{ready, Future};
+    use std::sync::Arc;
+    use swayipc_async::{Event, Fallible, Input, Node, NodeType, Workspace};
+    use tokio::sync::mpsc;
+
+    /// A Sway IPC connection which answers from canned JSON replies.
+    ///
+    /// Like Sway, focusing a workspace is answered with a focus event.
+    /// Any other command is accepted and ignored.
+    #[derive(Debug)]
+    pub struct FakeSwayIpc {
+        workspaces: Vec<Workspace>,
+        tree: Node,
+        events: mpsc::UnboundedSender<Event>,
+    }
+
+    impl FakeSwayIpc {
+        /// Creates a real Sway client on top of the canned replies,
+        /// along with a sender for further events.
+        ///
+        /// # Panics
+        /// Will panic if the replies are invalid.
+        pub fn client(
+            workspaces: Value,
+            tree: Value,
+        ) -> (Arc<Client<Self>>, mpsc::UnboundedSender<Event>) {
+            let (events, rx) = mpsc::unbounded_channel();
+
+            let ipc = Self {
+                workspaces: serde_json::from_value(workspaces).expect("workspaces to be valid"),
+                tree: serde_json::from_value(tree).expect("tree to be valid"),
+                events: events.clone(),
+            };
+
+            let rx = Box::pin(stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|event| (Ok(event), rx))
+            }));
+
+            (Arc::new(Client::with_connection(ipc, rx)), events)
+        }
+
+        /// Fills in the fields Sway sends for every node,
+        /// so that tests only need to give those they use.
+        pub fn node(fields: Value) -> Value {
+            let rect = json!({ "x": 0, "y": 0, "width": 0, "height": 0 });
+
+            let mut node = json!({
+                "id": 0,
+                "type": "con",
+                "border": "none",
+                "current_border_width": 0,
+                "layout": "none",
+                "rect": rect,
+                "window_rect": rect,
+                "deco_rect": rect,
+                "geometry": rect,
+                "urgent": false,
+                "focused": false,
+                "focus": [],
+                "nodes": [],
+                "floating_nodes": [],
+                "sticky": false,
+            });
+
+            if let (Some(node), Value::Object(fields)) = (node.as_object_mut(), fields) {
+                node.extend(fields);
+            }
+
+            node
+        }
+
+        /// Creates a workspace event, as Sway would send it.
+        ///
+        /// # Panics
+        /// Will panic if the event is invalid.
+        pub fn workspace_event(change: &str, current: Value, old: Option<Value>) -> Event {
+            let event = json!({ "change": change, "current": current, "old": old });
+
+            Event::Workspace(Box::new(
+                serde_json::from_value(event).expect("event to be valid"),
+            ))
+        }
+
+        fn focus(&mut self, name: &str) {
+            let mut workspaces = workspace_nodes(&mut self.tree);
+
+            let Some(index) = workspaces
+                .iter()
+                .position(|workspace| workspace.name.as_deref() == Some(name))
+            else {
+                return;
+            };
+
+            let old = workspaces
+                .iter()
+                .find(|workspace| workspace.focused)
+                .map(|workspace| json!(workspace));
+
+            for workspace in &mut workspaces {
+                workspace.focused = workspace.name.as_deref() == Some(name);
+            }
+
+            let new = json!(workspaces[index]);
+
+            for workspace in &mut self.workspaces {
+                workspace.focused = workspace.name == name;
+            }
+
+            self.events
+                .send(Self::workspace_event("focus", new, old))
+                .ok();
+        }
+    }
+
+    fn workspace_nodes(node: &mut Node) -> Vec<&mut Node> {
+        if node.node_type == NodeType::Workspace {
+            vec![node]
+        } else {
+            node.nodes.iter_mut().flat_map(workspace_nodes).collect()
+        }
+    }
+
+    impl SwayIpc for FakeSwayIpc {
+        fn get_workspaces(&mut self) -> impl Future<Output = Fallible<Vec<Workspace>>> + Send {
+            ready(Ok(self.workspaces.clone()))
+        }
+
+        fn get_tree(&mut self) -> impl Future<Output = Fallible<Node>> + Send {
+            ready(Ok(self.tree.clone()))
+        }
+
+        fn get_inputs(&mut self) -> impl Future<Output = Fallible<Vec<Input>>> + Send {
+            ready(Ok(vec![]))
+        }
+
+        fn run_command(
+            &mut self,
+            payload: String,
+        ) -> impl Future<Output = Fallible<Vec<Fallible<()>>>> + Send {
+            if let Some(name) = payload.strip_prefix("workspace ") {
+                self.focus(name);
+            }
+
+            ready(Ok(vec![Ok(())]))
+        }
+    }
+}
+
+#[cfg(feature = "volume")]
+mod volume {
+    use crate::clients::volume::{
+        Card, Event, Sink, SinkInput, Source, SourceOutput, VolumeClient,
+    };
+    use crate::lock;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::broadcast;
+
+    /// An audio server which is controlled by the test.
+    ///
+    /// Like the Pulse client, changes made through the client
+    /// are applied straight away and sent as events.
+    #[derive(Debug)]
+    pub struct FakeVolumeClient {
+        tx: broadcast::Sender<Event>,
+        sinks: Arc<Mutex<Vec<Sink>>>,
+        sink_inputs: Arc<Mutex<Vec<SinkInput>>>,
+        sources: Arc<Mutex<Vec<Source>>>,
+        source_outputs: Arc<Mutex<Vec<SourceOutput>>>,
+        cards: Arc<Mutex<Vec<Card>>>,
+    }
+
+    impl FakeVolumeClient {
+        pub fn new() -> Arc<Self> {
+            let (tx, _) = broadcast::channel(16);
+
+            Arc::new(Self {
+                tx,
+                sinks: Arc::default(),
+                sink_inputs: Arc::default(),
+                sources: Arc::default(),
+                source_outputs: Arc::default(),
+                cards: Arc::default(),
+            })
+        }
+
+        /// Adds a recording stream, as the server would when an application starts recording.
+        pub fn add_source_output(&self, output: SourceOutput) {
+            lock!(self.source_outputs).push(output.clone());
+            self.tx.send(Event::AddOutput(output)).ok();
+        }
+
+        /// Removes a recording stream, as the server would when an application stops recording.
+        pub fn remove_source_output(&self, index: u32) {
+            lock!(self.source_outputs).retain(|output| output.index != index);
+            self.tx.send(Event::RemoveOutput(index)).ok();
+        }
+
+        fn update_sink(
+            &self,
+            devices: &Mutex<Vec<Sink>>,
+            name: &str,
+            update: impl FnOnce(&mut Sink),
+            event: fn(Sink) -> Event,
+        ) {
+            let mut devices = lock!(devices);
+            if let Some(device) = devices.iter_mut().find(|device| device.name == name) {
+                update(device);
+                self.tx.send(event(device.clone())).ok();
+            }
+        }
+
+        fn update_stream(
+            &self,
+            streams: &Mutex<Vec<SinkInput>>,
+            index: u32,
+            update: impl FnOnce(&mut SinkInput),
+            event: fn(SinkInput) -> Event,
+        ) {
+            let mut streams = lock!(streams);
+            if let Some(stream) = streams.iter_mut().find(|stream| stream.index == index) {
+                update(stream);
+                self.tx.send(event(stream.clone())).ok();
+            }
+        }
+
+        fn set_default(&self, devices: &Mutex<Vec<Sink>>, name: &str, event: fn(Sink) -> Event) {
+            for device in lock!(devices).iter_mut() {
+                device.active = device.name == name;
+                self.tx.send(event(device.clone())).ok();
+            }
+        }
+    }
+
+    impl VolumeClient for FakeVolumeClient {
+        fn subscribe(&self) -> broadcast::Receiver<Event> {
+            self.tx.subscribe()
+        }
+
+        fn sinks(&self) -> Arc<Mutex<Vec<Sink>>> {
+            self.sinks.clone()
+        }
+
+        fn set_default_sink(&self, name: &str, _move_streams: bool) {
+            self.set_default(&self.sinks, name, Event::UpdateSink);
+        }
+
+        fn set_sink_volume(&self, name: &str, volume_percent: f64) {
+            self.update_sink(
+                &self.sinks,
+                name,
+                |sink| sink.volume = volume_percent,
+                Event::UpdateSink,
+            );
+        }
+
+        fn set_sink_muted(&self, name: &str, muted: bool) {
+            self.update_sink(
+                &self.sinks,
+                name,
+                |sink| sink.muted = muted,
+                Event::UpdateSink,
+            );
+        }
+
+        fn sink_inputs(&self) -> Arc<Mutex<Vec<SinkInput>>> {
+            self.sink_inputs.clone()
+        }
+
+        fn set_input_volume(&self, index: u32, volume_percent: f64) {
+            self.update_stream(
+                &self.sink_inputs,
+                index,
+                |input| input.volume = volume_percent,
+                Event::UpdateInput,
+            );
+        }
+
+        fn set_input_muted(&self, index: u32, muted: bool) {
+            self.update_stream(
+                &self.sink_inputs,
+                index,
+                |input| input.muted = muted,
+                Event::UpdateInput,
+            );
+        }
+
+        fn sources(&self) -> Arc<Mutex<Vec<Source>>> {
+            self.sources.clone()
+        }
+
+        fn set_default_source(&self, name: &str) {
+            self.set_default(&self.sources, name, Event::UpdateSource);
+        }
+
+        fn set_source_volume(&self, name: &str, volume_percent: f64) {
+            self.update_sink(
+                &self.sources,
+                name,
+                |source| source.volume = volume_percent,
+                Event::UpdateSource,
+            );
+        }
+
+        fn set_source_muted(&self, name: &str, muted: bool) {
+            self.update_sink(
+                &self.sources,
+                name,
+                |source| source.muted = muted,
+                Event::UpdateSource,
+            );
+        }
+
+        fn source_outputs(&self) -> Arc<Mutex<Vec<SourceOutput>>> {
+            self.source_outputs.clone()
+        }
+
+        fn set_output_volume(&self, index: u32, volume_percent: f64) {
+            self.update_stream(
+                &self.source_outputs,
+                index,
+                |output| output.volume = volume_percent,
+                Event::UpdateOutput,
+            );
+        }
+
+        fn set_output_muted(&self, index: u32, muted: bool) {
+            self.update_stream(
+                &self.source_outputs,
+                index,
+                |output| output.muted = muted,
+                Event::UpdateOutput,
+            );
+        }
+
+        fn cards(&self) -> Arc<Mutex<Vec<Card>>> {
+            self.cards.clone()
+        }
+
+        fn set_card_profile(&self, card: u32, profile: &str) {
+            let mut cards = lock!(self.cards);
+            if let Some(card) = cards.iter_mut().find(|c| c.index == card) {
+                card.active_profile = Some(profile.to_string());
+                self.tx.send(Event::UpdateCard(card.clone())).ok();
+            }
+        }
+
+        fn set_default_sink_with_profile(&self, card: u32, profile: &str, _move_streams: bool) {
+            self.set_card_profile(card, profile);
+        }
+    }
+}
+
+#[cfg(feature = "workspaces")]
+mod workspaces {
+    use crate::clients::compositor::{
+        KeyboardLayoutUpdate, StateUpdate, Visibility, Workspace, WorkspaceClient, WorkspaceUpdate,
+    };
+    use crate::lock;
+    use color_eyre::{Report, Result};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::broadcast;
+
+    /// A compositor which is controlled by the test.
+    ///
+    /// Like the real clients, the workspace list is sent to each new subscriber,
+    /// and focus requests are answered with a focus event.
+    #[derive(Debug)]
+    pub struct FakeWorkspaceClient {
+        workspaces: Mutex<Vec<Workspace>>,
+        tx: broadcast::Sender<WorkspaceUpdate>,
+        state_tx: broadcast::Sender<StateUpdate>,
+        keyboard_tx: broadcast::Sender<KeyboardLayoutUpdate>,
+    }
+
+    impl FakeWorkspaceClient {
+        pub fn new(workspaces: Vec<Workspace>) -> Arc<Self> {
+            let (tx, _) = broadcast::channel(16);
+            let (state_tx, _) = broadcast::channel(16);
+            let (keyboard_tx, _) = broadcast::channel(16);
+
+            Arc::new(Self {
+                workspaces: Mutex::new(workspaces),
+                tx,
+                state_tx,
+                keyboard_tx,
+            })
+        }
+
+        /// Sends an event, as the compositor would.
+        pub fn send(&self, update: WorkspaceUpdate) {
+            self.tx.send(update).ok();
+        }
+    }
+
+    impl WorkspaceClient for FakeWorkspaceClient {
+        fn focus(&self, name: String) -> Result<()> {
+            let mut workspaces = lock!(self.workspaces);
+
+            if !workspaces.iter().any(|workspace| workspace.name == name) {
+                return Err(Report::msg(format!("Workspace '{name}' does not exist")));
+            }
+
+            let old = workspaces
+                .iter()
+                .find(|workspace| workspace.visibility.is_focused())
+                .cloned();
+
+            for workspace in workspaces.iter_mut() {
+                if workspace.name == name {
+                    workspace.visibility = Visibility::focused();
+                } else if workspace.visibility.is_focused() {
+                    workspace.visibility = Visibility::Hidden;
+                }
+            }
+
+            let new = workspaces
+                .iter()
+                .find(|workspace| workspace.name == name)
+                .cloned()
+                .expect("workspace to exist");
+
+            self.send(WorkspaceUpdate::Focus { old, new });
+            Ok(())
+        }
+
+        fn subscribe_workspace_change(&self) -> broadcast::Receiver<WorkspaceUpdate> {
+            let rx = self.tx.subscribe();
+            self.send(WorkspaceUpdate::Init(lock!(self.workspaces).clone()));
+            rx
+        }
+
+        fn subscribe_state_change(&self) -> broadcast::Receiver<StateUpdate> {
+            self.state_tx.subscribe()
+        }
+
+        fn next_keyboard_layout(&self) -> Result<()> {
+            Err(Report::msg("Keyboard layouts are not supported"))
+        }
+
+        fn subscribe_keyboard_layout(&self) -> broadcast::Receiver<KeyboardLayoutUpdate> {
+            self.keyboard_tx.subscribe()
+        }
+    }
+}
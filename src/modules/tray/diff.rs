@@ -18,12 +18,17 @@ pub struct MenuItemDiff {
     /// True if the item is visible in the menu.
     pub visible: Option<bool>,
     /// Icon name of the item, following the freedesktop.org icon spec.
-    // pub icon_name: Option<Option<String>>,
+    pub icon_name: Option<Option<String>>,
+    /// PNG data of the icon.
+    pub icon_data: Option<Option<Vec<u8>>>,
     /// Describe the current state of a "togglable" item. Can be one of:
     ///   - Some(true): on
     ///   - Some(false): off
     ///   - None: indeterminate
     pub toggle_state: Option<ToggleState>,
+    /// Set to `submenu` if the item has a submenu,
+    /// which may not have been filled in yet.
+    pub children_display: Option<Option<String>>,
     /// A submenu for this item, typically this would ve revealed to the user by hovering the current item
     pub submenu: Vec<Diff>,
 }
@@ -52,8 +57,10 @@ impl MenuItemDiff {
             label: diff!(&label),
             enabled: diff!(enabled),
             visible: diff!(visible),
-            // icon_name: diff!(&icon_name),
+            icon_name: diff!(&icon_name),
+            icon_data: diff!(&icon_data),
             toggle_state: diff!(toggle_state),
+            children_display: diff!(&children_display),
             submenu: get_diffs(&old.submenu, &new.submenu),
         }
     }
@@ -63,8 +70,10 @@ impl MenuItemDiff {
         self.label.is_some()
             || self.enabled.is_some()
             || self.visible.is_some()
-            // || self.icon_name.is_some()
+            || self.icon_name.is_some()
+            || self.icon_data.is_some()
             || self.toggle_state.is_some()
+            || self.children_display.is_some()
             || !self.submenu.is_empty()
     }
 }
@@ -77,6 +86,14 @@ pub fn get_diffs(old: &[MenuItem], new: &[MenuItem]) -> Vec<Diff> {
         let old_item = old.iter().find(|&item| item.id == new_item.id);
 
         let diff = match old_item {
+            // a different kind of widget is needed
+            Some(old_item)
+                if old_item.menu_type != new_item.menu_type
+                    || old_item.toggle_type != new_item.toggle_type =>
+            {
+                diffs.push(Diff::Remove(old_item.id));
+                Some(Diff::Add(new_item.clone()))
+            }
             Some(old_item) => {
                 let item_diff = MenuItemDiff::new(old_item, new_item);
                 if item_diff.has_diff() {
@@ -1,42 +1,13 @@
-use crate::image::ImageProvider;
+use crate::image::{
+    forget_scalable, get_icon_theme_search_paths, load_scalable, ImageProvider, Scalable,
+};
 use crate::modules::tray::interface::TrayMenu;
 use color_eyre::{Report, Result};
-use glib::ffi::g_strfreev;
-use glib::translate::ToGlibPtr;
-use gtk::ffi::gtk_icon_theme_get_search_path;
 use gtk::gdk_pixbuf::{Colorspace, InterpType, Pixbuf};
-use gtk::prelude::IconThemeExt;
+use gtk::gio::{Cancellable, MemoryInputStream};
+use gtk::prelude::*;
 use gtk::{IconLookupFlags, IconTheme, Image};
-use std::collections::HashSet;
-use std::ffi::CStr;
-use std::os::raw::{c_char, c_int};
-use std::ptr;
-
-/// Gets the GTK icon theme search paths by calling the FFI function.
-/// Conveniently returns the result as a `HashSet`.
-fn get_icon_theme_search_paths(icon_theme: &IconTheme) -> HashSet<String> {
-    let mut gtk_paths: *mut *mut c_char = ptr::null_mut();
-    let mut n_elements: c_int = 0;
-    let mut paths = HashSet::new();
-    unsafe {
-        gtk_icon_theme_get_search_path(
-            icon_theme.to_glib_none().0,
-            &mut gtk_paths,
-            &mut n_elements,
-        );
-        // n_elements is never negative (that would be weird)
-        for i in 0..n_elements as usize {
-            let c_str = CStr::from_ptr(*gtk_paths.add(i));
-            if let Ok(str) = c_str.to_str() {
-                paths.insert(str.to_owned());
-            }
-        }
-
-        g_strfreev(gtk_paths);
-    }
-
-    paths
-}
+use tracing::warn;
 
 pub fn get_image(
     item: &TrayMenu,
@@ -61,14 +32,23 @@ fn get_image_from_icon_name(item: &TrayMenu, icon_theme: &IconTheme, size: u32)
         }
     }
 
-    let icon_info = item.icon_name.as_ref().and_then(|icon_name| {
-        icon_theme.lookup_icon(icon_name, size as i32, IconLookupFlags::empty())
+    let icon_name = item.icon_name.as_ref().filter(|icon_name| {
+        icon_theme
+            .lookup_icon(icon_name, size as i32, IconLookupFlags::empty())
+            .is_some()
     });
 
-    if let Some(icon_info) = icon_info {
-        let pixbuf = icon_info.load_icon()?;
+    if let Some(icon_name) = icon_name {
         let image = Image::new();
-        ImageProvider::create_and_load_surface(&pixbuf, &image)?;
+
+        // rendered once the image is on the bar too,
+        // as the scale factor is unknown until then
+        let source = Scalable::Icon {
+            name: icon_name.clone(),
+            theme: icon_theme.clone(),
+        };
+        load_scalable(&image, source, size as i32)?;
+
         Ok(image)
     } else {
         Err(Report::msg("could not find icon"))
@@ -121,3 +101,51 @@ fn get_image_from_pixmap(item: &TrayMenu, size: u32) -> Result<Image> {
     ImageProvider::create_and_load_surface(&pixbuf, &image)?;
     Ok(image)
 }
+
+/// Loads a menu item's icon into the image,
+/// preferring the named icon from the theme over the PNG data.
+///
+/// Returns `false` if the item has no usable icon.
+pub fn load_menu_icon(
+    image: &Image,
+    icon_name: Option<&str>,
+    icon_data: Option<&[u8]>,
+    icon_theme: &IconTheme,
+    size: i32,
+) -> bool {
+    if let Some(icon_name) = icon_name.filter(|icon_name| icon_theme.has_icon(icon_name)) {
+        let source = Scalable::Icon {
+            name: icon_name.to_string(),
+            theme: icon_theme.clone(),
+        };
+
+        match load_scalable(image, source, size) {
+            Ok(()) => return true,
+            Err(err) => warn!("{err:?}"),
+        }
+    }
+
+    forget_scalable(image);
+
+    if let Some(data) = icon_data {
+        let scaled_size = size * image.scale_factor();
+        let stream = MemoryInputStream::from_bytes(&glib::Bytes::from(data));
+
+        let loaded = Pixbuf::from_stream_at_scale(
+            &stream,
+            scaled_size,
+            scaled_size,
+            true,
+            None::<&Cancellable>,
+        )
+        .map_err(Report::new)
+        .and_then(|pixbuf| ImageProvider::create_and_load_surface(&pixbuf, image));
+
+        match loaded {
+            Ok(()) => return true,
+            Err(err) => warn!("Failed to load menu item icon: {err:?}"),
+        }
+    }
+
+    false
+}
@@ -1,14 +1,31 @@
 use super::diff::{Diff, MenuItemDiff};
+use super::{icon, MenuRequest, ADDRESS_TAG};
+use crate::clients::tray::AboutToShow;
+use crate::gtk_helpers::IronbarGtkExt;
 use crate::{spawn, try_send};
 use glib::Propagation;
 use gtk::prelude::*;
-use gtk::{CheckMenuItem, Image, Label, Menu, MenuItem, SeparatorMenuItem};
+use gtk::{CheckMenuItem, IconTheme, Image, Label, Menu, MenuItem, Orientation, SeparatorMenuItem};
 use std::collections::HashMap;
 use system_tray::client::ActivateRequest;
 use system_tray::item::{IconPixmap, StatusNotifierItem};
 use system_tray::menu::{MenuItem as MenuItemInfo, MenuType, ToggleState, ToggleType};
 use tokio::sync::mpsc;
 
+/// The size to render menu item icons at.
+const MENU_ICON_SIZE: i32 = 16;
+
+/// The ID of the root menu, as sent with `AboutToShow`.
+const ROOT_MENU_ID: i32 = 0;
+
+/// An interaction with a menu item, by its ID.
+#[derive(Debug, Clone, Copy)]
+enum Interaction {
+    Activate(i32),
+    /// The item's submenu is opening.
+    AboutToShow(i32),
+}
+
 /// Calls a method on the underlying widget,
 /// passing in a single argument.
 ///
@@ -59,54 +76,77 @@ pub(crate) struct TrayMenu {
     menu: HashMap<i32, TrayMenuItem>,
     state: Vec<MenuItemInfo>,
 
+    /// The item's application-provided ID,
+    /// which stays the same between restarts.
+    pub id: String,
     pub title: Option<String>,
     pub icon_name: Option<String>,
     pub icon_theme_path: Option<String>,
     pub icon_pixmap: Option<Vec<IconPixmap>>,
 
-    tx: mpsc::Sender<i32>,
+    icon_theme: IconTheme,
+    tx: mpsc::Sender<Interaction>,
 }
 
 impl TrayMenu {
     pub fn new(
-        tx: mpsc::Sender<ActivateRequest>,
+        tx: mpsc::Sender<MenuRequest>,
         address: String,
         item: StatusNotifierItem,
+        icon_theme: &IconTheme,
     ) -> Self {
         let widget = MenuItem::new();
         widget.style_context().add_class("item");
+        widget.set_tag(ADDRESS_TAG, address.clone());
+        widget.set_interactive();
 
         let (item_tx, mut item_rx) = mpsc::channel(8);
 
         if let Some(menu) = item.menu {
             spawn(async move {
-                while let Some(id) = item_rx.recv().await {
-                    try_send!(
-                        tx,
-                        ActivateRequest {
+                while let Some(interaction) = item_rx.recv().await {
+                    let request = match interaction {
+                        Interaction::Activate(id) => MenuRequest::Activate(ActivateRequest {
                             submenu_id: id,
                             menu_path: menu.clone(),
                             address: address.clone(),
-                        }
-                    );
+                        }),
+                        Interaction::AboutToShow(id) => MenuRequest::AboutToShow(AboutToShow {
+                            address: address.clone(),
+                            id,
+                        }),
+                    };
+
+                    try_send!(tx, request);
                 }
             });
         }
 
         let menu = Menu::new();
+        menu.add_class("tray-menu");
         widget.set_submenu(Some(&menu));
 
+        {
+            let tx = item_tx.clone();
+            // only a hint, so skipped if the item has no menu or is not keeping up
+            menu.connect_show(move |_| {
+                tx.try_send(Interaction::AboutToShow(ROOT_MENU_ID)).ok();
+            });
+        }
+
         Self {
             widget,
             menu_widget: menu,
             image_widget: None,
             label_widget: None,
             state: vec![],
+            id: item.id,
             title: item.title,
             icon_name: item.icon_name,
             icon_theme_path: item.icon_theme_path,
             icon_pixmap: item.icon_pixmap,
             menu: HashMap::new(),
+            icon_theme: icon_theme.clone(),
             tx: item_tx,
         }
     }
@@ -158,7 +198,7 @@ impl TrayMenu {
         for diff in diffs {
             match diff {
                 Diff::Add(info) => {
-                    let item = TrayMenuItem::new(&info, self.tx.clone());
+                    let item = TrayMenuItem::new(&info, self.tx.clone(), &self.icon_theme);
                     call!(self.menu_widget, add, item.widget);
                     self.menu.insert(item.id, item);
                     // self.widget.show_all();
@@ -185,7 +225,10 @@ impl TrayMenu {
         &self.state
     }
 
+    /// Stores the menu state the diffs were applied from,
+    /// and moves the items into its order.
     pub fn set_state(&mut self, state: Vec<MenuItemInfo>) {
+        reorder(&self.menu_widget, &self.menu, &state);
         self.state = state;
     }
 
@@ -202,9 +245,18 @@ impl TrayMenu {
 struct TrayMenuItem {
     id: i32,
     widget: TrayMenuWidget,
+    /// The item's icon and label.
+    /// Separators have neither.
+    content: Option<MenuItemContent>,
+    icon_name: Option<String>,
+    icon_data: Option<Vec<u8>>,
+    /// Whether the item says it has a submenu,
+    /// which may be filled in once it is about to be shown.
+    has_submenu: bool,
     menu_widget: Menu,
     submenu: HashMap<i32, TrayMenuItem>,
-    tx: mpsc::Sender<i32>,
+    icon_theme: IconTheme,
+    tx: mpsc::Sender<Interaction>,
 }
 
 #[derive(Debug)]
@@ -214,70 +266,76 @@ enum TrayMenuWidget {
     Checkbox(CheckMenuItem),
 }
 
-impl TrayMenuItem {
-    fn new(info: &MenuItemInfo, tx: mpsc::Sender<i32>) -> Self {
-        let mut submenu = HashMap::new();
-        let menu = Menu::new();
+impl TrayMenuWidget {
+    fn menu_item(&self) -> &MenuItem {
+        match self {
+            Self::Separator(widget) => widget.upcast_ref(),
+            Self::Standard(widget) => widget,
+            Self::Checkbox(widget) => widget.upcast_ref(),
+        }
+    }
+}
 
-        macro_rules! add_submenu {
-            ($menu:expr, $widget:expr) => {
-                if !info.submenu.is_empty() {
-                    for sub_item in &info.submenu {
-                        let sub_item = TrayMenuItem::new(sub_item, tx.clone());
-                        call!($menu, add, sub_item.widget);
-                        submenu.insert(sub_item.id, sub_item);
-                    }
+#[derive(Debug)]
+struct MenuItemContent {
+    icon: Image,
+    label: Label,
+}
 
-                    $widget.set_submenu(Some(&menu));
-                }
-            };
-        }
+impl MenuItemContent {
+    fn new(widget: &MenuItem, label: Option<&str>) -> Self {
+        let container = gtk::Box::new(Orientation::Horizontal, 6);
+
+        let icon = Image::new();
+        icon.add_class("icon");
+
+        let label = Label::new(label);
+        label.add_class("label");
 
+        container.add(&icon);
+        container.add(&label);
+        container.show_all();
+
+        widget.add(&container);
+
+        Self { icon, label }
+    }
+}
+
+impl TrayMenuItem {
+    fn new(info: &MenuItemInfo, tx: mpsc::Sender<Interaction>, icon_theme: &IconTheme) -> Self {
         let widget = match (info.menu_type, info.toggle_type) {
             (MenuType::Separator, _) => TrayMenuWidget::Separator(SeparatorMenuItem::new()),
-            (MenuType::Standard, ToggleType::Checkmark) => {
+            (MenuType::Standard, ToggleType::Checkmark | ToggleType::Radio) => {
                 let widget = CheckMenuItem::builder()
-                    .visible(info.visible)
-                    .sensitive(info.enabled)
-                    .active(info.toggle_state == ToggleState::On)
+                    .draw_as_radio(info.toggle_type == ToggleType::Radio)
                     .build();
 
-                if let Some(label) = &info.label {
-                    widget.set_label(label);
-                }
-
-                add_submenu!(menu, widget);
+                set_toggle_state(&widget, info.toggle_state);
 
                 {
                     let tx = tx.clone();
                     let id = info.id;
 
+                    // `activate` is also emitted when the state is set from an update,
+                    // so only clicks are sent to the item
                     widget.connect_button_press_event(move |_item, _button| {
-                        try_send!(tx, id);
+                        try_send!(tx, Interaction::Activate(id));
                         Propagation::Proceed
                     });
                 }
 
                 TrayMenuWidget::Checkbox(widget)
             }
-            (MenuType::Standard, _) => {
-                let widget = MenuItem::builder()
-                    .visible(info.visible)
-                    .sensitive(info.enabled)
-                    .build();
-
-                if let Some(label) = &info.label {
-                    widget.set_label(label);
-                }
-
-                add_submenu!(menu, widget);
+            (MenuType::Standard, ToggleType::CannotBeToggled) => {
+                let widget = MenuItem::new();
 
                 {
                     let tx = tx.clone();
                     let id = info.id;
 
                     widget.connect_activate(move |_item| {
-                        try_send!(tx, id);
+                        try_send!(tx, Interaction::Activate(id));
                     });
                 }
 
@@ -285,13 +343,48 @@ impl TrayMenuItem {
             }
         };
 
-        Self {
+        let menu_item = widget.menu_item();
+        menu_item.set_visible(info.visible);
+        menu_item.set_sensitive(info.enabled);
+
+        let content = match &widget {
+            TrayMenuWidget::Separator(_) => None,
+            _ => Some(MenuItemContent::new(menu_item, info.label.as_deref())),
+        };
+
+        let menu = Menu::new();
+        menu.add_class("tray-menu");
+
+        {
+            let tx = tx.clone();
+            let id = info.id;
+
+            menu.connect_show(move |_| {
+                tx.try_send(Interaction::AboutToShow(id)).ok();
+            });
+        }
+
+        let mut item = Self {
             id: info.id,
             widget,
+            content,
+            icon_name: info.icon_name.clone(),
+            icon_data: info.icon_data.clone(),
+            has_submenu: has_submenu(info.children_display.as_deref()),
             menu_widget: menu,
-            submenu,
+            submenu: HashMap::new(),
+            icon_theme: icon_theme.clone(),
             tx,
+        };
+
+        for sub_item in &info.submenu {
+            item.add_sub_item(sub_item);
         }
+
+        item.update_icon();
+        item.update_submenu();
+
+        item
     }
 
     /// Applies a diff to this submenu item.
@@ -300,53 +393,48 @@ impl TrayMenuItem {
     /// applying the submenu diffs to any further submenu items.
     fn apply_diff(&mut self, diff: MenuItemDiff) {
         if let Some(label) = diff.label {
-            let label = label.unwrap_or_default();
-            match &self.widget {
-                TrayMenuWidget::Separator(widget) => widget.set_label(&label),
-                TrayMenuWidget::Standard(widget) => widget.set_label(&label),
-                TrayMenuWidget::Checkbox(widget) => widget.set_label(&label),
+            if let Some(content) = &self.content {
+                content.label.set_label(&label.unwrap_or_default());
             }
         }
 
-        // TODO: Image support
-        // if let Some(icon_name) = diff.icon_name {
-        //
-        // }
+        let mut icon_changed = false;
+
+        if let Some(icon_name) = diff.icon_name {
+            self.icon_name = icon_name;
+            icon_changed = true;
+        }
+
+        if let Some(icon_data) = diff.icon_data {
+            self.icon_data = icon_data;
+            icon_changed = true;
+        }
+
+        if icon_changed {
+            self.update_icon();
+        }
 
         if let Some(enabled) = diff.enabled {
-            match &self.widget {
-                TrayMenuWidget::Separator(widget) => widget.set_sensitive(enabled),
-                TrayMenuWidget::Standard(widget) => widget.set_sensitive(enabled),
-                TrayMenuWidget::Checkbox(widget) => widget.set_sensitive(enabled),
-            }
+            self.widget.menu_item().set_sensitive(enabled);
         }
 
         if let Some(visible) = diff.visible {
-            match &self.widget {
-                TrayMenuWidget::Separator(widget) => widget.set_visible(visible),
-                TrayMenuWidget::Standard(widget) => widget.set_visible(visible),
-                TrayMenuWidget::Checkbox(widget) => widget.set_visible(visible),
-            }
+            self.widget.menu_item().set_visible(visible);
         }
 
         if let Some(toggle_state) = diff.toggle_state {
             if let TrayMenuWidget::Checkbox(widget) = &self.widget {
-                widget.set_active(toggle_state == ToggleState::On);
+                set_toggle_state(widget, toggle_state);
             }
         }
 
+        if let Some(children_display) = diff.children_display {
+            self.has_submenu = has_submenu(children_display.as_deref());
+        }
+
         for sub_diff in diff.submenu {
             match sub_diff {
-                Diff::Add(info) => {
-                    let menu_item = TrayMenuItem::new(&info, self.tx.clone());
-                    call!(self.menu_widget, add, menu_item.widget);
-
-                    if let TrayMenuWidget::Standard(widget) = &self.widget {
-                        widget.set_submenu(Some(&self.menu_widget));
-                    }
-
-                    self.submenu.insert(menu_item.id, menu_item);
-                }
+                Diff::Add(info) => self.add_sub_item(&info),
                 Diff::Update(id, diff) => {
                     if let Some(sub) = self.submenu.get_mut(&id) {
                         sub.apply_diff(diff);
@@ -356,13 +444,72 @@ impl TrayMenuItem {
                     if let Some(sub) = self.submenu.remove(&id) {
                         call!(self.menu_widget, remove, sub.widget);
                     }
-                    if let TrayMenuWidget::Standard(widget) = &self.widget {
-                        if self.submenu.is_empty() {
-                            widget.set_submenu(None::<&Menu>);
-                        }
-                    }
                 }
             }
         }
+
+        self.update_submenu();
+    }
+
+    /// Moves the submenu items into the order of the new state.
+    fn reorder(&self, submenu: &[MenuItemInfo]) {
+        reorder(&self.menu_widget, &self.submenu, submenu);
     }
+
+    fn add_sub_item(&mut self, info: &MenuItemInfo) {
+        let sub_item = Self::new(info, self.tx.clone(), &self.icon_theme);
+        call!(self.menu_widget, add, sub_item.widget);
+        self.submenu.insert(sub_item.id, sub_item);
+    }
+
+    /// Loads the icon, hiding it if the item has none.
+    fn update_icon(&self) {
+        if let Some(content) = &self.content {
+            let loaded = icon::load_menu_icon(
+                &content.icon,
+                self.icon_name.as_deref(),
+                self.icon_data.as_deref(),
+                &self.icon_theme,
+                MENU_ICON_SIZE,
+            );
+
+            content.icon.set_visible(loaded);
+        }
+    }
+
+    /// Attaches the submenu while it has items,
+    /// or the item says it has a submenu which has not been filled in yet.
+    fn update_submenu(&self) {
+        let menu_item = self.widget.menu_item();
+        let show = !self.submenu.is_empty() || self.has_submenu;
+
+        if show && menu_item.submenu().is_none() {
+            menu_item.set_submenu(Some(&self.menu_widget));
+        } else if !show && menu_item.submenu().is_some() {
+            menu_item.set_submenu(None::<&Menu>);
+        }
+    }
+}
+
+/// Moves the menu's items into the order of the new state, including in submenus.
+///
+/// Items added by diffs are appended to the end of their menu,
+/// so would otherwise be out of place when the layout changes.
+fn reorder(menu: &Menu, items: &HashMap<i32, TrayMenuItem>, state: &[MenuItemInfo]) {
+    for (position, info) in state.iter().enumerate() {
+        if let Some(item) = items.get(&info.id) {
+            menu.reorder_child(item.widget.menu_item(), position as i32);
+            item.reorder(&info.submenu);
+        }
+    }
+}
+
+/// Whether the `children-display` property says the item has a submenu.
+fn has_submenu(children_display: Option<&str>) -> bool {
+    children_display == Some("submenu")
+}
+
+fn set_toggle_state(widget: &CheckMenuItem, state: ToggleState) {
+    widget.set_active(state == ToggleState::On);
+    widget.set_inconsistent(state == ToggleState::Indeterminate);
 }
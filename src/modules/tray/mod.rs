@@ -1,16 +1,22 @@
 mod diff;
 mod icon;
 mod interface;
+mod order;
 
 use crate::clients::tray;
 use crate::config::CommonConfig;
+#[cfg(feature = "ipc")]
+use crate::gtk_helpers::IronbarGtkExt;
 use crate::modules::tray::diff::get_diffs;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, lock, module_impl, send_async, spawn};
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
+use crate::{glib_recv, send_async};
 use color_eyre::{Report, Result};
 use gtk::{prelude::*, PackDirection};
 use gtk::{IconTheme, MenuBar};
 use interface::TrayMenu;
+use order::ItemOrder;
 use serde::Deserialize;
 use std::collections::HashMap;
 use system_tray::client::Event;
@@ -18,6 +24,18 @@ use system_tray::client::{ActivateRequest, UpdateEvent};
 use tokio::sync::mpsc;
 use tracing::{debug, error, warn};
 
+/// The data tag holding the address of the item shown by each tray icon.
+const ADDRESS_TAG: &str = "tray-address";
+
+/// A request from a tray menu to its item.
+#[derive(Debug, Clone)]
+pub enum MenuRequest {
+    /// Activates a menu item, as if clicked.
+    Activate(ActivateRequest),
+    /// Tells the item a menu or submenu is opening.
+    AboutToShow(tray::AboutToShow),
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TrayModule {
@@ -43,6 +61,35 @@ pub struct TrayModule {
     #[cfg_attr(feature = "schema", schemars(schema_with = "schema_pack_direction"))]
     direction: Option<PackDirection>,
 
+    /// The name of the only output to show the tray on.
+    ///
+    /// This is useful when the same bar is shown on every output,
+    /// as only a single tray is expected by most apps.
+    /// Takes priority over the common `outputs` option.
+    ///
+    /// **Default**: `null`
+    preferred_output: Option<String>,
+
+    /// The IDs of tray items to show first, in the given order.
+    /// Other items are shown after these.
+    ///
+    /// Item IDs are set by each application,
+    /// and are shown in the debug logs when an item is added.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    order: Vec<String>,
+
+    /// Whether to remember the order items appear in,
+    /// and restore it when they next appear.
+    /// Items not seen before are added to the end.
+    ///
+    /// The order is saved to `$XDG_STATE_HOME/ironbar/tray-order`.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    remember_order: bool,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -83,48 +130,48 @@ fn schema_pack_direction(gen: &mut schemars::gen::SchemaGenerator) -> schemars::
 
 impl Module<MenuBar> for TrayModule {
     type SendMessage = Event;
-    type ReceiveMessage = ActivateRequest;
+    type ReceiveMessage = MenuRequest;
+
+    fn name() -> &'static str {
+        "tray"
+    }
+
+    fn take_common(&mut self) -> CommonConfig {
+        let mut common = self.common.take().unwrap_or_default();
 
-    module_impl!("tray");
+        if let Some(output) = self.preferred_output.take() {
+            common.outputs = Some(vec![output]);
+        }
+
+        common
+    }
 
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
 
         let client = context.try_client::<tray::Client>()?;
-        let mut tray_rx = client.subscribe();
-
-        let initial_items = lock!(client.items()).clone();
+        let (mut tray_rx, about_to_show_tx) = tray::subscribe(client.clone());
 
         // listen to tray updates
-        spawn(async move {
-            for (key, (item, menu)) in initial_items {
-                send_async!(
-                    tx,
-                    ModuleUpdateEvent::Update(Event::Add(key.clone(), item.into()))
-                );
-
-                if let Some(menu) = menu.clone() {
-                    send_async!(
-                        tx,
-                        ModuleUpdateEvent::Update(Event::Update(key, UpdateEvent::Menu(menu)))
-                    );
-                }
-            }
-
-            while let Ok(message) = tray_rx.recv().await {
+        context.spawn(async move {
+            while let Some(message) = tray_rx.recv().await {
                 send_async!(tx, ModuleUpdateEvent::Update(message));
             }
         });
 
         // send tray commands
-        spawn(async move {
-            while let Some(cmd) = rx.recv().await {
-                client.activate(cmd).await?;
+        context.spawn(async move {
+            while let Some(request) = rx.recv().await {
+                match request {
+                    MenuRequest::Activate(request) => client.activate(request).await?,
+                    MenuRequest::AboutToShow(request) => {
+                        send_async!(about_to_show_tx, request);
+                    }
+                }
             }
 
             Ok::<_, Report>(())
@@ -153,12 +200,16 @@ impl Module<MenuBar> for TrayModule {
 
         {
             let container = container.clone();
-            let mut menus = HashMap::new();
+            let mut items = Items {
+                menus: HashMap::new(),
+                order: ItemOrder::new(self.order, self.remember_order),
+            };
             let icon_theme = info.icon_theme.clone();
+            let icon_size = info.scaled(self.icon_size as i32) as u32;
 
             // listen for UI updates
             glib_recv!(context.subscribe(), update =>
-                on_update(update, &container, &mut menus, &icon_theme, self.icon_size, self.prefer_theme_icons, &context.controller_tx)
+                on_update(update, &container, &mut items, &icon_theme, icon_size, self.prefer_theme_icons, &context.controller_tx)
             );
         };
 
@@ -169,23 +220,72 @@ impl Module<MenuBar> for TrayModule {
     }
 }
 
+/// Opens the item's menu from its icon on the first bar showing it,
+/// as if the icon had been clicked.
+///
+/// Returns `false` if the item is not shown on any bar.
+#[cfg(feature = "ipc")]
+pub fn open_menu(application: &gtk::Application, address: &str) -> bool {
+    application
+        .windows()
+        .iter()
+        .find_map(|window| find_item(window.upcast_ref(), address))
+        // the widget activate signal for menu items is `activate-item`, which opens the submenu
+        .is_some_and(|item| WidgetExt::activate(&item))
+}
+
+/// Finds the visible tray icon for the item within the widget.
+#[cfg(feature = "ipc")]
+fn find_item(widget: &gtk::Widget, address: &str) -> Option<gtk::MenuItem> {
+    if let Some(item) = widget.downcast_ref::<gtk::MenuItem>() {
+        let is_item = item
+            .get_tag::<String>(ADDRESS_TAG)
+            .is_some_and(|item_address| item_address == address);
+
+        if is_item && item.is_mapped() {
+            return Some(item.clone());
+        }
+    }
+
+    widget
+        .downcast_ref::<gtk::Container>()?
+        .children()
+        .iter()
+        .find_map(|child| find_item(child, address))
+}
+
+/// The menu for each tray item by address,
+/// and the order they are shown in.
+struct Items {
+    menus: HashMap<Box<str>, TrayMenu>,
+    order: ItemOrder,
+}
+
 /// Handles UI updates as callback,
 /// getting the diff since the previous update and applying it to the menu.
 fn on_update(
     update: Event,
     container: &MenuBar,
-    menus: &mut HashMap<Box<str>, TrayMenu>,
+    items: &mut Items,
     icon_theme: &IconTheme,
     icon_size: u32,
     prefer_icons: bool,
-    tx: &mpsc::Sender<ActivateRequest>,
+    tx: &mpsc::Sender<MenuRequest>,
 ) {
     match update {
         Event::Add(address, item) => {
             debug!("Received new tray item at '{address}': {item:?}");
 
-            let mut menu_item = TrayMenu::new(tx.clone(), address.clone(), *item);
-            container.add(&menu_item.widget);
+            // the item may be re-sent when re-syncing with the shared client
+            if let Some(menu_item) = items.menus.remove(address.as_str()) {
+                container.remove(&menu_item.widget);
+                items.order.remove(&menu_item.id);
+            }
+
+            let mut menu_item = TrayMenu::new(tx.clone(), address.clone(), *item, icon_theme);
+
+            let pos = items.order.insert(&menu_item.id);
+            container.insert(&menu_item.widget, pos as i32);
 
             if let Ok(image) = icon::get_image(&menu_item, icon_theme, icon_size, prefer_icons) {
                 menu_item.set_image(&image);
@@ -195,12 +295,12 @@ fn on_update(
             };
 
             menu_item.widget.show();
-            menus.insert(address.into(), menu_item);
+            items.menus.insert(address.into(), menu_item);
         }
         Event::Update(address, update) => {
             debug!("Received tray update for '{address}': {update:?}");
 
-            let Some(menu_item) = menus.get_mut(address.as_str()) else {
+            let Some(menu_item) = items.menus.get_mut(address.as_str()) else {
                 error!("Attempted to update menu at '{address}' but could not find it");
                 return;
             };
@@ -246,8 +346,9 @@ fn on_update(
         Event::Remove(address) => {
             debug!("Removing tray item at '{address}'");
 
-            if let Some(menu) = menus.get(address.as_str()) {
+            if let Some(menu) = items.menus.remove(address.as_str()) {
                 container.remove(&menu.widget);
+                items.order.remove(&menu.id);
             }
         }
     };
@@ -0,0 +1,162 @@
+use crate::state::{state_file, write_state};
+use std::fs;
+use std::path::PathBuf;
+use tracing::{debug, error};
+
+/// Decides where each tray item is placed.
+///
+/// Items listed in the config come first, in that order,
+/// followed by items in the order they were last seen,
+/// and finally any never seen before in the order they registered.
+#[derive(Debug, Default)]
+pub struct ItemOrder {
+    /// Item IDs set in the config.
+    config: Vec<String>,
+    /// Item IDs in the order they were previously seen.
+    remembered: Vec<String>,
+    /// The file to save the remembered order to.
+    /// If unset, the order is not remembered.
+    path: Option<PathBuf>,
+
+    /// The IDs of the items currently shown, in order.
+    shown: Vec<String>,
+}
+
+/// Sorts items by the priority of their source, then their position within it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Rank {
+    Config(usize),
+    Remembered(usize),
+    New,
+}
+
+impl ItemOrder {
+    pub fn new(config: Vec<String>, remember: bool) -> Self {
+        let path = remember.then(|| state_file("tray-order")).flatten();
+
+        let remembered = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(ToString::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            config,
+            remembered,
+            path,
+            shown: vec![],
+        }
+    }
+
+    fn rank(&self, id: &str) -> Rank {
+        if let Some(pos) = self.config.iter().position(|item| item == id) {
+            Rank::Config(pos)
+        } else if let Some(pos) = self.remembered.iter().position(|item| item == id) {
+            Rank::Remembered(pos)
+        } else {
+            Rank::New
+        }
+    }
+
+    /// Records that the item is being shown,
+    /// and gets the position to insert its widget at.
+    pub fn insert(&mut self, id: &str) -> usize {
+        let rank = self.rank(id);
+
+        // new items have equal rank, so end up in registration order.
+        let pos = self
+            .shown
+            .iter()
+            .position(|item| self.rank(item) > rank)
+            .unwrap_or(self.shown.len());
+
+        self.shown.insert(pos, id.to_string());
+
+        if !id.is_empty() && !self.remembered.iter().any(|item| item == id) {
+            self.remembered.push(id.to_string());
+            self.save();
+        }
+
+        pos
+    }
+
+    /// Records that the item is no longer shown.
+    /// It keeps its remembered position for when it returns.
+    pub fn remove(&mut self, id: &str) {
+        if let Some(pos) = self.shown.iter().position(|item| item == id) {
+            self.shown.remove(pos);
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        debug!("Saving tray order to {}", path.display());
+
+        let mut contents = self.remembered.join("\n");
+        contents.push('\n');
+
+        if let Err(err) = write_state(path, contents) {
+            error!("Failed to save tray order: {err:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(config: &[&str], remembered: &[&str]) -> ItemOrder {
+        ItemOrder {
+            config: config.iter().map(ToString::to_string).collect(),
+            remembered: remembered.iter().map(ToString::to_string).collect(),
+            ..ItemOrder::default()
+        }
+    }
+
+    #[test]
+    fn restores_remembered_order() {
+        let mut order = order(&[], &["a", "b", "c"]);
+
+        assert_eq!(order.insert("c"), 0);
+        assert_eq!(order.insert("new"), 1);
+        assert_eq!(order.insert("a"), 0);
+        assert_eq!(order.insert("b"), 1);
+
+        assert_eq!(order.shown, ["a", "b", "c", "new"]);
+        assert_eq!(order.remembered, ["a", "b", "c", "new"]);
+    }
+
+    #[test]
+    fn config_takes_precedence() {
+        let mut order = order(&["c", "x"], &["a", "b", "c"]);
+
+        assert_eq!(order.insert("a"), 0);
+        assert_eq!(order.insert("c"), 0);
+        assert_eq!(order.insert("new"), 2);
+        assert_eq!(order.insert("x"), 1);
+
+        assert_eq!(order.shown, ["c", "x", "a", "new"]);
+    }
+
+    #[test]
+    fn removed_items_keep_position() {
+        let mut order = order(&[], &[]);
+
+        order.insert("a");
+        order.insert("b");
+        order.remove("a");
+        order.insert("c");
+
+        assert_eq!(order.insert("a"), 0);
+        assert_eq!(order.shown, ["a", "b", "c"]);
+    }
+}
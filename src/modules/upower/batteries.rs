@@ -0,0 +1,100 @@
+use std::pin::Pin;
+
+use color_eyre::Result;
+use futures_lite::stream::{self, Stream, StreamExt};
+use tracing::{debug, error};
+use upower_dbus::DeviceProxy;
+
+use super::UpowerEvent;
+use crate::clients::upower;
+use crate::modules::{ControllerContext, ModuleUpdateEvent};
+use crate::send_async;
+
+/// A single battery powering the system,
+/// as listed in the popup.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Battery {
+    /// The battery model,
+    /// or its kernel name (for example `BAT0`) if that is unknown.
+    pub name: String,
+    pub percentage: f64,
+}
+
+impl Battery {
+    async fn read(device: &DeviceProxy<'_>) -> Result<Self> {
+        let model = device.model().await?;
+
+        let name = if model.trim().is_empty() {
+            let path = device.native_path().await?;
+            path.rsplit('/').next().unwrap_or_default().to_string()
+        } else {
+            model
+        };
+
+        Ok(Self {
+            name,
+            percentage: device.percentage().await?,
+        })
+    }
+}
+
+/// Starts watching each battery individually,
+/// sending the full list whenever any percentage changes.
+///
+/// Systems with a single battery still send it,
+/// for the popup to decide whether to list it.
+pub fn spawn_controller(context: &ControllerContext<UpowerEvent>) {
+    let tx = context.tx.clone();
+
+    context.spawn(async move {
+        let devices = match upower::create_battery_proxies().await {
+            Ok(devices) => devices,
+            Err(err) => {
+                error!("Failed to find batteries: {err:?}");
+                return;
+            }
+        };
+
+        debug!("Found {} batteries", devices.len());
+        if devices.is_empty() {
+            return;
+        }
+
+        let mut changes: Pin<Box<dyn Stream<Item = ()> + Send>> = Box::pin(stream::pending());
+        for device in &devices {
+            let percentage = device.receive_percentage_changed().await.map(|_| ());
+            changes = Box::pin(changes.or(percentage));
+        }
+
+        let mut current = read_all(&devices).await;
+        send_async!(
+            tx,
+            ModuleUpdateEvent::Update(UpowerEvent::Batteries(current.clone()))
+        );
+
+        while changes.next().await.is_some() {
+            let batteries = read_all(&devices).await;
+            if batteries != current {
+                current.clone_from(&batteries);
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(UpowerEvent::Batteries(batteries))
+                );
+            }
+        }
+    });
+}
+
+/// Reads every battery, skipping any which cannot be read.
+async fn read_all(devices: &[DeviceProxy<'static>]) -> Vec<Battery> {
+    let mut batteries = Vec::with_capacity(devices.len());
+
+    for device in devices {
+        match Battery::read(device).await {
+            Ok(battery) => batteries.push(battery),
+            Err(err) => error!("Failed to read battery: {err:?}"),
+        }
+    }
+
+    batteries
+}
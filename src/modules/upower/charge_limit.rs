@@ -0,0 +1,208 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+use tokio::select;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+use super::{UpowerCommand, UpowerEvent};
+use crate::modules::{ControllerContext, ModuleUpdateEvent};
+use crate::script::{OutputStream, Script, ScriptInput};
+use crate::send_async;
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+const THRESHOLD_FILE: &str = "charge_control_end_threshold";
+
+/// The number of percent below the limit at which
+/// the battery is considered to be held at it.
+const LIMIT_TOLERANCE: f64 = 1.0;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ChargeLimitConfig {
+    /// The sysfs file containing the charge threshold.
+    ///
+    /// If unset, the first battery which supports a threshold is used.
+    ///
+    /// **Default**: `null`
+    path: Option<PathBuf>,
+
+    /// The limits to cycle between when the popup button is clicked,
+    /// as percentages.
+    ///
+    /// If empty, the limit is shown but cannot be changed.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    profiles: Vec<u8>,
+
+    /// A [script](scripts) to run to change the limit,
+    /// instead of writing to the sysfs file directly.
+    /// Use this if Ironbar does not have permission to write the file,
+    /// for example by calling a helper via `pkexec` or `sudo`.
+    ///
+    /// The new limit is passed as an argument, and can be accessed using `$0`.
+    ///
+    /// **Default**: `null`
+    set_command: Option<ScriptInput>,
+
+    /// How often to check for the limit being changed elsewhere, in seconds.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    interval: u64,
+}
+
+const fn default_interval() -> u64 {
+    5
+}
+
+impl ChargeLimitConfig {
+    /// Whether there are profiles to cycle between.
+    pub fn can_cycle(&self) -> bool {
+        !self.profiles.is_empty()
+    }
+}
+
+/// Finds the first battery which supports a charge threshold.
+fn find_threshold_file() -> Option<PathBuf> {
+    let mut paths = fs::read_dir(POWER_SUPPLY_DIR)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("BAT"))
+        .map(|entry| entry.path().join(THRESHOLD_FILE))
+        .collect::<Vec<_>>();
+
+    paths.sort();
+    paths.into_iter().find(|path| path.exists())
+}
+
+fn read_limit(path: &Path) -> Option<u8> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Gets the profile after the current limit,
+/// or the first profile if the limit is not one of them.
+fn next_profile(profiles: &[u8], current: Option<u8>) -> Option<u8> {
+    let pos = current.and_then(|current| profiles.iter().position(|&limit| limit == current));
+
+    match pos {
+        Some(pos) => profiles.get((pos + 1) % profiles.len()).copied(),
+        None => profiles.first().copied(),
+    }
+}
+
+/// Whether charging is being held back by the limit,
+/// rather than the battery being full or discharging.
+pub fn is_limited(limit: Option<u8>, percentage: f64, on_battery: bool) -> bool {
+    limit.is_some_and(|limit| {
+        limit < 100 && !on_battery && percentage >= f64::from(limit) - LIMIT_TOLERANCE
+    })
+}
+
+/// Starts watching the charge limit, and handles requests to change it.
+///
+/// If the hardware does not support a charge limit,
+/// nothing is started and the limit is never shown.
+pub fn spawn_controller(
+    config: ChargeLimitConfig,
+    context: &ControllerContext<UpowerEvent>,
+    mut rx: mpsc::Receiver<UpowerCommand>,
+) {
+    let Some(path) = config
+        .path
+        .clone()
+        .filter(|path| path.exists())
+        .or_else(find_threshold_file)
+    else {
+        debug!("No battery charge limit control found");
+        return;
+    };
+
+    debug!("Using charge limit control at {}", path.display());
+
+    let set_command = config
+        .set_command
+        .map(|script| Script::new_polling(script).with_env(&context.script_env));
+
+    let tx = context.tx.clone();
+    // ticks after resume too, as the limit may be reset by firmware while suspended
+    let mut ticker = context.ticker(Duration::from_secs(config.interval.max(1)));
+    context.spawn(async move {
+        let mut current = read_limit(&path);
+        send_async!(
+            tx,
+            ModuleUpdateEvent::Update(UpowerEvent::ChargeLimit(current))
+        );
+
+        loop {
+            select! {
+                () = ticker.tick() => {}
+                Some(command) = rx.recv() => match command {
+                    UpowerCommand::CycleChargeLimit => {
+                        if let Some(limit) = next_profile(&config.profiles, current) {
+                            set_limit(&path, limit, set_command.as_ref()).await;
+                        }
+                    }
+                },
+            }
+
+            let limit = read_limit(&path);
+            if limit != current {
+                current = limit;
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(UpowerEvent::ChargeLimit(current))
+                );
+            }
+        }
+    });
+}
+
+async fn set_limit(path: &Path, limit: u8, set_command: Option<&Script>) {
+    debug!("Setting charge limit to {limit}%");
+
+    match set_command {
+        Some(script) => match script.get_output(Some(&[limit.to_string()])).await {
+            Ok((OutputStream::Stderr(out), _)) => error!("Failed to set charge limit: {out}"),
+            Err(err) => error!("{:?}", err.wrap_err("Failed to set charge limit")),
+            _ => {}
+        },
+        None => {
+            if let Err(err) = fs::write(path, limit.to_string()) {
+                error!(
+                    "Failed to write charge limit to {}: {err} (consider using `set_command`)",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_profiles() {
+        let profiles = [80, 100];
+
+        assert_eq!(next_profile(&profiles, Some(80)), Some(100));
+        assert_eq!(next_profile(&profiles, Some(100)), Some(80));
+        assert_eq!(next_profile(&profiles, Some(60)), Some(80));
+        assert_eq!(next_profile(&profiles, None), Some(80));
+        assert_eq!(next_profile(&[], Some(80)), None);
+    }
+
+    #[test]
+    fn limited_while_held_at_threshold() {
+        assert!(is_limited(Some(80), 80.0, false));
+        assert!(is_limited(Some(80), 79.4, false));
+        assert!(!is_limited(Some(80), 60.0, false));
+        assert!(!is_limited(Some(80), 80.0, true));
+        assert!(!is_limited(Some(100), 100.0, false));
+        assert!(!is_limited(None, 80.0, false));
+    }
+}
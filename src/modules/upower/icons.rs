@@ -0,0 +1,351 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use glib::{ControlFlow, SourceId};
+use gtk::prelude::*;
+use gtk::{IconTheme, Image, Label, Orientation};
+use serde::Deserialize;
+use upower_dbus::BatteryState;
+
+use super::UpowerProperties;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::ImageProvider;
+
+/// How long each icon is shown for while animating the charging ramp.
+const ANIMATION_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct IconsConfig {
+    /// Icons to show while charging, from empty to full.
+    /// One is picked based on the percentage,
+    /// so the list can be any length.
+    ///
+    /// If empty, the `discharging` icons are used instead.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    charging: Vec<String>,
+
+    /// Icons to show while not charging, from empty to full.
+    /// One is picked based on the percentage,
+    /// so the list can be any length.
+    ///
+    /// If empty, the icon provided by UPower is used.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    discharging: Vec<String>,
+
+    /// Icon to show once the device is fully charged.
+    ///
+    /// If unset, the last charging icon is used.
+    ///
+    /// **Default**: `null`
+    full: Option<String>,
+
+    /// Icon to show once the device is empty.
+    ///
+    /// If unset, the first discharging icon is used.
+    ///
+    /// **Default**: `null`
+    empty: Option<String>,
+}
+
+const fn is_charging(state: BatteryState) -> bool {
+    matches!(state, BatteryState::Charging | BatteryState::PendingCharge)
+}
+
+/// Gets the index of the ramp icon for a percentage,
+/// splitting the range evenly between the icons.
+fn ramp_index(len: usize, percentage: f64) -> usize {
+    let percentage = percentage.clamp(0.0, 100.0);
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let index = (percentage / 100.0 * len as f64) as usize;
+
+    index.min(len.saturating_sub(1))
+}
+
+impl IconsConfig {
+    /// Gets the icons to pick from in a state.
+    fn ramp(&self, state: BatteryState) -> &[String] {
+        if is_charging(state) && !self.charging.is_empty() {
+            &self.charging
+        } else {
+            &self.discharging
+        }
+    }
+
+    /// Gets the icon for a state and percentage,
+    /// or `None` to use the icon provided by UPower.
+    ///
+    /// While charging, `frame` moves the icon further up the ramp,
+    /// wrapping back round to the current level once it reaches the top.
+    fn icon(&self, state: BatteryState, percentage: f64, frame: usize) -> Option<&str> {
+        let ramp = self.ramp(state);
+
+        let icon = match state {
+            BatteryState::FullyCharged => self
+                .full
+                .as_ref()
+                .or_else(|| self.charging.last())
+                .or_else(|| ramp.last()),
+            BatteryState::Empty => self.empty.as_ref().or_else(|| ramp.first()),
+            _ if ramp.is_empty() => None,
+            _ => {
+                let index = ramp_index(ramp.len(), percentage);
+                let index = if is_charging(state) {
+                    index + frame % (ramp.len() - index)
+                } else {
+                    index
+                };
+
+                ramp.get(index)
+            }
+        };
+
+        icon.map(String::as_str)
+    }
+
+    /// Gets the number of icons to cycle between while animating,
+    /// which is one when there is nothing to animate.
+    fn frames(&self, state: BatteryState, percentage: f64) -> usize {
+        if !is_charging(state) {
+            return 1;
+        }
+
+        let ramp = self.ramp(state);
+        ramp.len() - ramp_index(ramp.len(), percentage)
+    }
+}
+
+#[derive(Debug, Default)]
+struct IconState {
+    properties: Option<UpowerProperties>,
+    frame: usize,
+    timer: Option<SourceId>,
+}
+
+/// The widget's device icon.
+///
+/// Each icon can be a text glyph or any image input,
+/// so both an image and label are kept and the relevant one shown.
+#[derive(Debug, Clone)]
+pub struct BatteryIcon {
+    container: gtk::Box,
+    image: Image,
+    label: Label,
+
+    icon_theme: IconTheme,
+    icon_size: i32,
+    config: Rc<Option<IconsConfig>>,
+    animate: bool,
+
+    state: Rc<RefCell<IconState>>,
+}
+
+impl BatteryIcon {
+    pub fn new(
+        config: Option<IconsConfig>,
+        animate: bool,
+        icon_theme: &IconTheme,
+        icon_size: i32,
+    ) -> Self {
+        let container = gtk::Box::new(Orientation::Horizontal, 0);
+
+        let image = Image::new();
+        image.add_class("icon");
+        image.add_class("image");
+
+        let label = Label::builder().use_markup(true).build();
+        label.add_class("icon");
+        label.add_class("text-icon");
+
+        container.add(&image);
+        container.add(&label);
+
+        container.show_all();
+        container.set_no_show_all(true);
+        label.hide();
+
+        let icon = Self {
+            container,
+            image,
+            label,
+            icon_theme: icon_theme.clone(),
+            icon_size,
+            config: Rc::new(config),
+            animate,
+            state: Rc::default(),
+        };
+
+        // the animation only runs while the icon is on screen
+        let on_map = icon.clone();
+        icon.container
+            .connect_map(move |_| on_map.update_animation());
+
+        let on_unmap = icon.clone();
+        icon.container
+            .connect_unmap(move |_| on_unmap.stop_animation());
+
+        icon
+    }
+
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+
+    pub fn set_properties(&self, properties: &UpowerProperties) {
+        self.state.borrow_mut().properties = Some(properties.clone());
+
+        self.render();
+        self.update_animation();
+    }
+
+    fn render(&self) {
+        let state = self.state.borrow();
+        let Some(properties) = &state.properties else {
+            return;
+        };
+
+        let icon = (*self.config)
+            .as_ref()
+            .and_then(|config| config.icon(properties.state, properties.percentage, state.frame));
+
+        match icon {
+            Some(icon) => self.set_input(icon),
+            None => self.set_input(&format!("icon:{}", properties.icon_name)),
+        }
+    }
+
+    fn set_input(&self, input: &str) {
+        let is_image = ImageProvider::is_definitely_image_input(input);
+
+        if is_image {
+            ImageProvider::parse(input, &self.icon_theme, false, self.icon_size)
+                .map(|provider| provider.load_into_image(self.image.clone()));
+        } else {
+            self.label.set_label(input);
+        }
+
+        self.image.set_visible(is_image);
+        self.label.set_visible(!is_image);
+    }
+
+    /// Starts the animation if the device is charging and the icon is visible,
+    /// or stops it otherwise.
+    fn update_animation(&self) {
+        let frames = {
+            let state = self.state.borrow();
+            match (&*self.config, &state.properties) {
+                (Some(config), Some(properties)) if self.animate => {
+                    config.frames(properties.state, properties.percentage)
+                }
+                _ => 1,
+            }
+        };
+
+        if frames <= 1 || !self.container.is_mapped() {
+            self.stop_animation();
+            return;
+        }
+
+        if self.state.borrow().timer.is_some() {
+            return;
+        }
+
+        let icon = self.clone();
+        let timer = glib::timeout_add_local(ANIMATION_INTERVAL, move || {
+            {
+                let mut state = icon.state.borrow_mut();
+                state.frame = state.frame.wrapping_add(1);
+            }
+
+            icon.render();
+            ControlFlow::Continue
+        });
+
+        self.state.borrow_mut().timer = Some(timer);
+    }
+
+    fn stop_animation(&self) {
+        let timer = {
+            let mut state = self.state.borrow_mut();
+            state.frame = 0;
+            state.timer.take()
+        };
+
+        if let Some(timer) = timer {
+            timer.remove();
+            self.render();
+        }
+    }
+}
+
+/// Sets the `.charging`, `.full` and `.discharging` classes for the state.
+pub fn set_state_classes<W: IsA<gtk::Widget>>(widget: &W, state: BatteryState) {
+    widget.set_class("charging", is_charging(state));
+    widget.set_class("full", state == BatteryState::FullyCharged);
+    widget.set_class(
+        "discharging",
+        matches!(
+            state,
+            BatteryState::Discharging | BatteryState::PendingDischarge | BatteryState::Empty
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icons() -> IconsConfig {
+        IconsConfig {
+            charging: vec![String::from("c0"), String::from("c1"), String::from("c2")],
+            discharging: vec![
+                String::from("d0"),
+                String::from("d1"),
+                String::from("d2"),
+                String::from("d3"),
+            ],
+            full: Some(String::from("full")),
+            empty: None,
+        }
+    }
+
+    #[test]
+    fn picks_ramp_icon() {
+        let icons = icons();
+
+        assert_eq!(icons.icon(BatteryState::Discharging, 0.0, 0), Some("d0"));
+        assert_eq!(icons.icon(BatteryState::Discharging, 49.0, 0), Some("d1"));
+        assert_eq!(icons.icon(BatteryState::Discharging, 50.0, 0), Some("d2"));
+        assert_eq!(icons.icon(BatteryState::Discharging, 100.0, 0), Some("d3"));
+        assert_eq!(icons.icon(BatteryState::Charging, 40.0, 0), Some("c1"));
+        assert_eq!(
+            icons.icon(BatteryState::FullyCharged, 100.0, 0),
+            Some("full")
+        );
+        assert_eq!(icons.icon(BatteryState::Empty, 0.0, 0), Some("d0"));
+
+        assert_eq!(
+            IconsConfig::default().icon(BatteryState::Charging, 40.0, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn animates_charging_ramp() {
+        let icons = icons();
+
+        assert_eq!(icons.frames(BatteryState::Charging, 40.0), 2);
+        assert_eq!(icons.icon(BatteryState::Charging, 40.0, 1), Some("c2"));
+        assert_eq!(icons.icon(BatteryState::Charging, 40.0, 2), Some("c1"));
+
+        assert_eq!(icons.frames(BatteryState::Charging, 100.0), 1);
+        assert_eq!(icons.frames(BatteryState::Discharging, 40.0), 1);
+        assert_eq!(icons.icon(BatteryState::Discharging, 40.0, 1), Some("d1"));
+    }
+}
@@ -0,0 +1,615 @@
+use color_eyre::Result;
+use futures_lite::stream::StreamExt;
+use gtk::{prelude::*, Button};
+use gtk::{Label, Orientation};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+use upower_dbus::{BatteryState, BatteryType};
+use zbus;
+use zbus::fdo::PropertiesProxy;
+
+use crate::clients::upower;
+use crate::config::CommonConfig;
+use crate::duration::{format_duration, DurationStyle};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::i18n::tr;
+use crate::modules::PopupButton;
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    WidgetContext,
+};
+use crate::script::{Script, ScriptInput};
+use crate::{glib_recv, module_impl, send_async, try_send};
+
+use self::batteries::Battery;
+use self::charge_limit::ChargeLimitConfig;
+use self::icons::{BatteryIcon, IconsConfig};
+
+mod batteries;
+mod charge_limit;
+mod icons;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct UpowerModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{percentage}%`
+    #[serde(default = "default_format")]
+    format: String,
+
+    /// The size to render the icon at, in pixels.
+    ///
+    /// **Default**: `24`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// Icons to show based on the device state and percentage,
+    /// instead of the icon provided by UPower.
+    /// Each icon can be a text glyph or an [image](images).
+    /// See [below](#icons) for options.
+    ///
+    /// **Default**: `null`
+    icons: Option<IconsConfig>,
+
+    /// Whether to cycle through the charging icons above the current level
+    /// while the device is charging.
+    /// This only runs while the widget is visible, and requires `icons`.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    animate_charging: bool,
+
+    /// How to write the time remaining,
+    /// both in the label tokens and the popup.
+    ///
+    /// **Valid options**: `clock`, `compact`, `verbose`
+    /// <br>
+    /// **Default**: `compact`
+    #[serde(default = "default_duration_style")]
+    duration_style: DurationStyle,
+
+    /// The type of device to show.
+    ///
+    /// If neither this nor `model` is set,
+    /// the combined display device is shown.
+    ///
+    /// **Valid options**: `battery`, `ups`, `mouse`, `keyboard`, `phone`
+    /// <br>
+    /// **Default**: `null`
+    device_type: Option<DeviceType>,
+
+    /// Shows the first device with a model containing this value.
+    /// This is not case-sensitive.
+    ///
+    /// If neither this nor `device_type` is set,
+    /// the combined display device is shown.
+    ///
+    /// **Default**: `null`
+    model: Option<String>,
+
+    /// A [script](scripts) to run when the device state changes,
+    /// and when the percentage changes while it is discharging.
+    ///
+    /// The script is passed the state (for example `discharging`) as `$0`,
+    /// and the percentage as `$1`.
+    ///
+    /// **Supported script types**: `oneshot`.
+    /// <br>
+    /// **Default**: `null`
+    on_state_change: Option<ScriptInput>,
+
+    /// Shows the battery's charge limit, and optionally allows changing it,
+    /// on hardware which supports a charge threshold.
+    /// See [below](#charge-limit) for options.
+    ///
+    /// **Default**: `null`
+    charge_limit: Option<ChargeLimitConfig>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    String::from("{percentage}%")
+}
+
+const fn default_icon_size() -> i32 {
+    24
+}
+
+const fn default_duration_style() -> DurationStyle {
+    DurationStyle::Compact
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DeviceType {
+    Battery,
+    Ups,
+    Mouse,
+    Keyboard,
+    Phone,
+}
+
+impl From<DeviceType> for BatteryType {
+    fn from(value: DeviceType) -> Self {
+        match value {
+            DeviceType::Battery => Self::Battery,
+            DeviceType::Ups => Self::Ups,
+            DeviceType::Mouse => Self::Mouse,
+            DeviceType::Keyboard => Self::Keyboard,
+            DeviceType::Phone => Self::Phone,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UpowerProperties {
+    percentage: f64,
+    icon_name: String,
+    state: BatteryState,
+    time_to_full: i64,
+    time_to_empty: i64,
+    is_ups: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum UpowerEvent {
+    Properties(UpowerProperties),
+    /// The battery's charge limit percentage,
+    /// if it could be read.
+    ChargeLimit(Option<u8>),
+    /// Each battery powering the system.
+    Batteries(Vec<Battery>),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum UpowerCommand {
+    /// Switches to the next configured charge limit profile.
+    CycleChargeLimit,
+}
+
+impl UpowerProperties {
+    /// Whether the device is currently running on its own power,
+    /// rather than being charged or on mains.
+    const fn is_on_battery(&self) -> bool {
+        matches!(
+            self.state,
+            BatteryState::Discharging | BatteryState::PendingDischarge | BatteryState::Empty
+        )
+    }
+}
+
+impl Module<gtk::Button> for UpowerModule {
+    type SendMessage = UpowerEvent;
+    type ReceiveMessage = UpowerCommand;
+
+    module_impl!("upower");
+
+    fn spawn_controller(
+        &self,
+        context: &ControllerContext<Self::SendMessage>,
+        rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        if let Some(charge_limit) = self.charge_limit.clone() {
+            charge_limit::spawn_controller(charge_limit, context, rx);
+        }
+
+        batteries::spawn_controller(context);
+
+        let tx = context.tx.clone();
+
+        let device_type = self.device_type.map(BatteryType::from);
+        let model = self.model.clone();
+
+        let display_proxy =
+            (device_type.is_none() && model.is_none()).then(|| context.client::<PropertiesProxy>());
+
+        let on_state_change = self
+            .on_state_change
+            .clone()
+            .map(|script| Script::new_polling(script).with_env(&context.script_env));
+
+        context.spawn(async move {
+            let display_proxy = match display_proxy {
+                Some(proxy) => proxy,
+                None => match upower::create_device_proxy(device_type, model.as_deref()).await {
+                    Ok(proxy) => Arc::new(proxy),
+                    Err(err) => {
+                        error!("{err:?}");
+                        return Ok(());
+                    }
+                },
+            };
+
+            let mut prop_changed_stream = display_proxy.receive_properties_changed().await?;
+
+            let device_interface_name =
+                zbus::names::InterfaceName::from_static_str("org.freedesktop.UPower.Device")
+                    .expect("failed to create zbus InterfaceName");
+
+            let properties = display_proxy.get_all(device_interface_name.clone()).await?;
+
+            let percentage = *properties["Percentage"]
+                .downcast_ref::<f64>()
+                .expect("expected percentage: f64 in HashMap of all properties");
+            let icon_name = properties["IconName"]
+                .downcast_ref::<str>()
+                .expect("expected IconName: str in HashMap of all properties")
+                .to_string();
+            let state = u32_to_battery_state(
+                *properties["State"]
+                    .downcast_ref::<u32>()
+                    .expect("expected State: u32 in HashMap of all properties"),
+            )
+            .unwrap_or(BatteryState::Unknown);
+            let time_to_full = *properties["TimeToFull"]
+                .downcast_ref::<i64>()
+                .expect("expected TimeToFull: i64 in HashMap of all properties");
+            let time_to_empty = *properties["TimeToEmpty"]
+                .downcast_ref::<i64>()
+                .expect("expected TimeToEmpty: i64 in HashMap of all properties");
+            let is_ups = properties
+                .get("Type")
+                .and_then(|value| value.downcast_ref::<u32>())
+                .is_some_and(|&device_type| device_type == BatteryType::Ups as u32);
+            let mut properties = UpowerProperties {
+                percentage,
+                icon_name: icon_name.clone(),
+                state,
+                time_to_full,
+                time_to_empty,
+                is_ups,
+            };
+
+            send_async!(
+                tx,
+                ModuleUpdateEvent::Update(UpowerEvent::Properties(properties.clone()))
+            );
+
+            while let Some(signal) = prop_changed_stream.next().await {
+                let args = signal.args().expect("Invalid signal arguments");
+                if args.interface_name != device_interface_name {
+                    continue;
+                }
+
+                let previous = properties.clone();
+
+                for (name, changed_value) in args.changed_properties {
+                    match name {
+                        "Percentage" => {
+                            properties.percentage = changed_value
+                                .downcast::<f64>()
+                                .expect("expected Percentage to be f64");
+                        }
+                        "IconName" => {
+                            properties.icon_name = changed_value
+                                .downcast_ref::<str>()
+                                .expect("expected IconName to be str")
+                                .to_string();
+                        }
+                        "State" => {
+                            properties.state =
+                                u32_to_battery_state(changed_value.downcast::<u32>().unwrap_or(0))
+                                    .expect("expected State to be BatteryState");
+                        }
+                        "TimeToFull" => {
+                            properties.time_to_full = changed_value
+                                .downcast::<i64>()
+                                .expect("expected TimeToFull to be i64");
+                        }
+                        "TimeToEmpty" => {
+                            properties.time_to_empty = changed_value
+                                .downcast::<i64>()
+                                .expect("expected TimeToEmpty to be i64");
+                        }
+                        _ => {}
+                    }
+                }
+
+                if let Some(script) = &on_state_change {
+                    let changed = properties.state != previous.state
+                        || (properties.is_on_battery()
+                            && properties.percentage != previous.percentage);
+
+                    if changed {
+                        script.run_as_oneshot(Some(&[
+                            battery_state_to_arg(properties.state).to_string(),
+                            properties.percentage.to_string(),
+                        ]));
+                    }
+                }
+
+                send_async!(
+                    tx,
+                    ModuleUpdateEvent::Update(UpowerEvent::Properties(properties.clone()))
+                );
+            }
+
+            Result::<()>::Ok(())
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let icon = BatteryIcon::new(
+            self.icons.clone(),
+            self.animate_charging,
+            info.icon_theme,
+            info.scaled(self.icon_size),
+        );
+
+        let label = Label::builder()
+            .label(&self.format)
+            .use_markup(true)
+            .build();
+        label.add_class("label");
+
+        let container = gtk::Box::new(Orientation::Horizontal, 5);
+        container.add_class("contents");
+
+        let button = Button::new();
+        button.add_class("button");
+
+        container.add(icon.widget());
+        container.add(&label);
+        button.add(&container);
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        let format = self.format.clone();
+        let duration_style = self.duration_style;
+
+        let rx = context.subscribe();
+        let widget = button.clone();
+
+        let mut current = None;
+        let mut charge_limit = None;
+
+        glib_recv!(rx, event => {
+            match event {
+                UpowerEvent::Properties(properties) => current = Some(properties),
+                UpowerEvent::ChargeLimit(limit) => charge_limit = limit,
+                UpowerEvent::Batteries(_) => {}
+            }
+
+            if let Some(properties) = &current {
+                let state = properties.state;
+                let time_remaining = time_remaining(properties, duration_style);
+
+                let state_string = if properties.is_ups {
+                    if properties.is_on_battery() { "On battery" } else { "Online" }
+                } else {
+                    battery_state_to_string(state)
+                };
+
+                let charge_limit_string = charge_limit
+                    .map(|limit| format!("{limit}%"))
+                    .unwrap_or_default();
+
+                let format = format.replace("{percentage}", &properties.percentage.to_string())
+                    .replace("{time_remaining}", &time_remaining)
+                    .replace("{time_to_empty}", &time_to_string(properties.time_to_empty, duration_style))
+                    .replace("{time_to_full}", &time_to_string(properties.time_to_full, duration_style))
+                    .replace("{state}", state_string)
+                    .replace("{charge_limit}", &charge_limit_string);
+
+                // also set on the bar window, so the whole bar can be styled
+                let on_battery = properties.is_on_battery();
+                widget.set_class("on-battery", on_battery);
+                if let Some(window) = widget.toplevel() {
+                    window.set_class("on-battery", on_battery);
+                }
+
+                widget.set_class(
+                    "limited",
+                    charge_limit::is_limited(charge_limit, properties.percentage, on_battery),
+                );
+
+                icons::set_state_classes(&widget, state);
+                icon.set_properties(properties);
+
+                label.set_markup(format.as_ref());
+            }
+        });
+
+        let rx = context.subscribe();
+        let popup = self
+            .into_popup(context.controller_tx.clone(), rx, context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        tx: mpsc::Sender<Self::ReceiveMessage>,
+        rx: broadcast::Receiver<Self::SendMessage>,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let duration_style = self.duration_style;
+
+        let container = gtk::Box::builder()
+            .orientation(Orientation::Vertical)
+            .build();
+
+        let label = Label::new(None);
+        label.add_class("upower-details");
+        container.add(&label);
+
+        // only shown for more than one battery,
+        // as otherwise it matches the display device
+        let batteries = gtk::Box::new(Orientation::Vertical, 0);
+        batteries.add_class("batteries");
+        container.add(&batteries);
+
+        let charge_limit_button = Button::new();
+        charge_limit_button.add_class("charge-limit");
+        container.add(&charge_limit_button);
+
+        let can_cycle = self
+            .charge_limit
+            .as_ref()
+            .is_some_and(ChargeLimitConfig::can_cycle);
+
+        charge_limit_button.set_sensitive(can_cycle);
+        charge_limit_button.connect_clicked(move |_| {
+            try_send!(tx, UpowerCommand::CycleChargeLimit);
+        });
+
+        glib_recv!(rx, event => {
+            match event {
+                UpowerEvent::Properties(properties) => {
+                    let state = properties.state;
+                    let format = match state {
+                        BatteryState::Charging | BatteryState::PendingCharge => {
+                            let ttf = properties.time_to_full;
+                            if ttf > 0 {
+                                format!("Full in {}", time_to_string(ttf, duration_style))
+                            } else {
+                                String::new()
+                            }
+                        }
+                        BatteryState::Discharging | BatteryState::PendingDischarge => {
+                            let tte = properties.time_to_empty;
+                            if tte > 0 {
+                                format!("Empty in {}", time_to_string(tte, duration_style))
+                            } else {
+                                String::new()
+                            }
+                        }
+                        _ => String::new(),
+                    };
+
+                    label.set_markup(&format);
+                }
+                UpowerEvent::ChargeLimit(limit) => {
+                    if let Some(limit) = limit {
+                        charge_limit_button.set_label(
+                            &tr("upower.charge_limit").replace("{limit}", &limit.to_string()),
+                        );
+                    }
+
+                    charge_limit_button.set_visible(limit.is_some());
+                }
+                UpowerEvent::Batteries(list) => {
+                    for child in batteries.children() {
+                        batteries.remove(&child);
+                    }
+
+                    for battery in &list {
+                        let label = Label::new(Some(&format!(
+                            "{}: {}%",
+                            battery.name,
+                            battery.percentage.round()
+                        )));
+                        label.set_halign(gtk::Align::Start);
+                        label.add_class("battery");
+                        batteries.add(&label);
+                    }
+
+                    batteries.show_all();
+                    batteries.set_visible(list.len() > 1);
+                }
+            }
+        });
+
+        container.show_all();
+
+        // only shown once a limit has been read
+        charge_limit_button.hide();
+        batteries.hide();
+
+        Some(container)
+    }
+}
+
+/// Gets the time until the device is empty or full,
+/// depending on whether it is charging.
+///
+/// This is empty while the estimate is unknown,
+/// and when the device is neither charging nor discharging.
+fn time_remaining(properties: &UpowerProperties, style: DurationStyle) -> String {
+    match properties.state {
+        BatteryState::Charging | BatteryState::PendingCharge => {
+            time_to_string(properties.time_to_full, style)
+        }
+        BatteryState::Discharging | BatteryState::PendingDischarge => {
+            time_to_string(properties.time_to_empty, style)
+        }
+        _ => String::new(),
+    }
+}
+
+/// Formats a time reported by UPower,
+/// which uses zero when the time is unknown.
+fn time_to_string(seconds: i64, style: DurationStyle) -> String {
+    if seconds == 0 {
+        String::new()
+    } else {
+        format_duration(seconds, style)
+    }
+}
+
+const fn u32_to_battery_state(number: u32) -> Result<BatteryState, u32> {
+    if number == (BatteryState::Unknown as u32) {
+        Ok(BatteryState::Unknown)
+    } else if number == (BatteryState::Charging as u32) {
+        Ok(BatteryState::Charging)
+    } else if number == (BatteryState::Discharging as u32) {
+        Ok(BatteryState::Discharging)
+    } else if number == (BatteryState::Empty as u32) {
+        Ok(BatteryState::Empty)
+    } else if number == (BatteryState::FullyCharged as u32) {
+        Ok(BatteryState::FullyCharged)
+    } else if number == (BatteryState::PendingCharge as u32) {
+        Ok(BatteryState::PendingCharge)
+    } else if number == (BatteryState::PendingDischarge as u32) {
+        Ok(BatteryState::PendingDischarge)
+    } else {
+        Err(number)
+    }
+}
+
+/// Gets the state as passed to the `on_state_change` script.
+const fn battery_state_to_arg(state: BatteryState) -> &'static str {
+    match state {
+        BatteryState::Unknown => "unknown",
+        BatteryState::Charging => "charging",
+        BatteryState::Discharging => "discharging",
+        BatteryState::Empty => "empty",
+        BatteryState::FullyCharged => "fully_charged",
+        BatteryState::PendingCharge => "pending_charge",
+        BatteryState::PendingDischarge => "pending_discharge",
+    }
+}
+
+fn battery_state_to_string(state: BatteryState) -> &'static str {
+    match state {
+        BatteryState::Unknown => "Unknown",
+        BatteryState::Charging => "Charging",
+        BatteryState::Discharging => "Discharging",
+        BatteryState::Empty => "Empty",
+        BatteryState::FullyCharged => "Fully charged",
+        BatteryState::PendingCharge => "Pending charge",
+        BatteryState::PendingDischarge => "Pending discharge",
+    }
+}
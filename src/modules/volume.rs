@@ -1,17 +1,30 @@
-use crate::clients::volume::{self, Event};
+use crate::clients::volume::{self, Card, Event, Sink, SinkInput};
 use crate::config::CommonConfig;
 use crate::gtk_helpers::IronbarGtkExt;
+use crate::i18n::tr;
+use crate::image::ImageProvider;
 use crate::modules::{
-    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent,
+    PopupButton, WidgetContext,
 };
-use crate::{glib_recv, lock, module_impl, send_async, spawn, try_send};
+use crate::script::{Script, ScriptEnv, ScriptInput};
+use crate::scroll::{ScrollStep, Scroller};
+use crate::{glib_recv, lock, module_impl, send_async, try_send};
+use color_eyre::{Report, Result};
 use glib::Propagation;
 use gtk::pango::EllipsizeMode;
 use gtk::prelude::*;
-use gtk::{Button, CellRendererText, ComboBoxText, Label, Orientation, Scale, ToggleButton};
+use gtk::{
+    Button, CellRendererText, ComboBoxText, IconTheme, Image, Label, Orientation, Scale,
+    ToggleButton,
+};
 use serde::Deserialize;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
+use tracing::warn;
 
 #[derive(Debug, Clone, Deserialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -23,6 +36,19 @@ pub struct VolumeModule {
     #[serde(default = "default_format")]
     format: String,
 
+    /// The type of device to show and control.
+    /// Use `source` for a microphone indicator.
+    ///
+    /// In source mode, clicking the widget toggles mute,
+    /// scrolling changes the capture volume,
+    /// and right-clicking opens the popup.
+    ///
+    /// **Valid options**: `sink`, `source`
+    /// <br>
+    /// **Default**: `sink`
+    #[serde(default)]
+    mode: Mode,
+
     /// Maximum value to allow volume sliders to reach.
     /// Pulse supports values > 100 but this may result in distortion.
     ///
@@ -30,6 +56,46 @@ pub struct VolumeModule {
     #[serde(default = "default_max_volume")]
     max_volume: f64,
 
+    /// Whether to move playing streams to the new default device
+    /// when it is changed from the popup.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    move_streams: bool,
+
+    /// Whether to show a profile selector for each sound card in the popup.
+    /// Cards with only one usable profile are not shown.
+    ///
+    /// **Default**: `true`
+    #[serde(default = "crate::config::default_true")]
+    show_profiles: bool,
+
+    /// The size to render application icons at in the popup, in pixels.
+    ///
+    /// **Default**: `16`
+    #[serde(default = "default_icon_size")]
+    icon_size: i32,
+
+    /// A sound to play when the output volume is changed from the popup or an action,
+    /// so that the new level can be heard.
+    /// This is not played in source mode.
+    /// This can be a path to a sound file,
+    /// or a [freedesktop sound name](https://specifications.freedesktop.org/sound-naming-spec/latest/)
+    /// such as `audio-volume-change`.
+    ///
+    /// Muting and unmuting does not play the sound.
+    ///
+    /// **Default**: `null`
+    feedback_sound: Option<String>,
+
+    /// A [script](scripts) to run to play the feedback sound.
+    /// The sound is passed as an argument, and can be accessed using `$0`.
+    ///
+    /// If unset, `canberra-gtk-play` is used.
+    ///
+    /// **Default**: `null`
+    feedback_command: Option<ScriptInput>,
+
     /// Volume state icons.
     ///
     /// See [icons](#icons).
@@ -41,6 +107,39 @@ pub struct VolumeModule {
     pub common: Option<CommonConfig>,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Mode {
+    /// Output devices, and the applications playing to them.
+    #[default]
+    Sink,
+    /// Input devices, and the applications recording from them.
+    Source,
+}
+
+impl Mode {
+    /// Checks whether the event is for a device or stream of this type.
+    /// Card events are always for both.
+    fn is_relevant(self, event: &Event) -> bool {
+        match event {
+            Event::AddSink(_)
+            | Event::UpdateSink(_)
+            | Event::RemoveSink(_)
+            | Event::AddInput(_)
+            | Event::UpdateInput(_)
+            | Event::RemoveInput(_) => self == Self::Sink,
+            Event::AddSource(_)
+            | Event::UpdateSource(_)
+            | Event::RemoveSource(_)
+            | Event::AddOutput(_)
+            | Event::UpdateOutput(_)
+            | Event::RemoveOutput(_) => self == Self::Source,
+            Event::AddCard(_) | Event::UpdateCard(_) | Event::RemoveCard(_) => true,
+        }
+    }
+}
+
 fn default_format() -> String {
     String::from("{icon} {percentage}%")
 }
@@ -94,6 +193,10 @@ impl Default for Icons {
     }
 }
 
+const fn default_icon_size() -> i32 {
+    16
+}
+
 const fn default_max_volume() -> f64 {
     100.0
 }
@@ -114,14 +217,116 @@ fn default_icon_muted() -> String {
     String::from("󰝟")
 }
 
+/// A change requested from the widget, popup or an action.
+///
+/// In source mode, the sink and input variants
+/// act on sources and source outputs instead.
 #[derive(Debug, Clone)]
 pub enum Update {
     SinkChange(String),
+    /// Switches the card to the profile,
+    /// and sets the sink it creates as the default.
+    SinkChangeWithProfile {
+        card: u32,
+        profile: String,
+    },
+    CardProfile(u32, String),
     SinkVolume(String, f64),
     SinkMute(String, bool),
 
     InputVolume(u32, f64),
     InputMute(u32, bool),
+
+    /// Changes the default sink volume by a relative amount.
+    /// Sent by the `up` and `down` actions.
+    DefaultVolumeStep(f64),
+    /// Sets the default sink volume.
+    /// Sent by the `set` action.
+    DefaultVolume(f64),
+    /// Toggles the default sink mute state.
+    /// Sent by the `mute-toggle` action.
+    DefaultMuteToggle,
+}
+
+/// The minimum time between feedback sounds,
+/// so that scrolling a slider does not queue up a sound for each step.
+const FEEDBACK_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Plays the configured feedback sound when the volume changes.
+#[derive(Debug)]
+struct Feedback {
+    sound: String,
+    script: Script,
+    last_played: Option<Instant>,
+}
+
+impl Feedback {
+    fn new(sound: String, command: Option<ScriptInput>, script_env: &ScriptEnv) -> Self {
+        let script = command
+            .map_or_else(
+                || Script::from(default_feedback_command(&sound)),
+                Script::new_polling,
+            )
+            .with_env(script_env);
+
+        Self {
+            sound,
+            script,
+            last_played: None,
+        }
+    }
+
+    /// Plays the sound, unless it was played too recently.
+    fn play(&mut self) {
+        let now = Instant::now();
+
+        if is_feedback_due(self.last_played, now) {
+            self.last_played = Some(now);
+            self.script.run_as_oneshot(Some(&[self.sound.clone()]));
+        }
+    }
+}
+
+/// Gets the command to play the sound using libcanberra,
+/// which takes files and sound names using different flags.
+fn default_feedback_command(sound: &str) -> &'static str {
+    if sound.contains('/') {
+        "canberra-gtk-play -f \"$0\""
+    } else {
+        "canberra-gtk-play -i \"$0\""
+    }
+}
+
+fn is_feedback_due(last_played: Option<Instant>, now: Instant) -> bool {
+    last_played.is_none_or(|last| now.duration_since(last) >= FEEDBACK_INTERVAL)
+}
+
+/// The volume step used by the `up` and `down` actions
+/// when none is provided.
+const DEFAULT_ACTION_STEP: f64 = 5.0;
+
+/// Gets the volume change for scrolling or swiping over the widget.
+const fn volume_step(step: ScrollStep) -> f64 {
+    match step {
+        ScrollStep::Up => DEFAULT_ACTION_STEP,
+        ScrollStep::Down => -DEFAULT_ACTION_STEP,
+    }
+}
+
+/// Parses the first action argument as a volume percentage,
+/// falling back to `default` if it is not present.
+///
+/// Negative and non-finite values are rejected.
+fn parse_volume_arg(args: &[String], default: Option<f64>) -> Result<f64> {
+    match (args.first(), default) {
+        (Some(arg), _) => arg
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .ok_or_else(|| Report::msg(format!("Invalid volume value '{arg}'"))),
+        (None, Some(default)) => Ok(default),
+        (None, None) => Err(Report::msg("Missing volume value")),
+    }
 }
 
 impl Module<Button> for VolumeModule {
@@ -132,61 +337,144 @@ impl Module<Button> for VolumeModule {
 
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> color_eyre::Result<()>
     where
         <Self as Module<Button>>::SendMessage: Clone,
     {
-        let client = context.client::<volume::Client>();
+        let client = context.client::<dyn volume::VolumeClient>();
+        let mode = self.mode;
+        let max_volume = self.max_volume;
+        let move_streams = self.move_streams;
+
+        let mut feedback = self
+            .feedback_sound
+            .clone()
+            .filter(|_| mode == Mode::Sink)
+            .map(|sound| Feedback::new(sound, self.feedback_command.clone(), &context.script_env));
 
         {
             let client = client.clone();
             let mut rx = client.subscribe();
             let tx = context.tx.clone();
 
-            spawn(async move {
+            context.spawn(async move {
                 // init
-                let sinks = {
-                    let sinks = client.sinks();
-                    let sinks = lock!(sinks);
-                    sinks.iter().cloned().collect::<Vec<_>>()
+                let (devices, streams) = match mode {
+                    Mode::Sink => (client.sinks(), client.sink_inputs()),
+                    Mode::Source => (client.sources(), client.source_outputs()),
                 };
 
-                let inputs = {
-                    let inputs = client.sink_inputs();
-                    let inputs = lock!(inputs);
-                    inputs.iter().cloned().collect::<Vec<_>>()
+                let devices = lock!(devices).iter().cloned().collect::<Vec<_>>();
+                let streams = lock!(streams).iter().cloned().collect::<Vec<_>>();
+
+                let cards = {
+                    let cards = client.cards();
+                    let cards = lock!(cards);
+                    cards.iter().cloned().collect::<Vec<_>>()
                 };
 
-                for sink in sinks {
-                    send_async!(tx, ModuleUpdateEvent::Update(Event::AddSink(sink)));
+                for card in cards {
+                    send_async!(tx, ModuleUpdateEvent::Update(Event::AddCard(card)));
                 }
 
-                for input in inputs {
-                    send_async!(
-                        tx,
-                        ModuleUpdateEvent::Update(Event::AddInput(input.clone()))
-                    );
+                for device in devices {
+                    let event = match mode {
+                        Mode::Sink => Event::AddSink(device),
+                        Mode::Source => Event::AddSource(device),
+                    };
+
+                    send_async!(tx, ModuleUpdateEvent::Update(event));
+                }
+
+                for stream in streams {
+                    let event = match mode {
+                        Mode::Sink => Event::AddInput(stream),
+                        Mode::Source => Event::AddOutput(stream),
+                    };
+
+                    send_async!(tx, ModuleUpdateEvent::Update(event));
                 }
 
                 // recv loop
                 while let Ok(event) = rx.recv().await {
-                    send_async!(tx, ModuleUpdateEvent::Update(event));
+                    if mode.is_relevant(&event) {
+                        send_async!(tx, ModuleUpdateEvent::Update(event));
+                    }
                 }
             });
         }
 
         // ui events
-        spawn(async move {
+        context.spawn(async move {
             while let Some(update) = rx.recv().await {
                 match update {
-                    Update::SinkChange(name) => client.set_default_sink(&name),
-                    Update::SinkVolume(name, volume) => client.set_sink_volume(&name, volume),
-                    Update::SinkMute(name, muted) => client.set_sink_muted(&name, muted),
-                    Update::InputVolume(index, volume) => client.set_input_volume(index, volume),
-                    Update::InputMute(index, muted) => client.set_input_muted(index, muted),
+                    Update::SinkChange(name) => match mode {
+                        Mode::Sink => client.set_default_sink(&name, move_streams),
+                        Mode::Source => client.set_default_source(&name),
+                    },
+                    Update::SinkChangeWithProfile { card, profile } => {
+                        client.set_default_sink_with_profile(card, &profile, move_streams);
+                    }
+                    Update::CardProfile(card, profile) => client.set_card_profile(card, &profile),
+                    Update::SinkVolume(name, volume) => {
+                        set_device_volume(&client, mode, &name, volume);
+                        if let Some(feedback) = &mut feedback {
+                            feedback.play();
+                        }
+                    }
+                    Update::SinkMute(name, muted) => set_device_muted(&client, mode, &name, muted),
+                    Update::InputVolume(index, volume) => match mode {
+                        Mode::Sink => client.set_input_volume(index, volume),
+                        Mode::Source => client.set_output_volume(index, volume),
+                    },
+                    Update::InputMute(index, muted) => match mode {
+                        Mode::Sink => client.set_input_muted(index, muted),
+                        Mode::Source => client.set_output_muted(index, muted),
+                    },
+                    Update::DefaultVolumeStep(_)
+                    | Update::DefaultVolume(_)
+                    | Update::DefaultMuteToggle => {
+                        let sink = {
+                            let sinks = match mode {
+                                Mode::Sink => client.sinks(),
+                                Mode::Source => client.sources(),
+                            };
+                            let sinks = lock!(sinks);
+                            sinks.iter().find(|sink| sink.active).cloned()
+                        };
+
+                        let Some(sink) = sink else {
+                            continue;
+                        };
+
+                        match update {
+                            Update::DefaultVolumeStep(step) => {
+                                set_device_volume(
+                                    &client,
+                                    mode,
+                                    &sink.name,
+                                    (sink.volume + step).clamp(0.0, max_volume),
+                                );
+                                if let Some(feedback) = &mut feedback {
+                                    feedback.play();
+                                }
+                            }
+                            Update::DefaultVolume(volume) => {
+                                set_device_volume(
+                                    &client,
+                                    mode,
+                                    &sink.name,
+                                    volume.clamp(0.0, max_volume),
+                                );
+                                if let Some(feedback) = &mut feedback {
+                                    feedback.play();
+                                }
+                            }
+                            _ => set_device_muted(&client, mode, &sink.name, !sink.muted),
+                        }
+                    }
                 }
             }
         });
@@ -194,6 +482,19 @@ impl Module<Button> for VolumeModule {
         Ok(())
     }
 
+    fn parse_action(action: &str, args: &[String]) -> Result<Self::ReceiveMessage> {
+        match action {
+            "up" => {
+                parse_volume_arg(args, Some(DEFAULT_ACTION_STEP)).map(Update::DefaultVolumeStep)
+            }
+            "down" => parse_volume_arg(args, Some(DEFAULT_ACTION_STEP))
+                .map(|step| Update::DefaultVolumeStep(-step)),
+            "set" => parse_volume_arg(args, None).map(Update::DefaultVolume),
+            "mute-toggle" => Ok(Update::DefaultMuteToggle),
+            _ => Err(Report::msg(format!("Unknown action '{action}'"))),
+        }
+    }
+
     fn into_widget(
         self,
         context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
@@ -204,12 +505,53 @@ impl Module<Button> for VolumeModule {
     {
         let button = Button::new();
 
-        {
-            let tx = context.tx.clone();
+        match self.mode {
+            Mode::Sink => {
+                {
+                    let tx = context.tx.clone();
 
-            button.connect_clicked(move |button| {
-                try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
-            });
+                    button.connect_clicked(move |button| {
+                        try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                    });
+                }
+
+                if info.touch.swipe {
+                    let tx = context.controller_tx.clone();
+
+                    Scroller::new(info.scroll).connect_swipe(&button, move |_, step| {
+                        try_send!(tx, Update::DefaultVolumeStep(volume_step(step)));
+                    });
+                }
+            }
+            Mode::Source => {
+                {
+                    let tx = context.controller_tx.clone();
+                    button.connect_clicked(move |_| try_send!(tx, Update::DefaultMuteToggle));
+                }
+
+                {
+                    let tx = context.tx.clone();
+
+                    button.connect_button_press_event(move |button, event| {
+                        if event.button() == 3 {
+                            try_send!(tx, ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                            Propagation::Stop
+                        } else {
+                            Propagation::Proceed
+                        }
+                    });
+                }
+
+                {
+                    let tx = context.controller_tx.clone();
+
+                    let scroller = Scroller::new(info.scroll).with_swipe(info.touch.swipe);
+
+                    scroller.connect(&button, move |_, step| {
+                        try_send!(tx, Update::DefaultVolumeStep(volume_step(step)));
+                    });
+                }
+            }
         }
 
         {
@@ -221,13 +563,17 @@ impl Module<Button> for VolumeModule {
 
             glib_recv!(rx, event => {
                 match event {
-                    Event::AddSink(sink) | Event::UpdateSink(sink) if sink.active => {
+                    Event::AddSink(sink)
+                    | Event::UpdateSink(sink)
+                    | Event::AddSource(sink)
+                    | Event::UpdateSource(sink) if sink.active => {
                         let label = format
                             .replace("{icon}", if sink.muted { &icons.muted } else { icons.volume_icon(sink.volume) })
                             .replace("{percentage}", &sink.volume.to_string())
                             .replace("{name}", &sink.description);
 
                         button.set_label(&label);
+                        button.set_class("muted", sink.muted);
                     },
                     _ => {}
                 }
@@ -251,11 +597,12 @@ impl Module<Button> for VolumeModule {
         tx: mpsc::Sender<Self::ReceiveMessage>,
         rx: tokio::sync::broadcast::Receiver<Self::SendMessage>,
         _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _info: &ModuleInfo,
+        info: &ModuleInfo,
     ) -> Option<gtk::Box>
     where
         Self: Sized,
     {
+        let icon_size = info.scaled(self.icon_size);
         let container = gtk::Box::new(Orientation::Horizontal, 10);
 
         let sink_container = gtk::Box::new(Orientation::Vertical, 5);
@@ -281,11 +628,31 @@ impl Module<Button> for VolumeModule {
         renderer.set_width_chars(20);
         renderer.set_ellipsize(EllipsizeMode::End);
 
+        // set while the selectors are updated to match the server,
+        // so that their changes are not sent back to it.
+        let updating = Rc::new(Cell::new(false));
+        let entries = Rc::new(RefCell::new(Vec::<DeviceEntry>::new()));
+
         {
             let tx = tx.clone();
+            let updating = updating.clone();
+            let entries = entries.clone();
+
             sink_selector.connect_changed(move |selector| {
-                if let Some(name) = selector.active_id() {
-                    try_send!(tx, Update::SinkChange(name.into()));
+                if updating.get() {
+                    return;
+                }
+
+                let entry = selector
+                    .active()
+                    .and_then(|pos| entries.borrow().get(pos as usize).cloned());
+
+                match entry {
+                    Some(DeviceEntry::Sink(name)) => try_send!(tx, Update::SinkChange(name)),
+                    Some(DeviceEntry::Port { card, profile }) => {
+                        try_send!(tx, Update::SinkChangeWithProfile { card, profile });
+                    }
+                    None => {}
                 }
             });
         }
@@ -335,60 +702,127 @@ impl Module<Button> for VolumeModule {
             });
         }
 
+        let profiles_container = gtk::Box::new(Orientation::Vertical, 5);
+        profiles_container.add_class("profiles-box");
+        sink_container.add(&profiles_container);
+
+        let bluetooth_ui = BluetoothUi::new(&tx, &updating);
+        sink_container.add(&bluetooth_ui.container);
+
         container.show_all();
+        bluetooth_ui.container.hide();
 
-        let mut inputs = HashMap::new();
+        let mut inputs = HashMap::<u32, InputUi>::new();
+        let icon_theme = info.icon_theme.clone();
+        let mut card_uis = HashMap::<u32, CardUi>::new();
 
         {
             let input_container = input_container.clone();
 
             let mut sinks = vec![];
+            let mut cards = vec![];
+            let mut default_sink = None;
+
+            let icons = self.icons.clone();
+            let update_sink_ui = move |info: &Sink| {
+                slider.set_value(info.volume);
+
+                btn_mute.set_active(info.muted);
+                btn_mute.set_label(if info.muted {
+                    &icons.muted
+                } else {
+                    icons.volume_icon(info.volume)
+                });
+            };
 
             glib_recv!(rx, event => {
-                match event {
-                    Event::AddSink(info) => {
-                        sink_selector.append(Some(&info.name), &info.description);
+                let devices_changed = !matches!(
+                    event,
+                    Event::AddInput(_) | Event::UpdateInput(_) | Event::RemoveInput(_)
+                        | Event::AddOutput(_) | Event::UpdateOutput(_) | Event::RemoveOutput(_)
+                );
 
+                match event {
+                    Event::AddSink(info) | Event::AddSource(info) => {
                         if info.active {
-                            sink_selector.set_active(Some(sinks.len() as u32));
-                            slider.set_value(info.volume);
-
-                            btn_mute.set_active(info.muted);
-                            btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
+                            default_sink = Some(info.name.clone());
+                            update_sink_ui(&info);
                         }
 
                         sinks.push(info);
+                        update_device_selector(&sink_selector, &entries, &updating, &sinks, &cards, default_sink.as_deref());
                     }
-                    Event::UpdateSink(info) => {
-                        if info.active {
-                            if let Some(pos) = sinks.iter().position(|s| s.name == info.name) {
-                                sink_selector.set_active(Some(pos as u32));
-                                slider.set_value(info.volume);
-
-                                btn_mute.set_active(info.muted);
-                                btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
+                    Event::UpdateSink(info) | Event::UpdateSource(info) => {
+                        if let Some(pos) = sinks.iter().position(|s| s.name == info.name) {
+                            if info.active {
+                                default_sink = Some(info.name.clone());
+                                update_sink_ui(&info);
+
+                                updating.set(true);
+                                sink_selector.set_active_id(Some(&info.name));
+                                updating.set(false);
                             }
+
+                            sinks[pos] = info;
                         }
                     }
-                    Event::RemoveSink(name) => {
+                    Event::RemoveSink(name) | Event::RemoveSource(name) => {
                         if let Some(pos) = sinks.iter().position(|s| s.name == name) {
-                            ComboBoxTextExt::remove(&sink_selector, pos as i32);
                             sinks.remove(pos);
+                            update_device_selector(&sink_selector, &entries, &updating, &sinks, &cards, default_sink.as_deref());
                         }
                     }
 
-                    Event::AddInput(info) => {
+                    Event::AddCard(card) | Event::UpdateCard(card) => {
+                        if self.show_profiles {
+                            let ui = card_uis.entry(card.index).or_insert_with(|| {
+                                let ui = CardUi::new(card.index, &tx, &updating);
+                                profiles_container.add(&ui.container);
+                                ui
+                            });
+
+                            ui.update(&card, &updating);
+                        }
+
+                        // only output ports are offered in the device selector
+                        if self.mode == Mode::Sink {
+                            match cards.iter().position(|c: &Card| c.index == card.index) {
+                                Some(pos) => cards[pos] = card,
+                                None => cards.push(card),
+                            }
+                        }
+
+                        update_device_selector(&sink_selector, &entries, &updating, &sinks, &cards, default_sink.as_deref());
+                    }
+                    Event::RemoveCard(index) => {
+                        if let Some(ui) = card_uis.remove(&index) {
+                            profiles_container.remove(&ui.container);
+                        }
+
+                        cards.retain(|card| card.index != index);
+                        update_device_selector(&sink_selector, &entries, &updating, &sinks, &cards, default_sink.as_deref());
+                    }
+
+                    Event::AddInput(info) | Event::AddOutput(info) => {
                         let index = info.index;
 
                         let item_container = gtk::Box::new(Orientation::Vertical, 0);
                         item_container.add_class("app-box");
 
-                        let label = Label::new(Some(&info.name));
+                        let header = gtk::Box::new(Orientation::Horizontal, 5);
+                        header.add_class("header");
+
+                        let icon = Image::new();
+                        icon.add_class("icon");
+
+                        let label = Label::new(None);
                         label.add_class("title");
 
-                        let slider = Scale::builder().sensitive(info.can_set_volume).build();
+                        header.add(&icon);
+                        header.add(&label);
+
+                        let slider = Scale::builder().build();
                         slider.set_range(0.0, self.max_volume);
-                        slider.set_value(info.volume);
                         slider.add_class("slider");
 
                         {
@@ -405,45 +839,61 @@ impl Module<Button> for VolumeModule {
                         let btn_mute = ToggleButton::new();
                         btn_mute.add_class("btn-mute");
 
-                        btn_mute.set_active(info.muted);
-                        btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
+                        let updating = Rc::new(Cell::new(false));
 
                         {
                             let tx = tx.clone();
+                            let updating = updating.clone();
+
                             btn_mute.connect_toggled(move |btn| {
+                                if updating.get() {
+                                    return;
+                                }
+
                                 let muted = btn.is_active();
                                 try_send!(tx, Update::InputMute(index, muted));
                             });
                         }
 
-                        item_container.add(&label);
+                        item_container.add(&header);
                         item_container.add(&slider);
                         item_container.add(&btn_mute);
                         item_container.show_all();
 
                         input_container.add(&item_container);
 
-                        inputs.insert(info.index, InputUi {
+                        let mut ui = InputUi {
                             container: item_container,
+                            icon,
                             label,
                             slider,
-                            btn_mute
-                        });
+                            btn_mute,
+                            updating,
+                            app_icons: None,
+                        };
+
+                        ui.update(&info, &self.icons, &icon_theme, icon_size);
+                        inputs.insert(index, ui);
                     }
-                    Event::UpdateInput(info) => {
-                        if let Some(ui) = inputs.get(&info.index) {
-                            ui.label.set_label(&info.name);
-                            ui.slider.set_value(info.volume);
-                            ui.slider.set_sensitive(info.can_set_volume);
-                            ui.btn_mute.set_label(if info.muted { &self.icons.muted } else { self.icons.volume_icon(info.volume) });
+                    Event::UpdateInput(info) | Event::UpdateOutput(info) => {
+                        if let Some(ui) = inputs.get_mut(&info.index) {
+                            ui.update(&info, &self.icons, &icon_theme, icon_size);
                         }
                     }
-                    Event::RemoveInput(index) => {
+                    Event::RemoveInput(index) | Event::RemoveOutput(index) => {
                         if let Some(ui) = inputs.remove(&index) {
                             input_container.remove(&ui.container);
                         }
                     }
                 }
+
+                if devices_changed {
+                    let default = sinks
+                        .iter()
+                        .find(|sink| default_sink.as_ref() == Some(&sink.name));
+
+                    bluetooth_ui.update(default, &cards, &updating);
+                }
             });
         }
 
@@ -451,9 +901,383 @@ impl Module<Button> for VolumeModule {
     }
 }
 
+/// Sets the volume of the sink or source, depending on the mode.
+fn set_device_volume(client: &dyn volume::VolumeClient, mode: Mode, name: &str, volume: f64) {
+    match mode {
+        Mode::Sink => client.set_sink_volume(name, volume),
+        Mode::Source => client.set_source_volume(name, volume),
+    }
+}
+
+/// Mutes or unmutes the sink or source, depending on the mode.
+fn set_device_muted(client: &dyn volume::VolumeClient, mode: Mode, name: &str, muted: bool) {
+    match mode {
+        Mode::Sink => client.set_sink_muted(name, muted),
+        Mode::Source => client.set_source_muted(name, muted),
+    }
+}
+
+/// An option in the device selector.
+#[derive(Debug, Clone)]
+enum DeviceEntry {
+    /// An existing sink.
+    Sink(String),
+    /// A card output port which needs a profile change
+    /// before its sink exists.
+    Port { card: u32, profile: String },
+}
+
+/// Replaces the device selector options with the sinks,
+/// and any card outputs which are not available with the active profiles.
+fn update_device_selector(
+    selector: &ComboBoxText,
+    entries: &RefCell<Vec<DeviceEntry>>,
+    updating: &Cell<bool>,
+    sinks: &[Sink],
+    cards: &[Card],
+    default_sink: Option<&str>,
+) {
+    let mut new_entries = vec![];
+
+    updating.set(true);
+    selector.remove_all();
+
+    for sink in sinks {
+        selector.append(Some(&sink.name), &sink.description);
+        new_entries.push(DeviceEntry::Sink(sink.name.clone()));
+    }
+
+    for card in cards {
+        for port in card.ports.iter().filter(|port| port.available) {
+            let has_sink = sinks
+                .iter()
+                .any(|sink| sink.card == Some(card.index) && sink.ports.contains(&port.name));
+
+            if has_sink {
+                continue;
+            }
+
+            if let Some(profile) = card.profile_for_port(port) {
+                selector.append(
+                    None,
+                    &format!("{} ({})", port.description, card.description),
+                );
+
+                new_entries.push(DeviceEntry::Port {
+                    card: card.index,
+                    profile: profile.name.clone(),
+                });
+            }
+        }
+    }
+
+    entries.replace(new_entries);
+
+    if let Some(default_sink) = default_sink {
+        selector.set_active_id(Some(default_sink));
+    }
+
+    updating.set(false);
+}
+
+/// The profile selector for a single card.
+struct CardUi {
+    container: gtk::Box,
+    label: Label,
+    selector: ComboBoxText,
+}
+
+impl CardUi {
+    fn new(index: u32, tx: &mpsc::Sender<Update>, updating: &Rc<Cell<bool>>) -> Self {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+        container.add_class("card-box");
+
+        let label = Label::new(None);
+        label.add_class("title");
+        label.set_ellipsize(EllipsizeMode::End);
+        label.set_max_width_chars(20);
+
+        let selector = ComboBoxText::new();
+        selector.add_class("profile-selector");
+
+        {
+            let tx = tx.clone();
+            let updating = updating.clone();
+
+            selector.connect_changed(move |selector| {
+                if updating.get() {
+                    return;
+                }
+
+                if let Some(profile) = selector.active_id() {
+                    try_send!(tx, Update::CardProfile(index, profile.into()));
+                }
+            });
+        }
+
+        container.add(&label);
+        container.add(&selector);
+
+        Self {
+            container,
+            label,
+            selector,
+        }
+    }
+
+    fn update(&self, card: &Card, updating: &Cell<bool>) {
+        self.label.set_label(&card.description);
+
+        let profiles = card
+            .profiles
+            .iter()
+            .filter(|profile| profile.available)
+            .collect::<Vec<_>>();
+
+        updating.set(true);
+        self.selector.remove_all();
+
+        for profile in &profiles {
+            self.selector
+                .append(Some(&profile.name), &profile.description);
+        }
+
+        self.selector.set_active_id(card.active_profile.as_deref());
+        updating.set(false);
+
+        self.container.set_visible(profiles.len() > 1);
+        if profiles.len() > 1 {
+            self.container.show_all();
+        }
+    }
+}
+
+/// The codec and battery of the default sink,
+/// shown when it is a Bluetooth device.
+struct BluetoothUi {
+    container: gtk::Box,
+    codec: Label,
+    codec_selector: ComboBoxText,
+    battery: Label,
+    /// The index of the card the codec selector switches the profile of.
+    card: Rc<Cell<Option<u32>>>,
+}
+
+impl BluetoothUi {
+    fn new(tx: &mpsc::Sender<Update>, updating: &Rc<Cell<bool>>) -> Self {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+        container.add_class("bluetooth-box");
+
+        let codec = Label::new(None);
+        codec.add_class("codec");
+        codec.set_tooltip_text(Some(tr("volume.codec")));
+
+        let codec_selector = ComboBoxText::new();
+        codec_selector.add_class("codec-selector");
+
+        let battery = Label::new(None);
+        battery.add_class("battery");
+        battery.set_tooltip_text(Some(tr("volume.battery")));
+
+        let card = Rc::new(Cell::new(None));
+
+        {
+            let tx = tx.clone();
+            let updating = updating.clone();
+            let card = card.clone();
+
+            codec_selector.connect_changed(move |selector| {
+                if updating.get() {
+                    return;
+                }
+
+                if let (Some(card), Some(profile)) = (card.get(), selector.active_id()) {
+                    try_send!(tx, Update::CardProfile(card, profile.into()));
+                }
+            });
+        }
+
+        container.add(&codec);
+        container.add(&codec_selector);
+        container.add(&battery);
+
+        Self {
+            container,
+            codec,
+            codec_selector,
+            battery,
+            card,
+        }
+    }
+
+    /// Shows the details of the sink if it is a Bluetooth device,
+    /// or hides them otherwise.
+    ///
+    /// The codec selector is shown in place of the codec
+    /// when the sink's card has a profile for more than one codec.
+    fn update(&self, sink: Option<&Sink>, cards: &[Card], updating: &Cell<bool>) {
+        let Some((sink, bluetooth)) =
+            sink.and_then(|sink| sink.bluetooth.as_ref().map(|bluetooth| (sink, bluetooth)))
+        else {
+            self.container.hide();
+            return;
+        };
+
+        let card = cards.iter().find(|card| Some(card.index) == sink.card);
+        let profiles = card.map(Card::codec_profiles).unwrap_or_default();
+
+        updating.set(true);
+        self.codec_selector.remove_all();
+
+        for profile in &profiles {
+            self.codec_selector
+                .append(Some(&profile.name), &profile.description);
+        }
+
+        self.codec_selector
+            .set_active_id(card.and_then(|card| card.active_profile.as_deref()));
+        updating.set(false);
+
+        self.card.set(card.map(|card| card.index));
+
+        let has_selector = profiles.len() > 1;
+        self.codec_selector.set_visible(has_selector);
+
+        match &bluetooth.codec {
+            Some(codec) if !has_selector => {
+                self.codec.set_label(codec);
+                self.codec.show();
+            }
+            _ => self.codec.hide(),
+        }
+
+        match bluetooth.battery {
+            Some(battery) => {
+                self.battery.set_label(&format!("{battery}%"));
+                self.battery.show();
+            }
+            None => self.battery.hide(),
+        }
+
+        self.container.show();
+    }
+}
+
 struct InputUi {
     container: gtk::Box,
+    icon: Image,
     label: Label,
     slider: Scale,
     btn_mute: ToggleButton,
+    /// Set while the mute button is updated to match the server.
+    updating: Rc<Cell<bool>>,
+    /// The icon names last looked up.
+    app_icons: Option<Vec<String>>,
+}
+
+impl InputUi {
+    fn update(&mut self, info: &SinkInput, icons: &Icons, icon_theme: &IconTheme, icon_size: i32) {
+        // paused streams are hidden rather than removed,
+        // so that their row keeps its place when playback resumes
+        self.container.set_visible(!info.corked);
+
+        self.label
+            .set_label(info.app_name.as_deref().unwrap_or(&info.name));
+        self.label.set_tooltip_text(Some(&info.name));
+
+        self.slider.set_value(info.volume);
+        self.slider.set_sensitive(info.can_set_volume);
+
+        self.updating.set(true);
+        self.btn_mute.set_active(info.muted);
+        self.updating.set(false);
+
+        self.btn_mute.set_label(if info.muted {
+            &icons.muted
+        } else {
+            icons.volume_icon(info.volume)
+        });
+
+        if self.app_icons.as_ref() != Some(&info.app_icons) {
+            self.app_icons = Some(info.app_icons.clone());
+            self.load_icon(&info.app_icons, icon_theme, icon_size);
+        }
+    }
+
+    /// Loads the first of the application's icons found,
+    /// hiding the image if there are none.
+    fn load_icon(&self, names: &[String], icon_theme: &IconTheme, icon_size: i32) {
+        let image = names
+            .iter()
+            .find_map(|name| ImageProvider::parse(name, icon_theme, false, icon_size));
+
+        let loaded = match image {
+            Some(image) => match image.load_into_image(self.icon.clone()) {
+                Ok(()) => true,
+                Err(err) => {
+                    warn!("{err:?}");
+                    false
+                }
+            },
+            None => false,
+        };
+
+        self.icon.set_visible(loaded);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_volume_args() {
+        let parse = |arg: &str| parse_volume_arg(&[arg.to_string()], None).ok();
+
+        assert_eq!(parse("5"), Some(5.0));
+        assert_eq!(parse("0"), Some(0.0));
+        assert_eq!(parse_volume_arg(&[], Some(5.0)).ok(), Some(5.0));
+
+        for arg in ["-5", "NaN", "inf", "loud"] {
+            assert_eq!(parse(arg), None, "{arg}");
+        }
+    }
+
+    #[test]
+    fn rate_limits_feedback() {
+        let start = Instant::now();
+
+        assert!(is_feedback_due(None, start));
+        assert!(!is_feedback_due(Some(start), start));
+        assert!(!is_feedback_due(
+            Some(start),
+            start + Duration::from_millis(100)
+        ));
+        assert!(is_feedback_due(Some(start), start + FEEDBACK_INTERVAL));
+    }
+
+    #[test]
+    fn plays_files_and_sound_names() {
+        assert_eq!(
+            default_feedback_command("/usr/share/sounds/click.oga"),
+            "canberra-gtk-play -f \"$0\""
+        );
+        assert_eq!(
+            default_feedback_command("audio-volume-change"),
+            "canberra-gtk-play -i \"$0\""
+        );
+    }
+
+    #[test]
+    fn forwards_events_for_mode() {
+        let sink = Event::RemoveSink(String::from("alsa_output.pci"));
+        let output = Event::RemoveOutput(3);
+        let card = Event::RemoveCard(0);
+
+        assert!(Mode::Sink.is_relevant(&sink));
+        assert!(!Mode::Sink.is_relevant(&output));
+        assert!(Mode::Source.is_relevant(&output));
+        assert!(!Mode::Source.is_relevant(&sink));
+        assert!(Mode::Sink.is_relevant(&card) && Mode::Source.is_relevant(&card));
+    }
 }
@@ -1,17 +1,22 @@
 use crate::clients::compositor::{Visibility, Workspace, WorkspaceClient, WorkspaceUpdate};
-use crate::config::CommonConfig;
-use crate::gtk_helpers::IronbarGtkExt;
+use crate::config::{ClassList, CommonConfig, ScrollConfig};
+use crate::glob::glob_match;
+use crate::gtk_helpers::{window_drag_target, IronbarGtkExt};
 use crate::image::new_icon_button;
-use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
-use crate::{glib_recv, module_impl, send_async, spawn, try_send, Ironbar};
+use crate::modules::{
+    ControllerContext, Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext,
+};
+use crate::scroll::{ScrollStep, Scroller};
+use crate::{glib_recv, module_impl, send_async, try_send, Ironbar};
 use color_eyre::{Report, Result};
+use gtk::gdk::DragAction;
 use gtk::prelude::*;
-use gtk::{Button, IconTheme};
+use gtk::{Button, DestDefaults, IconTheme};
 use serde::Deserialize;
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use tokio::sync::mpsc::{Receiver, Sender};
-use tracing::{debug, trace, warn};
+use tracing::{debug, error, trace, warn};
 
 #[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -30,6 +35,59 @@ impl Default for SortOrder {
     }
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ShowEmpty {
+    /// Shows all empty workspaces.
+    All,
+    /// Shows only empty workspaces which the compositor keeps open.
+    Persistent,
+    /// Shows no empty workspaces, other than the focused workspace.
+    None,
+}
+
+impl Default for ShowEmpty {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+impl ShowEmpty {
+    fn allows(self, persistent: bool) -> bool {
+        match self {
+            Self::All => true,
+            Self::Persistent => persistent,
+            Self::None => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum MonitorFilter {
+    /// Shows only workspaces on the bar's own monitor.
+    Own,
+    /// Shows workspaces from all monitors.
+    All,
+}
+
+impl Default for MonitorFilter {
+    fn default() -> Self {
+        Self::Own
+    }
+}
+
+impl MonitorFilter {
+    fn includes(self, output: &str, monitor: &str) -> bool {
+        match self {
+            Self::Own => output == monitor,
+            Self::All => true,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(untagged)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -55,6 +113,27 @@ pub struct WorkspacesModule {
     /// it will fall back to using its actual name.
     name_map: Option<HashMap<String, String>>,
 
+    /// Map of workspace names to extra CSS classes to add to their buttons,
+    /// alongside the `focused`, `visible` and `inactive` classes.
+    ///
+    /// Names can include `*` to match any characters and `?` to match any single character,
+    /// and are matched against the actual workspace name, ignoring case.
+    /// Classes from every matching entry are added.
+    ///
+    /// **Default**: `{}`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// {
+    ///   type = "workspaces"
+    ///   class_map.mail = "work"
+    ///   class_map."code-*" = [ "work" "dev" ]
+    /// }
+    /// ```
+    #[serde(default)]
+    class_map: HashMap<String, ClassList>,
+
     /// Workspaces which should always be shown.
     /// This can either be an array of workspace names,
     /// or a map of monitor names to arrays of workspace names.
@@ -88,13 +167,34 @@ pub struct WorkspacesModule {
     #[serde(default)]
     hidden: Vec<String>,
 
-    /// Whether to display workspaces from all monitors.
-    /// When false, only shows workspaces on the current monitor.
+    /// Which monitors to show workspaces from.
+    /// `own` shows only workspaces on the bar's monitor,
+    /// following workspaces as they are moved between monitors.
+    ///
+    /// **Valid options**: `own`, `all`
+    /// <br>
+    /// **Default**: `own`
+    #[serde(default)]
+    monitor_filter: MonitorFilter,
+
+    /// Shorthand for `monitor_filter = "all"` when `true`.
     ///
     /// **Default**: `false`
     #[serde(default = "crate::config::default_false")]
     all_monitors: bool,
 
+    /// Which empty workspaces to show.
+    /// This includes favourites which are not currently open.
+    ///
+    /// Persistent workspaces are those the compositor keeps open while empty.
+    /// Currently these are only reported by Hyprland.
+    ///
+    /// **Valid options**: `all`, `persistent`, `none`
+    /// <br>
+    /// **Default**: `all`
+    #[serde(default)]
+    show_empty: ShowEmpty,
+
     /// The method used for sorting workspaces.
     /// `added` always appends to the end, `alphanumeric` sorts by number/name.
     ///
@@ -110,6 +210,13 @@ pub struct WorkspacesModule {
     #[serde(default = "default_icon_size")]
     icon_size: i32,
 
+    /// Whether scrolling past the last workspace wraps around to the first,
+    /// and past the first to the last.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    scroll_wrap: bool,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -119,6 +226,99 @@ const fn default_icon_size() -> i32 {
     32
 }
 
+#[derive(Debug, Clone)]
+pub enum WorkspaceCommand {
+    /// Focuses the workspace with this name.
+    Focus(String),
+    /// Moves the app's window with this title
+    /// to the workspace with this name.
+    MoveWindow {
+        app_id: String,
+        title: String,
+        workspace: String,
+    },
+}
+
+/// The widget tag storing whether a button's workspace is empty.
+const EMPTY_TAG: &str = "empty";
+
+/// The widget tag storing the classes added to a button from the class map.
+const CLASS_MAP_TAG: &str = "class-map";
+
+/// Switches workspace when scrolling or swiping over the buttons.
+#[derive(Debug, Clone)]
+struct ScrollHandler {
+    wrap: bool,
+    scroller: Scroller,
+}
+
+impl ScrollHandler {
+    fn new(wrap: bool, config: ScrollConfig, swipe: bool) -> Self {
+        Self {
+            wrap,
+            scroller: Scroller::new(config).with_swipe(swipe),
+        }
+    }
+
+    fn connect(&self, button: &Button, tx: &Sender<WorkspaceCommand>) {
+        let wrap = self.wrap;
+        let tx = tx.clone();
+
+        self.scroller.connect(button, move |button, step| {
+            let forward = step == ScrollStep::Down;
+
+            if let Some(name) = button
+                .parent()
+                .and_then(|container| container.downcast::<gtk::Box>().ok())
+                .and_then(|container| adjacent_workspace(&container, forward, wrap))
+            {
+                try_send!(tx, WorkspaceCommand::Focus(name));
+            }
+        });
+    }
+}
+
+/// Gets the name of the workspace shown next to the focused one,
+/// skipping hidden buttons.
+fn adjacent_workspace(container: &gtk::Box, forward: bool, wrap: bool) -> Option<String> {
+    let buttons = container
+        .children()
+        .into_iter()
+        .filter(WidgetExt::is_visible)
+        .collect::<Vec<_>>();
+
+    let focused = buttons
+        .iter()
+        .position(|button| button.style_context().has_class("focused"))
+        .or_else(|| {
+            buttons
+                .iter()
+                .position(|button| button.style_context().has_class("visible"))
+        });
+
+    step_index(focused, buttons.len(), forward, wrap)
+        .map(|index| buttons[index].widget_name().to_string())
+}
+
+/// Gets the index one step from the current one,
+/// or the first or last if there is no current index.
+fn step_index(current: Option<usize>, len: usize, forward: bool, wrap: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+
+    let last = len - 1;
+
+    match (current, forward) {
+        (None, true) => Some(0),
+        (None, false) => Some(last),
+        (Some(current), true) if current < last => Some(current + 1),
+        (Some(current), false) if current > 0 => Some(current - 1),
+        (Some(_), true) => wrap.then_some(0),
+        (Some(_), false) => wrap.then_some(last),
+    }
+}
+
 /// Creates a button from a workspace
 fn create_button(
     name: &str,
@@ -126,12 +326,15 @@ fn create_button(
     name_map: &HashMap<String, String>,
     icon_theme: &IconTheme,
     icon_size: i32,
-    tx: &Sender<String>,
+    context: &WidgetContext<WorkspaceUpdate, WorkspaceCommand>,
+    scroll: &ScrollHandler,
 ) -> Button {
+    let tx = &context.controller_tx;
     let label = name_map.get(name).map_or(name, String::as_str);
 
     let button = new_icon_button(label, icon_theme, icon_size);
     button.set_widget_name(name);
+    button.set_interactive();
 
     let style_context = button.style_context();
     style_context.add_class("item");
@@ -152,13 +355,119 @@ fn create_button(
         let tx = tx.clone();
         let name = name.to_string();
         button.connect_clicked(move |_item| {
-            try_send!(tx, name.clone());
+            try_send!(tx, WorkspaceCommand::Focus(name.clone()));
+        });
+    }
+
+    scroll.connect(&button, tx);
+
+    // launcher items can be dropped on the button to move their window
+    button.drag_dest_set(
+        DestDefaults::MOTION | DestDefaults::DROP,
+        &[window_drag_target()],
+        DragAction::MOVE,
+    );
+
+    button.connect_drag_motion(|button, _, _, _, _| {
+        button.add_class("drop-target");
+        true
+    });
+
+    // also emitted when the drag is dropped or cancelled
+    button.connect_drag_leave(|button, _, _| {
+        button.set_class("drop-target", false);
+    });
+
+    {
+        let tx = tx.clone();
+        let ironbar = context.ironbar.clone();
+        let name = name.to_string();
+        button.connect_drag_data_received(move |_, _, _, _, data, _, _| {
+            // the launcher sends the toplevel ID of the window
+            let window = String::from_utf8(data.data())
+                .ok()
+                .and_then(|id| id.parse::<usize>().ok())
+                .and_then(|id| {
+                    let wayland = ironbar.clients.borrow_mut().wayland();
+                    wayland
+                        .toplevel_info_all()
+                        .into_iter()
+                        .find(|info| info.id == id)
+                });
+
+            match window {
+                Some(window) => try_send!(
+                    tx,
+                    WorkspaceCommand::MoveWindow {
+                        app_id: window.app_id,
+                        title: window.title,
+                        workspace: name.clone(),
+                    }
+                ),
+                None => warn!("Received invalid window drop"),
+            }
         });
     }
 
     button
 }
 
+/// Gets the classes for the workspace from each matching class map entry.
+fn mapped_classes(class_map: &HashMap<String, ClassList>, name: &str) -> Vec<String> {
+    let mut classes = class_map
+        .iter()
+        .filter(|(pattern, _)| glob_match(pattern, name))
+        .flat_map(|(_, classes)| classes.iter())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    classes.sort();
+    classes.dedup();
+    classes
+}
+
+/// Sets the button's classes from the class map,
+/// removing any from a previous name which no longer match.
+fn set_mapped_classes(button: &Button, class_map: &HashMap<String, ClassList>, name: &str) {
+    let classes = mapped_classes(class_map, name);
+
+    if let Some(previous) = button.get_tag::<Vec<String>>(CLASS_MAP_TAG) {
+        for class in previous.iter().filter(|class| !classes.contains(class)) {
+            button.style_context().remove_class(class);
+        }
+    }
+
+    for class in &classes {
+        button.add_class(class);
+    }
+
+    button.set_tag(CLASS_MAP_TAG, classes);
+}
+
+/// Updates the button for whether its workspace is empty or persistent.
+fn set_empty_state(button: &Button, empty: bool, persistent: bool, show_empty: ShowEmpty) {
+    button.set_tag(EMPTY_TAG, empty);
+    button.set_class("persistent", persistent);
+
+    refresh_visible(button, show_empty);
+}
+
+/// Hides the button if its workspace is empty
+/// and empty workspaces of its kind should not be shown.
+/// Focused workspaces are always shown.
+fn refresh_visible(button: &Button, show_empty: ShowEmpty) {
+    let style_context = button.style_context();
+
+    let empty = button
+        .get_tag::<bool>(EMPTY_TAG)
+        .copied()
+        .unwrap_or_default();
+    let persistent = style_context.has_class("persistent");
+    let focused = style_context.has_class("focused");
+
+    button.set_visible(focused || !empty || show_empty.allows(persistent));
+}
+
 fn reorder_workspaces(container: &gtk::Box) {
     let mut buttons = container
         .children()
@@ -190,28 +499,35 @@ fn find_btn(map: &HashMap<i64, Button>, workspace: &Workspace) -> Option<Button>
 }
 
 impl WorkspacesModule {
-    fn show_workspace_check(&self, output: &String, work: &Workspace) -> bool {
+    fn monitor_filter(&self) -> MonitorFilter {
+        if self.all_monitors {
+            MonitorFilter::All
+        } else {
+            self.monitor_filter
+        }
+    }
+
+    fn show_workspace_check(&self, output: &str, work: &Workspace) -> bool {
         (work.visibility.is_focused() || !self.hidden.contains(&work.name))
-            && (self.all_monitors || output == &work.monitor)
+            && self.monitor_filter().includes(output, &work.monitor)
     }
 }
 
 impl Module<gtk::Box> for WorkspacesModule {
     type SendMessage = WorkspaceUpdate;
-    type ReceiveMessage = String;
+    type ReceiveMessage = WorkspaceCommand;
 
     module_impl!("workspaces");
 
     fn spawn_controller(
         &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        context: &ControllerContext<Self::SendMessage>,
         mut rx: Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
-        let client = context.ironbar.clients.borrow_mut().workspaces()?;
+        let client = context.clients.borrow_mut().workspaces()?;
         // Subscribe & send events
-        spawn(async move {
+        context.spawn(async move {
             let mut srx = client.subscribe_workspace_change();
 
             trace!("Set up workspace subscription");
@@ -225,11 +541,25 @@ impl Module<gtk::Box> for WorkspacesModule {
         let client = context.try_client::<dyn WorkspaceClient>()?;
 
         // Change workspace focus
-        spawn(async move {
+        context.spawn(async move {
             trace!("Setting up UI event handler");
 
-            while let Some(name) = rx.recv().await {
-                client.focus(name)?;
+            while let Some(command) = rx.recv().await {
+                match command {
+                    WorkspaceCommand::Focus(name) => client.focus(name)?,
+                    WorkspaceCommand::MoveWindow {
+                        app_id,
+                        title,
+                        workspace,
+                    } => {
+                        debug!("Moving window '{title}' of '{app_id}' to workspace '{workspace}'");
+
+                        // a failed drop should not stop the module
+                        if let Err(err) = client.move_window(&app_id, &title, &workspace) {
+                            error!("{:?}", err.wrap_err("Failed to move window"));
+                        }
+                    }
+                }
             }
 
             Ok::<(), Report>(())
@@ -246,6 +576,7 @@ impl Module<gtk::Box> for WorkspacesModule {
         let container = gtk::Box::new(info.bar_position.orientation(), 0);
 
         let name_map = self.name_map.clone().unwrap_or_default();
+        let class_map = self.class_map.clone();
         let favs = self.favorites.clone();
         let mut fav_names: Vec<String> = vec![];
 
@@ -255,7 +586,9 @@ impl Module<gtk::Box> for WorkspacesModule {
             let container = container.clone();
             let output_name = info.output_name.to_string();
             let icon_theme = info.icon_theme.clone();
-            let icon_size = self.icon_size;
+            let icon_size = info.scaled(self.icon_size);
+            let show_empty = self.show_empty;
+            let scroll = ScrollHandler::new(self.scroll_wrap, info.scroll, info.touch.swipe);
 
             // keep track of whether init event has fired previously
             // since it fires for every workspace subscriber
@@ -269,16 +602,20 @@ impl Module<gtk::Box> for WorkspacesModule {
 
                             let mut added = HashSet::new();
 
-                            let mut add_workspace = |id: i64, name: &str, visibility: Visibility| {
+                            let mut add_workspace = |id: i64, name: &str, visibility: Visibility, empty: bool, persistent: bool| {
                                 let item = create_button(
                                     name,
                                     visibility,
                                     &name_map,
                                     &icon_theme,
                                     icon_size,
-                                    &context.controller_tx,
+                                    &context,
+                                    &scroll,
                                 );
 
+                                set_empty_state(&item, empty, persistent, show_empty);
+                                set_mapped_classes(&item, &class_map, name);
+
                                 container.add(&item);
                                 button_map.insert(id, item);
                             };
@@ -286,7 +623,13 @@ impl Module<gtk::Box> for WorkspacesModule {
                             // add workspaces from client
                             for workspace in &workspaces {
                                 if self.show_workspace_check(&output_name, workspace) {
-                                    add_workspace(workspace.id, &workspace.name, workspace.visibility);
+                                    add_workspace(
+                                        workspace.id,
+                                        &workspace.name,
+                                        workspace.visibility,
+                                        workspace.is_empty(),
+                                        workspace.persistent,
+                                    );
                                     added.insert(workspace.name.to_string());
                                 }
                             }
@@ -300,7 +643,7 @@ impl Module<gtk::Box> for WorkspacesModule {
                                         // as Hyprland will initialize them this way.
                                         // Since existing workspaces are added above,
                                         // this means there shouldn't be any issues with renaming.
-                                        add_workspace(-(Ironbar::unique_id() as i64), name, Visibility::Hidden);
+                                        add_workspace(-(Ironbar::unique_id() as i64), name, Visibility::Hidden, true, false);
                                         added.insert(name.to_string());
                                     }
                                 }
@@ -321,6 +664,12 @@ impl Module<gtk::Box> for WorkspacesModule {
                             }
 
                             container.show_all();
+
+                            // `show_all` overrides hiding empty workspaces
+                            for button in button_map.values() {
+                                refresh_visible(button, show_empty);
+                            }
+
                             has_initialized = true;
                         }
                     }
@@ -331,26 +680,36 @@ impl Module<gtk::Box> for WorkspacesModule {
                             }
 
                             btn.style_context().remove_class("focused");
+                            refresh_visible(&btn, show_empty);
                         }
 
                         if let Some(btn) = find_btn(&button_map, &new) {
                             btn.add_class("visible");
                             btn.add_class("focused");
+                            refresh_visible(&btn, show_empty);
                         }
                     }
                     WorkspaceUpdate::Rename { id, name } => {
                         if let Some(btn) = button_map.get(&id) {
+                            set_mapped_classes(btn, &class_map, &name);
+
                             let name = name_map.get(&name).unwrap_or(&name);
                             btn.set_label(name);
                         }
                     }
                     WorkspaceUpdate::Add(workspace) => {
+                        // the workspace may already have been added by a move onto this monitor
+                        let exists = button_map.contains_key(&workspace.id);
+
                         if fav_names.contains(&workspace.name) {
-                            let btn = button_map.get(&workspace.id);
+                            let btn = button_map
+                                .get(&workspace.id)
+                                .filter(|_| self.monitor_filter().includes(&output_name, &workspace.monitor));
                             if let Some(btn) = btn {
                                 btn.style_context().remove_class("inactive");
+                                set_empty_state(btn, workspace.is_empty(), workspace.persistent, show_empty);
                             }
-                        } else if self.show_workspace_check(&output_name, &workspace) {
+                        } else if !exists && self.show_workspace_check(&output_name, &workspace) {
                             let name = workspace.name;
                             let item = create_button(
                                 &name,
@@ -358,7 +717,8 @@ impl Module<gtk::Box> for WorkspacesModule {
                                 &name_map,
                                 &icon_theme,
                                 icon_size,
-                                &context.controller_tx,
+                                &context,
+                                &scroll,
                             );
 
                             container.add(&item);
@@ -367,6 +727,8 @@ impl Module<gtk::Box> for WorkspacesModule {
                             }
 
                             item.show();
+                            set_empty_state(&item, workspace.is_empty(), workspace.persistent, show_empty);
+                            set_mapped_classes(&item, &class_map, &name);
 
                             if !name.is_empty() {
                                 button_map.insert(workspace.id, item);
@@ -374,31 +736,51 @@ impl Module<gtk::Box> for WorkspacesModule {
                         }
                     }
                     WorkspaceUpdate::Move(workspace) => {
-                        if !self.hidden.contains(&workspace.name) && !self.all_monitors {
-                            if workspace.monitor == output_name {
-                                let name = workspace.name;
-                                let item = create_button(
-                                    &name,
-                                    workspace.visibility,
-                                    &name_map,
-                                    &icon_theme,
-                                    icon_size,
-                                    &context.controller_tx,
-                                );
+                        // with all monitors shown, moving changes nothing
+                        if self.monitor_filter() == MonitorFilter::Own {
+                            let existing = find_btn(&button_map, &workspace);
+
+                            if self.show_workspace_check(&output_name, &workspace) {
+                                if let Some(btn) = existing {
+                                    btn.style_context().remove_class("inactive");
+                                    set_empty_state(&btn, workspace.is_empty(), workspace.persistent, show_empty);
+                                } else {
+                                    let name = workspace.name;
+                                    let item = create_button(
+                                        &name,
+                                        workspace.visibility,
+                                        &name_map,
+                                        &icon_theme,
+                                        icon_size,
+                                        &context,
+                                        &scroll,
+                                    );
+
+                                    container.add(&item);
+
+                                    if self.sort == SortOrder::Alphanumeric {
+                                        reorder_workspaces(&container);
+                                    }
 
-                                container.add(&item);
+                                    item.show();
+                                    set_empty_state(&item, workspace.is_empty(), workspace.persistent, show_empty);
+                                    set_mapped_classes(&item, &class_map, &name);
 
-                                if self.sort == SortOrder::Alphanumeric {
-                                    reorder_workspaces(&container);
+                                    if !name.is_empty() {
+                                        button_map.insert(workspace.id, item);
+                                    }
                                 }
-
-                                item.show();
-
-                                if !name.is_empty() {
-                                    button_map.insert(workspace.id, item);
+                            } else if let Some(btn) = existing {
+                                // favourites stay on every bar they are configured for
+                                if fav_names.contains(&workspace.name) {
+                                    btn.style_context().remove_class("visible");
+                                    btn.style_context().remove_class("focused");
+                                    btn.add_class("inactive");
+                                    set_empty_state(&btn, true, false, show_empty);
+                                } else {
+                                    container.remove(&btn);
+                                    button_map.retain(|_, item| item != &btn);
                                 }
-                            } else if let Some(item) = button_map.get(&workspace.id) {
-                                container.remove(item);
                             }
                         }
                     }
@@ -408,11 +790,17 @@ impl Module<gtk::Box> for WorkspacesModule {
                             if workspace < 0 {
                             // if fav_names.contains(&workspace) {
                                 item.style_context().add_class("inactive");
+                                set_empty_state(item, true, false, show_empty);
                             } else {
                                 container.remove(item);
                             }
                         }
                     }
+                    WorkspaceUpdate::Update(workspace) => {
+                        if let Some(btn) = find_btn(&button_map, &workspace) {
+                            set_empty_state(&btn, workspace.is_empty(), workspace.persistent, show_empty);
+                        }
+                    }
                     WorkspaceUpdate::Unknown => warn!("Received unknown type workspace event")
                 };
             });
@@ -424,3 +812,157 @@ impl Module<gtk::Box> for WorkspacesModule {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::testing::{config, ControllerHarness, FakeWorkspaceClient};
+
+    fn workspace(id: i64, visibility: Visibility) -> Workspace {
+        Workspace {
+            id,
+            name: id.to_string(),
+            monitor: String::from("TEST-1"),
+            visibility,
+            windows: 0,
+            persistent: false,
+        }
+    }
+
+    #[test]
+    fn forwards_updates_and_focus_requests() {
+        let client = FakeWorkspaceClient::new(vec![
+            workspace(1, Visibility::focused()),
+            workspace(2, Visibility::Hidden),
+        ]);
+        let module: WorkspacesModule = config("{}");
+
+        let mut harness = ControllerHarness::new();
+        harness.clients().set_workspaces(client.clone());
+        harness.start(&module).expect("controller to start");
+
+        let WorkspaceUpdate::Init(workspaces) = harness.next_update() else {
+            panic!("expected initial workspaces");
+        };
+        assert_eq!(workspaces.len(), 2);
+
+        harness.send(WorkspaceCommand::Focus(String::from("2")));
+        let WorkspaceUpdate::Focus { old, new } = harness.next_update() else {
+            panic!("expected focus change");
+        };
+        assert_eq!(old.map(|workspace| workspace.id), Some(1));
+        assert_eq!(new.id, 2);
+        assert!(new.visibility.is_focused());
+
+        client.send(WorkspaceUpdate::Remove(1));
+        assert!(matches!(harness.next_update(), WorkspaceUpdate::Remove(1)));
+    }
+
+    #[cfg(feature = "workspaces+sway")]
+    #[test]
+    fn follows_sway_ipc() {
+        use crate::modules::testing::FakeSwayIpc;
+        use serde_json::{json, Value};
+
+        let rect = json!({ "x": 0, "y": 0, "width": 0, "height": 0 });
+        let workspace = |id: i64, focused: bool| {
+            json!({
+                "id": id, "num": id, "name": id.to_string(), "output": "TEST-1",
+                "visible": focused, "focused": focused, "urgent": false,
+                "representation": null, "rect": rect,
+            })
+        };
+        let workspace_node = |id: i64, focused: bool, windows: Vec<Value>| {
+            FakeSwayIpc::node(json!({
+                "id": id, "type": "workspace", "name": id.to_string(), "output": "TEST-1",
+                "focused": focused, "nodes": windows,
+            }))
+        };
+        let window = |id: i64| FakeSwayIpc::node(json!({ "id": id, "app_id": "foot" }));
+
+        let tree = FakeSwayIpc::node(json!({
+            "type": "root",
+            "nodes": [FakeSwayIpc::node(json!({
+                "type": "output",
+                "nodes": [
+                    workspace_node(1, true, vec![window(10), window(11)]),
+                    workspace_node(2, false, vec![]),
+                ],
+            }))],
+        }));
+
+        let (client, events) =
+            FakeSwayIpc::client(json!([workspace(1, true), workspace(2, false)]), tree);
+        let module: WorkspacesModule = config("{}");
+
+        let mut harness = ControllerHarness::new();
+        harness.clients().set_workspaces(client);
+        harness.start(&module).expect("controller to start");
+
+        let WorkspaceUpdate::Init(workspaces) = harness.next_update() else {
+            panic!("expected initial workspaces");
+        };
+        let windows = workspaces
+            .iter()
+            .map(|workspace| (workspace.id, workspace.windows))
+            .collect::<Vec<_>>();
+        assert_eq!(windows, [(1, 2), (2, 0)]);
+
+        harness.send(WorkspaceCommand::Focus(String::from("2")));
+        let WorkspaceUpdate::Focus { old, new } = harness.next_update() else {
+            panic!("expected focus change");
+        };
+        assert_eq!(old.map(|workspace| workspace.id), Some(1));
+        assert_eq!(new.id, 2);
+        assert!(new.visibility.is_focused());
+
+        events
+            .send(FakeSwayIpc::workspace_event(
+                "empty",
+                workspace_node(1, false, vec![]),
+                None,
+            ))
+            .expect("client to be listening");
+        assert!(matches!(harness.next_update(), WorkspaceUpdate::Remove(1)));
+    }
+
+    #[test]
+    fn filters_workspaces_by_monitor() {
+        let mut other = workspace(2, Visibility::Hidden);
+        other.monitor = String::from("TEST-2");
+
+        let own: WorkspacesModule = config("{}");
+        assert!(own.show_workspace_check("TEST-1", &workspace(1, Visibility::Hidden)));
+        assert!(!own.show_workspace_check("TEST-1", &other));
+
+        let all: WorkspacesModule = config(r#"{ "monitor_filter": "all" }"#);
+        assert!(all.show_workspace_check("TEST-1", &other));
+
+        let legacy: WorkspacesModule = config(r#"{ "all_monitors": true }"#);
+        assert_eq!(legacy.monitor_filter(), MonitorFilter::All);
+    }
+
+    #[test]
+    fn steps_between_workspaces() {
+        assert_eq!(step_index(Some(1), 3, true, false), Some(2));
+        assert_eq!(step_index(Some(1), 3, false, false), Some(0));
+        assert_eq!(step_index(Some(2), 3, true, false), None);
+        assert_eq!(step_index(Some(2), 3, true, true), Some(0));
+        assert_eq!(step_index(Some(0), 3, false, true), Some(2));
+        assert_eq!(step_index(None, 3, false, false), Some(2));
+        assert_eq!(step_index(None, 0, true, true), None);
+    }
+
+    #[test]
+    fn maps_classes_by_pattern() {
+        let module: WorkspacesModule = config(
+            r#"{ "class_map": { "mail": "work", "code-*": ["work", "dev"], "?": "single" } }"#,
+        );
+        let classes = |name| mapped_classes(&module.class_map, name);
+
+        assert_eq!(classes("code-rust"), ["dev", "work"]);
+        assert_eq!(classes("Mail"), ["work"]);
+        assert_eq!(classes("1"), ["single"]);
+        assert!(classes("music").is_empty());
+    }
+}
@@ -0,0 +1,380 @@
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::modules::{ModulePopupParts, PopupButton};
+use crate::popup::{Popup, PopupAnchor, PopupRequest, PopupTarget};
+use crate::Ironbar;
+use gtk::prelude::*;
+use gtk::{Button, EventBox, Orientation};
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::rc::Rc;
+use tracing::debug;
+
+/// The widget tag holding a module's configured `priority`.
+pub const PRIORITY_TAG: &str = "overflow-priority";
+
+/// The order in which modules are kept in the bar.
+///
+/// Modules with a higher priority are kept for longest.
+/// Between modules with the same priority,
+/// those nearest the outer edge of the bar are kept for longest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Rank {
+    priority: i32,
+    distance: Reverse<usize>,
+}
+
+impl Rank {
+    /// Creates the rank for the module at `index` in a section of `len` modules.
+    /// Set `from_end` for sections at the end of the bar,
+    /// where the last module is nearest the edge.
+    fn new(priority: i32, index: usize, len: usize, from_end: bool) -> Self {
+        let distance = if from_end { len - 1 - index } else { index };
+
+        Self {
+            priority,
+            distance: Reverse(distance),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ItemState {
+    rank: Rank,
+    collapsed: bool,
+}
+
+/// Gets the index of the lowest-ranked item still in the bar.
+fn next_to_collapse(states: &[ItemState]) -> Option<usize> {
+    states
+        .iter()
+        .enumerate()
+        .filter(|(_, state)| !state.collapsed)
+        .min_by_key(|(_, state)| state.rank)
+        .map(|(i, _)| i)
+}
+
+/// Gets the index of the highest-ranked item in the menu.
+fn next_to_expand(states: &[ItemState]) -> Option<usize> {
+    states
+        .iter()
+        .enumerate()
+        .filter(|(_, state)| state.collapsed)
+        .max_by_key(|(_, state)| state.rank)
+        .map(|(i, _)| i)
+}
+
+#[derive(Debug)]
+struct Item {
+    widget: EventBox,
+    state: ItemState,
+    /// The length the bar content shrank by when the item was collapsed.
+    /// This is the space needed to expand it again.
+    length: i32,
+}
+
+/// A bar section with an overflow menu.
+#[derive(Debug)]
+struct Section {
+    container: gtk::Box,
+    button: Button,
+    menu: gtk::Box,
+    /// Whether the overflow button is placed before the modules.
+    button_first: bool,
+    items: Vec<Item>,
+}
+
+impl Section {
+    fn new(container: &gtk::Box, orientation: Orientation, from_end: bool, popup: &Popup) -> Self {
+        let children = container
+            .children()
+            .into_iter()
+            .filter_map(|child| child.downcast::<EventBox>().ok())
+            .collect::<Vec<_>>();
+
+        let len = children.len();
+        let items = children
+            .into_iter()
+            .enumerate()
+            .map(|(index, widget)| {
+                let priority = widget.get_tag::<i32>(PRIORITY_TAG).copied().unwrap_or(0);
+
+                Item {
+                    widget,
+                    state: ItemState {
+                        rank: Rank::new(priority, index, len, from_end),
+                        collapsed: false,
+                    },
+                    length: 0,
+                }
+            })
+            .collect();
+
+        let button = Button::with_label("»");
+        button.add_class("overflow");
+        container.add(&button);
+        if from_end {
+            container.reorder_child(&button, 0);
+        }
+
+        let menu = gtk::Box::new(orientation, container.spacing());
+        menu.add_class("overflow");
+        menu.show();
+
+        let key = Ironbar::unique_id();
+        popup.register_content(
+            key,
+            String::from("overflow"),
+            ModulePopupParts {
+                container: menu.clone(),
+                buttons: vec![button.clone()],
+            },
+        );
+
+        {
+            let popup = popup.clone();
+            button.connect_clicked(move |button| {
+                popup.request(PopupRequest::Toggle(PopupTarget {
+                    widget_id: key,
+                    anchor: PopupAnchor::Button(button.popup_id()),
+                }));
+            });
+        }
+
+        Self {
+            container: container.clone(),
+            button,
+            menu,
+            button_first: from_end,
+            items,
+        }
+    }
+
+    fn states(&self) -> Vec<ItemState> {
+        self.items.iter().map(|item| item.state).collect()
+    }
+
+    /// Moves the item into the menu, keeping the configured order.
+    fn collapse(&mut self, index: usize) {
+        let position = self.items[..index]
+            .iter()
+            .filter(|item| item.state.collapsed)
+            .count();
+
+        let item = &mut self.items[index];
+        item.state.collapsed = true;
+
+        self.container.remove(&item.widget);
+        self.menu.add(&item.widget);
+        self.menu.reorder_child(&item.widget, position as i32);
+
+        self.button.show();
+    }
+
+    /// Moves the item back into the bar, keeping the configured order.
+    fn expand(&mut self, index: usize) {
+        let mut position = self.items[..index]
+            .iter()
+            .filter(|item| !item.state.collapsed)
+            .count();
+
+        if self.button_first {
+            position += 1;
+        }
+
+        let item = &mut self.items[index];
+        item.state.collapsed = false;
+
+        self.menu.remove(&item.widget);
+        self.container.add(&item.widget);
+        self.container.reorder_child(&item.widget, position as i32);
+
+        if self.items.iter().all(|item| !item.state.collapsed) {
+            self.button.hide();
+        }
+    }
+}
+
+/// Moves modules from the start and end of the bar into menus
+/// when there is not enough space for them,
+/// and back again once there is.
+///
+/// Widgets are moved rather than recreated,
+/// so modules keep receiving updates while collapsed.
+#[derive(Debug, Clone)]
+pub struct Overflow {
+    orientation: Orientation,
+    /// The length available to the bar content along its edge.
+    available: Rc<Cell<i32>>,
+    sections: Rc<RefCell<Vec<Section>>>,
+    pending: Rc<Cell<bool>>,
+}
+
+impl Overflow {
+    /// Sets up overflow menus for the start and end sections.
+    ///
+    /// This must be called after the modules have been added.
+    pub fn new(
+        content: &gtk::Box,
+        start: &gtk::Box,
+        end: &gtk::Box,
+        orientation: Orientation,
+        available: Rc<Cell<i32>>,
+        popup: &Popup,
+    ) -> Self {
+        let sections = vec![
+            Section::new(start, orientation, false, popup),
+            Section::new(end, orientation, true, popup),
+        ];
+
+        let overflow = Self {
+            orientation,
+            available,
+            sections: Rc::new(RefCell::new(sections)),
+            pending: Rc::new(Cell::new(false)),
+        };
+
+        {
+            let overflow = overflow.clone();
+            content.connect_size_allocate(move |content, _| overflow.queue_check(content));
+        }
+
+        overflow
+    }
+
+    /// Checks the available space once control returns to the main loop.
+    /// Checking during allocation would resize the bar mid-layout.
+    fn queue_check(&self, content: &gtk::Box) {
+        if !self.pending.replace(true) {
+            let overflow = self.clone();
+            let content = content.clone();
+            glib::idle_add_local_once(move || {
+                overflow.pending.set(false);
+                overflow.check(&content);
+            });
+        }
+    }
+
+    fn min_length(&self, widget: &impl IsA<gtk::Widget>) -> i32 {
+        if self.orientation == Orientation::Horizontal {
+            widget.preferred_width().0
+        } else {
+            widget.preferred_height().0
+        }
+    }
+
+    fn check(&self, content: &gtk::Box) {
+        let available = self.available.get();
+        if available <= 0 {
+            return;
+        }
+
+        let mut sections = self.sections.borrow_mut();
+
+        // restarted modules are replaced, so their old widgets are dropped.
+        for section in sections.iter_mut() {
+            section.items.retain(|item| item.widget.parent().is_some());
+        }
+
+        let mut required = self.min_length(content);
+        let mut collapsed = false;
+
+        while required > available {
+            // the center section stays centered,
+            // so only shrinking the longest side frees space.
+            let Some((section_index, index)) = sections
+                .iter()
+                .enumerate()
+                .filter_map(|(i, section)| {
+                    next_to_collapse(&section.states()).map(|index| (i, index, section))
+                })
+                .max_by_key(|(_, _, section)| self.min_length(&section.container))
+                .map(|(i, index, _)| (i, index))
+            else {
+                break;
+            };
+
+            let section = &mut sections[section_index];
+            section.collapse(index);
+
+            let new_required = self.min_length(content);
+            section.items[index].length = required - new_required;
+            required = new_required;
+            collapsed = true;
+        }
+
+        if collapsed {
+            debug!("Collapsed bar modules into overflow menu");
+            return;
+        }
+
+        loop {
+            let mut candidates = sections
+                .iter()
+                .enumerate()
+                .filter_map(|(i, section)| {
+                    next_to_expand(&section.states()).map(|index| {
+                        let item = &section.items[index];
+                        (i, index, item.state.rank, item.length)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            candidates.sort_by_key(|&(_, _, rank, _)| Reverse(rank));
+
+            let Some(&(section_index, index, _, length)) = candidates
+                .iter()
+                .find(|&&(_, _, _, length)| required + length <= available)
+            else {
+                break;
+            };
+
+            sections[section_index].expand(index);
+            required += length;
+            debug!("Expanded bar module from overflow menu");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states(ranks: &[Rank], collapsed: &[usize]) -> Vec<ItemState> {
+        ranks
+            .iter()
+            .enumerate()
+            .map(|(i, &rank)| ItemState {
+                rank,
+                collapsed: collapsed.contains(&i),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn collapses_nearest_center_first() {
+        let start = (0..3)
+            .map(|i| Rank::new(0, i, 3, false))
+            .collect::<Vec<_>>();
+        assert_eq!(next_to_collapse(&states(&start, &[])), Some(2));
+        assert_eq!(next_to_collapse(&states(&start, &[2])), Some(1));
+
+        let end = (0..3).map(|i| Rank::new(0, i, 3, true)).collect::<Vec<_>>();
+        assert_eq!(next_to_collapse(&states(&end, &[])), Some(0));
+        assert_eq!(next_to_expand(&states(&end, &[0, 1])), Some(1));
+    }
+
+    #[test]
+    fn keeps_higher_priority() {
+        let ranks = [
+            Rank::new(0, 0, 3, false),
+            Rank::new(-1, 1, 3, false),
+            Rank::new(5, 2, 3, false),
+        ];
+
+        assert_eq!(next_to_collapse(&states(&ranks, &[])), Some(1));
+        assert_eq!(next_to_collapse(&states(&ranks, &[1])), Some(0));
+        assert_eq!(next_to_collapse(&states(&ranks, &[0, 1, 2])), None);
+        assert_eq!(next_to_expand(&states(&ranks, &[0, 1])), Some(0));
+        assert_eq!(next_to_expand(&states(&ranks, &[])), None);
+    }
+}
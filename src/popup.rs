@@ -1,18 +1,23 @@
 use glib::Propagation;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
-use gtk::gdk::Monitor;
+use gtk::gdk::{keys, Monitor};
 use gtk::prelude::*;
-use gtk::{ApplicationWindow, Button, Orientation};
-use gtk_layer_shell::LayerShell;
+use gtk::{ApplicationWindow, Button, DirectionType, Orientation};
+use gtk_layer_shell::{KeyboardMode, LayerShell};
 use tracing::{debug, trace};
 
 use crate::config::BarPosition;
 use crate::gtk_helpers::{IronbarGtkExt, WidgetGeometry};
-use crate::modules::{ModuleInfo, ModulePopupParts, PopupButton};
-use crate::rc_mut;
+use crate::modules::{ModuleInfo, ModulePopupParts, ModuleUpdateEvent, PopupButton};
+use crate::{rc_mut, Ironbar};
+
+/// The gap kept between the popup and the ends of the available space.
+const EDGE_GAP: i32 = 5;
 
 #[derive(Debug, Clone)]
 pub struct PopupCacheValue {
@@ -20,21 +25,156 @@ pub struct PopupCacheValue {
     pub content: ModulePopupParts,
 }
 
+/// A thread-safe handle for checking
+/// whether a specific widget's popup is open.
+#[derive(Debug, Clone)]
+pub struct PopupOpenState {
+    open_widget: Arc<AtomicUsize>,
+    widget_id: usize,
+}
+
+impl PopupOpenState {
+    pub fn is_open(&self) -> bool {
+        self.open_widget.load(Ordering::Relaxed) == self.widget_id
+    }
+}
+
+/// Where the popup is positioned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PopupAnchor {
+    /// Centred on the button with the given popup ID.
+    Button(usize),
+    /// Centred on the given geometry.
+    Geometry(WidgetGeometry),
+}
+
+/// The widget the popup is showing content for,
+/// and where it is positioned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PopupTarget {
+    pub widget_id: usize,
+    pub anchor: PopupAnchor,
+}
+
+/// A request to change the popup state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PopupRequest {
+    /// Opens the popup for the target,
+    /// or closes it if it is already open for the target widget.
+    Toggle(PopupTarget),
+    /// Opens the popup for the target,
+    /// replacing any other widget's content.
+    Open(PopupTarget),
+    /// Closes the popup, whichever widget it is open for.
+    Close,
+}
+
+impl PopupRequest {
+    /// Creates the request for a module's update event,
+    /// or `None` if the event does not affect the popup.
+    ///
+    /// If `button_id` is set, it is used in place of the button in the event.
+    pub fn from_event<T: Clone>(
+        event: &ModuleUpdateEvent<T>,
+        widget_id: usize,
+        button_id: Option<usize>,
+    ) -> Option<Self> {
+        let target = |event_button_id| PopupTarget {
+            widget_id,
+            anchor: PopupAnchor::Button(button_id.unwrap_or(event_button_id)),
+        };
+
+        match event {
+            ModuleUpdateEvent::Update(_) => None,
+            ModuleUpdateEvent::TogglePopup(id) => Some(Self::Toggle(target(*id))),
+            ModuleUpdateEvent::OpenPopup(id) => Some(Self::Open(target(*id))),
+            #[cfg(feature = "launcher")]
+            ModuleUpdateEvent::OpenPopupAt(geometry) => Some(Self::Open(PopupTarget {
+                widget_id,
+                anchor: PopupAnchor::Geometry(*geometry),
+            })),
+            ModuleUpdateEvent::ClosePopup => Some(Self::Close),
+        }
+    }
+
+    /// Gets the new popup state from applying the request to the current state.
+    pub fn apply(self, current: Option<PopupTarget>) -> Option<PopupTarget> {
+        match self {
+            Self::Toggle(target)
+                if current.is_some_and(|current| current.widget_id == target.widget_id) =>
+            {
+                None
+            }
+            Self::Toggle(target) | Self::Open(target) => Some(target),
+            Self::Close => None,
+        }
+    }
+}
+
+/// The space reserved at each edge of an output
+/// by the exclusive zones of bars.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReservedEdges {
+    pub top: i32,
+    pub bottom: i32,
+    pub left: i32,
+    pub right: i32,
+}
+
+impl ReservedEdges {
+    /// Adds space reserved by a bar at the position.
+    pub fn reserve(&mut self, position: BarPosition, size: i32) {
+        let edge = match position {
+            BarPosition::Top => &mut self.top,
+            BarPosition::Bottom => &mut self.bottom,
+            BarPosition::Left => &mut self.left,
+            BarPosition::Right => &mut self.right,
+        };
+
+        *edge += size.max(0);
+    }
+}
+
+/// Everything needed to position the popup along the bar's edge.
+#[derive(Debug, Clone)]
+struct Placement {
+    pos: BarPosition,
+    monitor: Monitor,
+    /// The distance from the start of the output to the start of the bar,
+    /// or `None` if the bar is centered.
+    bar_origin: Rc<Cell<Option<i32>>>,
+    ironbar: Rc<Ironbar>,
+    bar_name: String,
+    output_name: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct Popup {
     pub window: ApplicationWindow,
     pub container_cache: Rc<RefCell<HashMap<usize, PopupCacheValue>>>,
     pub button_cache: Rc<RefCell<Vec<Button>>>,
-    monitor: Monitor,
-    pos: BarPosition,
-    current_widget: Rc<RefCell<Option<(usize, usize)>>>,
+    placement: Placement,
+    /// The state currently shown on screen.
+    current: Rc<RefCell<Option<PopupTarget>>>,
+    /// The state to show once pending requests are applied.
+    requested: Rc<RefCell<Option<PopupTarget>>>,
+    /// Whether requests are waiting to be applied.
+    pending: Rc<Cell<bool>>,
+    /// Whether the popup should take keyboard focus when opened.
+    keyboard_focus: Rc<Cell<bool>>,
+    open_widget: Arc<AtomicUsize>,
 }
 
 impl Popup {
     /// Creates a new popup window.
     /// This includes setting up gtk-layer-shell
     /// and an empty `gtk::Box` container.
-    pub fn new(module_info: &ModuleInfo, gap: i32) -> Self {
+    ///
+    /// Popups use their own namespace on the overlay layer,
+    /// so that they always stack above bars.
+    /// They do not reserve an exclusive zone,
+    /// so compositors keep them clear of other bars' exclusive zones.
+    pub fn new(module_info: &ModuleInfo, gap: i32, ironbar: Rc<Ironbar>) -> Self {
         let pos = module_info.bar_position;
         let orientation = pos.orientation();
 
@@ -45,7 +185,8 @@ impl Popup {
         win.init_layer_shell();
         win.set_monitor(module_info.monitor);
         win.set_layer(gtk_layer_shell::Layer::Overlay);
-        win.set_namespace(env!("CARGO_PKG_NAME"));
+        win.set_namespace(concat!(env!("CARGO_PKG_NAME"), "-popup"));
+        win.set_exclusive_zone(0);
 
         win.set_layer_shell_margin(
             gtk_layer_shell::Edge::Top,
@@ -105,13 +246,51 @@ impl Popup {
             Propagation::Proceed
         });
 
+        win.connect_key_press_event(|win, ev| {
+            if ev.keyval() == keys::constants::Escape {
+                win.hide();
+                return Propagation::Stop;
+            }
+
+            Propagation::Proceed
+        });
+
+        let open_widget = Arc::new(AtomicUsize::new(usize::MAX));
+        let current = rc_mut!(None);
+        let requested = rc_mut!(None);
+
+        {
+            let open_widget = open_widget.clone();
+            let current = current.clone();
+            let requested = requested.clone();
+
+            // the window may also be hidden directly, such as on mouse leave,
+            // so any state is reset here.
+            win.connect_hide(move |win| {
+                win.set_keyboard_mode(KeyboardMode::None);
+                open_widget.store(usize::MAX, Ordering::Relaxed);
+                current.replace(None);
+                requested.replace(None);
+            });
+        }
+
         Self {
             window: win,
             container_cache: rc_mut!(HashMap::new()),
             button_cache: rc_mut!(vec![]),
-            monitor: module_info.monitor.clone(),
-            pos,
-            current_widget: rc_mut!(None),
+            placement: Placement {
+                pos,
+                monitor: module_info.monitor.clone(),
+                bar_origin: Rc::new(Cell::new(None)),
+                ironbar,
+                bar_name: module_info.bar_name.to_string(),
+                output_name: module_info.output_name.to_string(),
+            },
+            current,
+            requested,
+            pending: Rc::new(Cell::new(false)),
+            keyboard_focus: Rc::new(Cell::new(false)),
+            open_widget,
         }
     }
 
@@ -120,14 +299,13 @@ impl Popup {
 
         for button in &content.buttons {
             button.ensure_popup_id();
+            button.set_interactive();
         }
 
-        let orientation = self.pos.orientation();
-        let monitor = self.monitor.clone();
+        let placement = self.placement.clone();
         let window = self.window.clone();
 
-        let current_widget = self.current_widget.clone();
-        let cache = self.container_cache.clone();
+        let current = self.current.clone();
         let button_cache = self.button_cache.clone();
 
         content
@@ -136,16 +314,16 @@ impl Popup {
                 if container.is_visible() {
                     trace!("Resized:  {}x{}", rect.width(), rect.height());
 
-                    if let Some((widget_id, button_id)) = *current_widget.borrow() {
-                        if let Some(PopupCacheValue { .. }) = cache.borrow().get(&widget_id) {
-                            Self::set_position(
-                                &button_cache.borrow(),
-                                button_id,
-                                orientation,
-                                &monitor,
-                                &window,
-                            );
-                        }
+                    // only reposition for the content currently shown,
+                    // so a previous widget's content can never move the popup.
+                    match *current.borrow() {
+                        Some(target) if target.widget_id == key => Self::set_anchor_position(
+                            &button_cache.borrow(),
+                            target.anchor,
+                            &placement,
+                            &window,
+                        ),
+                        _ => {}
                     }
                 }
             });
@@ -159,51 +337,138 @@ impl Popup {
             .insert(key, PopupCacheValue { name, content });
     }
 
-    pub fn show(&self, widget_id: usize, button_id: usize) {
-        self.clear_window();
+    /// Removes the content registered for the widget,
+    /// hiding the popup if it is open for it.
+    pub fn unregister_content(&self, key: usize) {
+        let Some(value) = self.container_cache.borrow_mut().remove(&key) else {
+            return;
+        };
 
-        if let Some(PopupCacheValue { content, .. }) = self.container_cache.borrow().get(&widget_id)
-        {
-            *self.current_widget.borrow_mut() = Some((widget_id, button_id));
+        debug!("Unregistered popup content for #{}", key);
 
-            content.container.add_class("popup");
-            self.window.add(&content.container);
+        self.button_cache
+            .borrow_mut()
+            .retain(|button| !value.content.buttons.contains(button));
 
-            self.window.show();
+        if self.current_widget() == Some(key) {
+            self.hide();
+        }
+    }
 
-            Self::set_position(
-                &self.button_cache.borrow(),
-                button_id,
-                self.pos.orientation(),
-                &self.monitor,
-                &self.window,
-            );
+    /// Queues a request to change the popup state.
+    ///
+    /// Requests are applied together once control returns to the main loop,
+    /// so only the final state is shown, replacing any content and position
+    /// from previous requests. When requests race, the last one wins.
+    pub fn request(&self, request: PopupRequest) {
+        let requested = request.apply(*self.requested.borrow());
+        trace!("Popup request {request:?} --> {requested:?}");
+        self.requested.replace(requested);
+
+        if !self.pending.replace(true) {
+            let popup = self.clone();
+            glib::idle_add_local_once(move || {
+                popup.pending.set(false);
+
+                let requested = *popup.requested.borrow();
+                popup.apply(requested);
+            });
         }
     }
 
+    /// Opens the popup for the widget,
+    /// positioned on the button with the given ID.
+    pub fn show(&self, widget_id: usize, button_id: usize) {
+        self.request(PopupRequest::Open(PopupTarget {
+            widget_id,
+            anchor: PopupAnchor::Button(button_id),
+        }));
+    }
+
+    /// Opens the popup for the widget,
+    /// positioned on the given geometry.
     pub fn show_at(&self, widget_id: usize, geometry: WidgetGeometry) {
-        self.clear_window();
+        self.request(PopupRequest::Open(PopupTarget {
+            widget_id,
+            anchor: PopupAnchor::Geometry(geometry),
+        }));
+    }
+
+    /// Swaps the window to show the target's content and position,
+    /// or hides it.
+    fn apply(&self, target: Option<PopupTarget>) {
+        let current = *self.current.borrow();
+        if current == target {
+            return;
+        }
+
+        let Some(target) = target else {
+            self.window.hide();
+            return;
+        };
+
+        let cache = self.container_cache.borrow();
+        let Some(PopupCacheValue { content, .. }) = cache.get(&target.widget_id) else {
+            self.requested.replace(None);
+            self.window.hide();
+            return;
+        };
+
+        self.current.replace(Some(target));
+        self.open_widget.store(target.widget_id, Ordering::Relaxed);
+
+        let is_shown = self
+            .window
+            .child()
+            .is_some_and(|child| child == content.container);
+
+        if !is_shown {
+            self.clear_window();
 
-        if let Some(PopupCacheValue { content, .. }) = self.container_cache.borrow().get(&widget_id)
-        {
             content.container.add_class("popup");
             self.window.add(&content.container);
+        }
 
-            self.window.show();
-            Self::set_pos(
-                geometry,
-                self.pos.orientation(),
-                &self.monitor,
-                &self.window,
-            );
+        self.window.show();
+
+        if self.keyboard_focus.get() {
+            self.window.set_keyboard_mode(KeyboardMode::OnDemand);
+            self.window.set_focus_visible(true);
+            self.window.present();
+
+            if self.window.focused_widget().is_none() {
+                content.container.child_focus(DirectionType::TabForward);
+            }
+        }
+
+        Self::set_anchor_position(
+            &self.button_cache.borrow(),
+            target.anchor,
+            &self.placement,
+            &self.window,
+        );
+    }
+
+    fn set_anchor_position(
+        buttons: &[Button],
+        anchor: PopupAnchor,
+        placement: &Placement,
+        window: &ApplicationWindow,
+    ) {
+        match anchor {
+            PopupAnchor::Button(button_id) => {
+                Self::set_position(buttons, button_id, placement, window);
+            }
+            PopupAnchor::Geometry(geometry) => {
+                Self::set_pos(geometry, placement, window);
+            }
         }
     }
 
     fn set_position(
         buttons: &[Button],
         button_id: usize,
-        orientation: Orientation,
-        monitor: &Monitor,
+        placement: &Placement,
         window: &ApplicationWindow,
     ) {
         let button = buttons
@@ -211,8 +476,15 @@ impl Popup {
             .find(|b| b.popup_id() == button_id)
             .expect("to find valid button");
 
-        let geometry = button.geometry(orientation);
-        Self::set_pos(geometry, orientation, monitor, window);
+        let geometry = button.geometry(placement.pos.orientation());
+        Self::set_pos(geometry, placement, window);
+    }
+
+    /// Sets where the bar starts along its edge,
+    /// so that the popup is positioned relative to the bar rather than the output.
+    /// Use `None` for a centered bar.
+    pub fn set_bar_origin(&self, origin: Option<i32>) {
+        self.placement.bar_origin.set(origin);
     }
 
     fn clear_window(&self) {
@@ -222,61 +494,309 @@ impl Popup {
         }
     }
 
+    /// Sets whether the popup takes keyboard focus when opened,
+    /// so that its contents can be navigated using the keyboard.
+    pub fn set_keyboard_focus(&self, enabled: bool) {
+        self.keyboard_focus.set(enabled);
+
+        if !enabled {
+            self.window.set_keyboard_mode(KeyboardMode::None);
+        }
+    }
+
     /// Hides the popup
     pub fn hide(&self) {
-        *self.current_widget.borrow_mut() = None;
-        self.window.hide();
+        self.request(PopupRequest::Close);
     }
 
-    /// Checks if the popup is currently visible
+    /// Checks if the popup is currently visible,
+    /// or will be once pending requests are applied.
     pub fn visible(&self) -> bool {
-        self.window.is_visible()
+        self.requested.borrow().is_some()
     }
 
+    /// Gets the ID of the widget the popup is open for,
+    /// or will be once pending requests are applied.
     pub fn current_widget(&self) -> Option<usize> {
-        self.current_widget.borrow().map(|w| w.0)
+        self.requested.borrow().map(|target| target.widget_id)
     }
 
-    /// Sets the popup's X/Y position relative to the left or border of the screen
+    /// Gets a handle for checking if the popup is open for the given widget.
+    pub fn open_state(&self, widget_id: usize) -> PopupOpenState {
+        PopupOpenState {
+            open_widget: self.open_widget.clone(),
+            widget_id,
+        }
+    }
+
+    /// Sets the popup's X/Y position relative to the left or top of the space available
     /// (depending on orientation).
-    fn set_pos(
-        geometry: WidgetGeometry,
-        orientation: Orientation,
-        monitor: &Monitor,
-        window: &ApplicationWindow,
-    ) {
-        let mon_workarea = monitor.workarea();
-        let screen_size = if orientation == Orientation::Horizontal {
-            mon_workarea.width()
+    fn set_pos(geometry: WidgetGeometry, placement: &Placement, window: &ApplicationWindow) {
+        let output = placement.monitor.geometry();
+        let reserved = placement
+            .ironbar
+            .reserved_edges(&placement.output_name, &placement.bar_name);
+
+        let offset = popup_offset(
+            geometry,
+            placement.pos,
+            placement.bar_origin.get(),
+            (output.width(), output.height()),
+            reserved,
+            window.size(),
+        );
+
+        let edge = if placement.pos.orientation() == Orientation::Horizontal {
+            gtk_layer_shell::Edge::Left
         } else {
-            mon_workarea.height()
+            gtk_layer_shell::Edge::Top
         };
 
-        let (popup_width, popup_height) = window.size();
-        let popup_size = if orientation == Orientation::Horizontal {
-            popup_width
+        window.set_layer_shell_margin(edge, offset);
+    }
+}
+
+/// Gets the popup's distance from the start of the space available along the bar's edge,
+/// so that it is centered on the widget where possible.
+///
+/// The popup is kept within the output, clear of the space reserved by other bars.
+/// As the popup does not reserve an exclusive zone,
+/// the compositor places it within the remaining space,
+/// so the distance is from the end of any space reserved at the start of the edge.
+fn popup_offset(
+    geometry: WidgetGeometry,
+    position: BarPosition,
+    bar_origin: Option<i32>,
+    (output_width, output_height): (i32, i32),
+    reserved: ReservedEdges,
+    (popup_width, popup_height): (i32, i32),
+) -> i32 {
+    let (output_size, popup_size, reserved_start, reserved_end) =
+        if position.orientation() == Orientation::Horizontal {
+            (output_width, popup_width, reserved.left, reserved.right)
         } else {
-            popup_height
+            (output_height, popup_height, reserved.top, reserved.bottom)
         };
 
-        let widget_center = f64::from(geometry.position) + f64::from(geometry.size) / 2.0;
+    let widget_center = f64::from(geometry.position) + f64::from(geometry.size) / 2.0;
+
+    let bar_offset = bar_origin.map_or_else(
+        || (f64::from(output_size) - f64::from(geometry.bar_size)) / 2.0,
+        f64::from,
+    );
+
+    let offset = bar_offset + (widget_center - (f64::from(popup_size) / 2.0)).round();
+
+    // the start is kept on screen if the popup is too large to fit
+    let min = reserved_start + EDGE_GAP;
+    let max = output_size - reserved_end - popup_size - EDGE_GAP;
+    let offset = (offset as i32).min(max).max(min);
 
-        let bar_offset = (f64::from(screen_size) - f64::from(geometry.bar_size)) / 2.0;
+    offset - reserved_start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
 
-        let mut offset = bar_offset + (widget_center - (f64::from(popup_size) / 2.0)).round();
+    const A: usize = 1;
+    const B: usize = 2;
 
-        if offset < 5.0 {
-            offset = 5.0;
-        } else if offset > f64::from(screen_size - popup_size) - 5.0 {
-            offset = f64::from(screen_size - popup_size) - 5.0;
+    fn target(widget_id: usize, button_id: usize) -> Option<PopupTarget> {
+        Some(PopupTarget {
+            widget_id,
+            anchor: PopupAnchor::Button(button_id),
+        })
+    }
+
+    /// Sends each event through a module update channel in order,
+    /// then applies the received requests, as the popup does.
+    fn resolve(
+        current: Option<PopupTarget>,
+        events: Vec<(usize, ModuleUpdateEvent<()>)>,
+    ) -> Option<PopupTarget> {
+        let (tx, mut rx) = mpsc::channel(events.len().max(1));
+        for event in events {
+            tx.try_send(event).expect("to send");
         }
 
-        let edge = if orientation == Orientation::Horizontal {
-            gtk_layer_shell::Edge::Left
-        } else {
-            gtk_layer_shell::Edge::Top
+        let mut state = current;
+        while let Ok((widget_id, event)) = rx.try_recv() {
+            if let Some(request) = PopupRequest::from_event(&event, widget_id, None) {
+                state = request.apply(state);
+            }
+        }
+
+        state
+    }
+
+    #[test]
+    fn last_open_wins() {
+        let state = resolve(
+            None,
+            vec![
+                (A, ModuleUpdateEvent::TogglePopup(10)),
+                (B, ModuleUpdateEvent::TogglePopup(20)),
+            ],
+        );
+
+        assert_eq!(state, target(B, 20));
+    }
+
+    #[test]
+    fn close_after_open_for_other_widget() {
+        let state = resolve(
+            target(A, 10),
+            vec![
+                (B, ModuleUpdateEvent::OpenPopup(20)),
+                (A, ModuleUpdateEvent::ClosePopup),
+            ],
+        );
+        assert_eq!(state, None);
+
+        let state = resolve(
+            target(A, 10),
+            vec![
+                (A, ModuleUpdateEvent::ClosePopup),
+                (B, ModuleUpdateEvent::OpenPopup(20)),
+            ],
+        );
+        assert_eq!(state, target(B, 20));
+    }
+
+    #[test]
+    fn toggle_uses_queued_state() {
+        let state = resolve(
+            None,
+            vec![
+                (A, ModuleUpdateEvent::TogglePopup(10)),
+                (A, ModuleUpdateEvent::TogglePopup(10)),
+            ],
+        );
+        assert_eq!(state, None);
+
+        // toggling another widget swaps rather than closes
+        let state = resolve(target(A, 10), vec![(B, ModuleUpdateEvent::TogglePopup(20))]);
+        assert_eq!(state, target(B, 20));
+    }
+
+    #[test]
+    fn updates_are_ignored() {
+        let state = resolve(
+            target(A, 10),
+            vec![
+                (B, ModuleUpdateEvent::Update(())),
+                (A, ModuleUpdateEvent::Update(())),
+            ],
+        );
+
+        assert_eq!(state, target(A, 10));
+    }
+
+    #[test]
+    fn fixed_button_overrides_event() {
+        let request =
+            PopupRequest::from_event(&ModuleUpdateEvent::<()>::OpenPopup(10), A, Some(30));
+        assert_eq!(
+            request,
+            Some(PopupRequest::Open(target(A, 30).expect("target")))
+        );
+    }
+
+    const OUTPUT: (i32, i32) = (1920, 1080);
+
+    fn geometry(position: i32, size: i32, bar_size: i32) -> WidgetGeometry {
+        WidgetGeometry {
+            position,
+            size,
+            bar_size,
+        }
+    }
+
+    #[test]
+    fn offsets_from_bar_origin() {
+        let geometry = geometry(100, 50, 1000);
+        let offset = |bar_origin, popup_width| {
+            popup_offset(
+                geometry,
+                BarPosition::Top,
+                bar_origin,
+                OUTPUT,
+                ReservedEdges::default(),
+                (popup_width, 300),
+            )
+        };
+
+        // centered bar on a 1920px screen starts at 460.
+        assert_eq!(offset(None, 200), 485);
+        assert_eq!(offset(Some(800), 200), 825);
+
+        // kept on screen
+        assert_eq!(offset(Some(0), 400), 5);
+        assert_eq!(offset(Some(1800), 200), 1715);
+    }
+
+    #[test]
+    fn places_along_each_edge() {
+        let reserved = ReservedEdges {
+            top: 30,
+            bottom: 40,
+            left: 50,
+            right: 60,
+        };
+        let popup = (300, 200);
+
+        // horizontal bars use the width, and the space reserved at the left and right
+        for position in [BarPosition::Top, BarPosition::Bottom] {
+            let offset = |widget| popup_offset(widget, position, Some(0), OUTPUT, reserved, popup);
+
+            assert_eq!(offset(geometry(900, 100, 1920)), 800 - 50);
+            assert_eq!(offset(geometry(0, 40, 1920)), 5);
+            assert_eq!(offset(geometry(1880, 40, 1920)), 1920 - 60 - 300 - 5 - 50);
+        }
+
+        // vertical bars use the height, and the space reserved at the top and bottom
+        for position in [BarPosition::Left, BarPosition::Right] {
+            let offset = |widget| popup_offset(widget, position, Some(0), OUTPUT, reserved, popup);
+
+            assert_eq!(offset(geometry(500, 100, 1080)), 450 - 30);
+            assert_eq!(offset(geometry(0, 40, 1080)), 5);
+            assert_eq!(offset(geometry(1040, 40, 1080)), 1080 - 40 - 200 - 5 - 30);
+        }
+    }
+
+    #[test]
+    fn keeps_start_visible_when_too_large() {
+        let reserved = ReservedEdges {
+            left: 50,
+            ..ReservedEdges::default()
         };
 
-        window.set_layer_shell_margin(edge, offset as i32);
+        let offset = popup_offset(
+            geometry(1800, 40, 1920),
+            BarPosition::Bottom,
+            None,
+            OUTPUT,
+            reserved,
+            (2000, 200),
+        );
+        assert_eq!(offset, 5);
+    }
+
+    #[test]
+    fn reserves_edges() {
+        let mut reserved = ReservedEdges::default();
+        reserved.reserve(BarPosition::Top, 30);
+        reserved.reserve(BarPosition::Top, 20);
+        reserved.reserve(BarPosition::Right, -1);
+
+        assert_eq!(
+            reserved,
+            ReservedEdges {
+                top: 50,
+                ..ReservedEdges::default()
+            }
+        );
     }
 }
@@ -1,3 +1,6 @@
+use crate::clients::timer::Ticker;
+use crate::diagnostics::ModuleCounters;
+use crate::popup::PopupOpenState;
 use crate::{send_async, spawn};
 use color_eyre::eyre::WrapErr;
 use color_eyre::{Report, Result};
@@ -5,6 +8,8 @@ use serde::Deserialize;
 use std::cmp::min;
 use std::fmt::{Display, Formatter};
 use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::select;
@@ -84,6 +89,8 @@ pub struct Script {
     pub cmd: String,
     #[serde(default = "default_interval")]
     pub(crate) interval: u64,
+    #[serde(skip)]
+    pub(crate) env: ScriptEnv,
 }
 
 const fn default_interval() -> u64 {
@@ -96,10 +103,100 @@ impl Default for Script {
             mode: ScriptMode::default(),
             interval: default_interval(),
             cmd: String::new(),
+            env: ScriptEnv::default(),
         }
     }
 }
 
+/// Information about the bar and widget a script was started from.
+/// This is passed to the script process as `IRONBAR_*` environment variables.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptEnv {
+    vars: Vec<(&'static str, String)>,
+    popup: Option<PopupOpenState>,
+    diagnostics: Option<Arc<ModuleCounters>>,
+    dedicated_timer: bool,
+}
+
+impl ScriptEnv {
+    /// Creates a new environment for scripts belonging to a widget.
+    pub fn new(bar_name: &str, monitor_name: &str, widget_name: &str) -> Self {
+        Self {
+            vars: vec![
+                ("IRONBAR_BAR", bar_name.to_string()),
+                ("IRONBAR_MONITOR", monitor_name.to_string()),
+                ("IRONBAR_WIDGET", widget_name.to_string()),
+            ],
+            popup: None,
+            diagnostics: None,
+            dedicated_timer: false,
+        }
+    }
+
+    /// Sets the popup state used to populate `IRONBAR_POPUP_OPEN`.
+    #[must_use]
+    pub fn with_popup(mut self, popup: PopupOpenState) -> Self {
+        self.popup = Some(popup);
+        self
+    }
+
+    /// Creates a copy of the environment for a click handler
+    /// with `IRONBAR_BUTTON` set to the mouse button number.
+    #[must_use]
+    pub fn with_button(&self, button: u32) -> Self {
+        let mut env = self.clone();
+        env.vars.push(("IRONBAR_BUTTON", button.to_string()));
+        env
+    }
+
+    /// Gets the name of the monitor the widget's bar is on.
+    pub fn monitor_name(&self) -> &str {
+        self.vars
+            .iter()
+            .find(|(key, _)| *key == "IRONBAR_MONITOR")
+            .map_or("", |(_, value)| value)
+    }
+
+    /// Sets the diagnostics counters to record started scripts against.
+    #[must_use]
+    pub fn with_diagnostics(mut self, diagnostics: Option<Arc<ModuleCounters>>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Sets whether polling uses a dedicated timer,
+    /// rather than the shared timer.
+    #[must_use]
+    pub fn with_dedicated_timer(mut self, dedicated_timer: bool) -> Self {
+        self.dedicated_timer = dedicated_timer;
+        self
+    }
+
+    /// Creates a ticker for polling at the interval,
+    /// using a dedicated timer if set.
+    pub fn ticker(&self, interval: Duration) -> Ticker {
+        Ticker::new(interval, self.dedicated_timer)
+    }
+
+    fn record_script(&self) {
+        if let Some(diagnostics) = &self.diagnostics {
+            diagnostics.record_script();
+        }
+    }
+
+    /// Gets the variables to set on the script process.
+    /// Popup state is read at the time of calling.
+    fn vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = self.vars.clone();
+
+        if let Some(popup) = &self.popup {
+            vars.push(("IRONBAR_POPUP_OPEN", popup.is_open().to_string()));
+        }
+
+        vars
+    }
+}
+
 impl From<ScriptInput> for Script {
     fn from(input: ScriptInput) -> Self {
         match input {
@@ -196,12 +293,25 @@ impl Script {
         script
     }
 
+    /// Sets the bar context passed to the script process.
+    #[must_use]
+    pub fn with_env(mut self, env: &ScriptEnv) -> Self {
+        self.env = env.clone();
+        self
+    }
+
     /// Runs the script, passing `args` if provided.
     /// Runs `f`, passing the output stream and whether the command returned 0.
     pub async fn run<F>(&self, args: Option<&[String]>, callback: F)
     where
         F: Fn(OutputStream, bool),
     {
+        let interval = Duration::from_millis(self.interval);
+
+        // polling scripts re-run straight away after resume,
+        // rather than showing stale output until the next interval.
+        let mut ticker = (self.mode == ScriptMode::Poll).then(|| self.env.ticker(interval));
+
         loop {
             match self.mode {
                 ScriptMode::Poll => match self.get_output(args).await {
@@ -218,7 +328,10 @@ impl Script {
                 },
             };
 
-            sleep(tokio::time::Duration::from_millis(self.interval)).await;
+            match &mut ticker {
+                Some(ticker) => ticker.tick().await,
+                None => sleep(interval).await,
+            }
         }
     }
 
@@ -236,9 +349,11 @@ impl Script {
         }
 
         debug!("Running sh with args: {args_list:?}");
+        self.env.record_script();
 
         let output = Command::new("/bin/sh")
             .args(&args_list)
+            .envs(self.env.vars())
             .output()
             .await
             .wrap_err("Failed to get script output")?;
@@ -270,11 +385,13 @@ impl Script {
     pub fn spawn(&self) -> Result<mpsc::Receiver<OutputStream>> {
         let mut handle = Command::new("/bin/sh")
             .args(["-c", &self.cmd])
+            .envs(self.env.vars())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .stdin(Stdio::null())
             .spawn()?;
 
+        self.env.record_script();
         debug!("Spawned a long-running process for '{}'", self.cmd);
         trace!("Handle: {:?}", handle);
 
@@ -413,4 +530,17 @@ mod tests {
         assert_eq!(script.interval, interval);
         assert_eq!(script.mode, mode);
     }
+
+    #[test]
+    fn test_env_button() {
+        let env = ScriptEnv::new("bar-0", "DP-1", "clock");
+        let vars = env.with_button(3).vars();
+
+        assert!(vars.contains(&("IRONBAR_BAR", "bar-0".to_string())));
+        assert!(vars.contains(&("IRONBAR_MONITOR", "DP-1".to_string())));
+        assert!(vars.contains(&("IRONBAR_WIDGET", "clock".to_string())));
+        assert!(vars.contains(&("IRONBAR_BUTTON", "3".to_string())));
+        assert!(!env.vars().iter().any(|(key, _)| *key == "IRONBAR_BUTTON"));
+        assert_eq!(env.monitor_name(), "DP-1");
+    }
 }
@@ -0,0 +1,374 @@
+use crate::config::ScrollConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use glib::Propagation;
+use gtk::gdk::{EventMask, EventScroll, ScrollDirection};
+use gtk::prelude::*;
+use gtk::{EventSequenceState, GestureDrag, PropagationPhase, Widget};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// The direction of a single scroll step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollStep {
+    /// Scrolling up or left.
+    Up,
+    /// Scrolling down or right.
+    Down,
+}
+
+/// The most steps which can be queued in one direction,
+/// so that a fast scroll does not keep stepping long after it stops.
+const MAX_QUEUED: i32 = 2;
+
+/// Collects scroll deltas into whole steps,
+/// so that touchpads which send many small deltas
+/// move one step at a time.
+///
+/// Steps scrolled faster than the minimum interval are queued,
+/// up to a limit, and taken once it has passed.
+#[derive(Debug)]
+struct ScrollAccumulator {
+    threshold: f64,
+    interval: Duration,
+
+    delta: f64,
+    /// The number of steps waiting for the interval to pass,
+    /// positive for down and negative for up.
+    queued: i32,
+    last_step: Option<Instant>,
+}
+
+impl ScrollAccumulator {
+    fn new(config: ScrollConfig) -> Self {
+        Self {
+            threshold: config.threshold,
+            interval: Duration::from_millis(config.interval),
+            delta: 0.0,
+            queued: 0,
+            last_step: None,
+        }
+    }
+
+    /// Adds a scroll delta, positive for down and negative for up.
+    ///
+    /// Once the threshold is reached, returns the step to take
+    /// if the interval has passed, or queues it otherwise.
+    /// The delta then starts again from zero,
+    /// so the rest of the same swipe does not carry over.
+    fn add(&mut self, delta: f64, now: Instant) -> Option<ScrollStep> {
+        if delta == 0.0 {
+            return None;
+        }
+
+        // changing direction discards the scroll so far,
+        // including any steps still queued
+        if delta.signum() != self.delta.signum() {
+            self.delta = 0.0;
+        }
+
+        if self.queued != 0 && (self.queued > 0) != (delta > 0.0) {
+            self.queued = 0;
+        }
+
+        self.delta += delta;
+
+        if self.delta.abs() >= self.threshold {
+            let step = if self.delta > 0.0 { 1 } else { -1 };
+            self.queued = (self.queued + step).clamp(-MAX_QUEUED, MAX_QUEUED);
+            self.delta = 0.0;
+        }
+
+        self.poll(now)
+    }
+
+    /// Takes a queued step, if the interval has passed since the last one.
+    fn poll(&mut self, now: Instant) -> Option<ScrollStep> {
+        if self.queued == 0 || self.until_next(now).is_some_and(|wait| !wait.is_zero()) {
+            return None;
+        }
+
+        let step = if self.queued > 0 {
+            ScrollStep::Down
+        } else {
+            ScrollStep::Up
+        };
+
+        self.queued -= self.queued.signum();
+        self.last_step = Some(now);
+
+        Some(step)
+    }
+
+    /// Gets how long until the next queued step can be taken,
+    /// or `None` if there is no queued step.
+    fn next_due(&self, now: Instant) -> Option<Duration> {
+        (self.queued != 0).then(|| self.until_next(now).unwrap_or_default())
+    }
+
+    fn until_next(&self, now: Instant) -> Option<Duration> {
+        self.last_step
+            .map(|last| self.interval.saturating_sub(now.duration_since(last)))
+    }
+}
+
+/// Gets the scroll distance of an event, positive for down or right.
+/// Discrete events, such as from a mouse wheel, move one notch.
+fn event_delta(event: &EventScroll) -> Option<f64> {
+    match event.direction() {
+        ScrollDirection::Up | ScrollDirection::Left => Some(-1.0),
+        ScrollDirection::Down | ScrollDirection::Right => Some(1.0),
+        ScrollDirection::Smooth => {
+            let (dx, dy) = event.delta();
+            Some(if dy == 0.0 { dx } else { dy })
+        }
+        _ => None,
+    }
+}
+
+/// The distance in pixels swiped on a touchscreen
+/// to move as far as one notch of a mouse wheel.
+const SWIPE_DISTANCE: f64 = 32.0;
+
+/// Gets the scroll delta for a swipe moving between two offsets
+/// from where it started, positive for down or right.
+///
+/// Only the axis which has moved furthest from the start counts,
+/// so that a swipe does not need to be perfectly straight.
+fn swipe_delta(last: (f64, f64), current: (f64, f64)) -> f64 {
+    let (x, y) = current;
+
+    let delta = if x.abs() > y.abs() {
+        x - last.0
+    } else {
+        y - last.1
+    };
+
+    delta / SWIPE_DISTANCE
+}
+
+/// Turns scroll events into throttled steps,
+/// so that scrolling behaves the same with a touchpad or mouse wheel
+/// across every module on the bar.
+///
+/// Widgets connected to the same scroller share their progress,
+/// so a swipe across several of them counts once.
+#[derive(Debug, Clone)]
+pub struct Scroller {
+    accumulator: Rc<RefCell<ScrollAccumulator>>,
+    /// Whether a timer is waiting to take a queued step.
+    scheduled: Rc<Cell<bool>>,
+    /// Whether swiping on a touchscreen also scrolls.
+    swipe: bool,
+}
+
+impl Scroller {
+    pub fn new(config: ScrollConfig) -> Self {
+        Self {
+            accumulator: Rc::new(RefCell::new(ScrollAccumulator::new(config))),
+            scheduled: Rc::default(),
+            swipe: false,
+        }
+    }
+
+    /// Sets whether swiping over connected widgets on a touchscreen
+    /// takes steps as well as scrolling.
+    pub const fn with_swipe(mut self, swipe: bool) -> Self {
+        self.swipe = swipe;
+        self
+    }
+
+    /// Calls `on_step` for each step scrolled over the widget,
+    /// and each step swiped if enabled.
+    pub fn connect<W, F>(&self, widget: &W, on_step: F)
+    where
+        W: IsA<Widget> + Clone + 'static,
+        F: Fn(&W, ScrollStep) + 'static,
+    {
+        widget.add_events(EventMask::SCROLL_MASK | EventMask::SMOOTH_SCROLL_MASK);
+
+        let scroller = self.clone();
+        let on_step = Rc::new(on_step);
+
+        if self.swipe {
+            self.install_swipe(widget, &on_step);
+        }
+
+        widget.connect_scroll_event(move |widget, event| {
+            let Some(delta) = event_delta(event) else {
+                return Propagation::Proceed;
+            };
+
+            scroller.scroll(widget, &on_step, delta);
+            Propagation::Stop
+        });
+    }
+
+    /// Calls `on_step` for each step swiped over the widget on a touchscreen,
+    /// without responding to scrolling.
+    pub fn connect_swipe<W, F>(&self, widget: &W, on_step: F)
+    where
+        W: IsA<Widget> + Clone + 'static,
+        F: Fn(&W, ScrollStep) + 'static,
+    {
+        self.install_swipe(widget, &Rc::new(on_step));
+    }
+
+    /// Turns touchscreen swipes over the widget into scroll deltas,
+    /// using whichever axis has moved furthest.
+    fn install_swipe<W, F>(&self, widget: &W, on_step: &Rc<F>)
+    where
+        W: IsA<Widget> + Clone + 'static,
+        F: Fn(&W, ScrollStep) + 'static,
+    {
+        let gesture = GestureDrag::new(widget);
+        gesture.set_touch_only(true);
+
+        // runs before the widget's own click handling,
+        // so that the swipe can stop it counting as a tap
+        gesture.set_propagation_phase(PropagationPhase::Capture);
+
+        let last = Rc::new(Cell::new((0.0, 0.0)));
+
+        {
+            let last = last.clone();
+            gesture.connect_drag_begin(move |_, _, _| last.set((0.0, 0.0)));
+        }
+
+        let scroller = self.clone();
+        let on_step = on_step.clone();
+        let widget_ref = widget.clone();
+
+        gesture.connect_drag_update(move |gesture, x, y| {
+            let delta = swipe_delta(last.replace((x, y)), (x, y));
+
+            if x.abs().max(y.abs()) >= SWIPE_DISTANCE {
+                gesture.set_state(EventSequenceState::Claimed);
+            }
+
+            scroller.scroll(&widget_ref, &on_step, delta);
+        });
+
+        widget.set_tag("swipe-controller", gesture);
+    }
+
+    /// Adds a scroll delta, positive for down or right,
+    /// and takes any step it completes.
+    fn scroll<W, F>(&self, widget: &W, on_step: &Rc<F>, delta: f64)
+    where
+        W: IsA<Widget> + Clone + 'static,
+        F: Fn(&W, ScrollStep) + 'static,
+    {
+        let step = self.accumulator.borrow_mut().add(delta, Instant::now());
+
+        if let Some(step) = step {
+            on_step(widget, step);
+        }
+
+        self.schedule(widget, on_step);
+    }
+
+    /// Starts a timer to take the next queued step,
+    /// unless one is already running.
+    fn schedule<W, F>(&self, widget: &W, on_step: &Rc<F>)
+    where
+        W: IsA<Widget> + Clone + 'static,
+        F: Fn(&W, ScrollStep) + 'static,
+    {
+        if self.scheduled.get() {
+            return;
+        }
+
+        let Some(due) = self.accumulator.borrow().next_due(Instant::now()) else {
+            return;
+        };
+
+        self.scheduled.set(true);
+
+        let scroller = self.clone();
+        let widget = widget.clone();
+        let on_step = on_step.clone();
+
+        glib::timeout_add_local_once(due, move || {
+            scroller.scheduled.set(false);
+
+            let step = scroller.accumulator.borrow_mut().poll(Instant::now());
+            if let Some(step) = step {
+                on_step(&widget, step);
+            }
+
+            scroller.schedule(&widget, &on_step);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accumulator(interval: u64) -> ScrollAccumulator {
+        ScrollAccumulator::new(ScrollConfig {
+            threshold: 1.0,
+            interval,
+        })
+    }
+
+    #[test]
+    fn accumulates_small_deltas() {
+        let mut scroll = accumulator(0);
+        let now = Instant::now();
+
+        assert_eq!(scroll.add(1.0, now), Some(ScrollStep::Down));
+        assert_eq!(scroll.add(-1.0, now), Some(ScrollStep::Up));
+
+        let steps = (0..25).filter_map(|_| scroll.add(0.1, now)).count();
+        assert_eq!(steps, 2);
+
+        // reversing direction starts again
+        assert_eq!(scroll.add(0.6, now), None);
+        assert_eq!(scroll.add(-0.6, now), None);
+        assert_eq!(scroll.add(-0.6, now), Some(ScrollStep::Up));
+    }
+
+    #[test]
+    fn swipes_along_furthest_axis() {
+        assert_eq!(swipe_delta((0.0, 0.0), (16.0, 4.0)), 0.5);
+        assert_eq!(swipe_delta((16.0, 4.0), (8.0, 4.0)), -0.25);
+
+        // sideways drift does not count
+        assert_eq!(swipe_delta((4.0, -32.0), (12.0, -64.0)), -1.0);
+    }
+
+    #[test]
+    fn throttles_steps() {
+        let mut scroll = accumulator(150);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        assert_eq!(scroll.add(1.0, at(0)), Some(ScrollStep::Down));
+        assert_eq!(scroll.add(1.0, at(10)), None);
+        assert_eq!(scroll.add(1.0, at(20)), None);
+        assert_eq!(scroll.next_due(at(100)), Some(Duration::from_millis(50)));
+
+        assert_eq!(scroll.poll(at(100)), None);
+        assert_eq!(scroll.poll(at(150)), Some(ScrollStep::Down));
+        assert_eq!(scroll.poll(at(200)), None);
+        assert_eq!(scroll.poll(at(300)), Some(ScrollStep::Down));
+        assert_eq!(scroll.next_due(at(300)), None);
+
+        // reversing direction cancels the queued steps
+        assert_eq!(scroll.add(1.0, at(310)), None);
+        assert_eq!(scroll.add(-1.0, at(320)), None);
+        assert_eq!(scroll.poll(at(450)), Some(ScrollStep::Up));
+        assert_eq!(scroll.poll(at(600)), None);
+
+        // only a couple of steps are queued however fast the scroll
+        let steps = (610..650)
+            .filter_map(|millis| scroll.add(1.0, at(millis)))
+            .count();
+        assert_eq!(steps, 1);
+        assert_eq!(scroll.poll(at(760)), Some(ScrollStep::Down));
+        assert_eq!(scroll.poll(at(910)), Some(ScrollStep::Down));
+        assert_eq!(scroll.next_due(at(910)), None);
+    }
+}
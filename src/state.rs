@@ -0,0 +1,83 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Gets the path to the named file storing state kept between runs,
+/// inside `$XDG_STATE_HOME/ironbar`.
+#[cfg_attr(
+    not(any(
+        feature = "clipboard",
+        feature = "clock",
+        feature = "launcher",
+        feature = "tray"
+    )),
+    allow(dead_code)
+)]
+pub fn state_file(name: &str) -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_dir)
+        .map(|dir| dir.join("ironbar").join(name))
+}
+
+/// Writes a state file, creating its directory if needed.
+///
+/// The contents are written to a temporary file which replaces it,
+/// so that the file is never left partly written.
+/// Only the current user can read the file.
+pub fn write_state(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)?;
+
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = parent.join(temp_name);
+
+    let result = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        fs::remove_file(&temp_path).ok();
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn writes_state_privately() {
+        let dir = std::env::temp_dir().join(format!("ironbar-state-{}", std::process::id()));
+        let path = dir.join("nested").join("state");
+
+        write_state(&path, "first").expect("to write state");
+        write_state(&path, "second").expect("to write state");
+
+        assert_eq!(fs::read_to_string(&path).ok().as_deref(), Some("second"));
+
+        let mode = fs::metadata(&path)
+            .expect("file to exist")
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let files = fs::read_dir(path.parent().expect("path to have parent"))
+            .expect("to read dir")
+            .count();
+        assert_eq!(files, 1);
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -1,12 +1,17 @@
-use crate::{glib_recv_mpsc, spawn, try_send};
+use crate::config::FontConfig;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::{glib_recv_mpsc, spawn, try_send, Ironbar};
 use color_eyre::{Help, Report};
-use gtk::ffi::GTK_STYLE_PROVIDER_PRIORITY_USER;
-use gtk::prelude::CssProviderExt;
-use gtk::{gdk, gio, CssProvider, StyleContext};
+use gtk::ffi::{GTK_STYLE_PROVIDER_PRIORITY_APPLICATION, GTK_STYLE_PROVIDER_PRIORITY_USER};
+use gtk::prelude::*;
+use gtk::{gdk, gio, pango, CssProvider, StyleContext, Widget};
 use notify::event::ModifyKind;
 use notify::{recommended_watcher, Event, EventKind, RecursiveMode, Result, Watcher};
+use std::cell::RefCell;
 use std::env;
+use std::fmt::Write;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::time::sleep;
@@ -83,3 +88,167 @@ pub fn load_css(style_path: PathBuf) {
         }
     });
 }
+
+/// The DPI GTK uses for text when the system does not set one.
+const DEFAULT_DPI: f64 = 96.0;
+
+/// The font size GTK uses when the system does not set one, in points.
+const DEFAULT_FONT_SIZE: f64 = 10.0;
+
+/// Applies a bar's font to its windows.
+///
+/// The stylesheet is scoped to a class unique to the bar,
+/// and loaded beneath the user stylesheet so that user CSS can still override it.
+#[derive(Debug, Clone)]
+pub struct FontProvider {
+    provider: CssProvider,
+    class: String,
+    font: Rc<RefCell<Option<FontConfig>>>,
+}
+
+impl FontProvider {
+    /// Creates the provider, scoping it to the window.
+    /// It is removed once the window is destroyed.
+    pub fn new(window: &impl IsA<Widget>) -> Self {
+        let provider = CssProvider::new();
+
+        let screen = gdk::Screen::default().expect("Failed to get default GTK screen");
+        StyleContext::add_provider_for_screen(
+            &screen,
+            &provider,
+            GTK_STYLE_PROVIDER_PRIORITY_APPLICATION as u32,
+        );
+
+        {
+            let provider = provider.clone();
+            window.connect_destroy(move |_| {
+                StyleContext::remove_provider_for_screen(&screen, &provider);
+            });
+        }
+
+        let font_provider = Self {
+            provider,
+            class: format!("ironbar-font-{}", Ironbar::unique_id()),
+            font: Rc::default(),
+        };
+
+        font_provider.attach(window);
+        font_provider
+    }
+
+    /// Applies the font to another of the bar's windows, such as its popup.
+    pub fn attach(&self, window: &impl IsA<Widget>) {
+        window.add_class(&self.class);
+    }
+
+    /// Replaces the applied font.
+    /// This can be called at any time to update it in place.
+    pub fn apply(&self, font: Option<&FontConfig>) {
+        let css = font
+            .map(|font| font_css(&self.class, font, default_font_size()))
+            .unwrap_or_default();
+
+        if let Err(err) = self.provider.load_from_data(css.as_bytes()) {
+            error!("{:?}", Report::new(err).wrap_err("Failed to load bar font"));
+        }
+
+        self.font.replace(font.cloned());
+    }
+
+    /// Checks whether the font currently applied matches the provided one.
+    pub fn has_font(&self, font: Option<&FontConfig>) -> bool {
+        self.font.borrow().as_ref() == font
+    }
+}
+
+/// Gets the factor to scale module pixel sizes, such as icon sizes, by
+/// so that they stay in proportion to the bar's text.
+pub fn font_scale(font: Option<&FontConfig>) -> f64 {
+    font.map_or(1.0, |font| {
+        if font.system_scale {
+            font.scale * system_dpi() / DEFAULT_DPI
+        } else {
+            font.scale
+        }
+    })
+}
+
+/// Gets the DPI GTK uses for text,
+/// which includes the system text scaling factor.
+fn system_dpi() -> f64 {
+    gtk::Settings::default()
+        .map(|settings| settings.gtk_xft_dpi())
+        .filter(|dpi| *dpi > 0)
+        .map_or(DEFAULT_DPI, |dpi| f64::from(dpi) / 1024.0)
+}
+
+/// Gets the size of the GTK default font, in points.
+fn default_font_size() -> f64 {
+    gtk::Settings::default()
+        .and_then(|settings| settings.gtk_font_name())
+        .map(|name| pango::FontDescription::from_string(&name).size())
+        .filter(|size| *size > 0)
+        .map_or(DEFAULT_FONT_SIZE, |size| {
+            f64::from(size) / f64::from(pango::SCALE)
+        })
+}
+
+/// Builds the stylesheet for the font, scoped to the class.
+///
+/// GTK scales point sizes by the system DPI, but not pixel sizes,
+/// so sizes which should ignore the system factor are given in pixels.
+fn font_css(class: &str, font: &FontConfig, default_size: f64) -> String {
+    let mut css = format!(".{class} {{");
+
+    if let Some(family) = &font.family {
+        let family = family.replace('\\', "\\\\").replace('"', "\\\"");
+        let _ = write!(css, " font-family: \"{family}\";");
+    }
+
+    let size = font.size.unwrap_or(default_size) * font.scale;
+
+    if !font.system_scale {
+        // 1pt is 1/72in, and GTK lays out at 96px per inch before scaling
+        let _ = write!(css, " font-size: {}px;", size * DEFAULT_DPI / 72.0);
+    } else if font.size.is_some() || font.scale != 1.0 {
+        let _ = write!(css, " font-size: {size}pt;");
+    }
+
+    css.push_str(" }");
+    css
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font(size: Option<f64>, scale: f64, system_scale: bool) -> FontConfig {
+        FontConfig {
+            family: None,
+            size,
+            scale,
+            system_scale,
+        }
+    }
+
+    #[test]
+    fn builds_font_css() {
+        let mut config = font(Some(12.0), 1.0, true);
+        config.family = Some(String::from(r#"My "Font""#));
+
+        assert_eq!(
+            font_css("bar", &config, 10.0),
+            r#".bar { font-family: "My \"Font\""; font-size: 12pt; }"#
+        );
+
+        assert_eq!(
+            font_css("bar", &font(None, 1.5, true), 10.0),
+            ".bar { font-size: 15pt; }"
+        );
+        assert_eq!(font_css("bar", &font(None, 1.0, true), 10.0), ".bar { }");
+        assert_eq!(
+            font_css("bar", &font(Some(9.0), 1.0, false), 10.0),
+            ".bar { font-size: 12px; }"
+        );
+    }
+}